@@ -0,0 +1,100 @@
+use move_core_types::account_address::AccountAddress;
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Domain separator of resource account addresses (`Scheme::DeriveResourceAccountAddress`)
+const DERIVE_RESOURCE_ACCOUNT_ADDRESS: u8 = 255;
+
+/// Domain separator of named object addresses (`Scheme::DeriveObjectAddressFromSeed`)
+const DERIVE_OBJECT_ADDRESS_FROM_SEED: u8 = 254;
+
+/// Seed prefix of the objects `0x1::object_code_deployment::publish` creates
+const OBJECT_CODE_DEPLOYMENT_DOMAIN_SEPARATOR: &[u8] = b"aptos_framework::object_code_deployment";
+
+/// Address of the resource account `publisher` creates with `seed`, as
+/// `0x1::account::create_resource_address`: sha3-256 of the publisher, the seed and the scheme byte
+pub fn resource_account_address(publisher: &AccountAddress, seed: &[u8]) -> AccountAddress {
+    let mut hasher = Sha3_256::new();
+    hasher.update(publisher.as_ref());
+    hasher.update(seed);
+    hasher.update([DERIVE_RESOURCE_ACCOUNT_ADDRESS]);
+    AccountAddress::new(hasher.finalize().into())
+}
+
+/// Address of the object `0x1::object_code_deployment::publish` creates when `publisher` sends it
+/// as the transaction of `sequence_number`, as the aptos CLI's
+/// `create-object-and-publish-package`: a named object of `publisher` whose seed is the BCS
+/// domain separator followed by the BCS of `sequence_number + 1`
+pub fn object_code_address(publisher: &AccountAddress, sequence_number: u64) -> AccountAddress {
+    let mut hasher = Sha3_256::new();
+    hasher.update(publisher.as_ref());
+    hasher.update(object_code_seed(sequence_number));
+    hasher.update([DERIVE_OBJECT_ADDRESS_FROM_SEED]);
+    AccountAddress::new(hasher.finalize().into())
+}
+
+/// Seed of the object created by the publish sent as the transaction of `sequence_number`: the
+/// BCS of `aptos_framework::object_code_deployment` followed by the BCS of `sequence_number + 1`
+pub fn object_code_seed(sequence_number: u64) -> Vec<u8> {
+    let mut seed = vec![OBJECT_CODE_DEPLOYMENT_DOMAIN_SEPARATOR.len() as u8];
+    seed.extend_from_slice(OBJECT_CODE_DEPLOYMENT_DOMAIN_SEPARATOR);
+    seed.extend_from_slice(&(sequence_number + 1).to_le_bytes());
+    seed
+}
+
+/// Leading hex digits an address should start with, e.g. `0xcafe`; an odd number of digits
+/// matches the high nibble of the last byte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressPrefix {
+    digits: String,
+}
+
+impl AddressPrefix {
+    /// Whether the standard (64-digit) form of `address` starts with the prefix
+    pub fn matches(&self, address: &AccountAddress) -> bool {
+        let bytes = address.as_ref();
+        self.digits.bytes().enumerate().all(|(i, digit)| {
+            let byte = bytes[i / 2];
+            let nibble = if i % 2 == 0 { byte >> 4 } else { byte & 0xf };
+            hex_value(digit) == nibble
+        })
+    }
+
+    /// Expected number of seeds to try before one matches: 16 to the power of the digits
+    pub fn expected_attempts(&self) -> u64 {
+        16u64.saturating_pow(self.digits.len() as u32)
+    }
+}
+
+impl fmt::Display for AddressPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.digits)
+    }
+}
+
+impl FromStr for AddressPrefix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let digits = s.strip_prefix("0x").unwrap_or(s).to_ascii_lowercase();
+        if digits.is_empty() || digits.len() > AccountAddress::LENGTH * 2 {
+            anyhow::bail!(
+                "invalid address prefix `{}`; expected 1 to {} hex digits",
+                s,
+                AccountAddress::LENGTH * 2
+            );
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            anyhow::bail!("invalid address prefix `{}`; `{}` is not a hex digit", s, c);
+        }
+        Ok(AddressPrefix { digits })
+    }
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        _ => digit - b'a' + 10,
+    }
+}
@@ -0,0 +1,1235 @@
+use crate::address::{object_code_address, resource_account_address};
+use crate::db_schema::TableDefinition;
+use crate::diagnostics::{Diagnostics, did_you_mean};
+use crate::move_manifest::{load_package_name, manifest_path};
+use crate::standard_templates::StandardTemplates;
+use anyhow::Result;
+use move_core_types::account_address::AccountAddress;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct YeaptorConfig {
+    pub format_version: u64,
+    /// On-chain address where the module `ra_code_deployment` is published
+    #[schemars(with = "String")]
+    pub yeaptor_address: AccountAddress,
+    /// Module of `yeaptor_address` called by resource account deployments, for forks that
+    /// renamed it (default: `ra_code_deployment`)
+    pub deploy_module: Option<String>,
+    /// Entry function of `deploy_module` taking the seed, metadata and modules (default: `deploy`)
+    pub deploy_function: Option<String>,
+    /// Settings replacing the ones above on one network, selected with `--network <name>`
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkSettings>,
+    /// Publisher name to account address, referenced by `deployments.publisher`: an address,
+    /// or `{ address, key }` with the key source signing for it
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, PublisherSchema>")]
+    pub publishers: BTreeMap<String, Publisher>,
+    /// Fixed named addresses passed to every package build
+    #[serde(default, rename = "named-addresses")]
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// Packages published under a resource account derived from a publisher and a seed
+    #[serde(default)]
+    pub deployments: Vec<Deployment>,
+    /// Format of the payload files of `deployment build` (default: `entry-function-json`)
+    pub payload_format: Option<String>,
+    /// Path of each payload file under the output directory, from `{deployment}` (the
+    /// deployment's directory name), `{order}`, `{package}` and `{file}` (the file name of the
+    /// payload format) (default: `{deployment}/{file}`)
+    pub payload_file_name: Option<String>,
+    /// `ordered` (default) or `stable`: whether the `{file}` of `payload_file_name` starts with
+    /// the package's position in the deployment order
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub payload_naming: Option<PayloadNaming>,
+    #[serde(default)]
+    pub processor: ProcessorSettings,
+    /// `[policy]` section: how `deployment build` treats risky upgrade policies and which
+    /// dependencies it accepts
+    #[serde(default)]
+    pub policy: PolicySettings,
+}
+
+impl YeaptorConfig {
+    /// Account of a deployment's `publisher`: an alias from `[publishers]` or a literal address
+    pub fn publisher_address(&self, publisher: &str) -> Option<AccountAddress> {
+        self.publishers
+            .get(publisher)
+            .map(|entry| entry.address)
+            .or_else(|| AccountAddress::from_hex_literal(publisher).ok())
+    }
+
+    /// Key source of a deployment's `publisher` in `[publishers]`; `None` for a literal address
+    /// or an alias without `key`
+    pub fn publisher_key(&self, publisher: &str) -> Option<&KeySource> {
+        self.publishers.get(publisher)?.key.as_ref()
+    }
+
+    /// Entry function resource account deployments call
+    pub fn deploy_function(&self) -> DeployFunction {
+        DeployFunction {
+            address: self.yeaptor_address,
+            module: self
+                .deploy_module
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DEPLOY_MODULE.to_string()),
+            function: self
+                .deploy_function
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DEPLOY_FUNCTION.to_string()),
+        }
+    }
+
+    /// The config with the settings of `[networks.<network>]` in place of the top-level ones;
+    /// an unknown network is an error naming the closest one
+    pub fn for_network(mut self, network: &str) -> Result<Self> {
+        let Some(settings) = self.networks.get(network).cloned() else {
+            let suggestion = match did_you_mean(network, self.networks.keys().map(String::as_str)) {
+                Some(known) => format!(", did you mean `{}`?", known),
+                None if self.networks.is_empty() => "; yeaptor.toml has no [networks]".to_string(),
+                None => format!(
+                    "; known networks: {}",
+                    self.networks.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            };
+            anyhow::bail!("unknown network `{}`{}", network, suggestion);
+        };
+        if let Some(address) = settings.yeaptor_address {
+            self.yeaptor_address = address;
+        }
+        if settings.deploy_module.is_some() {
+            self.deploy_module = settings.deploy_module;
+        }
+        if settings.deploy_function.is_some() {
+            self.deploy_function = settings.deploy_function;
+        }
+        if let Some(policy) = settings.policy {
+            self.policy.arbitrary_upgrade =
+                policy.arbitrary_upgrade.or(self.policy.arbitrary_upgrade);
+            self.policy.upgrade_policy_mismatch = policy
+                .upgrade_policy_mismatch
+                .or(self.policy.upgrade_policy_mismatch);
+            if policy.allowed_dependency_addresses.is_some() {
+                self.policy.allowed_dependency_addresses = policy.allowed_dependency_addresses;
+            }
+            if policy.allowed_git_sources.is_some() {
+                self.policy.allowed_git_sources = policy.allowed_git_sources;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Account every deployment publishes to (`Deployment::account`), in order; `None` when its
+    /// publisher is unknown
+    pub fn deployment_addresses(&self) -> Vec<Option<AccountAddress>> {
+        self.deployments
+            .iter()
+            .map(|deployment| {
+                self.publisher_address(&deployment.publisher)
+                    .map(|publisher| deployment.account(&publisher))
+            })
+            .collect()
+    }
+
+    /// Fail on a tag of `tags` that no deployment or package carries, suggesting the closest one
+    pub fn check_tags(&self, tags: &[String]) -> Result<()> {
+        let known: BTreeSet<&str> = self
+            .deployments
+            .iter()
+            .flat_map(|deployment| {
+                deployment
+                    .tags
+                    .iter()
+                    .chain(deployment.packages.iter().flat_map(|package| &package.tags))
+            })
+            .map(String::as_str)
+            .collect();
+        for tag in tags {
+            if known.contains(tag.as_str()) {
+                continue;
+            }
+            match did_you_mean(tag, known.iter().copied()) {
+                Some(closest) => {
+                    anyhow::bail!("unknown tag `{}`, did you mean `{}`?", tag, closest)
+                }
+                None if known.is_empty() => {
+                    anyhow::bail!("unknown tag `{}`; no deployment or package has tags", tag)
+                }
+                None => anyhow::bail!(
+                    "unknown tag `{}`; known tags: {}",
+                    tag,
+                    known.iter().copied().collect::<Vec<_>>().join(", ")
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Point the `path` of every package with `git` at its directory in the checkout under
+    /// `cache_dir` (`GitSource::package_dir`), without fetching anything. A package needs
+    /// exactly one of `path` and `git`, and `subdir` must stay inside the repository; the error
+    /// is `Diagnostics`.
+    pub fn resolve_git_sources(&mut self, cache_dir: &Path) -> Result<()> {
+        let mut diagnostics = Diagnostics::default();
+        for (i, deployment) in self.deployments.iter_mut().enumerate() {
+            for (j, package) in deployment.packages.iter_mut().enumerate() {
+                let location = format!("deployments[{}].packages[{}]", i, j);
+                let Some(git) = &package.git else {
+                    if package.path.as_os_str().is_empty() {
+                        diagnostics.push(location, "missing `path` or `git`");
+                    }
+                    continue;
+                };
+                if !package.path.as_os_str().is_empty() {
+                    diagnostics.push(
+                        location,
+                        "set either `path` or `git`, not both; `subdir` of `git` selects a directory of the repository",
+                    );
+                    continue;
+                }
+                let escapes = git.subdir.as_deref().is_some_and(|subdir| {
+                    Path::new(subdir)
+                        .components()
+                        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+                });
+                if escapes {
+                    diagnostics.push(
+                        location,
+                        format!(
+                            "`subdir` {} must be a relative path inside the repository",
+                            git.subdir.as_deref().unwrap_or_default()
+                        ),
+                    );
+                    continue;
+                }
+                package.path = git.package_dir(cache_dir);
+            }
+        }
+        diagnostics.into_result()
+    }
+
+    /// Replace every package whose `path` is a pattern by one package per matching directory
+    /// holding a Move.toml, in path order, with `{dir}` and `{package}` of its `address_name`
+    /// filled in (`PackageSpec::path`). A pattern matching nothing is an error (`Diagnostics`).
+    pub fn expand_package_paths(&mut self) -> Result<()> {
+        let mut diagnostics = Diagnostics::default();
+        for (i, deployment) in self.deployments.iter_mut().enumerate() {
+            let mut packages = Vec::with_capacity(deployment.packages.len());
+            for (j, package) in deployment.packages.drain(..).enumerate() {
+                if !is_path_pattern(&package.path) {
+                    packages.push(package);
+                    continue;
+                }
+                let location = format!("deployments[{}].packages[{}]", i, j);
+                let dirs = match expand_path_pattern(&package.path) {
+                    Ok(dirs) if dirs.is_empty() => {
+                        diagnostics.push(
+                            location,
+                            format!(
+                                "`{}` matches no directory with a Move.toml",
+                                package.path.display()
+                            ),
+                        );
+                        continue;
+                    }
+                    Ok(dirs) => dirs,
+                    Err(e) => {
+                        diagnostics.push(location, format!("{:#}", e));
+                        continue;
+                    }
+                };
+                for dir in dirs {
+                    let dir_name = dir
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let mut address_name = package
+                        .address_name
+                        .replace("{dir}", &address_name_part(&dir_name));
+                    if address_name.contains("{package}") {
+                        match load_package_name(&dir) {
+                            Ok(Some(name)) => {
+                                address_name =
+                                    address_name.replace("{package}", &address_name_part(&name));
+                            }
+                            Ok(None) => {
+                                diagnostics.push(
+                                    location.clone(),
+                                    format!(
+                                        "{} has no package name for `{{package}}`",
+                                        manifest_path(&dir).display()
+                                    ),
+                                );
+                                continue;
+                            }
+                            Err(e) => {
+                                diagnostics.push(
+                                    location.clone(),
+                                    format!("{}: {:#}", manifest_path(&dir).display(), e),
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    packages.push(PackageSpec {
+                        address_name,
+                        path: dir,
+                        ..package.clone()
+                    });
+                }
+            }
+            deployment.packages = packages;
+        }
+        diagnostics.into_result()
+    }
+
+    /// Rejects deployments publishing to the same account (the same publisher and seed,
+    /// publisher aliases of one account with the same seed, one publisher's own account twice or
+    /// one object twice), settings that do not fit a deployment's mode (`Deployment::mode_problems`),
+    /// deployments sharing an output directory or whose directory name is empty, `.` or `..`,
+    /// named addresses bound to two different addresses, naming both places of each collision,
+    /// packages whose `addresses` rebind their own `address_name` and `git` sources whose `url`
+    /// or `rev` starts with `-`. The error is `Diagnostics`.
+    pub fn validate(&self) -> Result<()> {
+        let mut diagnostics = Diagnostics::default();
+        let names = [
+            ("deploy_module".to_string(), &self.deploy_module),
+            ("deploy_function".to_string(), &self.deploy_function),
+        ];
+        let network_names = self.networks.iter().flat_map(|(network, settings)| {
+            [
+                (
+                    format!("networks.{}.deploy_module", network),
+                    &settings.deploy_module,
+                ),
+                (
+                    format!("networks.{}.deploy_function", network),
+                    &settings.deploy_function,
+                ),
+            ]
+        });
+        for (location, name) in names.into_iter().chain(network_names) {
+            if let Some(name) = name.as_deref().filter(|name| !is_identifier(name)) {
+                diagnostics.push(location, format!("`{}` is not a Move identifier", name));
+            }
+        }
+        for (i, deployment) in self.deployments.iter().enumerate() {
+            for problem in deployment.mode_problems() {
+                diagnostics.push(format!("deployments[{}]", i), problem);
+            }
+            for (j, package) in deployment.packages.iter().enumerate() {
+                let Some(git) = &package.git else {
+                    continue;
+                };
+                // both are passed to git as arguments, where a leading `-` reads as an option
+                for (key, value) in [("url", &git.url), ("rev", &git.rev)] {
+                    if value.starts_with('-') {
+                        diagnostics.push(
+                            format!("deployments[{}].packages[{}].git.{}", i, j, key),
+                            format!("`{}` must not start with `-`", value),
+                        );
+                    }
+                }
+            }
+        }
+        let addresses = self.deployment_addresses();
+        let mut accounts: BTreeMap<AccountAddress, usize> = BTreeMap::new();
+        let mut bound: BTreeMap<&str, (String, AccountAddress)> = self
+            .named_addresses
+            .iter()
+            .map(|(name, address)| (name.as_str(), ("[named-addresses]".to_string(), *address)))
+            .collect();
+        for (i, address) in addresses.iter().copied().enumerate() {
+            let Some(address) = address else {
+                continue;
+            };
+            let deployment = &self.deployments[i];
+            if let Some(&first) = accounts.get(&address) {
+                let first_target = self.deployments[first].target();
+                diagnostics.push(
+                    format!("deployments[{}]", i),
+                    match deployment.mode() {
+                        DeploymentMode::ResourceAccount => format!(
+                            "publisher `{}` and seed `{}` derive resource account {}, like deployments[{}] ({})",
+                            deployment.publisher,
+                            deployment.seed(),
+                            address.to_standard_string(),
+                            first,
+                            first_target
+                        ),
+                        DeploymentMode::Account => format!(
+                            "publisher `{}` publishes to its own account {}, like deployments[{}] ({})",
+                            deployment.publisher,
+                            address.to_standard_string(),
+                            first,
+                            first_target
+                        ),
+                        DeploymentMode::Object => format!(
+                            "{} publishes to object {}, like deployments[{}] ({})",
+                            deployment.target(),
+                            address.to_standard_string(),
+                            first,
+                            first_target
+                        ),
+                    },
+                );
+            } else {
+                accounts.insert(address, i);
+            }
+            for (j, package) in deployment.packages.iter().enumerate() {
+                let location = format!("deployments[{}].packages[{}]", i, j);
+                if package.addresses.contains_key(&package.address_name) {
+                    diagnostics.push(
+                        location.clone(),
+                        format!(
+                            "`addresses` rebinds the package's own address_name `{}`, which is always the deployment's account",
+                            package.address_name
+                        ),
+                    );
+                }
+                match bound.get(package.address_name.as_str()) {
+                    Some((first, first_address)) if *first_address != address => {
+                        diagnostics.push(
+                            location,
+                            format!(
+                                "named address `{}` is {} here but {} in {}",
+                                package.address_name,
+                                address.to_standard_string(),
+                                first_address.to_standard_string(),
+                                first
+                            ),
+                        );
+                    }
+                    Some(_) => {}
+                    None => {
+                        bound.insert(&package.address_name, (location, address));
+                    }
+                }
+            }
+        }
+        // Deployments of the same resource account are reported above, not again for their
+        // directory
+        let mut dir_names: BTreeMap<String, usize> = BTreeMap::new();
+        for (i, deployment) in self.deployments.iter().enumerate() {
+            let dir_name = deployment.dir_name();
+            if matches!(dir_name.as_str(), "" | "." | "..") {
+                diagnostics.push(
+                    format!("deployments[{}]", i),
+                    format!(
+                        "output directory `{}` is not a directory of its own under the output directory; give the deployment another `name`",
+                        dir_name
+                    ),
+                );
+                continue;
+            }
+            match dir_names.get(&dir_name) {
+                Some(&first) if addresses[i].is_some() && addresses[i] == addresses[first] => {}
+                Some(first) => diagnostics.push(
+                    format!("deployments[{}]", i),
+                    format!(
+                        "output directory `{}` is also the one of deployments[{}]; give one of them another `name`",
+                        dir_name, first
+                    ),
+                ),
+                None => {
+                    dir_names.insert(dir_name, i);
+                }
+            }
+        }
+        diagnostics.into_result()
+    }
+}
+
+/// Whether `path` has a `*` or `?` wildcard (`PackageSpec::path`)
+fn is_path_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?'])
+}
+
+/// Directories matching `pattern` component by component that hold a Move.toml, sorted; hidden
+/// directories only match a component starting with `.`
+fn expand_path_pattern(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let component = component.as_os_str().to_string_lossy();
+        if !component.contains(['*', '?']) {
+            for path in &mut matches {
+                path.push(component.as_ref());
+            }
+            continue;
+        }
+        let mut next = Vec::new();
+        for base in &matches {
+            let dir = if base.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                base.as_path()
+            };
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if (name.starts_with('.') && !component.starts_with('.'))
+                    || !entry.path().is_dir()
+                    || !wildcard_matches(&component, &name)
+                {
+                    continue;
+                }
+                next.push(base.join(name));
+            }
+        }
+        matches = next;
+    }
+    let mut dirs = matches
+        .into_iter()
+        .filter(|dir| manifest_path(dir).is_file())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any characters and `?` for one
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // matched[j]: whether the pattern so far matches the first j characters of `name`
+    let mut matched = vec![false; name.len() + 1];
+    matched[0] = true;
+    for p in pattern {
+        let mut next = vec![false; name.len() + 1];
+        for j in 0..=name.len() {
+            next[j] = match p {
+                '*' => matched[j] || (j > 0 && next[j - 1]),
+                '?' => j > 0 && matched[j - 1],
+                c => j > 0 && matched[j - 1] && name[j - 1] == c,
+            };
+        }
+        matched = next;
+    }
+    matched[name.len()]
+}
+
+/// `name` as part of a named address: characters other than ASCII letters, digits and `_`
+/// replaced by `_`
+fn address_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Whether `name` can name a Move module or function: a letter or `_` followed by letters,
+/// digits and `_`
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Module of `ra_code_deployment::deploy` unless `deploy_module` renames it
+pub const DEFAULT_DEPLOY_MODULE: &str = "ra_code_deployment";
+
+/// Entry function of `ra_code_deployment::deploy` unless `deploy_function` renames it
+pub const DEFAULT_DEPLOY_FUNCTION: &str = "deploy";
+
+/// An entry of `[publishers]`: `alias = "0x..."`, or `alias = { address = "0x...", key = ... }`
+/// to also say where its signing key comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Publisher {
+    pub address: AccountAddress,
+    pub key: Option<KeySource>,
+}
+
+impl From<AccountAddress> for Publisher {
+    fn from(address: AccountAddress) -> Self {
+        Publisher { address, key: None }
+    }
+}
+
+impl<'de> Deserialize<'de> for Publisher {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Table {
+            address: AccountAddress,
+            key: Option<KeySource>,
+        }
+
+        struct PublisherVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PublisherVisitor {
+            type Value = Publisher;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an address or a table with `address` and `key`")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Publisher, E> {
+                AccountAddress::from_str(value)
+                    .map(Publisher::from)
+                    .map_err(E::custom)
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<Publisher, A::Error> {
+                let table = Table::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                Ok(Publisher {
+                    address: table.address,
+                    key: table.key,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(PublisherVisitor)
+    }
+}
+
+/// JSON Schema of a `[publishers]` entry (`Publisher`)
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[schemars(rename = "Publisher")]
+#[serde(untagged, deny_unknown_fields)]
+enum PublisherSchema {
+    Address(String),
+    WithKey {
+        address: String,
+        key: Option<KeySource>,
+    },
+}
+
+/// Where the signing key of a publisher comes from (`key` of a `[publishers]` entry), for the
+/// commands that sign and submit: `{ profile = "<name>" }` (a profile of the aptos CLI config),
+/// `{ env = "<VAR>" }` (a hex private key in an environment variable), `{ keystore = "<path>" }`
+/// (an encrypted keystore file) or `"external"` (signed outside yeaptor, e.g. on a hardware
+/// wallet or through `deployment export-unsigned`)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeySource {
+    Profile(String),
+    Env(String),
+    Keystore(PathBuf),
+    External,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::Profile(profile) => write!(f, "aptos profile `{}`", profile),
+            KeySource::Env(var) => write!(f, "environment variable `{}`", var),
+            KeySource::Keystore(path) => write!(f, "keystore {}", path.display()),
+            KeySource::External => f.write_str("external signer"),
+        }
+    }
+}
+
+/// `[networks.<name>]`: settings replacing the top-level ones with `--network <name>`, for a
+/// deployment contract living at another address, or under another name, on that network
+#[derive(Deserialize, Debug, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkSettings {
+    #[schemars(with = "Option<String>")]
+    pub yeaptor_address: Option<AccountAddress>,
+    pub deploy_module: Option<String>,
+    pub deploy_function: Option<String>,
+    /// Keys of `[policy]` set differently on this network, e.g. `arbitrary_upgrade = "error"` on
+    /// mainnet only
+    pub policy: Option<PolicySettings>,
+}
+
+/// `[policy]` section: what `deployment build` does about packages whose upgrade policy is risky,
+/// and the dependencies packages may pull in
+#[derive(Deserialize, Debug, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PolicySettings {
+    /// A package built with `upgrade_policy = "arbitrary"`, which lets its publisher replace it
+    /// with incompatible code (default: `warn`)
+    pub arbitrary_upgrade: Option<PolicyLevel>,
+    /// Packages of one deployment built with different upgrade policies (default: `warn`)
+    pub upgrade_policy_mismatch: Option<PolicyLevel>,
+    /// Addresses dependency modules may be published at, besides the accounts of the
+    /// deployments themselves, e.g. `["0x1", "0x3", "0x4"]`; a build depending on a module at
+    /// any other address fails (default: any address)
+    #[schemars(with = "Option<Vec<String>>")]
+    pub allowed_dependency_addresses: Option<Vec<AccountAddress>>,
+    /// Git repositories dependencies may be fetched from, as URLs or URL prefixes (e.g.
+    /// `https://github.com/aptos-labs/`); a package or local dependency declaring a `git`
+    /// dependency elsewhere fails the build (default: any repository)
+    pub allowed_git_sources: Option<Vec<String>>,
+}
+
+impl PolicySettings {
+    pub fn arbitrary_upgrade(&self) -> PolicyLevel {
+        self.arbitrary_upgrade.unwrap_or(PolicyLevel::Warn)
+    }
+
+    pub fn upgrade_policy_mismatch(&self) -> PolicyLevel {
+        self.upgrade_policy_mismatch.unwrap_or(PolicyLevel::Warn)
+    }
+
+    /// Whether dependency modules may be published at `address`
+    pub fn allows_dependency_address(&self, address: &AccountAddress) -> bool {
+        self.allowed_dependency_addresses
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(address))
+    }
+
+    /// Whether dependencies may be fetched from the git repository `url`: it equals or extends
+    /// one of `allowed_git_sources`, ignoring a trailing `/` or `.git`
+    pub fn allows_git_source(&self, url: &str) -> bool {
+        let url = normalize_git_url(url);
+        self.allowed_git_sources.as_ref().is_none_or(|allowed| {
+            allowed.iter().any(|source| {
+                let source = normalize_git_url(source);
+                url == source || url.starts_with(&format!("{}/", source))
+            })
+        })
+    }
+}
+
+fn normalize_git_url(url: &str) -> &str {
+    let url = url.trim();
+    let url = url.strip_suffix('/').unwrap_or(url);
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// Whether a `[policy]` finding is ignored, printed as a warning or fails the build
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyLevel {
+    Allow,
+    Warn,
+    Error,
+}
+
+/// Entry function `<address>::<module>::<function>(seed, metadata, modules)` publishing to a
+/// resource account: `ra_code_deployment::deploy` unless renamed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployFunction {
+    pub address: AccountAddress,
+    pub module: String,
+    pub function: String,
+}
+
+impl DeployFunction {
+    /// `ra_code_deployment::deploy` published at `address`
+    pub fn new(address: AccountAddress) -> Self {
+        DeployFunction {
+            address,
+            module: DEFAULT_DEPLOY_MODULE.to_string(),
+            function: DEFAULT_DEPLOY_FUNCTION.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DeployFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{}::{}",
+            self.address.to_standard_string(),
+            self.module,
+            self.function
+        )
+    }
+}
+
+/// `[processor]` section: settings for `yeaptor processor` commands
+#[derive(Deserialize, Debug, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessorSettings {
+    pub spec_creator: Option<String>,
+    pub spec_name: Option<String>,
+    /// When unset, the version is carried over from the previously generated file and bumped on change
+    pub spec_version: Option<String>,
+    /// Last version to process (inclusive), for bounded backfills and reproducible snapshots
+    pub ending_version: Option<u64>,
+    /// Table receiving events that have no mapping (`processor generate --catch-all-table`)
+    pub catch_all_table: Option<String>,
+    /// Restrict generated configs to transactions touching the deployments' accounts
+    /// (`processor generate --account-filter`)
+    #[serde(default)]
+    pub account_filter: bool,
+    /// Table schemas declared inline (`[processor.tables.<table>.columns]`), merged with `--db-schema`
+    #[serde(default)]
+    pub tables: BTreeMap<String, TableDefinition>,
+    /// Framework events (fungible asset, coin, object) indexed into ready-made tables
+    #[serde(default, rename = "standard-templates")]
+    pub standard_templates: StandardTemplates,
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Deployment {
+    /// Output subdirectory of the deployment (default: `<publisher>-<seed>`, `<publisher>` with
+    /// mode `account`, `<publisher>-<sequence_number>` or `<object_address>` with mode `object`)
+    pub name: Option<String>,
+    /// `resource-account` (default), `account` or `object`
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub mode: Option<DeploymentMode>,
+    pub publisher: String,
+    /// Seed deriving the resource account; required with mode `resource-account` only
+    pub seed: Option<String>,
+    /// Sequence number of the publisher's transaction creating the package's object, which its
+    /// address is derived from (mode `object`)
+    pub sequence_number: Option<u64>,
+    /// Existing code object to upgrade (mode `object`)
+    #[schemars(with = "Option<String>")]
+    pub object_address: Option<AccountAddress>,
+    /// Labels selecting the deployment's packages with `--tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub packages: Vec<PackageSpec>,
+}
+
+impl Deployment {
+    pub fn mode(&self) -> DeploymentMode {
+        self.mode.unwrap_or_default()
+    }
+
+    /// `seed`, empty when unset (modes `account` and `object`)
+    pub fn seed(&self) -> &str {
+        self.seed.as_deref().unwrap_or_default()
+    }
+
+    /// Account the packages are published to: the resource account of `publisher` and the seed,
+    /// `publisher` itself with mode `account`, or the code object with mode `object`
+    /// (`object_address`, or the object the publish of `sequence_number` creates)
+    pub fn account(&self, publisher: &AccountAddress) -> AccountAddress {
+        match self.mode() {
+            DeploymentMode::ResourceAccount => {
+                resource_account_address(publisher, self.seed().as_bytes())
+            }
+            DeploymentMode::Account => *publisher,
+            DeploymentMode::Object => match self.object_address {
+                Some(object) => object,
+                None => object_code_address(publisher, self.sequence_number.unwrap_or_default()),
+            },
+        }
+    }
+
+    /// Settings that do not fit the deployment's mode: a resource account deployment needs a
+    /// `seed`; an object deployment needs either `sequence_number` (to publish a new object) or
+    /// `object_address` (to upgrade one) and holds one package, as every publish creates its own
+    /// object
+    pub fn mode_problems(&self) -> Vec<String> {
+        let mode = self.mode();
+        let mut problems = Vec::new();
+        match (mode, &self.seed) {
+            (DeploymentMode::ResourceAccount, None) => problems.push(
+                "missing `seed`; a resource account is derived from the publisher and a seed"
+                    .to_string(),
+            ),
+            (DeploymentMode::Account, Some(_)) => problems.push(
+                "`seed` is not used with mode = \"account\", which publishes to the publisher's own account; remove it"
+                    .to_string(),
+            ),
+            (DeploymentMode::Object, Some(_)) => problems.push(
+                "`seed` is not used with mode = \"object\", whose address follows from the publisher's `sequence_number`; remove it"
+                    .to_string(),
+            ),
+            _ => {}
+        }
+        match (mode, self.sequence_number, self.object_address) {
+            (DeploymentMode::Object, None, None) => problems.push(
+                "mode = \"object\" needs `sequence_number` (of the publisher's transaction creating the object) or `object_address` (of the object to upgrade)"
+                    .to_string(),
+            ),
+            (DeploymentMode::Object, Some(_), Some(_)) => problems.push(
+                "set either `sequence_number` to publish a new object or `object_address` to upgrade one, not both"
+                    .to_string(),
+            ),
+            (DeploymentMode::Object, _, _) => {}
+            (_, None, None) => {}
+            (_, _, _) => problems.push(format!(
+                "`sequence_number` and `object_address` are only used with mode = \"object\", not \"{}\"",
+                mode
+            )),
+        }
+        if mode == DeploymentMode::Object && self.packages.len() > 1 {
+            problems.push(format!(
+                "an object deployment publishes one package, but this one has {}; move the others into deployments of their own",
+                self.packages.len()
+            ));
+        }
+        problems
+    }
+
+    /// How collision messages name the deployment's target
+    fn target(&self) -> String {
+        match self.mode() {
+            DeploymentMode::ResourceAccount => {
+                format!("publisher `{}`, seed `{}`", self.publisher, self.seed())
+            }
+            DeploymentMode::Account => format!("publisher `{}`, mode `account`", self.publisher),
+            DeploymentMode::Object => match (self.object_address, self.sequence_number) {
+                (Some(object), _) => format!("object_address {}", object.to_standard_string()),
+                (None, sequence_number) => format!(
+                    "publisher `{}`, sequence_number {}",
+                    self.publisher,
+                    sequence_number.unwrap_or_default()
+                ),
+            },
+        }
+    }
+
+    /// Output subdirectory: `name`, or `<publisher>-<seed>` (`<publisher>` with mode `account`),
+    /// with characters other than ASCII letters, digits, `.`, `_` and `-` replaced by `_`
+    pub fn dir_name(&self) -> String {
+        let name = match (&self.name, self.mode()) {
+            (Some(name), _) => name.clone(),
+            (None, DeploymentMode::ResourceAccount) => {
+                format!("{}-{}", self.publisher, self.seed())
+            }
+            (None, DeploymentMode::Account) => self.publisher.clone(),
+            (None, DeploymentMode::Object) => match (self.object_address, self.sequence_number) {
+                (Some(object), _) => object.to_standard_string(),
+                (None, sequence_number) => {
+                    format!("{}-{}", self.publisher, sequence_number.unwrap_or_default())
+                }
+            },
+        };
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Whether `package` of this deployment is selected by `tags`: every package when `tags` is
+    /// empty, otherwise those carrying one of them themselves or through the deployment
+    pub fn selects(&self, package: &PackageSpec, tags: &[String]) -> bool {
+        tags.is_empty()
+            || tags
+                .iter()
+                .any(|tag| self.tags.contains(tag) || package.tags.contains(tag))
+    }
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PackageSpec {
+    /// Named address bound to the deployment's account; with a `path` pattern, `{dir}` and
+    /// `{package}` stand for the directory name and the Move.toml package name of each match
+    pub address_name: String,
+    /// Package directory, or a pattern such as `packages/markets/*` (`*` and `?` within a path
+    /// component) standing for every matching directory with a Move.toml. With `git`, set on
+    /// load to the package's directory in the checkout.
+    #[serde(default)]
+    pub path: PathBuf,
+    /// Repository the package is fetched from instead of `path`
+    pub git: Option<GitSource>,
+    /// Named addresses bound for this package only, over `[named-addresses]` and the accounts
+    /// of the other packages, e.g. `oracle_addr` pointed at another oracle
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub addresses: BTreeMap<String, AccountAddress>,
+    /// `none`, `sparse` or `all`
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub include_artifacts: Option<IncludedArtifacts>,
+    /// Labels selecting the package with `--tags`, besides those of its deployment
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// `git` of a package: a repository checked out at a pinned revision into the git cache
+/// (`git_cache_dir`) before building
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GitSource {
+    pub url: String,
+    /// Commit, tag or branch checked out; a commit hash keeps the build reproducible, as a tag or
+    /// branch is only fetched once into the cache
+    pub rev: String,
+    /// Directory of the package in the repository (default: its root)
+    pub subdir: Option<String>,
+}
+
+impl GitSource {
+    /// Checkout of the repository at `rev` under `cache_dir`: the repository name followed by a
+    /// hash of the URL and revision, so every pinned revision gets its own directory
+    pub fn checkout_dir(&self, cache_dir: &Path) -> PathBuf {
+        let url = self.url.trim_end_matches('/');
+        let name = url
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".git");
+        let hash = Sha3_256::digest(format!("{}@{}", url, self.rev).as_bytes());
+        let hash = hash[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        let name = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        cache_dir.join(format!("{}-{}", name, hash))
+    }
+
+    /// Directory of the package: `subdir` of the checkout under `cache_dir`
+    pub fn package_dir(&self, cache_dir: &Path) -> PathBuf {
+        let checkout = self.checkout_dir(cache_dir);
+        match &self.subdir {
+            Some(subdir) => checkout.join(subdir),
+            None => checkout,
+        }
+    }
+}
+
+/// Where `git` packages are checked out: `$YEAPTOR_GIT_CACHE`, otherwise `~/.yeaptor/git`
+pub fn git_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("YEAPTOR_GIT_CACHE") {
+        return PathBuf::from(dir);
+    }
+    match std::env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".yeaptor/git"),
+        None => PathBuf::from(".yeaptor/git"),
+    }
+}
+
+/// Artifacts published with a package (`include_artifacts`), as the aptos CLI's
+/// `--included-artifacts`: `none`, `sparse` or `all`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludedArtifacts {
+    None,
+    Sparse,
+    All,
+}
+
+impl fmt::Display for IncludedArtifacts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IncludedArtifacts::None => "none",
+            IncludedArtifacts::Sparse => "sparse",
+            IncludedArtifacts::All => "all",
+        })
+    }
+}
+
+impl FromStr for IncludedArtifacts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(IncludedArtifacts::None),
+            "sparse" => Ok(IncludedArtifacts::Sparse),
+            "all" => Ok(IncludedArtifacts::All),
+            _ => anyhow::bail!(
+                "invalid included artifacts `{}`; expected none, sparse or all",
+                s
+            ),
+        }
+    }
+}
+
+/// Where a deployment publishes its packages (`mode`): `resource-account` publishes through
+/// `ra_code_deployment::deploy` to the resource account derived from the publisher and seed;
+/// `account` publishes with `0x1::code::publish_package_txn` to the publisher's own account;
+/// `object` publishes with `0x1::object_code_deployment::publish` to a new code object, or
+/// upgrades one with `object_code_deployment::upgrade`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeploymentMode {
+    #[default]
+    ResourceAccount,
+    Account,
+    Object,
+}
+
+impl fmt::Display for DeploymentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeploymentMode::ResourceAccount => "resource-account",
+            DeploymentMode::Account => "account",
+            DeploymentMode::Object => "object",
+        })
+    }
+}
+
+impl FromStr for DeploymentMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "resource-account" => Ok(DeploymentMode::ResourceAccount),
+            "account" => Ok(DeploymentMode::Account),
+            "object" => Ok(DeploymentMode::Object),
+            _ => anyhow::bail!(
+                "invalid deployment mode `{}`; expected resource-account, account or object",
+                s
+            ),
+        }
+    }
+}
+
+/// How payload files are named (`payload_naming`): `ordered` files start with the package's
+/// position in the deployment order (`0-core.package.json`), which changes when a package is
+/// inserted before it; `stable` files are named by the package alone (`core.package.json`) and
+/// the order is only recorded in `manifest.json`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadNaming {
+    #[default]
+    Ordered,
+    Stable,
+}
+
+impl fmt::Display for PayloadNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PayloadNaming::Ordered => "ordered",
+            PayloadNaming::Stable => "stable",
+        })
+    }
+}
+
+impl FromStr for PayloadNaming {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ordered" => Ok(PayloadNaming::Ordered),
+            "stable" => Ok(PayloadNaming::Stable),
+            _ => anyhow::bail!("invalid payload naming `{}`; expected ordered or stable", s),
+        }
+    }
+}
+
+/// Read, expand (`YeaptorConfig::resolve_git_sources` into `git_cache_dir`,
+/// `YeaptorConfig::expand_package_paths`) and validate yeaptor.toml; package paths are relative
+/// to the current directory
+pub fn load_config(path: &Path) -> Result<YeaptorConfig> {
+    let s = fs::read_to_string(path)?;
+    let mut cfg = parse_config(&s)?;
+    cfg.resolve_git_sources(&git_cache_dir())?;
+    cfg.expand_package_paths()?;
+    cfg.validate()?;
+    Ok(cfg)
+}
+
+/// Parse yeaptor.toml. Unknown keys are rejected rather than ignored; the error (`Diagnostics`)
+/// names the line and the closest known key, e.g. "unknown key `named_addresses`, did you mean
+/// `named-addresses`?"
+pub fn parse_config(s: &str) -> Result<YeaptorConfig> {
+    toml::from_str(s).map_err(|e| -> anyhow::Error {
+        let Some((key, expected)) = unknown_field(e.message()) else {
+            return e.into();
+        };
+        let line = e
+            .span()
+            .map_or(1, |span| s[..span.start].matches('\n').count() + 1);
+        let message = match did_you_mean(key, expected.iter().copied()) {
+            Some(known) => format!("unknown key `{}`, did you mean `{}`?", key, known),
+            None if expected.is_empty() => format!("unknown key `{}`", key),
+            None => format!(
+                "unknown key `{}`, expected one of {}",
+                key,
+                expected
+                    .iter()
+                    .map(|known| format!("`{}`", known))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.push(format!("line {}", line), message);
+        diagnostics.into()
+    })
+}
+
+/// `source` (a yeaptor.toml) with the `seed` of `deployments[index]` set to `seed`, keeping the
+/// rest of the file as written: the seed's value is replaced in place, or a `seed = ...` line is
+/// added after the deployment's `publisher` when it has none
+pub fn set_deployment_seed(source: &str, index: usize, seed: &str) -> Result<String> {
+    #[derive(Deserialize)]
+    struct Spans {
+        #[serde(default)]
+        deployments: Vec<DeploymentSpans>,
+    }
+    #[derive(Deserialize)]
+    struct DeploymentSpans {
+        publisher: toml::Spanned<String>,
+        seed: Option<toml::Spanned<String>>,
+    }
+
+    let spans: Spans = toml::from_str(source)?;
+    let Some(deployment) = spans.deployments.get(index) else {
+        anyhow::bail!(
+            "no deployments[{}]; the config has {} deployments",
+            index,
+            spans.deployments.len()
+        );
+    };
+    let value = toml::Value::String(seed.to_string()).to_string();
+    let updated = match &deployment.seed {
+        Some(old) => {
+            let span = old.span();
+            format!("{}{}{}", &source[..span.start], value, &source[span.end..])
+        }
+        None => {
+            let end = deployment.publisher.span().end;
+            let line_end = source[end..].find('\n').map_or(source.len(), |i| end + i);
+            let rest = source[end..line_end].trim();
+            if !(rest.is_empty() || rest.starts_with('#')) {
+                anyhow::bail!(
+                    "cannot add a seed to deployments[{}] written inline; add `seed = {}` by hand",
+                    index,
+                    value
+                );
+            }
+            format!(
+                "{}\nseed = {}{}",
+                &source[..line_end],
+                value,
+                &source[line_end..]
+            )
+        }
+    };
+    parse_config(&updated)?;
+    Ok(updated)
+}
+
+/// Key and expected keys of serde's "unknown field `x`, expected one of `a`, `b`" message
+fn unknown_field(message: &str) -> Option<(&str, Vec<&str>)> {
+    let rest = message.strip_prefix("unknown field ")?;
+    let mut quoted = rest.split('`').skip(1).step_by(2);
+    Some((quoted.next()?, quoted.collect()))
+}
+
+/// JSON Schema of yeaptor.toml, for editor completion and validating configs before a run
+pub fn config_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(YeaptorConfig)).unwrap_or_default()
+}
@@ -0,0 +1,915 @@
+use crate::event_definition::EventDefinition;
+use crate::processor_config::{
+    ColumnSpec, ColumnTypeSpec, ConflictStrategy, CustomConfig, IndexDefinition, IndexMethod,
+    PartitionDefinition, PartitionInterval, TableSchema,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use move_core_types::account_address::AccountAddress;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+const COLUMN_KINDS: &[&str] = &["move_type", "transaction_metadata", "event_metadata"];
+
+// ===================== CSV Loader for db_schema =====================
+fn parse_bool_cell(s: &str) -> bool {
+    matches!(
+        s.trim().to_ascii_lowercase().as_str(),
+        "true" | "t" | "1" | "yes" | "y"
+    )
+}
+
+fn parse_default_value_cell(s: Option<&str>, type_spec: &ColumnTypeSpec) -> Option<YamlValue> {
+    match s.map(|v| v.trim()) {
+        Some(v) if !v.is_empty() => {
+            match (type_spec.r#type.as_str(), type_spec.column_type.as_str()) {
+                // Numeric types
+                ("move_type", "u8" | "u16" | "u32" | "u64") => {
+                    v.parse::<u64>().ok().map(YamlValue::from).or_else(|| {
+                        // If parsing fails, keep as string
+                        Some(YamlValue::String(v.to_string()))
+                    })
+                }
+                // Boolean type
+                ("move_type", "bool") => {
+                    // need to be string "true" or "false" to be parsed by geomi
+                    Some(YamlValue::String(
+                        if parse_bool_cell(v) { "true" } else { "false" }.to_string(),
+                    ))
+                }
+                // Address type - keep as string
+                ("move_type", "address") => Some(YamlValue::String(v.to_string())),
+                ("transaction_metadata", _) => {
+                    v.parse::<u64>().ok().map(YamlValue::from).or_else(|| {
+                        // If parsing fails, keep as string
+                        Some(YamlValue::String(v.to_string()))
+                    })
+                }
+                // Timestamp and version types - treat as numeric if possible
+                ("event_metadata", "creation_number" | "sequence_number" | "event_index") => v
+                    .parse::<u64>()
+                    .ok()
+                    .map(YamlValue::from)
+                    .or_else(|| Some(YamlValue::String(v.to_string()))),
+                // Default case - keep as string
+                _ => Some(YamlValue::String(v.to_string())),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Check that a literal (e.g. a mapping constant) fits a `move_type` column and normalize it the
+/// way CSV defaults are: small integers as numbers, bools, addresses and wide integers as strings.
+pub fn normalize_column_value(spec: &ColumnSpec, value: &YamlValue) -> Result<YamlValue> {
+    if spec.column_type.r#type != "move_type" {
+        bail!(
+            "{} column {} cannot hold a constant",
+            spec.column_type.r#type,
+            spec.column_type.column_type
+        );
+    }
+    match value {
+        YamlValue::Null if spec.is_nullable || spec.is_option => Ok(YamlValue::Null),
+        YamlValue::Null => bail!("column is not nullable"),
+        YamlValue::Sequence(items) if spec.is_vec => items
+            .iter()
+            .map(|item| normalize_scalar(&spec.column_type.column_type, item))
+            .collect::<Result<Vec<_>>>()
+            .map(YamlValue::Sequence),
+        _ if spec.is_vec => bail!("expected a list for vector column"),
+        _ => normalize_scalar(&spec.column_type.column_type, value),
+    }
+}
+
+/// Unsigned decimal number such as `1.5` or `100`
+pub fn is_decimal(text: &str) -> bool {
+    let (integer, fraction) = text.split_once('.').unwrap_or((text, "0"));
+    [integer, fraction]
+        .iter()
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn normalize_scalar(column_type: &str, value: &YamlValue) -> Result<YamlValue> {
+    let text = match value {
+        YamlValue::String(v) => v.clone(),
+        YamlValue::Number(v) => v.to_string(),
+        YamlValue::Bool(v) => v.to_string(),
+        other => bail!("expected a {} value, got {:?}", column_type, other),
+    };
+    let invalid = || anyhow!("`{}` is not a valid {} value", text, column_type);
+    match column_type {
+        "u8" => text
+            .parse::<u8>()
+            .map(YamlValue::from)
+            .map_err(|_| invalid()),
+        "u16" => text
+            .parse::<u16>()
+            .map(YamlValue::from)
+            .map_err(|_| invalid()),
+        "u32" => text
+            .parse::<u32>()
+            .map(YamlValue::from)
+            .map_err(|_| invalid()),
+        "u64" => text
+            .parse::<u64>()
+            .map(YamlValue::from)
+            .map_err(|_| invalid()),
+        "u128" => text
+            .parse::<u128>()
+            .map(|_| YamlValue::String(text.clone()))
+            .map_err(|_| invalid()),
+        "u256" if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) => {
+            Ok(YamlValue::String(text.clone()))
+        }
+        "u256" => Err(invalid()),
+        "bool" if text == "true" || text == "false" => Ok(YamlValue::String(text.clone())),
+        "bool" => Err(invalid()),
+        "decimal" if is_decimal(&text) => Ok(YamlValue::String(text.clone())),
+        "decimal" => Err(invalid()),
+        "address" | "object" => AccountAddress::from_hex_literal(&text)
+            .map(|_| YamlValue::String(text.clone()))
+            .map_err(|_| invalid()),
+        _ => Ok(YamlValue::String(text.clone())),
+    }
+}
+
+// Serde helpers for CSV field decoding
+fn de_bool_flex<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Ok(parse_bool_cell(&raw))
+}
+
+fn de_opt_string<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    Ok(opt.and_then(|s| {
+        let t = s.trim().to_string();
+        if t.is_empty() { None } else { Some(t) }
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DBSchema {
+    pub table: String,
+    pub column: String,
+    pub column_type: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    #[serde(default, deserialize_with = "de_opt_string")]
+    pub default_value: Option<String>,
+    #[serde(deserialize_with = "de_bool_flex")]
+    pub is_index: bool,
+    #[serde(deserialize_with = "de_bool_flex")]
+    pub is_nullable: bool,
+    #[serde(deserialize_with = "de_bool_flex")]
+    pub is_option: bool,
+    #[serde(deserialize_with = "de_bool_flex")]
+    pub is_primary_key: bool,
+    #[serde(deserialize_with = "de_bool_flex")]
+    pub is_vec: bool,
+    /// Optional trailing columns; see [`load_db_schema_table_options`]
+    #[serde(default, deserialize_with = "de_opt_string")]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub on_conflict: Option<ConflictStrategy>,
+}
+
+pub fn load_db_schema_from_csv(path: &Path) -> Result<BTreeMap<String, TableSchema>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to open CSV: {}", path.display()))?;
+    db_schema_from_csv(path, &content)
+}
+
+fn db_schema_from_csv(path: &Path, content: &str) -> Result<BTreeMap<String, TableSchema>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut tables: BTreeMap<String, TableSchema> = BTreeMap::new();
+    for row in rdr.deserialize::<DBSchema>() {
+        let row = row.with_context(|| format!("failed to parse CSV row in {}", path.display()))?;
+        let column_type_spec = ColumnTypeSpec {
+            column_type: row.column_type,
+            r#type: row.r#type,
+        };
+        let col_spec = ColumnSpec {
+            default_value: parse_default_value_cell(
+                row.default_value.as_deref(),
+                &column_type_spec,
+            ),
+            column_type: column_type_spec,
+            is_index: row.is_index,
+            is_nullable: row.is_nullable,
+            is_option: row.is_option,
+            is_primary_key: row.is_primary_key,
+            is_vec: row.is_vec,
+        };
+        tables
+            .entry(row.table)
+            .or_insert_with(BTreeMap::new)
+            .insert(row.column, col_spec);
+    }
+
+    Ok(tables)
+}
+
+// ===================== Structured (TOML/YAML) loader for db_schema =====================
+
+/// Structured schema input, from `db_schema.toml`/`db_schema.yaml` or `[processor.tables]` in yeaptor.toml:
+///
+/// ```toml
+/// [tables.borrow_market.columns]
+/// market = { column_type = "address", is_primary_key = true }
+/// transaction_version = { column_type = "version", type = "transaction_metadata" }
+///
+/// [[tables.borrow_market.indexes]]
+/// columns = ["market", "transaction_version"]
+/// unique = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DbSchemaDocument {
+    #[serde(default)]
+    pub tables: BTreeMap<String, TableDefinition>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TableDefinition {
+    /// Processor group the table belongs to, e.g. `core` or `analytics`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// What the processor does when a row's primary key already exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_conflict: Option<ConflictStrategy>,
+    /// Composite, unique or non-btree indexes, beyond the single-column `is_index` flags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indexes: Vec<IndexDefinition>,
+    /// Time- or version-based range partitioning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<PartitionDefinition>,
+    #[serde(default)]
+    pub columns: BTreeMap<String, ColumnDefinition>,
+}
+
+/// Same fields as a db_schema.csv row; flags default to false and `type` to `move_type`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnDefinition {
+    pub column_type: String,
+    #[serde(
+        rename = "type",
+        default = "default_column_kind",
+        skip_serializing_if = "is_default_column_kind"
+    )]
+    pub r#type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub default_value: Option<YamlValue>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_index: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_nullable: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_option: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_primary_key: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_vec: bool,
+}
+
+fn default_column_kind() -> String {
+    "move_type".to_string()
+}
+
+fn is_default_column_kind(kind: &str) -> bool {
+    kind == "move_type"
+}
+
+pub(crate) fn is_false(flag: &bool) -> bool {
+    !*flag
+}
+
+impl DbSchemaDocument {
+    pub fn to_table_schemas(&self) -> Result<BTreeMap<String, TableSchema>> {
+        self.tables
+            .iter()
+            .map(|(table, definition)| {
+                definition
+                    .to_table_schema()
+                    .with_context(|| format!("invalid schema for table {}", table))
+                    .map(|schema| (table.clone(), schema))
+            })
+            .collect()
+    }
+}
+
+impl From<&BTreeMap<String, TableSchema>> for DbSchemaDocument {
+    fn from(schemas: &BTreeMap<String, TableSchema>) -> Self {
+        DbSchemaDocument::from_tables(schemas, &BTreeMap::new())
+    }
+}
+
+impl DbSchemaDocument {
+    /// Structured document for the given tables and their options, the inverse of
+    /// [`DbSchemaDocument::to_table_schemas`] and [`DbSchemaDocument::table_options`]
+    pub fn from_tables(
+        schemas: &BTreeMap<String, TableSchema>,
+        options: &BTreeMap<String, TableOptions>,
+    ) -> Self {
+        let tables = schemas
+            .iter()
+            .map(|(table, schema)| {
+                let columns = schema
+                    .iter()
+                    .map(|(column, spec)| {
+                        (
+                            column.clone(),
+                            ColumnDefinition {
+                                column_type: spec.column_type.column_type.clone(),
+                                r#type: spec.column_type.r#type.clone(),
+                                default_value: spec.default_value.clone(),
+                                is_index: spec.is_index,
+                                is_nullable: spec.is_nullable,
+                                is_option: spec.is_option,
+                                is_primary_key: spec.is_primary_key,
+                                is_vec: spec.is_vec,
+                            },
+                        )
+                    })
+                    .collect();
+                let options = options.get(table).cloned().unwrap_or_default();
+                (
+                    table.clone(),
+                    TableDefinition {
+                        group: options.group,
+                        on_conflict: options.on_conflict,
+                        indexes: options.indexes,
+                        partition_by: options.partition_by,
+                        columns,
+                    },
+                )
+            })
+            .collect();
+        DbSchemaDocument { tables }
+    }
+}
+
+impl TableDefinition {
+    pub fn to_table_schema(&self) -> Result<TableSchema> {
+        let mut schema = TableSchema::new();
+        for (column, definition) in &self.columns {
+            if !COLUMN_KINDS.contains(&definition.r#type.as_str()) {
+                bail!(
+                    "column {} has unknown type `{}`, expected one of {}",
+                    column,
+                    definition.r#type,
+                    COLUMN_KINDS.join(", ")
+                );
+            }
+            let column_type_spec = ColumnTypeSpec {
+                column_type: definition.column_type.clone(),
+                r#type: definition.r#type.clone(),
+            };
+            // Scalars go through the same typing rules as CSV cells
+            let default_value = match &definition.default_value {
+                Some(YamlValue::String(v)) => {
+                    parse_default_value_cell(Some(v.as_str()), &column_type_spec)
+                }
+                Some(YamlValue::Bool(v)) => {
+                    parse_default_value_cell(Some(v.to_string().as_str()), &column_type_spec)
+                }
+                Some(YamlValue::Number(v)) => {
+                    parse_default_value_cell(Some(v.to_string().as_str()), &column_type_spec)
+                }
+                Some(YamlValue::Null) | None => None,
+                Some(other) => Some(other.clone()),
+            };
+            schema.insert(
+                column.clone(),
+                ColumnSpec {
+                    column_type: column_type_spec,
+                    default_value,
+                    is_index: definition.is_index,
+                    is_nullable: definition.is_nullable,
+                    is_option: definition.is_option,
+                    is_primary_key: definition.is_primary_key,
+                    is_vec: definition.is_vec,
+                },
+            );
+        }
+        Ok(schema)
+    }
+}
+
+pub fn load_db_schema_from_toml(path: &Path) -> Result<BTreeMap<String, TableSchema>> {
+    schema_document_from_toml(path, &read_schema(path)?)?
+        .to_table_schemas()
+        .with_context(|| format!("invalid schema in {}", path.display()))
+}
+
+pub fn load_db_schema_from_yaml(path: &Path) -> Result<BTreeMap<String, TableSchema>> {
+    schema_document_from_yaml(path, &read_schema(path)?)?
+        .to_table_schemas()
+        .with_context(|| format!("invalid schema in {}", path.display()))
+}
+
+/// Load a db schema, picking the format from the file extension (`.toml`, `.yaml`/`.yml`, otherwise CSV)
+pub fn load_db_schema(path: &Path) -> Result<BTreeMap<String, TableSchema>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml" | "yaml" | "yml") => parse_db_schema(path, &read_schema(path)?),
+        _ => load_db_schema_from_csv(path),
+    }
+}
+
+/// `load_db_schema` of `content` already read (and possibly rendered) from `path`, whose
+/// extension picks the format
+pub fn parse_db_schema(path: &Path, content: &str) -> Result<BTreeMap<String, TableSchema>> {
+    let doc = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => schema_document_from_toml(path, content)?,
+        Some("yaml" | "yml") => schema_document_from_yaml(path, content)?,
+        _ => return db_schema_from_csv(path, content),
+    };
+    doc.to_table_schemas()
+        .with_context(|| format!("invalid schema in {}", path.display()))
+}
+
+fn read_schema(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read schema: {}", path.display()))
+}
+
+fn schema_document_from_toml(path: &Path, content: &str) -> Result<DbSchemaDocument> {
+    toml::from_str(content)
+        .with_context(|| format!("failed to parse TOML schema: {}", path.display()))
+}
+
+fn schema_document_from_yaml(path: &Path, content: &str) -> Result<DbSchemaDocument> {
+    serde_yaml::from_str(content)
+        .with_context(|| format!("failed to parse YAML schema: {}", path.display()))
+}
+
+// ===================== Table options =====================
+
+/// Group of tables that are not tagged with one
+pub const DEFAULT_TABLE_GROUP: &str = "default";
+
+/// Table-level settings from the schema input, alongside the column schemas
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableOptions {
+    pub group: Option<String>,
+    pub on_conflict: Option<ConflictStrategy>,
+    pub indexes: Vec<IndexDefinition>,
+    pub partition_by: Option<PartitionDefinition>,
+}
+
+impl DbSchemaDocument {
+    /// Table -> options, for tables that set any
+    pub fn table_options(&self) -> BTreeMap<String, TableOptions> {
+        self.tables
+            .iter()
+            .map(|(table, definition)| {
+                (
+                    table.clone(),
+                    TableOptions {
+                        group: definition.group.clone(),
+                        on_conflict: definition.on_conflict,
+                        indexes: definition.indexes.clone(),
+                        partition_by: definition.partition_by.clone(),
+                    },
+                )
+            })
+            .filter(|(_, options)| options != &TableOptions::default())
+            .collect()
+    }
+}
+
+/// Read the table options of a schema file: `group`/`on_conflict`/`indexes`/`partition_by` per
+/// table in TOML/YAML, or trailing `group`/`on_conflict` columns in CSV, where rows of one table
+/// must agree and empty cells are ignored. CSV schemas cannot declare `indexes` or `partition_by`.
+pub fn load_db_schema_table_options(path: &Path) -> Result<BTreeMap<String, TableOptions>> {
+    parse_db_schema_table_options(path, &read_schema(path)?)
+}
+
+/// `load_db_schema_table_options` of `content` already read (and possibly rendered) from `path`
+pub fn parse_db_schema_table_options(
+    path: &Path,
+    content: &str,
+) -> Result<BTreeMap<String, TableOptions>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => Ok(schema_document_from_toml(path, content)?.table_options()),
+        Some("yaml" | "yml") => Ok(schema_document_from_yaml(path, content)?.table_options()),
+        _ => {
+            let mut rdr = csv::ReaderBuilder::new()
+                .has_headers(true)
+                .trim(csv::Trim::All)
+                .from_reader(content.as_bytes());
+            let mut tables: BTreeMap<String, TableOptions> = BTreeMap::new();
+            for row in rdr.deserialize::<DBSchema>() {
+                let row =
+                    row.with_context(|| format!("failed to parse CSV row in {}", path.display()))?;
+                if row.group.is_none() && row.on_conflict.is_none() {
+                    continue;
+                }
+                let options = tables.entry(row.table.clone()).or_default();
+                merge_table_option(&mut options.group, row.group, &row.table, "group")
+                    .with_context(|| format!("invalid table options in {}", path.display()))?;
+                merge_table_option(
+                    &mut options.on_conflict,
+                    row.on_conflict,
+                    &row.table,
+                    "on_conflict",
+                )
+                .with_context(|| format!("invalid table options in {}", path.display()))?;
+            }
+            Ok(tables)
+        }
+    }
+}
+
+fn merge_table_option<T: PartialEq + std::fmt::Debug>(
+    current: &mut Option<T>,
+    value: Option<T>,
+    table: &str,
+    name: &str,
+) -> Result<()> {
+    match (current.as_ref(), value) {
+        (Some(existing), Some(value)) if existing != &value => bail!(
+            "table {} has conflicting {} values {:?} and {:?}",
+            table,
+            name,
+            existing,
+            value
+        ),
+        (None, Some(value)) => *current = Some(value),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Tables together with their options, e.g. a schema input or the state recorded by migrations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaSnapshot {
+    pub tables: BTreeMap<String, TableSchema>,
+    pub options: BTreeMap<String, TableOptions>,
+}
+
+impl SchemaSnapshot {
+    /// Indexes declared for a table, empty when it has none
+    pub fn indexes(&self, table: &str) -> &[IndexDefinition] {
+        self.options
+            .get(table)
+            .map(|options| options.indexes.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn partition_by(&self, table: &str) -> Option<&PartitionDefinition> {
+        self.options
+            .get(table)
+            .and_then(|options| options.partition_by.as_ref())
+    }
+}
+
+pub fn load_schema_snapshot(path: &Path) -> Result<SchemaSnapshot> {
+    Ok(SchemaSnapshot {
+        tables: load_db_schema(path)?,
+        options: load_db_schema_table_options(path)?,
+    })
+}
+
+/// Render tables and their options in the structured db_schema.toml layout
+pub fn schema_snapshot_to_toml(snapshot: &SchemaSnapshot) -> Result<String> {
+    toml::to_string(&DbSchemaDocument::from_tables(
+        &snapshot.tables,
+        &snapshot.options,
+    ))
+    .context("failed to serialize TOML schema")
+}
+
+/// Check the `indexes` of a table against its columns and what each index method supports
+pub fn validate_table_indexes(
+    table: &str,
+    schema: &TableSchema,
+    indexes: &[IndexDefinition],
+) -> Result<()> {
+    let mut names = BTreeSet::new();
+    for index in indexes {
+        let name = index.index_name(table);
+        if index.columns.is_empty() {
+            bail!("index {} of table {} has no columns", name, table);
+        }
+        for column in &index.columns {
+            if !schema.contains_key(column) {
+                bail!(
+                    "index {} of table {} refers to unknown column {}",
+                    name,
+                    table,
+                    column
+                );
+            }
+        }
+        if index.method == Some(IndexMethod::Hash) && (index.unique || index.columns.len() > 1) {
+            bail!(
+                "hash index {} of table {} must be a single non-unique column",
+                name,
+                table
+            );
+        }
+        if index.method == Some(IndexMethod::Brin) && index.unique {
+            bail!("brin index {} of table {} cannot be unique", name, table);
+        }
+        if !names.insert(name.clone()) {
+            bail!("table {} declares index {} twice", table, name);
+        }
+    }
+    Ok(())
+}
+
+/// Check the `partition_by` of a table: the column must be a non-nullable scalar, a `timestamp`
+/// partitioned by period or an integer partitioned by a number of values, and part of the primary
+/// key and of every unique index, since Postgres enforces uniqueness per partition.
+pub fn validate_table_partitioning(
+    table: &str,
+    schema: &TableSchema,
+    indexes: &[IndexDefinition],
+    partition_by: &PartitionDefinition,
+) -> Result<()> {
+    let column = &partition_by.column;
+    let Some(spec) = schema.get(column) else {
+        bail!(
+            "table {} is partitioned by unknown column {}",
+            table,
+            column
+        );
+    };
+    if spec.is_vec || (!spec.is_primary_key && (spec.is_nullable || spec.is_option)) {
+        bail!(
+            "table {} is partitioned by {}, which must be a non-nullable scalar",
+            table,
+            column
+        );
+    }
+    let column_type = spec.column_type.column_type.as_str();
+    let is_timestamp = column_type == "timestamp";
+    let is_integer = matches!(
+        (spec.column_type.r#type.as_str(), column_type),
+        ("move_type", "u8" | "u16" | "u32" | "u64")
+            | ("transaction_metadata", "version" | "block_height" | "epoch")
+            | (
+                "event_metadata",
+                "creation_number" | "sequence_number" | "event_index"
+            )
+    );
+    match partition_by.interval {
+        PartitionInterval::Period(_) if !is_timestamp => bail!(
+            "table {} is partitioned by period over {}, which is not a timestamp",
+            table,
+            column
+        ),
+        PartitionInterval::Values(_) if !is_integer => bail!(
+            "table {} is partitioned by value range over {}, which is not an integer up to u64",
+            table,
+            column
+        ),
+        PartitionInterval::Values(0) => bail!("table {} has a partition interval of 0", table),
+        _ => {}
+    }
+    let has_primary_key = schema.values().any(|spec| spec.is_primary_key);
+    if has_primary_key && !spec.is_primary_key {
+        bail!(
+            "table {} is partitioned by {}, which must be part of its primary key",
+            table,
+            column
+        );
+    }
+    if let Some(index) = indexes
+        .iter()
+        .find(|index| index.unique && !index.columns.contains(column))
+    {
+        bail!(
+            "unique index {} of table {} must include partition column {}",
+            index.index_name(table),
+            table,
+            column
+        );
+    }
+    Ok(())
+}
+
+/// Partition tables by group; untagged tables go to [`DEFAULT_TABLE_GROUP`]
+pub fn split_table_schemas_by_group(
+    tables: &BTreeMap<String, TableSchema>,
+    groups: &BTreeMap<String, String>,
+) -> BTreeMap<String, BTreeMap<String, TableSchema>> {
+    let mut out: BTreeMap<String, BTreeMap<String, TableSchema>> = BTreeMap::new();
+    for (table, schema) in tables {
+        let group = groups
+            .get(table)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_TABLE_GROUP);
+        out.entry(group.to_string())
+            .or_default()
+            .insert(table.clone(), schema.clone());
+    }
+    out
+}
+
+/// Add tables from another schema source, refusing to silently redefine a table
+pub fn merge_table_schemas(
+    into: &mut BTreeMap<String, TableSchema>,
+    from: BTreeMap<String, TableSchema>,
+) -> Result<()> {
+    for (table, schema) in from {
+        if into.contains_key(&table) {
+            bail!("table {} is defined more than once", table);
+        }
+        into.insert(table, schema);
+    }
+    Ok(())
+}
+
+// ===================== Schema scaffolding from event definitions =====================
+
+/// Metadata columns added to every scaffolded table: (column, column_type, type, is_primary_key)
+const SCAFFOLD_METADATA_COLUMNS: &[(&str, &str, &str, bool)] = &[
+    (
+        "transaction_version",
+        "version",
+        "transaction_metadata",
+        true,
+    ),
+    ("event_index", "event_index", "event_metadata", true),
+    ("timestamp", "timestamp", "transaction_metadata", false),
+];
+
+/// Build a starter schema with one table per event, named after the event in snake_case
+/// (prefixed with the module name when two events share a name). Struct-typed fields are
+/// replaced by their nested leaves; Move types without a column equivalent become `string`.
+pub fn scaffold_db_schema(event_definitions: &[EventDefinition]) -> BTreeMap<String, TableSchema> {
+    let mut name_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for event in event_definitions {
+        *name_counts.entry(to_snake_case(&event.name)).or_default() += 1;
+    }
+    let mut tables = BTreeMap::new();
+    for event in event_definitions {
+        let mut table = to_snake_case(&event.name);
+        if name_counts[&table] > 1 {
+            table = format!("{}_{}", event.module_name, table);
+        }
+        let mut schema = TableSchema::new();
+        for (path, move_type) in event.fields.iter().chain(event.nested_fields.iter()) {
+            let nested_prefix = format!("{}.", path);
+            if event
+                .nested_fields
+                .keys()
+                .any(|k| k.starts_with(&nested_prefix))
+            {
+                // struct-typed: its leaves get their own columns
+                continue;
+            }
+            schema.insert(path.replace('.', "_"), column_for_move_type(move_type));
+        }
+        for (column, column_type, kind, is_primary_key) in SCAFFOLD_METADATA_COLUMNS {
+            schema.entry(column.to_string()).or_insert(ColumnSpec {
+                column_type: ColumnTypeSpec {
+                    column_type: column_type.to_string(),
+                    r#type: kind.to_string(),
+                },
+                default_value: None,
+                is_index: !is_primary_key,
+                is_nullable: !is_primary_key,
+                is_option: false,
+                is_primary_key: *is_primary_key,
+                is_vec: false,
+            });
+        }
+        tables.insert(table, schema);
+    }
+    tables
+}
+
+/// Column for a Move type as printed in event definitions (`u64`, `vector<address>`,
+/// `0x1::option::Option<u64>`, `0x1::object::Object<...>`, `0x1::string::String`)
+pub(crate) fn column_for_move_type(move_type: &str) -> ColumnSpec {
+    let mut inner = move_type.trim();
+    let mut is_option = false;
+    let mut is_vec = false;
+    if let Some(rest) = generic_argument(inner, "::option::Option<") {
+        is_option = true;
+        inner = rest;
+    }
+    if let Some(rest) = inner
+        .strip_prefix("vector<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        is_vec = true;
+        inner = rest;
+    }
+    let column_type = match inner {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "u256" | "bool" | "address" => inner,
+        _ if generic_argument(inner, "::object::Object<").is_some() => "object",
+        _ => "string",
+    };
+    ColumnSpec {
+        column_type: ColumnTypeSpec {
+            column_type: column_type.to_string(),
+            r#type: "move_type".to_string(),
+        },
+        default_value: None,
+        is_index: false,
+        is_nullable: true,
+        is_option,
+        is_primary_key: false,
+        is_vec,
+    }
+}
+
+/// `0x1::option::Option<T>` -> `T`, whatever form the `0x1` address is printed in
+pub(crate) fn generic_argument<'a>(move_type: &'a str, marker: &str) -> Option<&'a str> {
+    let (address, rest) = move_type.split_once(marker)?;
+    if !address.starts_with("0x") || address.contains('<') {
+        return None;
+    }
+    rest.strip_suffix('>')
+}
+
+/// `MarketCreatedEvent` -> `market_created_event`, as scaffolded table names
+pub fn to_snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Render a schema as db_schema.csv, one row per column
+pub fn db_schema_to_csv(tables: &BTreeMap<String, TableSchema>) -> Result<String> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record([
+        "table",
+        "column",
+        "column_type",
+        "type",
+        "default_value",
+        "is_index",
+        "is_nullable",
+        "is_option",
+        "is_primary_key",
+        "is_vec",
+    ])?;
+    for (table, schema) in tables {
+        for (column, spec) in schema {
+            let default_value = match &spec.default_value {
+                Some(YamlValue::String(v)) => v.clone(),
+                Some(v) => serde_yaml::to_string(v)?.trim().to_string(),
+                None => String::new(),
+            };
+            wtr.write_record([
+                table.as_str(),
+                column.as_str(),
+                spec.column_type.column_type.as_str(),
+                spec.column_type.r#type.as_str(),
+                default_value.as_str(),
+                bool_cell(spec.is_index),
+                bool_cell(spec.is_nullable),
+                bool_cell(spec.is_option),
+                bool_cell(spec.is_primary_key),
+                bool_cell(spec.is_vec),
+            ])?;
+        }
+    }
+    let bytes = wtr
+        .into_inner()
+        .map_err(|e| anyhow!("failed to write CSV schema: {}", e.error()))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn bool_cell(flag: bool) -> &'static str {
+    if flag { "True" } else { "False" }
+}
+
+/// Render a schema in the structured db_schema.toml layout
+pub fn db_schema_to_toml(tables: &BTreeMap<String, TableSchema>) -> Result<String> {
+    toml::to_string(&DbSchemaDocument::from(tables)).context("failed to serialize TOML schema")
+}
+
+pub fn load_db_schema_into_custom(custom: &mut CustomConfig, path: &Path) -> Result<()> {
+    custom.db_schema = load_db_schema(path)?;
+    Ok(())
+}
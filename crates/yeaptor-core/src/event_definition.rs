@@ -0,0 +1,34 @@
+use move_core_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDefinition {
+    pub package_name: String,
+    pub module_address: AccountAddress,
+    pub module_name: String,
+    pub name: String,
+    pub fields: BTreeMap<String, String>,
+    /// Whether the event is a module event (`#[event]`) or a legacy `EventHandle<T>` event.
+    /// Older event files without this key are module events.
+    #[serde(default)]
+    pub version: EventVersion,
+    /// Root package whose build pulled this definition in from a dependency (`--include-deps`);
+    /// absent for events declared by the package itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_of: Option<String>,
+    /// Fields inside struct-typed fields, keyed by dotted path (`position.collateral.value`).
+    /// Only structs declared in the built modules outside `0x1` are expanded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nested_fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventVersion {
+    /// Module event, declared with the `#[event]` attribute and emitted via `event::emit`
+    #[default]
+    V2,
+    /// Legacy event, emitted through an `0x1::event::EventHandle<T>` stored in a resource
+    V1,
+}
@@ -0,0 +1,87 @@
+use crate::db_schema::generic_argument;
+use crate::event_definition::EventDefinition;
+use move_core_types::account_address::AccountAddress;
+use serde_json::{Map, Value as JsonValue, json};
+
+/// Address every `address` field and object of a fixture holds
+pub const FIXTURE_ADDRESS: AccountAddress = AccountAddress::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0a,
+    0x11, 0xce,
+]);
+
+/// Move type of an event as the transaction stream prints it, `0x42::market::MarketCreatedEvent`
+pub fn event_type(definition: &EventDefinition) -> String {
+    format!(
+        "{}::{}::{}",
+        definition.module_address.to_hex_literal(),
+        definition.module_name,
+        definition.name
+    )
+}
+
+/// Synthetic data of an event, shaped like the JSON the transaction stream carries: `u64` and
+/// wider integers as decimal strings, addresses as hex, `vector<u8>` as a hex string, strings
+/// holding their field path, options with one value (`{"vec": [..]}`) and objects as
+/// `{"inner": address}`. Struct fields are filled from the nested fields of the definition;
+/// structs it does not expand are empty objects.
+pub fn event_fixture(definition: &EventDefinition) -> JsonValue {
+    let mut data = Map::new();
+    for (field, move_type) in &definition.fields {
+        data.insert(field.clone(), value_fixture(definition, field, move_type));
+    }
+    JsonValue::Object(data)
+}
+
+fn value_fixture(definition: &EventDefinition, path: &str, move_type: &str) -> JsonValue {
+    let move_type = move_type.trim();
+    if let Some(inner) = generic_argument(move_type, "::option::Option<") {
+        return json!({ "vec": [value_fixture(definition, path, inner)] });
+    }
+    if generic_argument(move_type, "::object::Object<").is_some() {
+        return json!({ "inner": FIXTURE_ADDRESS.to_standard_string() });
+    }
+    if let Some(inner) = move_type
+        .strip_prefix("vector<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        if inner.trim() == "u8" {
+            return JsonValue::String("0x0102".to_string());
+        }
+        return JsonValue::Array(vec![value_fixture(definition, path, inner)]);
+    }
+    match move_type {
+        "bool" => JsonValue::Bool(true),
+        "u8" | "u16" | "u32" => json!(1),
+        "u64" | "u128" | "u256" => JsonValue::String("100".to_string()),
+        "address" | "signer" => JsonValue::String(FIXTURE_ADDRESS.to_standard_string()),
+        _ if is_string(move_type) => JsonValue::String(path.to_string()),
+        _ => struct_fixture(definition, path),
+    }
+}
+
+/// Object of the nested fields of the definition directly under `path`
+fn struct_fixture(definition: &EventDefinition, path: &str) -> JsonValue {
+    let prefix = format!("{}.", path);
+    let mut data = Map::new();
+    for (nested, move_type) in &definition.nested_fields {
+        let Some(field) = nested.strip_prefix(&prefix) else {
+            continue;
+        };
+        if field.contains('.') {
+            continue;
+        }
+        data.insert(
+            field.to_string(),
+            value_fixture(definition, nested, move_type),
+        );
+    }
+    JsonValue::Object(data)
+}
+
+/// `0x1::string::String`, whatever form the `0x1` address is printed in
+fn is_string(move_type: &str) -> bool {
+    move_type
+        .strip_suffix("::string::String")
+        .and_then(|address| AccountAddress::from_hex_literal(address).ok())
+        == Some(AccountAddress::ONE)
+}
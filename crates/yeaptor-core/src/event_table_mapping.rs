@@ -0,0 +1,795 @@
+use anyhow::{Context, Result, anyhow, bail};
+use move_core_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::diagnostics::{Diagnostics, did_you_mean};
+use crate::event_definition::EventDefinition;
+use crate::processor_config::{
+    ComputeArg, ComputeFunction, CustomConfig, EventMapping, FilterOp, TableSchema, Transform,
+};
+
+/// Event name (`package::module::EventName`) -> table mapping rules
+pub type EventTableMappings = BTreeMap<String, Vec<TableMappingRule>>;
+
+/// How one event is written into one table
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TableMappingRule {
+    pub table: String,
+    /// Event field -> column, for fields whose column name differs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rename: BTreeMap<String, String>,
+    /// Event fields that must not be written to this table
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Column -> constant value written for every row produced by this event
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub constants: BTreeMap<String, YamlValue>,
+    /// Conditions such as `$.amount != "0"`; rows are only written when all hold
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+    /// Column -> expression over event fields, e.g. `hash($.market, $.user)` or `$.supplied - $.borrowed`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed: BTreeMap<String, String>,
+    /// Column -> rewrite of the field written to it: `hex_decode`, `lowercase` or `scale(8)`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transforms: BTreeMap<String, String>,
+}
+
+impl TableMappingRule {
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Entry function (`<address>::module::function`) -> table mapping rules
+pub type EntryFunctionMappings = BTreeMap<String, Vec<EntryFunctionRule>>;
+
+/// Fields of an entry function call that columns can be written from
+pub const ENTRY_FUNCTION_FIELDS: &[&str] = &["arguments", "function", "sender", "type_arguments"];
+
+/// How calls of one entry function are written into one table, for user actions that emit no
+/// event
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EntryFunctionRule {
+    pub table: String,
+    /// Column -> path into the call: `$.sender`, `$.function`, `$.type_arguments.0`,
+    /// `$.arguments.1` or a field of a struct argument (`$.arguments.1.inner`)
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+    /// Column -> constant value written for every call
+    #[serde(default)]
+    pub constants: BTreeMap<String, YamlValue>,
+}
+
+/// Value type of a Move table (`0x42::market::Position`, `u64`) -> table mapping rules
+pub type TableItemMappings = BTreeMap<String, Vec<TableItemRule>>;
+
+/// Fields of a table item write that columns can be written from
+pub const TABLE_ITEM_FIELDS: &[&str] = &["handle", "key", "key_type", "value", "value_type"];
+
+/// How writes of Move table items with one value type are written into one table, for state kept
+/// in tables that no event reports
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TableItemRule {
+    pub table: String,
+    /// Only items of tables with this key type, e.g. `address`
+    pub key_type: Option<String>,
+    /// Only items of the table with this handle
+    pub handle: Option<String>,
+    /// Column -> path into the write: `$.handle`, `$.key`, `$.value`, a field of a struct key or
+    /// value (`$.value.collateral`), `$.key_type` or `$.value_type`
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+    /// Column -> constant value written for every item
+    #[serde(default)]
+    pub constants: BTreeMap<String, YamlValue>,
+}
+
+/// Structured mapping file (`event_mapping.toml` / `event_mapping.yaml`):
+///
+/// ```toml
+/// [[events."yeap-borrow-protocol-common::market::MarketCreatedEvent"]]
+/// table = "borrow_market"
+/// rename = { collateral_asset = "collateral", collateral_risk_factor = "crf" }
+/// exclude = ["oracle"]
+/// constants = { event_source = "lending_v2" }
+/// filters = ['$.market == "0xabc"']
+/// computed = { market_key = 'concat($.market, ":", $.collateral_asset)' }
+/// transforms = { symbol = "hex_decode", supply_cap = "scale(8)" }
+///
+/// [[entry_functions."0x42::market::supply"]]
+/// table = "supply_call"
+/// columns = { user = "$.sender", market = "$.arguments.0", amount = "$.arguments.1" }
+///
+/// [[table_items."0x42::market::Position"]]
+/// table = "position"
+/// key_type = "address"
+/// columns = { user = "$.key", collateral = "$.value.collateral" }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventMappingDocument {
+    #[serde(default)]
+    pub events: EventTableMappings,
+    #[serde(default)]
+    pub entry_functions: EntryFunctionMappings,
+    #[serde(default)]
+    pub table_items: TableItemMappings,
+}
+
+// CSV Loader for event->table mappings
+pub fn load_event_table_mappings_from_csv(path: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to open CSV: {}", path.display()))?;
+    event_table_mappings_from_csv(path, &content)
+}
+
+fn event_table_mappings_from_csv(
+    path: &Path,
+    content: &str,
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut records = rdr.records();
+    // Skip header
+    let _ = records.next();
+
+    let mut map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for result in records {
+        let rec =
+            result.with_context(|| format!("failed to parse CSV row in {}", path.display()))?;
+        if rec.len() < 2 {
+            continue;
+        }
+        let event = rec.get(0).map(|s| s.trim()).unwrap_or("");
+        let table = rec.get(1).map(|s| s.trim()).unwrap_or("");
+        if event.is_empty() || table.is_empty() {
+            continue;
+        }
+        let entry = map.entry(event.to_string()).or_default();
+        if !entry.contains(&table.to_string()) {
+            entry.push(table.to_string());
+        }
+    }
+
+    // Sort table lists for deterministic order
+    for v in map.values_mut() {
+        v.sort();
+    }
+    Ok(map)
+}
+
+/// Convert two-column CSV entries into mapping rules.
+/// `Event -> table` rows create a rule; `Event::field -> table::column` rows become renames on that rule;
+/// `Event -> table::column = value` rows become constants on that rule.
+pub fn table_mappings_from_csv_entries(
+    entries: &BTreeMap<String, Vec<String>>,
+) -> Result<EventTableMappings> {
+    let mut mappings = EventTableMappings::new();
+    for (event, tables) in entries.iter().filter(|(k, _)| k.matches("::").count() == 2) {
+        let rules = mappings.entry(event.clone()).or_default();
+        rules.extend(
+            tables
+                .iter()
+                .filter(|t| !t.contains('='))
+                .map(TableMappingRule::new),
+        );
+    }
+    for (event, targets) in entries.iter().filter(|(k, _)| k.matches("::").count() == 2) {
+        for target in targets.iter().filter(|t| t.contains('=')) {
+            let (column_ref, value) = target.split_once('=').unwrap();
+            let (table, column) = column_ref.trim().split_once("::").ok_or_else(|| {
+                anyhow!(
+                    "invalid constant mapping {} -> {}, expected table::column = value",
+                    event,
+                    target
+                )
+            })?;
+            let value = parse_literal(value.trim())
+                .with_context(|| format!("invalid constant mapping {} -> {}", event, target))?;
+            let rule = mappings
+                .get_mut(event)
+                .and_then(|rules| rules.iter_mut().find(|r| r.table == table))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "constant mapping {} -> {} targets table {} which {} is not mapped to; add a `{}, {}` row",
+                        event,
+                        target,
+                        table,
+                        event,
+                        event,
+                        table
+                    )
+                })?;
+            rule.constants.insert(column.to_string(), value);
+        }
+    }
+    for (key, targets) in entries.iter().filter(|(k, _)| k.matches("::").count() > 2) {
+        let (event, field) = key.rsplit_once("::").unwrap();
+        for target in targets {
+            let (table, column) = target.split_once("::").ok_or_else(|| {
+                anyhow!(
+                    "invalid field mapping {} -> {}, expected table::column",
+                    key,
+                    target
+                )
+            })?;
+            let rule = mappings
+                .get_mut(event)
+                .and_then(|rules| rules.iter_mut().find(|r| r.table == table))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "field mapping {} -> {} targets table {} which {} is not mapped to; add a `{}, {}` row",
+                        key,
+                        target,
+                        table,
+                        event,
+                        event,
+                        table
+                    )
+                })?;
+            rule.rename.insert(field.to_string(), column.to_string());
+        }
+    }
+    Ok(mappings)
+}
+
+fn read_mapping(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read mapping: {}", path.display()))
+}
+
+fn mapping_document_from_toml(path: &Path, content: &str) -> Result<EventMappingDocument> {
+    toml::from_str(content)
+        .with_context(|| format!("failed to parse TOML mapping: {}", path.display()))
+}
+
+fn mapping_document_from_yaml(path: &Path, content: &str) -> Result<EventMappingDocument> {
+    serde_yaml::from_str(content)
+        .with_context(|| format!("failed to parse YAML mapping: {}", path.display()))
+}
+
+/// The structured document of a mapping file; None for CSV mappings
+fn mapping_document(path: &Path, content: &str) -> Result<Option<EventMappingDocument>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => mapping_document_from_toml(path, content).map(Some),
+        Some("yaml" | "yml") => mapping_document_from_yaml(path, content).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Load the `entry_functions` section of a structured mapping file; CSV mappings have none
+pub fn load_entry_function_mappings(path: &Path) -> Result<EntryFunctionMappings> {
+    parse_entry_function_mappings(path, &read_mapping(path)?)
+}
+
+/// `load_entry_function_mappings` of `content` already read (and possibly rendered) from `path`
+pub fn parse_entry_function_mappings(path: &Path, content: &str) -> Result<EntryFunctionMappings> {
+    let Some(document) = mapping_document(path, content)? else {
+        return Ok(EntryFunctionMappings::new());
+    };
+    let mappings = document.entry_functions;
+    for (function, rules) in &mappings {
+        parse_entry_function_id(function).with_context(|| format!("in {}", path.display()))?;
+        for rule in rules {
+            for (column, field) in &rule.columns {
+                parse_entry_function_path(field).with_context(|| {
+                    format!(
+                        "invalid column {} for {} -> {}",
+                        column, function, rule.table
+                    )
+                })?;
+            }
+        }
+    }
+    Ok(mappings)
+}
+
+/// Load the `table_items` section of a structured mapping file; CSV mappings have none
+pub fn load_table_item_mappings(path: &Path) -> Result<TableItemMappings> {
+    parse_table_item_mappings(path, &read_mapping(path)?)
+}
+
+/// `load_table_item_mappings` of `content` already read (and possibly rendered) from `path`
+pub fn parse_table_item_mappings(path: &Path, content: &str) -> Result<TableItemMappings> {
+    let Some(document) = mapping_document(path, content)? else {
+        return Ok(TableItemMappings::new());
+    };
+    let mappings = document.table_items;
+    for (value_type, rules) in &mappings {
+        normalize_move_type(value_type).with_context(|| format!("in {}", path.display()))?;
+        for rule in rules {
+            if let Some(key_type) = &rule.key_type {
+                normalize_move_type(key_type)
+                    .with_context(|| format!("in {} -> {}", value_type, rule.table))?;
+            }
+            if let Some(handle) = &rule.handle {
+                AccountAddress::from_str(handle).map_err(|e| {
+                    anyhow!(
+                        "invalid handle {} for {} -> {}: {}",
+                        handle,
+                        value_type,
+                        rule.table,
+                        e
+                    )
+                })?;
+            }
+            for (column, field) in &rule.columns {
+                parse_table_item_path(field).with_context(|| {
+                    format!(
+                        "invalid column {} for {} -> {}",
+                        column, value_type, rule.table
+                    )
+                })?;
+            }
+        }
+    }
+    Ok(mappings)
+}
+
+/// A Move type with its addresses in short form (`0x0..01::coin::Coin<0x1::aptos_coin::AptosCoin>`
+/// -> `0x1::coin::Coin<0x1::aptos_coin::AptosCoin>`) and no spaces, so types written either way
+/// compare equal
+pub fn normalize_move_type(move_type: &str) -> Result<String> {
+    if move_type.trim().is_empty() {
+        bail!("empty type");
+    }
+    let mut normalized = String::new();
+    for part in move_type.split_inclusive(['<', '>', ',']) {
+        let (name, delimiter) = match part.char_indices().last() {
+            Some((i, '<' | '>' | ',')) => (part[..i].trim(), &part[i..]),
+            _ => (part.trim(), ""),
+        };
+        match name.split_once("::") {
+            Some((address, rest)) => {
+                let address = AccountAddress::from_str(address)
+                    .map_err(|e| anyhow!("invalid address in type {}: {}", move_type, e))?;
+                normalized.push_str(&format!("{}::{}", address.to_hex_literal(), rest));
+            }
+            None if name.is_empty() && delimiter != ">" && delimiter != "," => {
+                bail!("invalid type `{}`", move_type)
+            }
+            None => normalized.push_str(name),
+        }
+        normalized.push_str(delimiter);
+    }
+    Ok(normalized)
+}
+
+/// Check a path into a table item write: `$.handle`, `$.key_type` and `$.value_type` are
+/// scalars, `$.key` and `$.value` the decoded key and value or, below them, their fields
+pub fn parse_table_item_path(path: &str) -> Result<()> {
+    let segments = path
+        .strip_prefix("$.")
+        .ok_or_else(|| anyhow!("invalid path `{}`, must start with `$.`", path))?
+        .split('.')
+        .collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["handle" | "key_type" | "value_type"] | ["key" | "value", ..] => Ok(()),
+        _ => bail!(
+            "invalid path `{}`, expected one of {}",
+            path,
+            TABLE_ITEM_FIELDS
+                .iter()
+                .map(|f| format!("$.{}", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Split `<address>::<module>::<function>`, parsing the address so short and long forms match
+pub fn parse_entry_function_id(function: &str) -> Result<(AccountAddress, String, String)> {
+    let parts = function.split("::").collect::<Vec<_>>();
+    let [address, module, name] = parts.as_slice() else {
+        bail!(
+            "invalid entry function {}, expected <address>::<module>::<function>",
+            function
+        );
+    };
+    let address = AccountAddress::from_str(address)
+        .map_err(|e| anyhow!("invalid address in entry function {}: {}", function, e))?;
+    Ok((address, module.to_string(), name.to_string()))
+}
+
+/// Check a path into an entry function call: `$.sender` and `$.function` are scalars,
+/// `$.type_arguments` and `$.arguments` are vectors indexed by position (`$.arguments.0`)
+pub fn parse_entry_function_path(path: &str) -> Result<()> {
+    let segments = path
+        .strip_prefix("$.")
+        .ok_or_else(|| anyhow!("invalid path `{}`, must start with `$.`", path))?
+        .split('.')
+        .collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["sender" | "function"] => Ok(()),
+        ["type_arguments" | "arguments"] => Ok(()),
+        ["type_arguments", index] | ["arguments", index, ..] if index.parse::<usize>().is_ok() => {
+            Ok(())
+        }
+        [field, ..] if !ENTRY_FUNCTION_FIELDS.contains(field) => bail!(
+            "invalid path `{}`, expected one of {}",
+            path,
+            ENTRY_FUNCTION_FIELDS
+                .iter()
+                .map(|f| format!("$.{}", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => bail!(
+            "invalid path `{}`, arguments are addressed by position, e.g. `$.arguments.0`",
+            path
+        ),
+    }
+}
+
+/// Render event mappings in the format of `path`'s extension, as read back by
+/// [`load_event_table_mappings`]: the `events` section of a structured mapping for `.toml` and
+/// `.yaml`/`.yml`, otherwise two-column CSV (`Event, table` and `Event::field, table::column`
+/// rows), which cannot express excludes, constants, filters, computed columns or transforms
+pub fn render_event_table_mappings(path: &Path, mappings: &EventTableMappings) -> Result<String> {
+    #[derive(Serialize)]
+    struct EventsDocument<'a> {
+        events: &'a EventTableMappings,
+    }
+    let document = EventsDocument { events: mappings };
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::to_string(&document).context("failed to serialize TOML mapping"),
+        Some("yaml" | "yml") => {
+            serde_yaml::to_string(&document).context("failed to serialize YAML mapping")
+        }
+        _ => event_table_mappings_to_csv(mappings),
+    }
+}
+
+fn event_table_mappings_to_csv(mappings: &EventTableMappings) -> Result<String> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(["event", "table"])?;
+    for (event, rules) in mappings {
+        for rule in rules {
+            if !(rule.exclude.is_empty()
+                && rule.constants.is_empty()
+                && rule.filters.is_empty()
+                && rule.computed.is_empty()
+                && rule.transforms.is_empty())
+            {
+                bail!(
+                    "mapping {} -> {} only fits a structured mapping (.toml or .yaml)",
+                    event,
+                    rule.table
+                );
+            }
+            wtr.write_record([event.as_str(), rule.table.as_str()])?;
+            for (field, column) in &rule.rename {
+                wtr.write_record([
+                    format!("{}::{}", event, field),
+                    format!("{}::{}", rule.table, column),
+                ])?;
+            }
+        }
+    }
+    let bytes = wtr
+        .into_inner()
+        .map_err(|e| anyhow!("failed to write CSV mapping: {}", e.error()))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Load event mappings, picking the format from the file extension (`.toml`, `.yaml`/`.yml`, otherwise CSV)
+pub fn load_event_table_mappings(path: &Path) -> Result<EventTableMappings> {
+    let content = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml" | "yaml" | "yml") => read_mapping(path)?,
+        _ => fs::read_to_string(path)
+            .with_context(|| format!("failed to open CSV: {}", path.display()))?,
+    };
+    parse_event_table_mappings(path, &content)
+}
+
+/// `load_event_table_mappings` of `content` already read (and possibly rendered) from `path`,
+/// whose extension picks the format
+pub fn parse_event_table_mappings(path: &Path, content: &str) -> Result<EventTableMappings> {
+    let mappings = match mapping_document(path, content)? {
+        Some(document) => document.events,
+        None => table_mappings_from_csv_entries(&event_table_mappings_from_csv(path, content)?)
+            .with_context(|| format!("invalid mapping in {}", path.display()))?,
+    };
+    for (event, rules) in &mappings {
+        if event.matches("::").count() != 2 {
+            bail!(
+                "invalid event name {} in {}, expected package::module::EventName",
+                event,
+                path.display()
+            );
+        }
+        for rule in rules {
+            if let Some(field) = rule.exclude.iter().find(|f| rule.rename.contains_key(*f)) {
+                bail!(
+                    "field {} of {} is both renamed and excluded for table {}",
+                    field,
+                    event,
+                    rule.table
+                );
+            }
+            for filter in &rule.filters {
+                parse_event_filter(filter)
+                    .with_context(|| format!("invalid filter for {} -> {}", event, rule.table))?;
+            }
+            for (column, expr) in &rule.computed {
+                parse_computed_expr(expr)
+                    .with_context(|| format!("invalid computed column {} for {}", column, event))?;
+            }
+            for (column, transform) in &rule.transforms {
+                parse_transform(transform).with_context(|| {
+                    format!("invalid transform of column {} for {}", column, event)
+                })?;
+            }
+        }
+    }
+    Ok(mappings)
+}
+
+/// Check every event a mapping names against the loaded definitions, and every field it renames
+/// or excludes (the `Event::field` rows of a CSV mapping) against the event's fields, so a typo
+/// is reported with the closest name instead of leaving a mapping that never applies. The error
+/// is `Diagnostics`, located at the event (`package::module::Event`) or the field
+/// (`package::module::Event::field`).
+pub fn check_mapping_references(
+    mappings: &EventTableMappings,
+    definitions: &[EventDefinition],
+) -> Result<()> {
+    let events = definitions
+        .iter()
+        .map(|e| {
+            (
+                format!("{}::{}::{}", e.package_name, e.module_name, e.name),
+                e,
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut diagnostics = Diagnostics::default();
+    for (event, rules) in mappings {
+        let Some(definition) = events.get(event) else {
+            let suggestion = did_you_mean(event, events.keys().map(String::as_str));
+            diagnostics.push(
+                event,
+                match suggestion {
+                    Some(name) => format!("no event definition has this name; did you mean `{}`?", name),
+                    None => "no event definition has this name; is its package's event file in the events directory?".to_string(),
+                },
+            );
+            continue;
+        };
+        let fields = definition
+            .fields
+            .keys()
+            .chain(definition.nested_fields.keys())
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        for rule in rules {
+            for field in rule.rename.keys().chain(rule.exclude.iter()) {
+                if fields.contains(&field.as_str()) {
+                    continue;
+                }
+                let mut message = format!(
+                    "{} has no field `{}` (mapped for table {})",
+                    definition.name, field, rule.table
+                );
+                if let Some(name) = did_you_mean(field, fields.iter().copied()) {
+                    message.push_str(&format!("; did you mean `{}`?", name));
+                }
+                diagnostics.push(format!("{}::{}", event, field), message);
+            }
+        }
+    }
+    diagnostics.into_result()
+}
+
+/// Parse a filter expression `<path> <op> <value>`, e.g. `$.market == "0xabc"` or `$.amount != 0`.
+pub fn parse_event_filter(expr: &str) -> Result<(String, FilterOp, YamlValue)> {
+    // The first operator in the expression wins, so operators inside quoted values are ignored
+    let (index, token, op) = FilterOp::ALL
+        .iter()
+        .filter_map(|(token, op)| expr.find(token).map(|index| (index, *token, *op)))
+        .min_by_key(|(index, _, _)| *index)
+        .ok_or_else(|| anyhow!("invalid filter `{}`, expected `$.field <op> value`", expr))?;
+    let path = expr[..index].trim();
+    let value = expr[index + token.len()..].trim();
+    if !path.starts_with("$.") || path.len() == 2 {
+        bail!("invalid filter `{}`, path must start with `$.`", expr);
+    }
+    let value =
+        parse_literal(value).with_context(|| format!("invalid value in filter `{}`", expr))?;
+    Ok((path.to_string(), op, value))
+}
+
+/// Parse a computed column expression: `concat(a, b, ...)`, `hash(a, b, ...)` or `a <op> b`
+/// with `+ - * /`, where each operand is a `$.path` or a literal.
+pub fn parse_computed_expr(expr: &str) -> Result<(ComputeFunction, Vec<ComputeArg>)> {
+    let expr = expr.trim();
+    for (name, function) in [
+        ("concat", ComputeFunction::Concat),
+        ("hash", ComputeFunction::Hash),
+    ] {
+        let Some(inner) = expr
+            .strip_prefix(name)
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+        else {
+            continue;
+        };
+        let args = split_args(inner)
+            .into_iter()
+            .map(parse_compute_arg)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("invalid expression `{}`", expr))?;
+        if args.is_empty() {
+            bail!("invalid expression `{}`, {} needs arguments", expr, name);
+        }
+        return Ok((function, args));
+    }
+    for (token, function) in [
+        (" + ", ComputeFunction::Add),
+        (" - ", ComputeFunction::Sub),
+        (" * ", ComputeFunction::Mul),
+        (" / ", ComputeFunction::Div),
+    ] {
+        if let Some((lhs, rhs)) = expr.split_once(token) {
+            let args = vec![parse_compute_arg(lhs)?, parse_compute_arg(rhs)?];
+            return Ok((function, args));
+        }
+    }
+    bail!(
+        "invalid expression `{}`, expected concat(..), hash(..) or `a <+|-|*|/> b`",
+        expr
+    )
+}
+
+/// Largest `scale(..)`: a `u256` has 78 digits
+const MAX_SCALE_DECIMALS: u32 = 78;
+
+/// Parse a column transform: `hex_decode`, `lowercase` or `scale(<decimals>)`
+pub fn parse_transform(transform: &str) -> Result<Transform> {
+    let transform = transform.trim();
+    match transform {
+        "hex_decode" => return Ok(Transform::HexDecode),
+        "lowercase" => return Ok(Transform::Lowercase),
+        _ => {}
+    }
+    let decimals = transform
+        .strip_prefix("scale")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| {
+            anyhow!(
+                "invalid transform `{}`, expected hex_decode, lowercase or scale(<decimals>)",
+                transform
+            )
+        })?;
+    let decimals = decimals.trim().parse::<u32>().map_err(|_| {
+        anyhow!(
+            "invalid transform `{}`, decimals must be a number",
+            transform
+        )
+    })?;
+    if decimals > MAX_SCALE_DECIMALS {
+        bail!(
+            "invalid transform `{}`, at most {} decimals",
+            transform,
+            MAX_SCALE_DECIMALS
+        );
+    }
+    Ok(Transform::Scale(decimals))
+}
+
+fn parse_compute_arg(arg: &str) -> Result<ComputeArg> {
+    let arg = arg.trim();
+    if arg.starts_with("$.") {
+        Ok(ComputeArg::Path(arg.to_string()))
+    } else {
+        parse_literal(arg).map(ComputeArg::Literal)
+    }
+}
+
+/// Split on commas that are not inside quotes
+fn split_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, ',') => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !s[start..].trim().is_empty() || !args.is_empty() {
+        args.push(s[start..].trim());
+    }
+    args
+}
+
+/// Quoted values and addresses are strings; other values are read as YAML scalars
+fn parse_literal(value: &str) -> Result<YamlValue> {
+    if value.is_empty() {
+        bail!("missing value");
+    }
+    let quoted = ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)));
+    Ok(match quoted {
+        Some(v) => YamlValue::String(v.to_string()),
+        // addresses stay strings rather than being read as hex integers
+        None if value.starts_with("0x") => YamlValue::String(value.to_string()),
+        None => serde_yaml::from_str(value)?,
+    })
+}
+
+/// Keep only the rules that write to `tables`, dropping events left without any
+pub fn restrict_mappings_to_tables(
+    mappings: &EventTableMappings,
+    tables: &BTreeMap<String, TableSchema>,
+) -> EventTableMappings {
+    restrict_rules_to_tables(mappings, tables, |rule| &rule.table)
+}
+
+/// Keep only the entry function rules that write to `tables`, dropping functions left without any
+pub fn restrict_entry_functions_to_tables(
+    mappings: &EntryFunctionMappings,
+    tables: &BTreeMap<String, TableSchema>,
+) -> EntryFunctionMappings {
+    restrict_rules_to_tables(mappings, tables, |rule| &rule.table)
+}
+
+/// Keep only the table item rules that write to `tables`, dropping types left without any
+pub fn restrict_table_items_to_tables(
+    mappings: &TableItemMappings,
+    tables: &BTreeMap<String, TableSchema>,
+) -> TableItemMappings {
+    restrict_rules_to_tables(mappings, tables, |rule| &rule.table)
+}
+
+fn restrict_rules_to_tables<R: Clone>(
+    mappings: &BTreeMap<String, Vec<R>>,
+    tables: &BTreeMap<String, TableSchema>,
+    table_of: impl Fn(&R) -> &String,
+) -> BTreeMap<String, Vec<R>> {
+    mappings
+        .iter()
+        .filter_map(|(name, rules)| {
+            let rules = rules
+                .iter()
+                .filter(|rule| tables.contains_key(table_of(rule)))
+                .cloned()
+                .collect::<Vec<_>>();
+            (!rules.is_empty()).then(|| (name.clone(), rules))
+        })
+        .collect()
+}
+
+pub fn ensure_events_exist_from_mapping(custom: &mut CustomConfig, mapping: &EventTableMappings) {
+    for (event, _rules) in mapping.iter() {
+        custom.events.entry(event.clone()).or_insert(EventMapping {
+            constant_values: Vec::new(),
+            event_fields: BTreeMap::new(),
+            event_metadata: BTreeMap::new(),
+            filters: Vec::new(),
+        });
+    }
+}
@@ -0,0 +1,17 @@
+//! Chain-independent core of yeaptor: yeaptor.toml parsing, resource account address derivation,
+//! event definitions, DB schema and event mapping loading, and processor config generation. It
+//! does not depend on the aptos CLI or framework crates.
+
+pub mod address;
+pub mod config;
+pub mod db_schema;
+pub mod diagnostics;
+pub mod event_definition;
+pub mod event_fixtures;
+pub mod event_table_mapping;
+pub mod lockfile;
+pub mod move_manifest;
+pub mod processor_config;
+pub mod processor_config_generator;
+pub mod standard_templates;
+pub mod template_variables;
@@ -0,0 +1,116 @@
+use crate::diagnostics::Diagnostics;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the yeaptor.lock layout written by this yeaptor
+pub const LOCKFILE_VERSION: u64 = 1;
+
+/// yeaptor.lock: content hashes of every dependency package (framework and git dependencies
+/// included) of the last accepted build, so a dependency changing under the same revision is
+/// caught by the next build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    pub version: u64,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, DependencyHashes>,
+}
+
+/// Hashes of one dependency package as built
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DependencyHashes {
+    /// SHA3-256 of its compiled modules, hex
+    pub bytecode_sha3_256: String,
+    /// SHA3-256 of its Move sources, hex
+    pub source_sha3_256: String,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            dependencies: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Compare the `built` dependencies with the pinned ones: a dependency whose hashes differ is
+    /// a problem, located at its name. Dependencies not pinned yet, or not in this build, are not.
+    pub fn check(&self, built: &BTreeMap<String, DependencyHashes>) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        for (name, hashes) in built {
+            let Some(pinned) = self.dependencies.get(name) else {
+                continue;
+            };
+            let mut changed = Vec::new();
+            if pinned.bytecode_sha3_256 != hashes.bytecode_sha3_256 {
+                changed.push("bytecode");
+            }
+            if pinned.source_sha3_256 != hashes.source_sha3_256 {
+                changed.push("sources");
+            }
+            if !changed.is_empty() {
+                diagnostics.push(
+                    format!("dependencies.{}", name),
+                    format!(
+                        "{} changed since yeaptor.lock was written (bytecode {} now, {} pinned); \
+                         if the new content is expected, rebuild with --update-lock",
+                        changed.join(" and "),
+                        short_hash(&hashes.bytecode_sha3_256),
+                        short_hash(&pinned.bytecode_sha3_256)
+                    ),
+                );
+            }
+        }
+        diagnostics
+    }
+
+    /// Pin the `built` dependencies, replacing the hashes of those already pinned
+    pub fn update(&mut self, built: &BTreeMap<String, DependencyHashes>) {
+        self.dependencies.extend(
+            built
+                .iter()
+                .map(|(name, hashes)| (name.clone(), hashes.clone())),
+        );
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(format!(
+            "# Generated by yeaptor deployment build; do not edit.\n{}",
+            toml::to_string(self)?
+        ))
+    }
+}
+
+/// yeaptor.lock next to the yeaptor.toml at `config_path`
+pub fn lockfile_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("yeaptor.lock")
+}
+
+/// The lockfile at `path`, `None` when there is none yet
+pub fn load_lockfile(path: &Path) -> Result<Option<Lockfile>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let s = fs::read_to_string(path)?;
+    let lockfile: Lockfile =
+        toml::from_str(&s).with_context(|| format!("invalid lockfile {}", path.display()))?;
+    if lockfile.version != LOCKFILE_VERSION {
+        anyhow::bail!(
+            "{} has version {}, but this yeaptor reads version {}",
+            path.display(),
+            lockfile.version,
+            LOCKFILE_VERSION
+        );
+    }
+    Ok(Some(lockfile))
+}
+
+fn short_hash(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
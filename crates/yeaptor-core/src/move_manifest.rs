@@ -0,0 +1,232 @@
+use anyhow::{Context, Result};
+use move_core_types::account_address::AccountAddress;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `[addresses]` and `[dev-addresses]` of a package's Move.toml; values are `_` (assigned at
+/// build time) or an address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ManifestAddresses {
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+    #[serde(default, rename = "dev-addresses")]
+    pub dev_addresses: BTreeMap<String, String>,
+}
+
+/// A named address the manifest pins to another address than the one yeaptor resolves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressConflict {
+    /// `addresses` or `dev-addresses`
+    pub section: &'static str,
+    pub name: String,
+    /// Value in the manifest, as written
+    pub pinned: String,
+    pub resolved: AccountAddress,
+}
+
+impl fmt::Display for AddressConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] pins {} = \"{}\", but yeaptor resolves it to {}",
+            self.section,
+            self.name,
+            self.pinned,
+            self.resolved.to_standard_string()
+        )
+    }
+}
+
+pub fn manifest_path(package_dir: &Path) -> PathBuf {
+    package_dir.join("Move.toml")
+}
+
+pub fn load_manifest_addresses(package_dir: &Path) -> Result<ManifestAddresses> {
+    let path = manifest_path(package_dir);
+    let s = fs::read_to_string(&path)?;
+    toml::from_str(&s).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ManifestDependencies {
+    #[serde(default)]
+    package: Option<toml::Value>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, toml::Value>,
+}
+
+fn load_manifest_dependencies(package_dir: &Path) -> Result<ManifestDependencies> {
+    let path = manifest_path(package_dir);
+    let s = fs::read_to_string(&path)?;
+    toml::from_str(&s).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// `name` of a package's `[package]`, the name it is published under
+pub fn load_package_name(package_dir: &Path) -> Result<Option<String>> {
+    let manifest = load_manifest_dependencies(package_dir)?;
+    Ok(manifest
+        .package
+        .as_ref()
+        .and_then(|package| package.get("name")?.as_str().map(str::to_string)))
+}
+
+/// `version` of a package's `[package]`, if any
+pub fn load_package_version(package_dir: &Path) -> Result<Option<String>> {
+    let manifest = load_manifest_dependencies(package_dir)?;
+    Ok(manifest
+        .package
+        .as_ref()
+        .and_then(|package| package.get("version")?.as_str().map(str::to_string)))
+}
+
+/// A `git = "..."` entry of a package's `[dependencies]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitDependency {
+    pub git: String,
+    pub rev: Option<String>,
+    pub subdir: Option<String>,
+}
+
+/// Directories of the `local = "..."` entries of a package's `[dependencies]`, by dependency
+/// name, relative to the current directory like `package_dir`
+pub fn load_local_dependencies(package_dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let manifest = load_manifest_dependencies(package_dir)?;
+    Ok(manifest
+        .dependencies
+        .into_iter()
+        .filter_map(|(name, dependency)| {
+            let local = dependency.get("local")?.as_str()?;
+            Some((name, package_dir.join(local)))
+        })
+        .collect())
+}
+
+/// The `git = "..."` entries of a package's `[dependencies]`, by dependency name
+pub fn load_git_dependencies(package_dir: &Path) -> Result<BTreeMap<String, GitDependency>> {
+    let manifest = load_manifest_dependencies(package_dir)?;
+    Ok(manifest
+        .dependencies
+        .into_iter()
+        .filter_map(|(name, dependency)| {
+            let field = |key: &str| dependency.get(key)?.as_str().map(str::to_string);
+            let git = GitDependency {
+                git: field("git")?,
+                rev: field("rev"),
+                subdir: field("subdir"),
+            };
+            Some((name, git))
+        })
+        .collect())
+}
+
+/// Directories of the packages reached from `package_dir` through `local` dependencies only, by
+/// package name
+pub fn local_dependency_dirs(package_dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut dirs = BTreeMap::new();
+    let mut pending = vec![package_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for (name, dependency_dir) in load_local_dependencies(&dir)? {
+            if !dirs.contains_key(&name) {
+                dirs.insert(name, dependency_dir.clone());
+                pending.push(dependency_dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Cycles of the dependency graph over packages `0..edges.len()`, where `edges[i]` are the
+/// packages `i` depends on: one per group of mutually dependent packages, as the shortest path
+/// from its lowest package back to it (`[a, b]` for a -> b -> a)
+pub fn dependency_cycles(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let reachable = (0..edges.len())
+        .map(|start| reachable_from(edges, start))
+        .collect::<Vec<_>>();
+    let mut in_cycle = BTreeSet::new();
+    let mut cycles = Vec::new();
+    for start in 0..edges.len() {
+        if in_cycle.contains(&start) || !reachable[start].contains(&start) {
+            continue;
+        }
+        let group = (0..edges.len())
+            .filter(|&i| reachable[start].contains(&i) && reachable[i].contains(&start))
+            .collect::<BTreeSet<_>>();
+        in_cycle.extend(group.iter().copied());
+        cycles.push(shortest_cycle(edges, start, &group));
+    }
+    cycles
+}
+
+/// Packages reachable from `start` through at least one edge
+fn reachable_from(edges: &[Vec<usize>], start: usize) -> BTreeSet<usize> {
+    let mut seen = BTreeSet::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(i) = queue.pop_front() {
+        for &next in edges.get(i).into_iter().flatten() {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+fn shortest_cycle(edges: &[Vec<usize>], start: usize, group: &BTreeSet<usize>) -> Vec<usize> {
+    let mut previous: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(i) = queue.pop_front() {
+        for &next in &edges[i] {
+            if next == start {
+                let mut cycle = vec![i];
+                while let Some(&before) = previous.get(cycle.last().unwrap_or(&start)) {
+                    cycle.push(before);
+                }
+                cycle.reverse();
+                return cycle;
+            }
+            if group.contains(&next) && !previous.contains_key(&next) {
+                previous.insert(next, i);
+                queue.push_back(next);
+            }
+        }
+    }
+    vec![start]
+}
+
+impl ManifestAddresses {
+    /// Whether `name` is a named address of the package, in either section
+    pub fn declares(&self, name: &str) -> bool {
+        self.addresses.contains_key(name) || self.dev_addresses.contains_key(name)
+    }
+
+    /// Names pinned to a concrete address other than their entry in `named_addresses`;
+    /// `[dev-addresses]` only count for dev builds
+    pub fn conflicts(
+        &self,
+        named_addresses: &BTreeMap<String, AccountAddress>,
+        dev: bool,
+    ) -> Vec<AddressConflict> {
+        let mut sections = vec![("addresses", &self.addresses)];
+        if dev {
+            sections.push(("dev-addresses", &self.dev_addresses));
+        }
+        sections
+            .into_iter()
+            .flat_map(|(section, pinned)| {
+                pinned.iter().filter_map(move |(name, value)| {
+                    let resolved = *named_addresses.get(name)?;
+                    let address = AccountAddress::from_hex_literal(value.trim()).ok()?;
+                    (address != resolved).then(|| AddressConflict {
+                        section,
+                        name: name.clone(),
+                        pinned: value.clone(),
+                        resolved,
+                    })
+                })
+            })
+            .collect()
+    }
+}
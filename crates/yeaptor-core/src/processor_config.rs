@@ -0,0 +1,385 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessorConfig {
+    pub spec_identifier: SpecIdentifier,
+    pub common_config: CommonConfig,
+    pub custom_config: CustomConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpecIdentifier {
+    pub spec_creator: String,
+    pub spec_name: String,
+    pub spec_version: String,
+}
+
+impl Default for SpecIdentifier {
+    fn default() -> Self {
+        Self {
+            spec_creator: "shepherd@aptoslabs.com".to_string(),
+            spec_name: "remapping-processor".to_string(),
+            spec_version: "0.0.10".to_string(),
+        }
+    }
+}
+
+/// Increment the last numeric component of a dotted version (`0.0.10` -> `0.0.11`).
+/// Returns `None` if the last component is not a number.
+pub fn bump_spec_version(version: &str) -> Option<String> {
+    let (head, last) = match version.rsplit_once('.') {
+        Some((head, last)) => (Some(head), last),
+        None => (None, version),
+    };
+    let next = last.parse::<u64>().ok()?.checked_add(1)?;
+    Some(match head {
+        Some(head) => format!("{}.{}", head, next),
+        None => next.to_string(),
+    })
+}
+
+/// Suffix of backfill configs: file `processor_config.backfill.yaml`, spec name `<name>-backfill`
+pub const BACKFILL_SUFFIX: &str = "backfill";
+
+/// Copy of a live config bounded to `from..=to`, under a distinct spec name so both can run side by side
+pub fn backfill_variant(cfg: &ProcessorConfig, from: u64, to: u64) -> ProcessorConfig {
+    let mut backfill = cfg.clone();
+    backfill.spec_identifier.spec_name =
+        format!("{}-{}", cfg.spec_identifier.spec_name, BACKFILL_SUFFIX);
+    backfill.common_config.starting_version = from;
+    backfill.common_config.starting_version_override = None;
+    backfill.common_config.ending_version = Some(to);
+    backfill
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommonConfig {
+    pub network: String,
+    pub starting_version: u64,
+    pub starting_version_override: Option<u64>,
+    /// Last version to process (inclusive); unset for an open-ended processor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ending_version: Option<u64>,
+    /// Accounts whose activity is indexed: transactions neither sent by one of them nor emitting
+    /// an event under one (event account or module address) are skipped before their events are
+    /// decoded. Empty to index every transaction
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub account_filter: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomConfig {
+    #[serde(default)]
+    pub db_schema: BTreeMap<String, TableSchema>,
+    #[serde(default)]
+    pub events: BTreeMap<String, EventMapping>,
+    #[serde(default)]
+    pub transaction_metadata: BTreeMap<String, Vec<ColumnTarget>>,
+    #[serde(default)]
+    pub payload: BTreeMap<String, YamlValue>,
+    #[serde(default)]
+    pub event_metadata: BTreeMap<String, Vec<ColumnTarget>>,
+}
+
+// A table schema is a mapping from column name to its specification.
+pub type TableSchema = BTreeMap<String, ColumnSpec>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnSpec {
+    pub column_type: ColumnTypeSpec,
+    #[serde(default)]
+    pub default_value: Option<YamlValue>,
+    pub is_index: bool,
+    pub is_nullable: bool,
+    pub is_option: bool,
+    pub is_primary_key: bool,
+    pub is_vec: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnTypeSpec {
+    pub column_type: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventMapping {
+    #[serde(default)]
+    pub constant_values: Vec<ConstantValue>,
+    #[serde(default)]
+    pub event_fields: BTreeMap<String, Vec<ColumnTarget>>,
+    #[serde(default)]
+    pub event_metadata: BTreeMap<String, Vec<ColumnTarget>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<EventFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnTarget {
+    pub column: String,
+    pub table: String,
+}
+
+// A condition on an event field; rows for `table` are only written when it holds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventFilter {
+    pub table: String,
+    pub path: String,
+    pub op: FilterOp,
+    pub value: YamlValue,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl FilterOp {
+    // Longer operators first so `>=` is not read as `>`
+    pub const ALL: [(&'static str, FilterOp); 6] = [
+        ("==", FilterOp::Eq),
+        ("!=", FilterOp::Ne),
+        (">=", FilterOp::Ge),
+        ("<=", FilterOp::Le),
+        (">", FilterOp::Gt),
+        ("<", FilterOp::Lt),
+    ];
+}
+
+// A column derived from event fields, emitted under `payload.computed_columns`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComputedColumn {
+    pub event: String,
+    pub table: String,
+    pub column: String,
+    pub function: ComputeFunction,
+    pub args: Vec<ComputeArg>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeFunction {
+    /// String concatenation of all arguments
+    Concat,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// Hex-encoded sha3-256 of the arguments, each prefixed with its length as a little-endian
+    /// u64, e.g. for synthetic primary keys
+    Hash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeArg {
+    /// JSON path into the event, `$.field`
+    Path(String),
+    Literal(YamlValue),
+}
+
+// A rewrite of the value an event field writes to a column, emitted under
+// `payload.column_transforms`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnTransform {
+    pub event: String,
+    pub table: String,
+    pub column: String,
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// `vector<u8>` hex (`0x6869`) decoded as UTF-8 text (`hi`)
+    HexDecode,
+    /// Hex letters of an address, object or string in lower case
+    Lowercase,
+    /// Integer divided by 10^decimals, written as a decimal (`150000000` -> `1.5` for 8)
+    Scale(u32),
+}
+
+// Rows written for every call of an entry function, emitted under `payload.entry_functions`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntryFunctionMapping {
+    /// `<address>::<module>::<function>`
+    pub function: String,
+    /// JSON path into the call (`$.sender`, `$.function`, `$.type_arguments.0`, `$.arguments.1`)
+    /// -> columns it is written to
+    #[serde(default)]
+    pub payload_fields: BTreeMap<String, Vec<ColumnTarget>>,
+    #[serde(default)]
+    pub constant_values: Vec<ConstantValue>,
+}
+
+/// How writes of Move table items are mapped, emitted under `payload.table_items`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableItemMapping {
+    /// Normalized Move type of the values, e.g. `0x42::market::Position`
+    pub value_type: String,
+    /// Only items with this key type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    /// Only items of the table with this handle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    /// JSON path into the write (`$.handle`, `$.key`, `$.value.collateral`, `$.value_type`)
+    /// -> columns it is written to
+    #[serde(default)]
+    pub payload_fields: BTreeMap<String, Vec<ColumnTarget>>,
+    #[serde(default)]
+    pub constant_values: Vec<ConstantValue>,
+}
+
+/// How rows whose primary key already exists are written, emitted under `payload.conflict_strategies`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictStrategy {
+    /// Plain insert; a duplicate key fails the batch
+    Insert,
+    /// Overwrite the existing row
+    Upsert,
+    /// Keep the existing row, e.g. when replaying events
+    IgnoreDuplicates,
+}
+
+/// Access method of an index; `btree` is the database default
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexMethod {
+    Btree,
+    /// Equality lookups only; single column and never unique
+    Hash,
+    /// Block range summaries, compact for columns correlated with insertion order (versions,
+    /// timestamps)
+    Brin,
+}
+
+/// A composite, unique or non-btree index declared under a table's `indexes`, emitted under
+/// `payload.indexes`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexDefinition {
+    /// Defaults to `<table>_<columns>_idx`, or `_key` for unique indexes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+    #[serde(default, skip_serializing_if = "crate::db_schema::is_false")]
+    pub unique: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<IndexMethod>,
+}
+
+impl IndexDefinition {
+    pub fn index_name(&self, table: &str) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!(
+                "{}_{}_{}",
+                table,
+                self.columns.join("_"),
+                if self.unique { "key" } else { "idx" }
+            ),
+        }
+    }
+}
+
+/// Range partitioning of a table, emitted under `payload.partitioning`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionDefinition {
+    /// A `timestamp` column for time-based partitions, or an integer column such as
+    /// `transaction_version` for version-based ones
+    pub column: String,
+    pub interval: PartitionInterval,
+}
+
+/// Width of each partition: a calendar period (`interval = "month"`) for time-based partitions,
+/// or a number of values (`interval = 10000000`) for version-based ones
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum PartitionInterval {
+    Period(PartitionPeriod),
+    Values(u64),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionPeriod {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+// A fixed value written to a column for every row an event produces.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConstantValue {
+    pub column: String,
+    pub table: String,
+    pub value: YamlValue,
+}
+
+// Helpers for YAML I/O
+pub fn load_processor_config_yaml(path: &Path) -> Result<ProcessorConfig> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("failed to read YAML config: {}", path.display()))?;
+    let cfg: ProcessorConfig = serde_yaml::from_str(&s)
+        .with_context(|| format!("failed to parse YAML config: {}", path.display()))?;
+    Ok(cfg)
+}
+
+/// Deep-merge a hand-written overrides document into a generated config. Mappings are merged
+/// key by key, any other value replaces the generated one, and `null` removes the key.
+pub fn apply_processor_overrides(
+    cfg: &ProcessorConfig,
+    overrides: &YamlValue,
+) -> Result<ProcessorConfig> {
+    let mut merged = serde_yaml::to_value(cfg).context("failed to serialize processor config")?;
+    merge_yaml(&mut merged, overrides);
+    serde_yaml::from_value(merged).context("overrides do not produce a valid processor config")
+}
+
+fn merge_yaml(base: &mut YamlValue, overrides: &YamlValue) {
+    match (base, overrides) {
+        (YamlValue::Mapping(base), YamlValue::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                if value.is_null() {
+                    base.remove(key);
+                } else if let Some(existing) = base.get_mut(key) {
+                    merge_yaml(existing, value);
+                } else {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+pub fn load_processor_overrides_yaml(path: &Path) -> Result<YamlValue> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("failed to read overrides: {}", path.display()))?;
+    serde_yaml::from_str(&s)
+        .with_context(|| format!("failed to parse YAML overrides: {}", path.display()))
+}
+
+pub fn save_processor_config_yaml(path: &Path, cfg: &ProcessorConfig) -> Result<()> {
+    let serialized = serde_yaml::to_string(cfg).context("failed to serialize YAML config")?;
+    fs::write(path, serialized)
+        .with_context(|| format!("failed to write YAML config: {}", path.display()))?;
+    Ok(())
+}
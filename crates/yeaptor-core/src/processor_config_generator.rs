@@ -0,0 +1,1127 @@
+use crate::db_schema::{
+    TableOptions, column_for_move_type, normalize_column_value, validate_table_indexes,
+    validate_table_partitioning,
+};
+use crate::diagnostics::did_you_mean;
+use crate::event_definition::EventDefinition;
+use crate::event_table_mapping::{
+    EntryFunctionMappings, EventTableMappings, TableItemMappings, TableMappingRule,
+    normalize_move_type, parse_computed_expr, parse_entry_function_path, parse_event_filter,
+    parse_table_item_path, parse_transform,
+};
+use crate::processor_config::{
+    ColumnSpec, ColumnTarget, ColumnTransform, ColumnTypeSpec, CommonConfig, ComputeArg,
+    ComputeFunction, ComputedColumn, ConflictStrategy, ConstantValue, CustomConfig,
+    EntryFunctionMapping, EventFilter, EventMapping, ProcessorConfig, SpecIdentifier,
+    TableItemMapping, TableSchema, Transform,
+};
+use anyhow::{Context, anyhow, bail};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+const EVENT_METADATA: &str = "event_metadata";
+const EVENT_METADATA_FIELDS: &[&str] = &[
+    "account_address",
+    "creation_number",
+    EVENT_INDEX,
+    EVENT_TYPE,
+    "sequence_number",
+];
+const EVENT_INDEX: &str = "event_index";
+const EVENT_TYPE: &str = "event_type";
+
+const NUMERIC_MOVE_TYPES: &[&str] = &["u8", "u16", "u32", "u64", "u128", "u256"];
+pub const COLUMN_TRANSFORMS: &str = "column_transforms";
+pub const COMPUTED_COLUMNS: &str = "computed_columns";
+pub const CONFLICT_STRATEGIES: &str = "conflict_strategies";
+pub const ENTRY_FUNCTIONS: &str = "entry_functions";
+pub const INDEXES: &str = "indexes";
+pub const PARTITIONING: &str = "partitioning";
+pub const TABLE_ITEMS: &str = "table_items";
+
+/// Column of the catch-all table holding the whole event as JSON
+pub const CATCH_ALL_PAYLOAD_COLUMN: &str = "payload";
+
+const TRANSACTION_METADATA: &str = "transaction_metadata";
+const TRANSACTION_METADATA_FIELDS: &[&str] = &["block_height", "epoch", "timestamp", "version"];
+pub fn load_event_definitions_from_dir(dir: &Path) -> anyhow::Result<Vec<EventDefinition>> {
+    let mut out: Vec<EventDefinition> = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(ext) = path.extension() {
+            if ext != "json" {
+                continue;
+            }
+        } else {
+            continue;
+        }
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read file: {}", path.display()))?;
+        let defs: Vec<EventDefinition> = serde_json::from_str(&data)
+            .with_context(|| format!("failed to parse JSON in {}", path.display()))?;
+        out.extend(defs);
+    }
+    Ok(out)
+}
+
+pub fn generate_processor_config(
+    spec_identifier: SpecIdentifier,
+    network: impl fmt::Display,
+    starting_version: u64,
+    event_definitions: &[EventDefinition],
+    // table schema
+    table_schemas: &BTreeMap<String, TableSchema>,
+    // event -> table mapping rules
+    event_mapping: &EventTableMappings,
+) -> anyhow::Result<(ProcessorConfig, Vec<String>, Vec<(String, String)>)> {
+    let mut mapped_table_columns = BTreeMap::new();
+    let mut unmapped_events = Vec::new();
+
+    // handle events
+    let mut mapped_events = BTreeMap::new();
+    let mut computed_columns = Vec::new();
+    let mut column_transforms = Vec::new();
+    for event_definition in event_definitions {
+        let event_name = format!(
+            "{}::{}::{}",
+            &event_definition.package_name, &event_definition.module_name, &event_definition.name
+        );
+
+        let Some(rules) = event_mapping.get(&event_name) else {
+            unmapped_events.push(event_name);
+            continue;
+        };
+        let materialized_event_name = format!(
+            "{}::{}::{}",
+            &event_definition.module_address, &event_definition.module_name, &event_definition.name
+        );
+
+        // Top-level fields plus dotted paths into struct-typed fields
+        let field_paths = event_definition
+            .fields
+            .keys()
+            .chain(event_definition.nested_fields.keys())
+            .collect::<Vec<_>>();
+        let mut event_fields: BTreeMap<String, Vec<ColumnTarget>> = BTreeMap::new();
+        let mut constant_values = Vec::new();
+        let mut filters = Vec::new();
+        for rule in rules {
+            let table_schema = table_schemas.get(&rule.table).ok_or(anyhow!(format!(
+                "Table schema for mapping {} -> {} not found",
+                &event_name, &rule.table
+            )))?;
+            for field_name in rule.rename.keys().chain(rule.exclude.iter()) {
+                if !field_paths.contains(&field_name) {
+                    bail!(
+                        "Field {} referenced by mapping {} -> {} not found in event definition",
+                        field_name,
+                        &event_name,
+                        &rule.table
+                    );
+                }
+            }
+            for (field_name, column) in &rule.rename {
+                if !table_schema.contains_key(column) {
+                    bail!(
+                        "Table Column for mapping {}::{} -> {}::{} not found",
+                        &event_name,
+                        field_name,
+                        &rule.table,
+                        column
+                    );
+                }
+            }
+            // Column -> what writes it, so each table column has a single source per event
+            let mut column_sources: BTreeMap<String, String> = BTreeMap::new();
+            // Column -> event field written to it
+            let mut field_columns: BTreeMap<String, &String> = BTreeMap::new();
+            for field_path in &field_paths {
+                if is_excluded(rule, field_path) {
+                    continue;
+                }
+                let column = mapped_column(rule, field_path);
+                if table_schema.contains_key(&column) {
+                    field_columns.insert(column.clone(), field_path);
+                    claim_column(
+                        &mut column_sources,
+                        &event_name,
+                        &rule.table,
+                        &column,
+                        format!("field {}", field_path),
+                    )?;
+                    mapped_table_columns
+                        .entry(rule.table.clone())
+                        .or_insert_with(Vec::new)
+                        .push(column.clone());
+                    event_fields
+                        .entry(format!("$.{}", field_path))
+                        .or_default()
+                        .push(ColumnTarget {
+                            column,
+                            table: rule.table.clone(),
+                        });
+                }
+            }
+            for (column, value) in &rule.constants {
+                let column_spec = table_schema.get(column).ok_or(anyhow!(format!(
+                    "Table Column for constant {} -> {}::{} not found",
+                    &event_name, &rule.table, column
+                )))?;
+                claim_column(
+                    &mut column_sources,
+                    &event_name,
+                    &rule.table,
+                    column,
+                    "constant".to_string(),
+                )?;
+                let value = normalize_column_value(column_spec, value).with_context(|| {
+                    format!(
+                        "invalid constant for {} -> {}::{}",
+                        &event_name, &rule.table, column
+                    )
+                })?;
+                mapped_table_columns
+                    .entry(rule.table.clone())
+                    .or_insert_with(Vec::new)
+                    .push(column.clone());
+                constant_values.push(ConstantValue {
+                    column: column.clone(),
+                    table: rule.table.clone(),
+                    value,
+                });
+            }
+            for filter in &rule.filters {
+                let (path, op, value) = parse_event_filter(filter)?;
+                if !field_paths.iter().any(|f| path[2..] == ***f) {
+                    bail!(
+                        "Field {} in filter `{}` for mapping {} -> {} not found in event definition",
+                        &path,
+                        filter,
+                        &event_name,
+                        &rule.table
+                    );
+                }
+                filters.push(EventFilter {
+                    table: rule.table.clone(),
+                    path,
+                    op,
+                    value,
+                });
+            }
+            for (column, expr) in &rule.computed {
+                if !table_schema.contains_key(column) {
+                    bail!(
+                        "Table Column for computed {} -> {}::{} not found",
+                        &event_name,
+                        &rule.table,
+                        column
+                    );
+                }
+                claim_column(
+                    &mut column_sources,
+                    &event_name,
+                    &rule.table,
+                    column,
+                    format!("computed `{}`", expr),
+                )?;
+                let (function, args) = parse_computed_expr(expr)?;
+                let arithmetic =
+                    !matches!(function, ComputeFunction::Concat | ComputeFunction::Hash);
+                for arg in &args {
+                    let ComputeArg::Path(path) = arg else {
+                        continue;
+                    };
+                    let field_type = event_definition
+                        .fields
+                        .get(&path[2..])
+                        .or_else(|| event_definition.nested_fields.get(&path[2..]))
+                        .ok_or(anyhow!(format!(
+                            "Field {} in computed column {} -> {}::{} not found in event definition",
+                            path, &event_name, &rule.table, column
+                        )))?;
+                    if arithmetic && !NUMERIC_MOVE_TYPES.contains(&field_type.as_str()) {
+                        bail!(
+                            "Field {} of type {} cannot be used in arithmetic for {} -> {}::{}",
+                            path,
+                            field_type,
+                            &event_name,
+                            &rule.table,
+                            column
+                        );
+                    }
+                }
+                mapped_table_columns
+                    .entry(rule.table.clone())
+                    .or_insert_with(Vec::new)
+                    .push(column.clone());
+                computed_columns.push(ComputedColumn {
+                    event: materialized_event_name.clone(),
+                    table: rule.table.clone(),
+                    column: column.clone(),
+                    function,
+                    args,
+                });
+            }
+            for (column, transform) in &rule.transforms {
+                let transform = parse_transform(transform).with_context(|| {
+                    format!(
+                        "invalid transform of {} -> {}::{}",
+                        &event_name, &rule.table, column
+                    )
+                })?;
+                let field_path = field_columns.get(column).ok_or(anyhow!(format!(
+                    "Column {}::{} transformed for {} is not written by an event field",
+                    &rule.table, column, &event_name
+                )))?;
+                let move_type = event_definition
+                    .fields
+                    .get(*field_path)
+                    .or_else(|| event_definition.nested_fields.get(*field_path))
+                    .unwrap();
+                if let Some(reason) =
+                    transform_mismatch(transform, move_type, &table_schema[column])
+                {
+                    bail!(
+                        "{}::{} ({}) -> {}::{}: {}",
+                        &event_name,
+                        field_path,
+                        move_type,
+                        &rule.table,
+                        column,
+                        reason
+                    );
+                }
+                column_transforms.push(ColumnTransform {
+                    event: materialized_event_name.clone(),
+                    table: rule.table.clone(),
+                    column: column.clone(),
+                    transform,
+                });
+            }
+        }
+        // A top-level field is covered when it, or any path below it, is mapped
+        for field_name in event_definition.fields.keys() {
+            let excluded = rules.iter().any(|rule| is_excluded(rule, field_name));
+            let key = format!("$.{}", field_name);
+            let nested_prefix = format!("{}.", key);
+            let mapped = event_fields
+                .keys()
+                .any(|k| *k == key || k.starts_with(&nested_prefix));
+            if !excluded && !mapped {
+                unmapped_events.push(format!("{}::{}", &event_name, field_name));
+            }
+        }
+
+        let mapped_tables = rules
+            .iter()
+            .map(|rule| rule.table.clone())
+            .collect::<BTreeSet<_>>();
+        let mut event_metadata = BTreeMap::new();
+        for key in [
+            "account_address",
+            "creation_number",
+            EVENT_INDEX,
+            EVENT_TYPE,
+            "sequence_number",
+        ] {
+            let targets = mapped_tables
+                .iter()
+                .filter_map(|mapped_table| {
+                    table_schemas
+                        .get(mapped_table)
+                        .unwrap()
+                        .iter()
+                        .find(|(_column_name, column_spec)| {
+                            column_spec.column_type.r#type == EVENT_METADATA
+                                && column_spec.column_type.column_type == key
+                        })
+                        .map(|item| ColumnTarget {
+                            table: mapped_table.to_string(),
+                            column: item.0.to_string(),
+                        })
+                })
+                .collect::<Vec<_>>();
+            targets.iter().for_each(|target| {
+                mapped_table_columns
+                    .entry(target.table.clone())
+                    .or_insert_with(Vec::new)
+                    .push(target.column.clone());
+            });
+            event_metadata.insert(key.to_string(), targets);
+        }
+
+        mapped_events.insert(
+            materialized_event_name,
+            EventMapping {
+                constant_values,
+                event_fields,
+                event_metadata,
+                filters,
+            },
+        );
+    }
+
+    // handle transaction metadata
+    let mut transaction_metadata = BTreeMap::new();
+    for key in TRANSACTION_METADATA_FIELDS {
+        let targets = table_schemas
+            .iter()
+            .filter_map(|(table_name, schema)| {
+                schema
+                    .iter()
+                    .find(|(_column_name, column_spec)| {
+                        &column_spec.column_type.r#type == TRANSACTION_METADATA
+                            && &column_spec.column_type.column_type == key
+                    })
+                    .map(|(column_name, _)| ColumnTarget {
+                        table: table_name.clone(),
+                        column: column_name.clone(),
+                    })
+            })
+            .collect::<Vec<_>>();
+        targets.iter().for_each(|target| {
+            mapped_table_columns
+                .entry(target.table.clone())
+                .or_insert_with(Vec::new)
+                .push(target.column.clone());
+        });
+        transaction_metadata.insert(key.to_string(), targets);
+    }
+
+    // handle event metadata
+    let mut event_metadata = BTreeMap::new();
+    for key in EVENT_METADATA_FIELDS {
+        let targets = table_schemas
+            .iter()
+            .filter_map(|(table_name, schema)| {
+                schema
+                    .iter()
+                    .find(|(_column_name, column_spec)| {
+                        &column_spec.column_type.r#type == EVENT_METADATA
+                            && &column_spec.column_type.column_type == key
+                    })
+                    .map(|(column_name, _)| ColumnTarget {
+                        table: table_name.clone(),
+                        column: column_name.clone(),
+                    })
+            })
+            .collect::<Vec<_>>();
+        targets.iter().for_each(|target| {
+            mapped_table_columns
+                .entry(target.table.clone())
+                .or_insert_with(Vec::new)
+                .push(target.column.clone());
+        });
+        event_metadata.insert(key.to_string(), targets);
+    }
+
+    // find_unmapped_table_columns(table_schemas, &mapped_table_columns)
+    //     .into_iter()
+    //     .for_each(|(table_name, column_name)| {
+    //         eprintln!("Warning: Column '{}' in table '{}' is not mapped by any event or transaction metadata.", column_name, table_name);
+    //     });
+
+    let mut payload = BTreeMap::new();
+    if !computed_columns.is_empty() {
+        payload.insert(
+            COMPUTED_COLUMNS.to_string(),
+            serde_yaml::to_value(&computed_columns)
+                .context("failed to serialize computed columns")?,
+        );
+    }
+    if !column_transforms.is_empty() {
+        payload.insert(
+            COLUMN_TRANSFORMS.to_string(),
+            serde_yaml::to_value(&column_transforms)
+                .context("failed to serialize column transforms")?,
+        );
+    }
+
+    let config = ProcessorConfig {
+        spec_identifier,
+        common_config: CommonConfig {
+            network: network.to_string(),
+            starting_version,
+            starting_version_override: None,
+            ending_version: None,
+            account_filter: Vec::new(),
+        },
+
+        custom_config: CustomConfig {
+            payload,
+            db_schema: table_schemas.clone(),
+            events: mapped_events,
+            transaction_metadata,
+            event_metadata,
+        },
+    };
+    Ok((
+        config,
+        unmapped_events,
+        find_unmapped_table_columns(table_schemas, &mapped_table_columns),
+    ))
+}
+
+/// Schema of the catch-all table: the whole event as JSON plus the metadata locating it
+pub fn catch_all_table_schema() -> TableSchema {
+    let column = |column_type: &str, kind: &str, is_primary_key: bool| ColumnSpec {
+        column_type: ColumnTypeSpec {
+            column_type: column_type.to_string(),
+            r#type: kind.to_string(),
+        },
+        default_value: None,
+        is_index: false,
+        is_nullable: !is_primary_key,
+        is_option: false,
+        is_primary_key,
+        is_vec: false,
+    };
+    TableSchema::from([
+        (
+            "transaction_version".to_string(),
+            column("version", TRANSACTION_METADATA, true),
+        ),
+        (
+            EVENT_INDEX.to_string(),
+            column(EVENT_INDEX, EVENT_METADATA, true),
+        ),
+        (
+            EVENT_TYPE.to_string(),
+            column(EVENT_TYPE, EVENT_METADATA, false),
+        ),
+        (
+            "account_address".to_string(),
+            column("account_address", EVENT_METADATA, false),
+        ),
+        (
+            "timestamp".to_string(),
+            column("timestamp", TRANSACTION_METADATA, false),
+        ),
+        (
+            CATCH_ALL_PAYLOAD_COLUMN.to_string(),
+            column("string", "move_type", false),
+        ),
+    ])
+}
+
+/// Rules sending every event of the built packages (not of `--include-deps` dependencies) that
+/// has no mapping to `table`. Fields are excluded as the event is stored whole, see
+/// [`attach_catch_all_payloads`].
+pub fn catch_all_mappings(
+    event_definitions: &[EventDefinition],
+    event_mapping: &EventTableMappings,
+    table: &str,
+) -> EventTableMappings {
+    event_definitions
+        .iter()
+        .filter(|e| e.dependency_of.is_none())
+        .map(|e| {
+            (
+                format!("{}::{}::{}", e.package_name, e.module_name, e.name),
+                e,
+            )
+        })
+        .filter(|(event_name, _)| !event_mapping.contains_key(event_name))
+        .map(|(event_name, e)| {
+            let mut rule = TableMappingRule::new(table);
+            rule.exclude = e.fields.keys().cloned().collect();
+            (event_name, vec![rule])
+        })
+        .collect()
+}
+
+/// Write the whole event (`$`) to the payload column for every event mapped to the catch-all table
+pub fn attach_catch_all_payloads(
+    config: &mut ProcessorConfig,
+    event_definitions: &[EventDefinition],
+    event_mapping: &EventTableMappings,
+    table: &str,
+) {
+    for e in event_definitions {
+        let event_name = format!("{}::{}::{}", e.package_name, e.module_name, e.name);
+        let caught = event_mapping
+            .get(&event_name)
+            .is_some_and(|rules| rules.iter().any(|rule| rule.table == table));
+        if !caught {
+            continue;
+        }
+        let materialized_event_name =
+            format!("{}::{}::{}", e.module_address, e.module_name, e.name);
+        if let Some(mapping) = config
+            .custom_config
+            .events
+            .get_mut(&materialized_event_name)
+        {
+            mapping
+                .event_fields
+                .entry("$".to_string())
+                .or_default()
+                .push(ColumnTarget {
+                    column: CATCH_ALL_PAYLOAD_COLUMN.to_string(),
+                    table: table.to_string(),
+                });
+        }
+    }
+}
+
+/// Emit the entry function mappings under `payload.entry_functions`, after checking their tables,
+/// columns and constants against the schema. Returns the (table, column) pairs the calls write,
+/// which are not unmapped even when no event writes them.
+pub fn apply_entry_function_mappings(
+    config: &mut ProcessorConfig,
+    mappings: &EntryFunctionMappings,
+) -> anyhow::Result<BTreeSet<(String, String)>> {
+    let mut entry_functions = Vec::new();
+    let mut written = BTreeSet::new();
+    for (function, rules) in mappings {
+        let mut payload_fields: BTreeMap<String, Vec<ColumnTarget>> = BTreeMap::new();
+        let mut constant_values = Vec::new();
+        for rule in rules {
+            let (fields, constants) = payload_rule_columns(
+                config,
+                function,
+                &rule.table,
+                &rule.columns,
+                &rule.constants,
+                parse_entry_function_path,
+                &mut written,
+            )?;
+            for (path, targets) in fields {
+                payload_fields.entry(path).or_default().extend(targets);
+            }
+            constant_values.extend(constants);
+        }
+        entry_functions.push(EntryFunctionMapping {
+            function: function.clone(),
+            payload_fields,
+            constant_values,
+        });
+    }
+    if !entry_functions.is_empty() {
+        config.custom_config.payload.insert(
+            ENTRY_FUNCTIONS.to_string(),
+            serde_yaml::to_value(&entry_functions)
+                .context("failed to serialize entry function mappings")?,
+        );
+    }
+    Ok(written)
+}
+
+/// Emit `mappings` under `payload.table_items`, one mapping per rule, after checking their tables,
+/// columns and constants against the schema; returns the `(table, column)` pairs they write
+pub fn apply_table_item_mappings(
+    config: &mut ProcessorConfig,
+    mappings: &TableItemMappings,
+) -> anyhow::Result<BTreeSet<(String, String)>> {
+    let mut table_items = Vec::new();
+    let mut written = BTreeSet::new();
+    for (value_type, rules) in mappings {
+        for rule in rules {
+            let (payload_fields, constant_values) = payload_rule_columns(
+                config,
+                value_type,
+                &rule.table,
+                &rule.columns,
+                &rule.constants,
+                parse_table_item_path,
+                &mut written,
+            )?;
+            table_items.push(TableItemMapping {
+                value_type: normalize_move_type(value_type)?,
+                key_type: rule
+                    .key_type
+                    .as_deref()
+                    .map(normalize_move_type)
+                    .transpose()?,
+                handle: rule.handle.clone(),
+                payload_fields,
+                constant_values,
+            });
+        }
+    }
+    if !table_items.is_empty() {
+        config.custom_config.payload.insert(
+            TABLE_ITEMS.to_string(),
+            serde_yaml::to_value(&table_items)
+                .context("failed to serialize table item mappings")?,
+        );
+    }
+    Ok(written)
+}
+
+/// Columns and constants one payload rule (entry function or table item) writes into `table`,
+/// checked against its schema; `source` names the rule in errors
+fn payload_rule_columns(
+    config: &ProcessorConfig,
+    source: &str,
+    table: &str,
+    columns: &BTreeMap<String, String>,
+    constants: &BTreeMap<String, serde_yaml::Value>,
+    parse_path: fn(&str) -> anyhow::Result<()>,
+    written: &mut BTreeSet<(String, String)>,
+) -> anyhow::Result<(BTreeMap<String, Vec<ColumnTarget>>, Vec<ConstantValue>)> {
+    let table_schema = config.custom_config.db_schema.get(table).ok_or(anyhow!(
+        "Table schema for mapping {} -> {} not found",
+        source,
+        table
+    ))?;
+    let mut payload_fields: BTreeMap<String, Vec<ColumnTarget>> = BTreeMap::new();
+    let mut constant_values = Vec::new();
+    let mut column_sources: BTreeMap<String, String> = BTreeMap::new();
+    for (column, path) in columns {
+        if !table_schema.contains_key(column) {
+            bail!(
+                "Table Column for mapping {} -> {}::{} not found",
+                source,
+                table,
+                column
+            );
+        }
+        parse_path(path)
+            .with_context(|| format!("invalid column {} for {} -> {}", column, source, table))?;
+        claim_column(
+            &mut column_sources,
+            source,
+            table,
+            column,
+            format!("path {}", path),
+        )?;
+        payload_fields
+            .entry(path.clone())
+            .or_default()
+            .push(ColumnTarget {
+                column: column.clone(),
+                table: table.to_string(),
+            });
+        written.insert((table.to_string(), column.clone()));
+    }
+    for (column, value) in constants {
+        let column_spec = table_schema.get(column).ok_or(anyhow!(
+            "Table Column for constant {} -> {}::{} not found",
+            source,
+            table,
+            column
+        ))?;
+        claim_column(
+            &mut column_sources,
+            source,
+            table,
+            column,
+            "constant".to_string(),
+        )?;
+        let value = normalize_column_value(column_spec, value)
+            .with_context(|| format!("invalid constant for {} -> {}::{}", source, table, column))?;
+        constant_values.push(ConstantValue {
+            column: column.clone(),
+            table: table.to_string(),
+            value,
+        });
+        written.insert((table.to_string(), column.clone()));
+    }
+    Ok((payload_fields, constant_values))
+}
+
+/// Emit the `on_conflict` setting of each table in the config under `payload.conflict_strategies`.
+/// Upserts and duplicate-ignoring need a primary key to detect the conflict.
+pub fn apply_conflict_strategies(
+    config: &mut ProcessorConfig,
+    table_options: &BTreeMap<String, TableOptions>,
+) -> anyhow::Result<()> {
+    let mut strategies = BTreeMap::new();
+    for (table, schema) in &config.custom_config.db_schema {
+        let Some(strategy) = table_options.get(table).and_then(|o| o.on_conflict) else {
+            continue;
+        };
+        if strategy != ConflictStrategy::Insert && !schema.values().any(|c| c.is_primary_key) {
+            bail!(
+                "Table {} uses on_conflict {:?} but has no primary key",
+                table,
+                strategy
+            );
+        }
+        strategies.insert(table.clone(), strategy);
+    }
+    if !strategies.is_empty() {
+        config.custom_config.payload.insert(
+            CONFLICT_STRATEGIES.to_string(),
+            serde_yaml::to_value(&strategies).context("failed to serialize conflict strategies")?,
+        );
+    }
+    Ok(())
+}
+
+/// Emit the declared `indexes` of each table in the config under `payload.indexes`, after checking
+/// them against the table's columns.
+pub fn apply_table_indexes(
+    config: &mut ProcessorConfig,
+    table_options: &BTreeMap<String, TableOptions>,
+) -> anyhow::Result<()> {
+    let mut indexes = BTreeMap::new();
+    for (table, schema) in &config.custom_config.db_schema {
+        let Some(options) = table_options.get(table) else {
+            continue;
+        };
+        if options.indexes.is_empty() {
+            continue;
+        }
+        validate_table_indexes(table, schema, &options.indexes)?;
+        indexes.insert(table.clone(), options.indexes.clone());
+    }
+    if !indexes.is_empty() {
+        config.custom_config.payload.insert(
+            INDEXES.to_string(),
+            serde_yaml::to_value(&indexes).context("failed to serialize indexes")?,
+        );
+    }
+    Ok(())
+}
+
+/// Emit the `partition_by` setting of each table in the config under `payload.partitioning`, so
+/// the processor (or a partition manager) can create range partitions ahead of the rows it writes.
+pub fn apply_table_partitioning(
+    config: &mut ProcessorConfig,
+    table_options: &BTreeMap<String, TableOptions>,
+) -> anyhow::Result<()> {
+    let mut partitioning = BTreeMap::new();
+    for (table, schema) in &config.custom_config.db_schema {
+        let Some(options) = table_options.get(table) else {
+            continue;
+        };
+        let Some(partition_by) = &options.partition_by else {
+            continue;
+        };
+        validate_table_partitioning(table, schema, &options.indexes, partition_by)?;
+        partitioning.insert(table.clone(), partition_by.clone());
+    }
+    if !partitioning.is_empty() {
+        config.custom_config.payload.insert(
+            PARTITIONING.to_string(),
+            serde_yaml::to_value(&partitioning).context("failed to serialize partitioning")?,
+        );
+    }
+    Ok(())
+}
+
+/// Report every inconsistency between event definitions, table schemas and mappings, rather than
+/// stopping at the first one like [`generate_processor_config`]. Nothing is generated.
+pub fn check_processor_mapping(
+    event_definitions: &[EventDefinition],
+    table_schemas: &BTreeMap<String, TableSchema>,
+    event_mapping: &EventTableMappings,
+) -> Vec<String> {
+    let events = event_definitions
+        .iter()
+        .map(|e| {
+            (
+                format!("{}::{}::{}", e.package_name, e.module_name, e.name),
+                e,
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut issues = Vec::new();
+    for (event_name, rules) in event_mapping {
+        let Some(event_definition) = events.get(event_name) else {
+            let mut issue = format!(
+                "Mapping refers to event {} which has no definition",
+                event_name
+            );
+            if let Some(name) = did_you_mean(event_name, events.keys().map(String::as_str)) {
+                issue.push_str(&format!("; did you mean {}?", name));
+            }
+            issues.push(issue);
+            continue;
+        };
+        let field_types = event_definition
+            .fields
+            .iter()
+            .chain(event_definition.nested_fields.iter())
+            .map(|(path, move_type)| (path.as_str(), move_type.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        for rule in rules {
+            let Some(table_schema) = table_schemas.get(&rule.table) else {
+                issues.push(format!(
+                    "Table {} mapped from {} not found in schema",
+                    &rule.table, event_name
+                ));
+                continue;
+            };
+            for field_name in rule.rename.keys().chain(rule.exclude.iter()) {
+                if !field_types.contains_key(field_name.as_str()) {
+                    issues.push(format!(
+                        "Field {} referenced by mapping {} -> {} not found in event definition",
+                        field_name, event_name, &rule.table
+                    ));
+                }
+            }
+            for (field_path, move_type) in &field_types {
+                if is_excluded(rule, field_path) {
+                    continue;
+                }
+                let renamed = rule.rename.get(*field_path);
+                let column = mapped_column(rule, field_path);
+                match table_schema.get(&column) {
+                    Some(column_spec) => {
+                        // an invalid transform is reported below, with the other transforms
+                        let mismatch =
+                            match rule.transforms.get(&column).map(|t| parse_transform(t)) {
+                                Some(Ok(transform)) => {
+                                    transform_mismatch(transform, move_type, column_spec)
+                                }
+                                Some(Err(_)) => None,
+                                None => type_mismatch(move_type, column_spec),
+                            };
+                        if let Some(reason) = mismatch {
+                            issues.push(format!(
+                                "{}::{} ({}) -> {}::{}: {}",
+                                event_name, field_path, move_type, &rule.table, column, reason
+                            ));
+                        }
+                    }
+                    None if renamed.is_some() => issues.push(format!(
+                        "Table Column for mapping {}::{} -> {}::{} not found",
+                        event_name, field_path, &rule.table, column
+                    )),
+                    None => {}
+                }
+            }
+            for (column, value) in &rule.constants {
+                match table_schema.get(column) {
+                    Some(column_spec) => {
+                        if let Err(e) = normalize_column_value(column_spec, value) {
+                            issues.push(format!(
+                                "Invalid constant for {} -> {}::{}: {:#}",
+                                event_name, &rule.table, column, e
+                            ));
+                        }
+                    }
+                    None => issues.push(format!(
+                        "Table Column for constant {} -> {}::{} not found",
+                        event_name, &rule.table, column
+                    )),
+                }
+            }
+            for filter in &rule.filters {
+                match parse_event_filter(filter) {
+                    Ok((path, _, _)) if !field_types.contains_key(&path[2..]) => {
+                        issues.push(format!(
+                            "Field {} in filter `{}` for mapping {} -> {} not found in event definition",
+                            path, filter, event_name, &rule.table
+                        ))
+                    }
+                    Ok(_) => {}
+                    Err(e) => issues.push(format!(
+                        "Invalid filter for {} -> {}: {:#}",
+                        event_name, &rule.table, e
+                    )),
+                }
+            }
+            for (column, expr) in &rule.computed {
+                if !table_schema.contains_key(column) {
+                    issues.push(format!(
+                        "Table Column for computed {} -> {}::{} not found",
+                        event_name, &rule.table, column
+                    ));
+                }
+                let (function, args) = match parse_computed_expr(expr) {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        issues.push(format!(
+                            "Invalid computed column {} -> {}::{}: {:#}",
+                            event_name, &rule.table, column, e
+                        ));
+                        continue;
+                    }
+                };
+                let arithmetic =
+                    !matches!(function, ComputeFunction::Concat | ComputeFunction::Hash);
+                for arg in &args {
+                    let ComputeArg::Path(path) = arg else {
+                        continue;
+                    };
+                    match field_types.get(&path[2..]) {
+                        None => issues.push(format!(
+                            "Field {} in computed column {} -> {}::{} not found in event definition",
+                            path, event_name, &rule.table, column
+                        )),
+                        Some(field_type)
+                            if arithmetic && !NUMERIC_MOVE_TYPES.contains(field_type) =>
+                        {
+                            issues.push(format!(
+                                "Field {} of type {} cannot be used in arithmetic for {} -> {}::{}",
+                                path, field_type, event_name, &rule.table, column
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            for (column, transform) in &rule.transforms {
+                if let Err(e) = parse_transform(transform) {
+                    issues.push(format!(
+                        "Invalid transform of {} -> {}::{}: {:#}",
+                        event_name, &rule.table, column, e
+                    ));
+                }
+                if !table_schema.contains_key(column) {
+                    issues.push(format!(
+                        "Table Column for transform {} -> {}::{} not found",
+                        event_name, &rule.table, column
+                    ));
+                    continue;
+                }
+                let written = field_types
+                    .keys()
+                    .any(|path| !is_excluded(rule, path) && mapped_column(rule, path) == *column);
+                if !written {
+                    issues.push(format!(
+                        "Column {}::{} transformed for {} is not written by an event field",
+                        &rule.table, column, event_name
+                    ));
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Why a Move field cannot be written to a column, if it cannot. Integers may widen.
+fn type_mismatch(move_type: &str, column_spec: &ColumnSpec) -> Option<String> {
+    if column_spec.column_type.r#type != "move_type" {
+        return Some(format!(
+            "{} column cannot hold an event field",
+            column_spec.column_type.r#type
+        ));
+    }
+    let expected = column_for_move_type(move_type);
+    if expected.is_vec != column_spec.is_vec {
+        return Some(format!(
+            "is_vec is {} but the field is {}a vector",
+            column_spec.is_vec,
+            if expected.is_vec { "" } else { "not " }
+        ));
+    }
+    let from = expected.column_type.column_type.as_str();
+    let to = column_spec.column_type.column_type.as_str();
+    let rank = |t: &str| NUMERIC_MOVE_TYPES.iter().position(|n| *n == t);
+    match (rank(from), rank(to)) {
+        (Some(from_rank), Some(to_rank)) if from_rank <= to_rank => None,
+        _ if from == to => None,
+        _ => Some(format!("expected a {} column, found {}", from, to)),
+    }
+}
+
+/// Why a transform cannot rewrite a Move field into a column, if it cannot
+fn transform_mismatch(
+    transform: Transform,
+    move_type: &str,
+    column_spec: &ColumnSpec,
+) -> Option<String> {
+    if column_spec.column_type.r#type != "move_type" {
+        return Some(format!(
+            "{} column cannot hold an event field",
+            column_spec.column_type.r#type
+        ));
+    }
+    let field = column_for_move_type(move_type);
+    let from = field.column_type.column_type.as_str();
+    let to = column_spec.column_type.column_type.as_str();
+    let (fits_field, fits_column, expected) = match transform {
+        Transform::HexDecode => (field.is_vec && from == "u8", to == "string", "string"),
+        Transform::Lowercase => (
+            !field.is_vec && ["address", "object", "string"].contains(&from),
+            ["address", "object", "string"].contains(&to),
+            "address, object or string",
+        ),
+        Transform::Scale(_) => (
+            !field.is_vec && NUMERIC_MOVE_TYPES.contains(&from),
+            to == "decimal",
+            "decimal",
+        ),
+    };
+    let field_reason = match transform {
+        Transform::HexDecode => "hex_decode needs a vector<u8> field",
+        Transform::Lowercase => "lowercase needs an address, object or string field",
+        Transform::Scale(_) => "scale needs an integer field",
+    };
+    if !fits_field {
+        Some(field_reason.to_string())
+    } else if !fits_column || column_spec.is_vec {
+        Some(format!(
+            "expected a {} column, found {}{}",
+            expected,
+            to,
+            if column_spec.is_vec { " vector" } else { "" }
+        ))
+    } else {
+        None
+    }
+}
+
+/// Column a field is written to: its rename, or its path with `_` for nested paths
+/// (position.collateral -> position_collateral)
+fn mapped_column(rule: &TableMappingRule, field_path: &str) -> String {
+    rule.rename
+        .get(field_path)
+        .cloned()
+        .unwrap_or_else(|| field_path.replace('.', "_"))
+}
+
+/// Record `source` as the writer of `table::column`, failing if another source already writes it
+fn claim_column(
+    column_sources: &mut BTreeMap<String, String>,
+    event_name: &str,
+    table: &str,
+    column: &str,
+    source: String,
+) -> anyhow::Result<()> {
+    if let Some(existing) = column_sources.get(column) {
+        bail!(
+            "Column {}::{} is written by both {} and {} of {}; rename or exclude one of them",
+            table,
+            column,
+            existing,
+            source,
+            event_name
+        );
+    }
+    column_sources.insert(column.to_string(), source);
+    Ok(())
+}
+
+/// A path is excluded when it, or a struct field containing it, is listed in `exclude`
+fn is_excluded(rule: &TableMappingRule, field_path: &str) -> bool {
+    rule.exclude.iter().any(|excluded| {
+        field_path == excluded
+            || field_path
+                .strip_prefix(excluded.as_str())
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+fn find_unmapped_table_columns(
+    table_schemas: &BTreeMap<String, TableSchema>,
+    mapped_table_columns: &BTreeMap<String, Vec<String>>,
+) -> Vec<(String, String)> {
+    table_schemas
+        .iter()
+        .flat_map(|(table_name, schema)| {
+            schema.iter().filter_map(|(column_name, _)| {
+                if !mapped_table_columns
+                    .get(table_name)
+                    .map_or(false, |columns| columns.contains(column_name))
+                {
+                    Some((table_name.clone(), column_name.clone()))
+                } else {
+                    None
+                }
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,280 @@
+use crate::event_definition::{EventDefinition, EventVersion};
+use crate::event_table_mapping::{EventTableMappings, TableMappingRule};
+use crate::processor_config::{ColumnSpec, ColumnTypeSpec, TableSchema};
+use move_core_types::account_address::AccountAddress;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+
+/// Package name the framework events are mapped under (`AptosFramework::coin::DepositEvent`)
+pub const FRAMEWORK_PACKAGE: &str = "AptosFramework";
+/// Column telling the events written into one template table apart
+pub const ACTIVITY_TYPE_COLUMN: &str = "activity_type";
+
+/// `[processor.standard-templates]`: framework events every protocol indexes, written to
+/// ready-made tables without declaring their definitions, schema or mapping.
+///
+/// ```toml
+/// [processor.standard-templates]
+/// fungible-asset = true
+/// coin = true
+/// object = true
+/// ```
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct StandardTemplates {
+    /// `0x1::fungible_asset::{Deposit, Withdraw}` into `fungible_asset_activities`
+    #[serde(default)]
+    pub fungible_asset: bool,
+    /// `0x1::coin::{DepositEvent, WithdrawEvent}` into `coin_activities`
+    #[serde(default)]
+    pub coin: bool,
+    /// `0x1::object::{Transfer, TransferEvent}` into `object_transfers`
+    #[serde(default)]
+    pub object: bool,
+}
+
+struct Template {
+    table: &'static str,
+    /// Columns besides the key columns
+    columns: &'static [TemplateColumn],
+    events: &'static [TemplateEvent],
+}
+
+struct TemplateEvent {
+    module: &'static str,
+    name: &'static str,
+    version: EventVersion,
+    fields: &'static [(&'static str, &'static str)],
+    /// Written to `activity_type` when the table has that column
+    activity: Option<&'static str>,
+}
+
+/// (column, column_type, type, is_index)
+type TemplateColumn = (&'static str, &'static str, &'static str, bool);
+
+/// Locate every event; `transaction_version` and `event_index` form the primary key
+const KEY_COLUMNS: [TemplateColumn; 3] = [
+    (
+        "transaction_version",
+        "version",
+        "transaction_metadata",
+        false,
+    ),
+    ("event_index", "event_index", "event_metadata", false),
+    (
+        "transaction_timestamp",
+        "timestamp",
+        "transaction_metadata",
+        false,
+    ),
+];
+
+static FUNGIBLE_ASSET: Template = Template {
+    table: "fungible_asset_activities",
+    columns: &[
+        ("store", "address", "move_type", true),
+        ("amount", "u64", "move_type", false),
+        (ACTIVITY_TYPE_COLUMN, "string", "move_type", false),
+    ],
+    events: &[
+        TemplateEvent {
+            module: "fungible_asset",
+            name: "Deposit",
+            version: EventVersion::V2,
+            fields: &[("store", "address"), ("amount", "u64")],
+            activity: Some("deposit"),
+        },
+        TemplateEvent {
+            module: "fungible_asset",
+            name: "Withdraw",
+            version: EventVersion::V2,
+            fields: &[("store", "address"), ("amount", "u64")],
+            activity: Some("withdraw"),
+        },
+    ],
+};
+
+/// Legacy coin events are emitted from the owner's `CoinStore`, so the event key's account is
+/// the owner
+static COIN: Template = Template {
+    table: "coin_activities",
+    columns: &[
+        ("owner", "account_address", "event_metadata", true),
+        ("amount", "u64", "move_type", false),
+        (ACTIVITY_TYPE_COLUMN, "string", "move_type", false),
+    ],
+    events: &[
+        TemplateEvent {
+            module: "coin",
+            name: "DepositEvent",
+            version: EventVersion::V1,
+            fields: &[("amount", "u64")],
+            activity: Some("deposit"),
+        },
+        TemplateEvent {
+            module: "coin",
+            name: "WithdrawEvent",
+            version: EventVersion::V1,
+            fields: &[("amount", "u64")],
+            activity: Some("withdraw"),
+        },
+    ],
+};
+
+static OBJECT: Template = Template {
+    table: "object_transfers",
+    columns: &[
+        ("object", "address", "move_type", true),
+        ("from", "address", "move_type", false),
+        ("to", "address", "move_type", false),
+    ],
+    events: &[
+        TemplateEvent {
+            module: "object",
+            name: "Transfer",
+            version: EventVersion::V2,
+            fields: &[
+                ("object", "address"),
+                ("from", "address"),
+                ("to", "address"),
+            ],
+            activity: None,
+        },
+        TemplateEvent {
+            module: "object",
+            name: "TransferEvent",
+            version: EventVersion::V1,
+            fields: &[
+                ("object", "address"),
+                ("from", "address"),
+                ("to", "address"),
+            ],
+            activity: None,
+        },
+    ],
+};
+
+impl StandardTemplates {
+    pub fn is_empty(&self) -> bool {
+        !(self.fungible_asset || self.coin || self.object)
+    }
+
+    fn enabled(&self) -> Vec<&'static Template> {
+        [
+            (self.fungible_asset, &FUNGIBLE_ASSET),
+            (self.coin, &COIN),
+            (self.object, &OBJECT),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, template)| template)
+        .collect()
+    }
+
+    /// Schemas of the enabled templates' tables
+    pub fn table_schemas(&self) -> BTreeMap<String, TableSchema> {
+        self.enabled()
+            .into_iter()
+            .map(|template| (template.table.to_string(), template_table_schema(template)))
+            .collect()
+    }
+
+    /// Definitions of the enabled templates' framework events
+    pub fn event_definitions(&self) -> Vec<EventDefinition> {
+        self.enabled()
+            .into_iter()
+            .flat_map(|template| template.events)
+            .map(|event| EventDefinition {
+                package_name: FRAMEWORK_PACKAGE.to_string(),
+                module_address: AccountAddress::ONE,
+                module_name: event.module.to_string(),
+                name: event.name.to_string(),
+                fields: event
+                    .fields
+                    .iter()
+                    .map(|(field, move_type)| (field.to_string(), move_type.to_string()))
+                    .collect(),
+                version: event.version,
+                dependency_of: None,
+                nested_fields: BTreeMap::new(),
+            })
+            .collect()
+    }
+
+    /// Add the templates' tables; a table declared by the user keeps its own layout
+    pub fn extend_table_schemas(&self, db_schema: &mut BTreeMap<String, TableSchema>) {
+        for (table, schema) in self.table_schemas() {
+            db_schema.entry(table).or_insert(schema);
+        }
+    }
+
+    /// Add the templates' framework events not already among `definitions` (e.g. built with
+    /// `--include-deps`)
+    pub fn extend_event_definitions(&self, definitions: &mut Vec<EventDefinition>) {
+        for definition in self.event_definitions() {
+            let declared = definitions.iter().any(|d| {
+                d.module_address == definition.module_address
+                    && d.module_name == definition.module_name
+                    && d.name == definition.name
+            });
+            if !declared {
+                definitions.push(definition);
+            }
+        }
+    }
+
+    /// Add the templates' mappings for events the user's mapping does not cover
+    pub fn extend_event_mapping(&self, mapping: &mut EventTableMappings) {
+        for (event, rules) in self.event_mappings() {
+            mapping.entry(event).or_insert(rules);
+        }
+    }
+
+    /// Mapping of each framework event into its template table
+    pub fn event_mappings(&self) -> EventTableMappings {
+        self.enabled()
+            .into_iter()
+            .flat_map(|template| template.events.iter().map(move |event| (template, event)))
+            .map(|(template, event)| {
+                let mut rule = TableMappingRule::new(template.table);
+                if let Some(activity) = event.activity {
+                    rule.constants.insert(
+                        ACTIVITY_TYPE_COLUMN.to_string(),
+                        YamlValue::String(activity.to_string()),
+                    );
+                }
+                (
+                    format!("{}::{}::{}", FRAMEWORK_PACKAGE, event.module, event.name),
+                    vec![rule],
+                )
+            })
+            .collect()
+    }
+}
+
+fn template_table_schema(template: &Template) -> TableSchema {
+    KEY_COLUMNS
+        .iter()
+        .chain(template.columns)
+        .map(|(column, column_type, kind, is_index)| {
+            let is_primary_key = KEY_COLUMNS[..2].iter().any(|key| key.0 == *column);
+            (
+                column.to_string(),
+                ColumnSpec {
+                    column_type: ColumnTypeSpec {
+                        column_type: column_type.to_string(),
+                        r#type: kind.to_string(),
+                    },
+                    default_value: None,
+                    is_index: *is_index,
+                    is_nullable: false,
+                    is_option: false,
+                    is_primary_key,
+                    is_vec: false,
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,89 @@
+use crate::config::YeaptorConfig;
+use crate::diagnostics::did_you_mean;
+use anyhow::{Result, anyhow, bail};
+use move_core_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+
+/// Values of the `{{...}}` placeholders of schema and mapping inputs, so one set of files works
+/// across environments whose module addresses differ:
+///
+/// - `{{address:<package>}}`: account the package (`address_name` in yeaptor.toml) is published to
+/// - `{{network}}`: network the processor config is generated for
+/// - `{{starting_version}}`: first version it processes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateVariables {
+    pub addresses: BTreeMap<String, AccountAddress>,
+    pub network: Option<String>,
+    pub starting_version: Option<u64>,
+}
+
+impl TemplateVariables {
+    /// Addresses of the packages of `config`; deployments with an unknown publisher are left out
+    pub fn from_config(config: &YeaptorConfig) -> Self {
+        let mut addresses = BTreeMap::new();
+        for (deployment, account) in config.deployments.iter().zip(config.deployment_addresses()) {
+            let Some(account) = account else {
+                continue;
+            };
+            for package in &deployment.packages {
+                addresses.insert(package.address_name.clone(), account);
+            }
+        }
+        TemplateVariables {
+            addresses,
+            network: None,
+            starting_version: None,
+        }
+    }
+
+    /// `text` with every placeholder replaced; an unknown package or a variable without a value
+    /// fails, naming it
+    pub fn render(&self, text: &str) -> Result<String> {
+        let mut rendered = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| anyhow!("unterminated placeholder `{}`", &rest[start..]))?;
+            rendered.push_str(&rest[..start]);
+            let name = rest[start + 2..start + end].trim();
+            rendered.push_str(&self.value(name)?);
+            rest = &rest[start + end + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    fn value(&self, name: &str) -> Result<String> {
+        if let Some(package) = name.strip_prefix("address:") {
+            let package = package.trim();
+            let Some(address) = self.addresses.get(package) else {
+                let suggestion = did_you_mean(package, self.addresses.keys().map(String::as_str))
+                    .map(|name| format!("; did you mean `{}`?", name))
+                    .unwrap_or_default();
+                bail!(
+                    "unknown package `{}` in `{{{{{}}}}}`{}",
+                    package,
+                    name,
+                    suggestion
+                );
+            };
+            return Ok(address.to_hex_literal());
+        }
+        let value = match name {
+            "network" => self.network.clone(),
+            "starting_version" => self.starting_version.map(|v| v.to_string()),
+            _ => bail!(
+                "unknown placeholder `{{{{{}}}}}`, expected `{{{{address:<package>}}}}`, \
+                 `{{{{network}}}}` or `{{{{starting_version}}}}`",
+                name
+            ),
+        };
+        value.ok_or_else(|| {
+            anyhow!(
+                "`{{{{{}}}}}` has no value here; it is set by `processor generate`",
+                name
+            )
+        })
+    }
+}
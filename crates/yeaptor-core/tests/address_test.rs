@@ -0,0 +1,64 @@
+use move_core_types::account_address::AccountAddress;
+use yeaptor_core::address::{AddressPrefix, object_code_address, resource_account_address};
+
+#[test]
+fn test_resource_account_address() {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    assert_eq!(
+        resource_account_address(&publisher, b"v1"),
+        AccountAddress::from_hex_literal(
+            "0x04df5c039d011a05c1f7f579b24ca4d74ee5285222c7ec31b61ffe2add8a2dfd"
+        )
+        .unwrap()
+    );
+    let publisher = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    assert_eq!(
+        resource_account_address(&publisher, b"yeap").to_standard_string(),
+        "0xf2f1668f4d6eafe79ca1059d7e54a14dbbee04f5078c9120d39ecf5bc81c0cdf"
+    );
+}
+
+#[test]
+fn test_object_code_address() {
+    // the object of the publish sent with sequence number 0, derived from 0 + 1
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    assert_eq!(
+        object_code_address(&publisher, 0).to_standard_string(),
+        "0xb351067ac35ee711a437bab69e267de634efc5384e6cbde128be6aa5c195a8ef"
+    );
+    let publisher = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    assert_eq!(
+        object_code_address(&publisher, 7).to_standard_string(),
+        "0xebbbb93c52d52181d4d1afb6643bc8d2c8947c5fe1a98c6cedfb237b411326d8"
+    );
+}
+
+#[test]
+fn test_address_prefix() {
+    let address = AccountAddress::from_hex_literal(
+        "0xca18aea53653478447560257138c45c759e4abf9cf85509a724a3d565e1f5cf9",
+    )
+    .unwrap();
+    for prefix in ["0xca", "CA1", "ca18aea5"] {
+        assert!(prefix.parse::<AddressPrefix>().unwrap().matches(&address));
+    }
+    assert!(!"0xcb".parse::<AddressPrefix>().unwrap().matches(&address));
+    assert!(!"0xca2".parse::<AddressPrefix>().unwrap().matches(&address));
+    assert!(
+        "0000"
+            .parse::<AddressPrefix>()
+            .unwrap()
+            .matches(&AccountAddress::ONE)
+    );
+
+    let prefix: AddressPrefix = "0xCAfe".parse().unwrap();
+    assert_eq!(prefix.to_string(), "0xcafe");
+    assert_eq!(prefix.expected_attempts(), 65536);
+
+    assert_eq!(
+        "0xcafg".parse::<AddressPrefix>().unwrap_err().to_string(),
+        "invalid address prefix `0xcafg`; `g` is not a hex digit"
+    );
+    assert!("0x".parse::<AddressPrefix>().is_err());
+    assert!("0".repeat(65).parse::<AddressPrefix>().is_err());
+}
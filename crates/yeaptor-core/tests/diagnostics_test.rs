@@ -0,0 +1,28 @@
+use yeaptor_core::diagnostics::{Diagnostics, did_you_mean};
+
+#[test]
+fn test_did_you_mean() {
+    let candidates = ["named-addresses", "publishers", "deployments"];
+    assert_eq!(
+        did_you_mean("named_addresses", candidates),
+        Some("named-addresses")
+    );
+    assert_eq!(did_you_mean("deployment", candidates), Some("deployments"));
+    assert_eq!(did_you_mean("processor", candidates), None);
+}
+
+#[test]
+fn test_diagnostics_display() {
+    let mut diagnostics = Diagnostics::default();
+    assert!(diagnostics.clone().into_result().is_ok());
+    diagnostics.push("deployments[0]", "publisher `bob` is unknown");
+    assert_eq!(
+        diagnostics.to_string(),
+        "deployments[0]: publisher `bob` is unknown"
+    );
+    diagnostics.push("deployments[1]", "seed is empty");
+    assert_eq!(
+        diagnostics.into_result().unwrap_err().to_string(),
+        "2 problems:\n  - deployments[0]: publisher `bob` is unknown\n  - deployments[1]: seed is empty"
+    );
+}
@@ -0,0 +1,69 @@
+use move_core_types::account_address::AccountAddress;
+use serde_json::json;
+use std::collections::BTreeMap;
+use yeaptor_core::event_definition::{EventDefinition, EventVersion};
+use yeaptor_core::event_fixtures::{FIXTURE_ADDRESS, event_fixture, event_type};
+
+fn fields(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(name, move_type)| (name.to_string(), move_type.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_event_fixture() {
+    let definition = EventDefinition {
+        package_name: "lending".to_string(),
+        module_address: AccountAddress::from_hex_literal("0x42").unwrap(),
+        module_name: "market".to_string(),
+        name: "MarketCreatedEvent".to_string(),
+        fields: fields(&[
+            ("amount", "u64"),
+            ("decimals", "u8"),
+            ("enabled", "bool"),
+            ("owner", "address"),
+            ("name", "0x1::string::String"),
+            (
+                "symbol",
+                "0x0000000000000000000000000000000000000000000000000000000000000001::string::String",
+            ),
+            ("memo", "vector<u8>"),
+            ("holders", "vector<address>"),
+            ("cap", "0x1::option::Option<u128>"),
+            (
+                "metadata",
+                "0x1::object::Object<0x1::fungible_asset::Metadata>",
+            ),
+            ("config", "0x42::market::Config"),
+            ("fee", "0x1::fixed_point32::FixedPoint32"),
+        ]),
+        version: EventVersion::V2,
+        dependency_of: None,
+        nested_fields: fields(&[
+            ("config.ltv", "u64"),
+            ("config.rate", "0x42::market::Rate"),
+            ("config.rate.base", "u128"),
+        ]),
+    };
+    assert_eq!(event_type(&definition), "0x42::market::MarketCreatedEvent");
+
+    let address = FIXTURE_ADDRESS.to_standard_string();
+    assert_eq!(
+        event_fixture(&definition),
+        json!({
+            "amount": "100",
+            "decimals": 1,
+            "enabled": true,
+            "owner": address,
+            "name": "name",
+            "symbol": "symbol",
+            "memo": "0x0102",
+            "holders": [address],
+            "cap": { "vec": ["100"] },
+            "metadata": { "inner": address },
+            "config": { "ltv": "100", "rate": { "base": "100" } },
+            "fee": {},
+        })
+    );
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+use yeaptor_core::lockfile::{DependencyHashes, Lockfile, load_lockfile, lockfile_path};
+
+fn hashes(bytecode: &str, source: &str) -> DependencyHashes {
+    DependencyHashes {
+        bytecode_sha3_256: bytecode.repeat(64),
+        source_sha3_256: source.repeat(64),
+    }
+}
+
+#[test]
+fn test_lockfile_round_trip() {
+    let dir = tempdir().unwrap();
+    let path = lockfile_path(&dir.path().join("yeaptor.toml"));
+    assert_eq!(path, dir.path().join("yeaptor.lock"));
+    assert_eq!(load_lockfile(&path).unwrap(), None);
+
+    let mut lockfile = Lockfile::default();
+    lockfile.update(&BTreeMap::from([
+        ("AptosFramework".to_string(), hashes("a", "b")),
+        ("AptosStdlib".to_string(), hashes("c", "d")),
+    ]));
+    let toml = lockfile.to_toml().unwrap();
+    assert!(toml.starts_with("# Generated by yeaptor"));
+    fs::write(&path, &toml).unwrap();
+    assert_eq!(load_lockfile(&path).unwrap(), Some(lockfile));
+
+    fs::write(&path, toml.replace("version = 1", "version = 9")).unwrap();
+    let err = load_lockfile(&path).unwrap_err().to_string();
+    assert!(err.contains("has version 9"), "{}", err);
+    assert!(
+        load_lockfile(Path::new("missing/yeaptor.lock"))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_lockfile_check() {
+    let mut lockfile = Lockfile::default();
+    lockfile.update(&BTreeMap::from([
+        ("AptosFramework".to_string(), hashes("a", "b")),
+        ("AptosStdlib".to_string(), hashes("c", "d")),
+    ]));
+
+    // unchanged, or not pinned yet
+    let built = BTreeMap::from([
+        ("AptosFramework".to_string(), hashes("a", "b")),
+        ("Pyth".to_string(), hashes("e", "f")),
+    ]);
+    assert!(lockfile.check(&built).is_empty());
+
+    // same revision, new content
+    let built = BTreeMap::from([
+        ("AptosFramework".to_string(), hashes("a", "0")),
+        ("AptosStdlib".to_string(), hashes("1", "2")),
+    ]);
+    let err = lockfile
+        .check(&built)
+        .into_result()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("dependencies.AptosFramework: sources changed"),
+        "{}",
+        err
+    );
+    assert!(
+        err.contains("dependencies.AptosStdlib: bytecode and sources changed"),
+        "{}",
+        err
+    );
+    assert!(err.contains("--update-lock"), "{}", err);
+
+    lockfile.update(&built);
+    assert!(lockfile.check(&built).is_empty());
+    assert_eq!(lockfile.dependencies.len(), 2);
+}
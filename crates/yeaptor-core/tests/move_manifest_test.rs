@@ -0,0 +1,151 @@
+use move_core_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor_core::move_manifest::{
+    GitDependency, dependency_cycles, load_git_dependencies, load_local_dependencies,
+    load_manifest_addresses, load_package_version, local_dependency_dirs,
+};
+
+const MANIFEST: &str = r#"
+[package]
+name = "Core"
+version = "1.0.0"
+
+[addresses]
+core = "_"
+oracle = "0x5"
+admin = "0xad"
+
+[dev-addresses]
+core = "0xc0"
+
+[dependencies]
+AptosFramework = { git = "https://github.com/aptos-labs/aptos-framework.git", rev = "mainnet", subdir = "aptos-framework" }
+"#;
+
+fn address(hex: &str) -> AccountAddress {
+    AccountAddress::from_hex_literal(hex).unwrap()
+}
+
+#[test]
+fn test_manifest_address_conflicts() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("Move.toml"), MANIFEST).unwrap();
+    let manifest = load_manifest_addresses(dir.path()).unwrap();
+    assert_eq!(manifest.addresses["core"], "_");
+    assert_eq!(manifest.dev_addresses["core"], "0xc0");
+    assert!(manifest.declares("admin"));
+    assert!(!manifest.declares("vault"));
+
+    let named_addresses = BTreeMap::from([
+        ("core".to_string(), address("0xc1")),
+        ("oracle".to_string(), address("0x6")),
+        // Same address written differently is not a conflict
+        ("admin".to_string(), address("0x00ad")),
+    ]);
+    let conflicts = manifest.conflicts(&named_addresses, false);
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].name, "oracle");
+    assert_eq!(
+        conflicts[0].to_string(),
+        format!(
+            "[addresses] pins oracle = \"0x5\", but yeaptor resolves it to {}",
+            address("0x6").to_standard_string()
+        )
+    );
+
+    let conflicts = manifest.conflicts(&named_addresses, true);
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|conflict| (conflict.section, conflict.name.as_str()))
+            .collect::<Vec<_>>(),
+        [("addresses", "oracle"), ("dev-addresses", "core")]
+    );
+}
+
+#[test]
+fn test_missing_manifest() {
+    let dir = tempdir().unwrap();
+    let err = load_manifest_addresses(dir.path()).unwrap_err();
+    assert!(err.downcast_ref::<std::io::Error>().is_some());
+}
+
+#[test]
+fn test_local_dependencies() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("Move.toml"),
+        format!("{}Oracle = {{ local = \"../oracle\" }}\n", MANIFEST),
+    )
+    .unwrap();
+    let dependencies = load_local_dependencies(dir.path()).unwrap();
+    assert_eq!(
+        dependencies,
+        BTreeMap::from([("Oracle".to_string(), dir.path().join("../oracle"))])
+    );
+}
+
+#[test]
+fn test_git_dependencies() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("Move.toml"),
+        format!("{}Oracle = {{ local = \"../oracle\" }}\n", MANIFEST),
+    )
+    .unwrap();
+    assert_eq!(
+        load_git_dependencies(dir.path()).unwrap(),
+        BTreeMap::from([(
+            "AptosFramework".to_string(),
+            GitDependency {
+                git: "https://github.com/aptos-labs/aptos-framework.git".to_string(),
+                rev: Some("mainnet".to_string()),
+                subdir: Some("aptos-framework".to_string()),
+            }
+        )])
+    );
+    assert_eq!(
+        load_package_version(dir.path()).unwrap().as_deref(),
+        Some("1.0.0")
+    );
+}
+
+#[test]
+fn test_local_dependency_dirs() {
+    let dir = tempdir().unwrap();
+    for (package, dependencies) in [
+        (
+            "app",
+            "Core = { local = \"../core\" }\nOracle = { local = \"../oracle\" }\n",
+        ),
+        ("core", "Oracle = { local = \"../oracle\" }\n"),
+        ("oracle", ""),
+    ] {
+        fs::create_dir_all(dir.path().join(package)).unwrap();
+        fs::write(
+            dir.path().join(package).join("Move.toml"),
+            format!(
+                "[package]\nname = \"{}\"\n\n[dependencies]\n{}",
+                package, dependencies
+            ),
+        )
+        .unwrap();
+    }
+    let dirs = local_dependency_dirs(&dir.path().join("app")).unwrap();
+    assert_eq!(dirs.keys().collect::<Vec<_>>(), ["Core", "Oracle"]);
+    assert!(
+        local_dependency_dirs(&dir.path().join("oracle"))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_dependency_cycles() {
+    // 0 -> 1 -> 2 -> 0 and 2 -> 3 -> 0, 4 -> 4, 5 alone
+    let edges = vec![vec![1], vec![2], vec![0, 3], vec![0], vec![4], vec![]];
+    assert_eq!(dependency_cycles(&edges), [vec![0, 1, 2], vec![4]]);
+    assert!(dependency_cycles(&[vec![1], vec![2], vec![]]).is_empty());
+}
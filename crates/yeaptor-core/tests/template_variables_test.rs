@@ -0,0 +1,81 @@
+use move_core_types::account_address::AccountAddress;
+use yeaptor_core::address::resource_account_address;
+use yeaptor_core::config::parse_config;
+use yeaptor_core::template_variables::TemplateVariables;
+
+const CONFIG: &str = r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+deployer = "0x10"
+
+[[deployments]]
+publisher = "deployer"
+seed = "core"
+packages = [{ address_name = "yeap_core", path = "packages/core" }]
+
+[[deployments]]
+publisher = "unknown"
+seed = "oracle"
+packages = [{ address_name = "yeap_oracle", path = "packages/oracle" }]
+"#;
+
+#[test]
+fn test_template_variables_from_config() {
+    let config = parse_config(CONFIG).unwrap();
+    let variables = TemplateVariables::from_config(&config);
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    assert_eq!(
+        variables.addresses.get("yeap_core"),
+        Some(&resource_account_address(&publisher, b"core"))
+    );
+    // packages of deployments whose publisher is unknown have no address
+    assert!(!variables.addresses.contains_key("yeap_oracle"));
+}
+
+#[test]
+fn test_render_template_variables() {
+    let variables = TemplateVariables {
+        addresses: [(
+            "yeap_core".to_string(),
+            AccountAddress::from_hex_literal("0x42").unwrap(),
+        )]
+        .into_iter()
+        .collect(),
+        network: Some("testnet".to_string()),
+        starting_version: Some(100),
+    };
+    assert_eq!(
+        variables
+            .render(
+                r#"[[entry_functions."{{address:yeap_core}}::market::supply"]]
+constants = { network = "{{ network }}", since = {{starting_version}} }"#
+            )
+            .unwrap(),
+        r#"[[entry_functions."0x42::market::supply"]]
+constants = { network = "testnet", since = 100 }"#
+    );
+    assert_eq!(
+        variables.render("no placeholders").unwrap(),
+        "no placeholders"
+    );
+
+    for (text, expected) in [
+        ("{{address:yeap_cor}}", "did you mean `yeap_core`?"),
+        ("{{chain}}", "unknown placeholder `{{chain}}`"),
+        ("{{network", "unterminated placeholder"),
+    ] {
+        let err = variables.render(text).unwrap_err();
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+
+    let err = TemplateVariables::default()
+        .render("{{network}}")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("set by `processor generate`"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,246 @@
+use crate::config::{DeploymentMode, YeaptorConfig};
+use crate::diagnostics::did_you_mean;
+use anyhow::Result;
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::str::FromStr;
+use yeaptor_core::address::object_code_seed;
+
+/// Where one address of yeaptor.toml comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressExplanation {
+    /// Named address, publisher alias or setting the address is bound to
+    pub name: String,
+    pub address: String,
+    /// `yeaptor_address`, `networks.<name>.yeaptor_address`, `[named-addresses]`, `[publishers]`
+    /// or `deployments[i].packages[j]`
+    pub location: String,
+    #[serde(flatten)]
+    pub derivation: Derivation,
+}
+
+/// How an address is obtained from yeaptor.toml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "derivation", rename_all = "kebab-case")]
+pub enum Derivation {
+    /// Written as is
+    Declared,
+    /// `sha3-256(publisher | seed | 0xff)`, as `0x1::account::create_resource_address`
+    ResourceAccount {
+        publisher_name: String,
+        publisher: String,
+        seed: String,
+        /// The seed bytes hashed, as `0x`-prefixed hex
+        seed_hex: String,
+    },
+    /// The publisher's own account
+    Account {
+        publisher_name: String,
+        publisher: String,
+    },
+    /// `sha3-256(publisher | seed | 0xfe)` of the object created by the publisher's transaction
+    /// of `sequence_number`, or the existing `object_address` (both `None` then)
+    Object {
+        publisher_name: String,
+        publisher: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sequence_number: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seed_hex: Option<String>,
+    },
+}
+
+/// Every place of `config` an address comes from, and the deployments whose publisher is
+/// unknown as (named address, location, publisher)
+fn config_addresses(
+    config: &YeaptorConfig,
+) -> (Vec<AddressExplanation>, Vec<(String, String, String)>) {
+    let declared = |name: &str, location: String, address: &AccountAddress| AddressExplanation {
+        name: name.to_string(),
+        address: address.to_standard_string(),
+        location,
+        derivation: Derivation::Declared,
+    };
+    let mut explanations = vec![declared(
+        "yeaptor_address",
+        "yeaptor_address".to_string(),
+        &config.yeaptor_address,
+    )];
+    for (network, settings) in &config.networks {
+        if let Some(address) = &settings.yeaptor_address {
+            explanations.push(declared(
+                "yeaptor_address",
+                format!("networks.{}.yeaptor_address", network),
+                address,
+            ));
+        }
+    }
+    for (name, address) in &config.named_addresses {
+        explanations.push(declared(name, "[named-addresses]".to_string(), address));
+    }
+    for (name, publisher) in &config.publishers {
+        explanations.push(declared(
+            name,
+            "[publishers]".to_string(),
+            &publisher.address,
+        ));
+    }
+    let mut unresolved = Vec::new();
+    for (i, deployment) in config.deployments.iter().enumerate() {
+        let publisher = config.publisher_address(&deployment.publisher);
+        for (j, package) in deployment.packages.iter().enumerate() {
+            let location = format!("deployments[{}].packages[{}]", i, j);
+            let Some(publisher) = publisher else {
+                unresolved.push((
+                    package.address_name.clone(),
+                    location,
+                    deployment.publisher.clone(),
+                ));
+                continue;
+            };
+            let publisher_name = deployment.publisher.clone();
+            let derivation = match deployment.mode() {
+                DeploymentMode::ResourceAccount => Derivation::ResourceAccount {
+                    publisher_name,
+                    publisher: publisher.to_standard_string(),
+                    seed: deployment.seed().to_string(),
+                    seed_hex: format!("0x{}", hex::encode(deployment.seed())),
+                },
+                DeploymentMode::Account => Derivation::Account {
+                    publisher_name,
+                    publisher: publisher.to_standard_string(),
+                },
+                DeploymentMode::Object => {
+                    let sequence_number = match deployment.object_address {
+                        Some(_) => None,
+                        None => Some(deployment.sequence_number.unwrap_or_default()),
+                    };
+                    Derivation::Object {
+                        publisher_name,
+                        publisher: publisher.to_standard_string(),
+                        sequence_number,
+                        seed_hex: sequence_number.map(|sequence_number| {
+                            format!("0x{}", hex::encode(object_code_seed(sequence_number)))
+                        }),
+                    }
+                }
+            };
+            explanations.push(AddressExplanation {
+                name: package.address_name.clone(),
+                address: deployment.account(&publisher).to_standard_string(),
+                location,
+                derivation,
+            });
+        }
+    }
+    (explanations, unresolved)
+}
+
+/// Every place of `config` the named address (or publisher alias, or `yeaptor_address`) `query`
+/// is declared or derived, or, when `query` is no such name but an address, every name bound to
+/// it. Fails naming the closest name when nothing matches.
+pub fn explain_address(config: &YeaptorConfig, query: &str) -> Result<Vec<AddressExplanation>> {
+    let (explanations, unresolved) = config_addresses(config);
+    let by_name = explanations
+        .iter()
+        .filter(|explanation| explanation.name == query)
+        .cloned()
+        .collect::<Vec<_>>();
+    if let Some((_, location, publisher)) = unresolved.iter().find(|(name, ..)| name == query) {
+        anyhow::bail!(
+            "`{}` of {} cannot be derived: publisher `{}` is neither in [publishers] nor an address",
+            query,
+            location,
+            publisher
+        );
+    }
+    if !by_name.is_empty() {
+        return Ok(by_name);
+    }
+    if let Ok(address) = AccountAddress::from_str(query) {
+        let address = address.to_standard_string();
+        let by_address = explanations
+            .into_iter()
+            .filter(|explanation| explanation.address == address)
+            .collect::<Vec<_>>();
+        if by_address.is_empty() {
+            anyhow::bail!(
+                "{} is neither declared in nor derived from the config",
+                address
+            );
+        }
+        return Ok(by_address);
+    }
+    let names = explanations
+        .iter()
+        .map(|explanation| explanation.name.as_str())
+        .chain(unresolved.iter().map(|(name, ..)| name.as_str()));
+    match did_you_mean(query, names) {
+        Some(closest) => anyhow::bail!(
+            "unknown named address `{}`, did you mean `{}`?",
+            query,
+            closest
+        ),
+        None => anyhow::bail!(
+            "unknown named address `{}`; it is not in [named-addresses], [publishers] or the packages of the deployments",
+            query
+        ),
+    }
+}
+
+/// `explanations` as text: per address, its name and value, where it comes from and how it is
+/// derived
+pub fn render_explanations(explanations: &[AddressExplanation]) -> String {
+    explanations
+        .iter()
+        .map(|explanation| {
+            let derivation = match &explanation.derivation {
+                Derivation::Declared => "declared as is".to_string(),
+                Derivation::ResourceAccount {
+                    publisher_name,
+                    publisher,
+                    seed,
+                    seed_hex,
+                } => format!(
+                    "resource account of publisher `{}` ({}) with seed {:?} ({})\n  \
+                     = sha3-256(publisher | seed | 0xff), as 0x1::account::create_resource_address",
+                    publisher_name, publisher, seed, seed_hex
+                ),
+                Derivation::Account {
+                    publisher_name,
+                    publisher,
+                } => format!(
+                    "account of publisher `{}` ({}) itself (mode account)",
+                    publisher_name, publisher
+                ),
+                Derivation::Object {
+                    publisher_name,
+                    publisher,
+                    sequence_number: Some(sequence_number),
+                    seed_hex,
+                } => format!(
+                    "code object created by the transaction of sequence number {} of publisher \
+                     `{}` ({}), with seed {}\n  \
+                     = sha3-256(publisher | seed | 0xfe), as 0x1::object_code_deployment::publish",
+                    sequence_number,
+                    publisher_name,
+                    publisher,
+                    seed_hex.as_deref().unwrap_or_default()
+                ),
+                Derivation::Object {
+                    publisher_name,
+                    publisher,
+                    ..
+                } => format!(
+                    "existing code object set as `object_address`, upgraded by publisher `{}` ({})",
+                    publisher_name, publisher
+                ),
+            };
+            format!(
+                "{} = {}\n  {}: {}",
+                explanation.name, explanation.address, explanation.location, derivation
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
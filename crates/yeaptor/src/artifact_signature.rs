@@ -0,0 +1,199 @@
+use crate::diagnostics::Diagnostics;
+use anyhow::{Context, Result, anyhow};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Detached signature written next to the payloads by `yeaptor artifacts sign`
+pub const SIGNATURE_FILE_NAME: &str = "signature.json";
+
+/// Prefix of the signed message, so that a signature over artifacts is never valid for anything
+/// else signed with the same key
+const SIGNING_DOMAIN: &str = "yeaptor::artifacts::v1";
+
+/// Detached ed25519 signature over the manifest.json of a build output directory and the payload
+/// files it lists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArtifactSignature {
+    /// Ed25519 public key of the signer, hex
+    pub public_key: String,
+    /// Signed files with their hashes, in signing order
+    pub files: Vec<SignedFile>,
+    /// Ed25519 signature of the files and their hashes, hex
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignedFile {
+    /// Path relative to the output directory, with `/` separators
+    pub path: String,
+    /// SHA3-256 of the file, hex
+    pub sha3_256: String,
+}
+
+/// Files of `out_dir` covered by a signature: manifest.json, then the payload files it lists in
+/// deployment order, as paths relative to `out_dir`. Fails on a listed file that is not a
+/// relative path inside `out_dir`.
+pub fn signed_file_paths(out_dir: &Path) -> Result<Vec<String>> {
+    let manifest_path = out_dir.join("manifest.json");
+    let manifest: serde_json::Value = serde_json::from_slice(
+        &fs::read(&manifest_path)
+            .with_context(|| format!("failed to read {}", manifest_path.display()))?,
+    )
+    .with_context(|| format!("invalid {}", manifest_path.display()))?;
+    let packages = manifest
+        .get("packages")
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| anyhow!("{} has no packages", manifest_path.display()))?;
+    let mut paths = vec!["manifest.json".to_string()];
+    for package in packages {
+        let file = package
+            .get("file")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| anyhow!("a package of {} has no file", manifest_path.display()))?;
+        let file = file.replace('\\', "/");
+        check_out_dir_path(&file)
+            .with_context(|| format!("invalid package file in {}", manifest_path.display()))?;
+        paths.push(file);
+    }
+    Ok(paths)
+}
+
+/// Sign manifest.json of `out_dir` and the payload files it lists with `key`
+pub fn sign_artifacts(out_dir: &Path, key: &SigningKey) -> Result<ArtifactSignature> {
+    let files = signed_file_paths(out_dir)?
+        .into_iter()
+        .map(|path| {
+            Ok(SignedFile {
+                sha3_256: file_sha3_256(out_dir, &path)?,
+                path,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let signature = key.sign(&signing_message(&files)?);
+    Ok(ArtifactSignature {
+        public_key: encode_hex(key.verifying_key().as_bytes()),
+        files,
+        signature: encode_hex(&signature.to_bytes()),
+    })
+}
+
+/// Check `signature` against `public_key` and the files of `out_dir`: the signer must be
+/// `public_key`, the signature must cover the listed hashes, and every file manifest.json lists
+/// must be signed and unchanged. Problems are located at the file concerned, or at the signature.
+pub fn verify_artifacts(
+    out_dir: &Path,
+    signature: &ArtifactSignature,
+    public_key: &VerifyingKey,
+) -> Result<Diagnostics> {
+    let mut diagnostics = Diagnostics::default();
+    let expected = encode_hex(public_key.as_bytes());
+    if signature.public_key != expected {
+        diagnostics.push(
+            SIGNATURE_FILE_NAME,
+            format!(
+                "signed by {}, not by the expected key {}",
+                signature.public_key, expected
+            ),
+        );
+        return Ok(diagnostics);
+    }
+    let message = signing_message(&signature.files)?;
+    let valid = decode_hex::<64>(&signature.signature)
+        .and_then(|bytes| Ok(public_key.verify_strict(&message, &Signature::from_bytes(&bytes))?));
+    if let Err(e) = valid {
+        diagnostics.push(
+            SIGNATURE_FILE_NAME,
+            format!("signature does not match its file list: {}", e),
+        );
+        return Ok(diagnostics);
+    }
+    for file in &signature.files {
+        match file_sha3_256(out_dir, &file.path) {
+            Ok(hash) if hash == file.sha3_256 => {}
+            Ok(_) => diagnostics.push(&file.path, "changed since it was signed"),
+            Err(e) => diagnostics.push(&file.path, format!("{:#}", e)),
+        }
+    }
+    // a payload added to manifest.json after signing changes manifest.json too, but say which
+    if let Ok(paths) = signed_file_paths(out_dir) {
+        for path in paths {
+            if !signature.files.iter().any(|file| file.path == path) {
+                diagnostics.push(&path, "listed in manifest.json but not signed");
+            }
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// The key of an ed25519 private key file: hex, with or without `0x`, as written by
+/// `aptos key generate`
+pub fn load_private_key(path: &Path) -> Result<SigningKey> {
+    let s = fs::read_to_string(path)?;
+    let bytes = decode_hex::<32>(s.trim())
+        .map_err(|e| anyhow!("{} is not a hex ed25519 private key: {}", path.display(), e))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// `public_key` as hex, with or without `0x`, or as the path of a file holding it
+pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey> {
+    let path = PathBuf::from(public_key);
+    let encoded = if path.is_file() {
+        fs::read_to_string(&path)?.trim().to_string()
+    } else {
+        public_key.to_string()
+    };
+    decode_hex::<32>(&encoded)
+        .and_then(|bytes| Ok(VerifyingKey::from_bytes(&bytes)?))
+        .map_err(|e| anyhow!("`{}` is not a hex ed25519 public key: {}", public_key, e))
+}
+
+/// `0x`-prefixed hex, as the aptos CLI prints keys
+pub fn encode_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn decode_hex<const N: usize>(s: &str) -> Result<[u8; N]> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("expected {} bytes, got {}", N, len))
+}
+
+fn signing_message(files: &[SignedFile]) -> Result<Vec<u8>> {
+    let files = files
+        .iter()
+        .map(|file| (file.path.as_str(), file.sha3_256.as_str()))
+        .collect::<Vec<_>>();
+    Ok(bcs::to_bytes(&(SIGNING_DOMAIN, files))?)
+}
+
+/// Fails unless `path` is relative and made only of normal components, so that it names a file
+/// inside the out dir
+fn check_out_dir_path(path: &str) -> Result<()> {
+    let components = Path::new(path).components().collect::<Vec<_>>();
+    if components.is_empty()
+        || !components
+            .iter()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(anyhow!(
+            "`{}` is not a relative path inside the out dir",
+            path
+        ));
+    }
+    Ok(())
+}
+
+fn file_sha3_256(out_dir: &Path, path: &str) -> Result<String> {
+    check_out_dir_path(path)?;
+    let full_path = out_dir.join(path);
+    let bytes =
+        fs::read(&full_path).with_context(|| format!("failed to read {}", full_path.display()))?;
+    Ok(hex::encode(Sha3_256::digest(bytes)))
+}
@@ -0,0 +1,199 @@
+use crate::config::{PolicyLevel, PolicySettings};
+use crate::deployment_planner::PlannedPackage;
+use anyhow::{Context, Result};
+use aptos_framework::natives::code::PackageMetadata;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::file_format::{SignatureToken, Visibility};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use yeaptor_core::diagnostics::Diagnostics;
+
+/// What auditors review of a built package, from its compiled modules and metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageAudit {
+    pub package: String,
+    /// Output subdirectory of its deployment (`Deployment::dir_name`)
+    pub deployment: String,
+    /// Account the package is published to, as a standard string
+    pub account: String,
+    /// `arbitrary`, `compatible` or `immutable`
+    pub upgrade_policy: String,
+    pub modules: Vec<ModuleAudit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleAudit {
+    pub name: String,
+    /// Modules allowed to call its `public(friend)` functions, as `0x...::name`
+    pub friends: Vec<String>,
+    /// Public, friend and entry functions, and private ones taking a signer
+    pub functions: Vec<FunctionAudit>,
+    /// Structs with both `copy` and `store`, whose values can be duplicated into global storage
+    pub copy_store_structs: Vec<String>,
+    /// Functions declared `native`, implemented by the VM rather than in Move
+    pub natives: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionAudit {
+    pub name: String,
+    /// `public`, `friend` or `private`
+    pub visibility: String,
+    pub entry: bool,
+    /// Takes a `signer` or `&signer`, i.e. acts with the authority of an account
+    pub takes_signer: bool,
+}
+
+/// Audit report of `package`, from its `metadata` and `modules`
+pub fn audit_package(package: &PlannedPackage) -> Result<PackageAudit> {
+    let upgrade_policy = upgrade_policy(package)?;
+    let modules = package
+        .modules
+        .iter()
+        .map(|bytes| {
+            let module = CompiledModule::deserialize(bytes)
+                .with_context(|| format!("a module of {} does not deserialize", package.name))?;
+            Ok(audit_module(&module))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(PackageAudit {
+        package: package.name.clone(),
+        deployment: package.deployment.clone(),
+        account: package.account.to_standard_string(),
+        upgrade_policy: upgrade_policy_name(upgrade_policy).to_string(),
+        modules,
+    })
+}
+
+/// `UpgradePolicy::policy` of `package`, from its metadata
+pub fn upgrade_policy(package: &PlannedPackage) -> Result<u8> {
+    let metadata: PackageMetadata = bcs::from_bytes(&package.metadata)
+        .with_context(|| format!("package metadata of {} does not deserialize", package.name))?;
+    Ok(metadata.upgrade_policy.policy)
+}
+
+/// Apply `[policy]` to `packages`: a package with the `arbitrary` upgrade policy, which its
+/// publisher can replace with incompatible code, and a deployment whose packages have different
+/// policies are findings. Findings at `PolicyLevel::Error` fail with `Diagnostics` naming the
+/// package or deployment; those at `PolicyLevel::Warn` are returned as warnings.
+pub fn check_upgrade_policies(
+    packages: &[PlannedPackage],
+    policy: &PolicySettings,
+) -> Result<Vec<String>> {
+    let mut diagnostics = Diagnostics::default();
+    let mut warnings = Vec::new();
+    let mut report = |level: PolicyLevel, location: &str, message: String| match level {
+        PolicyLevel::Allow => {}
+        PolicyLevel::Warn => warnings.push(format!("{}: {}", location, message)),
+        PolicyLevel::Error => diagnostics.push(location, message),
+    };
+    let mut deployments: BTreeMap<&str, Vec<(&str, u8)>> = BTreeMap::new();
+    for package in packages {
+        let policy_value = upgrade_policy(package)?;
+        if policy_value == 0 {
+            report(
+                policy.arbitrary_upgrade(),
+                &package.name,
+                format!(
+                    "built with upgrade_policy = \"arbitrary\", so {} can be replaced with \
+                     incompatible code by whoever controls it; set `upgrade_policy = \"compatible\"` \
+                     in its Move.toml, or `arbitrary_upgrade = \"allow\"` under [policy]",
+                    package.account.to_standard_string()
+                ),
+            );
+        }
+        deployments
+            .entry(&package.deployment)
+            .or_default()
+            .push((&package.name, policy_value));
+    }
+    for (deployment, policies) in deployments {
+        if policies.iter().all(|(_, p)| *p == policies[0].1) {
+            continue;
+        }
+        report(
+            policy.upgrade_policy_mismatch(),
+            deployment,
+            format!(
+                "packages have different upgrade policies: {}",
+                policies
+                    .iter()
+                    .map(|(name, p)| format!("{} ({})", name, upgrade_policy_name(*p)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+    }
+    diagnostics.into_result()?;
+    Ok(warnings)
+}
+
+/// Name of an `UpgradePolicy::policy` value, as in Move.toml's `upgrade_policy`
+pub fn upgrade_policy_name(policy: u8) -> &'static str {
+    match policy {
+        0 => "arbitrary",
+        1 => "compatible",
+        _ => "immutable",
+    }
+}
+
+fn audit_module(module: &CompiledModule) -> ModuleAudit {
+    let friends = module
+        .friend_decls()
+        .iter()
+        .map(|handle| module.module_id_for_handle(handle).short_str_lossless())
+        .collect();
+    let mut functions = Vec::new();
+    let mut natives = Vec::new();
+    for def in module.function_defs() {
+        let handle = module.function_handle_at(def.function);
+        let name = module.identifier_at(handle.name).to_string();
+        if def.is_native() {
+            natives.push(name.clone());
+        }
+        let takes_signer = module
+            .signature_at(handle.parameters)
+            .0
+            .iter()
+            .any(is_signer);
+        if def.visibility == Visibility::Private && !def.is_entry && !takes_signer {
+            continue;
+        }
+        functions.push(FunctionAudit {
+            name,
+            visibility: match def.visibility {
+                Visibility::Public => "public",
+                Visibility::Friend => "friend",
+                Visibility::Private => "private",
+            }
+            .to_string(),
+            entry: def.is_entry,
+            takes_signer,
+        });
+    }
+    let copy_store_structs = module
+        .struct_defs()
+        .iter()
+        .map(|def| module.struct_handle_at(def.struct_handle))
+        .filter(|handle| handle.abilities.has_copy() && handle.abilities.has_store())
+        .map(|handle| module.identifier_at(handle.name).to_string())
+        .collect();
+    ModuleAudit {
+        name: module.self_id().name().to_string(),
+        friends,
+        functions,
+        copy_store_structs,
+        natives,
+    }
+}
+
+fn is_signer(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            matches!(**inner, SignatureToken::Signer)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,120 @@
+use crate::processor_config::{ColumnSpec, TableSchema};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// File name of the dataset manifest written next to the table schema files
+pub const DATASET_MANIFEST_FILE: &str = "dataset.json";
+
+/// BigQuery allows at most four clustering columns
+const MAX_CLUSTERING_FIELDS: usize = 4;
+
+/// One field of a BigQuery JSON schema file (`bq mk --schema`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BigQueryField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    /// `REQUIRED`, `NULLABLE` or `REPEATED`
+    pub mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Tables of the dataset and how to create them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatasetManifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+    pub tables: BTreeMap<String, ManifestTable>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestTable {
+    /// Schema file, relative to the manifest
+    pub schema_file: String,
+    /// Unenforced primary key (`PRIMARY KEY ... NOT ENFORCED`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
+    /// Primary key then indexed columns, up to BigQuery's limit of four
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clustering: Vec<String>,
+}
+
+/// BigQuery JSON schema of a table. Move integers map to the narrowest exact type: `INT64` up to
+/// `u32`, `NUMERIC` for `u64`, `BIGNUMERIC` for `u128`; `u256` exceeds BIGNUMERIC and is a STRING.
+pub fn bigquery_table_schema(schema: &TableSchema) -> Result<Vec<BigQueryField>> {
+    schema
+        .iter()
+        .map(|(column, spec)| {
+            let field_type = bigquery_type(spec).with_context(|| format!("column {}", column))?;
+            let mode = if spec.is_vec {
+                "REPEATED"
+            } else if !spec.is_primary_key && (spec.is_nullable || spec.is_option) {
+                "NULLABLE"
+            } else {
+                "REQUIRED"
+            };
+            Ok(BigQueryField {
+                name: column.clone(),
+                field_type: field_type.to_string(),
+                mode: mode.to_string(),
+                description: (spec.column_type.column_type == "u256")
+                    .then(|| "u256 as a decimal string".to_string()),
+            })
+        })
+        .collect()
+}
+
+fn bigquery_type(spec: &ColumnSpec) -> Result<&'static str> {
+    let column_type = spec.column_type.column_type.as_str();
+    Ok(match (spec.column_type.r#type.as_str(), column_type) {
+        ("move_type", "u8" | "u16" | "u32") => "INT64",
+        ("move_type", "u64") => "NUMERIC",
+        ("move_type", "u128" | "decimal") => "BIGNUMERIC",
+        ("move_type", "u256" | "address" | "object" | "string") => "STRING",
+        ("move_type", "bool") => "BOOL",
+        ("transaction_metadata", "version" | "block_height" | "epoch") => "INT64",
+        ("transaction_metadata", "timestamp") => "TIMESTAMP",
+        ("event_metadata", "creation_number" | "sequence_number" | "event_index") => "INT64",
+        ("event_metadata", "account_address" | "event_type") => "STRING",
+        (kind, _) => bail!("no BigQuery type for {} {}", kind, column_type),
+    })
+}
+
+/// Manifest listing every table with its schema file (`<table>.json`), primary key and clustering
+pub fn bigquery_dataset_manifest(
+    tables: &BTreeMap<String, TableSchema>,
+    dataset: Option<String>,
+) -> DatasetManifest {
+    let tables = tables
+        .iter()
+        .map(|(table, schema)| {
+            let primary_key = schema
+                .iter()
+                .filter(|(_, spec)| spec.is_primary_key)
+                .map(|(column, _)| column.clone())
+                .collect::<Vec<_>>();
+            // repeated fields cannot be clustered on
+            let indexed = schema
+                .iter()
+                .filter(|(_, spec)| spec.is_index && !spec.is_primary_key && !spec.is_vec)
+                .map(|(column, _)| column.clone());
+            let clustering = primary_key
+                .iter()
+                .cloned()
+                .chain(indexed)
+                .take(MAX_CLUSTERING_FIELDS)
+                .collect();
+            (
+                table.clone(),
+                ManifestTable {
+                    schema_file: format!("{}.json", table),
+                    primary_key,
+                    clustering,
+                },
+            )
+        })
+        .collect();
+    DatasetManifest { dataset, tables }
+}
@@ -0,0 +1,123 @@
+use crate::artifact_signature::{SIGNATURE_FILE_NAME, signed_file_paths};
+use crate::config::YeaptorConfig;
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Outputs of `deployment build` and `artifacts sign` in the out dir besides the payload files
+pub const OUT_DIR_OUTPUTS: &[&str] = &[
+    "manifest.json",
+    "addresses.toml",
+    "addresses.json",
+    "addresses.env",
+    "addresses.ts",
+    "sbom.json",
+    SIGNATURE_FILE_NAME,
+    "events",
+    "verification",
+];
+
+/// Existing paths removed by `yeaptor clean`, sorted: the payload files listed in
+/// `<out_dir>/manifest.json`, the subdirectory of every deployment of `config` and the other
+/// build outputs in `out_dir`, the `build/` directory of every package of `config`, and
+/// `cache_dirs` (Move build output directories). Paths inside another one are left out, as are
+/// manifest entries and deployment directory names that would lead out of `out_dir`.
+pub fn clean_targets(
+    config: &YeaptorConfig,
+    out_dir: &Path,
+    cache_dirs: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut candidates = BTreeSet::new();
+    // Payload file names come from a template, so only the manifest knows all of them
+    for path in signed_file_paths(out_dir).unwrap_or_default() {
+        candidates.extend(out_dir_entry(out_dir, Path::new(&path)));
+    }
+    for name in OUT_DIR_OUTPUTS {
+        candidates.insert(out_dir.join(name));
+    }
+    for deployment in &config.deployments {
+        candidates.extend(out_dir_entry(out_dir, Path::new(&deployment.dir_name())));
+        for package in &deployment.packages {
+            candidates.insert(package.path.join("build"));
+        }
+    }
+    candidates.extend(cache_dirs.iter().cloned());
+
+    let existing = candidates
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    existing
+        .iter()
+        .filter(|path| {
+            !existing
+                .iter()
+                .any(|other| other != *path && path.starts_with(other))
+        })
+        .cloned()
+        .collect()
+}
+
+/// `out_dir` joined with `relative`, if `relative` is made only of normal components and so
+/// names something inside `out_dir`
+fn out_dir_entry(out_dir: &Path, relative: &Path) -> Option<PathBuf> {
+    let normal = relative.components().next().is_some()
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    Some(out_dir.join(relative)).filter(|path| normal && path.starts_with(out_dir))
+}
+
+/// Removes `targets`, then the directories inside `out_dir` they leave empty. Fails before
+/// removing anything if a target is, or contains, `out_dir` or one of `protected` (e.g. the
+/// current directory, the config file and the package directories), comparing canonical paths
+/// so that `..` or a symlink cannot hide it.
+pub fn remove_targets(targets: &[PathBuf], out_dir: &Path, protected: &[PathBuf]) -> Result<()> {
+    let protected = std::iter::once(out_dir)
+        .chain(protected.iter().map(PathBuf::as_path))
+        .map(|path| Ok((path, canonical(path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    for target in targets {
+        let canonical_target = canonical(target)?;
+        if let Some((path, _)) = protected
+            .iter()
+            .find(|(_, canonical_path)| canonical_path.starts_with(&canonical_target))
+        {
+            bail!(
+                "refusing to remove {}, which contains {}",
+                target.display(),
+                path.display()
+            );
+        }
+    }
+    for target in targets {
+        if target.is_dir() {
+            fs::remove_dir_all(target)
+        } else {
+            fs::remove_file(target)
+        }
+        .with_context(|| format!("failed to remove {}", target.display()))?;
+    }
+    for target in targets.iter().filter(|target| target.starts_with(out_dir)) {
+        let mut dir = target.parent();
+        while let Some(current) = dir.filter(|current| *current != out_dir) {
+            // Stops at the first directory that still has files or is already gone
+            if fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+    Ok(())
+}
+
+/// `path` with symlinks and `..` resolved; absolute only for a path that does not exist (yet)
+fn canonical(path: &Path) -> Result<PathBuf> {
+    if path.exists() {
+        fs::canonicalize(path)
+    } else {
+        std::path::absolute(path)
+    }
+    .with_context(|| format!("failed to resolve {}", path.display()))
+}
@@ -0,0 +1,277 @@
+use crate::config::{DeployFunction, DeploymentMode};
+use crate::deployment_planner::DeploymentPlan;
+use crate::offline_signing::publish_entry_function;
+use crate::preflight::{account_sequence_number, fetch_published_packages};
+use anyhow::{Context, Result, bail};
+use aptos_rest_client::Client;
+use aptos_types::account_address::AccountAddress;
+use aptos_types::chain_id::ChainId;
+use aptos_types::transaction::authenticator::{AccountAuthenticator, TransactionAuthenticator};
+use aptos_types::transaction::{RawTransaction, SignedTransaction, TransactionPayload};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Octas in one APT
+pub const OCTAS_PER_APT: u64 = 100_000_000;
+
+/// Seconds a simulated transaction is valid for; it is never submitted
+const SIMULATION_EXPIRATION_SECS: u64 = 600;
+
+/// Whether a publish creates the package or upgrades one already published at its account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishKind {
+    Create,
+    Upgrade,
+}
+
+impl fmt::Display for PublishKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PublishKind::Create => "create",
+            PublishKind::Upgrade => "upgrade",
+        })
+    }
+}
+
+/// Simulated cost of publishing one package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageCost {
+    pub order: usize,
+    pub deployment: String,
+    pub package: String,
+    /// Publisher account, as a standard string
+    pub publisher: String,
+    pub kind: PublishKind,
+    /// Whether the simulation executed successfully; a failed one is left out of the totals
+    pub success: bool,
+    /// VM status of the simulation, e.g. `Executed successfully` or the abort
+    pub vm_status: String,
+    pub gas_used: u64,
+    /// `gas_used` at the report's gas unit price
+    pub octas: u64,
+}
+
+/// Costs of one publisher's packages added up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PublisherCost {
+    pub publisher: String,
+    pub packages: usize,
+    pub gas_used: u64,
+    pub octas: u64,
+    /// Packages whose simulation failed, not counted in `gas_used` and `octas`
+    pub failed: usize,
+}
+
+/// Simulated cost of publishing every package of a plan, by package and by publisher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CostReport {
+    pub gas_unit_price: u64,
+    pub packages: Vec<PackageCost>,
+    pub publishers: Vec<PublisherCost>,
+    pub total_gas_used: u64,
+    pub total_octas: u64,
+}
+
+impl CostReport {
+    /// Report of `packages` at `gas_unit_price`, adding up the successful simulations per
+    /// publisher
+    pub fn new(gas_unit_price: u64, packages: Vec<PackageCost>) -> Self {
+        let mut publishers: BTreeMap<&str, PublisherCost> = BTreeMap::new();
+        for package in &packages {
+            let publisher = publishers
+                .entry(package.publisher.as_str())
+                .or_insert_with(|| PublisherCost {
+                    publisher: package.publisher.clone(),
+                    packages: 0,
+                    gas_used: 0,
+                    octas: 0,
+                    failed: 0,
+                });
+            publisher.packages += 1;
+            if package.success {
+                publisher.gas_used += package.gas_used;
+                publisher.octas += package.octas;
+            } else {
+                publisher.failed += 1;
+            }
+        }
+        let publishers = publishers.into_values().collect::<Vec<_>>();
+        CostReport {
+            gas_unit_price,
+            total_gas_used: publishers.iter().map(|p| p.gas_used).sum(),
+            total_octas: publishers.iter().map(|p| p.octas).sum(),
+            packages,
+            publishers,
+        }
+    }
+}
+
+/// `octas` as APT with all eight decimals, e.g. `0.01234500 APT`
+pub fn format_apt(octas: u64) -> String {
+    format!("{}.{:08} APT", octas / OCTAS_PER_APT, octas % OCTAS_PER_APT)
+}
+
+/// Simulate the publish of every package of `plan` on the node behind `client`, each from its
+/// publisher's current sequence number, with `max_gas_amount` at `gas_unit_price` (default: the
+/// node's estimate). A package already in the `PackageRegistry` of its account (or an object
+/// deployment with `object_address`) is simulated as an upgrade. Simulations run against the
+/// current chain state, so a package depending on another one of the plan that is not published
+/// yet fails, as do the packages of a publisher that does not exist.
+pub async fn simulate_plan(
+    client: &Client,
+    plan: &DeploymentPlan,
+    deploy: &DeployFunction,
+    max_gas_amount: u64,
+    gas_unit_price: Option<u64>,
+    now_secs: u64,
+) -> Result<CostReport> {
+    let chain_id = client
+        .get_index()
+        .await
+        .context("failed to read the chain id")?
+        .into_inner()
+        .chain_id;
+    let gas_unit_price = match gas_unit_price {
+        Some(price) => price,
+        None => {
+            client
+                .estimate_gas_price()
+                .await
+                .context("failed to estimate the gas price")?
+                .into_inner()
+                .gas_estimate
+        }
+    };
+    let mut sequence_numbers: BTreeMap<AccountAddress, Option<u64>> = BTreeMap::new();
+    let mut published: BTreeMap<AccountAddress, Vec<String>> = BTreeMap::new();
+    let mut costs = Vec::new();
+    for package in &plan.packages {
+        if !sequence_numbers.contains_key(&package.publisher) {
+            let sequence_number = account_sequence_number(client, package.publisher).await?;
+            sequence_numbers.insert(package.publisher, sequence_number);
+        }
+        if !published.contains_key(&package.account) {
+            let names = fetch_published_packages(client, package.account)
+                .await?
+                .into_iter()
+                .map(|metadata| metadata.name)
+                .collect();
+            published.insert(package.account, names);
+        }
+        let upgrade = (package.mode == DeploymentMode::Object && package.object_upgrade)
+            || published[&package.account].contains(&package.name);
+        let mut cost = PackageCost {
+            order: package.order,
+            deployment: package.deployment.clone(),
+            package: package.name.clone(),
+            publisher: package.publisher.to_standard_string(),
+            kind: if upgrade {
+                PublishKind::Upgrade
+            } else {
+                PublishKind::Create
+            },
+            success: false,
+            vm_status: String::new(),
+            gas_used: 0,
+            octas: 0,
+        };
+        let Some(sequence_number) = sequence_numbers[&package.publisher] else {
+            cost.vm_status = "publisher account does not exist".to_string();
+            costs.push(cost);
+            continue;
+        };
+        let raw = RawTransaction::new(
+            package.publisher,
+            sequence_number,
+            TransactionPayload::EntryFunction(publish_entry_function(package, deploy)?),
+            max_gas_amount,
+            gas_unit_price,
+            now_secs + SIMULATION_EXPIRATION_SECS,
+            ChainId::new(chain_id),
+        );
+        // simulation skips the signature check, so no key is needed
+        let transaction = SignedTransaction::new_signed_transaction(
+            raw,
+            TransactionAuthenticator::single_sender(AccountAuthenticator::NoAccountAuthenticator),
+        );
+        let simulated = client
+            .simulate(&transaction)
+            .await
+            .with_context(|| format!("failed to simulate the publish of {}", package.name))?
+            .into_inner();
+        let Some(simulated) = simulated.into_iter().next() else {
+            bail!("the simulation of {} returned no transaction", package.name);
+        };
+        cost.success = simulated.info.success;
+        cost.vm_status = simulated.info.vm_status;
+        cost.gas_used = simulated.info.gas_used.0;
+        cost.octas = cost.gas_used.saturating_mul(gas_unit_price);
+        costs.push(cost);
+    }
+    Ok(CostReport::new(gas_unit_price, costs))
+}
+
+/// `report` as text: one line per package, the totals per publisher and the grand total in APT
+pub fn render_cost_report(report: &CostReport) -> String {
+    let mut rows = vec![
+        ["PACKAGE", "DEPLOYMENT", "KIND", "GAS", "COST"]
+            .map(str::to_string)
+            .to_vec(),
+    ];
+    for package in &report.packages {
+        rows.push(vec![
+            package.package.clone(),
+            package.deployment.clone(),
+            package.kind.to_string(),
+            package.gas_used.to_string(),
+            if package.success {
+                format_apt(package.octas)
+            } else {
+                format!("failed: {}", package.vm_status)
+            },
+        ]);
+    }
+    let widths = (0..rows[0].len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let mut lines = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    lines.push(String::new());
+    for publisher in &report.publishers {
+        let mut line = format!(
+            "{}: {} package(s), {} gas, {}",
+            publisher.publisher,
+            publisher.packages,
+            publisher.gas_used,
+            format_apt(publisher.octas)
+        );
+        if publisher.failed > 0 {
+            line.push_str(&format!(" ({} failed, not counted)", publisher.failed));
+        }
+        lines.push(line);
+    }
+    lines.push(format!(
+        "Total: {} gas at {} octas per unit = {}",
+        report.total_gas_used,
+        report.gas_unit_price,
+        format_apt(report.total_octas)
+    ));
+    lines.join("\n")
+}
@@ -0,0 +1,291 @@
+use crate::processor_config::{ColumnTarget, ComputedColumn, ProcessorConfig};
+use crate::processor_config_generator::COMPUTED_COLUMNS;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Lowercase SQL keywords likely to collide with column names; quoted with `adapter.quote`
+const SQL_KEYWORDS: &[&str] = &[
+    "all",
+    "and",
+    "as",
+    "by",
+    "case",
+    "from",
+    "group",
+    "limit",
+    "not",
+    "null",
+    "or",
+    "order",
+    "select",
+    "table",
+    "timestamp",
+    "to",
+    "user",
+    "when",
+    "where",
+];
+
+#[derive(Debug, Serialize)]
+struct DbtSourcesFile {
+    version: u32,
+    sources: Vec<DbtSource>,
+}
+
+#[derive(Debug, Serialize)]
+struct DbtSource {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    description: String,
+    tables: Vec<DbtNode>,
+}
+
+#[derive(Debug, Serialize)]
+struct DbtModelsFile {
+    version: u32,
+    models: Vec<DbtNode>,
+}
+
+/// Source table or staging model
+#[derive(Debug, Serialize)]
+struct DbtNode {
+    name: String,
+    description: String,
+    meta: DbtLineage,
+    columns: Vec<DbtColumn>,
+}
+
+#[derive(Debug, Serialize)]
+struct DbtLineage {
+    /// On-chain event types (`0x..::module::Event`) whose rows land in the table
+    events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DbtColumn {
+    name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    data_tests: Vec<String>,
+}
+
+/// Everything written into each table of a processor config: (table, column) -> origins
+/// (e.g. "`amount` of `0x42::position::PositionUpdatedEvent`"), and table -> event types
+type Lineage = (
+    BTreeMap<(String, String), Vec<String>>,
+    BTreeMap<String, BTreeSet<String>>,
+);
+
+fn lineage(config: &ProcessorConfig) -> Lineage {
+    let custom = &config.custom_config;
+    let mut columns: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    let mut tables: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut record = |target: &ColumnTarget, origin: String, event: Option<&String>| {
+        columns
+            .entry((target.table.clone(), target.column.clone()))
+            .or_default()
+            .push(origin);
+        if let Some(event) = event {
+            tables
+                .entry(target.table.clone())
+                .or_default()
+                .insert(event.clone());
+        }
+    };
+    for (event, mapping) in &custom.events {
+        for (field, targets) in &mapping.event_fields {
+            let origin = if field == "$" {
+                format!("whole `{}` event as JSON", event)
+            } else {
+                format!("`{}` of `{}`", field, event)
+            };
+            for target in targets {
+                record(target, origin.clone(), Some(event));
+            }
+        }
+        for (field, targets) in &mapping.event_metadata {
+            for target in targets {
+                record(
+                    target,
+                    format!("event `{}` of `{}`", field, event),
+                    Some(event),
+                );
+            }
+        }
+        for constant in &mapping.constant_values {
+            let target = ColumnTarget {
+                column: constant.column.clone(),
+                table: constant.table.clone(),
+            };
+            let value = serde_yaml::to_string(&constant.value).unwrap_or_default();
+            record(
+                &target,
+                format!("constant `{}` for `{}`", value.trim(), event),
+                Some(event),
+            );
+        }
+    }
+    for (field, targets) in &custom.transaction_metadata {
+        for target in targets {
+            record(target, format!("transaction `{}`", field), None);
+        }
+    }
+    for (field, targets) in &custom.event_metadata {
+        for target in targets {
+            record(target, format!("event `{}`", field), None);
+        }
+    }
+    if let Some(computed) = custom.payload.get(COMPUTED_COLUMNS) {
+        let computed: Vec<ComputedColumn> =
+            serde_yaml::from_value(computed.clone()).unwrap_or_default();
+        for column in computed {
+            let target = ColumnTarget {
+                column: column.column.clone(),
+                table: column.table.clone(),
+            };
+            let function = serde_yaml::to_string(&column.function).unwrap_or_default();
+            record(
+                &target,
+                format!("`{}` over `{}`", function.trim(), column.event),
+                Some(&column.event),
+            );
+        }
+    }
+    (columns, tables)
+}
+
+/// Scaffold dbt sources and staging models for the tables of a processor config, documenting
+/// for every table and column the events and fields it is written from.
+///
+/// Returns the files to write, keyed by path relative to the dbt project:
+/// `models/staging/<source>/_<source>__sources.yml`, `_<source>__models.yml` and one
+/// `stg_<source>__<table>.sql` per table.
+pub fn dbt_scaffold(
+    config: &ProcessorConfig,
+    source_name: &str,
+    schema: Option<String>,
+) -> Result<BTreeMap<String, String>> {
+    let (column_lineage, table_events) = lineage(config);
+    let dir = format!("models/staging/{}", source_name);
+    let mut files = BTreeMap::new();
+    let mut source_tables = Vec::new();
+    let mut models = Vec::new();
+
+    for (table, table_schema) in &config.custom_config.db_schema {
+        let events = table_events
+            .get(table)
+            .map(|events| events.iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let description = if events.is_empty() {
+            "Indexed table with no event mapped to it".to_string()
+        } else {
+            format!("Indexed from {}", events.join(", "))
+        };
+        let primary_key = table_schema
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .count();
+        let columns = table_schema
+            .iter()
+            .map(|(column, spec)| {
+                let mut data_tests = Vec::new();
+                if spec.is_primary_key {
+                    data_tests.push("not_null".to_string());
+                    if primary_key == 1 {
+                        data_tests.push("unique".to_string());
+                    }
+                }
+                DbtColumn {
+                    name: column.clone(),
+                    description: column_lineage
+                        .get(&(table.clone(), column.clone()))
+                        .map(|origins| origins.join("; "))
+                        .unwrap_or_default(),
+                    data_tests,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let model = format!("stg_{}__{}", source_name, table);
+        files.insert(
+            format!("{}/{}.sql", dir, model),
+            staging_model_sql(source_name, table, table_schema.keys(), &events),
+        );
+        models.push(DbtNode {
+            name: model,
+            description: description.clone(),
+            meta: DbtLineage {
+                events: events.clone(),
+            },
+            columns: columns.clone(),
+        });
+        source_tables.push(DbtNode {
+            name: table.clone(),
+            description,
+            meta: DbtLineage { events },
+            columns,
+        });
+    }
+
+    let sources = DbtSourcesFile {
+        version: 2,
+        sources: vec![DbtSource {
+            name: source_name.to_string(),
+            schema,
+            description: format!(
+                "Tables written by the {} processor",
+                config.spec_identifier.spec_name
+            ),
+            tables: source_tables,
+        }],
+    };
+    files.insert(
+        format!("{}/_{}__sources.yml", dir, source_name),
+        serde_yaml::to_string(&sources).context("failed to serialize dbt sources")?,
+    );
+    files.insert(
+        format!("{}/_{}__models.yml", dir, source_name),
+        serde_yaml::to_string(&DbtModelsFile { version: 2, models })
+            .context("failed to serialize dbt models")?,
+    );
+    Ok(files)
+}
+
+fn staging_model_sql<'a>(
+    source_name: &str,
+    table: &str,
+    columns: impl Iterator<Item = &'a String>,
+    events: &[String],
+) -> String {
+    let mut sql = String::new();
+    for event in events {
+        sql.push_str(&format!("-- written from {}\n", event));
+    }
+    let columns = columns
+        .map(String::as_str)
+        .map(dbt_identifier)
+        .collect::<Vec<_>>();
+    sql.push_str(&format!(
+        "with source as (\n\n    select * from {{{{ source('{}', '{}') }}}}\n\n)\n\nselect\n    {}\nfrom source\n",
+        source_name,
+        table,
+        columns.join(",\n    ")
+    ));
+    sql
+}
+
+/// Plain lowercase identifiers as is, anything else through the adapter's quoting
+fn dbt_identifier(name: &str) -> String {
+    let plain = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if plain && !SQL_KEYWORDS.contains(&name) {
+        name.to_string()
+    } else {
+        format!("{{{{ adapter.quote('{}') }}}}", name)
+    }
+}
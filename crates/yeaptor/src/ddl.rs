@@ -0,0 +1,580 @@
+use crate::db_schema::{
+    SchemaSnapshot, is_decimal, validate_table_indexes, validate_table_partitioning,
+};
+use crate::processor_config::{
+    ColumnSpec, IndexDefinition, IndexMethod, PartitionDefinition, PartitionInterval,
+    PartitionPeriod, TableSchema,
+};
+use anyhow::{Context, Result, bail};
+use serde_yaml::Value as YamlValue;
+
+/// Render `CREATE TABLE` and `CREATE INDEX` statements for Postgres.
+///
+/// Columns are `NOT NULL` unless `is_nullable` or `is_option`; `is_vec` columns become arrays.
+/// Statements use `IF NOT EXISTS` so the output can be applied to an already provisioned database.
+/// Partitioned tables are `PARTITION BY RANGE` with a `DEFAULT` partition catching rows until range
+/// partitions of the declared interval are attached.
+pub fn postgres_ddl(schema: &SchemaSnapshot) -> Result<String> {
+    let mut out = String::new();
+    for (table, columns) in &schema.tables {
+        out.push_str(&postgres_create_table(
+            table,
+            columns,
+            schema.indexes(table),
+            schema.partition_by(table),
+        )?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Render the statements migrating a database from the `previous` schema to `current`:
+/// dropped and created tables, dropped, added and altered columns, primary key and index changes.
+/// Swapping the arguments gives the reverse migration.
+pub fn postgres_migration(previous: &SchemaSnapshot, current: &SchemaSnapshot) -> Result<String> {
+    let mut out = String::new();
+    for table in previous
+        .tables
+        .keys()
+        .filter(|t| !current.tables.contains_key(*t))
+    {
+        out.push_str(&format!(
+            "DROP TABLE IF EXISTS {};\n",
+            quote_identifier(table)
+        ));
+    }
+    for (table, schema) in &current.tables {
+        let Some(old_schema) = previous.tables.get(table) else {
+            out.push_str(&postgres_create_table(
+                table,
+                schema,
+                current.indexes(table),
+                current.partition_by(table),
+            )?);
+            continue;
+        };
+        validate_table_indexes(table, schema, current.indexes(table))?;
+        if previous.partition_by(table) != current.partition_by(table) {
+            bail!(
+                "changing the partitioning of table {} requires recreating it",
+                table
+            );
+        }
+        let quoted_table = quote_identifier(table);
+        let (old_indexes, indexes) = (previous.indexes(table), current.indexes(table));
+        for index in old_indexes.iter().filter(|i| !indexes.contains(i)) {
+            out.push_str(&format!(
+                "DROP INDEX IF EXISTS {};\n",
+                quote_identifier(&index.index_name(table))
+            ));
+        }
+        for (column, spec) in old_schema {
+            let still_indexed = schema.get(column).is_some_and(has_secondary_index);
+            if has_secondary_index(spec) && !still_indexed {
+                out.push_str(&format!(
+                    "DROP INDEX IF EXISTS {};\n",
+                    quote_identifier(&index_name(table, column))
+                ));
+            }
+        }
+        let primary_key = primary_key_columns(schema);
+        let pk_changed = primary_key != primary_key_columns(old_schema);
+        if pk_changed && !primary_key_columns(old_schema).is_empty() {
+            out.push_str(&format!(
+                "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};\n",
+                quoted_table,
+                quote_identifier(&format!("{}_pkey", table))
+            ));
+        }
+        for column in old_schema.keys().filter(|c| !schema.contains_key(*c)) {
+            out.push_str(&format!(
+                "ALTER TABLE {} DROP COLUMN {};\n",
+                quoted_table,
+                quote_identifier(column)
+            ));
+        }
+        for (column, spec) in schema {
+            let context = || format!("column {}::{}", table, column);
+            let new_column = PostgresColumn::new(spec).with_context(context)?;
+            let Some(old_spec) = old_schema.get(column) else {
+                out.push_str(&format!(
+                    "ALTER TABLE {} ADD COLUMN {};\n",
+                    quoted_table,
+                    new_column.render(column)
+                ));
+                continue;
+            };
+            let old_column = PostgresColumn::new(old_spec).with_context(context)?;
+            let alter = format!(
+                "ALTER TABLE {} ALTER COLUMN {}",
+                quoted_table,
+                quote_identifier(column)
+            );
+            if old_column.sql_type != new_column.sql_type {
+                out.push_str(&format!(
+                    "{} TYPE {} USING {}::{};\n",
+                    alter,
+                    new_column.sql_type,
+                    quote_identifier(column),
+                    new_column.sql_type
+                ));
+            }
+            if old_column.not_null != new_column.not_null {
+                let action = if new_column.not_null { "SET" } else { "DROP" };
+                out.push_str(&format!("{} {} NOT NULL;\n", alter, action));
+            }
+            if old_column.default != new_column.default {
+                match &new_column.default {
+                    Some(default) => out.push_str(&format!("{} SET DEFAULT {};\n", alter, default)),
+                    None => out.push_str(&format!("{} DROP DEFAULT;\n", alter)),
+                }
+            }
+        }
+        if pk_changed && !primary_key.is_empty() {
+            out.push_str(&format!(
+                "ALTER TABLE {} ADD PRIMARY KEY ({});\n",
+                quoted_table,
+                primary_key.join(", ")
+            ));
+        }
+        for (column, spec) in schema {
+            let was_indexed = old_schema.get(column).is_some_and(has_secondary_index);
+            if has_secondary_index(spec) && !was_indexed {
+                out.push_str(&create_index(table, column));
+            }
+        }
+        for index in indexes.iter().filter(|i| !old_indexes.contains(i)) {
+            out.push_str(&create_declared_index(table, index));
+        }
+    }
+    Ok(out)
+}
+
+/// When the only change from `previous` to `current` is new nullable columns (indexed or not),
+/// render it as idempotent `ADD COLUMN IF NOT EXISTS` statements with their reverse, which are safe
+/// to apply to a live table. Returns None for any other change.
+pub fn postgres_nullable_additions(
+    previous: &SchemaSnapshot,
+    current: &SchemaSnapshot,
+) -> Result<Option<(String, String)>> {
+    if !previous.tables.keys().eq(current.tables.keys()) {
+        return Ok(None);
+    }
+    let (mut up, mut down) = (String::new(), String::new());
+    for (table, schema) in &current.tables {
+        let old_schema = &previous.tables[table];
+        let unchanged = old_schema
+            .iter()
+            .all(|(column, spec)| schema.get(column) == Some(spec))
+            && previous.indexes(table) == current.indexes(table)
+            && previous.partition_by(table) == current.partition_by(table);
+        if !unchanged {
+            return Ok(None);
+        }
+        let quoted_table = quote_identifier(table);
+        for (column, spec) in schema.iter().filter(|(c, _)| !old_schema.contains_key(*c)) {
+            let column_sql = PostgresColumn::new(spec)
+                .with_context(|| format!("column {}::{}", table, column))?;
+            if column_sql.not_null {
+                return Ok(None);
+            }
+            up.push_str(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {};\n",
+                quoted_table,
+                column_sql.render(column)
+            ));
+            if has_secondary_index(spec) {
+                up.push_str(&create_index(table, column));
+                down.push_str(&format!(
+                    "DROP INDEX IF EXISTS {};\n",
+                    quote_identifier(&index_name(table, column))
+                ));
+            }
+            down.push_str(&format!(
+                "ALTER TABLE {} DROP COLUMN IF EXISTS {};\n",
+                quoted_table,
+                quote_identifier(column)
+            ));
+        }
+    }
+    Ok(Some((up, down)))
+}
+
+/// `CREATE TABLE` for one table (and its default partition) followed by the `CREATE INDEX` of each
+/// secondary index, then of each declared index
+pub fn postgres_create_table(
+    table: &str,
+    schema: &TableSchema,
+    indexes: &[IndexDefinition],
+    partition_by: Option<&PartitionDefinition>,
+) -> Result<String> {
+    validate_table_indexes(table, schema, indexes)?;
+    if let Some(partition_by) = partition_by {
+        validate_table_partitioning(table, schema, indexes, partition_by)?;
+    }
+    let mut lines = Vec::new();
+    for (column, spec) in schema {
+        let column_sql =
+            PostgresColumn::new(spec).with_context(|| format!("column {}::{}", table, column))?;
+        lines.push(column_sql.render(column));
+    }
+    let primary_key = primary_key_columns(schema);
+    if !primary_key.is_empty() {
+        lines.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+    }
+    let mut out = format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n    {}\n)",
+        quote_identifier(table),
+        lines.join(",\n    ")
+    );
+    match partition_by {
+        Some(partition_by) => out.push_str(&format!(
+            " PARTITION BY RANGE ({});\nCREATE TABLE IF NOT EXISTS {} PARTITION OF {} DEFAULT;\n",
+            quote_identifier(&partition_by.column),
+            quote_identifier(&format!("{}_default", table)),
+            quote_identifier(table)
+        )),
+        None => out.push_str(";\n"),
+    }
+    for (column, spec) in schema {
+        if has_secondary_index(spec) {
+            out.push_str(&create_index(table, column));
+        }
+    }
+    for index in indexes {
+        out.push_str(&create_declared_index(table, index));
+    }
+    Ok(out)
+}
+
+fn primary_key_columns(schema: &TableSchema) -> Vec<String> {
+    schema
+        .iter()
+        .filter(|(_, spec)| spec.is_primary_key)
+        .map(|(column, _)| quote_identifier(column))
+        .collect()
+}
+
+/// Primary key columns are already indexed by the key itself
+fn has_secondary_index(spec: &ColumnSpec) -> bool {
+    spec.is_index && !spec.is_primary_key
+}
+
+fn index_name(table: &str, column: &str) -> String {
+    format!("{}_{}_idx", table, column)
+}
+
+fn create_index(table: &str, column: &str) -> String {
+    format!(
+        "CREATE INDEX IF NOT EXISTS {} ON {} ({});\n",
+        quote_identifier(&index_name(table, column)),
+        quote_identifier(table),
+        quote_identifier(column)
+    )
+}
+
+fn create_declared_index(table: &str, index: &IndexDefinition) -> String {
+    let method = match index.method {
+        Some(IndexMethod::Btree) => " USING btree",
+        Some(IndexMethod::Hash) => " USING hash",
+        Some(IndexMethod::Brin) => " USING brin",
+        None => "",
+    };
+    format!(
+        "CREATE {}INDEX IF NOT EXISTS {} ON {}{} ({});\n",
+        if index.unique { "UNIQUE " } else { "" },
+        quote_identifier(&index.index_name(table)),
+        quote_identifier(table),
+        method,
+        index
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Postgres rendering of a column: full type (arrays included), nullability and default
+#[derive(Debug, PartialEq)]
+struct PostgresColumn {
+    sql_type: String,
+    not_null: bool,
+    default: Option<String>,
+}
+
+impl PostgresColumn {
+    fn new(spec: &ColumnSpec) -> Result<Self> {
+        let mut sql_type = postgres_type(spec)?.to_string();
+        if spec.is_vec {
+            sql_type.push_str("[]");
+        }
+        let default = match &spec.default_value {
+            Some(value) => Some(postgres_default(spec, value, &sql_type)?),
+            None => None,
+        };
+        Ok(Self {
+            sql_type,
+            not_null: spec.is_primary_key || !(spec.is_nullable || spec.is_option),
+            default,
+        })
+    }
+
+    fn render(&self, column: &str) -> String {
+        let mut line = format!("{} {}", quote_identifier(column), self.sql_type);
+        if self.not_null {
+            line.push_str(" NOT NULL");
+        }
+        if let Some(default) = &self.default {
+            line.push_str(&format!(" DEFAULT {}", default));
+        }
+        line
+    }
+}
+
+/// Scalar Postgres type of a column. Unsigned Move integers use the next wider signed type,
+/// or NUMERIC once BIGINT no longer fits; `decimal` columns (scaled integers) are unconstrained
+/// NUMERIC.
+pub(crate) fn postgres_type(spec: &ColumnSpec) -> Result<&'static str> {
+    let column_type = spec.column_type.column_type.as_str();
+    Ok(match (spec.column_type.r#type.as_str(), column_type) {
+        ("move_type", "u8") => "SMALLINT",
+        ("move_type", "u16") => "INTEGER",
+        ("move_type", "u32") => "BIGINT",
+        ("move_type", "u64") => "NUMERIC(20, 0)",
+        ("move_type", "u128") => "NUMERIC(39, 0)",
+        ("move_type", "u256") => "NUMERIC(78, 0)",
+        ("move_type", "decimal") => "NUMERIC",
+        ("move_type", "bool") => "BOOLEAN",
+        ("move_type", "address" | "object") => "VARCHAR(66)",
+        ("move_type", "string") => "TEXT",
+        ("transaction_metadata", "version" | "block_height" | "epoch") => "BIGINT",
+        ("transaction_metadata", "timestamp") => "TIMESTAMP",
+        ("event_metadata", "creation_number" | "sequence_number" | "event_index") => "BIGINT",
+        ("event_metadata", "account_address") => "VARCHAR(66)",
+        ("event_metadata", "event_type") => "TEXT",
+        (kind, _) => bail!("no Postgres type for {} {}", kind, column_type),
+    })
+}
+
+fn postgres_default(spec: &ColumnSpec, value: &YamlValue, sql_type: &str) -> Result<String> {
+    match value {
+        YamlValue::Null => Ok("NULL".to_string()),
+        YamlValue::Sequence(items) => {
+            let items = items
+                .iter()
+                .map(|item| postgres_literal(spec, item))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("ARRAY[{}]::{}", items.join(", "), sql_type))
+        }
+        _ => postgres_literal(spec, value),
+    }
+}
+
+fn postgres_literal(spec: &ColumnSpec, value: &YamlValue) -> Result<String> {
+    Ok(match literal(spec, value)? {
+        Literal::Number(n) => n,
+        Literal::Bool(b) => if b { "TRUE" } else { "FALSE" }.to_string(),
+        Literal::Text(t) => format!("'{}'", t.replace('\'', "''")),
+    })
+}
+
+/// Scalar default value, typed by its column
+enum Literal {
+    Number(String),
+    Bool(bool),
+    Text(String),
+}
+
+fn literal(spec: &ColumnSpec, value: &YamlValue) -> Result<Literal> {
+    let text = match value {
+        YamlValue::Bool(b) => b.to_string(),
+        YamlValue::Number(n) => n.to_string(),
+        YamlValue::String(s) => s.clone(),
+        other => bail!("unsupported default value {:?}", other),
+    };
+    let is_numeric = !text.is_empty() && text.chars().all(|c| c.is_ascii_digit());
+    Ok(match spec.column_type.column_type.as_str() {
+        "bool" => match text.to_ascii_lowercase().as_str() {
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            _ => bail!("invalid bool default {}", text),
+        },
+        // wide integers are stored as strings in the schema
+        "u8" | "u16" | "u32" | "u64" | "u128" | "u256" if is_numeric => Literal::Number(text),
+        "decimal" if is_decimal(&text) => Literal::Number(text),
+        _ if spec.column_type.r#type != "move_type" && is_numeric => Literal::Number(text),
+        _ => Literal::Text(text),
+    })
+}
+
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// ===================== ClickHouse =====================
+
+/// Render ClickHouse `CREATE TABLE` statements.
+///
+/// Tables with a primary key use `ReplacingMergeTree` ordered by it, so replayed rows collapse
+/// like upserts; other tables use `MergeTree` ordered by their non-nullable indexed columns.
+/// Other indexed columns get a `bloom_filter` data-skipping index. Declared indexes become
+/// data-skipping indexes too, `minmax` for `brin` and `bloom_filter` otherwise; ClickHouse has no
+/// unique constraints, so `unique` is not enforced. Partitioned tables get a `PARTITION BY` on the
+/// period (`toYYYYMM(..)`) or value range (`intDiv(.., interval)`) of their partition column.
+pub fn clickhouse_ddl(snapshot: &SchemaSnapshot) -> Result<String> {
+    let mut out = String::new();
+    for (table, schema) in &snapshot.tables {
+        let indexes = snapshot.indexes(table);
+        validate_table_indexes(table, schema, indexes)?;
+        let partition_by = match snapshot.partition_by(table) {
+            Some(partition_by) => {
+                validate_table_partitioning(table, schema, indexes, partition_by)?;
+                format!("\nPARTITION BY {}", clickhouse_partition_key(partition_by))
+            }
+            None => String::new(),
+        };
+        let mut lines = Vec::new();
+        for (column, spec) in schema {
+            let context = || format!("column {}::{}", table, column);
+            let mut line = format!(
+                "{} {}",
+                clickhouse_identifier(column),
+                clickhouse_column_type(spec).with_context(context)?
+            );
+            if let Some(value) = &spec.default_value {
+                let default = clickhouse_default(spec, value).with_context(context)?;
+                line.push_str(&format!(" DEFAULT {}", default));
+            }
+            lines.push(line);
+        }
+        let primary_key = schema
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .map(|(column, _)| clickhouse_identifier(column))
+            .collect::<Vec<_>>();
+        let (engine, order_by) = if primary_key.is_empty() {
+            let indexed = schema
+                .iter()
+                .filter(|(_, spec)| spec.is_index && !spec.is_vec && !is_nullable(spec))
+                .map(|(column, _)| clickhouse_identifier(column))
+                .collect::<Vec<_>>();
+            ("MergeTree", indexed)
+        } else {
+            for (column, spec) in schema {
+                if has_secondary_index(spec) {
+                    lines.push(format!(
+                        "INDEX {} {} TYPE bloom_filter GRANULARITY 4",
+                        clickhouse_identifier(&index_name(table, column)),
+                        clickhouse_identifier(column)
+                    ));
+                }
+            }
+            ("ReplacingMergeTree", primary_key)
+        };
+        for index in indexes {
+            let index_type = match index.method {
+                Some(IndexMethod::Brin) => "minmax",
+                _ => "bloom_filter",
+            };
+            let columns = index
+                .columns
+                .iter()
+                .map(|column| clickhouse_identifier(column))
+                .collect::<Vec<_>>();
+            let expression = match columns.as_slice() {
+                [column] => column.clone(),
+                columns => format!("({})", columns.join(", ")),
+            };
+            lines.push(format!(
+                "INDEX {} {} TYPE {} GRANULARITY 4",
+                clickhouse_identifier(&index.index_name(table)),
+                expression,
+                index_type
+            ));
+        }
+        let order_by = if order_by.is_empty() {
+            "tuple()".to_string()
+        } else {
+            format!("({})", order_by.join(", "))
+        };
+        out.push_str(&format!(
+            "CREATE TABLE IF NOT EXISTS {}\n(\n    {}\n)\nENGINE = {}{}\nORDER BY {};\n\n",
+            clickhouse_identifier(table),
+            lines.join(",\n    "),
+            engine,
+            partition_by,
+            order_by
+        ));
+    }
+    Ok(out)
+}
+
+fn clickhouse_partition_key(partition_by: &PartitionDefinition) -> String {
+    let column = clickhouse_identifier(&partition_by.column);
+    match partition_by.interval {
+        PartitionInterval::Period(PartitionPeriod::Day) => format!("toYYYYMMDD({})", column),
+        PartitionInterval::Period(PartitionPeriod::Week) => format!("toMonday({})", column),
+        PartitionInterval::Period(PartitionPeriod::Month) => format!("toYYYYMM({})", column),
+        PartitionInterval::Period(PartitionPeriod::Year) => format!("toYear({})", column),
+        PartitionInterval::Values(interval) => format!("intDiv({}, {})", column, interval),
+    }
+}
+
+fn is_nullable(spec: &ColumnSpec) -> bool {
+    !spec.is_primary_key && (spec.is_nullable || spec.is_option)
+}
+
+/// ClickHouse cannot wrap arrays in `Nullable`, so nullable vectors are plain (possibly empty) arrays
+fn clickhouse_column_type(spec: &ColumnSpec) -> Result<String> {
+    let column_type = spec.column_type.column_type.as_str();
+    let scalar = match (spec.column_type.r#type.as_str(), column_type) {
+        ("move_type", "u8") => "UInt8",
+        ("move_type", "u16") => "UInt16",
+        ("move_type", "u32") => "UInt32",
+        ("move_type", "u64") => "UInt64",
+        ("move_type", "u128") => "UInt128",
+        ("move_type", "u256") => "UInt256",
+        ("move_type", "decimal") => "Decimal256(38)",
+        ("move_type", "bool") => "Bool",
+        ("move_type", "address" | "object" | "string") => "String",
+        ("transaction_metadata", "version" | "block_height" | "epoch") => "UInt64",
+        ("transaction_metadata", "timestamp") => "DateTime64(6)",
+        ("event_metadata", "creation_number" | "sequence_number" | "event_index") => "UInt64",
+        ("event_metadata", "account_address" | "event_type") => "String",
+        (kind, _) => bail!("no ClickHouse type for {} {}", kind, column_type),
+    };
+    Ok(if spec.is_vec {
+        format!("Array({})", scalar)
+    } else if is_nullable(spec) {
+        format!("Nullable({})", scalar)
+    } else {
+        scalar.to_string()
+    })
+}
+
+fn clickhouse_default(spec: &ColumnSpec, value: &YamlValue) -> Result<String> {
+    match value {
+        YamlValue::Null => Ok("NULL".to_string()),
+        YamlValue::Sequence(items) => {
+            let items = items
+                .iter()
+                .map(|item| clickhouse_literal(spec, item))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("[{}]", items.join(", ")))
+        }
+        _ => clickhouse_literal(spec, value),
+    }
+}
+
+fn clickhouse_literal(spec: &ColumnSpec, value: &YamlValue) -> Result<String> {
+    Ok(match literal(spec, value)? {
+        Literal::Number(n) => n,
+        Literal::Bool(b) => b.to_string(),
+        Literal::Text(t) => format!("'{}'", t.replace('\\', "\\\\").replace('\'', "\\'")),
+    })
+}
+
+fn clickhouse_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('`', "\\`"))
+}
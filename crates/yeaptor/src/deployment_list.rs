@@ -0,0 +1,212 @@
+use crate::audit::upgrade_policy_name;
+use crate::config::{DeploymentMode, YeaptorConfig};
+use crate::diagnostics::Diagnostics;
+use crate::preflight::fetch_published_packages;
+use anyhow::Result;
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_rest_client::Client;
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use yeaptor_core::move_manifest::load_package_name;
+
+/// One package of a deployment of yeaptor.toml, with the account it publishes to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageListing {
+    /// `deployments[i]`
+    pub location: String,
+    /// Output subdirectory of the deployment (`Deployment::dir_name`)
+    pub deployment: String,
+    pub mode: DeploymentMode,
+    /// `address_name` of the package
+    pub package: String,
+    /// Move package name from its Move.toml, which it is published under; `None` when the
+    /// manifest cannot be read
+    pub name: Option<String>,
+    pub path: PathBuf,
+    /// The deployment's `publisher`, as written in yeaptor.toml
+    pub publisher_name: String,
+    pub publisher: String,
+    /// Empty unless `DeploymentMode::ResourceAccount`
+    pub seed: String,
+    /// Account the package is published to
+    pub account: String,
+    /// On-chain state, when looked up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<PublishStatus>,
+}
+
+/// Whether a package is published at its account, and at which upgrade
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PublishStatus {
+    pub published: bool,
+    /// 0 for the first publish
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upgrade_number: Option<u64>,
+    /// `arbitrary`, `compatible` or `immutable`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upgrade_policy: Option<String>,
+}
+
+impl PublishStatus {
+    /// Status of the package `name` among the packages published at its account
+    pub fn of(name: Option<&str>, published: &[PackageMetadata]) -> Self {
+        match published
+            .iter()
+            .find(|metadata| Some(metadata.name.as_str()) == name)
+        {
+            Some(metadata) => PublishStatus {
+                published: true,
+                upgrade_number: Some(metadata.upgrade_number),
+                upgrade_policy: Some(
+                    upgrade_policy_name(metadata.upgrade_policy.policy).to_string(),
+                ),
+            },
+            None => PublishStatus {
+                published: false,
+                upgrade_number: None,
+                upgrade_policy: None,
+            },
+        }
+    }
+}
+
+/// Every package of the deployments of `config` selected by `tags` (all when empty), in
+/// deployment order, from the config alone
+pub fn list_packages(config: &YeaptorConfig, tags: &[String]) -> Result<Vec<PackageListing>> {
+    let mut diagnostics = Diagnostics::default();
+    let mut listings = Vec::new();
+    for (i, deployment) in config.deployments.iter().enumerate() {
+        let location = format!("deployments[{}]", i);
+        let Some(publisher) = config.publisher_address(&deployment.publisher) else {
+            diagnostics.push(
+                &location,
+                format!(
+                    "publisher `{}` is neither in [publishers] nor an address",
+                    deployment.publisher
+                ),
+            );
+            continue;
+        };
+        let account = deployment.account(&publisher);
+        for package in deployment
+            .packages
+            .iter()
+            .filter(|package| deployment.selects(package, tags))
+        {
+            listings.push(PackageListing {
+                location: location.clone(),
+                deployment: deployment.dir_name(),
+                mode: deployment.mode(),
+                package: package.address_name.clone(),
+                name: load_package_name(&package.path).ok().flatten(),
+                path: package.path.clone(),
+                publisher_name: deployment.publisher.clone(),
+                publisher: publisher.to_standard_string(),
+                seed: deployment.seed().to_string(),
+                account: account.to_standard_string(),
+                status: None,
+            });
+        }
+    }
+    diagnostics.into_result()?;
+    Ok(listings)
+}
+
+/// Set the `status` of every listing from the packages published at its account, reading each
+/// account once
+pub async fn fetch_publish_status(client: &Client, listings: &mut [PackageListing]) -> Result<()> {
+    let mut published: BTreeMap<String, Vec<PackageMetadata>> = BTreeMap::new();
+    for listing in listings.iter_mut() {
+        if !published.contains_key(&listing.account) {
+            let address = AccountAddress::from_hex_literal(&listing.account)?;
+            published.insert(
+                listing.account.clone(),
+                fetch_published_packages(client, address).await?,
+            );
+        }
+        listing.status = Some(PublishStatus::of(
+            listing.name.as_deref(),
+            &published[&listing.account],
+        ));
+    }
+    Ok(())
+}
+
+/// `listings` as a table with aligned columns: deployment, package, publisher, seed, account and,
+/// when looked up, the publish status
+pub fn render_package_table(listings: &[PackageListing]) -> String {
+    let with_status = listings.iter().any(|listing| listing.status.is_some());
+    let mut rows = vec![
+        [
+            "DEPLOYMENT",
+            "MODE",
+            "PACKAGE",
+            "PUBLISHER",
+            "SEED",
+            "ACCOUNT",
+            "STATUS",
+        ]
+        .map(str::to_string)
+        .to_vec(),
+    ];
+    for listing in listings {
+        let package = match &listing.name {
+            Some(name) if *name != listing.package => format!("{} ({})", listing.package, name),
+            _ => listing.package.clone(),
+        };
+        let status = match &listing.status {
+            Some(PublishStatus {
+                published: true,
+                upgrade_number,
+                upgrade_policy,
+            }) => format!(
+                "published, upgrade {}, {}",
+                upgrade_number.unwrap_or_default(),
+                upgrade_policy.as_deref().unwrap_or("-")
+            ),
+            Some(_) => "not published".to_string(),
+            None => String::new(),
+        };
+        rows.push(vec![
+            listing.deployment.clone(),
+            listing.mode.to_string(),
+            package,
+            format!("{} ({})", listing.publisher_name, listing.publisher),
+            if listing.seed.is_empty() {
+                "-".to_string()
+            } else {
+                listing.seed.clone()
+            },
+            listing.account.clone(),
+            status,
+        ]);
+    }
+    if !with_status {
+        for row in &mut rows {
+            row.pop();
+        }
+    }
+    let columns = rows[0].len();
+    let widths = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,666 @@
+use crate::config::{DeployFunction, DeploymentMode, PolicySettings, YeaptorConfig};
+use crate::diagnostics::Diagnostics;
+use crate::env::{BuiltDeployment, YeaptorEnv};
+use crate::error::{YeaptorError, YeaptorResult};
+use crate::event_definition::EventDefinition;
+use crate::event_extractor::EventExtractor;
+use crate::lockfile::DependencyHashes;
+use crate::metadata_normalization::normalize_metadata;
+use crate::module_verifier::verify_modules;
+use anyhow::{Context, anyhow};
+use aptos::common::types::MovePackageOptions;
+use aptos::move_tool::IncludedArtifacts;
+use aptos_framework::BuiltPackage;
+use aptos_framework::docgen::DocgenOptions;
+use aptos_types::account_address::AccountAddress;
+use indicatif::ProgressBar;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use serde_json::json;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use yeaptor_core::move_manifest::{load_git_dependencies, local_dependency_dirs};
+
+/// Plans the deployments of a yeaptor.toml without writing anything: derives the resource account
+/// of every deployment, builds each package with the named addresses resolved to them, verifies
+/// its modules as the network would on publish (`verify_modules`), and renders the
+/// `ra_code_deployment::deploy` payloads in deployment order.
+///
+/// ```ignore
+/// let plan = DeploymentPlanner::new(load_config(path)?, MovePackageOptions::new())
+///     .with_events(true)
+///     .plan()?;
+/// for package in &plan.packages {
+///     println!("{} -> {}", package.file_name(), package.payload);
+/// }
+/// ```
+pub struct DeploymentPlanner {
+    env: YeaptorEnv,
+    move_options: MovePackageOptions,
+    included_artifacts: IncludedArtifacts,
+    docgen_options: Option<DocgenOptions>,
+    with_events: bool,
+    normalize_metadata: bool,
+    tags: Vec<String>,
+    progress: ProgressBar,
+}
+
+/// Output of `DeploymentPlanner::plan`
+pub struct DeploymentPlan {
+    pub packages: Vec<PlannedPackage>,
+    /// Named addresses of the build, including every package's resource account
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// Content hashes of the dependency packages fetched from elsewhere (git and framework
+    /// dependencies), by package name, as pinned in yeaptor.lock
+    pub dependencies: BTreeMap<String, DependencyHashes>,
+}
+
+/// A built package ready to publish
+pub struct PlannedPackage {
+    /// Position in the deployment order of yeaptor.toml
+    pub order: usize,
+    /// Output subdirectory of its deployment (`Deployment::dir_name`)
+    pub deployment: String,
+    pub name: String,
+    pub mode: DeploymentMode,
+    pub publisher: AccountAddress,
+    /// Empty unless `DeploymentMode::ResourceAccount`
+    pub seed: String,
+    /// Account the package is published to: the resource account, the publisher or the code
+    /// object, by `mode`
+    pub account: AccountAddress,
+    /// Upgrades the existing code object `account` rather than creating one
+    /// (`DeploymentMode::Object` with `object_address`)
+    pub object_upgrade: bool,
+    /// Entry function JSON for `aptos move run --json-file`: `ra_code_deployment::deploy`,
+    /// `0x1::code::publish_package_txn` with `DeploymentMode::Account`, or
+    /// `0x1::object_code_deployment::{publish, upgrade}` with `DeploymentMode::Object`
+    pub payload: serde_json::Value,
+    /// BCS-serialized `PackageMetadata`, for payload formats of your own
+    pub metadata: Vec<u8>,
+    /// Compiled modules in publishing order
+    pub modules: Vec<Vec<u8>>,
+    /// Event definitions of the package; empty unless `with_events` is set
+    pub events: Vec<EventDefinition>,
+}
+
+/// Size limit of a regular (non-governance) transaction on Aptos networks, in bytes of the raw
+/// transaction
+pub const MAX_TRANSACTION_SIZE: usize = 64 * 1024;
+
+/// Bytes of a raw transaction besides its payload: sender, sequence number, max gas amount, gas
+/// unit price, expiration and chain id
+const RAW_TRANSACTION_OVERHEAD: usize = 32 + 8 + 8 + 8 + 8 + 1;
+
+impl PlannedPackage {
+    /// `<order>-<package>.package.json`
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.package.json", self.order, self.name)
+    }
+
+    /// `<package>.package.json`, for `payload_naming = "stable"`
+    pub fn stable_file_name(&self) -> String {
+        format!("{}.package.json", self.name)
+    }
+
+    /// Size of the raw transaction publishing this package (`payload`), as compared with
+    /// `MAX_TRANSACTION_SIZE`; `deploy` is the entry function of resource account deployments
+    pub fn transaction_size(&self, deploy: &DeployFunction) -> usize {
+        match self.mode {
+            DeploymentMode::ResourceAccount => {
+                publish_transaction_size(deploy, &self.seed, &self.metadata, &self.modules)
+            }
+            DeploymentMode::Account => code_publish_transaction_size(&self.metadata, &self.modules),
+            DeploymentMode::Object => object_code_transaction_size(
+                &self.metadata,
+                &self.modules,
+                self.object_upgrade.then_some(self.account),
+            ),
+        }
+    }
+}
+
+impl DeploymentPlan {
+    /// `[addresses]` table of the resolved named addresses
+    pub fn addresses_toml(&self) -> String {
+        let mut addresses_toml = String::from("[addresses]\n");
+        for (name, addr) in &self.named_addresses {
+            addresses_toml.push_str(&format!("{} = \"{}\"\n", name, addr.to_standard_string()));
+        }
+        addresses_toml
+    }
+
+    /// The resolved named addresses as a JSON object, name to address
+    pub fn addresses_json(&self) -> String {
+        let addresses = self
+            .named_addresses
+            .iter()
+            .map(|(name, addr)| (name.clone(), addr.to_standard_string()))
+            .collect::<BTreeMap<_, _>>();
+        format!(
+            "{}\n",
+            serde_json::to_string_pretty(&addresses).expect("a map of strings serializes")
+        )
+    }
+
+    /// A `.env` file with one `<NAME>_ADDRESS=0x...` line per named address
+    pub fn addresses_env(&self) -> String {
+        let mut env = String::new();
+        for (name, addr) in &self.named_addresses {
+            env.push_str(&format!(
+                "{}={}\n",
+                address_constant_name(name),
+                addr.to_standard_string()
+            ));
+        }
+        env
+    }
+
+    /// A TypeScript module exporting one `<NAME>_ADDRESS` constant per named address
+    pub fn addresses_typescript(&self) -> String {
+        let mut module = String::from("// Generated by yeaptor; do not edit.\n\n");
+        for (name, addr) in &self.named_addresses {
+            module.push_str(&format!(
+                "export const {} = \"{}\" as const;\n",
+                address_constant_name(name),
+                addr.to_standard_string()
+            ));
+        }
+        module
+    }
+}
+
+/// `<NAME>_ADDRESS` for the named address `name`: upper-cased, with characters other than ASCII
+/// letters and digits replaced by `_` (`lending_core` → `LENDING_CORE_ADDRESS`)
+pub fn address_constant_name(name: &str) -> String {
+    let mut constant: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if constant.starts_with(|c: char| c.is_ascii_digit()) {
+        constant.insert(0, '_');
+    }
+    constant.push_str("_ADDRESS");
+    constant
+}
+
+impl DeploymentPlanner {
+    pub fn new(config: YeaptorConfig, move_options: MovePackageOptions) -> Self {
+        DeploymentPlanner {
+            env: YeaptorEnv::new(config),
+            move_options,
+            included_artifacts: IncludedArtifacts::Sparse,
+            docgen_options: None,
+            with_events: false,
+            normalize_metadata: false,
+            tags: Vec::new(),
+            progress: ProgressBar::hidden(),
+        }
+    }
+
+    /// Artifacts included in packages whose deployment does not set `include_artifacts`
+    /// (default: sparse)
+    pub fn included_artifacts(mut self, included_artifacts: IncludedArtifacts) -> Self {
+        self.included_artifacts = included_artifacts;
+        self
+    }
+
+    /// Generate documentation while building
+    pub fn docgen_options(mut self, docgen_options: Option<DocgenOptions>) -> Self {
+        self.docgen_options = docgen_options;
+        self
+    }
+
+    /// Also extract the event definitions of every package
+    pub fn with_events(mut self, with_events: bool) -> Self {
+        self.with_events = with_events;
+        self
+    }
+
+    /// Strip what differs between checkouts from the package metadata before serializing it
+    /// (`normalize_metadata`), so that the same sources give byte-identical payloads
+    pub fn normalize_metadata(mut self, normalize_metadata: bool) -> Self {
+        self.normalize_metadata = normalize_metadata;
+        self
+    }
+
+    /// Build only the packages carrying one of `tags` themselves or through their deployment;
+    /// every package when empty. Ignored when `move_options.package_dir` is set.
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Advanced by one per package built, with the package being built as message; its length is
+    /// set by `plan`
+    pub fn progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn config(&self) -> &YeaptorConfig {
+        self.env.config()
+    }
+
+    /// Named addresses of the build, including every package's resource account; known without
+    /// building anything
+    pub fn named_addresses(&self) -> &BTreeMap<String, AccountAddress> {
+        self.env.named_addresses()
+    }
+
+    /// Build every package of the deployments selected by `tags`, or only
+    /// `move_options.package_dir` when set
+    pub fn plan(&self) -> YeaptorResult<DeploymentPlan> {
+        let built = match &self.move_options.package_dir {
+            Some(package_dir) => {
+                self.progress.set_length(1);
+                self.progress
+                    .set_message(format!("building {}", package_dir.display()));
+                let built = self.env.build_deployment_package(
+                    package_dir,
+                    &self.included_artifacts,
+                    &self.move_options,
+                    self.docgen_options.clone(),
+                )?;
+                self.progress.inc(1);
+                vec![built]
+            }
+            None => {
+                self.progress.set_length(
+                    self.config()
+                        .deployments
+                        .iter()
+                        .map(|d| {
+                            d.packages
+                                .iter()
+                                .filter(|p| d.selects(p, &self.tags))
+                                .count() as u64
+                        })
+                        .sum(),
+                );
+                self.env.build_all(
+                    &self.included_artifacts,
+                    &self.move_options,
+                    self.docgen_options.clone(),
+                    &self.tags,
+                    &self.progress,
+                )?
+            }
+        };
+        let deploy = self.env.config().deploy_function();
+        let policy = &self.env.config().policy;
+        let own_accounts = self
+            .env
+            .config()
+            .deployment_addresses()
+            .into_iter()
+            .flatten()
+            .collect::<BTreeSet<_>>();
+        let mut dependencies = BTreeMap::new();
+        let packages = built
+            .into_iter()
+            .map(|(order, deployment)| {
+                let BuiltDeployment {
+                    deployment,
+                    mode,
+                    publisher,
+                    seed,
+                    account,
+                    object_upgrade,
+                    pack,
+                } = deployment;
+                let mut metadata = pack.extract_metadata().map_err(|e| {
+                    YeaptorError::build(
+                        pack.package_path(),
+                        e.context(format!("package metadata of {} is missing", pack.name())),
+                    )
+                })?;
+                if self.normalize_metadata {
+                    normalize_metadata(&mut metadata)
+                        .map_err(|e| YeaptorError::build(pack.package_path(), e))?;
+                }
+                let metadata_serialized = bcs::to_bytes(&metadata).map_err(|e| {
+                    YeaptorError::build(
+                        pack.package_path(),
+                        anyhow!("PackageMetadata should be serializable to BCS: {}", e),
+                    )
+                })?;
+                let events = if self.with_events {
+                    EventExtractor::new()
+                        .extract(&pack)
+                        .map_err(|e| YeaptorError::build(pack.package_path(), e))?
+                } else {
+                    Vec::new()
+                };
+                let modules = pack.extract_code();
+                verify_modules(&modules)
+                    .into_result()
+                    .map_err(|e| YeaptorError::build(pack.package_path(), e))?;
+                check_dependency_policy(&pack, policy, &own_accounts)
+                    .map_err(|e| YeaptorError::build(pack.package_path(), e))?;
+                for (name, hashes) in dependency_hashes(&pack)
+                    .map_err(|e| YeaptorError::build(pack.package_path(), e))?
+                {
+                    match dependencies.get(&name) {
+                        Some(other) if other != &hashes => {
+                            return Err(YeaptorError::build(
+                                pack.package_path(),
+                                anyhow!(
+                                    "dependency `{}` of {} differs from the one of the packages \
+                                     built before it; depend on the same revision everywhere",
+                                    name,
+                                    pack.name()
+                                ),
+                            ));
+                        }
+                        Some(_) => {}
+                        None => {
+                            dependencies.insert(name, hashes);
+                        }
+                    }
+                }
+                Ok(PlannedPackage {
+                    order,
+                    deployment,
+                    name: pack.name().to_string(),
+                    mode,
+                    publisher,
+                    payload: match mode {
+                        DeploymentMode::ResourceAccount => {
+                            publish_payload_json(&deploy, &seed, &metadata_serialized, &modules)
+                        }
+                        DeploymentMode::Account => {
+                            code_publish_payload_json(&metadata_serialized, &modules)
+                        }
+                        DeploymentMode::Object => object_code_payload_json(
+                            &metadata_serialized,
+                            &modules,
+                            object_upgrade.then_some(account),
+                        ),
+                    },
+                    account,
+                    object_upgrade,
+                    metadata: metadata_serialized,
+                    modules,
+                    seed,
+                    events,
+                })
+            })
+            .collect::<YeaptorResult<Vec<_>>>()?;
+        Ok(DeploymentPlan {
+            packages,
+            named_addresses: self.env.named_addresses().clone(),
+            dependencies,
+        })
+    }
+}
+
+/// Content hashes of the dependency packages of `pack` fetched from elsewhere, by package name.
+/// Packages reached through `local` dependencies are built from the working tree and not pinned.
+/// The bytecode hash covers the compiled units and the source hash the contents of their source
+/// files, both in a sorted order independent of where the dependency was fetched to.
+pub fn dependency_hashes(
+    pack: &BuiltPackage,
+) -> anyhow::Result<BTreeMap<String, DependencyHashes>> {
+    let local = local_dependency_dirs(pack.package_path())?;
+    let mut units: BTreeMap<String, (Vec<Vec<u8>>, BTreeSet<&Path>)> = BTreeMap::new();
+    for (dep_name, unit) in &pack.package.deps_compiled_units {
+        let dep_name = dep_name.to_string();
+        if local.contains_key(&dep_name) {
+            continue;
+        }
+        let (bytecode, sources) = units.entry(dep_name).or_default();
+        bytecode.push(unit.unit.serialize(None));
+        sources.insert(unit.source_path.as_path());
+    }
+    units
+        .into_iter()
+        .map(|(name, (bytecode, sources))| {
+            let mut source_digests = sources
+                .into_iter()
+                .map(|path| {
+                    fs::read(path)
+                        .map(|source| Sha3_256::digest(source).to_vec())
+                        .with_context(|| {
+                            format!("failed to read {} of dependency `{}`", path.display(), name)
+                        })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            source_digests.sort();
+            let hashes = DependencyHashes {
+                bytecode_sha3_256: bytecode_sha3_256(&bytecode),
+                source_sha3_256: sha3_of_parts(&source_digests),
+            };
+            Ok((name, hashes))
+        })
+        .collect()
+}
+
+/// Apply the dependency allowlists of `policy` to `pack`: the modules of its dependencies must be
+/// published at one of `allowed_dependency_addresses` or of `own_accounts`, and the `git`
+/// dependencies declared by the package and its local dependencies must come from one of
+/// `allowed_git_sources`. Problems are located at the dependency.
+pub fn check_dependency_policy(
+    pack: &BuiltPackage,
+    policy: &PolicySettings,
+    own_accounts: &BTreeSet<AccountAddress>,
+) -> anyhow::Result<()> {
+    let mut diagnostics = Diagnostics::default();
+    if policy.allowed_dependency_addresses.is_some() {
+        let mut denied: BTreeMap<String, BTreeSet<AccountAddress>> = BTreeMap::new();
+        for (dep_name, unit) in &pack.package.deps_compiled_units {
+            // scripts do not deserialize as modules and are skipped
+            let Ok(module) = CompiledModule::deserialize(&unit.unit.serialize(None)) else {
+                continue;
+            };
+            let address = *module.self_id().address();
+            if !policy.allows_dependency_address(&address) && !own_accounts.contains(&address) {
+                denied
+                    .entry(dep_name.to_string())
+                    .or_default()
+                    .insert(address);
+            }
+        }
+        for (dep_name, addresses) in denied {
+            diagnostics.push(
+                format!("dependencies.{}", dep_name),
+                format!(
+                    "has modules at {}, not in [policy] allowed_dependency_addresses",
+                    addresses
+                        .iter()
+                        .map(AccountAddress::to_standard_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+        }
+    }
+    if policy.allowed_git_sources.is_some() {
+        let package_dir = pack.package_path().to_path_buf();
+        let local_dirs = local_dependency_dirs(&package_dir)?;
+        let mut git = BTreeMap::new();
+        for dir in std::iter::once(&package_dir).chain(local_dirs.values()) {
+            git.extend(load_git_dependencies(dir)?);
+        }
+        for (dep_name, dependency) in git {
+            if !policy.allows_git_source(&dependency.git) {
+                diagnostics.push(
+                    format!("dependencies.{}", dep_name),
+                    format!(
+                        "comes from {}, not in [policy] allowed_git_sources",
+                        dependency.git
+                    ),
+                );
+            }
+        }
+    }
+    diagnostics.into_result()
+}
+
+/// SHA3-256 of compiled modules or scripts taken in sorted order, hex: the bytecode hash of
+/// yeaptor.lock and of SBOM components
+pub fn bytecode_sha3_256(units: &[Vec<u8>]) -> String {
+    let mut units = units.to_vec();
+    units.sort();
+    sha3_of_parts(&units)
+}
+
+/// SHA3-256 of `parts`, each prefixed with its length so that their boundaries count, hex
+fn sha3_of_parts(parts: &[Vec<u8>]) -> String {
+    let mut hasher = Sha3_256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Size in bytes of the raw transaction calling `deploy(seed, metadata, modules)`, by default
+/// `<yeaptor_address>::ra_code_deployment::deploy`
+pub fn publish_transaction_size(
+    deploy: &DeployFunction,
+    seed: &str,
+    metadata: &[u8],
+    modules: &[Vec<u8>],
+) -> usize {
+    // BCS layout of `TransactionPayload::EntryFunction`: variant, module address and name,
+    // function name, type arguments, then each argument as BCS bytes
+    let args = [
+        bcs::to_bytes(seed.as_bytes()),
+        bcs::to_bytes(metadata),
+        bcs::to_bytes(modules),
+    ];
+    entry_function_transaction_size(deploy.address, &deploy.module, &deploy.function, args)
+}
+
+/// Size in bytes of the raw transaction calling
+/// `0x1::code::publish_package_txn(metadata, modules)`
+pub fn code_publish_transaction_size(metadata: &[u8], modules: &[Vec<u8>]) -> usize {
+    let args = [bcs::to_bytes(metadata), bcs::to_bytes(modules)];
+    entry_function_transaction_size(AccountAddress::ONE, "code", "publish_package_txn", args)
+}
+
+/// Size in bytes of the raw transaction calling
+/// `0x1::object_code_deployment::publish(metadata, modules)`, or
+/// `upgrade(metadata, modules, object)` when `upgrade` names the code object
+pub fn object_code_transaction_size(
+    metadata: &[u8],
+    modules: &[Vec<u8>],
+    upgrade: Option<AccountAddress>,
+) -> usize {
+    let metadata = bcs::to_bytes(metadata);
+    let modules = bcs::to_bytes(modules);
+    match upgrade {
+        Some(object) => entry_function_transaction_size(
+            AccountAddress::ONE,
+            "object_code_deployment",
+            "upgrade",
+            [metadata, modules, bcs::to_bytes(&object)],
+        ),
+        None => entry_function_transaction_size(
+            AccountAddress::ONE,
+            "object_code_deployment",
+            "publish",
+            [metadata, modules],
+        ),
+    }
+}
+
+fn entry_function_transaction_size<const N: usize>(
+    address: AccountAddress,
+    module: &str,
+    function: &str,
+    args: [bcs::Result<Vec<u8>>; N],
+) -> usize {
+    let args = args
+        .into_iter()
+        .map(|arg| arg.unwrap_or_default())
+        .collect::<Vec<_>>();
+    let payload = (2u8, address, module, function, Vec::<u8>::new(), args);
+    RAW_TRANSACTION_OVERHEAD + bcs::serialized_size(&payload).unwrap_or_default()
+}
+
+/// `deploy(seed, metadata, modules)`, by default
+/// `<yeaptor_address>::ra_code_deployment::deploy`, as entry function JSON
+pub fn publish_payload_json(
+    deploy: &DeployFunction,
+    seed: &str,
+    metadata: &[u8],
+    modules: &[Vec<u8>],
+) -> serde_json::Value {
+    let seed_hex = format!("0x{}", hex::encode(seed.as_bytes()));
+    let meta_hex = format!("0x{}", hex::encode(metadata));
+    let module_hex: Vec<String> = modules
+        .iter()
+        .map(|m| format!("0x{}", hex::encode(m)))
+        .collect();
+    json!({
+        "function_id": deploy.to_string(),
+        "type_args": [],
+        "args": [
+            { "type": "hex", "value": seed_hex },
+            { "type": "hex", "value": meta_hex },
+            { "type": "hex", "value": module_hex },
+        ]
+    })
+}
+
+/// `0x1::code::publish_package_txn(metadata, modules)` as entry function JSON, publishing to the
+/// sender's own account
+pub fn code_publish_payload_json(metadata: &[u8], modules: &[Vec<u8>]) -> serde_json::Value {
+    let meta_hex = format!("0x{}", hex::encode(metadata));
+    let module_hex: Vec<String> = modules
+        .iter()
+        .map(|m| format!("0x{}", hex::encode(m)))
+        .collect();
+    json!({
+        "function_id": "0x1::code::publish_package_txn",
+        "type_args": [],
+        "args": [
+            { "type": "hex", "value": meta_hex },
+            { "type": "hex", "value": module_hex },
+        ]
+    })
+}
+
+/// `0x1::object_code_deployment::publish(metadata, modules)` as entry function JSON, creating a
+/// code object of the sender, or `upgrade(metadata, modules, object)` when `upgrade` names the
+/// code object to upgrade
+pub fn object_code_payload_json(
+    metadata: &[u8],
+    modules: &[Vec<u8>],
+    upgrade: Option<AccountAddress>,
+) -> serde_json::Value {
+    let meta_hex = format!("0x{}", hex::encode(metadata));
+    let module_hex: Vec<String> = modules
+        .iter()
+        .map(|m| format!("0x{}", hex::encode(m)))
+        .collect();
+    match upgrade {
+        Some(object) => json!({
+            "function_id": "0x1::object_code_deployment::upgrade",
+            "type_args": [],
+            "args": [
+                { "type": "hex", "value": meta_hex },
+                { "type": "hex", "value": module_hex },
+                { "type": "address", "value": object.to_standard_string() },
+            ]
+        }),
+        None => json!({
+            "function_id": "0x1::object_code_deployment::publish",
+            "type_args": [],
+            "args": [
+                { "type": "hex", "value": meta_hex },
+                { "type": "hex", "value": module_hex },
+            ]
+        }),
+    }
+}
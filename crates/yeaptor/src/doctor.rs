@@ -0,0 +1,463 @@
+use crate::config::{KeySource, YeaptorConfig};
+use crate::env::YeaptorEnv;
+use anyhow::{Context, Result, anyhow};
+use aptos_framework::{BuildOptions, BuiltPackage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Free space below which the out dir is reported, in bytes
+pub const MIN_FREE_BYTES: u64 = 1 << 30;
+
+/// How long a URL may take to answer before it counts as unreachable
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Outcome of one environment check
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DoctorCheck {
+    /// What was checked, e.g. `fullnode` or `deployments[0].packages[1]`
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// What to do about a warning or an error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    pub fn ok(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warning(
+        name: impl Into<String>,
+        message: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warning,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn error(
+        name: impl Into<String>,
+        message: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Error,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// One line per check, `ok[git] git version 2.43.0`, followed by its fix when it has one
+pub fn render_checks(checks: &[DoctorCheck]) -> String {
+    let mut lines = Vec::new();
+    for check in checks {
+        let status = match check.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Error => "error",
+        };
+        lines.push(format!("{}[{}] {}", status, check.name, check.message));
+        if let Some(fix) = &check.fix {
+            lines.push(format!("  fix: {}", fix));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Aptos Labs faucet of a network; mainnet has none
+pub fn default_faucet_url(network: &str) -> Option<&'static str> {
+    match network.to_ascii_lowercase().as_str() {
+        "testnet" => Some("https://faucet.testnet.aptoslabs.com"),
+        "devnet" => Some("https://faucet.devnet.aptoslabs.com"),
+        _ => None,
+    }
+}
+
+/// Ask the fullnode for its ledger info
+pub async fn check_fullnode(url: &str) -> DoctorCheck {
+    let fix = "check the network connection, or pass --node-url for a fullnode you can reach";
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return DoctorCheck::error("fullnode", format!("invalid URL {}: {}", url, e), fix);
+        }
+    };
+    let client = aptos_rest_client::Client::new(parsed);
+    match tokio::time::timeout(TIMEOUT, client.get_ledger_information()).await {
+        Ok(Ok(ledger)) => {
+            let ledger = ledger.into_inner();
+            DoctorCheck::ok(
+                "fullnode",
+                format!(
+                    "{} answers: chain id {}, ledger version {}",
+                    url, ledger.chain_id, ledger.version
+                ),
+            )
+        }
+        Ok(Err(e)) => DoctorCheck::error("fullnode", format!("{}: {}", url, e), fix),
+        Err(_) => DoctorCheck::error(
+            "fullnode",
+            format!("{} did not answer within {}s", url, TIMEOUT.as_secs()),
+            fix,
+        ),
+    }
+}
+
+/// Open a connection to the faucet; a faucet is only needed to fund test accounts, so failures
+/// are warnings
+pub async fn check_faucet(url: &str) -> DoctorCheck {
+    let fix = "check the network connection, or pass --faucet-url; accounts can also be funded from the Aptos web faucet";
+    let address = url::Url::parse(url)
+        .map_err(|e| anyhow!(e))
+        .and_then(|parsed| {
+            let host = parsed
+                .host_str()
+                .ok_or_else(|| anyhow!("URL has no host"))?
+                .to_string();
+            let port = parsed
+                .port_or_known_default()
+                .ok_or_else(|| anyhow!("URL has no port"))?;
+            Ok((host, port))
+        });
+    let (host, port) = match address {
+        Ok(address) => address,
+        Err(e) => {
+            return DoctorCheck::warning("faucet", format!("invalid URL {}: {}", url, e), fix);
+        }
+    };
+    match tokio::time::timeout(TIMEOUT, tokio::net::TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => DoctorCheck::ok("faucet", format!("{} is reachable", url)),
+        Ok(Err(e)) => DoctorCheck::warning("faucet", format!("{}: {}", url, e), fix),
+        Err(_) => DoctorCheck::warning(
+            "faucet",
+            format!("{} did not answer within {}s", url, TIMEOUT.as_secs()),
+            fix,
+        ),
+    }
+}
+
+/// What the doctor reads of an aptos CLI `config.yaml`
+#[derive(Deserialize)]
+struct AptosCliConfig {
+    #[serde(default)]
+    profiles: BTreeMap<String, AptosCliProfile>,
+}
+
+#[derive(Deserialize)]
+struct AptosCliProfile {
+    network: Option<String>,
+}
+
+/// aptos CLI configs in the order the CLI reads them: the workspace's `.aptos/config.yaml`,
+/// then the global one in the home directory
+pub fn aptos_config_files() -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(".aptos/config.yaml")];
+    if let Some(home) = std::env::var_os("HOME") {
+        files.push(Path::new(&home).join(".aptos/config.yaml"));
+    }
+    files
+}
+
+/// Find `profile` in the first of `config_files` that exists, and check it targets `network`
+pub fn check_aptos_profile(profile: &str, network: &str, config_files: &[PathBuf]) -> DoctorCheck {
+    let name = format!("aptos profile {}", profile);
+    let init = format!(
+        "run `aptos init --profile {} --network {}`",
+        profile, network
+    );
+    let Some(file) = config_files.iter().find(|file| file.is_file()) else {
+        return DoctorCheck::warning(name, "no aptos CLI config found", init);
+    };
+    let config = fs::read_to_string(file)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| {
+            serde_yaml::from_str::<AptosCliConfig>(&content).map_err(anyhow::Error::from)
+        });
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            return DoctorCheck::warning(
+                name,
+                format!("{} cannot be read: {:#}", file.display(), e),
+                format!("fix or remove {}, then {}", file.display(), init),
+            );
+        }
+    };
+    let Some(entry) = config.profiles.get(profile) else {
+        let known = config.profiles.keys().cloned().collect::<Vec<_>>();
+        return DoctorCheck::warning(
+            name,
+            format!(
+                "{} has no such profile (profiles: {})",
+                file.display(),
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ),
+            format!("{}, or pass --profile", init),
+        );
+    };
+    let other_network = entry
+        .network
+        .as_ref()
+        .filter(|profile_network| !profile_network.eq_ignore_ascii_case(network));
+    if let Some(profile_network) = other_network {
+        return DoctorCheck::warning(
+            name,
+            format!(
+                "profile in {} targets {}, not {}",
+                file.display(),
+                profile_network,
+                network
+            ),
+            format!("{}, or pass the profile of {}", init, network),
+        );
+    }
+    DoctorCheck::ok(name, format!("found in {}", file.display()))
+}
+
+/// Whether the key source of every publisher with a `key` in `[publishers]` is there: the aptos
+/// profile in the first of `config_files` that exists, the environment variable set, the
+/// keystore file present. An `external` signer cannot be checked.
+pub fn check_publisher_keys(config: &YeaptorConfig, config_files: &[PathBuf]) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    for (alias, publisher) in &config.publishers {
+        let Some(key) = &publisher.key else {
+            continue;
+        };
+        let name = format!("publisher {}", alias);
+        let check = match key {
+            KeySource::Profile(profile) => {
+                let config = config_files.iter().find(|file| file.is_file()).map(|file| {
+                    let profiles = fs::read_to_string(file)
+                        .ok()
+                        .and_then(|content| serde_yaml::from_str::<AptosCliConfig>(&content).ok())
+                        .map(|config| config.profiles)
+                        .unwrap_or_default();
+                    (file, profiles)
+                });
+                match config {
+                    Some((file, profiles)) if profiles.contains_key(profile) => {
+                        DoctorCheck::ok(name, format!("{} found in {}", key, file.display()))
+                    }
+                    Some((file, _)) => DoctorCheck::warning(
+                        name,
+                        format!("{} is not in {}", key, file.display()),
+                        format!("run `aptos init --profile {}`", profile),
+                    ),
+                    None => DoctorCheck::warning(
+                        name,
+                        format!("{} not found: no aptos CLI config", key),
+                        format!("run `aptos init --profile {}`", profile),
+                    ),
+                }
+            }
+            KeySource::Env(var) => match std::env::var_os(var) {
+                Some(value) if !value.is_empty() => {
+                    DoctorCheck::ok(name, format!("{} is set", key))
+                }
+                _ => DoctorCheck::warning(
+                    name,
+                    format!("{} is not set", key),
+                    format!(
+                        "export {} with the publisher's private key before signing",
+                        var
+                    ),
+                ),
+            },
+            KeySource::Keystore(path) if path.is_file() => {
+                DoctorCheck::ok(name, format!("{} found", key))
+            }
+            KeySource::Keystore(path) => DoctorCheck::warning(
+                name,
+                format!("{} not found", key),
+                format!(
+                    "put the publisher's keystore at {} or fix `key`",
+                    path.display()
+                ),
+            ),
+            KeySource::External => {
+                DoctorCheck::ok(name, "signed by an external signer, not checked")
+            }
+        };
+        checks.push(check);
+    }
+    checks
+}
+
+/// git fetches the git dependencies of Move packages
+pub fn check_git() -> DoctorCheck {
+    let fix = "install git and put it on PATH";
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => DoctorCheck::ok(
+            "git",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        Ok(output) => DoctorCheck::warning(
+            "git",
+            format!(
+                "`git --version` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            fix,
+        ),
+        Err(e) => DoctorCheck::warning(
+            "git",
+            format!(
+                "git not found ({}); Move packages with git dependencies cannot be fetched",
+                e
+            ),
+            fix,
+        ),
+    }
+}
+
+/// The checks `deployment build` runs before compiling (`YeaptorEnv::check`): one error per
+/// problem, located at its config entry, or one ok for all packages
+pub fn check_packages(config: &YeaptorConfig) -> Vec<DoctorCheck> {
+    let packages = config
+        .deployments
+        .iter()
+        .map(|deployment| deployment.packages.len())
+        .sum::<usize>();
+    match YeaptorEnv::new(config.clone()).check(None) {
+        Ok(()) => vec![DoctorCheck::ok(
+            "packages",
+            format!(
+                "every package has a Move.toml declaring its address_name ({} checked)",
+                packages
+            ),
+        )],
+        Err(e) => match e.diagnostics() {
+            Some(diagnostics) => diagnostics
+                .0
+                .iter()
+                .map(|diagnostic| {
+                    DoctorCheck::error(
+                        diagnostic.location.clone(),
+                        diagnostic.message.clone(),
+                        "fix the entry in yeaptor.toml or the package's Move.toml",
+                    )
+                })
+                .collect(),
+            None => vec![DoctorCheck::error(
+                "packages",
+                e.to_string(),
+                "fix the packages of yeaptor.toml",
+            )],
+        },
+    }
+}
+
+const PROBE_MANIFEST: &str = r#"[package]
+name = "YeaptorDoctor"
+version = "0.0.1"
+
+[addresses]
+yeaptor_doctor = "0xd0c"
+"#;
+
+const PROBE_MODULE: &str = r#"module yeaptor_doctor::probe {
+    public fun answer(): u64 { 42 }
+}
+"#;
+
+/// Compile a one-module package without dependencies in a scratch directory, to tell a broken
+/// compiler setup apart from a broken package
+pub fn check_move_compiler() -> DoctorCheck {
+    let dir = std::env::temp_dir().join(format!("yeaptor-doctor-{}", std::process::id()));
+    let result = build_probe(&dir);
+    let _ = fs::remove_dir_all(&dir);
+    match result {
+        Ok(()) => DoctorCheck::ok("move compiler", "compiled a probe package"),
+        Err(e) => DoctorCheck::error(
+            "move compiler",
+            format!("{:#}", e),
+            "reinstall yeaptor, or pass the compiler and language versions your packages need to `deployment build`",
+        ),
+    }
+}
+
+fn build_probe(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir.join("sources"))
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    fs::write(dir.join("Move.toml"), PROBE_MANIFEST).context("failed to write Move.toml")?;
+    fs::write(dir.join("sources/probe.move"), PROBE_MODULE)
+        .context("failed to write probe.move")?;
+    let options = BuildOptions {
+        skip_fetch_latest_git_deps: true,
+        ..BuildOptions::default()
+    };
+    BuiltPackage::build(dir.to_path_buf(), options).context("probe package failed to build")?;
+    Ok(())
+}
+
+/// Free space of the file system holding `dir` (or its closest existing parent), as `df` reports it
+pub fn check_disk_space(dir: &Path) -> DoctorCheck {
+    let name = format!("disk space {}", dir.display());
+    let existing = dir
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(Path::new("."));
+    let output = Command::new("df").arg("-Pk").arg(existing).output();
+    let available = output
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_df_available(&String::from_utf8_lossy(&output.stdout)));
+    match available {
+        Some(bytes) if bytes < MIN_FREE_BYTES => DoctorCheck::warning(
+            name,
+            format!(
+                "{} MiB free, builds need up to {} MiB",
+                bytes >> 20,
+                MIN_FREE_BYTES >> 20
+            ),
+            "free disk space, or pass another --out-dir",
+        ),
+        Some(bytes) => DoctorCheck::ok(name, format!("{} MiB free", bytes >> 20)),
+        None => DoctorCheck::warning(
+            name,
+            "free space unknown: `df` is not available",
+            "check the free space by hand",
+        ),
+    }
+}
+
+/// Available bytes in the output of `df -Pk <dir>`: the fourth column of its second line, in KiB
+pub fn parse_df_available(output: &str) -> Option<u64> {
+    let kib = output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
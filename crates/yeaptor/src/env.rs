@@ -1,14 +1,24 @@
-use crate::config::YeaptorConfig;
+use crate::config::{self, Deployment, DeploymentMode, YeaptorConfig};
+use crate::error::{YeaptorError, YeaptorResult};
+use crate::git_source::fetch_git_sources;
 use anyhow::anyhow;
 
-use aptos::common::types::{CliError, CliTypedResult, MovePackageOptions};
-use aptos::move_tool::{IncludedArtifacts, IncludedArtifactsArgs};
+use aptos::common::types::MovePackageOptions;
+use aptos::move_tool::IncludedArtifacts;
 use aptos_framework::BuiltPackage;
-use aptos_types::account_address::{AccountAddress, create_resource_address};
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
 use std::collections::BTreeMap;
 
-use std::path::Path;
 use aptos_framework::docgen::DocgenOptions;
+use indicatif::ProgressBar;
+use std::path::Path;
+use std::time::Instant;
+use tracing::{debug, info, info_span};
+use yeaptor_core::diagnostics::Diagnostics;
+use yeaptor_core::move_manifest::{
+    dependency_cycles, load_local_dependencies, load_manifest_addresses, manifest_path,
+};
 
 #[derive(Debug, Clone)]
 pub struct YeaptorEnv {
@@ -16,31 +26,50 @@ pub struct YeaptorEnv {
     named_addresses: BTreeMap<String, AccountAddress>,
 }
 pub struct BuiltDeployment {
-    #[allow(unused)]
+    /// Output subdirectory of the deployment (`Deployment::dir_name`)
+    pub deployment: String,
+    pub mode: DeploymentMode,
     pub publisher: AccountAddress,
+    /// Empty unless `DeploymentMode::ResourceAccount`
     pub seed: String,
+    /// Account the package is published to (`Deployment::account`)
+    pub account: AccountAddress,
+    /// Upgrades the existing code object `account` (`object_address` of an object deployment)
+    pub object_upgrade: bool,
 
     pub pack: BuiltPackage, // (package_name, metadata_serialized, modules)
 }
 
+/// A deployment's account, derived from yeaptor.toml alone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DerivedDeployment {
+    /// `deployments[i]`
+    pub location: String,
+    /// Output subdirectory of the deployment (`Deployment::dir_name`)
+    pub deployment: String,
+    pub mode: DeploymentMode,
+    pub publisher: AccountAddress,
+    /// Empty unless `DeploymentMode::ResourceAccount`
+    pub seed: String,
+    /// Account the packages are published to (`Deployment::account`)
+    pub account: AccountAddress,
+    /// `address_name` of the packages, each resolving to `account`
+    pub packages: Vec<String>,
+}
+
 impl YeaptorEnv {
     pub fn new(config: YeaptorConfig) -> Self {
         let mut named_addresses: BTreeMap<_, _> = config.named_addresses.clone();
+        // Deployments whose publisher does not resolve are reported by `check`
         let package_addresses = config
             .deployments
             .iter()
-            .flat_map(|de| {
-                let deployment_address = create_resource_address(
-                    config
-                        .publishers
-                        .get(de.publisher.as_str())
-                        .unwrap()
-                        .clone(),
-                    de.seed.as_bytes(),
-                );
+            .zip(config.deployment_addresses())
+            .filter_map(|(de, deployment_address)| Some((de, deployment_address?)))
+            .flat_map(|(de, deployment_address)| {
                 de.packages
                     .iter()
-                    .map(move |package| (package.address_name.clone(), deployment_address.clone()))
+                    .map(move |package| (package.address_name.clone(), deployment_address))
             })
             .collect::<BTreeMap<String, AccountAddress>>();
         named_addresses.extend(package_addresses);
@@ -54,25 +83,17 @@ impl YeaptorEnv {
         &self.config
     }
 
-    pub fn deploy_order(&self, package_path: &Path) -> CliTypedResult<Option<u64>> {
-        let package_path = package_path.canonicalize().map_err(|e| {
-            CliError::IO(
-                format!(
-                    "Failed to canonicalize package path {}",
-                    package_path.display()
-                ),
-                e,
-            )
-        })?;
+    pub fn deploy_order(&self, package_path: &Path) -> YeaptorResult<Option<u64>> {
+        let package_path = package_path
+            .canonicalize()
+            .map_err(|e| YeaptorError::io(package_path, e))?;
         let mut i = 0;
         for d in &self.config.deployments {
             for p in &d.packages {
-                let path = p.path.canonicalize().map_err(|e| {
-                    CliError::IO(
-                        format!("Failed to canonicalize package path {}", p.path.display()),
-                        e,
-                    )
-                })?;
+                let path = p
+                    .path
+                    .canonicalize()
+                    .map_err(|e| YeaptorError::io(&p.path, e))?;
                 if path == package_path {
                     return Ok(Some(i));
                 }
@@ -81,48 +102,270 @@ impl YeaptorEnv {
         }
         Ok(None)
     }
-    #[allow(unused)]
     pub fn named_addresses(&self) -> &BTreeMap<String, AccountAddress> {
         &self.named_addresses
     }
 
+    /// Named addresses the package at `package_dir` is built with: `named_addresses` with the
+    /// `addresses` of its entry in yeaptor.toml over them
+    pub fn package_named_addresses(&self, package_dir: &Path) -> BTreeMap<String, AccountAddress> {
+        let mut named_addresses = self.named_addresses.clone();
+        let canonical = package_dir.canonicalize().ok();
+        let package = self
+            .config
+            .deployments
+            .iter()
+            .flat_map(|deployment| &deployment.packages)
+            .find(|pkg| {
+                pkg.path == package_dir
+                    || (canonical.is_some() && pkg.path.canonicalize().ok() == canonical)
+            });
+        if let Some(package) = package {
+            named_addresses.extend(package.addresses.clone());
+        }
+        named_addresses
+    }
+
+    /// Build the packages selected by `tags` (every package when empty), each with its position
+    /// in the deployment order. Every package is built even after one fails; a single failure is
+    /// returned as is, several together as `Diagnostics` of a `Build` error naming the first.
     pub fn build_all(
         &self,
-        included_args: &IncludedArtifactsArgs,
+        included_artifacts: &IncludedArtifacts,
         move_options: &MovePackageOptions,
         docgen_options: Option<DocgenOptions>,
-    ) -> CliTypedResult<Vec<BuiltDeployment>> {
+        tags: &[String],
+        progress: &ProgressBar,
+    ) -> YeaptorResult<Vec<(usize, BuiltDeployment)>> {
+        fetch_git_sources(&self.config, &config::git_cache_dir())?;
+        self.check(None)?;
         let mut deployments = Vec::new();
-        for deployment in &self.config.deployments {
-            let publisher = self
-                .config
-                .publishers
-                .get(&deployment.publisher)
-                .expect(&format!(
-                    "Publisher address not found: {}",
-                    deployment.publisher
-                ))
-                .clone();
-            let seed = deployment.seed.clone();
-            for pkg in &deployment.packages {
+        let mut failures = Vec::new();
+        let mut i = 0;
+        for (k, deployment) in self.config.deployments.iter().enumerate() {
+            let publisher = self.publisher(deployment)?;
+            let seed = deployment.seed().to_string();
+            for (j, pkg) in deployment.packages.iter().enumerate() {
+                let order = i;
+                i += 1;
+                if !deployment.selects(pkg, tags) {
+                    continue;
+                }
                 let pkg_path = Path::new(&pkg.path);
-                let included_artifacts = pkg
-                    .include_artifacts
-                    .as_ref()
-                    .unwrap_or(&included_args.included_artifacts);
-                let pack = self
-                    .build_package(pkg_path, included_artifacts, move_options, docgen_options.clone())
-                    .expect("Failed to build package");
+                let pkg_artifacts = pkg.include_artifacts.map(aptos_included_artifacts);
+                let included_artifacts = pkg_artifacts.as_ref().unwrap_or(included_artifacts);
+                progress.set_message(format!("building {}", pkg_path.display()));
+                let built = self.build_package(
+                    pkg_path,
+                    included_artifacts,
+                    move_options,
+                    docgen_options.clone(),
+                );
+                progress.inc(1);
+                let pack = match built {
+                    Ok(pack) => pack,
+                    Err(e) => {
+                        failures.push((format!("deployments[{}].packages[{}]", k, j), e));
+                        continue;
+                    }
+                };
 
                 let d = BuiltDeployment {
+                    deployment: deployment.dir_name(),
+                    mode: deployment.mode(),
                     publisher: publisher.clone(),
                     seed: seed.clone(),
+                    account: deployment.account(&publisher),
+                    object_upgrade: deployment.object_address.is_some(),
                     pack,
                 };
-                deployments.push(d);
+                deployments.push((order, d));
+            }
+        }
+        if failures.len() > 1 {
+            let package = failures[0].1.path().cloned().unwrap_or_default();
+            let mut diagnostics = Diagnostics::default();
+            for (location, e) in failures {
+                diagnostics.push(location, e.to_string());
             }
+            return Err(YeaptorError::build(package, diagnostics));
+        }
+        match failures.pop() {
+            Some((_, e)) => Err(e),
+            None => Ok(deployments),
         }
-        Ok(deployments)
+    }
+
+    /// Check what can be checked before compiling, for every package or only the one at `only`:
+    /// the publisher of its deployment resolves, its directory has a readable Move.toml, and its
+    /// `address_name` is a named address there (otherwise the package would compile against its
+    /// own address instead of the resource account), as must every name its `addresses` bind.
+    /// Packages depending on each other in a cycle
+    /// through the local `[dependencies]` of their Move.toml, which no deployment order can
+    /// publish, are reported too. Every problem is reported at once, as `Diagnostics`.
+    pub fn check(&self, only: Option<&Path>) -> YeaptorResult<()> {
+        let mut diagnostics = Diagnostics::default();
+        for (i, deployment) in self.config.deployments.iter().enumerate() {
+            let packages = deployment
+                .packages
+                .iter()
+                .enumerate()
+                .filter(|(_, pkg)| only.is_none_or(|only| only == pkg.path.as_path()))
+                .collect::<Vec<_>>();
+            if packages.is_empty() {
+                continue;
+            }
+            if self
+                .config
+                .publisher_address(&deployment.publisher)
+                .is_none()
+            {
+                diagnostics.push(format!("deployments[{}]", i), unknown_publisher(deployment));
+            }
+            for (j, pkg) in packages {
+                let location = format!("deployments[{}].packages[{}]", i, j);
+                let manifest = manifest_path(&pkg.path);
+                match load_manifest_addresses(&pkg.path) {
+                    Err(e) => {
+                        diagnostics.push(location, format!("{}: {:#}", manifest.display(), e))
+                    }
+                    Ok(addresses) if !addresses.declares(&pkg.address_name) => diagnostics.push(
+                        location,
+                        format!(
+                            "address_name `{}` is not a named address in {} (declared: {})",
+                            pkg.address_name,
+                            manifest.display(),
+                            addresses
+                                .addresses
+                                .keys()
+                                .cloned()
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ),
+                    Ok(addresses) => {
+                        for name in pkg
+                            .addresses
+                            .keys()
+                            .filter(|name| !addresses.declares(name))
+                        {
+                            diagnostics.push(
+                                location.clone(),
+                                format!(
+                                    "`addresses` binds `{}`, which is not a named address in {}",
+                                    name,
+                                    manifest.display()
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        for cycle in self.dependency_cycles() {
+            if only.is_some_and(|only| !cycle.iter().any(|(_, path)| *path == only)) {
+                continue;
+            }
+            let (location, _) = &cycle[0];
+            let path = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|(location, path)| format!("{} ({})", path.display(), location))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            diagnostics.push(
+                location,
+                format!(
+                    "packages depend on each other in a cycle, so none can be published first: {}",
+                    path
+                ),
+            );
+        }
+        diagnostics.into_result().map_err(YeaptorError::invalid)
+    }
+
+    /// Cycles among the configured packages through their Move.toml local dependencies, each as
+    /// the locations and paths of its packages in dependency order. Packages whose directory or
+    /// manifest cannot be read are left out; `check` reports them.
+    fn dependency_cycles(&self) -> Vec<Vec<(String, &Path)>> {
+        let packages = self
+            .config
+            .deployments
+            .iter()
+            .enumerate()
+            .flat_map(|(i, deployment)| {
+                deployment.packages.iter().enumerate().map(move |(j, pkg)| {
+                    (
+                        format!("deployments[{}].packages[{}]", i, j),
+                        pkg.path.as_path(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let canonical = packages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (_, path))| Some((path.canonicalize().ok()?, index)))
+            .collect::<BTreeMap<_, _>>();
+        let edges = packages
+            .iter()
+            .map(|(_, path)| {
+                load_local_dependencies(path)
+                    .unwrap_or_default()
+                    .values()
+                    .filter_map(|dependency| canonical.get(&dependency.canonicalize().ok()?))
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        dependency_cycles(&edges)
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .into_iter()
+                    .map(|index| packages[index].clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Account of every deployment with a package selected by `tags` (every deployment when
+    /// empty), without compiling or querying anything; unknown publishers are reported together
+    pub fn derive_deployments(&self, tags: &[String]) -> YeaptorResult<Vec<DerivedDeployment>> {
+        let mut diagnostics = Diagnostics::default();
+        let mut derived = Vec::new();
+        for (i, deployment) in self.config.deployments.iter().enumerate() {
+            let packages = deployment
+                .packages
+                .iter()
+                .filter(|pkg| deployment.selects(pkg, tags))
+                .map(|pkg| pkg.address_name.clone())
+                .collect::<Vec<_>>();
+            if !tags.is_empty() && packages.is_empty() {
+                continue;
+            }
+            let Some(publisher) = self.config.publisher_address(&deployment.publisher) else {
+                diagnostics.push(format!("deployments[{}]", i), unknown_publisher(deployment));
+                continue;
+            };
+            derived.push(DerivedDeployment {
+                location: format!("deployments[{}]", i),
+                deployment: deployment.dir_name(),
+                mode: deployment.mode(),
+                publisher,
+                seed: deployment.seed().to_string(),
+                account: deployment.account(&publisher),
+                packages,
+            });
+        }
+        diagnostics.into_result().map_err(YeaptorError::invalid)?;
+        Ok(derived)
+    }
+
+    fn publisher(&self, deployment: &Deployment) -> YeaptorResult<AccountAddress> {
+        self.config
+            .publisher_address(&deployment.publisher)
+            .ok_or_else(|| YeaptorError::invalid(anyhow!("{}", unknown_publisher(deployment))))
     }
 
     pub fn build_package(
@@ -131,67 +374,73 @@ impl YeaptorEnv {
         included_args: &IncludedArtifacts,
         move_options: &MovePackageOptions,
         docgen_options: Option<DocgenOptions>,
-    ) -> CliTypedResult<BuiltPackage> {
-        let mut build_options = included_args.build_options(move_options)?;
+    ) -> YeaptorResult<BuiltPackage> {
+        let _span = info_span!("build_package", package = %package_dir.display()).entered();
+        let started = Instant::now();
+        let mut build_options = included_args
+            .build_options(move_options)
+            .map_err(|e| YeaptorError::build(package_dir, anyhow!(e.to_string())))?;
         build_options.install_dir = move_options.output_dir.clone();
-        let mut named_addresses = self.named_addresses.clone();
+        let mut named_addresses = self.package_named_addresses(package_dir);
         named_addresses.extend(build_options.named_addresses.clone());
         build_options.named_addresses = named_addresses;
+        check_manifest_addresses(
+            package_dir,
+            &build_options.named_addresses,
+            build_options.dev,
+        )?;
         build_options.with_docs = docgen_options.is_some();
         build_options.docgen_options = docgen_options;
-        let pack = BuiltPackage::build(package_dir.to_path_buf(), build_options)
-            .map_err(|e| anyhow!("Move compilation error: {:#}", e))?;
+        debug!(named_addresses = ?build_options.named_addresses, "building");
+        let pack = BuiltPackage::build(package_dir.to_path_buf(), build_options).map_err(|e| {
+            YeaptorError::build(package_dir, anyhow!("Move compilation error: {:#}", e))
+        })?;
+        info!(
+            name = %pack.name(),
+            modules = pack.modules().count(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "built package"
+        );
         Ok(pack)
     }
 
     pub fn build_deployment_package(
         &self,
         package_dir: &Path,
-        included_args: &IncludedArtifactsArgs,
+        included_artifacts: &IncludedArtifacts,
         move_options: &MovePackageOptions,
         doc_options: Option<DocgenOptions>,
-    ) -> CliTypedResult<(usize, BuiltDeployment)> {
+    ) -> YeaptorResult<(usize, BuiltDeployment)> {
+        fetch_git_sources(&self.config, &config::git_cache_dir())?;
         // Canonicalize the input package directory for proper comparison
-        let canonical_package_dir = package_dir.canonicalize().map_err(|e| {
-            CliError::IO(
-                format!(
-                    "Failed to canonicalize package directory {}",
-                    package_dir.display(),
-                ),
-                e,
-            )
-        })?;
+        let canonical_package_dir = package_dir
+            .canonicalize()
+            .map_err(|e| YeaptorError::io(package_dir, e))?;
         let mut i = 0;
         for deployment in &self.config.deployments {
             for pkg in &deployment.packages {
-                // Canonicalize the config package path for comparison
-                let canonical_pkg_path = Path::new(&pkg.path).canonicalize().map_err(|e| {
-                    CliError::IO(
-                        format!(
-                            "Failed to canonicalize package directory {}",
-                            package_dir.display(),
-                        ),
-                        e,
-                    )
-                })?;
+                // Canonicalize the config package path for comparison; packages that do not exist
+                // cannot be the one asked for
+                let Ok(canonical_pkg_path) = Path::new(&pkg.path).canonicalize() else {
+                    i += 1;
+                    continue;
+                };
                 if canonical_pkg_path == canonical_package_dir {
+                    self.check(Some(pkg.path.as_path()))?;
                     let built_package = self.build_package(
                         canonical_pkg_path.as_path(),
-                        &included_args.included_artifacts,
+                        included_artifacts,
                         move_options,
                         doc_options,
                     )?;
+                    let publisher = self.publisher(deployment)?;
                     let deployment = BuiltDeployment {
-                        publisher: self
-                            .config
-                            .publishers
-                            .get(&deployment.publisher)
-                            .expect(&format!(
-                                "Publisher address not found: {}",
-                                deployment.publisher
-                            ))
-                            .clone(),
-                        seed: deployment.seed.clone(),
+                        deployment: deployment.dir_name(),
+                        mode: deployment.mode(),
+                        publisher,
+                        seed: deployment.seed().to_string(),
+                        account: deployment.account(&publisher),
+                        object_upgrade: deployment.object_address.is_some(),
                         pack: built_package,
                     };
                     return Ok((i, deployment));
@@ -200,9 +449,52 @@ impl YeaptorEnv {
             }
         }
 
-        Err(CliError::UnexpectedError(format!(
+        Err(YeaptorError::invalid(anyhow!(
             "No deployment found for package directory: {}",
             package_dir.display()
         )))
     }
 }
+
+fn unknown_publisher(deployment: &Deployment) -> String {
+    format!(
+        "publisher `{}` is neither in [publishers] nor an address",
+        deployment.publisher
+    )
+}
+
+/// The aptos CLI's artifact set for `include_artifacts` of yeaptor.toml
+pub fn aptos_included_artifacts(artifacts: config::IncludedArtifacts) -> IncludedArtifacts {
+    match artifacts {
+        config::IncludedArtifacts::None => IncludedArtifacts::None,
+        config::IncludedArtifacts::Sparse => IncludedArtifacts::Sparse,
+        config::IncludedArtifacts::All => IncludedArtifacts::All,
+    }
+}
+
+/// Fail before compiling when the package's Move.toml pins a named address to another address
+/// than the build resolves it to, which the compiler only reports as a failed resolution
+fn check_manifest_addresses(
+    package_dir: &Path,
+    named_addresses: &BTreeMap<String, AccountAddress>,
+    dev: bool,
+) -> YeaptorResult<()> {
+    let manifest = manifest_path(package_dir);
+    let conflicts = load_manifest_addresses(package_dir)
+        .map_err(|e| YeaptorError::load(&manifest, e))?
+        .conflicts(named_addresses, dev);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(YeaptorError::config(
+        manifest,
+        anyhow!(
+            "{}",
+            conflicts
+                .iter()
+                .map(|conflict| conflict.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    ))
+}
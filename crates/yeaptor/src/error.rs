@@ -0,0 +1,170 @@
+use aptos::common::types::CliError;
+use std::fmt;
+use std::path::PathBuf;
+use yeaptor_core::diagnostics::Diagnostics;
+
+pub type YeaptorResult<T> = Result<T, YeaptorError>;
+
+/// Failure of a yeaptor command or library call, by class, so callers can tell a bad input apart
+/// from a failed build, a file system error, an unreachable chain service or an unmappable event
+#[derive(Debug)]
+pub enum YeaptorError {
+    /// yeaptor.toml, an input file (schema, mapping, processor config) or a command argument is
+    /// invalid; `path` names the file when there is one
+    Config {
+        path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    /// A Move package failed to build
+    Build {
+        package: PathBuf,
+        source: anyhow::Error,
+    },
+    /// A file or directory could not be read or written
+    Io {
+        path: PathBuf,
+        source: anyhow::Error,
+    },
+    /// The transaction stream, the node, the database or a sink failed
+    Chain { source: anyhow::Error },
+    /// Events could not be mapped onto the tables
+    Mapping { source: anyhow::Error },
+}
+
+impl YeaptorError {
+    /// Invalid content of the file at `path`
+    pub fn config(path: impl Into<PathBuf>, source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Config {
+            path: Some(path.into()),
+            source: source.into(),
+        }
+    }
+
+    /// Failure loading the input file at `path`: `Io` when it could not be read, `Config` when its
+    /// content is invalid
+    pub fn load(path: impl Into<PathBuf>, source: impl Into<anyhow::Error>) -> Self {
+        let source = source.into();
+        if source.downcast_ref::<std::io::Error>().is_some() {
+            YeaptorError::io(path, source)
+        } else {
+            YeaptorError::config(path, source)
+        }
+    }
+
+    /// Invalid command argument or setting, not tied to one file
+    pub fn invalid(source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Config {
+            path: None,
+            source: source.into(),
+        }
+    }
+
+    pub fn build(package: impl Into<PathBuf>, source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Build {
+            package: package.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn io(path: impl Into<PathBuf>, source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Io {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn chain(source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Chain {
+            source: source.into(),
+        }
+    }
+
+    pub fn mapping(source: impl Into<anyhow::Error>) -> Self {
+        YeaptorError::Mapping {
+            source: source.into(),
+        }
+    }
+
+    /// `config`, `build`, `io`, `chain` or `mapping`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            YeaptorError::Config { .. } => "config",
+            YeaptorError::Build { .. } => "build",
+            YeaptorError::Io { .. } => "io",
+            YeaptorError::Chain { .. } => "chain",
+            YeaptorError::Mapping { .. } => "mapping",
+        }
+    }
+
+    /// Every problem found, when the error collects several (an invalid yeaptor.toml, packages
+    /// failing the checks before a build)
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        match self {
+            YeaptorError::Config { source, .. }
+            | YeaptorError::Build { source, .. }
+            | YeaptorError::Io { source, .. }
+            | YeaptorError::Chain { source }
+            | YeaptorError::Mapping { source } => source.downcast_ref::<Diagnostics>(),
+        }
+    }
+
+    /// File the error is about, if any
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            YeaptorError::Config { path, .. } => path.as_ref(),
+            YeaptorError::Build { package, .. } => Some(package),
+            YeaptorError::Io { path, .. } => Some(path),
+            YeaptorError::Chain { .. } | YeaptorError::Mapping { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for YeaptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YeaptorError::Config {
+                path: Some(path),
+                source,
+            } => write!(f, "invalid {}: {:#}", path.display(), source),
+            YeaptorError::Config { path: None, source } => write!(f, "{:#}", source),
+            YeaptorError::Build { package, source } => {
+                write!(
+                    f,
+                    "failed to build package at {}: {:#}",
+                    package.display(),
+                    source
+                )
+            }
+            YeaptorError::Io { path, source } => write!(f, "{}: {:#}", path.display(), source),
+            YeaptorError::Chain { source } | YeaptorError::Mapping { source } => {
+                write!(f, "{:#}", source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for YeaptorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            YeaptorError::Config { source, .. }
+            | YeaptorError::Build { source, .. }
+            | YeaptorError::Io { source, .. }
+            | YeaptorError::Chain { source }
+            | YeaptorError::Mapping { source } => Some(&**source),
+        }
+    }
+}
+
+/// Errors of the aptos CLI helpers still used by the commands (`SaveFile`, build options)
+impl From<CliError> for YeaptorError {
+    fn from(e: CliError) -> Self {
+        match e {
+            CliError::IO(path, e) => YeaptorError::io(path, e),
+            CliError::UnableToReadFile(path, message) => {
+                YeaptorError::io(path, anyhow::anyhow!(message))
+            }
+            CliError::ApiError(message) => YeaptorError::chain(anyhow::anyhow!(message)),
+            e => YeaptorError::invalid(anyhow::anyhow!(e.to_string())),
+        }
+    }
+}
@@ -1,43 +1,68 @@
+use anyhow::{Result, anyhow};
 use aptos_types::account_address::AccountAddress;
 use aptos_types::vm::module_metadata::RuntimeModuleMetadataV1;
 use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
 #[allow(deprecated)]
-use move_binary_format::normalized::Module;
-use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EventDefinition {
-    pub package_name: String,
-    pub module_address: AccountAddress,
-    pub module_name: String,
-    pub name: String,
+use move_binary_format::normalized::{Module, Type};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub use yeaptor_core::event_definition::{EventDefinition, EventVersion};
+
+/// Fully qualified struct identifier: (module address, module name, struct name)
+pub type StructId = (AccountAddress, String, String);
+
+/// Fields (name, type) of every non-generic struct declared in a set of modules
+pub(crate) type StructLayouts = HashMap<StructId, Vec<(String, Type)>>;
+
+pub(crate) struct ExtractedEvent {
+    pub version: EventVersion,
     pub fields: BTreeMap<String, String>,
+    pub nested_fields: BTreeMap<String, String>,
 }
 
 pub(crate) fn extract_event_definitions(
     module: &CompiledModule,
-) -> BTreeMap<String, BTreeMap<String, String>> {
-    let metadata = aptos_types::vm::module_metadata::get_metadata_from_compiled_code(module);
-    if metadata.is_none() {
-        return BTreeMap::new();
-    }
-    let metadata = metadata.unwrap();
-    let events = extract_event_metadata(&metadata);
-    let view = Module::new(module).unwrap();
+    v1_events: &HashSet<StructId>,
+    layouts: &StructLayouts,
+) -> Result<BTreeMap<String, ExtractedEvent>> {
+    let v2_events = aptos_types::vm::module_metadata::get_metadata_from_compiled_code(module)
+        .map(|metadata| extract_event_metadata(&metadata))
+        .unwrap_or_default();
+    let module_address = *module.address();
+    let module_name = module.name().to_string();
+    let view = normalize(module)?;
 
-    view.structs
+    Ok(view
+        .structs
         .iter()
-        .filter(|(s, _)| events.contains(s.as_str()))
-        .map(|(name, s)| {
-            let fields = s
+        .filter_map(|(s, def)| {
+            let version = if v2_events.contains(s.as_str()) {
+                EventVersion::V2
+            } else if v1_events.contains(&(module_address, module_name.clone(), s.to_string())) {
+                EventVersion::V1
+            } else {
+                return None;
+            };
+            let fields = def
                 .fields
                 .iter()
                 .map(|f| (f.name.to_string(), f.type_.to_string()))
                 .collect::<BTreeMap<_, _>>();
-            (name.to_string(), fields)
+            let mut nested_fields = BTreeMap::new();
+            for f in &def.fields {
+                flatten_struct_field(&f.name.to_string(), &f.type_, layouts, &mut nested_fields);
+            }
+            Some((
+                s.to_string(),
+                ExtractedEvent {
+                    version,
+                    fields,
+                    nested_fields,
+                },
+            ))
         })
-        .collect::<BTreeMap<_, _>>()
+        .collect::<BTreeMap<_, _>>())
 }
 
 pub(crate) fn extract_event_metadata(metadata: &RuntimeModuleMetadataV1) -> HashSet<String> {
@@ -51,3 +76,100 @@ pub(crate) fn extract_event_metadata(metadata: &RuntimeModuleMetadataV1) -> Hash
     }
     event_structs
 }
+
+/// Collect the event types `T` of every `0x1::event::EventHandle<T>` field declared in a `key`
+/// struct of the module, as event handles are only usable from resources. The event struct
+/// itself may live in another module, so callers should gather these across a whole package
+/// before extracting definitions.
+pub fn extract_event_handle_types(module: &CompiledModule) -> Result<HashSet<StructId>> {
+    let view = normalize(module)?;
+    Ok(view
+        .structs
+        .values()
+        .filter(|s| s.abilities.has_key())
+        .flat_map(|s| s.fields.iter())
+        .filter_map(|f| match &f.type_ {
+            Type::Struct {
+                address,
+                module,
+                name,
+                type_arguments,
+            } if *address == AccountAddress::ONE
+                && module.as_str() == "event"
+                && name.as_str() == "EventHandle" =>
+            {
+                match type_arguments.first() {
+                    Some(Type::Struct {
+                        address,
+                        module,
+                        name,
+                        ..
+                    }) => Some((*address, module.to_string(), name.to_string())),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+pub(crate) fn extract_struct_layouts(module: &CompiledModule) -> Result<StructLayouts> {
+    let module_address = *module.address();
+    let module_name = module.name().to_string();
+    let view = normalize(module)?;
+    Ok(view
+        .structs
+        .iter()
+        .filter(|(_, s)| s.type_parameters.is_empty())
+        .map(|(name, s)| {
+            (
+                (module_address, module_name.clone(), name.to_string()),
+                s.fields
+                    .iter()
+                    .map(|f| (f.name.to_string(), f.type_.clone()))
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+fn normalize(module: &CompiledModule) -> Result<Module> {
+    Module::new(module).map_err(|e| {
+        anyhow!(
+            "failed to read the structs of module {}::{}: {:?}",
+            module.address().to_hex_literal(),
+            module.name(),
+            e
+        )
+    })
+}
+
+/// Record the leaves below `path` if `type_` is a struct with a known layout.
+/// Framework (`0x1`) structs such as `String` or `Object<T>` keep their own JSON encoding and are not expanded.
+fn flatten_struct_field(
+    path: &str,
+    type_: &Type,
+    layouts: &StructLayouts,
+    out: &mut BTreeMap<String, String>,
+) {
+    let Type::Struct {
+        address,
+        module,
+        name,
+        type_arguments,
+    } = type_
+    else {
+        return;
+    };
+    if *address == AccountAddress::ONE || !type_arguments.is_empty() {
+        return;
+    }
+    let Some(fields) = layouts.get(&(*address, module.to_string(), name.to_string())) else {
+        return;
+    };
+    for (field_name, field_type) in fields {
+        let field_path = format!("{}.{}", path, field_name);
+        out.insert(field_path.clone(), field_type.to_string());
+        flatten_struct_field(&field_path, field_type, layouts, out);
+    }
+}
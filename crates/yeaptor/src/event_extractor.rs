@@ -0,0 +1,141 @@
+use crate::config::YeaptorConfig;
+use crate::env::YeaptorEnv;
+use crate::error::{YeaptorError, YeaptorResult};
+use crate::event_definition::{
+    EventDefinition, StructLayouts, extract_event_definitions, extract_event_handle_types,
+    extract_struct_layouts,
+};
+use anyhow::Result;
+use aptos::common::types::MovePackageOptions;
+use aptos::move_tool::IncludedArtifacts;
+use aptos_framework::BuiltPackage;
+use indicatif::ProgressBar;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Extracts the event definitions (module events and legacy `EventHandle<T>` events, with
+/// struct-typed fields flattened) of compiled Move packages.
+///
+/// ```ignore
+/// let events = EventExtractor::new().include_deps(true).extract(&built_package)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventExtractor {
+    include_deps: bool,
+    progress: ProgressBar,
+}
+
+impl Default for EventExtractor {
+    fn default() -> Self {
+        EventExtractor {
+            include_deps: false,
+            progress: ProgressBar::hidden(),
+        }
+    }
+}
+
+impl EventExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also extract the events declared in dependency packages (including framework packages),
+    /// with `dependency_of` naming the package that pulled them in
+    pub fn include_deps(mut self, include_deps: bool) -> Self {
+        self.include_deps = include_deps;
+        self
+    }
+
+    /// Advanced by one per package built by `extract_deployments`, which sets its length
+    pub fn progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Events of a built package; fails on a module whose structs cannot be read
+    pub fn extract(&self, pack: &BuiltPackage) -> Result<Vec<EventDefinition>> {
+        let package_name = pack.name().to_string();
+        // (package name, root package that pulled it in, module)
+        let mut modules = pack
+            .modules()
+            .map(|m| (package_name.clone(), None, m.clone()))
+            .collect::<Vec<_>>();
+        if self.include_deps {
+            modules.extend(pack.package.deps_compiled_units.iter().filter_map(
+                |(dep_name, unit)| {
+                    // scripts do not deserialize as modules and are skipped
+                    CompiledModule::deserialize(&unit.unit.serialize(None))
+                        .ok()
+                        .map(|m| (dep_name.to_string(), Some(package_name.clone()), m))
+                },
+            ));
+        }
+        // Legacy events are identified by the EventHandle<T> fields of any module in the build
+        let mut v1_events = HashSet::new();
+        for (_, _, m) in &modules {
+            v1_events.extend(extract_event_handle_types(m)?);
+        }
+        // Struct-typed event fields are flattened using the layouts of the built modules
+        let mut layouts = StructLayouts::new();
+        for (_, _, m) in &modules {
+            layouts.extend(extract_struct_layouts(m)?);
+        }
+        let mut definitions = Vec::new();
+        for (package_name, dependency_of, m) in &modules {
+            let events = extract_event_definitions(m, &v1_events, &layouts)?;
+            let module_name = m.name().to_string();
+            definitions.extend(
+                events
+                    .into_iter()
+                    .map(|(event_name, extracted)| EventDefinition {
+                        package_name: package_name.clone(),
+                        module_address: *m.address(),
+                        module_name: module_name.clone(),
+                        name: event_name,
+                        fields: extracted.fields,
+                        version: extracted.version,
+                        dependency_of: dependency_of.clone(),
+                        nested_fields: extracted.nested_fields,
+                    }),
+            );
+        }
+        Ok(definitions)
+    }
+
+    /// Build the packages of every deployment in `config` (only `move_options.package_dir` when
+    /// set), with the deployments' named addresses, and extract their events. Returns
+    /// (package name, events) in deployment order.
+    pub fn extract_deployments(
+        &self,
+        config: YeaptorConfig,
+        move_options: &MovePackageOptions,
+    ) -> YeaptorResult<Vec<(String, Vec<EventDefinition>)>> {
+        let env = YeaptorEnv::new(config);
+        let packages: Vec<PathBuf> = match &move_options.package_dir {
+            Some(package_dir) => vec![package_dir.clone()],
+            None => env
+                .config()
+                .deployments
+                .iter()
+                .flat_map(|d| d.packages.iter().map(|p| p.path.clone()))
+                .collect(),
+        };
+        self.progress.set_length(packages.len() as u64);
+        packages
+            .iter()
+            .map(|package_dir| {
+                self.progress
+                    .set_message(format!("building {}", package_dir.display()));
+                let pack =
+                    env.build_package(package_dir, &IncludedArtifacts::None, move_options, None)?;
+                let events = self
+                    .extract(&pack)
+                    .map_err(|e| YeaptorError::build(package_dir, e))?;
+                self.progress.inc(1);
+                Ok((pack.name().to_string(), events))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::event_definition::{EventDefinition, EventVersion};
+use crate::event_table_mapping::EventTableMappings;
+use serde::Serialize;
+
+/// One event of the built packages, with whether the event mapping writes it anywhere
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventListing {
+    pub package: String,
+    /// `<module>::<struct>`
+    pub event: String,
+    pub version: EventVersion,
+    /// Top-level fields
+    pub fields: usize,
+    /// Fields inside struct-typed fields, by dotted path
+    pub nested_fields: usize,
+    /// Root package whose dependency declares the event (`--include-deps`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency_of: Option<String>,
+    /// Tables the event mapping writes the event to, empty when unmapped; `None` without a
+    /// mapping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<Vec<String>>,
+}
+
+impl EventListing {
+    pub fn mapped(&self) -> Option<bool> {
+        self.tables.as_ref().map(|tables| !tables.is_empty())
+    }
+}
+
+/// One listing per definition, sorted by package, module and event. With `mapping`, each
+/// carries the tables of its `<package>::<module>::<event>` rules.
+pub fn list_events(
+    definitions: &[EventDefinition],
+    mapping: Option<&EventTableMappings>,
+) -> Vec<EventListing> {
+    let mut listings = definitions
+        .iter()
+        .map(|definition| {
+            let key = format!(
+                "{}::{}::{}",
+                definition.package_name, definition.module_name, definition.name
+            );
+            EventListing {
+                package: definition.package_name.clone(),
+                event: format!("{}::{}", definition.module_name, definition.name),
+                version: definition.version,
+                fields: definition.fields.len(),
+                nested_fields: definition.nested_fields.len(),
+                dependency_of: definition.dependency_of.clone(),
+                tables: mapping.map(|mapping| {
+                    mapping
+                        .get(&key)
+                        .into_iter()
+                        .flatten()
+                        .map(|rule| rule.table.clone())
+                        .collect()
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+    listings.sort_by(|a, b| (&a.package, &a.event).cmp(&(&b.package, &b.event)));
+    listings
+}
+
+/// `listings` as a table with aligned columns, the mapping status last when known, followed by
+/// the mapping coverage
+pub fn render_event_table(listings: &[EventListing]) -> String {
+    let with_status = listings.iter().any(|listing| listing.tables.is_some());
+    let mut rows = vec![
+        ["PACKAGE", "EVENT", "KIND", "FIELDS", "STATUS"]
+            .map(str::to_string)
+            .to_vec(),
+    ];
+    for listing in listings {
+        let package = match &listing.dependency_of {
+            Some(root) => format!("{} (dep of {})", listing.package, root),
+            None => listing.package.clone(),
+        };
+        let kind = match listing.version {
+            EventVersion::V2 => "module",
+            EventVersion::V1 => "handle",
+        };
+        let fields = match listing.nested_fields {
+            0 => listing.fields.to_string(),
+            nested => format!("{} (+{} nested)", listing.fields, nested),
+        };
+        let status = match &listing.tables {
+            Some(tables) if tables.is_empty() => "unmapped".to_string(),
+            Some(tables) => format!("mapped: {}", tables.join(", ")),
+            None => String::new(),
+        };
+        rows.push(vec![
+            package,
+            listing.event.clone(),
+            kind.to_string(),
+            fields,
+            status,
+        ]);
+    }
+    if !with_status {
+        for row in &mut rows {
+            row.pop();
+        }
+    }
+    let columns = rows[0].len();
+    let widths = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let mut lines = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    if with_status {
+        let mapped = listings
+            .iter()
+            .filter(|listing| listing.mapped() == Some(true))
+            .count();
+        lines.push(String::new());
+        lines.push(format!("{} of {} events mapped", mapped, listings.len()));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,81 @@
+use crate::config::{GitSource, YeaptorConfig};
+use crate::error::{YeaptorError, YeaptorResult};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+/// Check out every `git` package source of `config` missing from `cache_dir`, each repository
+/// once per revision, and return the checkouts made. A checkout already in the cache is reused
+/// as is, without contacting the remote.
+pub fn fetch_git_sources(config: &YeaptorConfig, cache_dir: &Path) -> YeaptorResult<Vec<PathBuf>> {
+    let sources = config
+        .deployments
+        .iter()
+        .flat_map(|deployment| &deployment.packages)
+        .filter_map(|package| package.git.as_ref())
+        .map(|git| (git.checkout_dir(cache_dir), git))
+        .collect::<BTreeMap<_, _>>();
+    let mut fetched = Vec::new();
+    for (checkout, git) in sources {
+        if checkout.join(".git").is_dir() {
+            continue;
+        }
+        fetch_git_source(git, &checkout).map_err(|e| YeaptorError::io(&checkout, e))?;
+        info!(url = %git.url, rev = %git.rev, checkout = %checkout.display(), "fetched git source");
+        fetched.push(checkout);
+    }
+    Ok(fetched)
+}
+
+/// Fetch `rev` of `git` alone into a scratch directory next to `checkout`, then move it in
+/// place, so an interrupted fetch never leaves a partial checkout in the cache
+fn fetch_git_source(git: &GitSource, checkout: &Path) -> anyhow::Result<()> {
+    let (Some(parent), Some(name)) = (checkout.parent(), checkout.file_name()) else {
+        anyhow::bail!("not a checkout directory");
+    };
+    fs::create_dir_all(parent)?;
+    let scratch = parent.join(format!(
+        "{}.partial-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch)?;
+    }
+    fs::create_dir_all(&scratch)?;
+    let result = (|| {
+        run_git(&scratch, &["init", "--quiet"])?;
+        run_git(&scratch, &["remote", "add", "--", "origin", &git.url])?;
+        run_git(
+            &scratch,
+            &["fetch", "--quiet", "--depth", "1", "--", "origin", &git.rev],
+        )
+        .with_context(|| format!("failed to fetch {} at `{}`", git.url, git.rev))?;
+        run_git(&scratch, &["checkout", "--quiet", "--detach", "FETCH_HEAD"])?;
+        fs::rename(&scratch, checkout)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&scratch);
+    }
+    result
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git; is it installed and on PATH?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
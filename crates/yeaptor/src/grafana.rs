@@ -0,0 +1,149 @@
+use crate::db_schema::SchemaSnapshot;
+use crate::ddl::quote_identifier;
+use crate::processor_config::TableSchema;
+use crate::processor_runtime::PROCESSOR_STATUS_TABLE;
+use anyhow::{Result, bail};
+use serde_json::{Value as JsonValue, json};
+
+/// Rows shown by the recent rows panel of each table
+const RECENT_ROWS: u32 = 20;
+
+/// Grafana dashboard schema version the dashboards are written in
+const GRAFANA_SCHEMA_VERSION: u32 = 39;
+
+/// Datasource plugin the panels query; the datasource itself is picked on import through the
+/// `datasource` variable
+const POSTGRES_DATASOURCE: &str = "grafana-postgresql-datasource";
+
+/// Grid width of a Grafana dashboard
+const GRID_WIDTH: u32 = 24;
+
+/// Render a starter Grafana dashboard (JSON model) for the indexed tables, reading the Postgres
+/// database `processor run` writes to:
+///
+/// - ingestion lag of every processor, from `yeaptor_processor_status`: seconds between now and
+///   the last committed transaction, and the last committed version
+/// - row counts per table, from the Postgres statistics (`pg_stat_user_tables`), so large tables
+///   are not scanned
+/// - the most recent rows of each table, newest first by transaction version and event index when
+///   it has them, else by primary key
+pub fn grafana_dashboard(schema: &SchemaSnapshot, title: &str) -> Result<JsonValue> {
+    if schema.tables.is_empty() {
+        bail!("the schema declares no table");
+    }
+    let datasource = json!({ "type": POSTGRES_DATASOURCE, "uid": "${datasource}" });
+    let mut panels = Vec::new();
+    let mut panel = |kind: &str, title: String, sql: String, (w, h, x, y): (u32, u32, u32, u32)| {
+        panels.push(json!({
+            "id": panels.len() + 1,
+            "type": kind,
+            "title": title,
+            "datasource": datasource,
+            "gridPos": { "h": h, "w": w, "x": x, "y": y },
+            "targets": [{
+                "refId": "A",
+                "datasource": datasource,
+                "editorMode": "code",
+                "format": "table",
+                "rawQuery": true,
+                "rawSql": sql,
+            }],
+        }));
+    };
+
+    let status = quote_identifier(PROCESSOR_STATUS_TABLE);
+    panel(
+        "stat",
+        "Ingestion lag (seconds)".to_string(),
+        format!(
+            "SELECT \"processor\", EXTRACT(EPOCH FROM (NOW() AT TIME ZONE 'UTC' - \
+             \"last_transaction_timestamp\")) AS \"lag_seconds\" FROM {} ORDER BY \"processor\"",
+            status
+        ),
+        (GRID_WIDTH / 2, 6, 0, 0),
+    );
+    panel(
+        "table",
+        "Last committed version".to_string(),
+        format!(
+            "SELECT \"processor\", \"last_success_version\", \"last_transaction_timestamp\", \
+             \"last_updated\" FROM {} ORDER BY \"processor\"",
+            status
+        ),
+        (GRID_WIDTH / 2, 6, GRID_WIDTH / 2, 0),
+    );
+    let tables = schema
+        .tables
+        .keys()
+        .map(|table| format!("'{}'", table.replace('\'', "''")))
+        .collect::<Vec<_>>();
+    panel(
+        "bargauge",
+        "Rows per table".to_string(),
+        format!(
+            "SELECT \"relname\" AS \"table\", \"n_live_tup\" AS \"rows\" FROM pg_stat_user_tables \
+             WHERE \"schemaname\" = current_schema() AND \"relname\" IN ({}) ORDER BY \"relname\"",
+            tables.join(", ")
+        ),
+        (GRID_WIDTH, 8, 0, 6),
+    );
+    for (i, (table, columns)) in schema.tables.iter().enumerate() {
+        let order = recent_order(columns);
+        panel(
+            "table",
+            format!("Recent rows: {}", table),
+            format!(
+                "SELECT * FROM {}{} LIMIT {}",
+                quote_identifier(table),
+                if order.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ORDER BY {}", order.join(", "))
+                },
+                RECENT_ROWS
+            ),
+            (GRID_WIDTH, 8, 0, 14 + 8 * i as u32),
+        );
+    }
+
+    Ok(json!({
+        "title": title,
+        "tags": ["yeaptor"],
+        "editable": true,
+        "schemaVersion": GRAFANA_SCHEMA_VERSION,
+        "refresh": "1m",
+        "time": { "from": "now-6h", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Postgres",
+                "type": "datasource",
+                "query": POSTGRES_DATASOURCE,
+            }],
+        },
+        "panels": panels,
+    }))
+}
+
+/// `ORDER BY` terms putting the newest rows of a table first
+fn recent_order(columns: &TableSchema) -> Vec<String> {
+    let metadata = |kind: &str, name: &str| {
+        columns
+            .iter()
+            .filter(|(_, spec)| {
+                spec.column_type.r#type == kind && spec.column_type.column_type == name
+            })
+            .map(|(column, _)| format!("{} DESC", quote_identifier(column)))
+            .collect::<Vec<_>>()
+    };
+    let mut order = metadata("transaction_metadata", "version");
+    order.extend(metadata("event_metadata", "event_index"));
+    if order.is_empty() {
+        order = columns
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .map(|(column, _)| format!("{} DESC", quote_identifier(column)))
+            .collect();
+    }
+    order
+}
@@ -0,0 +1,182 @@
+use crate::db_schema::SchemaSnapshot;
+use crate::processor_config::{ColumnSpec, TableSchema};
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeSet;
+
+/// Page size of list queries when no `limit` is given
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Scalars beyond the GraphQL built-ins; both are serialized as strings
+const CUSTOM_SCALARS: &str = r#""""Unsigned integer beyond 32 bits (u32..u256, versions, heights), as a decimal string"""
+scalar BigInt
+
+"""UTC timestamp in ISO 8601"""
+scalar Timestamp
+"#;
+
+/// Render a GraphQL SDL schema describing the indexed tables, for a generic GraphQL-over-Postgres
+/// server.
+///
+/// Every table gets an object type (PascalCase of the table, fields named after the columns), a
+/// `<table>` list query filterable (`where`) and sortable (`order_by`) on its primary key and
+/// indexed columns, paginated with `limit`/`offset`, and a `<table>_by_pk` lookup when it has a
+/// primary key.
+pub fn graphql_schema(schema: &SchemaSnapshot) -> Result<String> {
+    let mut types = String::new();
+    let mut queries = Vec::new();
+    let mut filters = BTreeSet::new();
+    for (table, columns) in &schema.tables {
+        let type_name = graphql_type_name(table);
+        let indexed = indexed_columns(columns, schema, table);
+
+        types.push_str(&format!("type {} {{\n", type_name));
+        for (column, spec) in columns {
+            let field_type = graphql_field_type(spec)
+                .with_context(|| format!("column {}::{}", table, column))?;
+            types.push_str(&format!("  {}: {}\n", column, field_type));
+        }
+        types.push_str("}\n\n");
+
+        let mut filter_fields = Vec::new();
+        let mut order_fields = Vec::new();
+        for column in &indexed {
+            let scalar = graphql_scalar(&columns[*column])?;
+            let filter = format!("{}Filter", scalar);
+            filter_fields.push(format!("  {}: {}\n", column, filter));
+            filters.insert(scalar);
+            order_fields.push(format!("  {}: OrderDirection\n", column));
+        }
+        let mut arguments = vec![
+            format!("limit: Int = {}", DEFAULT_PAGE_SIZE),
+            "offset: Int = 0".to_string(),
+        ];
+        if !filter_fields.is_empty() {
+            types.push_str(&format!(
+                "\"\"\"Filter on the indexed columns of {}; conditions are combined with AND\"\"\"\ninput {}Filter {{\n{}}}\n\n",
+                table,
+                type_name,
+                filter_fields.concat()
+            ));
+            types.push_str(&format!(
+                "input {}OrderBy {{\n{}}}\n\n",
+                type_name,
+                order_fields.concat()
+            ));
+            arguments.insert(0, format!("where: {}Filter", type_name));
+            arguments.insert(1, format!("order_by: [{}OrderBy!]", type_name));
+        }
+        queries.push(format!(
+            "  {}({}): [{}!]!\n",
+            table,
+            arguments.join(", "),
+            type_name
+        ));
+
+        let primary_key = columns
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .map(|(column, spec)| Ok(format!("{}: {}!", column, graphql_scalar(spec)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if !primary_key.is_empty() {
+            queries.push(format!(
+                "  {}_by_pk({}): {}\n",
+                table,
+                primary_key.join(", "),
+                type_name
+            ));
+        }
+    }
+
+    let mut out =
+        String::from("# @generated by `yeaptor processor graphql` from the DB schema\n\n");
+    out.push_str(CUSTOM_SCALARS);
+    out.push_str("\nenum OrderDirection {\n  asc\n  desc\n}\n\n");
+    for scalar in &filters {
+        out.push_str(&filter_input(scalar));
+    }
+    out.push_str(&types);
+    out.push_str(&format!("type Query {{\n{}}}\n", queries.concat()));
+    Ok(out)
+}
+
+/// Primary key, `is_index` and declared index columns; vectors cannot be filtered or sorted on
+pub(crate) fn indexed_columns<'a>(
+    columns: &'a TableSchema,
+    schema: &'a SchemaSnapshot,
+    table: &str,
+) -> BTreeSet<&'a String> {
+    let declared = schema
+        .indexes(table)
+        .iter()
+        .flat_map(|index| index.columns.iter())
+        .collect::<BTreeSet<_>>();
+    columns
+        .iter()
+        .filter(|(column, spec)| {
+            !spec.is_vec && (spec.is_primary_key || spec.is_index || declared.contains(column))
+        })
+        .map(|(column, _)| column)
+        .collect()
+}
+
+fn filter_input(scalar: &str) -> String {
+    let operators: &[&str] = match scalar {
+        "Boolean" => &["eq"],
+        "String" => &["eq", "neq", "in"],
+        _ => &["eq", "neq", "gt", "gte", "lt", "lte", "in"],
+    };
+    let fields = operators
+        .iter()
+        .map(|op| match *op {
+            "in" => format!("  in: [{}!]\n", scalar),
+            op => format!("  {}: {}\n", op, scalar),
+        })
+        .collect::<String>();
+    format!("input {}Filter {{\n{}}}\n\n", scalar, fields)
+}
+
+/// `borrow_position` -> `BorrowPosition`
+fn graphql_type_name(table: &str) -> String {
+    table
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn graphql_field_type(spec: &ColumnSpec) -> Result<String> {
+    let scalar = graphql_scalar(spec)?;
+    let field_type = if spec.is_vec {
+        format!("[{}!]", scalar)
+    } else {
+        scalar.to_string()
+    };
+    if !spec.is_primary_key && (spec.is_nullable || spec.is_option) {
+        Ok(field_type)
+    } else {
+        Ok(format!("{}!", field_type))
+    }
+}
+
+/// GraphQL `Int` is a signed 32 bit integer, so only `u8` and `u16` fit
+fn graphql_scalar(spec: &ColumnSpec) -> Result<&'static str> {
+    let column_type = spec.column_type.column_type.as_str();
+    Ok(match (spec.column_type.r#type.as_str(), column_type) {
+        ("move_type", "u8" | "u16") => "Int",
+        ("move_type", "u32" | "u64" | "u128" | "u256") => "BigInt",
+        // decimals do not fit a float without losing digits
+        ("move_type", "address" | "object" | "string" | "decimal") => "String",
+        ("move_type", "bool") => "Boolean",
+        ("transaction_metadata", "version" | "block_height" | "epoch") => "BigInt",
+        ("transaction_metadata", "timestamp") => "Timestamp",
+        ("event_metadata", "creation_number" | "sequence_number" | "event_index") => "BigInt",
+        ("event_metadata", "account_address" | "event_type") => "String",
+        (kind, _) => bail!("no GraphQL type for {} {}", kind, column_type),
+    })
+}
@@ -1,41 +1,144 @@
-pub mod config;
 pub mod env;
-pub mod processor_config;
-pub mod processor_config_generator;
+pub mod error;
+pub mod output;
+pub mod payload_format;
+pub mod payload_inspection;
+pub mod preflight;
+pub mod processor_config_builder;
+pub mod processor_config_lint;
+pub mod processor_export;
+#[cfg(feature = "kafka")]
+pub mod processor_kafka;
+pub mod processor_live;
+pub mod processor_metrics;
+pub mod processor_pipeline;
+pub mod processor_preview;
+pub mod processor_runtime;
+pub mod processor_snapshot;
+pub mod progress;
+pub mod prompt;
+pub mod sbom;
+pub mod seed_search;
 pub mod version;
 
-use crate::tools::{deployment, event, indexer};
-use clap::Parser;
+use crate::output::{CommandOutput, OutputFormat};
+use crate::prompt::Confirmation;
+use crate::tools::{artifacts, completions, deployment, event, indexer};
+use clap::{Parser, Subcommand};
 
-pub mod db_schema;
+pub mod address_explain;
+pub mod artifact_signature;
+pub mod audit;
+pub mod bigquery;
+pub mod clean;
+pub mod cost_report;
+pub mod dbt;
+pub mod ddl;
+pub mod deployment_list;
+pub mod deployment_planner;
+pub mod doctor;
 pub mod event_definition;
-pub mod event_table_mapping;
+pub mod event_extractor;
+pub mod event_listing;
+pub mod git_source;
+pub mod grafana;
+pub mod graphql;
+pub mod logging;
+pub mod mapping_engine;
+pub mod mapping_wizard;
+pub mod metadata_decoder;
+pub mod metadata_normalization;
+pub mod migrations;
+pub mod module_verifier;
+pub mod offline_signing;
+pub mod rest_api;
+pub mod source_diff;
 pub mod tools;
-pub type CliResult = Result<String, String>;
+pub mod verification_bundle;
+pub use deployment_planner::{DeploymentPlan, DeploymentPlanner, PlannedPackage};
+pub use error::{YeaptorError, YeaptorResult};
+pub use event_extractor::EventExtractor;
+pub use payload_format::{PayloadFormatter, PayloadFormatters};
+pub use processor_config_builder::{GeneratedProcessorConfig, ProcessorConfigBuilder};
+pub use yeaptor_core::{
+    config, db_schema, diagnostics, event_fixtures, event_table_mapping, lockfile,
+    processor_config, processor_config_generator, standard_templates, template_variables,
+};
+
+/// Output of a command, or the classified failure
+pub type CliResult = Result<CommandOutput, YeaptorError>;
 
 #[derive(Parser)]
 #[clap(name = "yeaptor", author, version, propagate_version = true, styles = aptos_cli_common::aptos_cli_style())]
+pub struct Yeaptor {
+    /// Print the result (files written, addresses derived, warnings) or the error as text or as one JSON object
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+    /// Log more to stderr: `-v` progress, `-vv` debug details, `-vvv` everything. `RUST_LOG` overrides it
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Write logs as JSON lines
+    #[clap(long, global = true)]
+    pub log_json: bool,
+    #[clap(flatten)]
+    pub confirmation: Confirmation,
+    #[clap(subcommand)]
+    pub tool: YeaptorTool,
+}
+
+#[derive(Subcommand)]
 pub enum YeaptorTool {
     /// Build publish payloads and optional event files from yeaptor.toml deployments
     #[clap(subcommand)]
     Deployment(deployment::DeploymentTool),
+    /// Sign build outputs, or verify their signature before submitting them
+    #[clap(subcommand)]
+    Artifacts(artifacts::ArtifactsTool),
     /// Generate event definition JSON from compiled Move packages
     #[clap(subcommand)]
     Event(event::EventTool),
-    /// Run the processor/indexer using the configured schema and mappings
+    /// Inspect the yeaptor.toml format
+    #[clap(subcommand)]
+    Config(tools::config::ConfigTool),
+    /// Decode BCS blobs such as package metadata
+    #[clap(subcommand)]
+    Decode(tools::decode::DecodeTool),
+    /// Generate processor configs and database artifacts, or run the processor/indexer
     #[clap(subcommand)]
     Processor(indexer::ProcessorTool),
+    /// Check the network, aptos profile, git, packages, Move compiler and disk space, printing
+    /// a fix for every problem
+    Doctor(tools::doctor::DoctorTool),
+    /// Remove payloads, event files, addresses.toml and other build outputs, and the Move
+    /// `build/` directories of the packages
+    Clean(tools::clean::CleanTool),
+    /// Show how an address of yeaptor.toml is derived (publisher, seed bytes, scheme) or where
+    /// it is declared
+    ExplainAddress(tools::explain_address::ExplainAddressTool),
     /// Print build and git version information
     Version(version::VersionTool),
+    /// Print a shell completion script (bash, zsh, fish, elvish, powershell)
+    Completions(completions::CompletionsTool),
 }
 
 impl YeaptorTool {
-    pub async fn execute(self) -> CliResult {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
         match self {
-            YeaptorTool::Deployment(tool) => tool.execute().await,
+            YeaptorTool::Deployment(tool) => tool.execute(confirmation).await,
+            YeaptorTool::Artifacts(tool) => tool.execute(confirmation).await,
+            YeaptorTool::Doctor(tool) => tool.execute().await,
+            YeaptorTool::Clean(tool) => tool.execute(confirmation).await,
+            YeaptorTool::ExplainAddress(tool) => tool.execute().await,
             YeaptorTool::Version(tool) => tool.execute().await,
-            YeaptorTool::Event(tool) => tool.execute().await,
-            YeaptorTool::Processor(tool) => tool.execute().await,
+            YeaptorTool::Event(tool) => tool.execute(confirmation).await,
+            YeaptorTool::Config(tool) => tool.execute().await,
+            YeaptorTool::Decode(tool) => tool.execute().await,
+            YeaptorTool::Processor(tool) => tool.execute(confirmation).await,
+            // `main` writes the script before rendering results; here it goes to stdout as well
+            YeaptorTool::Completions(tool) => {
+                tool.write(&mut std::io::stdout())?;
+                Ok(CommandOutput::default())
+            }
         }
     }
 }
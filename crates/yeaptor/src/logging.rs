@@ -0,0 +1,27 @@
+use tracing_subscriber::EnvFilter;
+
+/// Level of yeaptor's own logs for a `-v` count; other crates only log warnings
+pub fn log_level(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
+
+/// Log to stderr, so stdout only carries the command output. `RUST_LOG` takes precedence over
+/// `verbosity`; `json` writes one JSON object per event, with its span fields.
+pub fn init_logging(verbosity: u8, json: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("warn,yeaptor={}", log_level(verbosity))));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    // A subscriber set earlier (e.g. by an embedding application) is kept
+    let _ = if json {
+        builder.json().with_current_span(true).try_init()
+    } else {
+        builder.try_init()
+    };
+}
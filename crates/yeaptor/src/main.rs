@@ -10,7 +10,9 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 use aptos::move_tool;
 use clap::Parser;
 use std::{process::exit, time::Duration};
-use yeaptor::YeaptorTool;
+use yeaptor::logging::init_logging;
+use yeaptor::output::render_error;
+use yeaptor::{Yeaptor, YeaptorTool};
 
 fn main() {
     // Register hooks.
@@ -23,7 +25,23 @@ fn main() {
         .unwrap();
 
     // Run the corresponding tool.
-    let result = runtime.block_on(YeaptorTool::parse().execute());
+    let Yeaptor {
+        format,
+        verbose,
+        log_json,
+        confirmation,
+        tool,
+    } = Yeaptor::parse();
+    // The completion script is printed as it is, whatever `--format` says
+    if let YeaptorTool::Completions(completions) = tool {
+        if let Err(e) = completions.write(&mut std::io::stdout()) {
+            println!("{}", render_error(&e, format));
+            exit(1);
+        }
+        return;
+    }
+    init_logging(verbose, log_json);
+    let result = runtime.block_on(tool.execute(confirmation));
 
     // Shutdown the runtime with a timeout. We do this to make sure that we don't sit
     // here waiting forever waiting for tasks that sometimes don't want to exit on
@@ -31,9 +49,9 @@ fn main() {
     runtime.shutdown_timeout(Duration::from_millis(50));
 
     match result {
-        Ok(inner) => println!("{}", inner),
+        Ok(inner) => println!("{}", inner.render(format)),
         Err(inner) => {
-            println!("{}", inner);
+            println!("{}", render_error(&inner, format));
             exit(1);
         }
     }
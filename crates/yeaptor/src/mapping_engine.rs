@@ -0,0 +1,794 @@
+use crate::event_table_mapping::{normalize_move_type, parse_entry_function_id};
+use crate::processor_config::{
+    ColumnSpec, ColumnTarget, ColumnTransform, ComputeArg, ComputeFunction, ComputedColumn,
+    ConstantValue, EntryFunctionMapping, EventFilter, EventMapping, FilterOp, ProcessorConfig,
+    TableItemMapping, TableSchema, Transform,
+};
+use crate::processor_config_generator::{
+    COLUMN_TRANSFORMS, COMPUTED_COLUMNS, ENTRY_FUNCTIONS, TABLE_ITEMS,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use aptos_types::account_address::AccountAddress;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use sha3::{Digest, Sha3_256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A committed transaction and its events, independent of where it was read from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionRecord {
+    pub version: u64,
+    pub block_height: u64,
+    pub epoch: u64,
+    /// Microseconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(default)]
+    pub events: Vec<EventRecord>,
+    /// Entry function the transaction called; None for other payloads and system transactions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<EntryFunctionCall>,
+    /// Move table items the transaction wrote, in write set order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub table_items: Vec<TableItemRecord>,
+}
+
+/// A user transaction's entry function payload, as `entry_functions` mappings see it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntryFunctionCall {
+    pub sender: String,
+    /// `0x42::market::supply`
+    pub function: String,
+    /// Move types, e.g. `0x1::aptos_coin::AptosCoin`
+    #[serde(default)]
+    pub type_arguments: Vec<String>,
+    /// Arguments as JSON; `u64` and wider integers are decimal strings
+    #[serde(default)]
+    pub arguments: Vec<JsonValue>,
+}
+
+/// A write of a Move table item, as `table_items` mappings see it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableItemRecord {
+    /// Address of the table
+    pub handle: String,
+    /// Move type of the keys, e.g. `address`
+    pub key_type: String,
+    /// Key as JSON, decoded like event fields
+    pub key: JsonValue,
+    /// Move type of the values, e.g. `0x42::market::Position`
+    pub value_type: String,
+    /// Value as JSON, decoded like event fields
+    pub value: JsonValue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventRecord {
+    pub account_address: String,
+    pub creation_number: u64,
+    pub sequence_number: u64,
+    /// Move type of the event, e.g. `0x42::market::MarketCreatedEvent`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Event fields as JSON; `u64` and wider integers are decimal strings
+    pub data: JsonValue,
+}
+
+/// Transactions of a JSON Lines file, one `TransactionRecord` per line; blank lines are skipped
+pub fn load_transactions_jsonl(path: &Path) -> Result<Vec<TransactionRecord>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read transactions: {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| {
+                format!(
+                    "invalid transaction on line {} of {}",
+                    index + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// A row produced by the mapping: column -> value as text, None for NULL. Vector columns hold a
+/// JSON array; the writer converts values to the column types.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Row {
+    pub table: String,
+    /// Mapped event the row comes from, as named in the config (`0x42::market::MarketCreatedEvent`),
+    /// the entry function for rows of `entry_functions` mappings (`0x42::market::supply`), or the
+    /// value type for rows of `table_items` mappings (`0x42::market::Position`)
+    pub event: String,
+    pub values: BTreeMap<String, Option<String>>,
+}
+
+/// A mapped field a transaction has no value at (absent or JSON null), which leaves the column
+/// it maps to NULL or at its default
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MissingField {
+    pub table: String,
+    pub column: String,
+    /// Mapped event, entry function or table item value type the field was looked up in
+    pub source: String,
+    /// Path of the field, e.g. `$.fee.value`
+    pub path: String,
+}
+
+/// `0x42::market::MarketCreatedEvent`, with the address parsed so short and long forms match
+type EventKey = (AccountAddress, String, String);
+
+/// Applies the event -> table mappings of a processor config to transactions, the way the hosted
+/// remapping processor does: every mapped event yields one row per table it writes to, unless a
+/// filter on that table rejects it. A call of a mapped entry function yields one row per table
+/// too, before the rows of its events, and every table item write a mapping matches one row per
+/// table after them.
+pub struct MappingEngine {
+    tables: BTreeMap<String, TableSchema>,
+    events: BTreeMap<EventKey, (String, EventMapping)>,
+    computed: BTreeMap<EventKey, Vec<ComputedColumn>>,
+    transforms: BTreeMap<EventKey, Vec<ColumnTransform>>,
+    entry_functions: BTreeMap<EventKey, EntryFunctionMapping>,
+    table_items: Vec<TableItemMapping>,
+    transaction_metadata: BTreeMap<String, Vec<ColumnTarget>>,
+    event_metadata: BTreeMap<String, Vec<ColumnTarget>>,
+}
+
+impl MappingEngine {
+    pub fn new(config: &ProcessorConfig) -> Result<Self> {
+        let custom = &config.custom_config;
+        let mut events = BTreeMap::new();
+        for (event, mapping) in &custom.events {
+            events.insert(event_key(event)?, (event.clone(), mapping.clone()));
+        }
+        let mut computed: BTreeMap<EventKey, Vec<ComputedColumn>> = BTreeMap::new();
+        if let Some(value) = custom.payload.get(COMPUTED_COLUMNS) {
+            let columns: Vec<ComputedColumn> = serde_yaml::from_value(value.clone())
+                .context("invalid payload.computed_columns")?;
+            for column in columns {
+                computed
+                    .entry(event_key(&column.event)?)
+                    .or_default()
+                    .push(column);
+            }
+        }
+        let mut transforms: BTreeMap<EventKey, Vec<ColumnTransform>> = BTreeMap::new();
+        if let Some(value) = custom.payload.get(COLUMN_TRANSFORMS) {
+            let columns: Vec<ColumnTransform> = serde_yaml::from_value(value.clone())
+                .context("invalid payload.column_transforms")?;
+            for column in columns {
+                transforms
+                    .entry(event_key(&column.event)?)
+                    .or_default()
+                    .push(column);
+            }
+        }
+        let mut entry_functions = BTreeMap::new();
+        if let Some(value) = custom.payload.get(ENTRY_FUNCTIONS) {
+            let mappings: Vec<EntryFunctionMapping> =
+                serde_yaml::from_value(value.clone()).context("invalid payload.entry_functions")?;
+            for mapping in mappings {
+                entry_functions.insert(parse_entry_function_id(&mapping.function)?, mapping);
+            }
+        }
+        let table_items = match custom.payload.get(TABLE_ITEMS) {
+            Some(value) => {
+                serde_yaml::from_value(value.clone()).context("invalid payload.table_items")?
+            }
+            None => Vec::new(),
+        };
+        let engine = MappingEngine {
+            tables: custom.db_schema.clone(),
+            events,
+            computed,
+            transforms,
+            entry_functions,
+            table_items,
+            transaction_metadata: custom.transaction_metadata.clone(),
+            event_metadata: custom.event_metadata.clone(),
+        };
+        for (event, mapping) in engine.events.values() {
+            for table in engine.mapped_tables(mapping, &[]) {
+                if !engine.tables.contains_key(&table) {
+                    bail!("event {} is mapped to unknown table {}", event, table);
+                }
+            }
+        }
+        for mapping in engine.entry_functions.values() {
+            for table in payload_tables(&mapping.payload_fields, &mapping.constant_values) {
+                if !engine.tables.contains_key(&table) {
+                    bail!(
+                        "entry function {} is mapped to unknown table {}",
+                        mapping.function,
+                        table
+                    );
+                }
+            }
+        }
+        for mapping in &engine.table_items {
+            for table in payload_tables(&mapping.payload_fields, &mapping.constant_values) {
+                if !engine.tables.contains_key(&table) {
+                    bail!(
+                        "table items of {} are mapped to unknown table {}",
+                        mapping.value_type,
+                        table
+                    );
+                }
+            }
+        }
+        Ok(engine)
+    }
+
+    pub fn tables(&self) -> &BTreeMap<String, TableSchema> {
+        &self.tables
+    }
+
+    /// Rows written for a sequence of transactions, in order
+    pub fn map_transactions(&self, transactions: &[TransactionRecord]) -> Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        for txn in transactions {
+            rows.extend(self.map_transaction(txn)?);
+        }
+        Ok(rows)
+    }
+
+    /// Rows written for the entry function call of a transaction, then for its events in event
+    /// order, then for its table item writes in write set order
+    pub fn map_transaction(&self, txn: &TransactionRecord) -> Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        let called = txn.payload.as_ref().and_then(|call| {
+            let key = parse_entry_function_id(&call.function).ok()?;
+            Some((call, self.entry_functions.get(&key)?))
+        });
+        if let Some((call, mapping)) = called {
+            let data = serde_json::to_value(call)?;
+            for table in payload_tables(&mapping.payload_fields, &mapping.constant_values) {
+                let values = self
+                    .map_payload(
+                        txn,
+                        &data,
+                        &table,
+                        &mapping.payload_fields,
+                        &mapping.constant_values,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "failed to map call of {} in version {} to table {}",
+                            mapping.function, txn.version, table
+                        )
+                    })?;
+                rows.push(Row {
+                    table,
+                    event: mapping.function.clone(),
+                    values,
+                });
+            }
+        }
+        for (index, event) in txn.events.iter().enumerate() {
+            let Ok(key) = event_key(&event.event_type) else {
+                continue;
+            };
+            let Some((name, mapping)) = self.events.get(&key) else {
+                continue;
+            };
+            let computed = self.computed.get(&key).map(Vec::as_slice).unwrap_or(&[]);
+            for table in self.mapped_tables(mapping, computed) {
+                let values = self
+                    .map_event(txn, index as u64, event, &table, mapping, &key)
+                    .with_context(|| {
+                        format!(
+                            "failed to map event {} #{} of version {} to table {}",
+                            name, index, txn.version, table
+                        )
+                    })?;
+                rows.extend(values.map(|values| Row {
+                    table: table.clone(),
+                    event: name.clone(),
+                    values,
+                }));
+            }
+        }
+        for (index, item) in txn.table_items.iter().enumerate() {
+            let data = serde_json::to_value(item)?;
+            for mapping in self.table_item_mappings(item) {
+                for table in payload_tables(&mapping.payload_fields, &mapping.constant_values) {
+                    let values = self
+                        .map_payload(
+                            txn,
+                            &data,
+                            &table,
+                            &mapping.payload_fields,
+                            &mapping.constant_values,
+                        )
+                        .with_context(|| {
+                            format!(
+                                "failed to map table item #{} of version {} to table {}",
+                                index, txn.version, table
+                            )
+                        })?;
+                    rows.push(Row {
+                        table,
+                        event: mapping.value_type.clone(),
+                        values,
+                    });
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Whether an event of this type (`0x42::market::MarketCreatedEvent`) is mapped
+    pub fn is_mapped_event(&self, event_type: &str) -> bool {
+        event_key(event_type).is_ok_and(|key| self.events.contains_key(&key))
+    }
+
+    /// Mapped fields the transaction has no value at, for the rows `map_transaction` writes;
+    /// explains their NULL columns. Tables whose filter rejects an event are left out.
+    pub fn missing_fields(&self, txn: &TransactionRecord) -> Result<Vec<MissingField>> {
+        let mut missing = Vec::new();
+        let called = txn.payload.as_ref().and_then(|call| {
+            let key = parse_entry_function_id(&call.function).ok()?;
+            Some((call, self.entry_functions.get(&key)?))
+        });
+        if let Some((call, mapping)) = called {
+            let data = serde_json::to_value(call)?;
+            missing_in(
+                &mapping.function,
+                &data,
+                &mapping.payload_fields,
+                |_| true,
+                &mut missing,
+            );
+        }
+        for event in &txn.events {
+            let Ok(key) = event_key(&event.event_type) else {
+                continue;
+            };
+            let Some((name, mapping)) = self.events.get(&key) else {
+                continue;
+            };
+            let mut rejected = BTreeSet::new();
+            for filter in &mapping.filters {
+                if !filter_matches(filter, &event.data)? {
+                    rejected.insert(filter.table.as_str());
+                }
+            }
+            missing_in(
+                name,
+                &event.data,
+                &mapping.event_fields,
+                |table| !rejected.contains(table),
+                &mut missing,
+            );
+        }
+        for item in &txn.table_items {
+            let data = serde_json::to_value(item)?;
+            for mapping in self.table_item_mappings(item) {
+                missing_in(
+                    &mapping.value_type,
+                    &data,
+                    &mapping.payload_fields,
+                    |_| true,
+                    &mut missing,
+                );
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Mappings matching the value type, key type and handle of a table item write
+    fn table_item_mappings<'a>(
+        &'a self,
+        item: &TableItemRecord,
+    ) -> impl Iterator<Item = &'a TableItemMapping> {
+        let value_type = normalize_move_type(&item.value_type).ok();
+        let key_type = normalize_move_type(&item.key_type).ok();
+        let handle = AccountAddress::from_str(&item.handle).ok();
+        self.table_items.iter().filter(move |mapping| {
+            value_type.as_ref() == Some(&mapping.value_type)
+                && mapping
+                    .key_type
+                    .as_ref()
+                    .is_none_or(|k| key_type.as_ref() == Some(k))
+                && mapping
+                    .handle
+                    .as_ref()
+                    .is_none_or(|h| handle.is_some() && AccountAddress::from_str(h).ok() == handle)
+        })
+    }
+
+    fn mapped_tables(
+        &self,
+        mapping: &EventMapping,
+        computed: &[ComputedColumn],
+    ) -> BTreeSet<String> {
+        mapping
+            .event_fields
+            .values()
+            .chain(mapping.event_metadata.values())
+            .flatten()
+            .map(|target| target.table.clone())
+            .chain(mapping.constant_values.iter().map(|c| c.table.clone()))
+            .chain(computed.iter().map(|c| c.table.clone()))
+            .collect()
+    }
+
+    /// Values of the row the event writes to `table`; None when a filter on the table rejects it
+    fn map_event(
+        &self,
+        txn: &TransactionRecord,
+        event_index: u64,
+        event: &EventRecord,
+        table: &str,
+        mapping: &EventMapping,
+        key: &EventKey,
+    ) -> Result<Option<BTreeMap<String, Option<String>>>> {
+        for filter in mapping.filters.iter().filter(|f| f.table == table) {
+            if !filter_matches(filter, &event.data)? {
+                return Ok(None);
+            }
+        }
+        let schema = &self.tables[table];
+        let mut values = BTreeMap::new();
+        let mut set = |column: &str, value: &JsonValue| -> Result<()> {
+            let spec = schema
+                .get(column)
+                .ok_or_else(|| anyhow!("unknown column {}", column))?;
+            let text = column_text(spec, value).with_context(|| format!("column {}", column))?;
+            values.insert(column.to_string(), text);
+            Ok(())
+        };
+        let transforms = self.transforms.get(key).map(Vec::as_slice).unwrap_or(&[]);
+        for (path, targets) in &mapping.event_fields {
+            for target in targets.iter().filter(|t| t.table == table) {
+                let value = lookup(&event.data, path).unwrap_or(&JsonValue::Null);
+                let transform = transforms
+                    .iter()
+                    .find(|t| t.table == table && t.column == target.column);
+                match transform {
+                    Some(transform) => {
+                        let value = apply_transform(transform.transform, value)
+                            .with_context(|| format!("transform of column {}", target.column))?;
+                        set(&target.column, &value)?;
+                    }
+                    None => set(&target.column, value)?,
+                }
+            }
+        }
+        let event_metadata = mapping.event_metadata.iter().chain(&self.event_metadata);
+        for (field, targets) in event_metadata {
+            for target in targets.iter().filter(|t| t.table == table) {
+                set(
+                    &target.column,
+                    &event_metadata_value(field, event_index, event)?,
+                )?;
+            }
+        }
+        for (field, targets) in &self.transaction_metadata {
+            for target in targets.iter().filter(|t| t.table == table) {
+                set(&target.column, &transaction_metadata_value(field, txn)?)?;
+            }
+        }
+        for constant in mapping.constant_values.iter().filter(|c| c.table == table) {
+            set(&constant.column, &yaml_to_json(&constant.value)?)?;
+        }
+        let computed = self.computed.get(key).map(Vec::as_slice).unwrap_or(&[]);
+        for column in computed.iter().filter(|c| c.table == table) {
+            let value = compute(column, &event.data)
+                .with_context(|| format!("computed column {}", column.column))?;
+            set(&column.column, &JsonValue::String(value))?;
+        }
+        complete_row(schema, &mut values)?;
+        Ok(Some(values))
+    }
+
+    /// Values of the row an entry function call or a table item write (`data`) writes to
+    /// `table`; event metadata columns are left to their defaults
+    fn map_payload(
+        &self,
+        txn: &TransactionRecord,
+        data: &JsonValue,
+        table: &str,
+        payload_fields: &BTreeMap<String, Vec<ColumnTarget>>,
+        constant_values: &[ConstantValue],
+    ) -> Result<BTreeMap<String, Option<String>>> {
+        let schema = &self.tables[table];
+        let mut values = BTreeMap::new();
+        let mut set = |column: &str, value: &JsonValue| -> Result<()> {
+            let spec = schema
+                .get(column)
+                .ok_or_else(|| anyhow!("unknown column {}", column))?;
+            let text = column_text(spec, value).with_context(|| format!("column {}", column))?;
+            values.insert(column.to_string(), text);
+            Ok(())
+        };
+        for (path, targets) in payload_fields {
+            for target in targets.iter().filter(|t| t.table == table) {
+                let value = lookup(data, path).unwrap_or(&JsonValue::Null);
+                set(&target.column, value)?;
+            }
+        }
+        for (field, targets) in &self.transaction_metadata {
+            for target in targets.iter().filter(|t| t.table == table) {
+                set(&target.column, &transaction_metadata_value(field, txn)?)?;
+            }
+        }
+        for constant in constant_values.iter().filter(|c| c.table == table) {
+            set(&constant.column, &yaml_to_json(&constant.value)?)?;
+        }
+        complete_row(schema, &mut values)?;
+        Ok(values)
+    }
+}
+
+/// Targets in the tables `written` of the fields of `data` that have no value
+fn missing_in(
+    source: &str,
+    data: &JsonValue,
+    fields: &BTreeMap<String, Vec<ColumnTarget>>,
+    written: impl Fn(&str) -> bool,
+    missing: &mut Vec<MissingField>,
+) {
+    for (path, targets) in fields {
+        if lookup(data, path).is_some_and(|value| !value.is_null()) {
+            continue;
+        }
+        missing.extend(
+            targets
+                .iter()
+                .filter(|target| written(&target.table))
+                .map(|target| MissingField {
+                    table: target.table.clone(),
+                    column: target.column.clone(),
+                    source: source.to_string(),
+                    path: path.clone(),
+                }),
+        );
+    }
+}
+
+fn payload_tables(
+    payload_fields: &BTreeMap<String, Vec<ColumnTarget>>,
+    constant_values: &[ConstantValue],
+) -> BTreeSet<String> {
+    payload_fields
+        .values()
+        .flatten()
+        .map(|target| target.table.clone())
+        .chain(constant_values.iter().map(|c| c.table.clone()))
+        .collect()
+}
+
+/// Fill the columns nothing wrote with their defaults, failing on a non-nullable one left NULL
+fn complete_row(schema: &TableSchema, values: &mut BTreeMap<String, Option<String>>) -> Result<()> {
+    for (column, spec) in schema {
+        if values.contains_key(column) {
+            continue;
+        }
+        let default = match &spec.default_value {
+            Some(default) => column_text(spec, &yaml_to_json(default)?)?,
+            None => None,
+        };
+        values.insert(column.clone(), default);
+    }
+    for (column, spec) in schema {
+        let nullable = !spec.is_primary_key && (spec.is_nullable || spec.is_option);
+        if !nullable && values[column].is_none() {
+            bail!("no value for non-nullable column {}", column);
+        }
+    }
+    Ok(())
+}
+
+fn event_key(event_type: &str) -> Result<EventKey> {
+    // generic instances (`Event<T>`) share the mapping of the generic event
+    let base = event_type.split('<').next().unwrap_or(event_type);
+    let mut parts = base.splitn(3, "::");
+    let (Some(address), Some(module), Some(name)) = (parts.next(), parts.next(), parts.next())
+    else {
+        bail!(
+            "invalid event type {}, expected <address>::<module>::<name>",
+            event_type
+        );
+    };
+    let address = AccountAddress::from_str(address)
+        .map_err(|e| anyhow!("invalid address in event type {}: {}", event_type, e))?;
+    Ok((address, module.to_string(), name.to_string()))
+}
+
+/// `$` is the whole event, `$.a.b` the field `b` of the struct field `a`, and `$.a.0` the first
+/// element of the vector `a`
+fn lookup<'a>(data: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    let path = path.strip_prefix('$')?;
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(data, |value, segment| match value {
+            JsonValue::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+fn event_metadata_value(field: &str, event_index: u64, event: &EventRecord) -> Result<JsonValue> {
+    Ok(match field {
+        "account_address" => JsonValue::String(event.account_address.clone()),
+        "creation_number" => event.creation_number.into(),
+        "sequence_number" => event.sequence_number.into(),
+        "event_index" => event_index.into(),
+        "event_type" => JsonValue::String(event.event_type.clone()),
+        other => bail!("unknown event metadata field {}", other),
+    })
+}
+
+fn transaction_metadata_value(field: &str, txn: &TransactionRecord) -> Result<JsonValue> {
+    Ok(match field {
+        "version" => txn.version.into(),
+        "block_height" => txn.block_height.into(),
+        "epoch" => txn.epoch.into(),
+        "timestamp" => {
+            let timestamp = chrono::DateTime::from_timestamp_micros(txn.timestamp as i64)
+                .ok_or_else(|| anyhow!("invalid timestamp {}", txn.timestamp))?;
+            JsonValue::String(timestamp.format("%Y-%m-%d %H:%M:%S%.6f").to_string())
+        }
+        other => bail!("unknown transaction metadata field {}", other),
+    })
+}
+
+/// Text of a value for a column: Move `Option`s (`{"vec": [..]}`) are unwrapped for `is_option`
+/// columns, vectors stay JSON arrays and structs mapped to a scalar column are written as JSON.
+fn column_text(spec: &ColumnSpec, value: &JsonValue) -> Result<Option<String>> {
+    let value = match value.get("vec") {
+        Some(JsonValue::Array(items)) if spec.is_option => match items.as_slice() {
+            [] => return Ok(None),
+            [item] => item,
+            _ => bail!("expected an Option, got {}", value),
+        },
+        _ => value,
+    };
+    if spec.is_vec {
+        return match value {
+            JsonValue::Null => Ok(None),
+            JsonValue::Array(_) => Ok(Some(value.to_string())),
+            other => bail!("expected a vector, got {}", other),
+        };
+    }
+    Ok(scalar_text(value))
+}
+
+fn scalar_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Number(number) => Some(number.to_string()),
+        JsonValue::Bool(flag) => Some(flag.to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn yaml_to_json(value: &YamlValue) -> Result<JsonValue> {
+    serde_json::to_value(value).context("value cannot be represented as JSON")
+}
+
+/// Numbers (including `u64`+ written as strings) compare numerically, anything else as text.
+/// A filter on a missing field does not match.
+fn filter_matches(filter: &EventFilter, data: &JsonValue) -> Result<bool> {
+    let Some(actual) = lookup(data, &filter.path).and_then(scalar_text) else {
+        return Ok(false);
+    };
+    let expected = scalar_text(&yaml_to_json(&filter.value)?).unwrap_or_default();
+    let ordering = match (BigInt::from_str(&actual), BigInt::from_str(&expected)) {
+        (Ok(actual), Ok(expected)) => actual.cmp(&expected),
+        _ => actual.cmp(&expected),
+    };
+    Ok(match filter.op {
+        FilterOp::Eq => ordering == Ordering::Equal,
+        FilterOp::Ne => ordering != Ordering::Equal,
+        FilterOp::Gt => ordering == Ordering::Greater,
+        FilterOp::Ge => ordering != Ordering::Less,
+        FilterOp::Lt => ordering == Ordering::Less,
+        FilterOp::Le => ordering != Ordering::Greater,
+    })
+}
+
+/// Rewrite the value of an event field for its column; a Move `Option` is rewritten inside, and a
+/// missing value stays missing
+fn apply_transform(transform: Transform, value: &JsonValue) -> Result<JsonValue> {
+    if let Some(JsonValue::Array(items)) = value.get("vec") {
+        let items = items
+            .iter()
+            .map(|item| apply_transform(transform, item))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(serde_json::json!({ "vec": items }));
+    }
+    let Some(text) = scalar_text(value) else {
+        return Ok(JsonValue::Null);
+    };
+    Ok(JsonValue::String(match transform {
+        Transform::HexDecode => {
+            let bytes = hex::decode(text.strip_prefix("0x").unwrap_or(&text))
+                .map_err(|_| anyhow!("`{}` is not hex", text))?;
+            String::from_utf8(bytes).map_err(|_| anyhow!("`{}` is not UTF-8 text", text))?
+        }
+        Transform::Lowercase => text.to_lowercase(),
+        Transform::Scale(decimals) => scale(&text, decimals as usize)?,
+    }))
+}
+
+/// `150000000` scaled by 8 decimals is `1.5`
+fn scale(integer: &str, decimals: usize) -> Result<String> {
+    if integer.is_empty() || !integer.chars().all(|c| c.is_ascii_digit()) {
+        bail!("`{}` is not an unsigned integer", integer);
+    }
+    let digits = format!("{:0>width$}", integer, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let whole = whole.trim_start_matches('0');
+    let whole = if whole.is_empty() { "0" } else { whole };
+    let fraction = fraction.trim_end_matches('0');
+    Ok(if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    })
+}
+
+fn compute(column: &ComputedColumn, data: &JsonValue) -> Result<String> {
+    let args = column
+        .args
+        .iter()
+        .map(|arg| {
+            let value = match arg {
+                ComputeArg::Path(path) => lookup(data, path)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("event has no field {}", path))?,
+                ComputeArg::Literal(value) => yaml_to_json(value)?,
+            };
+            Ok(scalar_text(&value).unwrap_or_default())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let number =
+        |text: &String| BigInt::from_str(text).map_err(|_| anyhow!("`{}` is not an integer", text));
+    match column.function {
+        ComputeFunction::Concat => return Ok(args.concat()),
+        ComputeFunction::Hash => {
+            // each argument prefixed with its length, so ("0x1", "23") and ("0x12", "3") differ
+            let mut hasher = Sha3_256::new();
+            for arg in &args {
+                hasher.update((arg.len() as u64).to_le_bytes());
+                hasher.update(arg);
+            }
+            return Ok(hex::encode(hasher.finalize()));
+        }
+        _ => {}
+    }
+    let [lhs, rhs] = args.as_slice() else {
+        bail!(
+            "{:?} takes two arguments, got {}",
+            column.function,
+            args.len()
+        );
+    };
+    let (lhs, rhs) = (number(lhs)?, number(rhs)?);
+    let result = match column.function {
+        ComputeFunction::Add => lhs + rhs,
+        ComputeFunction::Sub => lhs - rhs,
+        ComputeFunction::Mul => lhs * rhs,
+        ComputeFunction::Div => {
+            if rhs == BigInt::from(0) {
+                bail!("division by zero");
+            }
+            lhs / rhs
+        }
+        ComputeFunction::Concat | ComputeFunction::Hash => unreachable!(),
+    };
+    if result < BigInt::from(0) {
+        bail!("result {} is negative", result);
+    }
+    Ok(result.to_string())
+}
@@ -0,0 +1,425 @@
+use crate::db_schema::to_snake_case;
+use crate::diagnostics::{did_you_mean, edit_distance};
+use crate::event_definition::EventDefinition;
+use crate::event_table_mapping::{EventTableMappings, TableMappingRule};
+use crate::processor_config::TableSchema;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+
+/// Tables offered for an event
+pub const MAX_TABLE_SUGGESTIONS: usize = 5;
+
+/// Score from which `processor map` without `--interactive` maps an event to its best table
+pub const AUTO_MAP_MIN_SCORE: u32 = 50;
+
+/// A table an event could be written to, with the column suggested for each of its fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableSuggestion {
+    pub table: String,
+    /// 0-100: half from how close the table name is to the event name, half from the share of
+    /// event fields with a column
+    pub score: u32,
+    /// Event field (dotted path of nested fields) -> column
+    pub columns: BTreeMap<String, String>,
+}
+
+/// Fields of `event` that get a column, with their Move types: struct-typed fields are replaced
+/// by their nested leaves
+pub fn leaf_fields(event: &EventDefinition) -> Vec<(&str, &str)> {
+    event
+        .fields
+        .iter()
+        .chain(event.nested_fields.iter())
+        .filter(|(path, _)| {
+            let prefix = format!("{}.", path);
+            !event.nested_fields.keys().any(|k| k.starts_with(&prefix))
+        })
+        .map(|(path, move_type)| (path.as_str(), move_type.as_str()))
+        .collect()
+}
+
+/// Column of `table` for each field: the column named after the field (`position.collateral`
+/// -> `position_collateral`), else one equal to it up to case and underscores, else the closest
+/// by name. A column is suggested for one field at most.
+pub fn suggest_columns<'a>(
+    fields: impl IntoIterator<Item = &'a str>,
+    table: &TableSchema,
+) -> BTreeMap<String, String> {
+    let fields = fields.into_iter().collect::<Vec<_>>();
+    let mut free = table.keys().map(String::as_str).collect::<BTreeSet<_>>();
+    let mut columns = BTreeMap::new();
+    for field in &fields {
+        let column = field.replace('.', "_");
+        if free.remove(column.as_str()) {
+            columns.insert(field.to_string(), column);
+        }
+    }
+    for field in fields.iter().filter(|f| !columns.contains_key(**f)) {
+        let column = field.replace('.', "_");
+        let found = free
+            .iter()
+            .copied()
+            .find(|c| squash(c) == squash(&column))
+            .or_else(|| did_you_mean(&column, free.iter().copied()));
+        if let Some(found) = found {
+            free.remove(found);
+            columns.insert(field.to_string(), found.to_string());
+        }
+    }
+    columns
+}
+
+/// Tables for `event`, best first, leaving out those sharing no column and little of the name
+pub fn suggest_tables(
+    event: &EventDefinition,
+    tables: &BTreeMap<String, TableSchema>,
+) -> Vec<TableSuggestion> {
+    let fields = leaf_fields(event);
+    let snake = to_snake_case(&event.name);
+    let base = snake.strip_suffix("_event").unwrap_or(&snake);
+    let mut suggestions = tables
+        .iter()
+        .map(|(table, schema)| {
+            let columns = suggest_columns(fields.iter().map(|(path, _)| *path), schema);
+            let name = name_similarity(table, &snake).max(name_similarity(table, base));
+            let coverage = if fields.is_empty() {
+                0.0
+            } else {
+                columns.len() as f64 / fields.len() as f64
+            };
+            TableSuggestion {
+                table: table.clone(),
+                score: ((name + coverage) * 50.0).round() as u32,
+                columns,
+            }
+        })
+        .filter(|s| !s.columns.is_empty() || s.score >= AUTO_MAP_MIN_SCORE / 2)
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.table.cmp(&b.table)));
+    suggestions.truncate(MAX_TABLE_SUGGESTIONS);
+    suggestions
+}
+
+/// Mapping of each event to its best table when that scores at least [`AUTO_MAP_MIN_SCORE`]
+pub fn auto_mapping(
+    definitions: &[EventDefinition],
+    tables: &BTreeMap<String, TableSchema>,
+) -> EventTableMappings {
+    let mut mapping = EventTableMappings::new();
+    for event in definitions {
+        let Some(best) = suggest_tables(event, tables)
+            .into_iter()
+            .next()
+            .filter(|s| s.score >= AUTO_MAP_MIN_SCORE)
+        else {
+            continue;
+        };
+        mapping
+            .entry(event_name(event))
+            .or_default()
+            .push(mapping_rule(&best.table, &best.columns));
+    }
+    mapping
+}
+
+/// Walk through `definitions`, showing the fields of each event and its table suggestions on
+/// `output` and reading the chosen tables and columns from `input`. Returns the mapping made
+/// when every event is answered, `q` is entered or `input` ends, and fails when `input` ends
+/// before the first answer, so nothing is written for it. `allow_exclude` lets a field be
+/// left out of a table having its column, which two-column CSV mappings cannot express.
+pub fn run_mapping_wizard(
+    definitions: &[EventDefinition],
+    tables: &BTreeMap<String, TableSchema>,
+    allow_exclude: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<EventTableMappings> {
+    let mut wizard = Wizard {
+        tables,
+        allow_exclude,
+        input,
+        output,
+        answered: false,
+    };
+    let mut mapping = EventTableMappings::new();
+    for (i, event) in definitions.iter().enumerate() {
+        let name = event_name(event);
+        let fields = leaf_fields(event);
+        let suggestions = suggest_tables(event, tables);
+        writeln!(
+            wizard.output,
+            "\n[{}/{}] {}",
+            i + 1,
+            definitions.len(),
+            name
+        )?;
+        writeln!(
+            wizard.output,
+            "  fields: {}",
+            fields
+                .iter()
+                .map(|(path, move_type)| format!("{}: {}", path, move_type))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        for (n, suggestion) in suggestions.iter().enumerate() {
+            writeln!(
+                wizard.output,
+                "  {}) {} ({}%): {}",
+                n + 1,
+                suggestion.table,
+                suggestion.score,
+                render_columns(&fields, &suggestion.columns)
+            )?;
+        }
+        let Some(chosen) = wizard.choose_tables(&suggestions)? else {
+            break;
+        };
+        let mut rules = Vec::new();
+        for table in chosen {
+            let suggested = suggestions
+                .iter()
+                .find(|s| s.table == table)
+                .map(|s| s.columns.clone())
+                .unwrap_or_else(|| {
+                    suggest_columns(fields.iter().map(|(path, _)| *path), &tables[&table])
+                });
+            let Some(rule) = wizard.confirm_columns(&table, &fields, suggested)? else {
+                return Ok(with_rules(mapping, name, rules));
+            };
+            rules.push(rule);
+        }
+        mapping = with_rules(mapping, name, rules);
+    }
+    Ok(mapping)
+}
+
+struct Wizard<'a, R, W> {
+    tables: &'a BTreeMap<String, TableSchema>,
+    allow_exclude: bool,
+    input: &'a mut R,
+    output: &'a mut W,
+    /// Some answer was read
+    answered: bool,
+}
+
+impl<R: BufRead, W: Write> Wizard<'_, R, W> {
+    /// Next answer to `prompt`, trimmed; `None` at the end of the input
+    fn ask(&mut self, prompt: &str) -> Result<Option<String>> {
+        write!(self.output, "{} > ", prompt)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self
+            .input
+            .read_line(&mut line)
+            .context("failed to read answer")?
+            == 0
+        {
+            writeln!(self.output)?;
+            if !self.answered {
+                bail!("the input ended before the first answer");
+            }
+            return Ok(None);
+        }
+        self.answered = true;
+        Ok(Some(line.trim().to_string()))
+    }
+
+    /// Tables picked by number or name, comma-separated; empty for `s`, `None` for `q`
+    fn choose_tables(&mut self, suggestions: &[TableSuggestion]) -> Result<Option<Vec<String>>> {
+        let default = suggestions.first().map(|s| s.table.as_str());
+        let prompt = format!(
+            "  table (number or name, comma-separated for several; s = skip, q = write and quit) [{}]",
+            if default.is_some() { "1" } else { "s" }
+        );
+        loop {
+            let Some(answer) = self.ask(&prompt)? else {
+                return Ok(None);
+            };
+            let picks = match answer.as_str() {
+                "q" => return Ok(None),
+                "s" => return Ok(Some(Vec::new())),
+                "" => return Ok(Some(default.map(str::to_string).into_iter().collect())),
+                _ => answer
+                    .split(',')
+                    .map(|pick| self.table_pick(pick.trim(), suggestions))
+                    .collect::<std::result::Result<Vec<_>, String>>(),
+            };
+            match picks {
+                Ok(tables) => return Ok(Some(tables)),
+                Err(problem) => writeln!(self.output, "  {}", problem)?,
+            }
+        }
+    }
+
+    fn table_pick(
+        &self,
+        pick: &str,
+        suggestions: &[TableSuggestion],
+    ) -> std::result::Result<String, String> {
+        if let Ok(n) = pick.parse::<usize>() {
+            return match suggestions.get(n.wrapping_sub(1)) {
+                Some(suggestion) => Ok(suggestion.table.clone()),
+                None => Err(format!("no table {}", n)),
+            };
+        }
+        if self.tables.contains_key(pick) {
+            return Ok(pick.to_string());
+        }
+        let mut problem = format!("unknown table {}", pick);
+        if let Some(name) = did_you_mean(pick, self.tables.keys().map(String::as_str)) {
+            problem.push_str(&format!("; did you mean {}?", name));
+        }
+        Err(problem)
+    }
+
+    /// Rule writing `fields` to `table` through `columns`, as accepted or edited field by field;
+    /// `None` at the end of the input
+    fn confirm_columns(
+        &mut self,
+        table: &str,
+        fields: &[(&str, &str)],
+        mut columns: BTreeMap<String, String>,
+    ) -> Result<Option<TableMappingRule>> {
+        writeln!(
+            self.output,
+            "  {}: {}",
+            table,
+            render_columns(fields, &columns)
+        )?;
+        let Some(answer) = self.ask("  accept? (y = yes, e = edit column by column) [y]")? else {
+            return Ok(None);
+        };
+        if answer.eq_ignore_ascii_case("e") {
+            let tables = self.tables;
+            let schema = &tables[table];
+            for (field, move_type) in fields {
+                let current = columns.remove(*field);
+                let prompt = format!(
+                    "    column for {} ({}; - = not written) [{}]",
+                    field,
+                    move_type,
+                    current.as_deref().unwrap_or("-")
+                );
+                loop {
+                    let Some(answer) = self.ask(&prompt)? else {
+                        return Ok(None);
+                    };
+                    let column = match answer.as_str() {
+                        "" => current.clone(),
+                        "-" => None,
+                        _ => Some(answer),
+                    };
+                    match self.column_problem(schema, field, column.as_deref(), &columns) {
+                        Some(problem) => writeln!(self.output, "    {}", problem)?,
+                        None => {
+                            columns.extend(column.map(|c| (field.to_string(), c)));
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        let mut rule = mapping_rule(table, &columns);
+        rule.exclude = fields
+            .iter()
+            .filter(|(field, _)| {
+                !columns.contains_key(*field) && schema_has(self.tables, table, field)
+            })
+            .map(|(field, _)| field.to_string())
+            .collect();
+        Ok(Some(rule))
+    }
+
+    fn column_problem(
+        &self,
+        schema: &TableSchema,
+        field: &str,
+        column: Option<&str>,
+        taken: &BTreeMap<String, String>,
+    ) -> Option<String> {
+        let Some(column) = column else {
+            let default = field.replace('.', "_");
+            return (!self.allow_exclude && schema.contains_key(&default)).then(|| {
+                format!(
+                    "column {} exists, so a CSV mapping writes {} to it; write a .toml or .yaml mapping to leave it out",
+                    default, field
+                )
+            });
+        };
+        if !schema.contains_key(column) {
+            let mut problem = format!("no column {}", column);
+            if let Some(name) = did_you_mean(column, schema.keys().map(String::as_str)) {
+                problem.push_str(&format!("; did you mean {}?", name));
+            }
+            return Some(problem);
+        }
+        taken
+            .iter()
+            .find(|(_, c)| c.as_str() == column)
+            .map(|(other, _)| format!("column {} is already written by {}", column, other))
+    }
+}
+
+fn schema_has(tables: &BTreeMap<String, TableSchema>, table: &str, field: &str) -> bool {
+    tables[table].contains_key(&field.replace('.', "_"))
+}
+
+fn with_rules(
+    mut mapping: EventTableMappings,
+    event: String,
+    rules: Vec<TableMappingRule>,
+) -> EventTableMappings {
+    if !rules.is_empty() {
+        mapping.insert(event, rules);
+    }
+    mapping
+}
+
+/// Rule writing each field to its column in `columns`, renaming those whose column differs
+fn mapping_rule(table: &str, columns: &BTreeMap<String, String>) -> TableMappingRule {
+    let mut rule = TableMappingRule::new(table);
+    rule.rename = columns
+        .iter()
+        .filter(|(field, column)| field.replace('.', "_") != **column)
+        .map(|(field, column)| (field.clone(), column.clone()))
+        .collect();
+    rule
+}
+
+fn render_columns(fields: &[(&str, &str)], columns: &BTreeMap<String, String>) -> String {
+    fields
+        .iter()
+        .map(|(field, _)| match columns.get(*field) {
+            Some(column) => format!("{} -> {}", field, column),
+            None => format!("{} -> (not written)", field),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn event_name(event: &EventDefinition) -> String {
+    format!(
+        "{}::{}::{}",
+        event.package_name, event.module_name, event.name
+    )
+}
+
+/// 0-1, 1 for equal names
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// Lowercase without underscores, so `collateralAsset` and `collateral_asset` compare equal
+fn squash(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
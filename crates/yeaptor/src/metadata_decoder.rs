@@ -0,0 +1,131 @@
+use crate::audit::upgrade_policy_name;
+use anyhow::{Context, Result, anyhow};
+use aptos_framework::natives::code::{PackageMetadata, PackageRegistry};
+use aptos_framework::unzip_metadata_str;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// `PackageMetadata` with its compressed parts unzipped, as `decode package-metadata` prints it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedPackageMetadata {
+    pub name: String,
+    /// `arbitrary`, `compatible` or `immutable`
+    pub upgrade_policy: String,
+    pub upgrade_number: u64,
+    pub source_digest: String,
+    /// Move.toml the package was built from; empty when built without it
+    pub manifest: String,
+    pub modules: Vec<DecodedModuleMetadata>,
+    pub deps: Vec<DecodedPackageDep>,
+    pub extension: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedModuleMetadata {
+    pub name: String,
+    /// Unzipped source, when requested and included (artifacts `all`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Size of the zipped source, 0 when not included
+    pub source_size: usize,
+    /// Size of the zipped source map, 0 when not included
+    pub source_map_size: usize,
+    pub extension: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedPackageDep {
+    pub account: String,
+    pub package_name: String,
+}
+
+/// Bytes given as `input`: the content of the file at that path, as hex when it is hex text, or
+/// `input` itself as hex (`0x` optional)
+pub fn read_metadata_input(input: &str) -> Result<Vec<u8>> {
+    let path = Path::new(input);
+    if path.is_file() {
+        let bytes = fs::read(path)?;
+        return Ok(
+            match std::str::from_utf8(&bytes).ok().and_then(decode_hex) {
+                Some(decoded) => decoded,
+                None => bytes,
+            },
+        );
+    }
+    decode_hex(input).ok_or_else(|| anyhow!("`{}` is neither a file nor hex", input))
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    // possibly quoted, as copied from JSON
+    let s = s.trim().trim_matches('"');
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok()
+}
+
+/// Decode `bytes` as the BCS of one `PackageMetadata`, or of a `0x1::code::PackageRegistry`
+/// holding several; with `sources`, the module sources are unzipped too
+pub fn decode_package_metadata(bytes: &[u8], sources: bool) -> Result<Vec<DecodedPackageMetadata>> {
+    let packages = match bcs::from_bytes::<PackageMetadata>(bytes) {
+        Ok(metadata) => vec![metadata],
+        Err(metadata_error) => bcs::from_bytes::<PackageRegistry>(bytes)
+            .map(|registry| registry.packages)
+            .map_err(|_| {
+                anyhow!(metadata_error).context(
+                    "the bytes are the BCS of neither a PackageMetadata nor a PackageRegistry",
+                )
+            })?,
+    };
+    packages
+        .into_iter()
+        .map(|metadata| decode_one(metadata, sources))
+        .collect()
+}
+
+fn decode_one(metadata: PackageMetadata, sources: bool) -> Result<DecodedPackageMetadata> {
+    let manifest = if metadata.manifest.is_empty() {
+        String::new()
+    } else {
+        unzip_metadata_str(&metadata.manifest)
+            .with_context(|| format!("the manifest of {} does not unzip", metadata.name))?
+    };
+    let modules = metadata
+        .modules
+        .iter()
+        .map(|module| {
+            let source = if sources && !module.source.is_empty() {
+                Some(unzip_metadata_str(&module.source).with_context(|| {
+                    format!(
+                        "the source of {}::{} does not unzip",
+                        metadata.name, module.name
+                    )
+                })?)
+            } else {
+                None
+            };
+            Ok(DecodedModuleMetadata {
+                name: module.name.clone(),
+                source,
+                source_size: module.source.len(),
+                source_map_size: module.source_map.len(),
+                extension: module.extension.is_some(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(DecodedPackageMetadata {
+        upgrade_policy: upgrade_policy_name(metadata.upgrade_policy.policy).to_string(),
+        upgrade_number: metadata.upgrade_number,
+        source_digest: metadata.source_digest,
+        manifest,
+        modules,
+        deps: metadata
+            .deps
+            .into_iter()
+            .map(|dep| DecodedPackageDep {
+                account: dep.account.to_standard_string(),
+                package_name: dep.package_name,
+            })
+            .collect(),
+        extension: metadata.extension.is_some(),
+        name: metadata.name,
+    })
+}
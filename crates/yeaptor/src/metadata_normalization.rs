@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_framework::{unzip_metadata_str, zip_metadata_str};
+
+/// Remove what makes `PackageMetadata` differ between two clean checkouts of the same sources:
+/// source maps, which record locations of the build host, are dropped, and the Move.toml and
+/// module sources carried with `include_artifacts = "all"` are recompressed with `\n` line
+/// endings (a Windows checkout has `\r\n`) and a fixed gzip header. Bytecode, names, the upgrade
+/// policy and `source_digest` are kept as built.
+pub fn normalize_metadata(metadata: &mut PackageMetadata) -> Result<()> {
+    metadata.manifest = normalize_text(&metadata.manifest)
+        .with_context(|| format!("manifest of {} does not decompress", metadata.name))?;
+    for module in &mut metadata.modules {
+        module.source = normalize_text(&module.source).with_context(|| {
+            format!(
+                "source of {}::{} does not decompress",
+                metadata.name, module.name
+            )
+        })?;
+        module.source_map = Vec::new();
+    }
+    Ok(())
+}
+
+/// Gzipped text with `\n` line endings; empty stays empty, as for sparse artifacts
+fn normalize_text(zipped: &[u8]) -> Result<Vec<u8>> {
+    if zipped.is_empty() {
+        return Ok(Vec::new());
+    }
+    let text = unzip_metadata_str(zipped)?;
+    zip_metadata_str(&text.replace("\r\n", "\n"))
+}
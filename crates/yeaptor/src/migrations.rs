@@ -0,0 +1,218 @@
+use crate::db_schema::{SchemaSnapshot, load_schema_snapshot, schema_snapshot_to_toml};
+use crate::ddl::{postgres_migration, postgres_nullable_additions, postgres_type};
+use crate::processor_config::{ColumnSpec, TableSchema};
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Snapshot of the tables (and their declared indexes) the latest generated migration brings the database to, kept in the
+/// migrations directory and diffed against on the next run
+pub const SCHEMA_STATE_FILE: &str = "yeaptor_schema.toml";
+
+/// Generated migration: directory or file name and its up/down SQL
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub name: String,
+    pub up: String,
+    pub down: String,
+}
+
+/// Schema recorded in `dir`; empty before the first migration
+pub fn load_schema_state(dir: &Path) -> Result<SchemaSnapshot> {
+    let path = dir.join(SCHEMA_STATE_FILE);
+    if !path.exists() {
+        return Ok(SchemaSnapshot::default());
+    }
+    load_schema_snapshot(&path)
+}
+
+pub fn save_schema_state(dir: &Path, schema: &SchemaSnapshot) -> Result<()> {
+    let path = dir.join(SCHEMA_STATE_FILE);
+    fs::write(&path, schema_snapshot_to_toml(schema)?)
+        .with_context(|| format!("failed to write schema state: {}", path.display()))
+}
+
+/// Version after the highest numeric prefix (`<version>_<name>`) among the entries of `dir`,
+/// so generated migrations sort after Diesel's `00000000000000_diesel_initial_setup`
+pub fn next_migration_version(dir: &Path) -> Result<u64> {
+    if !dir.exists() {
+        return Ok(1);
+    }
+    let mut latest = 0;
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to read dir: {}", dir.display()))?
+    {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(Ok(version)) = name.split('_').next().map(str::parse::<u64>) {
+            latest = latest.max(version);
+        }
+    }
+    Ok(latest + 1)
+}
+
+/// Migration from the schema state in `dir` to `current`, or None when the SQL would be empty
+pub fn diesel_migration(dir: &Path, current: &SchemaSnapshot) -> Result<Option<Migration>> {
+    let previous = load_schema_state(dir)?;
+    let up = postgres_migration(&previous, current)?;
+    if up.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Migration {
+        name: format!("{:014}_yeaptor_schema", next_migration_version(dir)?),
+        up,
+        down: postgres_migration(current, &previous)?,
+    }))
+}
+
+/// Write `<dir>/<name>/up.sql` and `down.sql`, then record `current` as the schema state
+pub fn write_diesel_migration(
+    dir: &Path,
+    migration: &Migration,
+    current: &SchemaSnapshot,
+) -> Result<PathBuf> {
+    let migration_dir = dir.join(&migration.name);
+    fs::create_dir_all(&migration_dir)
+        .with_context(|| format!("failed to create dir: {}", migration_dir.display()))?;
+    fs::write(migration_dir.join("up.sql"), &migration.up)
+        .with_context(|| format!("failed to write {}/up.sql", migration_dir.display()))?;
+    fs::write(migration_dir.join("down.sql"), &migration.down)
+        .with_context(|| format!("failed to write {}/down.sql", migration_dir.display()))?;
+    save_schema_state(dir, current)?;
+    Ok(migration_dir)
+}
+
+/// Timestamped sqlx migration (`<timestamp>_yeaptor_schema.{up,down}.sql`) from the schema state
+/// in `dir` to `current`, or None when the SQL would be empty. Adding nullable columns only is
+/// written as `ADD COLUMN IF NOT EXISTS`.
+pub fn sqlx_migration(
+    dir: &Path,
+    current: &SchemaSnapshot,
+    timestamp: &str,
+) -> Result<Option<Migration>> {
+    let previous = load_schema_state(dir)?;
+    let (up, down) = match postgres_nullable_additions(&previous, current)? {
+        Some(additions) => additions,
+        None => (
+            postgres_migration(&previous, current)?,
+            postgres_migration(current, &previous)?,
+        ),
+    };
+    if up.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Migration {
+        name: format!("{}_yeaptor_schema", timestamp),
+        up,
+        down,
+    }))
+}
+
+/// Write `<dir>/<name>.up.sql` and `<name>.down.sql`, then record `current` as the schema state
+pub fn write_sqlx_migration(
+    dir: &Path,
+    migration: &Migration,
+    current: &SchemaSnapshot,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create dir: {}", dir.display()))?;
+    let up = dir.join(format!("{}.up.sql", migration.name));
+    fs::write(&up, &migration.up).with_context(|| format!("failed to write {}", up.display()))?;
+    let down = dir.join(format!("{}.down.sql", migration.name));
+    fs::write(&down, &migration.down)
+        .with_context(|| format!("failed to write {}", down.display()))?;
+    save_schema_state(dir, current)?;
+    Ok(up)
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+/// Render a Diesel `schema.rs` (`diesel::table!` per table) matching the Postgres DDL.
+/// Diesel requires a primary key, so tables without one are left out with a comment.
+pub fn diesel_schema_rs(tables: &BTreeMap<String, TableSchema>) -> Result<String> {
+    let mut out =
+        String::from("// @generated by `yeaptor processor migrations` from the DB schema\n");
+    let mut declared = Vec::new();
+    for (table, schema) in tables {
+        let primary_key = schema
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .map(|(column, _)| rust_identifier(column).1)
+            .collect::<Vec<_>>();
+        if primary_key.is_empty() {
+            out.push_str(&format!(
+                "\n// {} has no primary key and cannot be declared with diesel::table!\n",
+                table
+            ));
+            continue;
+        }
+        let (table_attr, table_name) = rust_identifier(table);
+        out.push_str("\ndiesel::table! {\n");
+        if let Some(attr) = table_attr {
+            out.push_str(&format!("    {}\n", attr));
+        }
+        out.push_str(&format!(
+            "    {} ({}) {{\n",
+            table_name,
+            primary_key.join(", ")
+        ));
+        for (column, spec) in schema {
+            let sql_type =
+                diesel_type(spec).with_context(|| format!("column {}::{}", table, column))?;
+            let (attr, name) = rust_identifier(column);
+            if let Some(attr) = attr {
+                out.push_str(&format!("        {}\n", attr));
+            }
+            out.push_str(&format!("        {} -> {},\n", name, sql_type));
+        }
+        out.push_str("    }\n}\n");
+        declared.push(table_name);
+    }
+    if declared.len() > 1 {
+        out.push_str("\ndiesel::allow_tables_to_appear_in_same_query!(\n");
+        for table in declared {
+            out.push_str(&format!("    {},\n", table));
+        }
+        out.push_str(");\n");
+    }
+    Ok(out)
+}
+
+/// Rust name for a SQL identifier, with the `#[sql_name]` attribute when it had to be renamed
+fn rust_identifier(name: &str) -> (Option<String>, String) {
+    if RUST_KEYWORDS.contains(&name) {
+        (
+            Some(format!("#[sql_name = \"{}\"]", name)),
+            format!("{}_", name),
+        )
+    } else {
+        (None, name.to_string())
+    }
+}
+
+fn diesel_type(spec: &ColumnSpec) -> Result<String> {
+    let postgres = postgres_type(spec)?;
+    let mut sql_type = match postgres {
+        "SMALLINT" => "Int2",
+        "INTEGER" => "Int4",
+        "BIGINT" => "Int8",
+        "BOOLEAN" => "Bool",
+        "TEXT" => "Text",
+        "TIMESTAMP" => "Timestamp",
+        t if t.starts_with("NUMERIC") => "Numeric",
+        t if t.starts_with("VARCHAR") => "Varchar",
+        other => bail!("no Diesel type for {}", other),
+    }
+    .to_string();
+    if spec.is_vec {
+        sql_type = format!("Array<{}>", sql_type);
+    }
+    if !spec.is_primary_key && (spec.is_nullable || spec.is_option) {
+        sql_type = format!("Nullable<{}>", sql_type);
+    }
+    Ok(sql_type)
+}
@@ -0,0 +1,38 @@
+use aptos_types::on_chain_config::Features;
+use aptos_types::vm::module_metadata::verify_module_metadata_for_module_publishing;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use move_bytecode_verifier::VerifierConfig;
+use move_bytecode_verifier::verify_module_with_config;
+use yeaptor_core::diagnostics::Diagnostics;
+
+/// Check compiled `modules` as the network does when they are published: each must deserialize,
+/// pass the Move bytecode verifier with the production limits, and carry Aptos metadata
+/// (attributes such as `#[event]` and `#[view]`) the publishing checks accept. A module failing
+/// here would make the publish transaction abort on chain. Problems are reported by module name,
+/// or by position for modules that do not deserialize.
+pub fn verify_modules(modules: &[Vec<u8>]) -> Diagnostics {
+    let config = VerifierConfig::production();
+    let features = Features::default();
+    let mut diagnostics = Diagnostics::default();
+    for (i, bytes) in modules.iter().enumerate() {
+        let module = match CompiledModule::deserialize(bytes) {
+            Ok(module) => module,
+            Err(e) => {
+                diagnostics.push(
+                    format!("modules[{}]", i),
+                    format!("does not deserialize: {}", e),
+                );
+                continue;
+            }
+        };
+        let name = module.self_id().name().to_string();
+        if let Err(e) = verify_module_with_config(&config, &module) {
+            diagnostics.push(&name, format!("bytecode verification failed: {}", e));
+        }
+        if let Err(e) = verify_module_metadata_for_module_publishing(&module, &features) {
+            diagnostics.push(&name, format!("invalid module metadata: {}", e));
+        }
+    }
+    diagnostics
+}
@@ -0,0 +1,334 @@
+use crate::config::{DeployFunction, DeploymentMode};
+use crate::deployment_planner::{DeploymentPlan, PlannedPackage};
+use crate::diagnostics::Diagnostics;
+use crate::preflight::account_sequence_number;
+use anyhow::{Context, Result, anyhow};
+use aptos_rest_client::Client;
+use aptos_types::account_address::AccountAddress;
+use aptos_types::chain_id::ChainId;
+use aptos_types::transaction::{
+    EntryFunction, RawTransaction, SignedTransaction, TransactionPayload,
+};
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::ModuleId;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use yeaptor_core::address::object_code_address;
+
+/// Version of the unsigned transaction bundle layout written by this yeaptor
+pub const UNSIGNED_BUNDLE_VERSION: u64 = 1;
+
+/// Domain of Aptos raw transaction signatures: the signing message is the SHA3-256 of it
+/// followed by the BCS-serialized `RawTransaction`
+const RAW_TRANSACTION_SALT: &[u8] = b"APTOS::RawTransaction";
+
+/// Chain parameters the exported transactions are built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionSettings {
+    pub chain_id: u8,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    pub expiration_timestamp_secs: u64,
+}
+
+/// What `deployment export-unsigned` writes for an offline signer: every publish transaction of
+/// a deployment plan, complete but unsigned, in deployment order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnsignedBundle {
+    pub format_version: u64,
+    pub chain_id: u8,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    /// Unix time after which the transactions are rejected
+    pub expiration_timestamp_secs: u64,
+    pub transactions: Vec<UnsignedTransaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnsignedTransaction {
+    pub order: usize,
+    pub deployment: String,
+    pub package: String,
+    /// Publisher signing the transaction, as a standard string
+    pub sender: String,
+    pub sequence_number: u64,
+    /// `<address>::<module>::<function>` called
+    pub function: String,
+    /// BCS-serialized `RawTransaction`, hex
+    pub raw_transaction: String,
+    /// Message to sign with the sender's ed25519 key, hex
+    pub signing_message: String,
+}
+
+impl UnsignedTransaction {
+    pub fn raw_transaction(&self) -> Result<RawTransaction> {
+        let bytes = decode_hex(&self.raw_transaction)?;
+        bcs::from_bytes(&bytes)
+            .with_context(|| format!("raw transaction of {} does not deserialize", self.package))
+    }
+}
+
+/// Entry function call publishing `package`, as its `payload`; `deploy` is the entry function of
+/// resource account deployments
+pub fn publish_entry_function(
+    package: &PlannedPackage,
+    deploy: &DeployFunction,
+) -> Result<EntryFunction> {
+    let metadata = bcs::to_bytes(&package.metadata)?;
+    let modules = bcs::to_bytes(&package.modules)?;
+    let (address, module, function, args) = match package.mode {
+        DeploymentMode::ResourceAccount => (
+            deploy.address,
+            deploy.module.as_str(),
+            deploy.function.as_str(),
+            vec![bcs::to_bytes(package.seed.as_bytes())?, metadata, modules],
+        ),
+        DeploymentMode::Account => (
+            AccountAddress::ONE,
+            "code",
+            "publish_package_txn",
+            vec![metadata, modules],
+        ),
+        DeploymentMode::Object if package.object_upgrade => (
+            AccountAddress::ONE,
+            "object_code_deployment",
+            "upgrade",
+            vec![metadata, modules, bcs::to_bytes(&package.account)?],
+        ),
+        DeploymentMode::Object => (
+            AccountAddress::ONE,
+            "object_code_deployment",
+            "publish",
+            vec![metadata, modules],
+        ),
+    };
+    Ok(EntryFunction::new(
+        ModuleId::new(address, Identifier::new(module)?),
+        Identifier::new(function)?,
+        Vec::new(),
+        args,
+    ))
+}
+
+/// Unsigned publish transactions of `plan` in deployment order. Each publisher sends its
+/// transactions with consecutive sequence numbers from `next_sequence_numbers` (0 for a publisher
+/// not on chain yet). A new code object must be created by the transaction its address was
+/// derived from (`sequence_number` of its deployment); a mismatch is reported at the package.
+pub fn unsigned_bundle(
+    plan: &DeploymentPlan,
+    deploy: &DeployFunction,
+    next_sequence_numbers: &BTreeMap<AccountAddress, u64>,
+    settings: &TransactionSettings,
+) -> Result<UnsignedBundle> {
+    let mut diagnostics = Diagnostics::default();
+    let mut sequence_numbers = next_sequence_numbers.clone();
+    let mut transactions = Vec::new();
+    for package in &plan.packages {
+        let sequence_number = sequence_numbers.entry(package.publisher).or_insert(0);
+        if package.mode == DeploymentMode::Object
+            && !package.object_upgrade
+            && object_code_address(&package.publisher, *sequence_number) != package.account
+        {
+            diagnostics.push(
+                &package.name,
+                format!(
+                    "code object {} is not the one sequence number {} of {} creates; set \
+                     sequence_number of its deployment to {}, or export after the publisher's \
+                     pending transactions",
+                    package.account.to_standard_string(),
+                    sequence_number,
+                    package.publisher.to_standard_string(),
+                    sequence_number
+                ),
+            );
+        }
+        let entry_function = publish_entry_function(package, deploy)?;
+        let function = format!(
+            "{}::{}",
+            entry_function.module().short_str_lossless(),
+            entry_function.function()
+        );
+        let raw = RawTransaction::new(
+            package.publisher,
+            *sequence_number,
+            TransactionPayload::EntryFunction(entry_function),
+            settings.max_gas_amount,
+            settings.gas_unit_price,
+            settings.expiration_timestamp_secs,
+            ChainId::new(settings.chain_id),
+        );
+        transactions.push(UnsignedTransaction {
+            order: package.order,
+            deployment: package.deployment.clone(),
+            package: package.name.clone(),
+            sender: package.publisher.to_standard_string(),
+            sequence_number: *sequence_number,
+            function,
+            raw_transaction: format!("0x{}", hex::encode(bcs::to_bytes(&raw)?)),
+            signing_message: format!("0x{}", hex::encode(signing_message(&raw)?)),
+        });
+        *sequence_number += 1;
+    }
+    diagnostics.into_result()?;
+    Ok(UnsignedBundle {
+        format_version: UNSIGNED_BUNDLE_VERSION,
+        chain_id: settings.chain_id,
+        max_gas_amount: settings.max_gas_amount,
+        gas_unit_price: settings.gas_unit_price,
+        expiration_timestamp_secs: settings.expiration_timestamp_secs,
+        transactions,
+    })
+}
+
+/// Read what the transactions of `publishers` are built with from the node: its chain id, the
+/// gas price estimate unless `gas_unit_price` is given, and the next sequence number of each
+/// publisher already on chain
+pub async fn fetch_transaction_settings(
+    client: &Client,
+    publishers: &BTreeSet<AccountAddress>,
+    max_gas_amount: u64,
+    gas_unit_price: Option<u64>,
+    expiration_timestamp_secs: u64,
+) -> Result<(TransactionSettings, BTreeMap<AccountAddress, u64>)> {
+    let chain_id = client
+        .get_index()
+        .await
+        .context("failed to read the chain id")?
+        .into_inner()
+        .chain_id;
+    let gas_unit_price = match gas_unit_price {
+        Some(price) => price,
+        None => {
+            client
+                .estimate_gas_price()
+                .await
+                .context("failed to estimate the gas price")?
+                .into_inner()
+                .gas_estimate
+        }
+    };
+    let mut sequence_numbers = BTreeMap::new();
+    for publisher in publishers {
+        if let Some(sequence_number) = account_sequence_number(client, *publisher).await? {
+            sequence_numbers.insert(*publisher, sequence_number);
+        }
+    }
+    let settings = TransactionSettings {
+        chain_id,
+        max_gas_amount,
+        gas_unit_price,
+        expiration_timestamp_secs,
+    };
+    Ok((settings, sequence_numbers))
+}
+
+/// Submit `transactions` one after the other, each once the previous one committed; returns
+/// their hashes. Stops at the first that fails or aborts, naming how many went through.
+pub async fn submit_transactions(
+    client: &Client,
+    transactions: &[SignedTransaction],
+) -> Result<Vec<String>> {
+    let mut hashes = Vec::new();
+    for (i, transaction) in transactions.iter().enumerate() {
+        let hash = transaction.committed_hash().to_hex_literal();
+        let committed = client
+            .submit_and_wait_bcs(transaction)
+            .await
+            .with_context(|| {
+                format!(
+                    "transaction {} ({} of {}) was not committed; {} before it were",
+                    hash,
+                    i + 1,
+                    transactions.len(),
+                    i
+                )
+            })?
+            .into_inner();
+        if !committed.info.status().is_success() {
+            anyhow::bail!(
+                "transaction {} ({} of {}) failed: {:?}; {} before it were committed",
+                hash,
+                i + 1,
+                transactions.len(),
+                committed.info.status(),
+                i
+            );
+        }
+        hashes.push(hash);
+    }
+    Ok(hashes)
+}
+
+/// Bytes an Aptos account signs to authorize `raw`
+pub fn signing_message(raw: &RawTransaction) -> Result<Vec<u8>> {
+    let mut message = Sha3_256::digest(RAW_TRANSACTION_SALT).to_vec();
+    message.extend(bcs::to_bytes(raw)?);
+    Ok(message)
+}
+
+/// A signed transaction file: BCS bytes, or the same as hex text with or without `0x`
+pub fn parse_signed_transaction(bytes: &[u8]) -> Result<SignedTransaction> {
+    let hex = std::str::from_utf8(bytes).ok().map(str::trim).filter(|s| {
+        !s.is_empty()
+            && s.trim_start_matches("0x")
+                .bytes()
+                .all(|b| b.is_ascii_hexdigit())
+    });
+    let bytes = match hex {
+        Some(hex) => decode_hex(hex)?,
+        None => bytes.to_vec(),
+    };
+    Ok(bcs::from_bytes(&bytes)?)
+}
+
+/// Put `signed` in the order of `bundle`: each must carry exactly one of its raw transactions,
+/// with a valid signature, and each of its raw transactions must be signed. Problems are located
+/// at the package, or at the position in `signed` of a transaction not in the bundle.
+pub fn match_signed_transactions(
+    bundle: &UnsignedBundle,
+    signed: Vec<SignedTransaction>,
+) -> Result<Vec<SignedTransaction>> {
+    let mut diagnostics = Diagnostics::default();
+    let mut by_raw = BTreeMap::new();
+    for (i, transaction) in signed.into_iter().enumerate() {
+        let raw = bcs::to_bytes(transaction.raw_transaction_ref())?;
+        if let Err(e) = transaction.clone().check_signature() {
+            diagnostics.push(
+                format!("signed[{}]", i),
+                format!("invalid signature: {}", e),
+            );
+            continue;
+        }
+        by_raw.insert(raw, (i, transaction));
+    }
+    let mut ordered = Vec::new();
+    for unsigned in &bundle.transactions {
+        let raw = decode_hex(&unsigned.raw_transaction)?;
+        match by_raw.remove(&raw) {
+            Some((_, transaction)) => ordered.push(transaction),
+            None => diagnostics.push(
+                &unsigned.package,
+                format!(
+                    "no signed transaction for sequence number {} of {}",
+                    unsigned.sequence_number, unsigned.sender
+                ),
+            ),
+        }
+    }
+    for (i, _) in by_raw.into_values() {
+        diagnostics.push(
+            format!("signed[{}]", i),
+            "not one of the exported transactions; it was changed before signing or belongs to \
+             another export",
+        );
+    }
+    diagnostics.into_result()?;
+    Ok(ordered)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| anyhow!("invalid hex: {}", e))
+}
@@ -0,0 +1,114 @@
+use crate::error::YeaptorError;
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// How command results and errors are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Warnings then a summary line
+    #[default]
+    Text,
+    /// One JSON object: `{"status": "ok", "message", "files", "addresses", "warnings", ...}`,
+    /// or `{"status": "error", "kind", "path", "message", "diagnostics"}`
+    Json,
+}
+
+/// Result of a command: a summary plus what it wrote, derived and warned about
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CommandOutput {
+    pub message: String,
+    /// Files written
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PathBuf>,
+    /// Named addresses derived, as standard strings
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub addresses: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Command-specific results (counts, versions, findings), flattened into the JSON object
+    #[serde(flatten)]
+    pub details: BTreeMap<String, Value>,
+}
+
+impl CommandOutput {
+    pub fn new(message: impl Into<String>) -> Self {
+        CommandOutput {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn files(mut self, files: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.files.extend(files);
+        self
+    }
+
+    pub fn addresses(mut self, addresses: &BTreeMap<String, AccountAddress>) -> Self {
+        self.addresses.extend(
+            addresses
+                .iter()
+                .map(|(name, address)| (name.clone(), address.to_standard_string())),
+        );
+        self
+    }
+
+    pub fn warnings(mut self, warnings: impl IntoIterator<Item = String>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    /// Adds `key` to the JSON object; values that do not serialize are recorded as null
+    pub fn detail(mut self, key: &str, value: impl Serialize) -> Self {
+        self.details.insert(
+            key.to_string(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+        self
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text => self
+                .warnings
+                .iter()
+                .map(|warning| format!("warning: {}", warning))
+                .chain(std::iter::once(self.message.clone()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            OutputFormat::Json => {
+                let mut object = json!({ "status": "ok" });
+                if let (Value::Object(object), Ok(Value::Object(fields))) =
+                    (&mut object, serde_json::to_value(self))
+                {
+                    object.extend(fields);
+                }
+                pretty(&object)
+            }
+        }
+    }
+}
+
+pub fn render_error(error: &YeaptorError, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => error.to_string(),
+        OutputFormat::Json => {
+            let mut object = json!({
+                "status": "error",
+                "kind": error.kind(),
+                "path": error.path(),
+                "message": error.to_string(),
+            });
+            if let Some(diagnostics) = error.diagnostics() {
+                object["diagnostics"] = json!(diagnostics);
+            }
+            pretty(&object)
+        }
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
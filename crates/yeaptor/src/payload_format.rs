@@ -0,0 +1,250 @@
+use crate::config::PayloadNaming;
+use crate::deployment_planner::PlannedPackage;
+use anyhow::{Result, anyhow, bail};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the default payload format: entry function JSON for `aptos move run --json-file`
+pub const DEFAULT_PAYLOAD_FORMAT: &str = "entry-function-json";
+
+/// Default `payload_file_name`: the payload format's file name in the deployment's subdirectory
+pub const DEFAULT_PAYLOAD_FILE_NAME: &str = "{deployment}/{file}";
+
+/// Placeholders of `payload_file_name`
+const FILE_NAME_PLACEHOLDERS: [&str; 4] = ["deployment", "order", "package", "file"];
+
+/// Check a `payload_file_name` template before building: it names only known placeholders and
+/// stays inside the output directory
+pub fn check_payload_file_name(template: &str) -> Result<()> {
+    render_file_name(template, |_| "x".to_string()).map(|_| ())
+}
+
+/// Path of the payload file of `package` relative to the output directory, rendering the
+/// `payload_file_name` template: `{deployment}` is the deployment's directory name, `{order}` the
+/// package's position in the deployment order, `{package}` its name and `{file}` the file name
+/// of `formatter` (its `stable_file_name` with `PayloadNaming::Stable`). Unknown placeholders,
+/// absolute paths and `..` are rejected.
+pub fn payload_path(
+    template: &str,
+    naming: PayloadNaming,
+    formatter: &dyn PayloadFormatter,
+    package: &PlannedPackage,
+) -> Result<PathBuf> {
+    render_file_name(template, |placeholder| match placeholder {
+        "deployment" => package.deployment.clone(),
+        "order" => package.order.to_string(),
+        "package" => package.name.clone(),
+        _ => match naming {
+            PayloadNaming::Ordered => formatter.file_name(package),
+            PayloadNaming::Stable => formatter.stable_file_name(package),
+        },
+    })
+}
+
+fn render_file_name(template: &str, value: impl Fn(&str) -> String) -> Result<PathBuf> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            bail!("unclosed `{{` in payload file name `{}`", template);
+        };
+        let placeholder = &rest[start + 1..start + end];
+        if !FILE_NAME_PLACEHOLDERS.contains(&placeholder) {
+            bail!(
+                "unknown placeholder `{{{}}}` in payload file name `{}`; expected one of {}",
+                placeholder,
+                template,
+                FILE_NAME_PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("`{{{}}}`", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        rendered.push_str(&value(placeholder));
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+    let path = PathBuf::from(rendered);
+    if path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        || path.file_name().is_none()
+    {
+        bail!(
+            "payload file name `{}` renders to `{}`, which is not a file inside the output directory",
+            template,
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
+/// Fail on the first of `paths` (payload files relative to the output directory) that two
+/// packages would be written to, as when `payload_file_name` leaves out `{package}` and
+/// `{file}`, or two deployments publish packages of the same name and it leaves out
+/// `{deployment}` with stable naming
+pub fn check_distinct_paths<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    for path in paths {
+        if !seen.insert(path) {
+            bail!(
+                "two packages would be written to `{}`; tell them apart in the payload file name (e.g. with `{{deployment}}` and `{{file}}`)",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Renders the payload file `deployment build` writes for each package. Implement it for a format
+/// of your own (e.g. the envelope a signing service expects), register it on `PayloadFormatters`
+/// and select it with `payload_format = "<name>"` in yeaptor.toml or `--payload-format`.
+///
+/// ```ignore
+/// struct SigningEnvelope;
+///
+/// impl PayloadFormatter for SigningEnvelope {
+///     fn name(&self) -> &str {
+///         "signing-envelope"
+///     }
+///     fn file_name(&self, package: &PlannedPackage) -> String {
+///         format!("{}-{}.envelope.json", package.order, package.name)
+///     }
+///     fn format(&self, package: &PlannedPackage) -> anyhow::Result<Vec<u8>> {
+///         Ok(serde_json::to_vec(&json!({ "payload": package.payload, "seed": package.seed }))?)
+///     }
+/// }
+///
+/// let formatters = PayloadFormatters::default().register(SigningEnvelope);
+/// ```
+pub trait PayloadFormatter: Send + Sync {
+    /// Name selecting the format
+    fn name(&self) -> &str;
+    /// Name of the file the payload is written to; placed by `payload_file_name` (by default in
+    /// the deployment's subdirectory of the output directory)
+    fn file_name(&self, package: &PlannedPackage) -> String;
+    /// File name under `payload_naming = "stable"`, which must not depend on `package.order`;
+    /// by default `file_name` without a leading `<order>-`
+    fn stable_file_name(&self, package: &PlannedPackage) -> String {
+        let file_name = self.file_name(package);
+        match file_name.strip_prefix(&format!("{}-", package.order)) {
+            Some(stable) => stable.to_string(),
+            None => file_name,
+        }
+    }
+    fn format(&self, package: &PlannedPackage) -> Result<Vec<u8>>;
+}
+
+/// `<order>-<package>.package.json` holding the entry function JSON of `ra_code_deployment::deploy`
+pub struct EntryFunctionJson;
+
+impl PayloadFormatter for EntryFunctionJson {
+    fn name(&self) -> &str {
+        DEFAULT_PAYLOAD_FORMAT
+    }
+
+    fn file_name(&self, package: &PlannedPackage) -> String {
+        package.file_name()
+    }
+
+    fn stable_file_name(&self, package: &PlannedPackage) -> String {
+        package.stable_file_name()
+    }
+
+    fn format(&self, package: &PlannedPackage) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(&package.payload)?)
+    }
+}
+
+/// `<order>-<package>.publish-args.json` holding the plain arguments of
+/// `0x1::code::publish_package_txn`, `metadata_serialized` and `code` as hex, without the entry
+/// function around them, for accounts published through governance modules of their own
+pub struct PublishArgsJson;
+
+impl PayloadFormatter for PublishArgsJson {
+    fn name(&self) -> &str {
+        "publish-args-json"
+    }
+
+    fn file_name(&self, package: &PlannedPackage) -> String {
+        format!("{}-{}.publish-args.json", package.order, package.name)
+    }
+
+    fn format(&self, package: &PlannedPackage) -> Result<Vec<u8>> {
+        let code = package
+            .modules
+            .iter()
+            .map(|module| format!("0x{}", hex::encode(module)))
+            .collect::<Vec<_>>();
+        Ok(serde_json::to_vec_pretty(&serde_json::json!({
+            "metadata_serialized": format!("0x{}", hex::encode(&package.metadata)),
+            "code": code,
+        }))?)
+    }
+}
+
+/// `<order>-<package>.publish-args.bcs` holding the BCS of the argument pair
+/// `(metadata_serialized: vector<u8>, code: vector<vector<u8>>)` of
+/// `0x1::code::publish_package_txn`
+pub struct PublishArgsBcs;
+
+impl PayloadFormatter for PublishArgsBcs {
+    fn name(&self) -> &str {
+        "publish-args-bcs"
+    }
+
+    fn file_name(&self, package: &PlannedPackage) -> String {
+        format!("{}-{}.publish-args.bcs", package.order, package.name)
+    }
+
+    fn format(&self, package: &PlannedPackage) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&(&package.metadata, &package.modules))?)
+    }
+}
+
+/// Payload formats by name; `entry-function-json`, `publish-args-json` and `publish-args-bcs`
+/// are always available
+pub struct PayloadFormatters {
+    formatters: BTreeMap<String, Box<dyn PayloadFormatter>>,
+}
+
+impl Default for PayloadFormatters {
+    fn default() -> Self {
+        PayloadFormatters {
+            formatters: BTreeMap::new(),
+        }
+        .register(EntryFunctionJson)
+        .register(PublishArgsJson)
+        .register(PublishArgsBcs)
+    }
+}
+
+impl PayloadFormatters {
+    /// Adds `formatter`, replacing a format registered under the same name
+    pub fn register(mut self, formatter: impl PayloadFormatter + 'static) -> Self {
+        self.formatters
+            .insert(formatter.name().to_string(), Box::new(formatter));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters.keys().map(String::as_str).collect()
+    }
+
+    /// Format registered as `name`, or `entry-function-json` when unset
+    pub fn get(&self, name: Option<&str>) -> Result<&dyn PayloadFormatter> {
+        let name = name.unwrap_or(DEFAULT_PAYLOAD_FORMAT);
+        self.formatters
+            .get(name)
+            .map(|formatter| formatter.as_ref())
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown payload format `{}`; available: {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,239 @@
+use crate::audit::upgrade_policy_name;
+use anyhow::{Context, Result, anyhow};
+use aptos_framework::natives::code::PackageMetadata;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// What a payload file of `deployment build` publishes, decoded for review before signing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PayloadInspection {
+    /// Payload format the file was recognized as: `entry-function-json`, `publish-args-json` or
+    /// `publish-args-bcs`
+    pub format: String,
+    /// `<address>::<module>::<function>` called; `None` for the publish arguments alone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// Resource account seed, as UTF-8 when it is valid UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed_hex: Option<String>,
+    /// Code object upgraded by `object_code_deployment::upgrade`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+    pub package: String,
+    /// `arbitrary`, `compatible` or `immutable`
+    pub upgrade_policy: String,
+    pub upgrade_number: u64,
+    pub source_digest: String,
+    /// Package dependencies as `<account>::<package>`
+    pub dependencies: Vec<String>,
+    /// Size of the BCS-serialized `PackageMetadata`
+    pub metadata_size: usize,
+    /// Modules in publishing order
+    pub modules: Vec<ModuleInspection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleInspection {
+    /// `<address>::<name>` of the bytecode
+    pub name: String,
+    /// Bytecode size in bytes
+    pub size: usize,
+    /// Whether the metadata carries the module's source (included artifacts `all`)
+    pub source: bool,
+}
+
+/// Decode the payload file at `path`: entry function JSON, publish arguments JSON, or the BCS
+/// publish arguments when it ends in `.bcs`
+pub fn inspect_payload_file(path: &Path) -> Result<PayloadInspection> {
+    let bytes = std::fs::read(path)?;
+    if path.extension().is_some_and(|extension| extension == "bcs") {
+        let (metadata, modules): (Vec<u8>, Vec<Vec<u8>>) = bcs::from_bytes(&bytes)
+            .context("expected the BCS of the publish arguments (metadata, modules)")?;
+        return inspect_package("publish-args-bcs", None, None, None, &metadata, &modules);
+    }
+    let payload: Value = serde_json::from_slice(&bytes).context("invalid payload JSON")?;
+    inspect_payload_json(&payload)
+}
+
+/// Decode an entry function JSON payload (`function_id` and `args`, as `aptos move run
+/// --json-file` takes) or publish arguments JSON (`metadata_serialized` and `code`)
+pub fn inspect_payload_json(payload: &Value) -> Result<PayloadInspection> {
+    if let Some(metadata) = payload.get("metadata_serialized") {
+        let metadata = hex_value(metadata).context("metadata_serialized")?;
+        let modules = payload
+            .get("code")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("`code` is not an array of hex strings"))?
+            .iter()
+            .map(hex_value)
+            .collect::<Result<Vec<_>>>()
+            .context("code")?;
+        return inspect_package("publish-args-json", None, None, None, &metadata, &modules);
+    }
+    let function = payload
+        .get("function_id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            anyhow!("neither `function_id` (entry function) nor `metadata_serialized` found")
+        })?;
+    let args = payload
+        .get("args")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("`args` of {} is not an array", function))?
+        .iter()
+        .map(|arg| arg.get("value").unwrap_or(arg))
+        .collect::<Vec<_>>();
+    // (seed,) metadata, modules (, object): the modules are the one vector argument
+    let modules_at = args
+        .iter()
+        .position(|arg| arg.is_array())
+        .filter(|&i| i >= 1)
+        .ok_or_else(|| anyhow!("{} has no metadata and modules arguments", function))?;
+    let modules = args[modules_at]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(hex_value)
+        .collect::<Result<Vec<_>>>()
+        .context("modules argument")?;
+    let metadata = hex_value(args[modules_at - 1]).context("metadata argument")?;
+    let seed = match modules_at {
+        2 => Some(hex_value(args[0]).context("seed argument")?),
+        _ => None,
+    };
+    let object = args
+        .get(modules_at + 1)
+        .and_then(|arg| arg.as_str())
+        .map(str::to_string);
+    inspect_package(
+        "entry-function-json",
+        Some(function.to_string()),
+        seed,
+        object,
+        &metadata,
+        &modules,
+    )
+}
+
+fn inspect_package(
+    format: &str,
+    function: Option<String>,
+    seed: Option<Vec<u8>>,
+    object: Option<String>,
+    metadata_bytes: &[u8],
+    modules: &[Vec<u8>],
+) -> Result<PayloadInspection> {
+    let metadata: PackageMetadata =
+        bcs::from_bytes(metadata_bytes).context("package metadata does not deserialize")?;
+    let modules = modules
+        .iter()
+        .enumerate()
+        .map(|(i, bytes)| {
+            let module = CompiledModule::deserialize(bytes)
+                .with_context(|| format!("module {} does not deserialize", i))?;
+            let id = module.self_id();
+            let source = metadata
+                .modules
+                .iter()
+                .any(|m| m.name == id.name().as_str() && !m.source.is_empty());
+            Ok(ModuleInspection {
+                name: format!("{}::{}", id.address().to_hex_literal(), id.name()),
+                size: bytes.len(),
+                source,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(PayloadInspection {
+        format: format.to_string(),
+        function,
+        seed: seed
+            .as_ref()
+            .and_then(|seed| String::from_utf8(seed.clone()).ok()),
+        seed_hex: seed.map(|seed| format!("0x{}", hex::encode(seed))),
+        object,
+        package: metadata.name,
+        upgrade_policy: upgrade_policy_name(metadata.upgrade_policy.policy).to_string(),
+        upgrade_number: metadata.upgrade_number,
+        source_digest: metadata.source_digest,
+        dependencies: metadata
+            .deps
+            .iter()
+            .map(|dep| format!("{}::{}", dep.account.to_hex_literal(), dep.package_name))
+            .collect(),
+        metadata_size: metadata_bytes.len(),
+        modules,
+    })
+}
+
+fn hex_value(value: &Value) -> Result<Vec<u8>> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected a hex string, got {}", value))?;
+    hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| anyhow!("invalid hex: {}", e))
+}
+
+/// `inspection` as text: the call, the package and one line per module with its size
+pub fn render_inspection(inspection: &PayloadInspection) -> String {
+    let mut lines = Vec::new();
+    if let Some(function) = &inspection.function {
+        lines.push(format!("Function:       {}", function));
+    } else {
+        lines.push(format!("Format:         {}", inspection.format));
+    }
+    if let Some(seed_hex) = &inspection.seed_hex {
+        lines.push(match &inspection.seed {
+            Some(seed) => format!("Seed:           {:?} ({})", seed, seed_hex),
+            None => format!("Seed:           {} (not UTF-8)", seed_hex),
+        });
+    }
+    if let Some(object) = &inspection.object {
+        lines.push(format!("Object:         {}", object));
+    }
+    lines.push(format!("Package:        {}", inspection.package));
+    lines.push(format!("Upgrade policy: {}", inspection.upgrade_policy));
+    lines.push(format!("Upgrade number: {}", inspection.upgrade_number));
+    lines.push(format!("Source digest:  {}", inspection.source_digest));
+    lines.push(format!(
+        "Dependencies:   {}",
+        if inspection.dependencies.is_empty() {
+            "-".to_string()
+        } else {
+            inspection.dependencies.join(", ")
+        }
+    ));
+    lines.push(format!(
+        "Metadata:       {} bytes",
+        inspection.metadata_size
+    ));
+    let total = inspection
+        .modules
+        .iter()
+        .map(|module| module.size)
+        .sum::<usize>();
+    lines.push(format!(
+        "Modules:        {} ({} bytes)",
+        inspection.modules.len(),
+        total
+    ));
+    let width = inspection
+        .modules
+        .iter()
+        .map(|module| module.name.len())
+        .max()
+        .unwrap_or(0);
+    for module in &inspection.modules {
+        lines.push(format!(
+            "  {:<width$}  {:>7} bytes{}",
+            module.name,
+            module.size,
+            if module.source { ", with source" } else { "" },
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,419 @@
+use crate::config::{DeployFunction, DeploymentMode, YeaptorConfig};
+use crate::deployment_planner::PlannedPackage;
+use anyhow::{Context, Result, anyhow};
+use aptos_framework::natives::code::{PackageMetadata, PackageRegistry};
+use aptos_rest_client::Client;
+use aptos_rest_client::aptos_api_types::AptosErrorCode;
+use aptos_rest_client::error::RestError;
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use yeaptor_core::diagnostics::Diagnostics;
+
+/// Gas units budgeted per package publish when estimating what a publisher must hold; real
+/// publishes of ordinary packages stay well below it
+pub const DEFAULT_MAX_GAS_PER_PACKAGE: u64 = 200_000;
+
+/// A deployment as seen by the preflight: who publishes how many packages to which account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PreflightTarget {
+    /// `deployments[i]`
+    pub location: String,
+    /// The deployment's `publisher`, as written in yeaptor.toml
+    pub publisher_name: String,
+    pub publisher: AccountAddress,
+    pub mode: DeploymentMode,
+    /// Empty unless `DeploymentMode::ResourceAccount`
+    pub seed: String,
+    /// Account the packages are published to: the publisher itself with `DeploymentMode::Account`,
+    /// the code object with `DeploymentMode::Object`
+    pub resource_account: AccountAddress,
+    /// `sequence_number` of an object deployment creating its object
+    pub sequence_number: Option<u64>,
+    pub packages: usize,
+}
+
+/// On-chain state of a resource account a deployment publishes to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceAccountState {
+    pub exists: bool,
+    /// Holds the `ra_code_deployment::PublishPackageCap` of the yeaptor address
+    pub publish_cap: bool,
+    /// Admin of its `manageable` role, allowed to publish upgrades
+    pub admin: Option<AccountAddress>,
+}
+
+/// What the preflight read from the node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainState {
+    /// Balance of each publisher in octas; `None` when the account does not exist
+    pub publishers: BTreeMap<AccountAddress, Option<u64>>,
+    /// Next sequence number of each existing publisher
+    pub sequence_numbers: BTreeMap<AccountAddress, u64>,
+    pub resource_accounts: BTreeMap<AccountAddress, ResourceAccountState>,
+    pub gas_unit_price: u64,
+}
+
+/// Deployments of `config` whose publisher resolves, counting their packages selected by `tags`
+/// (all when empty); the others are reported by `YeaptorEnv::check`. With `tags`, deployments
+/// without a selected package are left out.
+pub fn preflight_targets(config: &YeaptorConfig, tags: &[String]) -> Vec<PreflightTarget> {
+    config
+        .deployments
+        .iter()
+        .zip(config.deployment_addresses())
+        .enumerate()
+        .filter_map(|(i, (deployment, resource_account))| {
+            let packages = deployment
+                .packages
+                .iter()
+                .filter(|package| deployment.selects(package, tags))
+                .count();
+            if !tags.is_empty() && packages == 0 {
+                return None;
+            }
+            Some(PreflightTarget {
+                location: format!("deployments[{}]", i),
+                publisher_name: deployment.publisher.clone(),
+                publisher: config.publisher_address(&deployment.publisher)?,
+                mode: deployment.mode(),
+                seed: deployment.seed().to_string(),
+                resource_account: resource_account?,
+                sequence_number: match deployment.object_address {
+                    Some(_) => None,
+                    None => deployment.sequence_number,
+                },
+                packages,
+            })
+        })
+        .collect()
+}
+
+/// Read the publishers, resource accounts and gas price of `targets` from the node behind
+/// `client`. `gas_unit_price` replaces the node's estimate when set.
+pub async fn fetch_chain_state(
+    client: &Client,
+    deploy: &DeployFunction,
+    targets: &[PreflightTarget],
+    gas_unit_price: Option<u64>,
+) -> Result<ChainState> {
+    let gas_unit_price = match gas_unit_price {
+        Some(price) => price,
+        None => {
+            client
+                .estimate_gas_price()
+                .await
+                .context("failed to estimate the gas price")?
+                .into_inner()
+                .gas_estimate
+        }
+    };
+    let mut state = ChainState {
+        gas_unit_price,
+        ..Default::default()
+    };
+    for target in targets {
+        if !state.publishers.contains_key(&target.publisher) {
+            let sequence_number = account_sequence_number(client, target.publisher).await?;
+            if let Some(sequence_number) = sequence_number {
+                state
+                    .sequence_numbers
+                    .insert(target.publisher, sequence_number);
+            }
+            let balance = if sequence_number.is_some() {
+                Some(
+                    client
+                        .view_apt_account_balance(target.publisher)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "failed to read the balance of {}",
+                                target.publisher.to_standard_string()
+                            )
+                        })?
+                        .into_inner(),
+                )
+            } else {
+                None
+            };
+            state.publishers.insert(target.publisher, balance);
+        }
+        if target.mode != DeploymentMode::ResourceAccount {
+            continue;
+        }
+        if account_sequence_number(client, target.resource_account)
+            .await?
+            .is_none()
+        {
+            state
+                .resource_accounts
+                .insert(target.resource_account, ResourceAccountState::default());
+            continue;
+        }
+        let resources = client
+            .get_account_resources(target.resource_account)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to read the resources of {}",
+                    target.resource_account.to_standard_string()
+                )
+            })?
+            .into_inner();
+        let mut account = ResourceAccountState {
+            exists: true,
+            ..Default::default()
+        };
+        for resource in resources {
+            let tag = &resource.resource_type;
+            match (tag.module.as_str(), tag.name.as_str()) {
+                (module, "PublishPackageCap")
+                    if module == deploy.module && *tag.address.inner() == deploy.address =>
+                {
+                    account.publish_cap = true;
+                }
+                ("manageable", "AdminRole") => {
+                    account.admin = resource.data["admin"]
+                        .as_str()
+                        .and_then(|admin| AccountAddress::from_str(admin).ok());
+                }
+                _ => {}
+            }
+        }
+        state
+            .resource_accounts
+            .insert(target.resource_account, account);
+    }
+    Ok(state)
+}
+
+/// Sequence number of the account at `address`; `None` when it does not exist
+pub async fn account_sequence_number(
+    client: &Client,
+    address: AccountAddress,
+) -> Result<Option<u64>> {
+    match client.get_account(address).await {
+        Ok(account) => Ok(Some(account.into_inner().sequence_number)),
+        Err(e) if is_not_found(&e) => Ok(None),
+        Err(e) => Err(anyhow!(e).context(format!(
+            "failed to look up account {}",
+            address.to_standard_string()
+        ))),
+    }
+}
+
+fn is_not_found(e: &RestError) -> bool {
+    matches!(e, RestError::Api(e) if matches!(
+        e.error.error_code,
+        AptosErrorCode::AccountNotFound | AptosErrorCode::ResourceNotFound
+    ))
+}
+
+/// Packages published at `address` (its `0x1::code::PackageRegistry`); empty when there is none
+pub async fn fetch_published_packages(
+    client: &Client,
+    address: AccountAddress,
+) -> Result<Vec<PackageMetadata>> {
+    match client
+        .get_account_resource_bcs::<PackageRegistry>(address, "0x1::code::PackageRegistry")
+        .await
+    {
+        Ok(registry) => Ok(registry.into_inner().packages),
+        Err(e) if is_not_found(&e) => Ok(Vec::new()),
+        Err(e) => Err(anyhow!(e).context(format!(
+            "failed to read the packages published at {}",
+            address.to_standard_string()
+        ))),
+    }
+}
+
+/// Whether a package was built with its module sources, as with `all` included artifacts;
+/// `sparse` and `none` leave them out, so the two cannot be told apart on chain
+pub fn includes_sources(metadata: &PackageMetadata) -> bool {
+    metadata
+        .modules
+        .iter()
+        .any(|module| !module.source.is_empty())
+}
+
+/// Warning when an upgrade of the package `name` changes whether its sources are included
+/// (`includes_sources` of the published and the planned metadata), which breaks (or newly
+/// enables) verifying the published code against its sources
+pub fn artifact_change(name: &str, published: bool, planned: bool) -> Option<String> {
+    match (published, planned) {
+        (true, false) => Some(format!(
+            "package {} was published with its sources (included artifacts `all`) but the \
+             upgrade leaves them out (`sparse` or `none`), so its code can no longer be verified \
+             against its sources; set include_artifacts = \"all\" to keep them",
+            name
+        )),
+        (false, true) => Some(format!(
+            "package {} was published without sources (`sparse` or `none`) but the upgrade \
+             includes them (`all`); check that publishing its sources is intended",
+            name
+        )),
+        _ => None,
+    }
+}
+
+/// `artifact_change` of every planned package already published at its resource account
+pub async fn upgrade_artifact_warnings(
+    client: &Client,
+    packages: &[PlannedPackage],
+) -> Result<Vec<String>> {
+    let mut published: BTreeMap<AccountAddress, Vec<PackageMetadata>> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for package in packages {
+        let address = package.account;
+        if !published.contains_key(&address) {
+            published.insert(address, fetch_published_packages(client, address).await?);
+        }
+        let Some(previous) = published[&address]
+            .iter()
+            .find(|previous| previous.name == package.name)
+        else {
+            continue;
+        };
+        let planned: PackageMetadata = bcs::from_bytes(&package.metadata)
+            .with_context(|| format!("invalid metadata of package {}", package.name))?;
+        warnings.extend(artifact_change(
+            &package.name,
+            includes_sources(previous),
+            includes_sources(&planned),
+        ));
+    }
+    Ok(warnings)
+}
+
+/// Check `targets` against `state`: every publisher exists and holds enough for
+/// `max_gas_per_package` at the gas price for each of its packages, every resource account
+/// that already exists (an upgrade) can still be published to and is administered by the
+/// deployment's publisher, and the `sequence_number` of every new code object is not used yet
+/// (the object would be created at another address). Returns whether each resource account target is an upgrade (targets
+/// publishing to the publisher's own account are left out), or every problem found.
+pub fn check_chain_state(
+    targets: &[PreflightTarget],
+    state: &ChainState,
+    deploy: &DeployFunction,
+    max_gas_per_package: u64,
+) -> Result<BTreeMap<String, bool>> {
+    let mut diagnostics = Diagnostics::default();
+    let mut upgrades = BTreeMap::new();
+    let mut packages: BTreeMap<AccountAddress, (&PreflightTarget, usize)> = BTreeMap::new();
+    for target in targets {
+        packages.entry(target.publisher).or_insert((target, 0)).1 += target.packages;
+    }
+    for (publisher, (first, count)) in &packages {
+        let publisher = publisher.to_standard_string();
+        match state.publishers.get(&first.publisher).copied().flatten() {
+            None => diagnostics.push(
+                &first.location,
+                format!(
+                    "publisher `{}` ({}) does not exist on chain; create and fund the account \
+                     before deploying",
+                    first.publisher_name, publisher
+                ),
+            ),
+            Some(balance) => {
+                let required = (*count as u64)
+                    .saturating_mul(max_gas_per_package)
+                    .saturating_mul(state.gas_unit_price);
+                if balance < required {
+                    diagnostics.push(
+                        &first.location,
+                        format!(
+                            "publisher `{}` ({}) holds {} octas but publishing {} package(s) may \
+                             cost up to {} octas ({} gas units each at {} octas); fund the \
+                             account or lower --max-gas",
+                            first.publisher_name,
+                            publisher,
+                            balance,
+                            count,
+                            required,
+                            max_gas_per_package,
+                            state.gas_unit_price
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    for target in targets {
+        let (Some(sequence_number), Some(next)) = (
+            target.sequence_number,
+            state.sequence_numbers.get(&target.publisher),
+        ) else {
+            continue;
+        };
+        if sequence_number < *next {
+            diagnostics.push(
+                &target.location,
+                format!(
+                    "sequence_number {} of publisher `{}` is already used (the account is at {}), \
+                     so publishing would create the code object at another address than {}; \
+                     set sequence_number to the one of the publishing transaction",
+                    sequence_number,
+                    target.publisher_name,
+                    next,
+                    target.resource_account.to_standard_string()
+                ),
+            );
+        }
+    }
+    for target in targets
+        .iter()
+        .filter(|t| t.mode == DeploymentMode::ResourceAccount)
+    {
+        let account = state
+            .resource_accounts
+            .get(&target.resource_account)
+            .cloned()
+            .unwrap_or_default();
+        upgrades.insert(target.location.clone(), account.exists);
+        if !account.exists {
+            continue;
+        }
+        let resource_account = target.resource_account.to_standard_string();
+        if !account.publish_cap {
+            diagnostics.push(
+                &target.location,
+                format!(
+                    "resource account {} of seed `{}` exists but holds no \
+                     {}::{}::PublishPackageCap; it was frozen or not created by {}, so nothing \
+                     can be published to it",
+                    resource_account,
+                    target.seed,
+                    deploy.address.to_standard_string(),
+                    deploy.module,
+                    deploy.module
+                ),
+            );
+            continue;
+        }
+        match account.admin {
+            Some(admin) if admin == target.publisher => {}
+            Some(admin) => diagnostics.push(
+                &target.location,
+                format!(
+                    "resource account {} is administered by {}, not by publisher `{}` ({}); \
+                     deploy as its admin or transfer the role with manageable::change_admin",
+                    resource_account,
+                    admin.to_standard_string(),
+                    target.publisher_name,
+                    target.publisher.to_standard_string()
+                ),
+            ),
+            None => diagnostics.push(
+                &target.location,
+                format!(
+                    "resource account {} has no manageable admin, so publisher `{}` cannot \
+                     upgrade it",
+                    resource_account, target.publisher_name
+                ),
+            ),
+        }
+    }
+    diagnostics.into_result()?;
+    Ok(upgrades)
+}
@@ -0,0 +1,293 @@
+use crate::db_schema::{TableOptions, split_table_schemas_by_group};
+use crate::error::{YeaptorError, YeaptorResult};
+use crate::event_definition::EventDefinition;
+use crate::event_table_mapping::{
+    EntryFunctionMappings, EventTableMappings, TableItemMappings,
+    restrict_entry_functions_to_tables, restrict_mappings_to_tables,
+    restrict_table_items_to_tables,
+};
+use crate::processor_config::{
+    ProcessorConfig, SpecIdentifier, TableSchema, apply_processor_overrides,
+};
+use crate::processor_config_generator::{
+    CATCH_ALL_PAYLOAD_COLUMN, apply_conflict_strategies, apply_entry_function_mappings,
+    apply_table_indexes, apply_table_item_mappings, apply_table_partitioning,
+    attach_catch_all_payloads, catch_all_mappings, catch_all_table_schema,
+    generate_processor_config,
+};
+use crate::standard_templates::StandardTemplates;
+use anyhow::{Context, anyhow};
+use aptos::common::init::Network;
+use aptos_types::account_address::AccountAddress;
+use serde_yaml::Value as YamlValue;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Generates processor configs from event definitions, a DB schema and an event -> table
+/// mapping, the way `processor generate` does, without reading or writing files.
+///
+/// ```ignore
+/// let generated = ProcessorConfigBuilder::new(Network::Mainnet, 1_000_000)
+///     .event_definitions(EventExtractor::new().extract(&built_package)?)
+///     .db_schema(load_db_schema(Path::new("db_schema.toml"))?)
+///     .event_mapping(load_event_table_mappings(Path::new("event_mapping.toml"))?)
+///     .build()?;
+/// save_processor_config_yaml(Path::new("processor_config.yaml"), &generated.config)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProcessorConfigBuilder {
+    spec_identifier: SpecIdentifier,
+    network: Network,
+    starting_version: u64,
+    ending_version: Option<u64>,
+    event_definitions: Vec<EventDefinition>,
+    db_schema: BTreeMap<String, TableSchema>,
+    table_options: BTreeMap<String, TableOptions>,
+    event_mapping: EventTableMappings,
+    entry_function_mapping: EntryFunctionMappings,
+    table_item_mapping: TableItemMappings,
+    standard_templates: StandardTemplates,
+    catch_all_table: Option<String>,
+    account_filter: Vec<AccountAddress>,
+    overrides: Option<YamlValue>,
+}
+
+/// A generated config, with what the mapping leaves out
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedProcessorConfig {
+    pub config: ProcessorConfig,
+    /// Defined events no table is mapped from
+    pub unmapped_events: Vec<String>,
+    /// (table, column) pairs no event writes to
+    pub unmapped_columns: Vec<(String, String)>,
+}
+
+impl ProcessorConfigBuilder {
+    pub fn new(network: Network, starting_version: u64) -> Self {
+        ProcessorConfigBuilder {
+            spec_identifier: SpecIdentifier::default(),
+            network,
+            starting_version,
+            ending_version: None,
+            event_definitions: Vec::new(),
+            db_schema: BTreeMap::new(),
+            table_options: BTreeMap::new(),
+            event_mapping: EventTableMappings::new(),
+            entry_function_mapping: EntryFunctionMappings::new(),
+            table_item_mapping: TableItemMappings::new(),
+            standard_templates: StandardTemplates::default(),
+            catch_all_table: None,
+            account_filter: Vec::new(),
+            overrides: None,
+        }
+    }
+
+    pub fn spec_identifier(mut self, spec_identifier: SpecIdentifier) -> Self {
+        self.spec_identifier = spec_identifier;
+        self
+    }
+
+    pub fn spec_version(mut self, spec_version: impl Into<String>) -> Self {
+        self.spec_identifier.spec_version = spec_version.into();
+        self
+    }
+
+    /// Last version to process (inclusive); forever when unset
+    pub fn ending_version(mut self, ending_version: Option<u64>) -> Self {
+        self.ending_version = ending_version;
+        self
+    }
+
+    pub fn event_definitions(mut self, event_definitions: Vec<EventDefinition>) -> Self {
+        self.event_definitions = event_definitions;
+        self
+    }
+
+    pub fn db_schema(mut self, db_schema: BTreeMap<String, TableSchema>) -> Self {
+        self.db_schema = db_schema;
+        self
+    }
+
+    /// Groups, conflict strategies, indexes and partitioning of the tables
+    pub fn table_options(mut self, table_options: BTreeMap<String, TableOptions>) -> Self {
+        self.table_options = table_options;
+        self
+    }
+
+    pub fn event_mapping(mut self, event_mapping: EventTableMappings) -> Self {
+        self.event_mapping = event_mapping;
+        self
+    }
+
+    /// Entry function calls written to tables, for user actions that emit no event
+    pub fn entry_function_mapping(mut self, entry_function_mapping: EntryFunctionMappings) -> Self {
+        self.entry_function_mapping = entry_function_mapping;
+        self
+    }
+
+    /// Move table item writes written to tables, for state no event reports
+    pub fn table_item_mapping(mut self, table_item_mapping: TableItemMappings) -> Self {
+        self.table_item_mapping = table_item_mapping;
+        self
+    }
+
+    /// Framework events added to the inputs; declared tables and mappings take precedence
+    pub fn standard_templates(mut self, standard_templates: StandardTemplates) -> Self {
+        self.standard_templates = standard_templates;
+        self
+    }
+
+    /// Write every defined event without a mapping to this table (whole event as JSON in
+    /// `payload`, plus metadata); the table gets a default layout unless the schema declares it
+    pub fn catch_all_table(mut self, catch_all_table: Option<String>) -> Self {
+        self.catch_all_table = catch_all_table;
+        self
+    }
+
+    /// Only index transactions sent by, or emitting events under, these accounts; all when empty
+    pub fn account_filter(mut self, accounts: Vec<AccountAddress>) -> Self {
+        self.account_filter = accounts;
+        self
+    }
+
+    /// YAML deep-merged into the generated config
+    pub fn overrides(mut self, overrides: Option<YamlValue>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Groups of the tables, for `build_group`; tables without one are in the default group
+    pub fn groups(&self) -> Vec<String> {
+        let (_, db_schema, _) = self.inputs();
+        split_table_schemas_by_group(&db_schema, &self.table_groups())
+            .into_keys()
+            .collect()
+    }
+
+    /// Config covering every table
+    pub fn build(&self) -> YeaptorResult<GeneratedProcessorConfig> {
+        let (event_definitions, db_schema, event_mapping) = self.inputs();
+        self.generate(
+            self.spec_identifier.clone(),
+            &event_definitions,
+            &db_schema,
+            &event_mapping,
+            &self.entry_function_mapping,
+            &self.table_item_mapping,
+        )
+    }
+
+    /// Config covering the tables of one group, with `-<group>` appended to the spec name
+    pub fn build_group(&self, group: &str) -> YeaptorResult<GeneratedProcessorConfig> {
+        let (event_definitions, db_schema, event_mapping) = self.inputs();
+        let tables = split_table_schemas_by_group(&db_schema, &self.table_groups())
+            .remove(group)
+            .ok_or_else(|| YeaptorError::invalid(anyhow!("no table in group {}", group)))?;
+        let mut spec_identifier = self.spec_identifier.clone();
+        spec_identifier.spec_name = format!("{}-{}", spec_identifier.spec_name, group);
+        self.generate(
+            spec_identifier,
+            &event_definitions,
+            &tables,
+            &restrict_mappings_to_tables(&event_mapping, &tables),
+            &restrict_entry_functions_to_tables(&self.entry_function_mapping, &tables),
+            &restrict_table_items_to_tables(&self.table_item_mapping, &tables),
+        )
+    }
+
+    fn table_groups(&self) -> BTreeMap<String, String> {
+        self.table_options
+            .iter()
+            .filter_map(|(table, options)| Some((table.clone(), options.group.clone()?)))
+            .collect()
+    }
+
+    /// Definitions, schema and mapping with the standard templates and the catch-all table added
+    fn inputs(
+        &self,
+    ) -> (
+        Vec<EventDefinition>,
+        BTreeMap<String, TableSchema>,
+        EventTableMappings,
+    ) {
+        let mut event_definitions = self.event_definitions.clone();
+        let mut db_schema = self.db_schema.clone();
+        let mut event_mapping = self.event_mapping.clone();
+        let templates = &self.standard_templates;
+        templates.extend_event_definitions(&mut event_definitions);
+        templates.extend_table_schemas(&mut db_schema);
+        templates.extend_event_mapping(&mut event_mapping);
+        if let Some(table) = &self.catch_all_table {
+            // A table declared in the schema takes precedence over the default layout
+            db_schema
+                .entry(table.clone())
+                .or_insert_with(catch_all_table_schema);
+            let caught = catch_all_mappings(&event_definitions, &event_mapping, table);
+            event_mapping.extend(caught);
+        }
+        (event_definitions, db_schema, event_mapping)
+    }
+
+    fn generate(
+        &self,
+        spec_identifier: SpecIdentifier,
+        event_definitions: &[EventDefinition],
+        db_schema: &BTreeMap<String, TableSchema>,
+        event_mapping: &EventTableMappings,
+        entry_function_mapping: &EntryFunctionMappings,
+        table_item_mapping: &TableItemMappings,
+    ) -> YeaptorResult<GeneratedProcessorConfig> {
+        if let Some(ending_version) = self.ending_version {
+            if ending_version < self.starting_version {
+                return Err(YeaptorError::invalid(anyhow!(
+                    "ending version {} is before starting version {}",
+                    ending_version,
+                    self.starting_version
+                )));
+            }
+        }
+        let (mut config, unmapped_events, mut unmapped_columns) = generate_processor_config(
+            spec_identifier,
+            self.network,
+            self.starting_version,
+            event_definitions,
+            db_schema,
+            event_mapping,
+        )
+        .map_err(YeaptorError::mapping)?;
+        config.common_config.ending_version = self.ending_version;
+        config.common_config.account_filter = self
+            .account_filter
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(AccountAddress::to_standard_string)
+            .collect();
+        if let Some(table) = &self.catch_all_table {
+            attach_catch_all_payloads(&mut config, event_definitions, event_mapping, table);
+            unmapped_columns
+                .retain(|(t, column)| !(t == table && column == CATCH_ALL_PAYLOAD_COLUMN));
+        }
+        let mut written = apply_entry_function_mappings(&mut config, entry_function_mapping)
+            .map_err(YeaptorError::mapping)?;
+        written.extend(
+            apply_table_item_mappings(&mut config, table_item_mapping)
+                .map_err(YeaptorError::mapping)?,
+        );
+        unmapped_columns
+            .retain(|(table, column)| !written.contains(&(table.clone(), column.clone())));
+        apply_conflict_strategies(&mut config, &self.table_options)
+            .and_then(|()| apply_table_indexes(&mut config, &self.table_options))
+            .and_then(|()| apply_table_partitioning(&mut config, &self.table_options))
+            .map_err(YeaptorError::invalid)?;
+        if let Some(overrides) = &self.overrides {
+            config = apply_processor_overrides(&config, overrides)
+                .context("invalid overrides")
+                .map_err(YeaptorError::invalid)?;
+        }
+        Ok(GeneratedProcessorConfig {
+            config,
+            unmapped_events,
+            unmapped_columns,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use crate::processor_config::{
+    ColumnTarget, ComputedColumn, EntryFunctionMapping, ProcessorConfig, TableItemMapping,
+};
+use crate::processor_config_generator::{COMPUTED_COLUMNS, ENTRY_FUNCTIONS, TABLE_ITEMS};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LintFinding {
+    /// Rule id, e.g. `table-without-primary-key`
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Table, `table::column` or event the finding is about
+    pub location: String,
+    pub message: String,
+}
+
+impl LintFinding {
+    fn new(
+        rule: &'static str,
+        severity: Severity,
+        location: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            rule,
+            severity,
+            location: location.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Lint a processor config. Findings are ordered by severity, rule and location.
+///
+/// Rules:
+/// - `table-without-primary-key` (error): replays cannot be deduplicated
+/// - `unknown-target` (error): a mapping writes to a table or column missing from `db_schema`
+/// - `unmapped-index-column` (warning): an indexed column nothing writes to
+/// - `event-without-fields` (warning): an event mapping that writes no field or constant
+/// - `duplicate-metadata-column` (warning): two columns of a table hold the same metadata,
+///   only the first is written by the generator
+pub fn lint_processor_config(cfg: &ProcessorConfig) -> Vec<LintFinding> {
+    let custom = &cfg.custom_config;
+    let mut findings = Vec::new();
+
+    for (table, schema) in &custom.db_schema {
+        if !schema.values().any(|c| c.is_primary_key) {
+            findings.push(LintFinding::new(
+                "table-without-primary-key",
+                Severity::Error,
+                table,
+                "table has no primary key column",
+            ));
+        }
+        let mut metadata_columns: BTreeMap<(&str, &str), &str> = BTreeMap::new();
+        for (column, spec) in schema {
+            if spec.column_type.r#type == "move_type" {
+                continue;
+            }
+            let key = (
+                spec.column_type.r#type.as_str(),
+                spec.column_type.column_type.as_str(),
+            );
+            if let Some(first) = metadata_columns.insert(key, column) {
+                findings.push(LintFinding::new(
+                    "duplicate-metadata-column",
+                    Severity::Warning,
+                    format!("{}::{}", table, column),
+                    format!("{} {} is also held by column {}", key.0, key.1, first),
+                ));
+            }
+        }
+    }
+
+    // Every (table, column) some part of the config writes to
+    let mut targets: Vec<&ColumnTarget> = Vec::new();
+    let mut written: BTreeSet<(String, String)> = BTreeSet::new();
+    for (event, mapping) in &custom.events {
+        if mapping.event_fields.is_empty() && mapping.constant_values.is_empty() {
+            findings.push(LintFinding::new(
+                "event-without-fields",
+                Severity::Warning,
+                event,
+                "event mapping writes no field or constant",
+            ));
+        }
+        targets.extend(mapping.event_fields.values().flatten());
+        targets.extend(mapping.event_metadata.values().flatten());
+        for constant in &mapping.constant_values {
+            written.insert((constant.table.clone(), constant.column.clone()));
+        }
+    }
+    targets.extend(custom.transaction_metadata.values().flatten());
+    targets.extend(custom.event_metadata.values().flatten());
+    for target in targets {
+        written.insert((target.table.clone(), target.column.clone()));
+    }
+    if let Some(computed) = custom.payload.get(COMPUTED_COLUMNS) {
+        let computed: Vec<ComputedColumn> =
+            serde_yaml::from_value(computed.clone()).unwrap_or_default();
+        for column in computed {
+            written.insert((column.table, column.column));
+        }
+    }
+    if let Some(entry_functions) = custom.payload.get(ENTRY_FUNCTIONS) {
+        let entry_functions: Vec<EntryFunctionMapping> =
+            serde_yaml::from_value(entry_functions.clone()).unwrap_or_default();
+        for mapping in entry_functions {
+            for target in mapping.payload_fields.values().flatten() {
+                written.insert((target.table.clone(), target.column.clone()));
+            }
+            for constant in mapping.constant_values {
+                written.insert((constant.table, constant.column));
+            }
+        }
+    }
+    if let Some(table_items) = custom.payload.get(TABLE_ITEMS) {
+        let table_items: Vec<TableItemMapping> =
+            serde_yaml::from_value(table_items.clone()).unwrap_or_default();
+        for mapping in table_items {
+            for target in mapping.payload_fields.values().flatten() {
+                written.insert((target.table.clone(), target.column.clone()));
+            }
+            for constant in mapping.constant_values {
+                written.insert((constant.table, constant.column));
+            }
+        }
+    }
+
+    for (table, column) in &written {
+        let known = custom
+            .db_schema
+            .get(table)
+            .is_some_and(|schema| schema.contains_key(column));
+        if !known {
+            findings.push(LintFinding::new(
+                "unknown-target",
+                Severity::Error,
+                format!("{}::{}", table, column),
+                "mapped column is not declared in db_schema",
+            ));
+        }
+    }
+    for (table, schema) in &custom.db_schema {
+        for (column, spec) in schema {
+            if spec.is_index && !written.contains(&(table.clone(), column.clone())) {
+                findings.push(LintFinding::new(
+                    "unmapped-index-column",
+                    Severity::Warning,
+                    format!("{}::{}", table, column),
+                    "indexed column is never written",
+                ));
+            }
+        }
+    }
+
+    findings
+        .sort_by(|a, b| (a.severity, a.rule, &a.location).cmp(&(b.severity, b.rule, &b.location)));
+    findings
+}
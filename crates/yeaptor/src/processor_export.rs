@@ -0,0 +1,231 @@
+use crate::mapping_engine::{MappingEngine, Row};
+use crate::processor_config::{ProcessorConfig, TableSchema};
+use crate::processor_metrics::ProcessorMetrics;
+use crate::processor_runtime::{
+    AccountFilter, RunSummary, TransactionStream, filtered_transaction_record, rows_to_csv,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use arrow_array::{ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// Zstd-compressed; every column is a nullable string holding the text the writer would bind
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Where `processor export` reads transactions from and writes files to
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub grpc_address: String,
+    pub auth_token: String,
+    pub starting_version: u64,
+    pub ending_version: u64,
+    pub out_dir: PathBuf,
+    pub format: ExportFormat,
+    /// Rows of a table buffered before they are written to a file of their own
+    pub rows_per_file: usize,
+}
+
+/// Rows of a table not written yet, with the versions they cover
+struct PendingFile {
+    first_version: u64,
+    last_version: u64,
+    rows: Vec<Row>,
+}
+
+/// Writes rows into files partitioned by table and version range:
+/// `<out_dir>/<table>/part-<first version>-<last version>.<csv|parquet>`, with zero-padded
+/// versions so the files of a table sort in version order.
+pub struct ExportWriter {
+    tables: BTreeMap<String, TableSchema>,
+    out_dir: PathBuf,
+    format: ExportFormat,
+    rows_per_file: usize,
+    pending: BTreeMap<String, PendingFile>,
+    files: Vec<PathBuf>,
+}
+
+impl ExportWriter {
+    pub fn new(
+        tables: &BTreeMap<String, TableSchema>,
+        out_dir: &Path,
+        format: ExportFormat,
+        rows_per_file: usize,
+    ) -> Self {
+        ExportWriter {
+            tables: tables.clone(),
+            out_dir: out_dir.to_path_buf(),
+            format,
+            rows_per_file: rows_per_file.max(1),
+            pending: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Buffer the rows of transactions `first_version..=last_version`, writing the files of
+    /// tables that reached `rows_per_file`
+    pub fn write(&mut self, rows: Vec<Row>, first_version: u64, last_version: u64) -> Result<()> {
+        for row in rows {
+            if !self.tables.contains_key(&row.table) {
+                bail!("unknown table {}", row.table);
+            }
+            let pending = self
+                .pending
+                .entry(row.table.clone())
+                .or_insert_with(|| PendingFile {
+                    first_version,
+                    last_version,
+                    rows: Vec::new(),
+                });
+            pending.last_version = last_version;
+            pending.rows.push(row);
+        }
+        let full = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.rows.len() >= self.rows_per_file)
+            .map(|(table, _)| table.clone())
+            .collect::<Vec<_>>();
+        for table in full {
+            self.flush(&table)?;
+        }
+        Ok(())
+    }
+
+    /// Write the remaining rows; returns every file written, in order
+    pub fn finish(mut self) -> Result<Vec<PathBuf>> {
+        let tables = self.pending.keys().cloned().collect::<Vec<_>>();
+        for table in tables {
+            self.flush(&table)?;
+        }
+        Ok(self.files)
+    }
+
+    fn flush(&mut self, table: &str) -> Result<()> {
+        let Some(pending) = self.pending.remove(table) else {
+            return Ok(());
+        };
+        let schema = &self.tables[table];
+        let dir = self.out_dir.join(table);
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = dir.join(format!(
+            "part-{:020}-{:020}.{}",
+            pending.first_version,
+            pending.last_version,
+            self.format.extension()
+        ));
+        let result = match self.format {
+            ExportFormat::Csv => write_csv(&path, table, schema, &pending.rows),
+            ExportFormat::Parquet => write_parquet(&path, schema, &pending.rows),
+        };
+        result.with_context(|| format!("failed to write {}", path.display()))?;
+        self.files.push(path);
+        Ok(())
+    }
+}
+
+fn write_csv(path: &Path, table: &str, schema: &TableSchema, rows: &[Row]) -> Result<()> {
+    let tables = BTreeMap::from([(table.to_string(), schema.clone())]);
+    let mut csv = rows_to_csv(&tables, rows)?;
+    let content = csv
+        .remove(table)
+        .ok_or_else(|| anyhow!("no CSV for table {}", table))?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn write_parquet(path: &Path, schema: &TableSchema, rows: &[Row]) -> Result<()> {
+    let fields = schema
+        .keys()
+        .map(|column| Field::new(column, DataType::Utf8, true))
+        .collect::<Vec<_>>();
+    let arrow_schema = Arc::new(Schema::new(fields));
+    let columns = schema
+        .keys()
+        .map(|column| {
+            let values = rows
+                .iter()
+                .map(|row| row.values.get(column).and_then(|v| v.as_deref()))
+                .collect::<StringArray>();
+            Arc::new(values) as ArrayRef
+        })
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_new(arrow_schema.clone(), columns)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let file = fs::File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, arrow_schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Stream `starting_version..=ending_version`, apply the config's mappings and write the rows to
+/// files instead of a database. Nothing is checkpointed: an interrupted export starts over.
+pub async fn run_export(
+    config: &ProcessorConfig,
+    options: &ExportOptions,
+) -> Result<(RunSummary, Vec<PathBuf>)> {
+    if options.ending_version < options.starting_version {
+        bail!(
+            "ending version {} is before starting version {}",
+            options.ending_version,
+            options.starting_version
+        );
+    }
+    let engine = MappingEngine::new(config)?;
+    let account_filter = AccountFilter::from_config(config)?;
+    let metrics = Arc::new(ProcessorMetrics::new(&config.spec_identifier.spec_name));
+    let mut stream = TransactionStream::connect(
+        &options.grpc_address,
+        &options.auth_token,
+        metrics,
+        options.starting_version,
+        Some(options.ending_version),
+    )
+    .await?;
+    let mut writer = ExportWriter::new(
+        engine.tables(),
+        &options.out_dir,
+        options.format,
+        options.rows_per_file,
+    );
+    let mut summary = RunSummary::default();
+    while let Some(batch) = stream.next_batch().await? {
+        let (Some(first), Some(last)) = (batch.first(), batch.last()) else {
+            continue;
+        };
+        let (first_version, last_version) = (first.version, last.version);
+        let transactions = batch
+            .iter()
+            .map(|txn| filtered_transaction_record(txn, account_filter.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+        let rows = engine.map_transactions(&transactions)?;
+        for row in &rows {
+            *summary.rows_written.entry(row.table.clone()).or_default() += 1;
+        }
+        summary.first_version.get_or_insert(first_version);
+        summary.last_version = Some(last_version);
+        writer.write(rows, first_version, last_version)?;
+    }
+    Ok((summary, writer.finish()?))
+}
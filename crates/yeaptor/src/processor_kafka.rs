@@ -0,0 +1,129 @@
+use crate::mapping_engine::Row;
+use crate::processor_config::TableSchema;
+use anyhow::{Context, Result, anyhow};
+use rdkafka::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How long a row may wait for room in the producer queue
+const KAFKA_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Which topic a row is published to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicPer {
+    /// `<prefix><table>`
+    Table,
+    /// `<prefix><address>.<module>.<event>`, e.g. `0x42.market.MarketCreatedEvent`
+    Event,
+}
+
+/// Kafka sink of `processor run`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaOptions {
+    /// `bootstrap.servers`, e.g. `localhost:9092`
+    pub brokers: String,
+    pub topic_prefix: String,
+    pub topic_per: TopicPer,
+    /// Publish rows to Kafka only; Postgres keeps just the processor checkpoint
+    pub only: bool,
+}
+
+/// A row as published to Kafka
+#[derive(Debug, Serialize)]
+struct KafkaRow<'a> {
+    table: &'a str,
+    event: &'a str,
+    /// Last version of the batch the row was committed with
+    version: u64,
+    values: &'a BTreeMap<String, Option<String>>,
+}
+
+/// Topic `row` is published to
+pub fn kafka_topic(options: &KafkaOptions, row: &Row) -> String {
+    let name = match options.topic_per {
+        TopicPer::Table => row.table.clone(),
+        // topic names only allow [a-zA-Z0-9._-]
+        TopicPer::Event => row.event.replace("::", "."),
+    };
+    format!("{}{}", options.topic_prefix, name)
+}
+
+/// Message key of `row`: its primary key values as a JSON object (all values for tables without
+/// one), so updates to a row land on one partition and keep their order
+pub fn kafka_key(tables: &BTreeMap<String, TableSchema>, row: &Row) -> String {
+    let key = match tables.get(&row.table) {
+        Some(schema) if schema.values().any(|spec| spec.is_primary_key) => row
+            .values
+            .iter()
+            .filter(|(column, _)| schema.get(*column).is_some_and(|spec| spec.is_primary_key))
+            .collect::<BTreeMap<_, _>>(),
+        _ => row.values.iter().collect(),
+    };
+    serde_json::to_string(&key).expect("row keys serialize to JSON")
+}
+
+/// JSON payload of `row`: `{"table", "event", "version", "values"}`
+pub fn kafka_payload(row: &Row, version: u64) -> String {
+    serde_json::to_string(&KafkaRow {
+        table: &row.table,
+        event: &row.event,
+        version,
+        values: &row.values,
+    })
+    .expect("rows serialize to JSON")
+}
+
+/// Idempotent producer publishing the rows of each batch before its checkpoint is committed, so
+/// every row reaches Kafka at least once
+pub struct KafkaSink {
+    producer: FutureProducer,
+    options: KafkaOptions,
+    tables: BTreeMap<String, TableSchema>,
+}
+
+impl KafkaSink {
+    pub fn connect(options: &KafkaOptions, tables: &BTreeMap<String, TableSchema>) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &options.brokers)
+            .set("enable.idempotence", "true")
+            .set("compression.type", "zstd")
+            .create()
+            .with_context(|| format!("failed to create Kafka producer for {}", options.brokers))?;
+        Ok(KafkaSink {
+            producer,
+            options: options.clone(),
+            tables: tables.clone(),
+        })
+    }
+
+    pub fn only(&self) -> bool {
+        self.options.only
+    }
+
+    /// Publish the rows of a batch and wait until Kafka acknowledged all of them
+    pub async fn send(&self, rows: &[Row], version: u64) -> Result<()> {
+        let messages = rows
+            .iter()
+            .map(|row| {
+                (
+                    kafka_topic(&self.options, row),
+                    kafka_key(&self.tables, row),
+                    kafka_payload(row, version),
+                )
+            })
+            .collect::<Vec<_>>();
+        let deliveries = messages.iter().map(|(topic, key, payload)| async move {
+            self.producer
+                .send(
+                    FutureRecord::to(topic).key(key).payload(payload),
+                    KAFKA_QUEUE_TIMEOUT,
+                )
+                .await
+                .map_err(|(e, _)| anyhow!("failed to publish to Kafka topic {}: {}", topic, e))
+        });
+        futures::future::try_join_all(deliveries).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use crate::mapping_engine::Row;
+use anyhow::{Context, Result};
+use futures::{SinkExt, Stream, StreamExt};
+use poem::listener::TcpListener;
+use poem::web::sse::{Event, SSE};
+use poem::web::websocket::{Message, WebSocket};
+use poem::web::{Data, Query};
+use poem::{EndpointExt, IntoResponse, Route, Server, get, handler};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Batches a subscriber can fall behind by before it skips ahead
+const LIVE_BUFFER: usize = 1024;
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Rows of a committed batch, as pushed to live subscribers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveBatch {
+    /// Last version of the batch, committed together with the rows
+    pub version: u64,
+    /// Microseconds since the Unix epoch
+    pub timestamp: u64,
+    pub rows: Vec<Row>,
+}
+
+impl LiveBatch {
+    /// The batch restricted to `tables` (all of them when empty); None when no row is left
+    pub fn for_tables(&self, tables: &BTreeSet<String>) -> Option<LiveBatch> {
+        let rows = self
+            .rows
+            .iter()
+            .filter(|row| tables.is_empty() || tables.contains(&row.table))
+            .cloned()
+            .collect::<Vec<_>>();
+        (!rows.is_empty()).then(|| LiveBatch {
+            version: self.version,
+            timestamp: self.timestamp,
+            rows,
+        })
+    }
+}
+
+/// Fan-out of committed rows to the WebSocket and SSE subscribers of `serve_live`.
+///
+/// Rows are published once their batch is committed, so subscribers never see rows a restart
+/// would write again. A subscriber that cannot keep up skips the oldest batches and is told how
+/// many it missed; it can catch up from Postgres.
+#[derive(Debug)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<Arc<LiveBatch>>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        LiveFeed {
+            sender: broadcast::channel(LIVE_BUFFER).0,
+        }
+    }
+}
+
+/// What a subscriber receives
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiveMessage {
+    Batch(LiveBatch),
+    /// Batches dropped because the subscriber fell behind
+    Lagged(u64),
+}
+
+impl LiveMessage {
+    pub fn to_json(&self) -> String {
+        match self {
+            LiveMessage::Batch(batch) => serde_json::to_string(batch),
+            LiveMessage::Lagged(skipped) => {
+                serde_json::to_string(&serde_json::json!({ "lagged": skipped }))
+            }
+        }
+        .expect("live messages serialize to JSON")
+    }
+}
+
+impl LiveFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Push a committed batch to the current subscribers
+    pub fn publish(&self, version: u64, timestamp: u64, rows: &[Row]) {
+        if rows.is_empty() || self.sender.receiver_count() == 0 {
+            return;
+        }
+        // fails only when every subscriber left in the meantime
+        let _ = self.sender.send(Arc::new(LiveBatch {
+            version,
+            timestamp,
+            rows: rows.to_vec(),
+        }));
+    }
+
+    /// Batches published from now on touching `tables` (all tables when empty)
+    pub fn subscribe(&self, tables: BTreeSet<String>) -> impl Stream<Item = LiveMessage> + use<> {
+        futures::stream::unfold(
+            (self.sender.subscribe(), tables),
+            |(mut receiver, tables)| async move {
+                loop {
+                    let message = match receiver.recv().await {
+                        Ok(batch) => match batch.for_tables(&tables) {
+                            Some(batch) => LiveMessage::Batch(batch),
+                            None => continue,
+                        },
+                        Err(RecvError::Lagged(skipped)) => LiveMessage::Lagged(skipped),
+                        Err(RecvError::Closed) => return None,
+                    };
+                    return Some((message, (receiver, tables)));
+                }
+            },
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LiveQuery {
+    /// Comma separated tables to receive; all tables when unset
+    table: Option<String>,
+}
+
+impl LiveQuery {
+    fn tables(&self) -> BTreeSet<String> {
+        self.table
+            .iter()
+            .flat_map(|tables| tables.split(','))
+            .map(str::trim)
+            .filter(|table| !table.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[handler]
+fn events(Data(feed): Data<&Arc<LiveFeed>>, Query(query): Query<LiveQuery>) -> SSE {
+    let messages = feed.subscribe(query.tables()).map(|message| {
+        let event = Event::message(message.to_json());
+        match message {
+            LiveMessage::Batch(_) => event.event_type("rows"),
+            LiveMessage::Lagged(_) => event.event_type("lagged"),
+        }
+    });
+    SSE::new(messages).keep_alive(SSE_KEEP_ALIVE)
+}
+
+#[handler]
+fn websocket(
+    ws: WebSocket,
+    Data(feed): Data<&Arc<LiveFeed>>,
+    Query(query): Query<LiveQuery>,
+) -> impl IntoResponse {
+    let messages = feed.subscribe(query.tables());
+    ws.on_upgrade(move |socket| async move {
+        let (mut sink, mut incoming) = socket.split();
+        let mut messages = std::pin::pin!(messages);
+        loop {
+            tokio::select! {
+                message = messages.next() => {
+                    let Some(message) = message else { break };
+                    if sink.send(Message::Text(message.to_json())).await.is_err() {
+                        break;
+                    }
+                }
+                received = incoming.next() => match received {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    })
+}
+
+/// Serve committed rows live until the process exits: `/events` as Server-Sent Events (event
+/// type `rows` or `lagged`) and `/ws` as WebSocket text messages, both JSON and optionally
+/// restricted with `?table=a,b`
+pub async fn serve_live(address: SocketAddr, feed: Arc<LiveFeed>) -> Result<()> {
+    let app = Route::new()
+        .at("/events", get(events))
+        .at("/ws", get(websocket))
+        .data(feed);
+    Server::new(TcpListener::bind(address))
+        .run(app)
+        .await
+        .with_context(|| format!("live endpoint on {} failed", address))
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use poem::listener::TcpListener;
+use poem::web::{Data, Json};
+use poem::{EndpointExt, Route, Server, get, handler};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Failed transaction stream attempts; the stream reconnects
+pub const STREAM_ERRORS: &str = "stream";
+/// Failed chain head lookups on the fullnode REST API
+pub const CHAIN_HEAD_ERRORS: &str = "chain_head";
+/// Events that could not be mapped to a row; fatal
+pub const MAPPING_ERRORS: &str = "mapping";
+/// Failed database writes; fatal
+pub const WRITE_ERRORS: &str = "write";
+/// Rows Kafka did not acknowledge; fatal
+pub const KAFKA_ERRORS: &str = "kafka";
+
+/// Progress of a running processor, shared between the runtime and the metrics endpoint
+#[derive(Debug, Default)]
+pub struct ProcessorMetrics {
+    status: Mutex<ProcessorStatus>,
+}
+
+/// Point-in-time view of the processor served by `/health`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessorStatus {
+    pub processor: String,
+    pub last_processed_version: Option<u64>,
+    /// Microseconds since the Unix epoch
+    pub last_transaction_timestamp: Option<u64>,
+    pub chain_head_version: Option<u64>,
+    /// Versions between the chain head and the last processed version
+    pub lag: Option<u64>,
+    pub rows_written: BTreeMap<String, u64>,
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl ProcessorMetrics {
+    pub fn new(processor: &str) -> Self {
+        ProcessorMetrics {
+            status: Mutex::new(ProcessorStatus {
+                processor: processor.to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn record_batch(
+        &self,
+        last_version: u64,
+        last_timestamp: u64,
+        rows: &BTreeMap<String, u64>,
+    ) {
+        let mut status = self.lock();
+        status.last_processed_version = Some(last_version);
+        status.last_transaction_timestamp = Some(last_timestamp);
+        for (table, count) in rows {
+            *status.rows_written.entry(table.clone()).or_default() += count;
+        }
+    }
+
+    pub fn record_chain_head(&self, version: u64) {
+        self.lock().chain_head_version = Some(version);
+    }
+
+    pub fn record_error(&self, kind: &str) {
+        *self.lock().errors.entry(kind.to_string()).or_default() += 1;
+    }
+
+    pub fn status(&self) -> ProcessorStatus {
+        let mut status = self.lock().clone();
+        status.lag = match (status.chain_head_version, status.last_processed_version) {
+            (Some(head), Some(last)) => Some(head.saturating_sub(last)),
+            _ => None,
+        };
+        status
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProcessorStatus> {
+        // counters stay meaningful even if a holder panicked
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Prometheus text exposition of a status; every series is labelled with the processor name
+pub fn prometheus_text(status: &ProcessorStatus) -> String {
+    let processor = escape_label(&status.processor);
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, value: Option<u64>| {
+        if let Some(value) = value {
+            out.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} gauge\n{}{{processor=\"{}\"}} {}\n",
+                name, help, name, name, processor, value
+            ));
+        }
+    };
+    gauge(
+        "yeaptor_processor_last_processed_version",
+        "Last transaction version committed to the database",
+        status.last_processed_version,
+    );
+    gauge(
+        "yeaptor_processor_last_transaction_timestamp_seconds",
+        "Timestamp of the last committed transaction",
+        status.last_transaction_timestamp.map(|t| t / 1_000_000),
+    );
+    gauge(
+        "yeaptor_processor_chain_head_version",
+        "Latest ledger version reported by the fullnode",
+        status.chain_head_version,
+    );
+    gauge(
+        "yeaptor_processor_lag_versions",
+        "Versions between the chain head and the last processed version",
+        status.lag,
+    );
+    let mut counter = |name: &str, help: &str, label: &str, values: &BTreeMap<String, u64>| {
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} counter\n",
+            name, help, name
+        ));
+        for (key, value) in values {
+            out.push_str(&format!(
+                "{}{{processor=\"{}\",{}=\"{}\"}} {}\n",
+                name,
+                processor,
+                label,
+                escape_label(key),
+                value
+            ));
+        }
+    };
+    counter(
+        "yeaptor_processor_rows_written_total",
+        "Rows written per table",
+        "table",
+        &status.rows_written,
+    );
+    counter(
+        "yeaptor_processor_errors_total",
+        "Errors per kind (stream, chain_head, mapping, write, kafka)",
+        "kind",
+        &status.errors,
+    );
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[handler]
+fn health(Data(metrics): Data<&Arc<ProcessorMetrics>>) -> Json<ProcessorStatus> {
+    Json(metrics.status())
+}
+
+#[handler]
+fn prometheus_metrics(Data(metrics): Data<&Arc<ProcessorMetrics>>) -> String {
+    prometheus_text(&metrics.status())
+}
+
+/// Serve `/health` (JSON status) and `/metrics` (Prometheus) until the process exits
+pub async fn serve_metrics(
+    address: SocketAddr,
+    processor_metrics: Arc<ProcessorMetrics>,
+) -> Result<()> {
+    let app = Route::new()
+        .at("/health", get(health))
+        .at("/metrics", get(prometheus_metrics))
+        .data(processor_metrics);
+    Server::new(TcpListener::bind(address))
+        .run(app)
+        .await
+        .with_context(|| format!("metrics endpoint on {} failed", address))
+}
@@ -0,0 +1,473 @@
+use crate::mapping_engine::{MappingEngine, Row, TransactionRecord};
+use crate::processor_config::{ConflictStrategy, ProcessorConfig, TableSchema};
+#[cfg(feature = "kafka")]
+use crate::processor_kafka::KafkaSink;
+use crate::processor_metrics::{KAFKA_ERRORS, MAPPING_ERRORS, ProcessorMetrics, WRITE_ERRORS};
+use crate::processor_runtime::{
+    AccountFilter, PostgresWriter, RunOptions, RunSummary, TransactionStream, conflict_strategies,
+    filtered_transaction_record,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use aptos_protos::transaction::v1::Transaction;
+use futures::StreamExt;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Batches handed to a write worker ahead of the one it is writing
+const WRITE_QUEUE: usize = 2;
+
+/// Built without the `kafka` feature, no run publishes to Kafka
+#[cfg(not(feature = "kafka"))]
+enum KafkaSink {}
+
+#[cfg(not(feature = "kafka"))]
+impl KafkaSink {
+    fn only(&self) -> bool {
+        match *self {}
+    }
+
+    async fn send(&self, _rows: &[Row], _version: u64) -> Result<()> {
+        match *self {}
+    }
+}
+
+/// Concurrency of the stages of `processor run`: fetch -> decode -> map -> write.
+///
+/// Fetching runs ahead of decoding by `fetch_buffer` stream responses. Decoding (protobuf to JSON
+/// events) and mapping (events to rows) work on that many batches at once, on blocking threads,
+/// and hand them on in stream order. Writing uses `write_workers` connections; rows are assigned
+/// to a connection by primary key, so writes to the same row keep their order, and the checkpoint
+/// only advances over batches every connection has written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineOptions {
+    pub fetch_buffer: usize,
+    pub decode_workers: usize,
+    pub map_workers: usize,
+    pub write_workers: usize,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        PipelineOptions {
+            fetch_buffer: 8,
+            decode_workers: 2,
+            map_workers: 4,
+            write_workers: 1,
+        }
+    }
+}
+
+/// Rows of a stream response, with the last version they cover
+struct MappedBatch {
+    first_version: u64,
+    last_version: u64,
+    /// Microseconds since the Unix epoch
+    last_timestamp: u64,
+    rows: Vec<Row>,
+}
+
+impl MappedBatch {
+    fn rows_per_table(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        for row in &self.rows {
+            *counts.entry(row.table.clone()).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// With several write connections, a batch's rows can be committed before the checkpoint of the
+/// previous batch, so a restart writes some rows twice: every table must tolerate duplicates.
+pub fn check_parallel_writes(config: &ProcessorConfig, write_workers: usize) -> Result<()> {
+    if write_workers <= 1 {
+        return Ok(());
+    }
+    let strategies = conflict_strategies(config)?;
+    let plain = config
+        .custom_config
+        .db_schema
+        .keys()
+        .filter(|table| {
+            strategies
+                .get(*table)
+                .copied()
+                .unwrap_or(ConflictStrategy::Insert)
+                == ConflictStrategy::Insert
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if !plain.is_empty() {
+        bail!(
+            "{} write workers need on_conflict upsert or ignore-duplicates, but {} use insert",
+            write_workers,
+            plain.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Split rows between `partitions` write workers, keeping rows with the same table and primary
+/// key (all values for tables without one) together and in order
+pub fn partition_rows(
+    tables: &BTreeMap<String, TableSchema>,
+    rows: Vec<Row>,
+    partitions: usize,
+) -> Vec<Vec<Row>> {
+    let partitions = partitions.max(1);
+    let mut shares = vec![Vec::new(); partitions];
+    for row in rows {
+        let mut hasher = DefaultHasher::new();
+        row.table.hash(&mut hasher);
+        match tables.get(&row.table) {
+            Some(schema) if schema.values().any(|spec| spec.is_primary_key) => {
+                for (column, spec) in schema {
+                    if spec.is_primary_key {
+                        row.values.get(column).hash(&mut hasher);
+                    }
+                }
+            }
+            _ => row.values.hash(&mut hasher),
+        }
+        shares[(hasher.finish() % partitions as u64) as usize].push(row);
+    }
+    shares
+}
+
+pub(crate) async fn run_pipeline(
+    engine: Arc<MappingEngine>,
+    config: &ProcessorConfig,
+    writer: PostgresWriter,
+    options: &RunOptions,
+    starting_version: u64,
+    ending_version: Option<u64>,
+) -> Result<RunSummary> {
+    let pipeline = &options.pipeline;
+    let metrics = options.metrics.clone();
+    #[cfg(feature = "kafka")]
+    let kafka = options
+        .kafka
+        .as_ref()
+        .map(|kafka| KafkaSink::connect(kafka, &config.custom_config.db_schema))
+        .transpose()?;
+    #[cfg(not(feature = "kafka"))]
+    let kafka: Option<KafkaSink> = None;
+
+    let mut stream = TransactionStream::connect(
+        &options.grpc_address,
+        &options.auth_token,
+        metrics.clone(),
+        starting_version,
+        ending_version,
+    )
+    .await?;
+    let (fetched_tx, fetched_rx) = mpsc::channel(pipeline.fetch_buffer.max(1));
+    let fetcher: JoinHandle<Result<()>> = tokio::spawn(async move {
+        while let Some(batch) = stream.next_batch().await? {
+            if fetched_tx.send(batch).await.is_err() {
+                // the write stage stopped
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let decode_metrics = metrics.clone();
+    let map_metrics = metrics.clone();
+    let account_filter = AccountFilter::from_config(config)?.map(Arc::new);
+    let batches = futures::stream::unfold(fetched_rx, |mut rx| async move {
+        rx.recv().await.map(|batch| (batch, rx))
+    })
+    .map(move |batch: Vec<Transaction>| {
+        let metrics = decode_metrics.clone();
+        let account_filter = account_filter.clone();
+        async move {
+            tokio::task::spawn_blocking(move || {
+                batch
+                    .iter()
+                    .map(|txn| filtered_transaction_record(txn, account_filter.as_deref()))
+                    .collect::<Result<Vec<_>>>()
+                    .inspect_err(|_| metrics.record_error(MAPPING_ERRORS))
+            })
+            .await
+            .context("decode worker panicked")?
+        }
+    })
+    .buffered(pipeline.decode_workers.max(1))
+    .map(move |decoded: Result<Vec<TransactionRecord>>| {
+        let engine = engine.clone();
+        let metrics = map_metrics.clone();
+        async move {
+            let transactions = decoded?;
+            tokio::task::spawn_blocking(move || map_batch(&engine, &transactions, &metrics))
+                .await
+                .context("map worker panicked")?
+        }
+    })
+    .buffered(pipeline.map_workers.max(1));
+
+    // rows going to Kafka only leave just the checkpoint to Postgres
+    let written = if pipeline.write_workers <= 1 || kafka.as_ref().is_some_and(KafkaSink::only) {
+        write_serially(batches, writer, options, kafka.as_ref()).await
+    } else {
+        write_in_parallel(batches, writer, config, options, kafka.as_ref()).await
+    };
+    if written.is_err() {
+        fetcher.abort();
+    }
+    let summary = written?;
+    fetcher.await.context("transaction fetcher panicked")??;
+    Ok(summary)
+}
+
+fn map_batch(
+    engine: &MappingEngine,
+    transactions: &[TransactionRecord],
+    metrics: &ProcessorMetrics,
+) -> Result<Option<MappedBatch>> {
+    let (Some(first), Some(last)) = (transactions.first(), transactions.last()) else {
+        return Ok(None);
+    };
+    let rows = engine
+        .map_transactions(transactions)
+        .inspect_err(|_| metrics.record_error(MAPPING_ERRORS))?;
+    Ok(Some(MappedBatch {
+        first_version: first.version,
+        last_version: last.version,
+        last_timestamp: last.timestamp,
+        rows,
+    }))
+}
+
+/// Account for a committed batch and push its rows to live subscribers
+fn record_batch(
+    summary: &mut RunSummary,
+    options: &RunOptions,
+    batch: &MappedBatch,
+    written: BTreeMap<String, u64>,
+) {
+    options
+        .metrics
+        .record_batch(batch.last_version, batch.last_timestamp, &written);
+    if let Some(live) = &options.live {
+        live.publish(batch.last_version, batch.last_timestamp, &batch.rows);
+    }
+    debug!(
+        first_version = batch.first_version,
+        last_version = batch.last_version,
+        rows = written.values().sum::<u64>(),
+        "committed batch"
+    );
+    summary.first_version.get_or_insert(batch.first_version);
+    summary.last_version = Some(batch.last_version);
+    for (table, count) in written {
+        *summary.rows_written.entry(table).or_default() += count;
+    }
+}
+
+/// Rows reach Kafka before the batch is checkpointed: a restart may publish them again, but never
+/// skips them
+async fn publish(kafka: &KafkaSink, batch: &MappedBatch, metrics: &ProcessorMetrics) -> Result<()> {
+    kafka
+        .send(&batch.rows, batch.last_version)
+        .await
+        .inspect_err(|_| metrics.record_error(KAFKA_ERRORS))
+}
+
+/// One connection: every batch is committed together with its checkpoint
+async fn write_serially(
+    batches: impl futures::Stream<Item = Result<Option<MappedBatch>>>,
+    mut writer: PostgresWriter,
+    options: &RunOptions,
+    kafka: Option<&KafkaSink>,
+) -> Result<RunSummary> {
+    let metrics = options.metrics.as_ref();
+    let mut summary = RunSummary::default();
+    let mut batches = std::pin::pin!(batches);
+    while let Some(batch) = batches.next().await {
+        let Some(batch) = batch? else {
+            continue;
+        };
+        let mut rows = batch.rows.as_slice();
+        if let Some(kafka) = kafka {
+            publish(kafka, &batch, metrics).await?;
+            if kafka.only() {
+                rows = &[];
+            }
+        }
+        writer
+            .write_batch(rows, batch.last_version, batch.last_timestamp)
+            .await
+            .inspect_err(|_| metrics.record_error(WRITE_ERRORS))?;
+        let written = batch.rows_per_table();
+        record_batch(&mut summary, options, &batch, written);
+    }
+    Ok(summary)
+}
+
+struct WriteWorker {
+    shares: mpsc::Sender<Vec<Row>>,
+    /// One message per share written, in order
+    acks: mpsc::UnboundedReceiver<()>,
+    acked: usize,
+    handle: JoinHandle<Result<()>>,
+}
+
+impl WriteWorker {
+    async fn spawn(options: &RunOptions, config: &ProcessorConfig) -> Result<Self> {
+        let mut writer = PostgresWriter::connect(&options.postgres_url, config).await?;
+        let metrics = options.metrics.clone();
+        let (shares, mut shares_rx) = mpsc::channel::<Vec<Row>>(WRITE_QUEUE);
+        let (acks_tx, acks) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(async move {
+            while let Some(rows) = shares_rx.recv().await {
+                writer
+                    .write_rows(&rows)
+                    .await
+                    .inspect_err(|_| metrics.record_error(WRITE_ERRORS))?;
+                if acks_tx.send(()).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        Ok(WriteWorker {
+            shares,
+            acks,
+            acked: 0,
+            handle,
+        })
+    }
+}
+
+/// Batches handed to the write workers and not yet checkpointed, oldest first
+struct PendingBatches {
+    batches: VecDeque<(MappedBatch, BTreeMap<String, u64>)>,
+    completed: usize,
+}
+
+impl PendingBatches {
+    /// Checkpoint the batches every worker has written, waiting for the slowest worker while
+    /// more than `max_pending` remain
+    async fn checkpoint(
+        &mut self,
+        workers: &mut [WriteWorker],
+        writer: &mut PostgresWriter,
+        summary: &mut RunSummary,
+        options: &RunOptions,
+        max_pending: usize,
+    ) -> Result<()> {
+        let metrics = options.metrics.as_ref();
+        loop {
+            for worker in workers.iter_mut() {
+                while worker.acks.try_recv().is_ok() {
+                    worker.acked += 1;
+                }
+            }
+            let written = workers.iter().map(|w| w.acked).min().unwrap_or_default();
+            while self.completed < written {
+                let (batch, counts) = self.batches.pop_front().ok_or_else(|| {
+                    anyhow!("acknowledged batch {} was not pending", self.completed)
+                })?;
+                writer
+                    .write_checkpoint(batch.last_version, batch.last_timestamp)
+                    .await
+                    .inspect_err(|_| metrics.record_error(WRITE_ERRORS))?;
+                record_batch(summary, options, &batch, counts);
+                self.completed += 1;
+            }
+            if self.batches.len() <= max_pending {
+                return Ok(());
+            }
+            let Some(slowest) = workers.iter_mut().min_by_key(|w| w.acked) else {
+                bail!("no write workers");
+            };
+            match slowest.acks.recv().await {
+                Some(()) => slowest.acked += 1,
+                None => bail!("write worker stopped"),
+            }
+        }
+    }
+}
+
+/// Several connections write the rows of a batch concurrently, and of the next batches while
+/// earlier ones wait for their checkpoint; `writer` records the checkpoint of every batch all of
+/// them have written.
+async fn write_in_parallel(
+    batches: impl futures::Stream<Item = Result<Option<MappedBatch>>>,
+    mut writer: PostgresWriter,
+    config: &ProcessorConfig,
+    options: &RunOptions,
+    kafka: Option<&KafkaSink>,
+) -> Result<RunSummary> {
+    let tables = &config.custom_config.db_schema;
+    let write_workers = options.pipeline.write_workers;
+    let mut workers = Vec::new();
+    for _ in 0..write_workers {
+        workers.push(WriteWorker::spawn(options, config).await?);
+    }
+    let mut summary = RunSummary::default();
+    let mut pending = PendingBatches {
+        batches: VecDeque::new(),
+        completed: 0,
+    };
+    let result: Result<()> = async {
+        let mut batches = std::pin::pin!(batches);
+        while let Some(batch) = batches.next().await {
+            let Some(mut batch) = batch? else {
+                continue;
+            };
+            if let Some(kafka) = kafka {
+                publish(kafka, &batch, options.metrics.as_ref()).await?;
+            }
+            let counts = batch.rows_per_table();
+            // live subscribers get the rows once the batch is checkpointed
+            let rows = match options.live {
+                Some(_) => batch.rows.clone(),
+                None => std::mem::take(&mut batch.rows),
+            };
+            let shares = partition_rows(tables, rows, write_workers);
+            for (worker, rows) in workers.iter().zip(shares) {
+                worker
+                    .shares
+                    .send(rows)
+                    .await
+                    .map_err(|_| anyhow!("write worker stopped"))?;
+            }
+            pending.batches.push_back((batch, counts));
+            pending
+                .checkpoint(
+                    &mut workers,
+                    &mut writer,
+                    &mut summary,
+                    options,
+                    write_workers * WRITE_QUEUE,
+                )
+                .await?;
+        }
+        pending
+            .checkpoint(&mut workers, &mut writer, &mut summary, options, 0)
+            .await
+    }
+    .await;
+
+    // a worker's own error explains why the pipeline saw it stop
+    let mut worker_error = None;
+    for WriteWorker { shares, handle, .. } in workers {
+        drop(shares);
+        if let Err(e) = handle
+            .await
+            .context("write worker panicked")
+            .and_then(|r| r)
+        {
+            worker_error.get_or_insert(e);
+        }
+    }
+    match worker_error {
+        Some(e) => Err(e),
+        None => result.map(|()| summary),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::mapping_engine::{MappingEngine, MissingField, Row, TransactionRecord};
+use crate::processor_metrics::ProcessorMetrics;
+use crate::processor_runtime::{TransactionStream, transaction_record};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// What a processor config does with one transaction: the rows it writes, the mapped fields the
+/// transaction has no value at, and the events no mapping picks up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionPreview {
+    pub version: u64,
+    pub rows: Vec<Row>,
+    pub missing_fields: Vec<MissingField>,
+    /// Types of the events of the transaction that are not mapped, in event order
+    pub unmapped_events: Vec<String>,
+}
+
+/// Stream the transaction at `version` and decode it the way `processor run` does
+pub async fn fetch_transaction(
+    grpc_address: &str,
+    auth_token: &str,
+    version: u64,
+) -> Result<TransactionRecord> {
+    let metrics = Arc::new(ProcessorMetrics::new("preview"));
+    let mut stream =
+        TransactionStream::connect(grpc_address, auth_token, metrics, version, Some(version))
+            .await?;
+    while let Some(batch) = stream.next_batch().await? {
+        if let Some(txn) = batch.iter().find(|txn| txn.version == version) {
+            return transaction_record(txn);
+        }
+    }
+    Err(anyhow!("transaction {} not found in the stream", version))
+}
+
+pub fn preview_transactions(
+    engine: &MappingEngine,
+    transactions: &[TransactionRecord],
+) -> Result<Vec<TransactionPreview>> {
+    transactions
+        .iter()
+        .map(|txn| {
+            Ok(TransactionPreview {
+                version: txn.version,
+                rows: engine.map_transaction(txn)?,
+                missing_fields: engine.missing_fields(txn)?,
+                unmapped_events: txn
+                    .events
+                    .iter()
+                    .filter(|event| !engine.is_mapped_event(&event.event_type))
+                    .map(|event| event.event_type.clone())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The rows of each transaction, one `column = value` line per column (`NULL` for NULL), then
+/// why mapped columns are NULL and which events are not mapped
+pub fn render_preview(previews: &[TransactionPreview]) -> String {
+    let mut lines = Vec::new();
+    for preview in previews {
+        lines.push(format!(
+            "version {}: {} row{}",
+            preview.version,
+            preview.rows.len(),
+            if preview.rows.len() == 1 { "" } else { "s" }
+        ));
+        for row in &preview.rows {
+            lines.push(format!("  {} (from {})", row.table, row.event));
+            let width = row.values.keys().map(String::len).max().unwrap_or(0);
+            for (column, value) in &row.values {
+                lines.push(format!(
+                    "    {:width$} = {}",
+                    column,
+                    value.as_deref().unwrap_or("NULL"),
+                ));
+            }
+        }
+        for field in &preview.missing_fields {
+            lines.push(format!(
+                "  {}.{}: {} has no value at {}",
+                field.table, field.column, field.source, field.path
+            ));
+        }
+        for event in &preview.unmapped_events {
+            lines.push(format!("  not mapped: {}", event));
+        }
+    }
+    lines.join("\n")
+}
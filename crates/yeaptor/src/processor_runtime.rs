@@ -0,0 +1,811 @@
+use crate::db_schema::SchemaSnapshot;
+use crate::ddl::{postgres_ddl, postgres_type, quote_identifier};
+use crate::mapping_engine::{
+    EntryFunctionCall, EventRecord, MappingEngine, Row, TableItemRecord, TransactionRecord,
+};
+use crate::processor_config::{ConflictStrategy, ProcessorConfig, TableSchema};
+use crate::processor_config_generator::{CONFLICT_STRATEGIES, INDEXES, PARTITIONING};
+#[cfg(feature = "kafka")]
+use crate::processor_kafka::KafkaOptions;
+use crate::processor_live::LiveFeed;
+use crate::processor_metrics::{CHAIN_HEAD_ERRORS, ProcessorMetrics, STREAM_ERRORS};
+use crate::processor_pipeline::{PipelineOptions, check_parallel_writes, run_pipeline};
+use anyhow::{Context, Result, anyhow, bail};
+use aptos_protos::indexer::v1::GetTransactionsRequest;
+use aptos_protos::indexer::v1::raw_data_client::RawDataClient;
+use aptos_protos::transaction::v1::Transaction;
+use aptos_protos::transaction::v1::move_type::Content;
+use aptos_protos::transaction::v1::transaction::TxnData;
+use aptos_protos::transaction::v1::transaction_payload::Payload;
+use aptos_protos::transaction::v1::write_set_change::Change;
+use aptos_protos::transaction::v1::{Event, MoveType, MoveTypes};
+use aptos_types::account_address::AccountAddress;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::codec::{CompressionEncoding, Streaming};
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Channel, ClientTlsConfig};
+use tracing::{debug, error, info, trace, warn};
+
+/// Table recording, per processor, the last version whose rows are committed
+pub const PROCESSOR_STATUS_TABLE: &str = "yeaptor_processor_status";
+
+const MAX_DECODING_MESSAGE_SIZE: usize = 256 * 1024 * 1024;
+/// Consecutive failed reconnections before giving up
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// How often the chain head is looked up for the lag metric
+const CHAIN_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Aptos Labs transaction stream endpoint of a network, as named in `common_config.network`
+pub fn default_grpc_address(network: &str) -> Option<&'static str> {
+    match network.to_ascii_lowercase().as_str() {
+        "mainnet" => Some("https://grpc.mainnet.aptoslabs.com:443"),
+        "testnet" => Some("https://grpc.testnet.aptoslabs.com:443"),
+        "devnet" => Some("https://grpc.devnet.aptoslabs.com:443"),
+        _ => None,
+    }
+}
+
+/// Aptos Labs fullnode REST endpoint of a network, used to look up the chain head
+pub fn default_node_url(network: &str) -> Option<&'static str> {
+    match network.to_ascii_lowercase().as_str() {
+        "mainnet" => Some("https://api.mainnet.aptoslabs.com"),
+        "testnet" => Some("https://api.testnet.aptoslabs.com"),
+        "devnet" => Some("https://api.devnet.aptoslabs.com"),
+        _ => None,
+    }
+}
+
+/// Where to read transactions from and write rows to
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub grpc_address: String,
+    pub auth_token: String,
+    pub postgres_url: String,
+    /// Run the config's `CREATE TABLE IF NOT EXISTS` statements before processing
+    pub create_tables: bool,
+    /// Fullnode REST endpoint polled for the chain head; no lag is reported when unset
+    pub node_url: Option<String>,
+    /// Progress and error counters, e.g. served by `processor_metrics::serve_metrics`
+    pub metrics: Arc<ProcessorMetrics>,
+    pub pipeline: PipelineOptions,
+    /// Committed rows are pushed here, e.g. served by `processor_live::serve_live`
+    pub live: Option<Arc<LiveFeed>>,
+    /// Also (or only) publish rows to Kafka
+    #[cfg(feature = "kafka")]
+    pub kafka: Option<KafkaOptions>,
+}
+
+/// Versions processed and rows written by a run that reached the config's `ending_version`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    pub first_version: Option<u64>,
+    pub last_version: Option<u64>,
+    pub rows_written: BTreeMap<String, u64>,
+}
+
+/// Stream transactions from the transaction stream service, apply the config's mappings and
+/// write the rows to Postgres, committing each batch together with its last version. The stages
+/// run concurrently as described in `processor_pipeline`.
+///
+/// Resumes after the last committed version unless `starting_version_override` is set, and runs
+/// until `ending_version` (forever when unset).
+pub async fn run_processor(config: &ProcessorConfig, options: &RunOptions) -> Result<RunSummary> {
+    check_parallel_writes(config, options.pipeline.write_workers)?;
+    let engine = Arc::new(MappingEngine::new(config)?);
+    let writer = PostgresWriter::connect(&options.postgres_url, config).await?;
+    if options.create_tables {
+        writer.create_tables(config).await?;
+    }
+    let common = &config.common_config;
+    let starting_version = match common.starting_version_override {
+        Some(version) => version,
+        None => match writer.last_processed_version().await? {
+            Some(version) => version + 1,
+            None => common.starting_version,
+        },
+    };
+    if common
+        .ending_version
+        .is_some_and(|end| end < starting_version)
+    {
+        info!(starting_version, "ending version already processed");
+        return Ok(RunSummary::default());
+    }
+    info!(
+        processor = %config.spec_identifier.spec_name,
+        starting_version,
+        ending_version = ?common.ending_version,
+        "starting processor"
+    );
+
+    let chain_head = match &options.node_url {
+        Some(url) => {
+            let url = url::Url::parse(url).with_context(|| format!("invalid node URL {}", url))?;
+            Some(tokio::spawn(poll_chain_head(
+                aptos_rest_client::Client::new(url),
+                options.metrics.clone(),
+            )))
+        }
+        None => None,
+    };
+    let result = run_pipeline(
+        engine,
+        config,
+        writer,
+        options,
+        starting_version,
+        common.ending_version,
+    )
+    .await;
+    if let Some(task) = chain_head {
+        task.abort();
+    }
+    result
+}
+
+async fn poll_chain_head(client: aptos_rest_client::Client, metrics: Arc<ProcessorMetrics>) {
+    loop {
+        match client.get_ledger_information().await {
+            Ok(response) => {
+                let version = response.into_inner().version;
+                trace!(version, "chain head");
+                metrics.record_chain_head(version);
+            }
+            Err(e) => {
+                metrics.record_error(CHAIN_HEAD_ERRORS);
+                warn!(error = %e, "failed to look up the chain head");
+            }
+        }
+        tokio::time::sleep(CHAIN_HEAD_POLL_INTERVAL).await;
+    }
+}
+
+/// Reconnecting client of the transaction stream (`aptos.indexer.v1.RawData/GetTransactions`)
+pub struct TransactionStream {
+    grpc_address: String,
+    auth_token: String,
+    metrics: Arc<ProcessorMetrics>,
+    next_version: u64,
+    ending_version: Option<u64>,
+    stream: Streaming<aptos_protos::indexer::v1::TransactionsResponse>,
+}
+
+impl TransactionStream {
+    pub async fn connect(
+        grpc_address: &str,
+        auth_token: &str,
+        metrics: Arc<ProcessorMetrics>,
+        starting_version: u64,
+        ending_version: Option<u64>,
+    ) -> Result<Self> {
+        let stream =
+            open_stream(grpc_address, auth_token, starting_version, ending_version).await?;
+        Ok(TransactionStream {
+            grpc_address: grpc_address.to_string(),
+            auth_token: auth_token.to_string(),
+            metrics,
+            next_version: starting_version,
+            ending_version,
+            stream,
+        })
+    }
+
+    /// Next batch of transactions, or None once `ending_version` has been streamed. Reconnects
+    /// from the next expected version when the stream breaks.
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<Transaction>>> {
+        let mut attempts = 0;
+        loop {
+            if self
+                .ending_version
+                .is_some_and(|end| self.next_version > end)
+            {
+                return Ok(None);
+            }
+            let error = match self.stream.message().await {
+                Ok(Some(response)) => {
+                    let transactions = response
+                        .transactions
+                        .into_iter()
+                        .filter(|txn| txn.version >= self.next_version)
+                        .filter(|txn| self.ending_version.is_none_or(|end| txn.version <= end))
+                        .collect::<Vec<_>>();
+                    if let Some(last) = transactions.last() {
+                        self.next_version = last.version + 1;
+                    }
+                    return Ok(Some(transactions));
+                }
+                Ok(None) if self.ending_version.is_some() => {
+                    anyhow!("stream ended before version {}", self.next_version)
+                }
+                Ok(None) => anyhow!("stream closed by the server"),
+                Err(status) => anyhow!("stream error: {}", status),
+            };
+            self.metrics.record_error(STREAM_ERRORS);
+            attempts += 1;
+            if attempts > MAX_RECONNECT_ATTEMPTS {
+                return Err(error.context(format!(
+                    "giving up after {} reconnection attempts",
+                    MAX_RECONNECT_ATTEMPTS
+                )));
+            }
+            warn!(
+                error = format!("{:#}", error),
+                next_version = self.next_version,
+                attempt = attempts,
+                "transaction stream broke; reconnecting"
+            );
+            tokio::time::sleep(Duration::from_secs(1 << attempts)).await;
+            let reopened = open_stream(
+                &self.grpc_address,
+                &self.auth_token,
+                self.next_version,
+                self.ending_version,
+            )
+            .await;
+            match reopened {
+                Ok(stream) => self.stream = stream,
+                Err(e) => warn!(
+                    error = format!("{:#}", e),
+                    "failed to reopen the transaction stream"
+                ),
+            }
+        }
+    }
+}
+
+async fn open_stream(
+    address: &str,
+    auth_token: &str,
+    starting_version: u64,
+    ending_version: Option<u64>,
+) -> Result<Streaming<aptos_protos::indexer::v1::TransactionsResponse>> {
+    debug!(
+        address,
+        starting_version,
+        ?ending_version,
+        "opening transaction stream"
+    );
+    let mut endpoint = Channel::from_shared(address.to_string())
+        .with_context(|| format!("invalid gRPC address {}", address))?
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .keep_alive_timeout(Duration::from_secs(10));
+    if address.starts_with("https://") {
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .context("failed to configure TLS")?;
+    }
+    let channel = endpoint
+        .connect()
+        .await
+        .with_context(|| format!("failed to connect to {}", address))?;
+    let mut client = RawDataClient::new(channel)
+        .accept_compressed(CompressionEncoding::Zstd)
+        .send_compressed(CompressionEncoding::Zstd)
+        .max_decoding_message_size(MAX_DECODING_MESSAGE_SIZE);
+    let mut request = tonic::Request::new(GetTransactionsRequest {
+        starting_version: Some(starting_version),
+        transactions_count: ending_version.map(|end| end + 1 - starting_version),
+        ..Default::default()
+    });
+    let authorization =
+        MetadataValue::try_from(format!("Bearer {}", auth_token)).context("invalid auth token")?;
+    request
+        .metadata_mut()
+        .insert("authorization", authorization);
+    let response = client
+        .get_transactions(request)
+        .await
+        .with_context(|| format!("failed to stream transactions from {}", address))?;
+    Ok(response.into_inner())
+}
+
+/// Source-independent view of a streamed transaction. Only transaction kinds that emit events
+/// carry any; failed transactions have none, and no entry function call or table item writes
+/// either.
+pub fn transaction_record(txn: &Transaction) -> Result<TransactionRecord> {
+    let events = transaction_events(txn)
+        .iter()
+        .map(|event| {
+            let key = event.key.clone().unwrap_or_default();
+            Ok(EventRecord {
+                account_address: key.account_address,
+                creation_number: key.creation_number,
+                sequence_number: event.sequence_number,
+                event_type: event.type_str.clone(),
+                data: serde_json::from_str(&event.data).with_context(|| {
+                    format!(
+                        "invalid data of event {} in version {}",
+                        event.type_str, txn.version
+                    )
+                })?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TransactionRecord {
+        events,
+        payload: entry_function_call(txn)?,
+        table_items: table_item_writes(txn)?,
+        ..transaction_header(txn)
+    })
+}
+
+/// Version and time of a transaction, without its events, payload or table item writes
+fn transaction_header(txn: &Transaction) -> TransactionRecord {
+    let timestamp = txn
+        .timestamp
+        .as_ref()
+        .map(|t| t.seconds as u64 * 1_000_000 + t.nanos as u64 / 1_000)
+        .unwrap_or_default();
+    TransactionRecord {
+        version: txn.version,
+        block_height: txn.block_height,
+        epoch: txn.epoch,
+        timestamp,
+        events: Vec::new(),
+        payload: None,
+        table_items: Vec::new(),
+    }
+}
+
+fn transaction_events(txn: &Transaction) -> &[Event] {
+    match &txn.txn_data {
+        Some(TxnData::User(user)) => user.events.as_slice(),
+        Some(TxnData::Genesis(genesis)) => genesis.events.as_slice(),
+        Some(TxnData::BlockMetadata(block)) => block.events.as_slice(),
+        _ => &[],
+    }
+}
+
+/// `common_config.account_filter` of a config, checked on streamed transactions before their
+/// events are decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountFilter {
+    accounts: BTreeSet<AccountAddress>,
+}
+
+impl AccountFilter {
+    /// None when the config indexes every transaction
+    pub fn from_config(config: &ProcessorConfig) -> Result<Option<Self>> {
+        let accounts = config
+            .common_config
+            .account_filter
+            .iter()
+            .map(|account| {
+                AccountAddress::from_str(account)
+                    .map_err(|e| anyhow!("invalid account {} in account_filter: {}", account, e))
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+        Ok((!accounts.is_empty()).then_some(AccountFilter { accounts }))
+    }
+
+    /// Whether one of the accounts sent the transaction, or one of its events is under one of
+    /// them: the event's account for handle events, the module address of its type for module
+    /// events (whose account is `0x0`)
+    pub fn touches(&self, txn: &Transaction) -> bool {
+        let matches = |address: &str| {
+            AccountAddress::from_str(address).is_ok_and(|address| self.accounts.contains(&address))
+        };
+        if let Some(TxnData::User(user)) = &txn.txn_data {
+            if user.request.as_ref().is_some_and(|r| matches(&r.sender)) {
+                return true;
+            }
+        }
+        transaction_events(txn).iter().any(|event| {
+            event
+                .key
+                .as_ref()
+                .is_some_and(|key| matches(&key.account_address))
+                || event
+                    .type_str
+                    .split_once("::")
+                    .is_some_and(|(address, _)| matches(address))
+        })
+    }
+}
+
+/// `transaction_record`, or only the version and time (for the checkpoint) of a transaction
+/// `filter` skips
+pub fn filtered_transaction_record(
+    txn: &Transaction,
+    filter: Option<&AccountFilter>,
+) -> Result<TransactionRecord> {
+    match filter {
+        Some(filter) if !filter.touches(txn) => Ok(transaction_header(txn)),
+        _ => transaction_record(txn),
+    }
+}
+
+/// Entry function payload of a successful user transaction
+fn entry_function_call(txn: &Transaction) -> Result<Option<EntryFunctionCall>> {
+    let Some(TxnData::User(user)) = &txn.txn_data else {
+        return Ok(None);
+    };
+    if !txn.info.as_ref().is_some_and(|info| info.success) {
+        return Ok(None);
+    }
+    let Some(request) = &user.request else {
+        return Ok(None);
+    };
+    let Some(Payload::EntryFunctionPayload(call)) =
+        request.payload.as_ref().and_then(|p| p.payload.as_ref())
+    else {
+        return Ok(None);
+    };
+    let arguments = call
+        .arguments
+        .iter()
+        .map(|argument| {
+            serde_json::from_str(argument).with_context(|| {
+                format!(
+                    "invalid argument of {} in version {}",
+                    call.entry_function_id_str, txn.version
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(EntryFunctionCall {
+        sender: request.sender.clone(),
+        function: call.entry_function_id_str.clone(),
+        type_arguments: call.type_arguments.iter().map(move_type_string).collect(),
+        arguments,
+    }))
+}
+
+/// Decoded table item writes of a successful transaction, in write set order
+fn table_item_writes(txn: &Transaction) -> Result<Vec<TableItemRecord>> {
+    let Some(info) = txn.info.as_ref().filter(|info| info.success) else {
+        return Ok(Vec::new());
+    };
+    info.changes
+        .iter()
+        .filter_map(|change| match &change.change {
+            Some(Change::WriteTableItem(item)) => Some(item),
+            _ => None,
+        })
+        .filter_map(|item| Some((item, item.data.as_ref()?)))
+        .map(|(item, data)| {
+            let decode = |json: &str| {
+                serde_json::from_str(json).with_context(|| {
+                    format!(
+                        "invalid item of table {} in version {}",
+                        item.handle, txn.version
+                    )
+                })
+            };
+            Ok(TableItemRecord {
+                handle: item.handle.clone(),
+                key_type: data.key_type.clone(),
+                key: decode(&data.key)?,
+                value_type: data.value_type.clone(),
+                value: decode(&data.value)?,
+            })
+        })
+        .collect()
+}
+
+/// A Move type as written in source, e.g. `vector<0x1::object::Object<0x1::fungible_asset::Metadata>>`
+fn move_type_string(move_type: &MoveType) -> String {
+    match &move_type.content {
+        Some(Content::Vector(item)) => format!("vector<{}>", move_type_string(item)),
+        Some(Content::Struct(tag)) => {
+            let mut name = format!("{}::{}::{}", tag.address, tag.module, tag.name);
+            if !tag.generic_type_params.is_empty() {
+                let params = tag
+                    .generic_type_params
+                    .iter()
+                    .map(move_type_string)
+                    .collect::<Vec<_>>();
+                name.push_str(&format!("<{}>", params.join(", ")));
+            }
+            name
+        }
+        Some(Content::GenericTypeParamIndex(index)) => format!("T{}", index),
+        Some(Content::Reference(reference)) => format!(
+            "&{}{}",
+            if reference.mutable { "mut " } else { "" },
+            reference
+                .to
+                .as_deref()
+                .map(move_type_string)
+                .unwrap_or_default()
+        ),
+        Some(Content::Unparsable(text)) => text.clone(),
+        None => match move_type.r#type() {
+            MoveTypes::Unspecified => String::new(),
+            other => other
+                .as_str_name()
+                .trim_start_matches("MOVE_TYPES_")
+                .to_lowercase(),
+        },
+    }
+}
+
+/// Writes mapped rows and the processor's checkpoint to Postgres
+pub struct PostgresWriter {
+    client: tokio_postgres::Client,
+    processor: String,
+    statements: BTreeMap<String, String>,
+}
+
+impl PostgresWriter {
+    pub async fn connect(url: &str, config: &ProcessorConfig) -> Result<Self> {
+        let tls = native_tls::TlsConnector::new().context("failed to set up TLS")?;
+        let (client, connection) =
+            tokio_postgres::connect(url, postgres_native_tls::MakeTlsConnector::new(tls))
+                .await
+                .context("failed to connect to Postgres")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!(error = %e, "Postgres connection error");
+            }
+        });
+        client
+            .batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (\n    \"processor\" TEXT NOT NULL PRIMARY KEY,\n    \"last_success_version\" BIGINT NOT NULL,\n    \"last_transaction_timestamp\" TIMESTAMP,\n    \"last_updated\" TIMESTAMP NOT NULL DEFAULT NOW()\n);",
+                quote_identifier(PROCESSOR_STATUS_TABLE)
+            ))
+            .await
+            .context("failed to create the processor status table")?;
+        let strategies = conflict_strategies(config)?;
+        let mut statements = BTreeMap::new();
+        for (table, schema) in &config.custom_config.db_schema {
+            let strategy = strategies
+                .get(table)
+                .copied()
+                .unwrap_or(ConflictStrategy::Insert);
+            statements.insert(
+                table.clone(),
+                postgres_insert_statement(table, schema, strategy)?,
+            );
+        }
+        Ok(PostgresWriter {
+            client,
+            processor: config.spec_identifier.spec_name.clone(),
+            statements,
+        })
+    }
+
+    /// Create the config's tables, with the indexes and partitioning of its payload
+    pub async fn create_tables(&self, config: &ProcessorConfig) -> Result<()> {
+        let ddl = postgres_ddl(&schema_snapshot_from_config(config)?)?;
+        self.client
+            .batch_execute(&ddl)
+            .await
+            .context("failed to create tables")
+    }
+
+    pub async fn last_processed_version(&self) -> Result<Option<u64>> {
+        let row = self
+            .client
+            .query_opt(
+                &format!(
+                    "SELECT \"last_success_version\" FROM {} WHERE \"processor\" = $1",
+                    quote_identifier(PROCESSOR_STATUS_TABLE)
+                ),
+                &[&self.processor],
+            )
+            .await
+            .context("failed to read the processor status")?;
+        Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+    }
+
+    /// Insert `rows` without recording progress, in one database transaction
+    pub async fn write_rows(&mut self, rows: &[Row]) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        insert_rows(&transaction, &self.statements, rows).await?;
+        transaction
+            .commit()
+            .await
+            .context("failed to commit the inserted rows")
+    }
+
+    /// Insert `rows` and record `version` as processed, in one database transaction
+    pub async fn write_batch(&mut self, rows: &[Row], version: u64, timestamp: u64) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        insert_rows(&transaction, &self.statements, rows).await?;
+        record_status(&transaction, &self.processor, version, timestamp).await?;
+        transaction
+            .commit()
+            .await
+            .with_context(|| format!("failed to commit the batch ending at version {}", version))
+    }
+
+    /// Record `version` as processed, once the rows up to it have been written
+    pub async fn write_checkpoint(&mut self, version: u64, timestamp: u64) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        record_status(&transaction, &self.processor, version, timestamp).await?;
+        transaction
+            .commit()
+            .await
+            .with_context(|| format!("failed to commit the checkpoint at version {}", version))
+    }
+}
+
+async fn record_status(
+    transaction: &tokio_postgres::Transaction<'_>,
+    processor: &str,
+    version: u64,
+    timestamp: u64,
+) -> Result<()> {
+    let timestamp = chrono::DateTime::from_timestamp_micros(timestamp as i64)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S%.6f").to_string());
+    transaction
+        .execute(
+            &format!(
+                "INSERT INTO {} (\"processor\", \"last_success_version\", \"last_transaction_timestamp\", \"last_updated\") \
+                 VALUES ($1, $2, $3::TEXT::TIMESTAMP, NOW()) \
+                 ON CONFLICT (\"processor\") DO UPDATE SET \
+                 \"last_success_version\" = EXCLUDED.\"last_success_version\", \
+                 \"last_transaction_timestamp\" = EXCLUDED.\"last_transaction_timestamp\", \
+                 \"last_updated\" = EXCLUDED.\"last_updated\"",
+                quote_identifier(PROCESSOR_STATUS_TABLE)
+            ),
+            &[&processor, &(version as i64), &timestamp],
+        )
+        .await
+        .context("failed to record the processor status")?;
+    Ok(())
+}
+
+/// Insert rows with the tables' prepared insert statements
+async fn insert_rows(
+    transaction: &tokio_postgres::Transaction<'_>,
+    statements: &BTreeMap<String, String>,
+    rows: &[Row],
+) -> Result<()> {
+    let mut prepared = BTreeMap::new();
+    for row in rows {
+        if !prepared.contains_key(&row.table) {
+            let sql = statements
+                .get(&row.table)
+                .ok_or_else(|| anyhow!("unknown table {}", row.table))?;
+            let statement = transaction
+                .prepare(sql)
+                .await
+                .with_context(|| format!("failed to prepare insert into {}", row.table))?;
+            prepared.insert(row.table.clone(), statement);
+        }
+        let params = row
+            .values
+            .values()
+            .map(|value| value as &(dyn tokio_postgres::types::ToSql + Sync))
+            .collect::<Vec<_>>();
+        transaction
+            .execute(&prepared[&row.table], &params)
+            .await
+            .with_context(|| format!("failed to insert into {}", row.table))?;
+    }
+    Ok(())
+}
+
+/// `payload.conflict_strategies` of a config
+pub fn conflict_strategies(config: &ProcessorConfig) -> Result<BTreeMap<String, ConflictStrategy>> {
+    match config.custom_config.payload.get(CONFLICT_STRATEGIES) {
+        Some(value) => {
+            serde_yaml::from_value(value.clone()).context("invalid payload.conflict_strategies")
+        }
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// Tables of a config with the indexes and partitioning recorded in its payload
+pub fn schema_snapshot_from_config(config: &ProcessorConfig) -> Result<SchemaSnapshot> {
+    let payload = &config.custom_config.payload;
+    let mut snapshot = SchemaSnapshot {
+        tables: config.custom_config.db_schema.clone(),
+        options: BTreeMap::new(),
+    };
+    if let Some(value) = payload.get(INDEXES) {
+        let indexes: BTreeMap<String, Vec<_>> =
+            serde_yaml::from_value(value.clone()).context("invalid payload.indexes")?;
+        for (table, indexes) in indexes {
+            snapshot.options.entry(table).or_default().indexes = indexes;
+        }
+    }
+    if let Some(value) = payload.get(PARTITIONING) {
+        let partitioning: BTreeMap<String, _> =
+            serde_yaml::from_value(value.clone()).context("invalid payload.partitioning")?;
+        for (table, partition_by) in partitioning {
+            snapshot.options.entry(table).or_default().partition_by = Some(partition_by);
+        }
+    }
+    Ok(snapshot)
+}
+
+/// `INSERT` of one row with every column of the table, in column order. Values are bound as text
+/// and cast to the column type; vector columns are bound as a JSON array.
+pub fn postgres_insert_statement(
+    table: &str,
+    schema: &TableSchema,
+    strategy: ConflictStrategy,
+) -> Result<String> {
+    let mut columns = Vec::new();
+    let mut values = Vec::new();
+    for (index, (column, spec)) in schema.iter().enumerate() {
+        let sql_type =
+            postgres_type(spec).with_context(|| format!("column {}::{}", table, column))?;
+        let param = format!("${}::TEXT", index + 1);
+        values.push(if spec.is_vec {
+            format!(
+                "CASE WHEN {} IS NULL THEN NULL ELSE ARRAY(SELECT jsonb_array_elements_text({}::JSONB))::{}[] END",
+                param, param, sql_type
+            )
+        } else {
+            format!("{}::{}", param, sql_type)
+        });
+        columns.push(quote_identifier(column));
+    }
+    let primary_key = schema
+        .iter()
+        .filter(|(_, spec)| spec.is_primary_key)
+        .map(|(column, _)| quote_identifier(column))
+        .collect::<Vec<_>>();
+    let on_conflict = match strategy {
+        ConflictStrategy::Insert => String::new(),
+        ConflictStrategy::IgnoreDuplicates => " ON CONFLICT DO NOTHING".to_string(),
+        ConflictStrategy::Upsert if primary_key.is_empty() => {
+            bail!(
+                "table {} uses on_conflict upsert but has no primary key",
+                table
+            )
+        }
+        ConflictStrategy::Upsert => {
+            let updates = columns
+                .iter()
+                .filter(|column| !primary_key.contains(*column))
+                .map(|column| format!("{} = EXCLUDED.{}", column, column))
+                .collect::<Vec<_>>();
+            if updates.is_empty() {
+                " ON CONFLICT DO NOTHING".to_string()
+            } else {
+                format!(
+                    " ON CONFLICT ({}) DO UPDATE SET {}",
+                    primary_key.join(", "),
+                    updates.join(", ")
+                )
+            }
+        }
+    };
+    Ok(format!(
+        "INSERT INTO {} ({}) VALUES ({}){}",
+        quote_identifier(table),
+        columns.join(", "),
+        values.join(", "),
+        on_conflict
+    ))
+}
+
+/// One CSV per table (`table -> content`), header in column order and one record per row. NULLs
+/// are empty cells and vector columns hold their JSON array; tables without rows only get the
+/// header.
+pub fn rows_to_csv(
+    tables: &BTreeMap<String, TableSchema>,
+    rows: &[Row],
+) -> Result<BTreeMap<String, String>> {
+    let mut writers = BTreeMap::new();
+    for (table, schema) in tables {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record(schema.keys())?;
+        writers.insert(table.as_str(), wtr);
+    }
+    for row in rows {
+        let wtr = writers
+            .get_mut(row.table.as_str())
+            .ok_or_else(|| anyhow!("unknown table {}", row.table))?;
+        wtr.write_record(
+            row.values
+                .values()
+                .map(|v| v.as_deref().unwrap_or_default()),
+        )?;
+    }
+    writers
+        .into_iter()
+        .map(|(table, wtr)| {
+            let bytes = wtr.into_inner().map_err(|e| anyhow!(e.to_string()))?;
+            Ok((table.to_string(), String::from_utf8(bytes)?))
+        })
+        .collect()
+}
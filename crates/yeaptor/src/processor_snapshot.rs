@@ -0,0 +1,84 @@
+use crate::db_schema::SchemaSnapshot;
+use crate::ddl::postgres_ddl;
+use crate::processor_config::ProcessorConfig;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Golden file of the generated processor config
+pub const CONFIG_SNAPSHOT_FILE: &str = "processor_config.yaml";
+
+/// Golden file of the Postgres DDL of the DB schema
+pub const DDL_SNAPSHOT_FILE: &str = "schema.sql";
+
+/// Golden files of a generated processor config and its DDL: file name -> content
+pub fn snapshot_files(
+    config: &ProcessorConfig,
+    schema: &SchemaSnapshot,
+) -> Result<BTreeMap<String, String>> {
+    let config = serde_yaml::to_string(config).context("failed to serialize YAML config")?;
+    Ok([
+        (CONFIG_SNAPSHOT_FILE.to_string(), config),
+        (DDL_SNAPSHOT_FILE.to_string(), postgres_ddl(schema)?),
+    ]
+    .into_iter()
+    .collect())
+}
+
+/// Write the golden files to `dir`, creating it
+pub fn write_snapshot(dir: &Path, files: &BTreeMap<String, String>) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create snapshot dir: {}", dir.display()))?;
+    files
+        .iter()
+        .map(|(name, content)| {
+            let path = dir.join(name);
+            fs::write(&path, content)
+                .with_context(|| format!("failed to write snapshot: {}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// One message per golden file in `dir` that is missing or differs from the regenerated
+/// `files`, naming the first line that differs; empty when the snapshot is up to date
+pub fn check_snapshot(dir: &Path, files: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    let mut differences = Vec::new();
+    for (name, generated) in files {
+        let path = dir.join(name);
+        if !path.exists() {
+            differences.push(format!("{} is missing", path.display()));
+            continue;
+        }
+        let stored = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read snapshot: {}", path.display()))?;
+        if let Some((line, expected, actual)) = first_difference(&stored, generated) {
+            differences.push(format!(
+                "{} differs at line {}: snapshot has `{}`, regenerated `{}`",
+                path.display(),
+                line,
+                expected.unwrap_or("<end of file>"),
+                actual.unwrap_or("<end of file>")
+            ));
+        }
+    }
+    Ok(differences)
+}
+
+/// 1-based number and both versions of the first line that differs, None when nothing does
+fn first_difference<'a>(
+    stored: &'a str,
+    generated: &'a str,
+) -> Option<(usize, Option<&'a str>, Option<&'a str>)> {
+    let mut stored = stored.lines();
+    let mut generated = generated.lines();
+    for line in 1.. {
+        match (stored.next(), generated.next()) {
+            (None, None) => return None,
+            (a, b) if a == b => continue,
+            (a, b) => return Some((line, a, b)),
+        }
+    }
+    None
+}
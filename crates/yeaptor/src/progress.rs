@@ -0,0 +1,16 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+const TICK_INTERVAL: Duration = Duration::from_millis(120);
+
+/// Bar over `len` steps on stderr, hidden when stderr is not a terminal. Its spinner keeps
+/// moving during long steps, since one package compile can take minutes.
+pub fn progress_bar(len: u64, prefix: &'static str) -> ProgressBar {
+    let bar = ProgressBar::new(len).with_prefix(prefix);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} {prefix} [{pos}/{len}] {wide_msg} {elapsed}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+    );
+    bar.enable_steady_tick(TICK_INTERVAL);
+    bar
+}
@@ -0,0 +1,68 @@
+use crate::error::{YeaptorError, YeaptorResult};
+use anyhow::anyhow;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+/// How confirmations (overwriting an existing file) are answered, from the global
+/// `--assume-yes`/`--assume-no` flags. Without either, yeaptor asks on the terminal, and fails
+/// instead of waiting for an answer when stdin is not a terminal (CI, pipes).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::Args)]
+pub struct Confirmation {
+    /// Answer yes to every prompt, e.g. overwrite existing files; for CI pipelines
+    #[clap(
+        long,
+        global = true,
+        visible_alias = "non-interactive",
+        conflicts_with = "assume_no"
+    )]
+    pub assume_yes: bool,
+    /// Answer no to every prompt, e.g. fail rather than overwrite an existing file
+    #[clap(long, global = true)]
+    pub assume_no: bool,
+}
+
+impl Confirmation {
+    /// Asks `question` unless the answer is assumed; an answer other than yes is an error
+    pub fn confirm(self, question: &str) -> YeaptorResult<()> {
+        if self.assume_yes {
+            return Ok(());
+        }
+        if self.assume_no {
+            return Err(YeaptorError::invalid(anyhow!(
+                "{}: answered no (--assume-no)",
+                question
+            )));
+        }
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return Err(YeaptorError::invalid(anyhow!(
+                "{}: stdin is not a terminal to answer it; pass --assume-yes or --assume-no",
+                question
+            )));
+        }
+        eprint!("{} [yes/no] > ", question);
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        stdin
+            .lock()
+            .read_line(&mut answer)
+            .map_err(YeaptorError::invalid)?;
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "yes" | "y" => Ok(()),
+            _ => Err(YeaptorError::invalid(anyhow!("{}: answered no", question))),
+        }
+    }
+
+    /// Confirms overwriting `path` if it exists, then writes `contents` to it
+    pub fn write_file(self, path: &Path, contents: &[u8]) -> YeaptorResult<()> {
+        self.confirm_overwrite(path)?;
+        std::fs::write(path, contents).map_err(|e| YeaptorError::io(path, e))
+    }
+
+    pub fn confirm_overwrite(self, path: &Path) -> YeaptorResult<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        self.confirm(&format!("{} already exists, overwrite it?", path.display()))
+    }
+}
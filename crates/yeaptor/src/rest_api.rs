@@ -0,0 +1,279 @@
+use crate::db_schema::SchemaSnapshot;
+use crate::graphql::indexed_columns;
+use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+
+/// Marker of `MAIN_RS` replaced with the table list
+const TABLES_MARKER: &str = "/* TABLES */";
+
+const CARGO_TOML: &str = r#"[package]
+name = "__NAME__"
+version = "0.1.0"
+edition = "2024"
+publish = false
+
+[dependencies]
+anyhow = "1"
+axum = "0.8"
+serde_json = "1"
+sqlx = { version = "0.8", features = ["runtime-tokio", "postgres", "json"] }
+tokio = { version = "1", features = ["macros", "rt-multi-thread", "net"] }
+"#;
+
+const MAIN_RS: &str = r#"//! Read API over the tables indexed by yeaptor, scaffolded by `yeaptor processor rest-api`.
+//!
+//! - `GET /<table>?<column>=<value>&limit=<n>&offset=<n>` lists rows, filtered on indexed columns
+//!   and ordered by primary key: `{"items": [...], "limit", "offset"}`
+//! - `GET /<table>/<primary key values...>` returns one row, 404 when there is none
+//!
+//! Rows are returned as the JSON Postgres builds for them (`to_jsonb`). Set `DATABASE_URL` and
+//! optionally `LISTEN_ADDRESS` (default: `0.0.0.0:8080`).
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::collections::HashMap;
+
+/// Page size when no `limit` is given
+const DEFAULT_LIMIT: i64 = 100;
+/// Largest `limit` accepted
+const MAX_LIMIT: i64 = 1000;
+
+struct Table {
+    name: &'static str,
+    /// Primary key columns, in the order of the path segments of `GET /<table>/...`
+    primary_key: &'static [&'static str],
+    /// Indexed columns usable as `?<column>=<value>` filters
+    filters: &'static [&'static str],
+}
+
+const TABLES: &[Table] = &[
+/* TABLES */];
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let database_url = std::env::var("DATABASE_URL")?;
+    let address = std::env::var("LISTEN_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+    let pool = PgPoolOptions::new()
+        .max_connections(8)
+        .connect(&database_url)
+        .await?;
+
+    let mut router = Router::new();
+    for table in TABLES {
+        router = router.route(
+            &format!("/{}", table.name),
+            get(
+                move |State(pool): State<PgPool>, Query(params): Query<HashMap<String, String>>| {
+                    list(pool, table, params)
+                },
+            ),
+        );
+        if !table.primary_key.is_empty() {
+            let key = table
+                .primary_key
+                .iter()
+                .map(|column| format!("{{{}}}", column))
+                .collect::<Vec<_>>()
+                .join("/");
+            router = router.route(
+                &format!("/{}/{}", table.name, key),
+                get(
+                    move |State(pool): State<PgPool>, Path(key): Path<Vec<String>>| {
+                        get_row(pool, table, key)
+                    },
+                ),
+            );
+        }
+    }
+
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    axum::serve(listener, router.with_state(pool)).await?;
+    Ok(())
+}
+
+async fn list(
+    pool: PgPool,
+    table: &'static Table,
+    mut params: HashMap<String, String>,
+) -> Result<Json<Value>, ApiError> {
+    let limit = take_number(&mut params, "limit", DEFAULT_LIMIT)?.clamp(1, MAX_LIMIT);
+    let offset = take_number(&mut params, "offset", 0)?.max(0);
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+    for (column, value) in params {
+        if !table.filters.contains(&column.as_str()) {
+            return Err(ApiError::bad_request(format!(
+                "cannot filter {} on {}; filterable columns: {}",
+                table.name,
+                column,
+                table.filters.join(", ")
+            )));
+        }
+        values.push(value);
+        conditions.push(format!("{}::text = ${}", quote(&column), values.len()));
+    }
+    let sql = format!(
+        "SELECT to_jsonb(t) FROM {} t{}{} LIMIT {} OFFSET {}",
+        quote(table.name),
+        where_clause(&conditions),
+        order_by(table),
+        limit,
+        offset
+    );
+    let mut query = sqlx::query_scalar::<_, Value>(&sql);
+    for value in &values {
+        query = query.bind(value);
+    }
+    let items = query.fetch_all(&pool).await?;
+    Ok(Json(
+        json!({ "items": items, "limit": limit, "offset": offset }),
+    ))
+}
+
+async fn get_row(
+    pool: PgPool,
+    table: &'static Table,
+    key: Vec<String>,
+) -> Result<Json<Value>, ApiError> {
+    let conditions = table
+        .primary_key
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("{}::text = ${}", quote(column), i + 1))
+        .collect::<Vec<_>>();
+    let sql = format!(
+        "SELECT to_jsonb(t) FROM {} t{}",
+        quote(table.name),
+        where_clause(&conditions)
+    );
+    let mut query = sqlx::query_scalar::<_, Value>(&sql);
+    for value in &key {
+        query = query.bind(value);
+    }
+    match query.fetch_optional(&pool).await? {
+        Some(row) => Ok(Json(row)),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("no such {}", table.name),
+        )),
+    }
+}
+
+fn take_number(
+    params: &mut HashMap<String, String>,
+    name: &str,
+    default: i64,
+) -> Result<i64, ApiError> {
+    match params.remove(name) {
+        Some(value) => value.parse().map_err(|_| {
+            ApiError::bad_request(format!("{} must be a number, got {}", name, value))
+        }),
+        None => Ok(default),
+    }
+}
+
+fn where_clause(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+}
+
+fn order_by(table: &Table) -> String {
+    if table.primary_key.is_empty() {
+        return String::new();
+    }
+    let columns = table
+        .primary_key
+        .iter()
+        .map(|column| quote(column))
+        .collect::<Vec<_>>();
+    format!(" ORDER BY {}", columns.join(", "))
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: String) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+"#;
+
+/// Scaffold a standalone axum crate serving the indexed tables read-only from Postgres: a list
+/// endpoint per table, filterable on its primary key, `is_index` and declared index columns and
+/// paginated with `limit`/`offset`, and a lookup by primary key when the table has one.
+///
+/// Returns the files to write (`Cargo.toml`, `src/main.rs`) by path relative to the crate
+/// directory. Queries compare columns as text, so the crate does not depend on the column types
+/// and only needs regenerating when tables, keys or indexes change.
+pub fn rest_api_scaffold(
+    schema: &SchemaSnapshot,
+    crate_name: &str,
+) -> Result<BTreeMap<String, String>> {
+    if schema.tables.is_empty() {
+        bail!("the schema declares no table");
+    }
+    let valid_name = crate_name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && crate_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        bail!(
+            "invalid crate name {}: use letters, digits, `_` and `-`, starting with a letter",
+            crate_name
+        );
+    }
+    let mut tables = String::new();
+    for (table, columns) in &schema.tables {
+        let primary_key = columns
+            .iter()
+            .filter(|(_, spec)| spec.is_primary_key)
+            .map(|(column, _)| format!("{:?}", column))
+            .collect::<Vec<_>>();
+        let filters = indexed_columns(columns, schema, table)
+            .into_iter()
+            .map(|column| format!("{:?}", column))
+            .collect::<Vec<_>>();
+        tables.push_str(&format!(
+            "    Table {{\n        name: {:?},\n        primary_key: &[{}],\n        filters: &[{}],\n    }},\n",
+            table,
+            primary_key.join(", "),
+            filters.join(", ")
+        ));
+    }
+    Ok([
+        (
+            "Cargo.toml".to_string(),
+            CARGO_TOML.replace("__NAME__", crate_name),
+        ),
+        (
+            "src/main.rs".to_string(),
+            MAIN_RS.replace(TABLES_MARKER, &tables),
+        ),
+    ]
+    .into_iter()
+    .collect())
+}
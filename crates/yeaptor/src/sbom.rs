@@ -0,0 +1,232 @@
+use crate::audit::upgrade_policy_name;
+use crate::deployment_planner::{DeploymentPlan, bytecode_sha3_256};
+use anyhow::{Context, Result};
+use aptos_framework::natives::code::PackageMetadata;
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use yeaptor_core::move_manifest::{
+    GitDependency, load_git_dependencies, load_package_version, local_dependency_dirs,
+};
+
+/// CycloneDX specification version of the SBOMs written
+pub const CYCLONEDX_SPEC_VERSION: &str = "1.5";
+
+/// Versions and origins of the packages of a build, read from their Move.toml files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SbomSources {
+    /// `[package] version` of each built package, by package name
+    pub versions: BTreeMap<String, String>,
+    /// `git` dependencies declared by the built packages and their local dependencies, by
+    /// dependency name
+    pub git: BTreeMap<String, GitDependency>,
+}
+
+/// CycloneDX JSON BOM of a deployment build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    pub bom_format: &'static str,
+    pub spec_version: &'static str,
+    pub version: u32,
+    pub metadata: BomMetadata,
+    pub components: Vec<Component>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// No timestamp, so that the same build always produces the same BOM
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BomMetadata {
+    pub tools: BomTools,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BomTools {
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    /// `library` for Move packages, `application` for yeaptor itself
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Package name, unique within a build
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    pub bom_ref: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hashes: Vec<Hash>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_references: Vec<ExternalReference>,
+    /// `aptos:*` properties: account, deployment, upgrade policy, source hashes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hash {
+    pub alg: &'static str,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalReference {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub depends_on: Vec<String>,
+}
+
+/// Versions of the packages at `package_dirs` (package name to directory) and the git
+/// dependencies they or their local dependencies declare
+pub fn sbom_sources<'a>(
+    package_dirs: impl IntoIterator<Item = (&'a str, &'a Path)>,
+) -> Result<SbomSources> {
+    let mut sources = SbomSources::default();
+    for (name, dir) in package_dirs {
+        if let Some(version) = load_package_version(dir)? {
+            sources.versions.insert(name.to_string(), version);
+        }
+        sources.git.extend(load_git_dependencies(dir)?);
+        for local_dir in local_dependency_dirs(dir)?.values() {
+            sources.git.extend(load_git_dependencies(local_dir)?);
+        }
+    }
+    Ok(sources)
+}
+
+/// BOM of `plan`: one component per built package, with its account, deployment and upgrade
+/// policy, one per dependency it lists in its metadata, with the hashes pinned in yeaptor.lock
+/// and the git repository and revision it was fetched from when known, and the dependency graph
+/// between them
+pub fn build_sbom(plan: &DeploymentPlan, sources: &SbomSources) -> Result<Bom> {
+    let mut components = Vec::new();
+    let mut dependencies = Vec::new();
+    let mut dependency_accounts = BTreeMap::new();
+    let built = plan
+        .packages
+        .iter()
+        .map(|package| package.name.as_str())
+        .collect::<BTreeSet<_>>();
+    for package in &plan.packages {
+        let metadata: PackageMetadata = bcs::from_bytes(&package.metadata).with_context(|| {
+            format!("package metadata of {} does not deserialize", package.name)
+        })?;
+        components.push(Component {
+            kind: "library",
+            bom_ref: Some(package.name.clone()),
+            name: package.name.clone(),
+            version: sources.versions.get(&package.name).cloned(),
+            hashes: vec![sha3_256_hash(bytecode_sha3_256(&package.modules))],
+            external_references: Vec::new(),
+            properties: vec![
+                property("aptos:account", package.account.to_standard_string()),
+                property("aptos:deployment", &package.deployment),
+                property(
+                    "aptos:upgrade_policy",
+                    upgrade_policy_name(metadata.upgrade_policy.policy),
+                ),
+                property("aptos:source_digest", &metadata.source_digest),
+                property(
+                    "aptos:metadata_sha3_256",
+                    hex::encode(Sha3_256::digest(&package.metadata)),
+                ),
+            ],
+        });
+        let mut depends_on = Vec::new();
+        for dep in &metadata.deps {
+            depends_on.push(dep.package_name.clone());
+            if !built.contains(dep.package_name.as_str()) {
+                dependency_accounts.insert(dep.package_name.clone(), dep.account);
+            }
+        }
+        depends_on.sort();
+        depends_on.dedup();
+        dependencies.push(Dependency {
+            reference: package.name.clone(),
+            depends_on,
+        });
+    }
+    for (name, account) in &dependency_accounts {
+        let mut component = Component {
+            kind: "library",
+            bom_ref: Some(name.clone()),
+            name: name.clone(),
+            version: None,
+            hashes: Vec::new(),
+            external_references: Vec::new(),
+            properties: vec![property("aptos:account", account.to_standard_string())],
+        };
+        // local dependencies are not pinned and only carry their account
+        if let Some(hashes) = plan.dependencies.get(name) {
+            component
+                .hashes
+                .push(sha3_256_hash(hashes.bytecode_sha3_256.clone()));
+            component
+                .properties
+                .push(property("aptos:source_sha3_256", &hashes.source_sha3_256));
+        }
+        if let Some(git) = sources.git.get(name) {
+            component.version = git.rev.clone();
+            component.external_references.push(ExternalReference {
+                kind: "vcs",
+                url: git.git.clone(),
+            });
+            if let Some(subdir) = &git.subdir {
+                component.properties.push(property("aptos:subdir", subdir));
+            }
+        }
+        components.push(component);
+    }
+    Ok(Bom {
+        bom_format: "CycloneDX",
+        spec_version: CYCLONEDX_SPEC_VERSION,
+        version: 1,
+        metadata: BomMetadata {
+            tools: BomTools {
+                components: vec![Component {
+                    kind: "application",
+                    bom_ref: None,
+                    name: "yeaptor".to_string(),
+                    version: Some(env!("CARGO_PKG_VERSION").to_string()),
+                    hashes: Vec::new(),
+                    external_references: Vec::new(),
+                    properties: Vec::new(),
+                }],
+            },
+        },
+        components,
+        dependencies,
+    })
+}
+
+fn sha3_256_hash(content: String) -> Hash {
+    Hash {
+        alg: "SHA3-256",
+        content,
+    }
+}
+
+fn property(name: &str, value: impl ToString) -> Property {
+    Property {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
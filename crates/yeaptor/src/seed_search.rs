@@ -0,0 +1,78 @@
+use aptos_types::account_address::AccountAddress;
+use indicatif::ProgressBar;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use yeaptor_core::address::{AddressPrefix, resource_account_address};
+
+/// Seeds a worker checks between updates of the shared attempt counter and the progress bar
+const BATCH: u64 = 4096;
+
+/// A seed whose resource account address starts with the wanted prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedMatch {
+    pub seed: String,
+    pub address: AccountAddress,
+    /// Seeds tried by all workers until the match was found
+    pub attempts: u64,
+}
+
+/// Search for a seed `<seed_prefix><n>` (n = 0, 1, ...) whose resource account of `publisher`
+/// starts with `prefix`, on `workers` threads each taking every `workers`-th n. Stops at the
+/// first match, or returns `None` after `max_attempts` seeds. `progress` counts the attempts.
+pub fn find_seed(
+    publisher: AccountAddress,
+    prefix: &AddressPrefix,
+    seed_prefix: &str,
+    workers: usize,
+    max_attempts: Option<u64>,
+    progress: &ProgressBar,
+) -> Option<SeedMatch> {
+    let workers = workers.max(1) as u64;
+    let limit = max_attempts.unwrap_or(u64::MAX);
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let matches = thread::scope(|scope| {
+        let handles = (0..workers)
+            .map(|worker| {
+                let (found, attempts) = (&found, &attempts);
+                scope.spawn(move || {
+                    let mut n = worker;
+                    let mut checked = 0;
+                    let mut best = None;
+                    while n < limit && !found.load(Ordering::Relaxed) {
+                        let seed = format!("{}{}", seed_prefix, n);
+                        let address = resource_account_address(&publisher, seed.as_bytes());
+                        checked += 1;
+                        if prefix.matches(&address) {
+                            found.store(true, Ordering::Relaxed);
+                            best = Some((n, seed, address));
+                            break;
+                        }
+                        if checked == BATCH {
+                            attempts.fetch_add(checked, Ordering::Relaxed);
+                            progress.inc(checked);
+                            checked = 0;
+                        }
+                        n += workers;
+                    }
+                    attempts.fetch_add(checked, Ordering::Relaxed);
+                    progress.inc(checked);
+                    best
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect::<Vec<_>>()
+    });
+    // several workers may match before they see the others stop; keep the smallest n
+    matches
+        .into_iter()
+        .min_by_key(|(n, _, _)| *n)
+        .map(|(_, seed, address)| SeedMatch {
+            seed,
+            address,
+            attempts: attempts.load(Ordering::Relaxed),
+        })
+}
@@ -0,0 +1,420 @@
+use anyhow::{Context, Result, bail};
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_framework::unzip_metadata_str;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Unchanged lines shown around each change of a unified diff
+pub const DIFF_CONTEXT_LINES: usize = 3;
+
+/// How a module differs between the published package and the local sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleChange {
+    /// Only in the local sources: the upgrade adds it
+    Added,
+    /// Only in the published package: the upgrade drops it, which upgrade compatibility rejects
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+/// Differences of one module, from the published source to the local one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleDiff {
+    pub module: String,
+    pub change: ModuleChange,
+    /// Functions only in the local source
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_functions: Vec<String>,
+    /// Functions only in the published source
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_functions: Vec<String>,
+    /// Functions whose declaration or body differ, comments and whitespace aside
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_functions: Vec<String>,
+    /// Unified diff of the source files declaring the module
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diff: Vec<String>,
+}
+
+/// Source of every module of a published package, by module name; an error when the package was
+/// published without sources (included artifacts `sparse` or `none`)
+pub fn published_module_sources(metadata: &PackageMetadata) -> Result<BTreeMap<String, String>> {
+    if metadata
+        .modules
+        .iter()
+        .all(|module| module.source.is_empty())
+    {
+        bail!(
+            "package {} was published without its sources (included artifacts `sparse` or \
+             `none`), so there is nothing to diff against",
+            metadata.name
+        );
+    }
+    metadata
+        .modules
+        .iter()
+        .map(|module| {
+            let source = unzip_metadata_str(&module.source).with_context(|| {
+                format!(
+                    "source of {}::{} does not decompress",
+                    metadata.name, module.name
+                )
+            })?;
+            Ok((module.name.clone(), source.replace("\r\n", "\n")))
+        })
+        .collect()
+}
+
+/// Source of every module declared under `<package_dir>/sources`, by module name: the whole
+/// `.move` file declaring it, as the compiler embeds it in the package metadata
+pub fn local_module_sources(package_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut sources = BTreeMap::new();
+    collect_module_sources(&package_dir.join("sources"), &mut sources)?;
+    Ok(sources)
+}
+
+fn collect_module_sources(dir: &Path, sources: &mut BTreeMap<String, String>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    let mut entries = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_module_sources(&path, sources)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "move")
+        {
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?
+                .replace("\r\n", "\n");
+            for module in module_sections(&source).into_keys() {
+                sources.insert(module, source.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compare the published sources of a package with the local ones, module by module
+pub fn diff_module_sources(
+    published: &BTreeMap<String, String>,
+    local: &BTreeMap<String, String>,
+) -> Vec<ModuleDiff> {
+    let mut modules = published.keys().chain(local.keys()).collect::<Vec<_>>();
+    modules.sort();
+    modules.dedup();
+    modules
+        .into_iter()
+        .map(|module| {
+            let old = published.get(module).map(String::as_str).unwrap_or("");
+            let new = local.get(module).map(String::as_str).unwrap_or("");
+            let change = match (published.contains_key(module), local.contains_key(module)) {
+                (false, _) => ModuleChange::Added,
+                (_, false) => ModuleChange::Removed,
+                _ if old == new => ModuleChange::Unchanged,
+                _ => ModuleChange::Changed,
+            };
+            let old_functions = module_functions(old, module);
+            let new_functions = module_functions(new, module);
+            ModuleDiff {
+                module: module.clone(),
+                change,
+                added_functions: new_functions
+                    .keys()
+                    .filter(|name| !old_functions.contains_key(*name))
+                    .cloned()
+                    .collect(),
+                removed_functions: old_functions
+                    .keys()
+                    .filter(|name| !new_functions.contains_key(*name))
+                    .cloned()
+                    .collect(),
+                changed_functions: old_functions
+                    .iter()
+                    .filter(|(name, text)| {
+                        new_functions.get(*name).is_some_and(|other| other != *text)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                diff: unified_diff(old, new, DIFF_CONTEXT_LINES),
+            }
+        })
+        .collect()
+}
+
+/// Summary of `diffs` for an upgrade review: a line per module that is not unchanged, naming the
+/// functions added, removed and changed, followed by the line diffs when `with_diff`
+pub fn render_module_diffs(diffs: &[ModuleDiff], with_diff: bool) -> String {
+    let mut lines = Vec::new();
+    for diff in diffs
+        .iter()
+        .filter(|diff| diff.change != ModuleChange::Unchanged)
+    {
+        let mut line = format!(
+            "{} {}",
+            match diff.change {
+                ModuleChange::Added => "added",
+                ModuleChange::Removed => "removed",
+                _ => "changed",
+            },
+            diff.module
+        );
+        for (label, functions) in [
+            ("added", &diff.added_functions),
+            ("removed", &diff.removed_functions),
+            ("changed", &diff.changed_functions),
+        ] {
+            if !functions.is_empty() {
+                line.push_str(&format!(
+                    "\n  {} functions: {}",
+                    label,
+                    functions.join(", ")
+                ));
+            }
+        }
+        lines.push(line);
+        if with_diff {
+            lines.push(diff.diff.join("\n"));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Module name -> comment-free text of its declaration, from its `module` keyword to the next
+/// one, for every module a source file declares (`module 0x1::coin`, `module yeap::market`)
+pub fn module_sections(source: &str) -> BTreeMap<String, String> {
+    let code = strip_comments(source);
+    let starts = keyword_positions(&code, "module")
+        .into_iter()
+        .filter_map(|start| {
+            let rest = &code[start + "module".len()..];
+            let path = rest
+                .trim_start()
+                .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+                .next()
+                .unwrap_or("");
+            // `spec module { .. }` names no module
+            let name = path.rsplit("::").next().unwrap_or("");
+            (!name.is_empty()).then(|| (start, name.to_string()))
+        })
+        .collect::<Vec<_>>();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, name))| {
+            let end = starts.get(i + 1).map_or(code.len(), |(next, _)| *next);
+            (name.clone(), code[*start..end].to_string())
+        })
+        .collect()
+}
+
+/// Function name -> declaration and body of `module` in `source`, with attributes and
+/// modifiers, whitespace collapsed
+pub fn module_functions(source: &str, module: &str) -> BTreeMap<String, String> {
+    let Some(code) = module_sections(source).remove(module) else {
+        return BTreeMap::new();
+    };
+    let mut functions = BTreeMap::new();
+    for start in keyword_positions(&code, "fun") {
+        let rest = code[start + "fun".len()..].trim_start();
+        let name = rest
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or("");
+        if name.is_empty() {
+            continue;
+        }
+        // attributes and modifiers since the end of the previous item
+        let begin = code[..start]
+            .rfind(['{', '}', ';'])
+            .map_or(0, |position| position + 1);
+        let end = item_end(&code, start);
+        functions.insert(
+            name.to_string(),
+            code[begin..end]
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+    functions
+}
+
+/// End of the item starting at `start`: past its `;` (native functions) or the `}` closing its
+/// first block
+fn item_end(code: &str, start: usize) -> usize {
+    let mut depth = 0usize;
+    for (offset, c) in code[start..].char_indices() {
+        match c {
+            ';' if depth == 0 => return start + offset + 1,
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return start + offset + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    code.len()
+}
+
+/// Byte offsets of `keyword` as a whole word in `code`
+fn keyword_positions(code: &str, keyword: &str) -> Vec<usize> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    code.match_indices(keyword)
+        .map(|(position, _)| position)
+        .filter(|position| {
+            let before = code[..*position].chars().next_back();
+            let after = code[position + keyword.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .collect()
+}
+
+/// `source` with `//` and `/* */` comments blanked (newlines kept), leaving string literals
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Same,
+    Old,
+    New,
+}
+
+/// Unified diff (`@@ -1,4 +1,5 @@` hunks of ` `, `-` and `+` lines) from `old` to `new` with
+/// `context` unchanged lines around changes; empty when they are equal
+pub fn unified_diff(old: &str, new: &str, context: usize) -> Vec<String> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let ops = line_ops(&old, &new);
+    let changes = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (line, _, _))| *line != Line::Same)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changes {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut out = Vec::new();
+    for (start, end) in hunks {
+        let ops = &ops[start..end];
+        let old_count = ops.iter().filter(|(line, _, _)| *line != Line::New).count();
+        let new_count = ops.iter().filter(|(line, _, _)| *line != Line::Old).count();
+        let (_, old_start, new_start) = ops[0];
+        out.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start + usize::from(old_count > 0),
+            old_count,
+            new_start + usize::from(new_count > 0),
+            new_count
+        ));
+        for (line, old_line, new_line) in ops {
+            out.push(match line {
+                Line::Same => format!(" {}", old[*old_line]),
+                Line::Old => format!("-{}", old[*old_line]),
+                Line::New => format!("+{}", new[*new_line]),
+            });
+        }
+    }
+    out
+}
+
+/// Edit script of a longest common subsequence of lines: each op with the indexes into `old`
+/// and `new` it is at
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<(Line, usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    // lengths of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut ops = (0..prefix).map(|i| (Line::Same, i, i)).collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((Line::Same, prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((Line::Old, prefix + i, prefix + j));
+            i += 1;
+        } else {
+            ops.push((Line::New, prefix + i, prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|k| (Line::Same, old.len() - suffix + k, new.len() - suffix + k)));
+    ops
+}
@@ -0,0 +1,112 @@
+use crate::CliResult;
+use crate::artifact_signature::{
+    ArtifactSignature, SIGNATURE_FILE_NAME, load_private_key, parse_public_key, sign_artifacts,
+    verify_artifacts,
+};
+use crate::error::YeaptorError;
+use crate::output::CommandOutput;
+use crate::prompt::Confirmation;
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+/// Sign the output of `yeaptor deployment build` on the build machine, and check the signature
+/// wherever the payloads are submitted from
+pub enum ArtifactsTool {
+    Sign(Sign),
+    Verify(Verify),
+}
+
+impl ArtifactsTool {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        match self {
+            ArtifactsTool::Sign(tool) => tool.execute(confirmation),
+            ArtifactsTool::Verify(tool) => tool.execute(),
+        }
+    }
+}
+
+#[derive(Parser)]
+/// Write a detached ed25519 signature over manifest.json of a build output directory and the
+/// payload files it lists
+pub struct Sign {
+    /// Output directory of `yeaptor deployment build`
+    #[clap(long, value_parser, default_value = "./deployments")]
+    pub(crate) out_dir: PathBuf,
+
+    /// File holding the ed25519 private key, hex (as written by `aptos key generate`)
+    #[clap(long, value_parser)]
+    pub(crate) private_key_file: PathBuf,
+
+    /// Where to write the signature (default: <out-dir>/signature.json)
+    #[clap(long, value_parser)]
+    pub(crate) signature_file: Option<PathBuf>,
+}
+
+impl Sign {
+    pub fn execute(self, confirmation: Confirmation) -> CliResult {
+        let key = load_private_key(&self.private_key_file)
+            .map_err(|e| YeaptorError::load(&self.private_key_file, e))?;
+        let signature = sign_artifacts(&self.out_dir, &key).map_err(YeaptorError::invalid)?;
+        let signature_file = self
+            .signature_file
+            .unwrap_or_else(|| self.out_dir.join(SIGNATURE_FILE_NAME));
+        confirmation.confirm_overwrite(&signature_file)?;
+        fs::write(
+            &signature_file,
+            serde_json::to_string_pretty(&signature).map_err(YeaptorError::invalid)?,
+        )
+        .map_err(|e| YeaptorError::io(&signature_file, e))?;
+        Ok(CommandOutput::new(format!(
+            "Signed {} files of {} with {}",
+            signature.files.len(),
+            self.out_dir.display(),
+            signature.public_key
+        ))
+        .files([signature_file])
+        .detail("public_key", &signature.public_key)
+        .detail("files", &signature.files))
+    }
+}
+
+#[derive(Parser)]
+/// Check a signature written by `yeaptor artifacts sign`: it must be by the expected key, and
+/// manifest.json and every payload file it lists must be signed and unchanged
+pub struct Verify {
+    /// Output directory of `yeaptor deployment build`
+    #[clap(long, value_parser, default_value = "./deployments")]
+    pub(crate) out_dir: PathBuf,
+
+    /// Ed25519 public key of the expected signer, hex, or a file holding it
+    #[clap(long)]
+    pub(crate) public_key: String,
+
+    /// Signature to check (default: <out-dir>/signature.json)
+    #[clap(long, value_parser)]
+    pub(crate) signature_file: Option<PathBuf>,
+}
+
+impl Verify {
+    pub fn execute(self) -> CliResult {
+        let public_key = parse_public_key(&self.public_key).map_err(YeaptorError::invalid)?;
+        let signature_file = self
+            .signature_file
+            .unwrap_or_else(|| self.out_dir.join(SIGNATURE_FILE_NAME));
+        let signature: ArtifactSignature = fs::read(&signature_file)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+            .map_err(|e| YeaptorError::load(&signature_file, e))?;
+        verify_artifacts(&self.out_dir, &signature, &public_key)
+            .and_then(|diagnostics| diagnostics.into_result())
+            .map_err(YeaptorError::invalid)?;
+        Ok(CommandOutput::new(format!(
+            "Verified the signature of {} files of {} by {}",
+            signature.files.len(),
+            self.out_dir.display(),
+            signature.public_key
+        ))
+        .detail("public_key", &signature.public_key)
+        .detail("files", &signature.files))
+    }
+}
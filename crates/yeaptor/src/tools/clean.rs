@@ -0,0 +1,73 @@
+use crate::CliResult;
+use crate::clean::{clean_targets, remove_targets};
+use crate::config::load_config;
+use crate::error::YeaptorError;
+use crate::output::CommandOutput;
+use crate::prompt::Confirmation;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+/// Remove what `deployment build` generated: the payloads, event files, addresses.toml and other
+/// outputs in the out dir, and the Move `build/` directory of every package of yeaptor.toml
+pub struct CleanTool {
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+    /// Output directory of `deployment build`
+    #[clap(long, value_parser, default_value = "./deployments")]
+    pub(crate) out_dir: PathBuf,
+    /// Move build output directory passed to `deployment build --output-dir`, removed as well
+    #[clap(long, value_parser)]
+    pub(crate) output_dir: Option<PathBuf>,
+    /// List what would be removed without removing anything
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+}
+
+impl CleanTool {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let cfg = load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+        let targets = clean_targets(&cfg, &self.out_dir, self.output_dir.as_slice());
+        if targets.is_empty() {
+            return Ok(CommandOutput::new("Nothing to clean"));
+        }
+        let listing = targets
+            .iter()
+            .map(|path| format!("  {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.dry_run {
+            return Ok(CommandOutput::new(format!(
+                "Would remove {} paths:\n{}",
+                targets.len(),
+                listing
+            ))
+            .detail("removed", &targets)
+            .detail("dry_run", true));
+        }
+        confirmation.confirm(&format!(
+            "{}\nRemove these {} paths?",
+            listing,
+            targets.len()
+        ))?;
+        // e.g. `--output-dir ..` must not take the project with it
+        let current_dir =
+            std::env::current_dir().map_err(|e| YeaptorError::io(Path::new("."), e))?;
+        let protected = [current_dir, self.config.clone()]
+            .into_iter()
+            .chain(
+                cfg.deployments
+                    .iter()
+                    .flat_map(|deployment| &deployment.packages)
+                    .map(|package| package.path.clone()),
+            )
+            .collect::<Vec<_>>();
+        remove_targets(&targets, &self.out_dir, &protected)
+            .map_err(|e| YeaptorError::io(&self.out_dir, e))?;
+        Ok(
+            CommandOutput::new(format!("Removed {} paths:\n{}", targets.len(), listing))
+                .detail("removed", targets),
+        )
+    }
+}
@@ -0,0 +1,96 @@
+use crate::Yeaptor;
+use crate::config::{YeaptorConfig, load_config};
+use crate::error::{YeaptorError, YeaptorResult};
+use clap::builder::PossibleValuesParser;
+use clap::{Command, CommandFactory, Parser};
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use yeaptor_core::move_manifest::load_package_name;
+
+#[derive(Parser)]
+/// Print a shell completion script, e.g. `yeaptor completions bash > /etc/bash_completion.d/yeaptor`.
+/// When yeaptor.toml exists, `--package-dir`, `--tags` and `deployment diff-source --package`
+/// complete to its packages and tags.
+pub struct CompletionsTool {
+    #[clap(value_enum)]
+    pub(crate) shell: Shell,
+    /// Path to yeaptor config (TOML) whose packages and tags are completed; skipped when the
+    /// file does not exist
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+}
+
+impl CompletionsTool {
+    /// Writes the script to `out` as it is: unlike the other commands, its output is not
+    /// rendered as text or JSON, which would break it
+    pub fn write(self, out: &mut impl Write) -> YeaptorResult<()> {
+        let mut command = Yeaptor::command();
+        if self.config.exists() {
+            let config =
+                load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+            command = complete_config_values(command, &ConfigValues::from_config(&config));
+        }
+        clap_complete::generate(self.shell, &mut command, "yeaptor", out);
+        Ok(())
+    }
+}
+
+/// Values of yeaptor.toml offered by the completion scripts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigValues {
+    /// Package directories, for `--package-dir`
+    pub package_dirs: Vec<String>,
+    /// `address_name` and Move package name of every package, for `--package`
+    pub packages: Vec<String>,
+    /// Tags of the deployments and packages, for `--tags`
+    pub tags: Vec<String>,
+}
+
+impl ConfigValues {
+    pub fn from_config(config: &YeaptorConfig) -> Self {
+        let mut package_dirs = BTreeSet::new();
+        let mut packages = BTreeSet::new();
+        let mut tags = BTreeSet::new();
+        for deployment in &config.deployments {
+            tags.extend(deployment.tags.iter().cloned());
+            for package in &deployment.packages {
+                package_dirs.insert(package.path.display().to_string());
+                packages.insert(package.address_name.clone());
+                packages.extend(load_package_name(&package.path).ok().flatten());
+                tags.extend(package.tags.iter().cloned());
+            }
+        }
+        ConfigValues {
+            package_dirs: package_dirs.into_iter().collect(),
+            packages: packages.into_iter().collect(),
+            tags: tags.into_iter().collect(),
+        }
+    }
+}
+
+/// Offer `values` for `--package-dir`, `--package` and `--tags` in every command taking them.
+/// Only used for generating scripts: the parser itself still accepts any value.
+pub fn complete_config_values(mut command: Command, values: &ConfigValues) -> Command {
+    for (id, offered) in [
+        ("package_dir", &values.package_dirs),
+        ("package", &values.packages),
+        ("tags", &values.tags),
+    ] {
+        if !offered.is_empty() && command.get_arguments().any(|arg| arg.get_id() == id) {
+            let parser = PossibleValuesParser::new(offered.clone());
+            command = command.mut_arg(id, |arg| arg.value_parser(parser));
+        }
+    }
+    let subcommands = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect::<Vec<_>>();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |subcommand| {
+            complete_config_values(subcommand, values)
+        });
+    }
+    command
+}
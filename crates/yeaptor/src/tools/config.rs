@@ -0,0 +1,49 @@
+use crate::CliResult;
+use crate::config::config_schema;
+use crate::error::YeaptorError;
+use crate::output::CommandOutput;
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ConfigTool {
+    /// Print or write the JSON Schema of yeaptor.toml
+    Schema(Schema),
+}
+
+impl ConfigTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            ConfigTool::Schema(tool) => tool.execute().await,
+        }
+    }
+}
+
+#[derive(Parser)]
+/// JSON Schema (draft 7) of yeaptor.toml, for editor completion and validation (e.g. taplo's
+/// `#:schema` directive) and for checking configs in CI before running yeaptor
+pub struct Schema {
+    /// Write the schema to this file instead of printing it
+    #[clap(long, value_parser)]
+    pub(crate) output_file: Option<PathBuf>,
+}
+
+impl Schema {
+    pub async fn execute(self) -> CliResult {
+        let schema = config_schema();
+        let rendered = serde_json::to_string_pretty(&schema).map_err(YeaptorError::invalid)?;
+        match self.output_file {
+            Some(output_file) => {
+                fs::write(&output_file, format!("{}\n", rendered))
+                    .map_err(|e| YeaptorError::io(&output_file, e))?;
+                Ok(CommandOutput::new(format!(
+                    "Wrote yeaptor.toml JSON Schema to {}",
+                    output_file.display()
+                ))
+                .files([output_file]))
+            }
+            None => Ok(CommandOutput::new(rendered).detail("schema", schema)),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::CliResult;
+use crate::error::YeaptorError;
+use crate::metadata_decoder::{decode_package_metadata, read_metadata_input};
+use crate::output::CommandOutput;
+use clap::{Parser, Subcommand};
+use std::path::Path;
+
+#[derive(Subcommand)]
+pub enum DecodeTool {
+    /// Print a BCS `PackageMetadata`, or every package of a `0x1::code::PackageRegistry`, as JSON
+    PackageMetadata(PackageMetadata),
+}
+
+impl DecodeTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            DecodeTool::PackageMetadata(tool) => tool.execute().await,
+        }
+    }
+}
+
+#[derive(Parser)]
+/// Decode the BCS of a package metadata, as in the payloads of `deployment build`, or of an
+/// on-chain `0x1::code::PackageRegistry`, and print it as JSON with the manifest unzipped, to
+/// compare built and published packages
+pub struct PackageMetadata {
+    /// Hex (`0x` optional), or a file holding the BCS bytes or their hex
+    pub(crate) input: String,
+    /// Also unzip the source of every module that carries it
+    #[clap(long)]
+    pub(crate) sources: bool,
+}
+
+impl PackageMetadata {
+    pub async fn execute(self) -> CliResult {
+        let bytes = read_metadata_input(&self.input).map_err(|e| {
+            if Path::new(&self.input).is_file() {
+                YeaptorError::load(&self.input, e)
+            } else {
+                YeaptorError::invalid(e)
+            }
+        })?;
+        let packages =
+            decode_package_metadata(&bytes, self.sources).map_err(YeaptorError::invalid)?;
+        let rendered = serde_json::to_string_pretty(&packages).map_err(YeaptorError::invalid)?;
+        Ok(CommandOutput::new(rendered).detail("packages", packages))
+    }
+}
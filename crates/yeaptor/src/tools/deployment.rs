@@ -1,27 +1,79 @@
-use crate::config::load_config;
-use crate::env::{BuiltDeployment, YeaptorEnv};
-use crate::tools::event::build_event_definition;
-use anyhow::Context;
-use aptos::common::types::{
-    CliCommand, CliError, CliResult, CliTypedResult, MovePackageOptions, PromptOptions, SaveFile,
+use crate::CliResult;
+use crate::audit::{audit_package, check_upgrade_policies};
+use crate::config::{
+    DeployFunction, DeploymentMode, PayloadNaming, YeaptorConfig, load_config, set_deployment_seed,
 };
+use crate::cost_report::{render_cost_report, simulate_plan};
+use crate::deployment_list::{fetch_publish_status, list_packages, render_package_table};
+use crate::deployment_planner::{DeploymentPlan, DeploymentPlanner, MAX_TRANSACTION_SIZE};
+use crate::diagnostics::Diagnostics;
+use crate::env::YeaptorEnv;
+use crate::error::{YeaptorError, YeaptorResult};
+use crate::lockfile::{load_lockfile, lockfile_path};
+use crate::offline_signing::{
+    UNSIGNED_BUNDLE_VERSION, UnsignedBundle, fetch_transaction_settings, match_signed_transactions,
+    parse_signed_transaction, submit_transactions, unsigned_bundle,
+};
+use crate::output::CommandOutput;
+use crate::payload_format::{
+    DEFAULT_PAYLOAD_FILE_NAME, PayloadFormatters, check_distinct_paths, check_payload_file_name,
+    payload_path,
+};
+use crate::payload_inspection::{inspect_payload_file, render_inspection};
+use crate::preflight::{
+    DEFAULT_MAX_GAS_PER_PACKAGE, check_chain_state, fetch_chain_state, fetch_published_packages,
+    preflight_targets, upgrade_artifact_warnings,
+};
+use crate::processor_runtime::default_node_url;
+use crate::progress::progress_bar;
+use crate::prompt::Confirmation;
+use crate::sbom::{build_sbom, sbom_sources};
+use crate::seed_search::find_seed;
+use crate::source_diff::{
+    ModuleChange, diff_module_sources, local_module_sources, published_module_sources,
+    render_module_diffs,
+};
+use crate::verification_bundle::{BuildSettings, verification_manifest, write_verification_bundle};
+use anyhow::anyhow;
+use aptos::common::types::MovePackageOptions;
 use aptos::move_tool::IncludedArtifactsArgs;
+use aptos_framework::docgen::DocgenOptions;
 use aptos_types::account_address::AccountAddress;
 use clap::{Parser, Subcommand};
-use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
-use aptos_framework::docgen::DocgenOptions;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use yeaptor_core::address::AddressPrefix;
+use yeaptor_core::move_manifest::{load_package_name, manifest_path};
 
 #[derive(Subcommand)]
 /// Build publish payload JSON files and optionally event definition files from yeaptor.toml deployments
 pub enum DeploymentTool {
     Build(Build),
+    FindSeed(FindSeed),
+    Derive(Derive),
+    Audit(Audit),
+    ExportUnsigned(ExportUnsigned),
+    ImportSigned(ImportSigned),
+    DiffSource(DiffSource),
+    List(List),
+    Inspect(Inspect),
+    CostReport(CostReport),
 }
 impl DeploymentTool {
-    pub async fn execute(self) -> CliResult {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
         match self {
-            DeploymentTool::Build(tool) => tool.execute_serialized().await,
+            DeploymentTool::Build(tool) => tool.execute(confirmation).await,
+            DeploymentTool::FindSeed(tool) => tool.execute(confirmation),
+            DeploymentTool::Derive(tool) => tool.execute(),
+            DeploymentTool::Audit(tool) => tool.execute(confirmation).await,
+            DeploymentTool::ExportUnsigned(tool) => tool.execute(confirmation).await,
+            DeploymentTool::ImportSigned(tool) => tool.execute(confirmation).await,
+            DeploymentTool::DiffSource(tool) => tool.execute().await,
+            DeploymentTool::List(tool) => tool.execute().await,
+            DeploymentTool::Inspect(tool) => tool.execute(),
+            DeploymentTool::CostReport(tool) => tool.execute().await,
         }
     }
 }
@@ -35,149 +87,377 @@ pub struct Build {
     pub(crate) move_options: MovePackageOptions,
     #[clap(flatten)]
     pub(crate) doc_options: Option<DocgenOptions>,
-    #[clap(flatten)]
-    pub(crate) prompt_options: PromptOptions,
     /// Path to yeaptor config (TOML)
     #[clap(long, default_value = "./yeaptor.toml", value_parser)]
     pub(crate) config: PathBuf,
 
-    /// Directory to write JSON payloads into (one file per package)
+    /// Directory to write JSON payloads into (one subdirectory per deployment)
     #[clap(long, value_parser, default_value = "./deployments")]
     pub(crate) out_dir: PathBuf,
 
+    /// Path of each payload file under --out-dir from `{deployment}`, `{order}`, `{package}` and
+    /// `{file}`; overrides `payload_file_name` in yeaptor.toml (default: {deployment}/{file})
+    #[clap(long)]
+    pub(crate) file_name: Option<String>,
+
+    /// `ordered` or `stable` (payload files named without their position in the deployment
+    /// order, which is recorded in manifest.json); overrides `payload_naming` in yeaptor.toml
+    /// (default: ordered)
+    #[clap(long)]
+    pub(crate) payload_naming: Option<PayloadNaming>,
+
     /// If true, will include events in the build process
     #[clap(long, default_value = "false")]
     pub(crate) with_event: bool,
+
+    /// Also write a source verification bundle per package to
+    /// `<out-dir>/verification/<deployment>/<package>/`: its Move.toml and sources, and
+    /// verification.json with the build settings and the expected metadata and module hashes
+    #[clap(long)]
+    pub(crate) verification_bundle: bool,
+
+    /// Normalize the package metadata before serializing it: drop source maps and store the
+    /// Move.toml and sources with `\n` line endings, so that two clean checkouts produce
+    /// byte-identical payloads
+    #[clap(long)]
+    pub(crate) normalize_metadata: bool,
+
+    /// Also write a CycloneDX software bill of materials to `<out-dir>/sbom.json`: every
+    /// package built and every dependency, with versions, hashes and accounts
+    #[clap(long)]
+    pub(crate) sbom: bool,
+
+    /// Build only the packages tagged with one of these (comma-separated), through `tags` of
+    /// the package or its deployment in yeaptor.toml
+    #[clap(long, value_delimiter = ',', conflicts_with = "package_dir")]
+    pub(crate) tags: Vec<String>,
+
+    /// Format of the payload files; overrides `payload_format` in yeaptor.toml
+    /// (default: entry-function-json)
+    #[clap(long)]
+    pub(crate) payload_format: Option<String>,
+
+    /// Before building, check on chain that every publisher exists and can pay for its
+    /// packages, and that resource accounts being upgraded are administered by their publisher
+    #[clap(long)]
+    pub(crate) preflight: bool,
+
+    /// Network deployed to: selects `[networks.<name>]` of yeaptor.toml (the address and name of
+    /// the deploy function there) and, with --preflight, the node queried for mainnet, testnet
+    /// or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint queried by --preflight; overrides --network
+    #[clap(long, requires = "preflight")]
+    pub(crate) node_url: Option<String>,
+
+    /// Gas units budgeted per package when checking publisher balances
+    #[clap(long, default_value_t = DEFAULT_MAX_GAS_PER_PACKAGE, requires = "preflight")]
+    pub(crate) max_gas: u64,
+
+    /// Gas unit price in octas for the balance check (default: the node's estimate)
+    #[clap(long, requires = "preflight")]
+    pub(crate) gas_unit_price: Option<u64>,
+
+    /// Also write the resolved named addresses as `json` (addresses.json), `env`
+    /// (addresses.env, `<NAME>_ADDRESS=0x...`) or `ts` (addresses.ts, TypeScript constants);
+    /// comma-separated
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub(crate) address_format: Vec<AddressFormat>,
+
+    /// Largest publish transaction accepted, in bytes; packages over it fail the build
+    #[clap(long, default_value_t = MAX_TRANSACTION_SIZE)]
+    pub(crate) max_transaction_size: usize,
+
+    /// Accept dependencies whose content changed since yeaptor.lock was written, pinning their
+    /// new hashes instead of failing the build
+    #[clap(long)]
+    pub(crate) update_lock: bool,
 }
 
-#[async_trait::async_trait]
-impl CliCommand<String> for Build {
-    fn command_name(&self) -> &'static str {
-        "Build"
-    }
-    async fn execute(self) -> CliTypedResult<String> {
-        let cfg = load_config(&self.config)
-            .with_context(|| format!("failed to load config at {}", self.config.display()))?;
+/// Address book formats written besides addresses.toml
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AddressFormat {
+    Json,
+    Env,
+    Ts,
+}
 
-        fs::create_dir_all(&self.out_dir)
-            .with_context(|| format!("failed to create output dir {}", self.out_dir.display()))?;
+impl Build {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        self.execute_with(confirmation, &PayloadFormatters::default())
+            .await
+    }
 
-        let mut package_written = 0usize;
-        let mut event_written = 0usize;
-        let env = YeaptorEnv::new(cfg);
+    /// Build with payload formats of your own registered in `formatters`
+    pub async fn execute_with(
+        self,
+        confirmation: Confirmation,
+        formatters: &PayloadFormatters,
+    ) -> CliResult {
+        let cfg = load_network_config(&self.config, self.network.as_deref())?;
+        let formatter = formatters
+            .get(
+                self.payload_format
+                    .as_deref()
+                    .or(cfg.payload_format.as_deref()),
+            )
+            .map_err(|e| match self.payload_format {
+                Some(_) => YeaptorError::invalid(e),
+                None => YeaptorError::config(&self.config, e),
+            })?;
+        cfg.check_tags(&self.tags).map_err(YeaptorError::invalid)?;
+        let naming = self
+            .payload_naming
+            .or(cfg.payload_naming)
+            .unwrap_or_default();
+        let file_name_template = self
+            .file_name
+            .clone()
+            .or_else(|| cfg.payload_file_name.clone())
+            .unwrap_or_else(|| DEFAULT_PAYLOAD_FILE_NAME.to_string());
+        check_payload_file_name(&file_name_template).map_err(|e| match self.file_name {
+            Some(_) => YeaptorError::invalid(e),
+            None => YeaptorError::config(&self.config, e),
+        })?;
 
-        // Check if a specific package directory is specified
-        let built_deployments = if let Some(ref package_dir) = self.move_options.package_dir {
-            // Build only the specific package
-            let built_deployment = env
-                .build_deployment_package(
-                    package_dir,
-                    &self.included_artifacts_args,
-                    &self.move_options,
-                    self.doc_options.clone(),
-                )
-                .with_context(|| format!("failed to build package at {}", package_dir.display()))?;
-            vec![built_deployment]
+        let deploy = cfg.deploy_function();
+        let policy = cfg.policy.clone();
+        // package directories and their own named addresses by deployment order, for the
+        // verification bundles
+        let package_dirs = cfg
+            .deployments
+            .iter()
+            .flat_map(|d| {
+                d.packages
+                    .iter()
+                    .map(|p| (p.path.clone(), p.addresses.clone()))
+            })
+            .collect::<Vec<_>>();
+        let build_settings = BuildSettings {
+            dev: self.move_options.dev,
+            compiler_version: self
+                .move_options
+                .compiler_version
+                .as_ref()
+                .map(ToString::to_string),
+            language_version: self
+                .move_options
+                .language_version
+                .as_ref()
+                .map(ToString::to_string),
+            bytecode_version: self.move_options.bytecode_version,
+        };
+        let client = if self.preflight {
+            Some(self.node_client()?)
         } else {
-            // Build all deployments as before
-            env.build_all(&self.included_artifacts_args, &self.move_options, self.doc_options.clone())
-                .with_context(|| "failed to build all deployments")?
-                .into_iter()
-                .enumerate()
-                .collect::<Vec<_>>()
+            None
+        };
+        let upgrades = match &client {
+            Some(client) => Some(self.preflight(client, &cfg).await?),
+            None => None,
         };
 
-        fs::create_dir_all(&self.out_dir).with_context(|| {
-            format!(
-                "failed to create output directory {}",
-                self.out_dir.display()
-            )
-        })?;
+        fs::create_dir_all(&self.out_dir).map_err(|e| YeaptorError::io(&self.out_dir, e))?;
+
+        let mut package_written = 0usize;
+        let mut event_written = 0usize;
+        let mut written = Vec::new();
+        let building = progress_bar(0, "build");
+        let plan = DeploymentPlanner::new(cfg, self.move_options)
+            .included_artifacts(self.included_artifacts_args.included_artifacts)
+            .docgen_options(self.doc_options)
+            .with_events(self.with_event)
+            .normalize_metadata(self.normalize_metadata)
+            .tags(self.tags)
+            .progress(building.clone())
+            .plan()
+            .inspect_err(|_| building.abandon())?;
+        building.finish_and_clear();
+        // Dependencies are pinned in yeaptor.lock next to yeaptor.toml
+        let lock_path = lockfile_path(&self.config);
+        let mut lockfile = load_lockfile(&lock_path)
+            .map_err(|e| YeaptorError::load(&lock_path, e))?
+            .unwrap_or_default();
+        if !self.update_lock {
+            lockfile
+                .check(&plan.dependencies)
+                .into_result()
+                .map_err(|e| YeaptorError::config(&lock_path, e))?;
+        }
+        let mut warnings = check_transaction_sizes(&plan, &deploy, self.max_transaction_size)?;
+        warnings.extend(
+            check_upgrade_policies(&plan.packages, &policy).map_err(YeaptorError::invalid)?,
+        );
+        if let Some(client) = &client {
+            warnings.extend(
+                upgrade_artifact_warnings(client, &plan.packages)
+                    .await
+                    .map_err(YeaptorError::chain)?,
+            );
+        }
+        let payload_paths = plan
+            .packages
+            .iter()
+            .map(|package| payload_path(&file_name_template, naming, formatter, package))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(YeaptorError::invalid)?;
+        check_distinct_paths(payload_paths.iter().map(PathBuf::as_path))
+            .map_err(YeaptorError::invalid)?;
+        let writing = progress_bar(plan.packages.len() as u64, "write");
+
         if self.with_event {
             // Ensure the events subdirectory exists
             let events_dir = self.out_dir.join("events");
-            fs::create_dir_all(&events_dir).with_context(|| {
-                format!("failed to create events directory {}", events_dir.display())
-            })?;
+            fs::create_dir_all(&events_dir).map_err(|e| YeaptorError::io(&events_dir, e))?;
         }
-        for (i, deployment) in built_deployments {
-            let BuiltDeployment {
-                publisher: _,
-                seed,
-                pack,
-            } = deployment;
-
-            let (pkg_name, metadata_serialized, modules) = {
-                let metadata = pack
-                    .extract_metadata()
-                    .expect("Package metadata should be present");
-                let metadata_serialized = bcs::to_bytes(&metadata)
-                    .expect("PackageMetadata should be serializable to BCS");
-                let modules = pack.extract_code();
-                (pack.name().to_string(), metadata_serialized, modules)
-            };
-            if self.with_event {
-                let all_events = build_event_definition(&pack);
-                if !all_events.is_empty() {
-                    // Ensure the events subdirectory exists
-                    let events_dir = self.out_dir.join("events");
-                    // write the events as json to the output directory
-                    let save_file = SaveFile {
-                        output_file: events_dir.join(format!("{}.event.json", pack.name())),
-                        prompt_options: self.prompt_options.clone(),
-                    };
-                    save_file.check_file()?;
-                    save_file.save_to_file(
-                        "Event definitions",
-                        serde_json::to_string_pretty(&all_events)
-                            .map_err(|err| CliError::UnexpectedError(format!("{}", err)))?
-                            .as_bytes(),
-                    )?;
-                    event_written += 1;
-                }
+        for (package, payload_path) in plan.packages.iter().zip(&payload_paths) {
+            if !package.events.is_empty() {
+                // write the events as json to the output directory
+                let output_file = self
+                    .out_dir
+                    .join("events")
+                    .join(format!("{}.event.json", package.name));
+                // Overwrite prompts would be drawn over
+                writing.suspend(|| confirmation.confirm_overwrite(&output_file))?;
+                fs::write(
+                    &output_file,
+                    serde_json::to_string_pretty(&package.events).map_err(YeaptorError::invalid)?,
+                )
+                .map_err(|e| YeaptorError::io(&output_file, e))?;
+                written.push(output_file);
+                event_written += 1;
             }
 
-            let json = make_publish_payload_json(
-                env.config().yeaptor_address,
-                seed.as_str(),
-                &metadata_serialized,
-                &modules,
-            );
-            let out_path = self
-                .out_dir
-                .join(format!("{}-{}.package.json", i, pkg_name));
-            let save_file = SaveFile {
-                output_file: out_path,
-                prompt_options: self.prompt_options.clone(),
-            };
-            save_file.check_file()?;
-            save_file.save_to_file(
-                "Publication entry function JSON file",
-                serde_json::to_string_pretty(&json)
-                    .map_err(|err| CliError::UnexpectedError(format!("{}", err)))?
-                    .as_bytes(),
-            )?;
+            let output_file = self.out_dir.join(payload_path);
+            writing.set_message(payload_path.display().to_string());
+            if let Some(dir) = output_file.parent() {
+                fs::create_dir_all(dir).map_err(|e| YeaptorError::io(dir, e))?;
+            }
+            writing.suspend(|| confirmation.confirm_overwrite(&output_file))?;
+            let payload = formatter.format(package).map_err(|e| {
+                YeaptorError::invalid(e.context(format!(
+                    "failed to format the payload of {} as {}",
+                    package.name,
+                    formatter.name()
+                )))
+            })?;
+            fs::write(&output_file, payload).map_err(|e| YeaptorError::io(&output_file, e))?;
+            written.push(output_file);
             package_written += 1;
+            writing.inc(1);
+        }
+        writing.finish_and_clear();
+
+        let mut bundles_written = 0usize;
+        if self.verification_bundle {
+            for package in &plan.packages {
+                let Some((package_dir, package_addresses)) = package_dirs.get(package.order) else {
+                    continue;
+                };
+                let bundle_dir = self
+                    .out_dir
+                    .join("verification")
+                    .join(&package.deployment)
+                    .join(&package.name);
+                confirmation.confirm_overwrite(&bundle_dir)?;
+                let mut named_addresses = plan.named_addresses.clone();
+                named_addresses.extend(package_addresses.clone());
+                let manifest = verification_manifest(package, &named_addresses, &build_settings)
+                    .map_err(|e| YeaptorError::build(package_dir, e))?;
+                written.extend(
+                    write_verification_bundle(package_dir, &bundle_dir, &manifest)
+                        .map_err(|e| YeaptorError::io(&bundle_dir, e))?,
+                );
+                bundles_written += 1;
+            }
+        }
+
+        if self.sbom {
+            let sources = sbom_sources(plan.packages.iter().filter_map(|package| {
+                let (dir, _) = package_dirs.get(package.order)?;
+                Some((package.name.as_str(), dir.as_path()))
+            }))
+            .map_err(|e| YeaptorError::load(&self.config, e))?;
+            let sbom = build_sbom(&plan, &sources).map_err(YeaptorError::invalid)?;
+            let sbom_path = self.out_dir.join("sbom.json");
+            confirmation.confirm_overwrite(&sbom_path)?;
+            fs::write(
+                &sbom_path,
+                serde_json::to_string_pretty(&sbom).map_err(YeaptorError::invalid)?,
+            )
+            .map_err(|e| YeaptorError::io(&sbom_path, e))?;
+            written.push(sbom_path);
         }
 
         // Write resolved named addresses to a TOML file at the end
         let addresses_path = self.out_dir.join("addresses.toml");
-        let mut addresses_toml = String::from("[addresses]\n");
-        for (name, addr) in env.named_addresses().iter() {
-            addresses_toml.push_str(&format!("{} = \"{}\"\n", name, addr.to_standard_string()));
+        fs::write(&addresses_path, plan.addresses_toml())
+            .map_err(|e| YeaptorError::io(&addresses_path, e))?;
+        written.push(addresses_path);
+        for format in &self.address_format {
+            let (file_name, contents) = match format {
+                AddressFormat::Json => ("addresses.json", plan.addresses_json()),
+                AddressFormat::Env => ("addresses.env", plan.addresses_env()),
+                AddressFormat::Ts => ("addresses.ts", plan.addresses_typescript()),
+            };
+            let path = self.out_dir.join(file_name);
+            if written.contains(&path) {
+                continue;
+            }
+            fs::write(&path, contents).map_err(|e| YeaptorError::io(&path, e))?;
+            written.push(path);
+        }
+
+        // Record the deployment order next to the payloads, as stable file names do not carry it
+        let manifest = plan
+            .packages
+            .iter()
+            .zip(&payload_paths)
+            .map(|(package, payload_path)| {
+                serde_json::json!({
+                    "order": package.order,
+                    "deployment": package.deployment,
+                    "name": package.name,
+                    "mode": package.mode,
+                    "publisher": package.publisher.to_standard_string(),
+                    "seed": package.seed,
+                    "account": package.account.to_standard_string(),
+                    "file": payload_path,
+                    "transaction_size": package.transaction_size(&deploy),
+                })
+            })
+            .collect::<Vec<_>>();
+        let manifest_path = self.out_dir.join("manifest.json");
+        fs::write(
+            &manifest_path,
+            serde_json::to_string_pretty(&serde_json::json!({ "packages": manifest }))
+                .map_err(YeaptorError::invalid)?,
+        )
+        .map_err(|e| YeaptorError::io(&manifest_path, e))?;
+        written.push(manifest_path);
+
+        lockfile.update(&plan.dependencies);
+        let lock = lockfile.to_toml().map_err(YeaptorError::invalid)?;
+        if fs::read_to_string(&lock_path).ok().as_deref() != Some(lock.as_str()) {
+            fs::write(&lock_path, lock).map_err(|e| YeaptorError::io(&lock_path, e))?;
+            written.push(lock_path);
         }
-        fs::write(&addresses_path, addresses_toml).with_context(|| {
-            format!(
-                "failed to write addresses file {}",
-                addresses_path.display()
-            )
-        })?;
 
         let mut output = format!(
-            "Wrote {} publish payload JSON files to {}",
+            "Wrote {} publish payload files ({}) to {}",
             package_written,
+            formatter.name(),
             self.out_dir.display()
         );
+        if bundles_written > 0 {
+            output.push_str(&format!(
+                ", Wrote {} source verification bundles to {}",
+                bundles_written,
+                self.out_dir.join("verification").display()
+            ));
+        }
         if event_written > 0 {
             output.push_str(&format!(
                 ", Wrote {} event definition files to {}",
@@ -185,8 +465,860 @@ impl CliCommand<String> for Build {
                 self.out_dir.join("events").display()
             ));
         }
-        Ok(output)
+        let mut result = CommandOutput::new(output).warnings(warnings);
+        if let Some(upgrades) = upgrades {
+            result = result.detail("upgrades", upgrades);
+        }
+        Ok(result
+            .files(written)
+            .addresses(&plan.named_addresses)
+            .detail("payload_format", formatter.name())
+            .detail("packages", manifest))
+    }
+}
+
+/// Fail when a publish transaction is over `max_transaction_size`, which the network would reject
+/// at submission; returns warnings for those within 10% of it
+fn check_transaction_sizes(
+    plan: &DeploymentPlan,
+    deploy: &DeployFunction,
+    max_transaction_size: usize,
+) -> YeaptorResult<Vec<String>> {
+    let mut diagnostics = Diagnostics::default();
+    let mut warnings = Vec::new();
+    for package in &plan.packages {
+        let size = package.transaction_size(deploy);
+        if size > max_transaction_size {
+            diagnostics.push(
+                &package.name,
+                format!(
+                    "publish transaction is {} bytes, over the {}-byte limit; split the \
+                     package into several packages of its deployment, or publish fewer \
+                     artifacts with `include_artifacts = \"none\"` or --included-artifacts none",
+                    size, max_transaction_size
+                ),
+            );
+        } else if size > max_transaction_size / 10 * 9 {
+            warnings.push(format!(
+                "publish transaction of {} is {} bytes, close to the {}-byte limit",
+                package.name, size, max_transaction_size
+            ));
+        }
     }
+    diagnostics.into_result().map_err(YeaptorError::invalid)?;
+    Ok(warnings)
+}
+
+impl Build {
+    /// Client of the node queried by --preflight
+    fn node_client(&self) -> YeaptorResult<aptos_rest_client::Client> {
+        node_client(
+            self.node_url.as_deref(),
+            self.network.as_deref(),
+            "--preflight",
+        )
+    }
+
+    /// Check the publishers and resource accounts of `cfg` on chain; returns whether each
+    /// deployment upgrades an existing resource account
+    async fn preflight(
+        &self,
+        client: &aptos_rest_client::Client,
+        cfg: &YeaptorConfig,
+    ) -> YeaptorResult<BTreeMap<String, bool>> {
+        let targets = preflight_targets(cfg, &self.tags);
+        let deploy = cfg.deploy_function();
+        let state = fetch_chain_state(client, &deploy, &targets, self.gas_unit_price)
+            .await
+            .map_err(YeaptorError::chain)?;
+        check_chain_state(&targets, &state, &deploy, self.max_gas).map_err(YeaptorError::chain)
+    }
+}
+
+#[derive(Parser)]
+/// Build the packages of yeaptor.toml and write an audit report per package: public and entry
+/// functions, friends, structs with copy and store, natives, the upgrade policy and functions
+/// taking a signer
+pub struct Audit {
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageOptions,
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Directory to write the reports into, `<deployment>/<package>.audit.json`
+    #[clap(long, value_parser, default_value = "./audit")]
+    pub(crate) out_dir: PathBuf,
+
+    /// Audit only the packages tagged with one of these (comma-separated)
+    #[clap(long, value_delimiter = ',', conflicts_with = "package_dir")]
+    pub(crate) tags: Vec<String>,
+}
+
+impl Audit {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let cfg = load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+        cfg.check_tags(&self.tags).map_err(YeaptorError::invalid)?;
+
+        let building = progress_bar(0, "build");
+        let plan = DeploymentPlanner::new(cfg, self.move_options)
+            .tags(self.tags)
+            .progress(building.clone())
+            .plan()
+            .inspect_err(|_| building.abandon())?;
+        building.finish_and_clear();
+
+        let mut written = Vec::new();
+        let mut summary = Vec::new();
+        for package in &plan.packages {
+            let audit = audit_package(package).map_err(YeaptorError::invalid)?;
+            let path = self
+                .out_dir
+                .join(&package.deployment)
+                .join(format!("{}.audit.json", package.name));
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| YeaptorError::io(dir, e))?;
+            }
+            let report = serde_json::to_string_pretty(&audit).map_err(YeaptorError::invalid)?;
+            confirmation.write_file(&path, report.as_bytes())?;
+            summary.push(serde_json::json!({
+                "package": audit.package,
+                "deployment": audit.deployment,
+                "upgrade_policy": audit.upgrade_policy,
+                "modules": audit.modules.len(),
+                "file": path,
+            }));
+            written.push(path);
+        }
+        Ok(CommandOutput::new(format!(
+            "Wrote {} audit reports to {}",
+            written.len(),
+            self.out_dir.display()
+        ))
+        .files(written)
+        .detail("packages", summary))
+    }
+}
+
+#[derive(Parser)]
+/// Print the account every deployment of yeaptor.toml publishes to and the named addresses of its
+/// packages, from the config alone: nothing is compiled and no network is queried
+pub struct Derive {
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Only the deployments and packages tagged with one of these (comma-separated)
+    #[clap(long, value_delimiter = ',')]
+    pub(crate) tags: Vec<String>,
+
+    /// Print only the named addresses, as `json`, `env` (`<NAME>_ADDRESS=0x...`) or `ts`
+    /// (TypeScript constants), for redirecting into a file
+    #[clap(long, value_enum)]
+    pub(crate) address_format: Option<AddressFormat>,
+}
+
+impl Derive {
+    pub fn execute(self) -> CliResult {
+        let cfg = load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+        cfg.check_tags(&self.tags)
+            .map_err(|e| YeaptorError::config(&self.config, e))?;
+        let env = YeaptorEnv::new(cfg);
+        let derived = env.derive_deployments(&self.tags)?;
+
+        let mut named_addresses = env.config().named_addresses.clone();
+        for deployment in &derived {
+            for package in &deployment.packages {
+                named_addresses.insert(package.clone(), deployment.account);
+            }
+        }
+        let plan = DeploymentPlan {
+            packages: Vec::new(),
+            named_addresses,
+            dependencies: BTreeMap::new(),
+        };
+        let message = match self.address_format {
+            Some(AddressFormat::Json) => plan.addresses_json(),
+            Some(AddressFormat::Env) => plan.addresses_env(),
+            Some(AddressFormat::Ts) => plan.addresses_typescript(),
+            None => {
+                let mut lines = vec![format!(
+                    "Derived {} deployments of {}",
+                    derived.len(),
+                    self.config.display()
+                )];
+                for deployment in &derived {
+                    lines.push(format!(
+                        "{} ({}): {}",
+                        deployment.deployment,
+                        deployment.mode,
+                        deployment.account.to_standard_string()
+                    ));
+                    for package in &deployment.packages {
+                        lines.push(format!("  {}", package));
+                    }
+                }
+                lines.join("\n")
+            }
+        };
+        Ok(CommandOutput::new(message.trim_end())
+            .addresses(&plan.named_addresses)
+            .detail("deployments", derived))
+    }
+}
+
+#[derive(Parser)]
+/// Search for a seed whose resource account address starts with a prefix of your choice, and
+/// optionally write it into a deployment of yeaptor.toml
+pub struct FindSeed {
+    /// Path to yeaptor config (TOML), for publisher aliases and --write
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Publisher: an alias from `[publishers]` in yeaptor.toml or an account address
+    #[clap(long)]
+    pub(crate) publisher: String,
+
+    /// Hex digits the resource account address should start with, e.g. 0xcafe; each digit
+    /// multiplies the expected search time by 16
+    #[clap(long)]
+    pub(crate) prefix: AddressPrefix,
+
+    /// Text every candidate seed starts with, followed by a counter
+    #[clap(long, default_value = "")]
+    pub(crate) seed_prefix: String,
+
+    /// Worker threads (default: the number of CPUs)
+    #[clap(long)]
+    pub(crate) threads: Option<usize>,
+
+    /// Give up after trying this many seeds
+    #[clap(long)]
+    pub(crate) max_attempts: Option<u64>,
+
+    /// Write the seed found into `seed` of deployments[N] of --config, keeping the rest of the
+    /// file as written
+    #[clap(long, value_name = "N")]
+    pub(crate) write: Option<usize>,
+}
+
+impl FindSeed {
+    pub fn execute(self, confirmation: Confirmation) -> CliResult {
+        let cfg = if self.write.is_some() || self.config.exists() {
+            Some(load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?)
+        } else {
+            None
+        };
+        let publisher = match &cfg {
+            Some(cfg) => cfg.publisher_address(&self.publisher),
+            None => AccountAddress::from_hex_literal(&self.publisher).ok(),
+        }
+        .ok_or_else(|| {
+            YeaptorError::invalid(anyhow!(
+                "unknown publisher `{}`; expected an alias from [publishers] of {} or an address",
+                self.publisher,
+                self.config.display()
+            ))
+        })?;
+        if let (Some(index), Some(cfg)) = (self.write, &cfg) {
+            let deployment = cfg.deployments.get(index).ok_or_else(|| {
+                YeaptorError::invalid(anyhow!(
+                    "--write {}: {} has {} deployments",
+                    index,
+                    self.config.display(),
+                    cfg.deployments.len()
+                ))
+            })?;
+            if deployment.mode() != DeploymentMode::ResourceAccount {
+                return Err(YeaptorError::invalid(anyhow!(
+                    "--write {}: deployments[{}] has mode `{}`, which takes no seed",
+                    index,
+                    index,
+                    deployment.mode()
+                )));
+            }
+            if cfg.publisher_address(&deployment.publisher) != Some(publisher) {
+                return Err(YeaptorError::invalid(anyhow!(
+                    "--write {}: deployments[{}] is published by `{}`, not {}",
+                    index,
+                    index,
+                    deployment.publisher,
+                    publisher.to_standard_string()
+                )));
+            }
+        }
+
+        let threads = self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, |threads| threads.get())
+        });
+        let progress = progress_bar(
+            self.max_attempts
+                .unwrap_or_else(|| self.prefix.expected_attempts()),
+            "seeds",
+        );
+        progress.set_message(format!("searching {} on {} threads", self.prefix, threads));
+        let found = find_seed(
+            publisher,
+            &self.prefix,
+            &self.seed_prefix,
+            threads,
+            self.max_attempts,
+            &progress,
+        );
+        progress.finish_and_clear();
+        let found = found.ok_or_else(|| {
+            YeaptorError::invalid(anyhow!(
+                "no seed among the first {} derives an address starting with {}",
+                self.max_attempts.unwrap_or_default(),
+                self.prefix
+            ))
+        })?;
+
+        let mut output = format!(
+            "Seed `{}` derives {} ({} seeds tried)",
+            found.seed,
+            found.address.to_standard_string(),
+            found.attempts
+        );
+        let mut files = Vec::new();
+        if let Some(index) = self.write {
+            let source =
+                fs::read_to_string(&self.config).map_err(|e| YeaptorError::io(&self.config, e))?;
+            let updated = set_deployment_seed(&source, index, &found.seed)
+                .map_err(|e| YeaptorError::config(&self.config, e))?;
+            confirmation.confirm(&format!(
+                "set the seed of deployments[{}] in {} to `{}`?",
+                index,
+                self.config.display(),
+                found.seed
+            ))?;
+            fs::write(&self.config, updated).map_err(|e| YeaptorError::io(&self.config, e))?;
+            output.push_str(&format!(
+                ", written to deployments[{}] of {}",
+                index,
+                self.config.display()
+            ));
+            files.push(self.config.clone());
+        }
+        Ok(CommandOutput::new(output)
+            .files(files)
+            .detail("publisher", publisher.to_standard_string())
+            .detail("seed", &found.seed)
+            .detail("address", found.address.to_standard_string())
+            .detail("attempts", found.attempts))
+    }
+}
+
+#[derive(Parser)]
+/// Build the packages of yeaptor.toml and write their publish transactions unsigned, for a
+/// signer without network access: raw transactions with the sequence numbers, gas price, expiry
+/// and chain id read from the node, and the message each publisher signs
+pub struct ExportUnsigned {
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageOptions,
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// File to write the unsigned transactions to
+    #[clap(long, value_parser, default_value = "./unsigned-transactions.json")]
+    pub(crate) output_file: PathBuf,
+
+    /// Export only the packages tagged with one of these (comma-separated)
+    #[clap(long, value_delimiter = ',', conflicts_with = "package_dir")]
+    pub(crate) tags: Vec<String>,
+
+    /// Network deployed to: selects `[networks.<name>]` of yeaptor.toml and the node of mainnet,
+    /// testnet or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint read from; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+
+    /// Max gas amount of each transaction
+    #[clap(long, default_value_t = DEFAULT_MAX_GAS_PER_PACKAGE)]
+    pub(crate) max_gas: u64,
+
+    /// Gas unit price in octas (default: the node's estimate)
+    #[clap(long)]
+    pub(crate) gas_unit_price: Option<u64>,
+
+    /// Seconds from now until the transactions expire; they must be signed and imported before
+    #[clap(long, default_value_t = 86_400)]
+    pub(crate) expiration_secs: u64,
+}
+
+impl ExportUnsigned {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let cfg = load_network_config(&self.config, self.network.as_deref())?;
+        cfg.check_tags(&self.tags).map_err(YeaptorError::invalid)?;
+        let client = node_client(
+            self.node_url.as_deref(),
+            self.network.as_deref(),
+            "export-unsigned",
+        )?;
+        let deploy = cfg.deploy_function();
+
+        let building = progress_bar(0, "build");
+        let plan = DeploymentPlanner::new(cfg, self.move_options)
+            .included_artifacts(self.included_artifacts_args.included_artifacts)
+            .tags(self.tags)
+            .progress(building.clone())
+            .plan()
+            .inspect_err(|_| building.abandon())?;
+        building.finish_and_clear();
+
+        let publishers = plan
+            .packages
+            .iter()
+            .map(|package| package.publisher)
+            .collect::<BTreeSet<_>>();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(YeaptorError::invalid)?
+            .as_secs();
+        let (settings, sequence_numbers) = fetch_transaction_settings(
+            &client,
+            &publishers,
+            self.max_gas,
+            self.gas_unit_price,
+            now + self.expiration_secs,
+        )
+        .await
+        .map_err(YeaptorError::chain)?;
+        let bundle = unsigned_bundle(&plan, &deploy, &sequence_numbers, &settings)
+            .map_err(YeaptorError::invalid)?;
+        let json = serde_json::to_string_pretty(&bundle).map_err(YeaptorError::invalid)?;
+        confirmation.write_file(&self.output_file, json.as_bytes())?;
+        Ok(CommandOutput::new(format!(
+            "Exported {} unsigned transactions for chain id {} to {}; they expire at unix time {}",
+            bundle.transactions.len(),
+            bundle.chain_id,
+            self.output_file.display(),
+            bundle.expiration_timestamp_secs
+        ))
+        .files([self.output_file])
+        .detail("chain_id", bundle.chain_id)
+        .detail(
+            "expiration_timestamp_secs",
+            bundle.expiration_timestamp_secs,
+        )
+        .detail("transactions", &bundle.transactions))
+    }
+}
+
+#[derive(Parser)]
+/// Submit publish transactions signed offline from the output of `deployment export-unsigned`,
+/// in deployment order, after checking that they are exactly the exported ones
+pub struct ImportSigned {
+    /// Unsigned transactions written by `deployment export-unsigned`
+    #[clap(long, value_parser, default_value = "./unsigned-transactions.json")]
+    pub(crate) unsigned: PathBuf,
+
+    /// Files of signed transactions, one BCS-serialized `SignedTransaction` each, as bytes or hex
+    #[clap(value_parser, required = true)]
+    pub(crate) signed: Vec<PathBuf>,
+
+    /// Network submitted to: the node of mainnet, testnet or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint submitted to; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+}
+
+impl ImportSigned {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let bundle: UnsignedBundle = fs::read(&self.unsigned)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+            .map_err(|e| YeaptorError::load(&self.unsigned, e))?;
+        if bundle.format_version != UNSIGNED_BUNDLE_VERSION {
+            return Err(YeaptorError::config(
+                &self.unsigned,
+                anyhow!(
+                    "format_version {} is not supported; this yeaptor reads version {}",
+                    bundle.format_version,
+                    UNSIGNED_BUNDLE_VERSION
+                ),
+            ));
+        }
+        let mut signed = Vec::new();
+        for path in &self.signed {
+            let bytes = fs::read(path).map_err(|e| YeaptorError::io(path, e))?;
+            signed
+                .push(parse_signed_transaction(&bytes).map_err(|e| YeaptorError::config(path, e))?);
+        }
+        let transactions =
+            match_signed_transactions(&bundle, signed).map_err(YeaptorError::invalid)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(YeaptorError::invalid)?
+            .as_secs();
+        if now >= bundle.expiration_timestamp_secs {
+            return Err(YeaptorError::invalid(anyhow!(
+                "the transactions expired at unix time {}; export and sign them again",
+                bundle.expiration_timestamp_secs
+            )));
+        }
+
+        let client = node_client(
+            self.node_url.as_deref(),
+            self.network.as_deref(),
+            "import-signed",
+        )?;
+        let chain_id = client
+            .get_index()
+            .await
+            .map_err(YeaptorError::chain)?
+            .into_inner()
+            .chain_id;
+        if chain_id != bundle.chain_id {
+            return Err(YeaptorError::chain(anyhow!(
+                "the node is on chain id {}, but the transactions were exported for chain id {}",
+                chain_id,
+                bundle.chain_id
+            )));
+        }
+        confirmation.confirm(&format!(
+            "submit {} publish transactions to chain id {}?",
+            transactions.len(),
+            chain_id
+        ))?;
+        let hashes = submit_transactions(&client, &transactions)
+            .await
+            .map_err(YeaptorError::chain)?;
+        Ok(CommandOutput::new(format!(
+            "Submitted {} publish transactions to chain id {}",
+            hashes.len(),
+            chain_id
+        ))
+        .detail("transactions", hashes))
+    }
+}
+
+#[derive(Parser)]
+/// Diff the sources of a published package against the local ones, for upgrade reviews: which
+/// modules the upgrade adds, removes and changes, and which functions change in each. The package
+/// must have been published with its sources (included artifacts `all`)
+pub struct DiffSource {
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Package to diff, by its `address_name` in yeaptor.toml or its Move package name
+    #[clap(long)]
+    pub(crate) package: String,
+
+    /// Network the package is published on: selects `[networks.<name>]` of yeaptor.toml and the
+    /// node of mainnet, testnet or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint read from; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+
+    /// Also print the line diff of every module that differs
+    #[clap(long)]
+    pub(crate) diff: bool,
+}
+
+impl DiffSource {
+    pub async fn execute(self) -> CliResult {
+        let cfg = load_network_config(&self.config, self.network.as_deref())?;
+        let client = node_client(
+            self.node_url.as_deref(),
+            self.network.as_deref(),
+            "diff-source",
+        )?;
+        let (deployment, package) = cfg
+            .deployments
+            .iter()
+            .flat_map(|deployment| deployment.packages.iter().map(move |p| (deployment, p)))
+            .find(|(_, package)| {
+                package.address_name == self.package
+                    || load_package_name(&package.path).ok().flatten().as_deref()
+                        == Some(self.package.as_str())
+            })
+            .ok_or_else(|| {
+                YeaptorError::config(
+                    &self.config,
+                    anyhow!("no package {} in the deployments", self.package),
+                )
+            })?;
+        let manifest = manifest_path(&package.path);
+        let name = load_package_name(&package.path)
+            .map_err(|e| YeaptorError::load(&manifest, e))?
+            .ok_or_else(|| YeaptorError::load(&manifest, anyhow!("[package] has no name")))?;
+        let publisher = cfg
+            .publisher_address(&deployment.publisher)
+            .ok_or_else(|| {
+                YeaptorError::config(
+                    &self.config,
+                    anyhow!("unknown publisher {}", deployment.publisher),
+                )
+            })?;
+        let account = deployment.account(&publisher);
+
+        let published = fetch_published_packages(&client, account)
+            .await
+            .map_err(YeaptorError::chain)?;
+        let metadata = published
+            .iter()
+            .find(|metadata| metadata.name == name)
+            .ok_or_else(|| {
+                YeaptorError::chain(anyhow!(
+                    "package {} is not published at {}",
+                    name,
+                    account.to_standard_string()
+                ))
+            })?;
+        let published_sources =
+            published_module_sources(metadata).map_err(YeaptorError::invalid)?;
+        let local_sources =
+            local_module_sources(&package.path).map_err(|e| YeaptorError::io(&package.path, e))?;
+        let mut diffs = diff_module_sources(&published_sources, &local_sources);
+
+        let differing = diffs
+            .iter()
+            .filter(|diff| diff.change != ModuleChange::Unchanged)
+            .count();
+        let mut message = format!(
+            "{} at {}: {} of {} modules differ in {}",
+            name,
+            account.to_standard_string(),
+            differing,
+            diffs.len(),
+            package.path.display()
+        );
+        let summary = render_module_diffs(&diffs, self.diff);
+        if !summary.is_empty() {
+            message.push('\n');
+            message.push_str(&summary);
+        }
+        if !self.diff {
+            for diff in &mut diffs {
+                diff.diff.clear();
+            }
+        }
+        Ok(CommandOutput::new(message)
+            .detail("package", name)
+            .detail("account", account.to_standard_string())
+            .detail("modules", diffs))
+    }
+}
+
+#[derive(Parser)]
+/// List the packages of the deployments of yeaptor.toml with their publishers, seeds and derived
+/// accounts; with `--remote`, also whether each is published on chain and at which upgrade
+pub struct List {
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Only the deployments and packages tagged with one of these (comma-separated)
+    #[clap(long, value_delimiter = ',')]
+    pub(crate) tags: Vec<String>,
+
+    /// Look up the publish status of every package on chain
+    #[clap(long)]
+    pub(crate) remote: bool,
+
+    /// Network deployed to: selects `[networks.<name>]` of yeaptor.toml and, with --remote, the
+    /// node of mainnet, testnet or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint read from with --remote; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+}
+
+impl List {
+    pub async fn execute(self) -> CliResult {
+        let cfg = load_network_config(&self.config, self.network.as_deref())?;
+        cfg.check_tags(&self.tags)
+            .map_err(|e| YeaptorError::config(&self.config, e))?;
+        let mut listings =
+            list_packages(&cfg, &self.tags).map_err(|e| YeaptorError::config(&self.config, e))?;
+        if self.remote {
+            let client = node_client(
+                self.node_url.as_deref(),
+                self.network.as_deref(),
+                "list --remote",
+            )?;
+            fetch_publish_status(&client, &mut listings)
+                .await
+                .map_err(YeaptorError::chain)?;
+        }
+        let message = if listings.is_empty() {
+            format!(
+                "No packages in the deployments of {}",
+                self.config.display()
+            )
+        } else {
+            render_package_table(&listings)
+        };
+        Ok(CommandOutput::new(message).detail("packages", listings))
+    }
+}
+
+#[derive(Parser)]
+/// Decode a payload file of `deployment build` so reviewers see what they sign: the function
+/// called, the seed, the package metadata (name, upgrade policy, dependencies) and the size of
+/// every module
+pub struct Inspect {
+    /// Payload file: entry function JSON, publish arguments JSON or publish arguments BCS
+    /// (`.bcs`)
+    #[clap(value_parser)]
+    pub(crate) payload: PathBuf,
+}
+
+impl Inspect {
+    pub fn execute(self) -> CliResult {
+        let inspection = inspect_payload_file(&self.payload)
+            .map_err(|e| YeaptorError::load(&self.payload, e))?;
+        Ok(CommandOutput::new(render_inspection(&inspection)).detail("payload", inspection))
+    }
+}
+
+#[derive(Parser)]
+/// Simulate the publish of every package, as a create or as an upgrade of the one on chain, and
+/// estimate the gas and APT it costs by publisher at the current gas price
+pub struct CostReport {
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageOptions,
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Report only the packages tagged with one of these (comma-separated)
+    #[clap(long, value_delimiter = ',', conflicts_with = "package_dir")]
+    pub(crate) tags: Vec<String>,
+
+    /// Network deployed to: selects `[networks.<name>]` of yeaptor.toml and the node of mainnet,
+    /// testnet or devnet
+    #[clap(long)]
+    pub(crate) network: Option<String>,
+
+    /// Fullnode REST endpoint simulated on; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+
+    /// Max gas amount of each simulated transaction
+    #[clap(long, default_value_t = DEFAULT_MAX_GAS_PER_PACKAGE)]
+    pub(crate) max_gas: u64,
+
+    /// Gas unit price in octas (default: the node's estimate)
+    #[clap(long)]
+    pub(crate) gas_unit_price: Option<u64>,
+}
+
+impl CostReport {
+    pub async fn execute(self) -> CliResult {
+        let cfg = load_network_config(&self.config, self.network.as_deref())?;
+        cfg.check_tags(&self.tags).map_err(YeaptorError::invalid)?;
+        let client = node_client(
+            self.node_url.as_deref(),
+            self.network.as_deref(),
+            "cost-report",
+        )?;
+        let deploy = cfg.deploy_function();
+
+        let building = progress_bar(0, "build");
+        let plan = DeploymentPlanner::new(cfg, self.move_options)
+            .included_artifacts(self.included_artifacts_args.included_artifacts)
+            .tags(self.tags)
+            .progress(building.clone())
+            .plan()
+            .inspect_err(|_| building.abandon())?;
+        building.finish_and_clear();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(YeaptorError::invalid)?
+            .as_secs();
+        let report = simulate_plan(
+            &client,
+            &plan,
+            &deploy,
+            self.max_gas,
+            self.gas_unit_price,
+            now,
+        )
+        .await
+        .map_err(YeaptorError::chain)?;
+        let warnings = report
+            .packages
+            .iter()
+            .filter(|package| !package.success)
+            .map(|package| {
+                format!(
+                    "simulation of {} failed ({}); it is not counted in the total",
+                    package.package, package.vm_status
+                )
+            })
+            .collect::<Vec<_>>();
+        Ok(CommandOutput::new(render_cost_report(&report))
+            .warnings(warnings)
+            .detail("gas_unit_price", report.gas_unit_price)
+            .detail("total_gas_used", report.total_gas_used)
+            .detail("total_octas", report.total_octas)
+            .detail("publishers", &report.publishers)
+            .detail("packages", &report.packages))
+    }
+}
+
+/// The config at `config` with the settings of `network` from its `[networks]` applied, if a
+/// network is given. mainnet, testnet and devnet need no `[networks]` entry, other names do.
+fn load_network_config(config: &Path, network: Option<&str>) -> YeaptorResult<YeaptorConfig> {
+    let cfg = load_config(config).map_err(|e| YeaptorError::load(config, e))?;
+    match network {
+        Some(network)
+            if cfg.networks.contains_key(network) || default_node_url(network).is_none() =>
+        {
+            cfg.for_network(network)
+                .map_err(|e| YeaptorError::config(config, e))
+        }
+        _ => Ok(cfg),
+    }
+}
+
+/// Client of the node of `node_url`, or of the Aptos Labs fullnode of `network`; `command` is
+/// what needs it, for the error when neither is given
+fn node_client(
+    node_url: Option<&str>,
+    network: Option<&str>,
+    command: &str,
+) -> YeaptorResult<aptos_rest_client::Client> {
+    let node_url = match (node_url, network) {
+        (Some(url), _) => url.to_string(),
+        (None, Some(network)) => default_node_url(network)
+            .ok_or_else(|| {
+                YeaptorError::invalid(anyhow!(
+                    "no default node URL for network {}; pass --node-url",
+                    network
+                ))
+            })?
+            .to_string(),
+        (None, None) => {
+            return Err(YeaptorError::invalid(anyhow!(
+                "{} needs --network or --node-url",
+                command
+            )));
+        }
+    };
+    let url = url::Url::parse(&node_url)
+        .map_err(|e| YeaptorError::invalid(anyhow!("invalid node URL {}: {}", node_url, e)))?;
+    Ok(aptos_rest_client::Client::new(url))
 }
 
 // fn read_package_manifest(package_dir: &Path) -> Result<SourceManifest> {
@@ -199,26 +1331,3 @@ impl CliCommand<String> for Build {
 //         })?,
 //     )
 // }
-
-fn make_publish_payload_json(
-    ra_code_deployment_address: AccountAddress,
-    seed: &str,
-    metadata: &[u8],
-    modules: &[Vec<u8>],
-) -> serde_json::Value {
-    let seed_hex = format!("0x{}", hex::encode(seed.as_bytes()));
-    let meta_hex = format!("0x{}", hex::encode(metadata));
-    let module_hex: Vec<String> = modules
-        .iter()
-        .map(|m| format!("0x{}", hex::encode(m)))
-        .collect();
-    json!({
-        "function_id": format!("{}::{}::{}", ra_code_deployment_address.to_standard_string(), "ra_code_deployment", "deploy"),
-        "type_args": [],
-        "args": [
-            { "type": "hex", "value": seed_hex },
-            { "type": "hex", "value": meta_hex },
-            { "type": "hex", "value": module_hex },
-        ]
-    })
-}
@@ -0,0 +1,101 @@
+use crate::CliResult;
+use crate::config::load_config;
+use crate::doctor::{
+    CheckStatus, DoctorCheck, aptos_config_files, check_aptos_profile, check_disk_space,
+    check_faucet, check_fullnode, check_git, check_move_compiler, check_packages,
+    check_publisher_keys, default_faucet_url, render_checks,
+};
+use crate::error::YeaptorError;
+use crate::output::CommandOutput;
+use crate::processor_runtime::default_node_url;
+use anyhow::anyhow;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+/// Check the local environment before deploying: the fullnode and faucet of the network, the
+/// aptos CLI profile, git, the packages of yeaptor.toml, the Move compiler and the free space in
+/// the out dir. Prints a fix for every problem and fails when a check errors.
+pub struct DoctorTool {
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+    /// Network whose fullnode and faucet are checked, and that the aptos profile should target
+    #[clap(long, default_value = "testnet")]
+    pub(crate) network: String,
+    /// Fullnode REST endpoint to check; overrides --network
+    #[clap(long)]
+    pub(crate) node_url: Option<String>,
+    /// Faucet to check; overrides --network (mainnet has none)
+    #[clap(long)]
+    pub(crate) faucet_url: Option<String>,
+    /// aptos CLI profile used to sign and submit
+    #[clap(long, default_value = "default")]
+    pub(crate) profile: String,
+    /// Output directory of `deployment build`, whose file system should have room for builds
+    #[clap(long, value_parser, default_value = "./deployments")]
+    pub(crate) out_dir: PathBuf,
+    /// Do not compile the probe package (the slowest check)
+    #[clap(long)]
+    pub(crate) skip_compile: bool,
+}
+
+impl DoctorTool {
+    pub async fn execute(self) -> CliResult {
+        let mut checks = Vec::new();
+        let node_url = self
+            .node_url
+            .clone()
+            .or_else(|| default_node_url(&self.network).map(str::to_string));
+        match node_url {
+            Some(url) => checks.push(check_fullnode(&url).await),
+            None => checks.push(DoctorCheck::error(
+                "fullnode",
+                format!("no default fullnode for network {}", self.network),
+                "pass --node-url",
+            )),
+        }
+        let faucet_url = self
+            .faucet_url
+            .clone()
+            .or_else(|| default_faucet_url(&self.network).map(str::to_string));
+        if let Some(url) = faucet_url {
+            checks.push(check_faucet(&url).await);
+        }
+        checks.push(check_aptos_profile(
+            &self.profile,
+            &self.network,
+            &aptos_config_files(),
+        ));
+        checks.push(check_git());
+        if self.config.exists() {
+            match load_config(&self.config) {
+                Ok(config) => {
+                    checks.extend(check_packages(&config));
+                    checks.extend(check_publisher_keys(&config, &aptos_config_files()));
+                }
+                Err(e) => checks.push(DoctorCheck::error(
+                    "config",
+                    format!("{}: {:#}", self.config.display(), e),
+                    "fix yeaptor.toml; `yeaptor config schema` prints its format",
+                )),
+            }
+        } else {
+            checks.push(DoctorCheck::warning(
+                "config",
+                format!("{} not found, packages not checked", self.config.display()),
+                "run in the project directory or pass --config",
+            ));
+        }
+        if !self.skip_compile {
+            checks.push(check_move_compiler());
+        }
+        checks.push(check_disk_space(&self.out_dir));
+
+        let report = render_checks(&checks);
+        if checks.iter().any(|c| c.status == CheckStatus::Error) {
+            return Err(YeaptorError::invalid(anyhow!(report)));
+        }
+        Ok(CommandOutput::new(report).detail("checks", checks))
+    }
+}
@@ -1,14 +1,20 @@
+use crate::CliResult;
 use crate::config::load_config;
-use crate::env::YeaptorEnv;
-use crate::event_definition::{EventDefinition, extract_event_definitions};
-use anyhow::Context;
-use aptos::common::types::{
-    CliCommand, CliError, CliResult, CliTypedResult, MovePackageOptions, PromptOptions, SaveFile,
-};
-use aptos::move_tool::IncludedArtifacts;
-use aptos_framework::BuiltPackage;
+use crate::error::YeaptorError;
+use crate::event_definition::{EventDefinition, EventVersion};
+use crate::event_extractor::EventExtractor;
+use crate::event_fixtures::{FIXTURE_ADDRESS, event_fixture, event_type};
+use crate::event_listing::{list_events, render_event_table};
+use crate::event_table_mapping::parse_event_table_mappings;
+use crate::mapping_engine::{EventRecord, TransactionRecord};
+use crate::output::CommandOutput;
+use crate::processor_config_generator::load_event_definitions_from_dir;
+use crate::progress::progress_bar;
+use crate::prompt::Confirmation;
+use crate::template_variables::TemplateVariables;
+use aptos::common::types::MovePackageOptions;
 use clap::{Parser, Subcommand};
-use move_binary_format::access::ModuleAccess;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -17,12 +23,19 @@ use std::path::PathBuf;
 pub enum EventTool {
     /// Generate event definition JSON files from compiled Move packages
     Generate(Generate),
+    /// Write synthetic event payloads for every event definition, as test fixtures
+    Fixtures(Fixtures),
+    /// Print every event of the packages with its field count and whether the event mapping
+    /// writes it
+    List(List),
 }
 
 impl EventTool {
-    pub async fn execute(self) -> CliResult {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
         match self {
-            EventTool::Generate(tool) => tool.execute_serialized().await,
+            EventTool::Generate(tool) => tool.execute(confirmation).await,
+            EventTool::Fixtures(tool) => tool.execute(confirmation).await,
+            EventTool::List(tool) => tool.execute().await,
         }
     }
 }
@@ -32,8 +45,6 @@ impl EventTool {
 pub struct Generate {
     #[clap(flatten)]
     pub(crate) move_options: MovePackageOptions,
-    #[clap(flatten)]
-    pub(crate) prompt_options: PromptOptions,
     /// Path to yeaptor config (TOML)
     #[clap(long, default_value = "./yeaptor.toml", value_parser)]
     pub(crate) config: PathBuf,
@@ -41,80 +52,211 @@ pub struct Generate {
     /// Directory to write JSON payloads into (one file per package)
     #[clap(long, value_parser, default_value = "./events")]
     pub(crate) out_dir: PathBuf,
+
+    /// Also extract events declared in dependency packages (including framework packages)
+    #[clap(long, default_value = "false")]
+    pub(crate) include_deps: bool,
 }
 
-#[async_trait::async_trait]
-impl CliCommand<String> for Generate {
-    fn command_name(&self) -> &'static str {
-        "generate_event_definitions"
-    }
-    async fn execute(self) -> CliTypedResult<String> {
-        let cfg = load_config(&self.config)
-            .with_context(|| format!("failed to load config at {}", self.config.display()))?;
+impl Generate {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let cfg = load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+
+        fs::create_dir_all(&self.out_dir).map_err(|e| YeaptorError::io(&self.out_dir, e))?;
 
-        fs::create_dir_all(&self.out_dir)
-            .with_context(|| format!("failed to create output dir {}", self.out_dir.display()))?;
+        let building = progress_bar(0, "build");
+        let packages = EventExtractor::new()
+            .include_deps(self.include_deps)
+            .progress(building.clone())
+            .extract_deployments(cfg, &self.move_options)
+            .inspect_err(|_| building.abandon())?;
+        building.finish_and_clear();
+        let writing = progress_bar(packages.len() as u64, "write");
+        let mut written = Vec::new();
+        for (package_name, all_events) in &packages {
+            // write the events as json to the output directory
+            let output_file = self.out_dir.join(format!("{}.event.json", package_name));
+            writing.set_message(output_file.display().to_string());
+            // Overwrite prompts would be drawn over
+            writing.suspend(|| confirmation.confirm_overwrite(&output_file))?;
+            fs::write(
+                &output_file,
+                serde_json::to_string_pretty(all_events).map_err(YeaptorError::invalid)?,
+            )
+            .map_err(|e| YeaptorError::io(&output_file, e))?;
+            written.push(output_file);
+            writing.inc(1);
+        }
+        writing.finish_and_clear();
 
-        let env = YeaptorEnv::new(cfg);
-        let packages: Vec<PathBuf> = if self.move_options.package_dir.is_none() {
-            env.config()
-                .deployments
+        Ok(CommandOutput::new(format!(
+            "wrote {} event definition files to {}",
+            written.len(),
+            self.out_dir.display()
+        ))
+        .files(written)
+        .detail(
+            "events",
+            packages
                 .iter()
-                .flat_map(|d| d.packages.iter().map(|p| p.path.clone()))
-                .collect::<Vec<_>>()
-        } else {
-            vec![self.move_options.package_dir.clone().unwrap()]
-        };
-        let mut writen = 0;
-        for package_dir in &packages {
-            let pack =
-                env.build_package(&package_dir, &IncludedArtifacts::None, &self.move_options, None)?;
+                .map(|(package_name, events)| (package_name.clone(), events.len()))
+                .collect::<std::collections::BTreeMap<_, _>>(),
+        ))
+    }
+}
 
-            let all_events = build_event_definition(&pack);
+#[derive(Parser)]
+/// Write one synthetic payload per event definition, shaped like the transaction stream's JSON
+/// (`u64` and wider integers as strings, addresses as hex): `<package>.fixtures.json` maps each
+/// event type to its data, and `transactions.jsonl` holds one transaction per event for
+/// `processor replay` and `processor preview`
+pub struct Fixtures {
+    /// Directory containing event definition JSON files
+    #[clap(long, default_value = "./events", value_parser)]
+    pub(crate) events_dir: PathBuf,
+    /// Directory to write the fixtures into
+    #[clap(long, value_parser, default_value = "./fixtures")]
+    pub(crate) out_dir: PathBuf,
+}
 
-            // write the events as json to the output directory
-            let save_file = SaveFile {
-                output_file: self.out_dir.join(format!("{}.event.json", pack.name())),
-                prompt_options: self.prompt_options.clone(),
-            };
-            save_file.check_file()?;
-            save_file.save_to_file(
-                "Event definitions",
-                serde_json::to_string_pretty(&all_events)
-                    .map_err(|err| CliError::UnexpectedError(format!("{}", err)))?
-                    .as_bytes(),
-            )?;
-            writen += 1;
+impl Fixtures {
+    pub async fn execute(self, confirmation: Confirmation) -> CliResult {
+        let definitions = load_event_definitions_from_dir(self.events_dir.as_path())
+            .map_err(|e| YeaptorError::load(&self.events_dir, e))?;
+        let mut packages: BTreeMap<&str, BTreeMap<String, serde_json::Value>> = BTreeMap::new();
+        for definition in &definitions {
+            packages
+                .entry(definition.package_name.as_str())
+                .or_default()
+                .insert(event_type(definition), event_fixture(definition));
         }
 
-        Ok(format!(
-            "wrote {} event definition files to {}",
-            writen,
+        fs::create_dir_all(&self.out_dir).map_err(|e| YeaptorError::io(&self.out_dir, e))?;
+        let mut written = Vec::new();
+        for (package_name, events) in &packages {
+            let output_file = self.out_dir.join(format!("{}.fixtures.json", package_name));
+            let content = serde_json::to_string_pretty(events).map_err(YeaptorError::invalid)?;
+            confirmation.write_file(&output_file, content.as_bytes())?;
+            written.push(output_file);
+        }
+        let mut lines = Vec::new();
+        for (index, definition) in definitions.iter().enumerate() {
+            let txn = fixture_transaction(index as u64 + 1, definition);
+            lines.push(serde_json::to_string(&txn).map_err(YeaptorError::invalid)?);
+        }
+        let output_file = self.out_dir.join("transactions.jsonl");
+        confirmation.write_file(&output_file, format!("{}\n", lines.join("\n")).as_bytes())?;
+        written.push(output_file);
+
+        Ok(CommandOutput::new(format!(
+            "wrote fixtures for {} events to {}",
+            definitions.len(),
             self.out_dir.display()
         ))
+        .files(written))
     }
 }
 
-pub(crate) fn build_event_definition(pack: &BuiltPackage) -> Vec<EventDefinition> {
-    let package_name = pack.name().to_string();
-    let modules = pack.modules().collect::<Vec<_>>();
-    let all_events = modules
-        .iter()
-        .flat_map(|m| {
-            let events = extract_event_definitions(m);
-            let module_name = m.name().to_string();
-            let package_name = package_name.clone();
-            events.into_iter().map(move |(event_name, fields)| {
-                let event = EventDefinition {
-                    package_name: package_name.clone(),
-                    module_address: *m.address(),
-                    module_name: module_name.clone(),
-                    name: event_name.clone(),
-                    fields,
-                };
-                event
-            })
-        })
-        .collect::<Vec<_>>();
-    all_events
+#[derive(Parser)]
+/// Print a table of the events of the packages of yeaptor.toml (or of `--package-dir`): their
+/// kind, field count and, when `--event-mapping` exists, the tables it writes them to, followed
+/// by the mapping coverage. Builds the packages unless `--events-dir` points at the output of
+/// `event generate`.
+pub struct List {
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageOptions,
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+
+    /// Read the event definition JSON files of a previous `event generate` instead of building
+    #[clap(long, value_parser)]
+    pub(crate) events_dir: Option<PathBuf>,
+
+    /// Event-to-table mapping (CSV, TOML or YAML) to report the status of each event against;
+    /// skipped when the file does not exist
+    #[clap(long, value_parser, default_value = "./event_mapping.csv")]
+    pub(crate) event_mapping: PathBuf,
+
+    /// Also list events declared in dependency packages (including framework packages)
+    #[clap(long, default_value = "false")]
+    pub(crate) include_deps: bool,
+}
+
+impl List {
+    pub async fn execute(self) -> CliResult {
+        let cfg = if self.events_dir.is_none() || self.config.exists() {
+            Some(load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?)
+        } else {
+            None
+        };
+        let definitions = match (&self.events_dir, &cfg) {
+            (Some(events_dir), _) => load_event_definitions_from_dir(events_dir)
+                .map_err(|e| YeaptorError::load(events_dir, e))?,
+            (None, Some(cfg)) => {
+                let building = progress_bar(0, "build");
+                let packages = EventExtractor::new()
+                    .include_deps(self.include_deps)
+                    .progress(building.clone())
+                    .extract_deployments(cfg.clone(), &self.move_options)
+                    .inspect_err(|_| building.abandon())?;
+                building.finish_and_clear();
+                packages
+                    .into_iter()
+                    .flat_map(|(_, events)| events)
+                    .collect()
+            }
+            (None, None) => unreachable!("the config is loaded unless --events-dir is given"),
+        };
+        let mapping = if self.event_mapping.exists() {
+            let mut content = fs::read_to_string(&self.event_mapping)
+                .map_err(|e| YeaptorError::io(&self.event_mapping, e))?;
+            if let (true, Some(cfg)) = (content.contains("{{"), &cfg) {
+                content = TemplateVariables::from_config(cfg)
+                    .render(&content)
+                    .map_err(|e| YeaptorError::config(&self.event_mapping, e))?;
+            }
+            let mut mapping = parse_event_table_mappings(&self.event_mapping, &content)
+                .map_err(|e| YeaptorError::load(&self.event_mapping, e))?;
+            if let Some(cfg) = &cfg {
+                cfg.processor
+                    .standard_templates
+                    .extend_event_mapping(&mut mapping);
+            }
+            Some(mapping)
+        } else {
+            None
+        };
+        let listings = list_events(&definitions, mapping.as_ref());
+        let message = if listings.is_empty() {
+            "No events declared".to_string()
+        } else {
+            render_event_table(&listings)
+        };
+        Ok(CommandOutput::new(message).detail("events", listings))
+    }
+}
+
+/// A transaction at `version` emitting the fixture of the event; module events are emitted
+/// under `0x0` like on chain, legacy events under the fixture address
+fn fixture_transaction(version: u64, definition: &EventDefinition) -> TransactionRecord {
+    let account_address = match definition.version {
+        EventVersion::V2 => "0x0".to_string(),
+        EventVersion::V1 => FIXTURE_ADDRESS.to_standard_string(),
+    };
+    TransactionRecord {
+        version,
+        block_height: version,
+        epoch: 1,
+        timestamp: 1_700_000_000_000_000 + version,
+        events: vec![EventRecord {
+            account_address,
+            creation_number: 0,
+            sequence_number: 0,
+            event_type: event_type(definition),
+            data: event_fixture(definition),
+        }],
+        payload: None,
+        table_items: Vec::new(),
+    }
 }
@@ -0,0 +1,30 @@
+use crate::CliResult;
+use crate::address_explain::{explain_address, render_explanations};
+use crate::config::load_config;
+use crate::error::YeaptorError;
+use crate::output::CommandOutput;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+/// Show how an address of yeaptor.toml is obtained: the publisher, seed bytes and scheme it is
+/// derived with, or where it is declared. Every `[networks.<name>]` override of
+/// `yeaptor_address` is listed too.
+pub struct ExplainAddressTool {
+    /// Named address (a package's `address_name`, a `[named-addresses]` or `[publishers]` entry,
+    /// or `yeaptor_address`), or an address to find the names bound to
+    pub(crate) address: String,
+    /// Path to yeaptor config (TOML)
+    #[clap(long, default_value = "./yeaptor.toml", value_parser)]
+    pub(crate) config: PathBuf,
+}
+
+impl ExplainAddressTool {
+    pub async fn execute(self) -> CliResult {
+        let cfg = load_config(&self.config).map_err(|e| YeaptorError::load(&self.config, e))?;
+        let explanations = explain_address(&cfg, &self.address)
+            .map_err(|e| YeaptorError::config(&self.config, e))?;
+        Ok(CommandOutput::new(render_explanations(&explanations))
+            .detail("explanations", explanations))
+    }
+}
@@ -1,3 +1,10 @@
+pub mod artifacts;
+pub mod clean;
+pub mod completions;
+pub mod config;
+pub mod decode;
 pub mod deployment;
+pub mod doctor;
 pub mod event;
+pub mod explain_address;
 pub mod indexer;
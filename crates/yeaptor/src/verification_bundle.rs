@@ -0,0 +1,171 @@
+use crate::audit::upgrade_policy_name;
+use crate::deployment_planner::PlannedPackage;
+use anyhow::{Context, Result};
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::CompiledModule;
+use move_binary_format::access::ModuleAccess;
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Compiler settings a package was built with, which a verifier must rebuild it with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildSettings {
+    pub dev: bool,
+    pub compiler_version: Option<String>,
+    pub language_version: Option<String>,
+    pub bytecode_version: Option<u32>,
+}
+
+/// `verification.json` of a source verification bundle: how to rebuild the package and what the
+/// rebuild must produce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerificationManifest {
+    pub package: String,
+    /// Account the package is published to, as a standard string
+    pub account: String,
+    /// Named addresses of the build, as standard strings
+    pub named_addresses: BTreeMap<String, String>,
+    pub build: BuildSettings,
+    /// `arbitrary`, `compatible` or `immutable`
+    pub upgrade_policy: String,
+    /// `source_digest` of the package metadata, which `aptos move verify-package` compares
+    pub source_digest: String,
+    /// SHA3-256 of the BCS-serialized package metadata, hex
+    pub metadata_sha3_256: String,
+    /// SHA3-256 of each module's bytecode, hex, by module name in publishing order
+    pub modules: Vec<ModuleHash>,
+    /// aptos CLI command comparing the bundle with the package published at `account`
+    pub verify_command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleHash {
+    pub name: String,
+    pub sha3_256: String,
+}
+
+/// Verification manifest of `package`, built with `named_addresses` and `build`
+pub fn verification_manifest(
+    package: &PlannedPackage,
+    named_addresses: &BTreeMap<String, AccountAddress>,
+    build: &BuildSettings,
+) -> Result<VerificationManifest> {
+    let metadata: PackageMetadata = bcs::from_bytes(&package.metadata)
+        .with_context(|| format!("package metadata of {} does not deserialize", package.name))?;
+    let modules = package
+        .modules
+        .iter()
+        .map(|bytes| {
+            let module = CompiledModule::deserialize(bytes)
+                .with_context(|| format!("a module of {} does not deserialize", package.name))?;
+            Ok(ModuleHash {
+                name: module.self_id().name().to_string(),
+                sha3_256: sha3_hex(bytes),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let named_addresses = named_addresses
+        .iter()
+        .map(|(name, address)| (name.clone(), address.to_standard_string()))
+        .collect::<BTreeMap<_, _>>();
+    let mut verify_command = format!(
+        "aptos move verify-package --package-dir . --account {}",
+        package.account.to_standard_string()
+    );
+    if !named_addresses.is_empty() {
+        verify_command.push_str(" --named-addresses ");
+        verify_command.push_str(
+            &named_addresses
+                .iter()
+                .map(|(name, address)| format!("{}={}", name, address))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    if build.dev {
+        verify_command.push_str(" --dev");
+    }
+    if let Some(version) = &build.compiler_version {
+        verify_command.push_str(&format!(" --compiler-version {}", version));
+    }
+    if let Some(version) = &build.language_version {
+        verify_command.push_str(&format!(" --language-version {}", version));
+    }
+    if let Some(version) = build.bytecode_version {
+        verify_command.push_str(&format!(" --bytecode-version {}", version));
+    }
+    Ok(VerificationManifest {
+        package: package.name.clone(),
+        account: package.account.to_standard_string(),
+        named_addresses,
+        build: build.clone(),
+        upgrade_policy: upgrade_policy_name(metadata.upgrade_policy.policy).to_string(),
+        source_digest: metadata.source_digest,
+        metadata_sha3_256: sha3_hex(&package.metadata),
+        modules,
+        verify_command,
+    })
+}
+
+/// Write the bundle of the package at `package_dir` into `bundle_dir`: its Move.toml, every
+/// `.move` file under `sources/` at the same relative path, and `manifest` as
+/// `verification.json`. Returns the files written.
+pub fn write_verification_bundle(
+    package_dir: &Path,
+    bundle_dir: &Path,
+    manifest: &VerificationManifest,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    fs::create_dir_all(bundle_dir)
+        .with_context(|| format!("failed to create {}", bundle_dir.display()))?;
+    let move_toml = bundle_dir.join("Move.toml");
+    fs::copy(package_dir.join("Move.toml"), &move_toml)
+        .with_context(|| format!("failed to copy the Move.toml of {}", package_dir.display()))?;
+    written.push(move_toml);
+    copy_move_sources(
+        &package_dir.join("sources"),
+        &bundle_dir.join("sources"),
+        &mut written,
+    )?;
+    let verification = bundle_dir.join("verification.json");
+    fs::write(&verification, serde_json::to_string_pretty(manifest)?)
+        .with_context(|| format!("failed to write {}", verification.display()))?;
+    written.push(verification);
+    Ok(written)
+}
+
+fn copy_move_sources(from: &Path, to: &Path, written: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(from).with_context(|| format!("failed to read {}", from.display()))?;
+    let mut entries = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to read {}", from.display()))?;
+    entries.sort();
+    for path in entries {
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let target = to.join(file_name);
+        if path.is_dir() {
+            copy_move_sources(&path, &target, written)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "move")
+        {
+            fs::create_dir_all(to).with_context(|| format!("failed to create {}", to.display()))?;
+            fs::copy(&path, &target)
+                .with_context(|| format!("failed to copy {}", path.display()))?;
+            written.push(target);
+        }
+    }
+    Ok(())
+}
+
+fn sha3_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha3_256::digest(bytes))
+}
@@ -1,18 +1,23 @@
+use crate::CliResult;
+use crate::output::CommandOutput;
 use clap::Parser;
 
-pub type CliResult = Result<String, String>;
-
 #[derive(Parser, Debug)]
 pub struct VersionTool {}
 
 impl VersionTool {
     pub async fn execute(self) -> CliResult {
-        Ok(format!(
+        let version = env!("CARGO_PKG_VERSION");
+        let git = option_env!("GIT_DESCRIBE").unwrap_or("unknown");
+        let build_date = option_env!("BUILD_DATE").unwrap_or("unknown");
+        let build_target = option_env!("BUILD_TARGET").unwrap_or("unknown");
+        Ok(CommandOutput::new(format!(
             "yeaptor {} (git: {}, build: {} {})",
-            env!("CARGO_PKG_VERSION"),
-            option_env!("GIT_DESCRIBE").unwrap_or("unknown"),
-            option_env!("BUILD_DATE").unwrap_or("unknown"),
-            option_env!("BUILD_TARGET").unwrap_or("unknown")
+            version, git, build_date, build_target
         ))
+        .detail("version", version)
+        .detail("git", git)
+        .detail("build_date", build_date)
+        .detail("build_target", build_target))
     }
 }
@@ -0,0 +1,152 @@
+use aptos_types::account_address::AccountAddress;
+use yeaptor::address_explain::{Derivation, explain_address, render_explanations};
+use yeaptor::config::parse_config;
+use yeaptor_core::address::{object_code_address, resource_account_address};
+
+const CONFIG: &str = r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[networks.testnet]
+yeaptor_address = "0x2"
+
+[named-addresses]
+oracle = "0x30"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{ address_name = "core", path = "core" }]
+
+[[deployments]]
+publisher = "0x20"
+mode = "account"
+packages = [{ address_name = "vault", path = "vault" }]
+
+[[deployments]]
+publisher = "alice"
+mode = "object"
+sequence_number = 7
+packages = [{ address_name = "router", path = "router" }]
+"#;
+
+#[test]
+fn test_explain_named_address() {
+    let config = parse_config(CONFIG).unwrap();
+    let alice = AccountAddress::from_hex_literal("0x10").unwrap();
+
+    let core = explain_address(&config, "core").unwrap();
+    assert_eq!(core.len(), 1);
+    assert_eq!(core[0].location, "deployments[0].packages[0]");
+    assert_eq!(
+        core[0].address,
+        resource_account_address(&alice, b"v1").to_standard_string()
+    );
+    assert_eq!(
+        core[0].derivation,
+        Derivation::ResourceAccount {
+            publisher_name: "alice".to_string(),
+            publisher: alice.to_standard_string(),
+            seed: "v1".to_string(),
+            seed_hex: "0x7631".to_string(),
+        }
+    );
+    let text = render_explanations(&core);
+    assert!(
+        text.starts_with(&format!("core = {}", core[0].address)),
+        "{}",
+        text
+    );
+    assert!(text.contains("seed \"v1\" (0x7631)"), "{}", text);
+    assert!(
+        text.contains("sha3-256(publisher | seed | 0xff)"),
+        "{}",
+        text
+    );
+
+    let router = explain_address(&config, "router").unwrap();
+    assert_eq!(
+        router[0].address,
+        object_code_address(&alice, 7).to_standard_string()
+    );
+    let Derivation::Object {
+        sequence_number,
+        seed_hex,
+        ..
+    } = &router[0].derivation
+    else {
+        panic!("{:?}", router[0].derivation);
+    };
+    assert_eq!(*sequence_number, Some(7));
+    assert!(seed_hex.as_ref().unwrap().ends_with("0800000000000000"));
+
+    let vault = explain_address(&config, "vault").unwrap();
+    assert_eq!(
+        vault[0].address,
+        AccountAddress::from_hex_literal("0x20")
+            .unwrap()
+            .to_standard_string()
+    );
+    assert!(matches!(vault[0].derivation, Derivation::Account { .. }));
+
+    // every network's yeaptor_address
+    let yeaptor = explain_address(&config, "yeaptor_address").unwrap();
+    assert_eq!(
+        yeaptor
+            .iter()
+            .map(|explanation| explanation.location.as_str())
+            .collect::<Vec<_>>(),
+        vec!["yeaptor_address", "networks.testnet.yeaptor_address"]
+    );
+    assert!(yeaptor.iter().all(|e| e.derivation == Derivation::Declared));
+
+    let json = serde_json::to_value(&core[0]).unwrap();
+    assert_eq!(json["derivation"], "resource-account");
+    assert_eq!(json["seed_hex"], "0x7631");
+}
+
+#[test]
+fn test_explain_address_value() {
+    let config = parse_config(CONFIG).unwrap();
+    let by_address = explain_address(&config, "0x10").unwrap();
+    assert_eq!(by_address.len(), 1);
+    assert_eq!(by_address[0].name, "alice");
+    assert_eq!(by_address[0].location, "[publishers]");
+
+    let oracle = explain_address(&config, "0x30").unwrap();
+    assert_eq!(oracle[0].name, "oracle");
+    assert_eq!(oracle[0].location, "[named-addresses]");
+
+    let err = explain_address(&config, "0x99").unwrap_err().to_string();
+    assert!(
+        err.contains("neither declared in nor derived from"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_explain_unknown_name() {
+    let config = parse_config(CONFIG).unwrap();
+    let err = explain_address(&config, "valt").unwrap_err().to_string();
+    assert!(err.contains("did you mean `vault`?"), "{}", err);
+
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "bob"
+seed = "v1"
+packages = [{ address_name = "core", path = "core" }]
+"#,
+    )
+    .unwrap();
+    let err = explain_address(&config, "core").unwrap_err().to_string();
+    assert!(err.contains("deployments[0].packages[0]"), "{}", err);
+    assert!(err.contains("publisher `bob`"), "{}", err);
+}
@@ -0,0 +1,128 @@
+use ed25519_dalek::SigningKey;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+use yeaptor::artifact_signature::{
+    encode_hex, load_private_key, parse_public_key, sign_artifacts, signed_file_paths,
+    verify_artifacts,
+};
+
+fn write_build_output(out_dir: &Path) {
+    fs::create_dir_all(out_dir.join("alice-v1")).unwrap();
+    fs::write(
+        out_dir.join("alice-v1/0-core.package.json"),
+        "{\"core\": 1}",
+    )
+    .unwrap();
+    fs::write(out_dir.join("alice-v1/1-app.package.json"), "{\"app\": 1}").unwrap();
+    fs::write(
+        out_dir.join("manifest.json"),
+        r#"{"packages": [
+            {"order": 0, "name": "core", "file": "alice-v1/0-core.package.json"},
+            {"order": 1, "name": "app", "file": "alice-v1/1-app.package.json"}
+        ]}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_sign_and_verify_artifacts() {
+    let dir = tempdir().unwrap();
+    write_build_output(dir.path());
+    assert_eq!(
+        signed_file_paths(dir.path()).unwrap(),
+        [
+            "manifest.json",
+            "alice-v1/0-core.package.json",
+            "alice-v1/1-app.package.json"
+        ]
+    );
+
+    let key_file = dir.path().join("signer.key");
+    fs::write(&key_file, format!("{}\n", encode_hex(&[7u8; 32]))).unwrap();
+    let key = load_private_key(&key_file).unwrap();
+    let public_key = parse_public_key(&encode_hex(key.verifying_key().as_bytes())).unwrap();
+
+    let signature = sign_artifacts(dir.path(), &key).unwrap();
+    assert_eq!(signature.files.len(), 3);
+    assert_eq!(
+        signature.public_key,
+        encode_hex(key.verifying_key().as_bytes())
+    );
+    assert!(
+        verify_artifacts(dir.path(), &signature, &public_key)
+            .unwrap()
+            .is_empty()
+    );
+
+    // a payload edited after signing
+    fs::write(
+        dir.path().join("alice-v1/1-app.package.json"),
+        "{\"app\": 2}",
+    )
+    .unwrap();
+    let err = verify_artifacts(dir.path(), &signature, &public_key)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        err,
+        "alice-v1/1-app.package.json: changed since it was signed"
+    );
+}
+
+#[test]
+fn test_verify_rejects_other_signers() {
+    let dir = tempdir().unwrap();
+    write_build_output(dir.path());
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    let other = SigningKey::from_bytes(&[8u8; 32]);
+    let signature = sign_artifacts(dir.path(), &key).unwrap();
+
+    let err = verify_artifacts(dir.path(), &signature, &other.verifying_key())
+        .unwrap()
+        .to_string();
+    assert!(err.starts_with("signature.json: signed by 0x"), "{}", err);
+
+    // hashes swapped in after signing
+    let mut forged = signature.clone();
+    forged.files[1].sha3_256 = forged.files[2].sha3_256.clone();
+    let err = verify_artifacts(dir.path(), &forged, &key.verifying_key())
+        .unwrap()
+        .to_string();
+    assert!(
+        err.starts_with("signature.json: signature does not match its file list"),
+        "{}",
+        err
+    );
+
+    assert!(parse_public_key("0x1234").is_err());
+}
+
+#[test]
+fn test_sign_rejects_files_outside_out_dir() {
+    let dir = tempdir().unwrap();
+    let out_dir = dir.path().join("deployments");
+    fs::create_dir_all(&out_dir).unwrap();
+    fs::write(dir.path().join("secret.key"), "0x07").unwrap();
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    for file in [
+        "../secret.key".to_string(),
+        dir.path().join("secret.key").display().to_string(),
+    ] {
+        fs::write(
+            out_dir.join("manifest.json"),
+            serde_json::json!({"packages": [{"file": file}]}).to_string(),
+        )
+        .unwrap();
+        let err = format!("{:#}", signed_file_paths(&out_dir).unwrap_err());
+        assert!(
+            err.ends_with(&format!(
+                "`{}` is not a relative path inside the out dir",
+                file
+            )),
+            "{}",
+            err
+        );
+        assert!(sign_artifacts(&out_dir, &key).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{MetadataBuilder, planned_package};
+use yeaptor::PlannedPackage;
+use yeaptor::audit::{audit_package, check_upgrade_policies, upgrade_policy_name};
+use yeaptor::config::{PolicyLevel, PolicySettings};
+
+#[test]
+fn test_upgrade_policy_name() {
+    assert_eq!(upgrade_policy_name(0), "arbitrary");
+    assert_eq!(upgrade_policy_name(1), "compatible");
+    assert_eq!(upgrade_policy_name(2), "immutable");
+}
+
+/// A package of `deployment` whose metadata has `upgrade_policy`
+fn package(deployment: &str, name: &str, upgrade_policy: u8) -> PlannedPackage {
+    PlannedPackage {
+        deployment: deployment.to_string(),
+        metadata: MetadataBuilder::new(name)
+            .upgrade_policy(upgrade_policy)
+            .bcs(),
+        ..planned_package(0, name)
+    }
+}
+
+#[test]
+fn test_check_upgrade_policies() {
+    let packages = [
+        package("core", "vault", 1),
+        package("core", "oracle", 0),
+        package("periphery", "router", 2),
+    ];
+    let warnings = check_upgrade_policies(&packages, &PolicySettings::default()).unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].starts_with("oracle: built with upgrade_policy = \"arbitrary\""));
+    assert_eq!(
+        warnings[1],
+        "core: packages have different upgrade policies: vault (compatible), oracle (arbitrary)"
+    );
+
+    let strict = PolicySettings {
+        arbitrary_upgrade: Some(PolicyLevel::Error),
+        upgrade_policy_mismatch: Some(PolicyLevel::Allow),
+    };
+    let err = check_upgrade_policies(&packages, &strict).unwrap_err();
+    let diagnostics = err
+        .downcast_ref::<yeaptor::diagnostics::Diagnostics>()
+        .unwrap();
+    assert_eq!(diagnostics.0.len(), 1);
+    assert_eq!(diagnostics.0[0].location, "oracle");
+
+    let compatible = [package("core", "vault", 1), package("core", "oracle", 1)];
+    assert!(
+        check_upgrade_policies(&compatible, &strict)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_audit_invalid_package() {
+    let package = PlannedPackage {
+        metadata: vec![0xff],
+        ..planned_package(0, "core")
+    };
+    assert_eq!(
+        audit_package(&package).unwrap_err().to_string(),
+        "package metadata of core does not deserialize"
+    );
+}
@@ -0,0 +1,41 @@
+use std::fs;
+use tempfile::Builder;
+use yeaptor::bigquery::{BigQueryField, bigquery_dataset_manifest, bigquery_table_schema};
+use yeaptor::db_schema::load_db_schema;
+
+#[test]
+fn test_bigquery_export() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+small = { column_type = "u32" }
+amount = { column_type = "u64", is_nullable = true }
+shares = { column_type = "u128", is_index = true }
+huge = { column_type = "u256" }
+tags = { column_type = "string", is_vec = true, is_index = true }
+timestamp = { column_type = "timestamp", type = "transaction_metadata", is_index = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let tables = load_db_schema(file.path()).unwrap();
+
+    let fields = bigquery_table_schema(&tables["borrow_position"]).unwrap();
+    let field =
+        |name: &str| -> BigQueryField { fields.iter().find(|f| f.name == name).unwrap().clone() };
+    assert_eq!(field("small").field_type, "INT64");
+    assert_eq!(field("amount").field_type, "NUMERIC");
+    assert_eq!(field("amount").mode, "NULLABLE");
+    assert_eq!(field("shares").field_type, "BIGNUMERIC");
+    assert_eq!(field("huge").field_type, "STRING");
+    assert!(field("huge").description.is_some());
+    assert_eq!(field("tags").mode, "REPEATED");
+    assert_eq!(field("owner").mode, "REQUIRED");
+    assert_eq!(field("timestamp").field_type, "TIMESTAMP");
+
+    let manifest = bigquery_dataset_manifest(&tables, Some("yeap".to_string()));
+    let table = &manifest.tables["borrow_position"];
+    assert_eq!(table.schema_file, "borrow_position.json");
+    assert_eq!(table.primary_key, vec!["owner"]);
+    // repeated fields are not clustered on
+    assert_eq!(table.clustering, vec!["owner", "shares", "timestamp"]);
+}
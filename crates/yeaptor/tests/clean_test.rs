@@ -0,0 +1,114 @@
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::clean::{clean_targets, remove_targets};
+use yeaptor::config::parse_config;
+
+#[test]
+fn test_clean_targets_and_remove() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    let out_dir = dir.path().join("deployments");
+    fs::create_dir_all(core.join("build/Core")).unwrap();
+    fs::create_dir_all(out_dir.join("alice-v1")).unwrap();
+    fs::create_dir_all(out_dir.join("events")).unwrap();
+    fs::create_dir_all(out_dir.join("payloads/alice")).unwrap();
+    fs::write(out_dir.join("alice-v1/1-core.json"), "{}").unwrap();
+    fs::write(out_dir.join("events/Core.event.json"), "[]").unwrap();
+    fs::write(out_dir.join("payloads/alice/core.json"), "{}").unwrap();
+    fs::write(out_dir.join("addresses.toml"), "[addresses]\n").unwrap();
+    fs::write(
+        out_dir.join("manifest.json"),
+        r#"{"packages": [{"file": "alice-v1/1-core.json"}, {"file": "payloads/alice/core.json"}]}"#,
+    )
+    .unwrap();
+    fs::write(out_dir.join("notes.md"), "kept").unwrap();
+    let cache = dir.path().join("move-cache");
+    fs::create_dir_all(&cache).unwrap();
+    let missing_cache = dir.path().join("missing-cache");
+
+    let config = parse_config(&format!(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{{ address_name = "core", path = "{}" }}]
+"#,
+        core.display()
+    ))
+    .unwrap();
+
+    let targets = clean_targets(&config, &out_dir, &[cache.clone(), missing_cache]);
+    assert_eq!(
+        targets,
+        vec![
+            core.join("build"),
+            out_dir.join("addresses.toml"),
+            out_dir.join("alice-v1"),
+            out_dir.join("events"),
+            out_dir.join("manifest.json"),
+            out_dir.join("payloads/alice/core.json"),
+            cache.clone(),
+        ]
+    );
+
+    remove_targets(&targets, &out_dir, &[core.clone()]).unwrap();
+    assert!(targets.iter().all(|path| !path.exists()));
+    // directories emptied by the removal go too, other files of the out dir stay
+    assert!(!out_dir.join("payloads").exists());
+    assert!(out_dir.join("notes.md").exists());
+    assert!(core.exists());
+    assert!(clean_targets(&config, &out_dir, &[cache]).is_empty());
+}
+
+#[test]
+fn test_clean_targets_stay_in_out_dir() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().join("project");
+    let out_dir = project.join("deployments");
+    fs::create_dir_all(&out_dir).unwrap();
+    fs::write(project.join("yeaptor.toml"), "kept").unwrap();
+    fs::write(dir.path().join("secret.txt"), "kept").unwrap();
+    fs::write(
+        out_dir.join("manifest.json"),
+        format!(
+            r#"{{"packages": [{{"file": "../../secret.txt"}}, {{"file": "{}"}}, {{"file": ".."}}]}}"#,
+            dir.path().join("secret.txt").display()
+        ),
+    )
+    .unwrap();
+
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+name = ".."
+publisher = "0x10"
+seed = "v1"
+packages = []
+"#,
+    )
+    .unwrap();
+
+    let targets = clean_targets(&config, &out_dir, &[]);
+    assert_eq!(targets, vec![out_dir.join("manifest.json")]);
+    remove_targets(&targets, &out_dir, &[]).unwrap();
+    assert!(project.join("yeaptor.toml").exists());
+    assert!(dir.path().join("secret.txt").exists());
+
+    assert!(remove_targets(&[project.clone()], &out_dir, &[]).is_err());
+    assert!(project.join("yeaptor.toml").exists());
+    // `..` of the out dir is the project, holding the config
+    let config_file = project.join("yeaptor.toml");
+    let elsewhere = dir.path().join("other");
+    assert!(remove_targets(&[out_dir.join("..")], &elsewhere, &[config_file.clone()]).is_err());
+    assert!(remove_targets(&[config_file.clone()], &elsewhere, &[config_file]).is_err());
+    assert!(project.join("yeaptor.toml").exists());
+}
@@ -0,0 +1,120 @@
+//! Fixtures shared by the integration tests; each test crate uses only some of them
+#![allow(dead_code)]
+
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_framework::zip_metadata_str;
+use aptos_types::account_address::AccountAddress;
+use yeaptor::PlannedPackage;
+use yeaptor::config::DeploymentMode;
+
+/// A `PackageMetadata` written through its BCS layout: by default with the compatible policy,
+/// upgrade number 0 and source digest `C0FFEE`, without manifest, modules, dependencies or
+/// extension
+pub struct MetadataBuilder {
+    name: String,
+    upgrade_policy: u8,
+    upgrade_number: u64,
+    manifest: String,
+    // (name, source, source map)
+    modules: Vec<(String, String, Vec<u8>)>,
+    deps: Vec<(AccountAddress, String)>,
+}
+
+impl MetadataBuilder {
+    pub fn new(name: &str) -> Self {
+        MetadataBuilder {
+            name: name.to_string(),
+            upgrade_policy: 1,
+            upgrade_number: 0,
+            manifest: String::new(),
+            modules: Vec::new(),
+            deps: Vec::new(),
+        }
+    }
+
+    pub fn upgrade_policy(mut self, upgrade_policy: u8) -> Self {
+        self.upgrade_policy = upgrade_policy;
+        self
+    }
+
+    pub fn upgrade_number(mut self, upgrade_number: u64) -> Self {
+        self.upgrade_number = upgrade_number;
+        self
+    }
+
+    /// Zipped Move.toml; empty stays empty
+    pub fn manifest(mut self, manifest: &str) -> Self {
+        self.manifest = manifest.to_string();
+        self
+    }
+
+    /// A module with its zipped `source` (empty stays empty) and `source_map`
+    pub fn module(mut self, name: &str, source: &str, source_map: Vec<u8>) -> Self {
+        self.modules
+            .push((name.to_string(), source.to_string(), source_map));
+        self
+    }
+
+    pub fn dependency(mut self, account: AccountAddress, package_name: &str) -> Self {
+        self.deps.push((account, package_name.to_string()));
+        self
+    }
+
+    pub fn bcs(&self) -> Vec<u8> {
+        let modules = self
+            .modules
+            .iter()
+            .map(|(name, source, source_map)| {
+                (
+                    name.as_str(),
+                    zip(source),
+                    source_map.clone(),
+                    Option::<u8>::None,
+                )
+            })
+            .collect::<Vec<_>>();
+        bcs::to_bytes(&(
+            self.name.as_str(),
+            self.upgrade_policy,
+            self.upgrade_number,
+            "C0FFEE",
+            zip(&self.manifest),
+            modules,
+            &self.deps,
+            Option::<u8>::None,
+        ))
+        .unwrap()
+    }
+
+    pub fn build(&self) -> PackageMetadata {
+        bcs::from_bytes(&self.bcs()).unwrap()
+    }
+}
+
+fn zip(text: &str) -> Vec<u8> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        zip_metadata_str(text).unwrap()
+    }
+}
+
+/// Package `name` at `order` of deployment `alice-v1`, published by `0x10` from its own account,
+/// with the default metadata of `MetadataBuilder` and no modules or events
+pub fn planned_package(order: usize, name: &str) -> PlannedPackage {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    PlannedPackage {
+        order,
+        deployment: "alice-v1".to_string(),
+        name: name.to_string(),
+        mode: DeploymentMode::Account,
+        publisher,
+        seed: String::new(),
+        account: publisher,
+        object_upgrade: false,
+        payload: serde_json::Value::Null,
+        metadata: MetadataBuilder::new(name).bcs(),
+        modules: Vec::new(),
+        events: Vec::new(),
+    }
+}
@@ -0,0 +1,75 @@
+use clap::{Command, CommandFactory};
+use clap_complete::Shell;
+use yeaptor::Yeaptor;
+use yeaptor::tools::completions::{ConfigValues, complete_config_values};
+
+fn values() -> ConfigValues {
+    ConfigValues {
+        package_dirs: vec!["./move/core".to_string(), "./move/oracle".to_string()],
+        packages: vec!["core".to_string(), "YeapCore".to_string()],
+        tags: vec!["core".to_string(), "oracle".to_string()],
+    }
+}
+
+fn script(shell: Shell, values: &ConfigValues) -> String {
+    let mut command = complete_config_values(Yeaptor::command(), values);
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, "yeaptor", &mut script);
+    String::from_utf8(script).unwrap()
+}
+
+/// Values offered for `arg` of the subcommand at `path`
+fn offered(command: &Command, path: &[&str], arg: &str) -> Vec<String> {
+    let subcommand = path.iter().fold(command, |command, name| {
+        command.find_subcommand(name).unwrap()
+    });
+    subcommand
+        .get_arguments()
+        .find(|candidate| candidate.get_id() == arg)
+        .unwrap()
+        .get_possible_values()
+        .iter()
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+#[test]
+fn test_completions_include_config_values() {
+    let values = values();
+    let bash = script(Shell::Bash, &values);
+    assert!(bash.contains("processor"));
+    assert!(bash.contains("./move/core ./move/oracle"));
+    let fish = script(Shell::Fish, &values);
+    assert!(fish.contains("./move/core"));
+
+    let command = complete_config_values(Yeaptor::command(), &values);
+    for path in [
+        &["deployment", "build"][..],
+        &["deployment", "export-unsigned"],
+        &["deployment", "cost-report"],
+        &["event", "generate"],
+        &["event", "list"],
+    ] {
+        assert_eq!(
+            offered(&command, path, "package_dir"),
+            values.package_dirs,
+            "{:?}",
+            path
+        );
+    }
+    for path in [
+        &["deployment", "build"][..],
+        &["deployment", "export-unsigned"],
+        &["deployment", "cost-report"],
+        &["deployment", "list"],
+    ] {
+        assert_eq!(offered(&command, path, "tags"), values.tags, "{:?}", path);
+    }
+    assert_eq!(
+        offered(&command, &["deployment", "diff-source"], "package"),
+        values.packages
+    );
+
+    // Without a config, --package-dir completes to any directory
+    assert!(!script(Shell::Bash, &ConfigValues::default()).contains("./move/core"));
+}
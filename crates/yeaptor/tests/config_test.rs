@@ -1,8 +1,14 @@
-use aptos_types::account_address::AccountAddress;
+use aptos_types::account_address::{AccountAddress, create_resource_address};
+use aptos_types::object_address::create_object_code_deployment_address;
 use std::fs;
 use std::path::Path;
 use tempfile::NamedTempFile;
-use yeaptor::config::load_config;
+use yeaptor::config::{
+    DeployFunction, DeploymentMode, IncludedArtifacts, KeySource, PolicyLevel, config_schema,
+    load_config, parse_config, set_deployment_seed,
+};
+use yeaptor_core::address::{object_code_address, resource_account_address};
+use yeaptor_core::diagnostics::Diagnostics;
 
 #[test]
 fn test_load_valid_config() {
@@ -47,12 +53,12 @@ packages = [
     // Test publishers
     assert_eq!(config.publishers.len(), 2);
     assert_eq!(
-        config.publishers.get("test-publisher").unwrap(),
-        &AccountAddress::from_hex_literal("0x10").unwrap()
+        config.publishers.get("test-publisher").unwrap().address,
+        AccountAddress::from_hex_literal("0x10").unwrap()
     );
     assert_eq!(
-        config.publishers.get("another-publisher").unwrap(),
-        &AccountAddress::from_hex_literal("0x20").unwrap()
+        config.publishers.get("another-publisher").unwrap().address,
+        AccountAddress::from_hex_literal("0x20").unwrap()
     );
 
     // Test named addresses
@@ -67,7 +73,7 @@ packages = [
 
     let first_deployment = &config.deployments[0];
     assert_eq!(first_deployment.publisher, "test-publisher");
-    assert_eq!(first_deployment.seed, "test-seed");
+    assert_eq!(first_deployment.seed(), "test-seed");
     assert_eq!(first_deployment.packages.len(), 2);
     assert_eq!(first_deployment.packages[0].address_name, "test_package");
     assert_eq!(
@@ -90,7 +96,7 @@ packages = [
 
     let second_deployment = &config.deployments[1];
     assert_eq!(second_deployment.publisher, "another-publisher");
-    assert_eq!(second_deployment.seed, "another-seed");
+    assert_eq!(second_deployment.seed(), "another-seed");
     assert_eq!(second_deployment.packages.len(), 1);
     assert_eq!(second_deployment.packages[0].address_name, "third_package");
     assert_eq!(
@@ -177,8 +183,8 @@ test-publisher = "0x10"
     // Publishers section is present
     assert_eq!(config.publishers.len(), 1);
     assert_eq!(
-        config.publishers.get("test-publisher").unwrap(),
-        &AccountAddress::from_hex_literal("0x10").unwrap()
+        config.publishers.get("test-publisher").unwrap().address,
+        AccountAddress::from_hex_literal("0x10").unwrap()
     );
 
     // Other sections should default to empty
@@ -300,8 +306,8 @@ test-address = "0x00000000000000000000000000000000000000000000000000000000000000
         .unwrap()
     );
     assert_eq!(
-        config.publishers.get("test-publisher").unwrap(),
-        &AccountAddress::from_hex_literal(
+        config.publishers.get("test-publisher").unwrap().address,
+        AccountAddress::from_hex_literal(
             "0x0000000000000000000000000000000000000000000000000000000000000010"
         )
         .unwrap()
@@ -482,3 +488,1006 @@ packages = [
     let error_msg = result.unwrap_err().to_string();
     assert!(error_msg.contains("invalid") || error_msg.contains("Invalid"));
 }
+
+#[test]
+fn test_processor_section() {
+    let config_content = r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[processor]
+spec_creator = "indexer@yeap.finance"
+spec_name = "yeap-processor"
+ending_version = 2000000
+"#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    fs::write(temp_file.path(), config_content).unwrap();
+
+    let config = load_config(temp_file.path()).unwrap();
+
+    assert_eq!(
+        config.processor.spec_creator.as_deref(),
+        Some("indexer@yeap.finance")
+    );
+    assert_eq!(
+        config.processor.spec_name.as_deref(),
+        Some("yeap-processor")
+    );
+    // Not set, so the version is carried over from the previous generated file
+    assert!(config.processor.spec_version.is_none());
+    assert_eq!(config.processor.ending_version, Some(2000000));
+}
+
+#[test]
+fn test_processor_section_defaults() {
+    let config_content = r#"
+format_version = 1
+yeaptor_address = "0x1"
+"#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    fs::write(temp_file.path(), config_content).unwrap();
+
+    let config = load_config(temp_file.path()).unwrap();
+
+    assert!(config.processor.spec_creator.is_none());
+    assert!(config.processor.spec_name.is_none());
+    assert!(config.processor.spec_version.is_none());
+    assert!(config.processor.ending_version.is_none());
+}
+
+#[test]
+fn test_processor_inline_tables() {
+    let config_content = r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[processor.tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    fs::write(temp_file.path(), config_content).unwrap();
+
+    let config = load_config(temp_file.path()).unwrap();
+
+    let table = config.processor.tables.get("borrow_market").unwrap();
+    assert!(table.columns.get("market").unwrap().is_primary_key);
+}
+
+#[test]
+fn test_config_schema() {
+    let schema = config_schema();
+    assert_eq!(schema["title"], "YeaptorConfig");
+    let properties = &schema["properties"];
+    for key in [
+        "format_version",
+        "yeaptor_address",
+        "publishers",
+        "named-addresses",
+        "deployments",
+        "processor",
+    ] {
+        assert!(properties.get(key).is_some(), "missing {}", key);
+    }
+    assert_eq!(properties["yeaptor_address"]["type"], "string");
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&"format_version".into()));
+    assert!(!required.contains(&"deployments".into()));
+
+    let definitions = &schema["definitions"];
+    assert!(
+        definitions["ProcessorSettings"]["properties"]
+            .get("standard-templates")
+            .is_some()
+    );
+    assert_eq!(
+        definitions["ConflictStrategy"]["enum"],
+        serde_json::json!(["insert", "upsert", "ignore-duplicates"])
+    );
+    assert_eq!(
+        definitions["StandardTemplates"]["additionalProperties"],
+        false
+    );
+}
+
+#[test]
+fn test_resource_account_address_matches_aptos() {
+    let publisher = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    for seed in ["", "v1", "yeaptor-deployment-seed"] {
+        assert_eq!(
+            resource_account_address(&publisher, seed.as_bytes()),
+            create_resource_address(publisher, seed.as_bytes())
+        );
+    }
+}
+
+#[test]
+fn test_object_code_address_matches_aptos() {
+    // the aptos CLI derives the object from the sequence number after the publishing one
+    let publisher = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    for sequence_number in [0, 1, 41] {
+        assert_eq!(
+            object_code_address(&publisher, sequence_number),
+            create_object_code_deployment_address(publisher, sequence_number + 1)
+        );
+    }
+}
+
+#[test]
+fn test_included_artifacts_names() {
+    for name in ["none", "sparse", "all"] {
+        let artifacts: IncludedArtifacts = name.parse().unwrap();
+        assert_eq!(artifacts.to_string(), name);
+    }
+    assert!("everything".parse::<IncludedArtifacts>().is_err());
+}
+
+fn load_str(config_content: &str) -> anyhow::Result<yeaptor::config::YeaptorConfig> {
+    let temp_file = NamedTempFile::new().unwrap();
+    fs::write(temp_file.path(), config_content).unwrap();
+    load_config(temp_file.path())
+}
+
+#[test]
+fn test_deployment_collisions() {
+    // The same publisher and seed twice, and an alias of the same account with that seed
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+alice-ledger = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{ address_name = "oracle", path = "packages/oracle" }]
+
+[[deployments]]
+publisher = "alice-ledger"
+seed = "v1"
+packages = [{ address_name = "vault", path = "packages/vault" }]
+"#,
+    )
+    .unwrap_err()
+    .to_string();
+    let account = create_resource_address(AccountAddress::from_hex_literal("0x10").unwrap(), b"v1")
+        .to_standard_string();
+    assert_eq!(
+        err,
+        format!(
+            "2 problems:\n  \
+             - deployments[1]: publisher `alice` and seed `v1` derive resource account {account}, like deployments[0] (publisher `alice`, seed `v1`)\n  \
+             - deployments[2]: publisher `alice-ledger` and seed `v1` derive resource account {account}, like deployments[0] (publisher `alice`, seed `v1`)"
+        )
+    );
+}
+
+#[test]
+fn test_named_address_collisions() {
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[named-addresses]
+oracle = "0x99"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [
+    { address_name = "core", path = "packages/core" },
+    { address_name = "core", path = "packages/core-extensions" },
+]
+
+[[deployments]]
+publisher = "alice"
+seed = "v2"
+packages = [
+    { address_name = "core", path = "packages/core-v2" },
+    { address_name = "oracle", path = "packages/oracle" },
+]
+"#,
+    )
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(
+        diagnostics
+            .0
+            .iter()
+            .map(|diagnostic| diagnostic.location.as_str())
+            .collect::<Vec<_>>(),
+        ["deployments[1].packages[0]", "deployments[1].packages[1]"]
+    );
+    assert!(
+        diagnostics.0[0]
+            .message
+            .starts_with("named address `core` is 0x")
+    );
+    assert!(
+        diagnostics.0[0]
+            .message
+            .ends_with(" in deployments[0].packages[0]")
+    );
+    assert_eq!(
+        diagnostics.0[1].message,
+        format!(
+            "named address `oracle` is {} here but {} in [named-addresses]",
+            create_resource_address(AccountAddress::from_hex_literal("0x10").unwrap(), b"v2")
+                .to_standard_string(),
+            AccountAddress::from_hex_literal("0x99")
+                .unwrap()
+                .to_standard_string()
+        )
+    );
+}
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[named_addresses]
+oracle = "0x30"
+"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostics>().unwrap().to_string(),
+        "line 5: unknown key `named_addresses`, did you mean `named-addresses`?"
+    );
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+packages = [{ address_name = "core", path = "core", include_artifact = "none" }]
+"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 8: unknown key `include_artifact`, did you mean `include_artifacts`?"
+    );
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[processor]
+network = "mainnet"
+"#,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("line 6: unknown key `network`, expected one of `spec_creator`")
+    );
+}
+
+#[test]
+fn test_deployment_dir_names() {
+    let config = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+name = "oracle/v1"
+publisher = "0x10"
+seed = "oracle v1"
+packages = [{ address_name = "oracle", path = "packages/oracle" }]
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.deployments[0].dir_name(), "alice-v1");
+    assert_eq!(config.deployments[1].dir_name(), "oracle_v1");
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+name = "core"
+publisher = "0x10"
+seed = "v1"
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+name = "core"
+publisher = "0x10"
+seed = "v2"
+packages = [{ address_name = "oracle", path = "packages/oracle" }]
+"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "deployments[1]: output directory `core` is also the one of deployments[0]; give one of them another `name`"
+    );
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+name = ".."
+publisher = "0x10"
+seed = "v1"
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+name = ""
+publisher = "0x10"
+seed = "v2"
+packages = [{ address_name = "oracle", path = "packages/oracle" }]
+
+[[deployments]]
+name = "."
+publisher = "0x10"
+seed = "v3"
+packages = [{ address_name = "vault", path = "packages/vault" }]
+"#,
+    )
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.0.len(), 3);
+    assert_eq!(diagnostics.0[0].location, "deployments[0]");
+    assert_eq!(
+        diagnostics.0[0].message,
+        "output directory `..` is not a directory of its own under the output directory; give the deployment another `name`"
+    );
+    assert_eq!(diagnostics.0[1].location, "deployments[1]");
+    assert_eq!(diagnostics.0[2].location, "deployments[2]");
+}
+
+#[test]
+fn test_deployment_tags() {
+    let config = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+tags = ["core"]
+packages = [
+    { address_name = "core", path = "packages/core" },
+    { address_name = "oracle", path = "packages/oracle", tags = ["periphery"] },
+]
+
+[[deployments]]
+publisher = "0x10"
+seed = "v2"
+packages = [{ address_name = "vault", path = "packages/vault", tags = ["periphery"] }]
+"#,
+    )
+    .unwrap();
+    let selected = |tags: &[&str]| {
+        let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        config
+            .deployments
+            .iter()
+            .flat_map(|d| {
+                d.packages
+                    .iter()
+                    .filter(|p| d.selects(p, &tags))
+                    .map(|p| p.address_name.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(selected(&[]), ["core", "oracle", "vault"]);
+    assert_eq!(selected(&["core"]), ["core", "oracle"]);
+    assert_eq!(selected(&["periphery"]), ["oracle", "vault"]);
+
+    config
+        .check_tags(&["core".to_string(), "periphery".to_string()])
+        .unwrap();
+    assert_eq!(
+        config
+            .check_tags(&["peripheri".to_string()])
+            .unwrap_err()
+            .to_string(),
+        "unknown tag `peripheri`, did you mean `periphery`?"
+    );
+    assert_eq!(
+        config
+            .check_tags(&["governance".to_string()])
+            .unwrap_err()
+            .to_string(),
+        "unknown tag `governance`; known tags: core, periphery"
+    );
+}
+
+#[test]
+fn test_account_mode() {
+    let config = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+mode = "account"
+publisher = "alice"
+packages = [{ address_name = "utils", path = "packages/utils" }]
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{ address_name = "core", path = "packages/core" }]
+"#,
+    )
+    .unwrap();
+    let alice = AccountAddress::from_hex_literal("0x10").unwrap();
+    assert_eq!(config.deployments[0].mode(), DeploymentMode::Account);
+    assert_eq!(
+        config.deployments[1].mode(),
+        DeploymentMode::ResourceAccount
+    );
+    assert_eq!(
+        config.deployment_addresses(),
+        [Some(alice), Some(create_resource_address(alice, b"v1"))]
+    );
+    assert_eq!(config.deployments[0].dir_name(), "alice");
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+mode = "account"
+publisher = "0x10"
+seed = "v1"
+packages = [{ address_name = "utils", path = "packages/utils" }]
+
+[[deployments]]
+publisher = "0x10"
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+name = "more-utils"
+mode = "account"
+publisher = "0x10"
+packages = [{ address_name = "more_utils", path = "packages/more-utils" }]
+"#,
+    )
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    let messages = diagnostics
+        .0
+        .iter()
+        .map(|d| format!("{}: {}", d.location, d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].starts_with("deployments[0]: `seed` is not used with mode = \"account\""));
+    assert!(messages[1].starts_with("deployments[1]: missing `seed`"));
+    assert!(
+        messages[2].starts_with("deployments[2]: publisher `0x10` publishes to its own account")
+    );
+    assert!(messages[2].ends_with("like deployments[0] (publisher `0x10`, mode `account`)"));
+}
+
+#[test]
+fn test_object_mode() {
+    let config = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+mode = "object"
+publisher = "alice"
+sequence_number = 3
+packages = [{ address_name = "vault", path = "packages/vault" }]
+
+[[deployments]]
+mode = "object"
+publisher = "alice"
+object_address = "0xc0de"
+packages = [{ address_name = "oracle", path = "packages/oracle" }]
+"#,
+    )
+    .unwrap();
+    let alice = AccountAddress::from_hex_literal("0x10").unwrap();
+    let object = AccountAddress::from_hex_literal("0xc0de").unwrap();
+    assert_eq!(config.deployments[0].mode(), DeploymentMode::Object);
+    assert_eq!(
+        config.deployment_addresses(),
+        [Some(object_code_address(&alice, 3)), Some(object)]
+    );
+    assert_eq!(config.deployments[0].dir_name(), "alice-3");
+    assert_eq!(
+        config.deployments[1].dir_name(),
+        object.to_standard_string()
+    );
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+mode = "object"
+publisher = "0x10"
+packages = [
+    { address_name = "vault", path = "packages/vault" },
+    { address_name = "oracle", path = "packages/oracle" },
+]
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+sequence_number = 3
+packages = [{ address_name = "core", path = "packages/core" }]
+"#,
+    )
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    let messages = diagnostics
+        .0
+        .iter()
+        .map(|d| format!("{}: {}", d.location, d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].starts_with("deployments[0]: mode = \"object\" needs `sequence_number`"));
+    assert!(messages[1].starts_with(
+        "deployments[0]: an object deployment publishes one package, but this one has 2"
+    ));
+    assert_eq!(
+        messages[2],
+        "deployments[1]: `sequence_number` and `object_address` are only used with mode = \"object\", not \"resource-account\""
+    );
+}
+
+#[test]
+fn test_set_deployment_seed() {
+    let source = r#"format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1" # replaced
+packages = []
+
+[[deployments]]
+publisher = "0x20"  # alice
+packages = []
+"#;
+    assert_eq!(
+        set_deployment_seed(source, 0, "v232").unwrap(),
+        source.replace("seed = \"v1\"", "seed = \"v232\"")
+    );
+    // a deployment without a seed gets one on the line after its publisher
+    let updated = set_deployment_seed(source, 1, "say \"hi\"").unwrap();
+    assert!(updated.contains("publisher = \"0x20\"  # alice\nseed = "));
+    assert_eq!(
+        parse_config(&updated).unwrap().deployments[1].seed(),
+        "say \"hi\""
+    );
+    assert_eq!(
+        set_deployment_seed(source, 2, "v1")
+            .unwrap_err()
+            .to_string(),
+        "no deployments[2]; the config has 2 deployments"
+    );
+
+    let inline = r#"format_version = 1
+yeaptor_address = "0x1"
+deployments = [{ publisher = "0x10", packages = [] }]
+"#;
+    assert!(set_deployment_seed(inline, 0, "v1").is_err());
+}
+
+#[test]
+fn test_networks() {
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[networks.testnet]
+yeaptor_address = "0xb"
+
+[networks.fork]
+yeaptor_address = "0xc"
+deploy_module = "fork_deployment"
+deploy_function = "deploy_package"
+"#,
+    )
+    .unwrap();
+    config.validate().unwrap();
+    let a = AccountAddress::from_hex_literal("0xa").unwrap();
+    assert_eq!(config.deploy_function(), DeployFunction::new(a));
+
+    let testnet = config.clone().for_network("testnet").unwrap();
+    assert_eq!(
+        testnet.deploy_function().to_string(),
+        format!(
+            "{}::ra_code_deployment::deploy",
+            AccountAddress::from_hex_literal("0xb")
+                .unwrap()
+                .to_standard_string()
+        )
+    );
+    let fork = config
+        .clone()
+        .for_network("fork")
+        .unwrap()
+        .deploy_function();
+    assert_eq!(fork.module, "fork_deployment");
+    assert_eq!(fork.function, "deploy_package");
+
+    assert_eq!(
+        config
+            .clone()
+            .for_network("testnte")
+            .unwrap_err()
+            .to_string(),
+        "unknown network `testnte`, did you mean `testnet`?"
+    );
+    assert_eq!(
+        config.for_network("mainnet").unwrap_err().to_string(),
+        "unknown network `mainnet`; known networks: fork, testnet"
+    );
+
+    let err = load_str(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+deploy_module = "ra-code"
+
+[networks.fork]
+deploy_function = "1deploy"
+"#,
+    )
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(
+        diagnostics
+            .0
+            .iter()
+            .map(|d| format!("{}: {}", d.location, d.message))
+            .collect::<Vec<_>>(),
+        [
+            "deploy_module: `ra-code` is not a Move identifier",
+            "networks.fork.deploy_function: `1deploy` is not a Move identifier"
+        ]
+    );
+}
+
+#[test]
+fn test_policy_settings() {
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[policy]
+upgrade_policy_mismatch = "allow"
+
+[networks.mainnet.policy]
+arbitrary_upgrade = "error"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.policy.arbitrary_upgrade(), PolicyLevel::Warn);
+    assert_eq!(config.policy.upgrade_policy_mismatch(), PolicyLevel::Allow);
+
+    let mainnet = config.for_network("mainnet").unwrap();
+    assert_eq!(mainnet.policy.arbitrary_upgrade(), PolicyLevel::Error);
+    assert_eq!(mainnet.policy.upgrade_policy_mismatch(), PolicyLevel::Allow);
+
+    let unknown_level = r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[policy]
+arbitrary_upgrade = "deny"
+"#;
+    assert!(parse_config(unknown_level).is_err());
+}
+
+#[test]
+fn test_dependency_allowlists() {
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[policy]
+allowed_git_sources = ["https://github.com/aptos-labs/", "https://github.com/pyth-network/pyth-crosschain.git"]
+
+[networks.mainnet.policy]
+allowed_dependency_addresses = ["0x1", "0x3", "0x4"]
+"#,
+    )
+    .unwrap();
+    let policy = &config.policy;
+    assert!(policy.allows_dependency_address(&AccountAddress::from_hex_literal("0x7").unwrap()));
+    assert!(policy.allows_git_source("https://github.com/aptos-labs/aptos-framework.git"));
+    assert!(policy.allows_git_source("https://github.com/pyth-network/pyth-crosschain"));
+    assert!(policy.allows_git_source("https://github.com/pyth-network/pyth-crosschain.git/"));
+    assert!(!policy.allows_git_source("https://github.com/aptos-labs-evil/aptos-framework.git"));
+    assert!(!policy.allows_git_source("https://github.com/pyth-network/pyth-crosschain-fork"));
+
+    let mainnet = config.for_network("mainnet").unwrap();
+    let policy = &mainnet.policy;
+    assert!(policy.allows_dependency_address(&AccountAddress::ONE));
+    assert!(policy.allows_dependency_address(&AccountAddress::from_hex_literal("0x4").unwrap()));
+    assert!(!policy.allows_dependency_address(&AccountAddress::from_hex_literal("0x7").unwrap()));
+    assert!(!policy.allows_git_source("https://gitlab.com/someone/move-stdlib"));
+}
+
+#[test]
+fn test_package_path_patterns() {
+    let dir = tempfile::tempdir().unwrap();
+    let markets = dir.path().join("packages/markets");
+    for (dir_name, package) in [("eth-usdc", "EthUsdcMarket"), ("apt-usdc", "AptUsdcMarket")] {
+        fs::create_dir_all(markets.join(dir_name)).unwrap();
+        fs::write(
+            markets.join(dir_name).join("Move.toml"),
+            format!("[package]\nname = \"{}\"\n", package),
+        )
+        .unwrap();
+    }
+    // neither a package nor visible: skipped
+    fs::create_dir_all(markets.join("shared")).unwrap();
+    fs::create_dir_all(markets.join(".cache")).unwrap();
+    fs::write(
+        markets.join(".cache/Move.toml"),
+        "[package]\nname = \"Cache\"\n",
+    )
+    .unwrap();
+
+    let config_file = dir.path().join("yeaptor.toml");
+    let write_config = |packages: &str| {
+        fs::write(
+            &config_file,
+            format!(
+                r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "markets"
+packages = [
+    {}
+]
+"#,
+                packages
+            ),
+        )
+        .unwrap();
+    };
+
+    write_config(&format!(
+        "{{ address_name = 'market_{{dir}}', path = '{}/*', tags = ['markets'] }}",
+        markets.display()
+    ));
+    let config = load_config(&config_file).unwrap();
+    let packages = &config.deployments[0].packages;
+    assert_eq!(
+        packages
+            .iter()
+            .map(|p| (p.address_name.as_str(), p.path.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("market_apt_usdc", markets.join("apt-usdc")),
+            ("market_eth_usdc", markets.join("eth-usdc")),
+        ]
+    );
+    assert!(packages.iter().all(|p| p.tags == ["markets"]));
+
+    write_config(&format!(
+        "{{ address_name = '{{package}}', path = '{}/eth-*' }}",
+        markets.display()
+    ));
+    let config = load_config(&config_file).unwrap();
+    assert_eq!(
+        config.deployments[0].packages[0].address_name,
+        "EthUsdcMarket"
+    );
+    assert_eq!(config.deployments[0].packages.len(), 1);
+
+    write_config(&format!(
+        "{{ address_name = 'market', path = '{}/btc-?' }}",
+        markets.display()
+    ));
+    let err = load_config(&config_file).unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.0[0].location, "deployments[0].packages[0]");
+    assert!(
+        diagnostics.0[0]
+            .message
+            .contains("matches no directory with a Move.toml"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_package_git_sources() {
+    let mut config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "s"
+packages = [
+    { address_name = "oracle", git = { url = "https://github.com/yeap-finance/oracle.git", rev = "3f2a9c1", subdir = "move/oracle" } },
+    { address_name = "core", path = "packages/core" },
+]
+"#,
+    )
+    .unwrap();
+    let cache = Path::new("/tmp/yeaptor-git");
+    config.resolve_git_sources(cache).unwrap();
+    let oracle = &config.deployments[0].packages[0];
+    let checkout = oracle.git.as_ref().unwrap().checkout_dir(cache);
+    assert!(
+        checkout
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("oracle-")
+    );
+    assert_eq!(oracle.path, checkout.join("move/oracle"));
+    assert_eq!(
+        config.deployments[0].packages[1].path,
+        Path::new("packages/core")
+    );
+
+    let mut invalid = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "s"
+packages = [
+    { address_name = "both", path = "packages/both", git = { url = "https://github.com/a/b", rev = "1" } },
+    { address_name = "neither" },
+    { address_name = "outside", git = { url = "https://github.com/a/b", rev = "1", subdir = "../c" } },
+]
+"#,
+    )
+    .unwrap();
+    let err = invalid.resolve_git_sources(cache).unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics.0[0].message.contains("either `path` or `git`"));
+    assert_eq!(diagnostics.0[1].message, "missing `path` or `git`");
+    assert_eq!(diagnostics.0[2].location, "deployments[0].packages[2]");
+    assert!(diagnostics.0[2].message.contains("inside the repository"));
+
+    let err = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "s"
+packages = [
+    { address_name = "oracle", git = { url = "-uhttps://github.com/a/b", rev = "--upload-pack=touch /tmp/pwned" } },
+]
+"#,
+    )
+    .unwrap()
+    .validate()
+    .unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(
+        diagnostics.0[0].location,
+        "deployments[0].packages[0].git.url"
+    );
+    assert_eq!(
+        diagnostics.0[1].message,
+        "`--upload-pack=touch /tmp/pwned` must not start with `-`"
+    );
+}
+
+#[test]
+fn test_publisher_key_sources() {
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[publishers]
+alice = "0x10"
+bob = { address = "0x20", key = { profile = "mainnet-deployer" } }
+carol = { address = "0x30", key = { env = "CAROL_PRIVATE_KEY" } }
+dave = { address = "0x40", key = { keystore = "keys/dave.json" } }
+erin = { address = "0x50", key = "external" }
+frank = { address = "0x60" }
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.publisher_address("bob"),
+        Some(AccountAddress::from_hex_literal("0x20").unwrap())
+    );
+    assert_eq!(config.publisher_key("alice"), None);
+    assert_eq!(config.publisher_key("frank"), None);
+    assert_eq!(config.publisher_key("0x20"), None);
+    assert_eq!(
+        config.publisher_key("bob"),
+        Some(&KeySource::Profile("mainnet-deployer".to_string()))
+    );
+    assert_eq!(
+        config.publisher_key("carol"),
+        Some(&KeySource::Env("CAROL_PRIVATE_KEY".to_string()))
+    );
+    assert_eq!(
+        config.publisher_key("dave").unwrap().to_string(),
+        "keystore keys/dave.json"
+    );
+    assert_eq!(config.publisher_key("erin"), Some(&KeySource::External));
+
+    let unknown_key = r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[publishers]
+bob = { address = "0x20", keys = { profile = "deployer" } }
+"#;
+    let err = parse_config(unknown_key).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostics>().unwrap().0[0].message,
+        "unknown key `keys`, did you mean `key`?"
+    );
+    let unknown_source = r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[publishers]
+bob = { address = "0x20", key = { ledger = "0" } }
+"#;
+    assert!(parse_config(unknown_source).is_err());
+}
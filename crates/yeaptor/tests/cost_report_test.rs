@@ -0,0 +1,66 @@
+use yeaptor::cost_report::{CostReport, PackageCost, PublishKind, format_apt, render_cost_report};
+
+fn cost(package: &str, publisher: &str, kind: PublishKind, gas_used: Option<u64>) -> PackageCost {
+    PackageCost {
+        order: 0,
+        deployment: "yeap".to_string(),
+        package: package.to_string(),
+        publisher: publisher.to_string(),
+        kind,
+        success: gas_used.is_some(),
+        vm_status: match gas_used {
+            Some(_) => "Executed successfully".to_string(),
+            None => "LINKER_ERROR".to_string(),
+        },
+        gas_used: gas_used.unwrap_or(40),
+        octas: gas_used.unwrap_or(40) * 100,
+    }
+}
+
+#[test]
+fn test_cost_report_by_publisher() {
+    let report = CostReport::new(
+        100,
+        vec![
+            cost("core", "0xb", PublishKind::Create, Some(12_000)),
+            cost("vault", "0xa", PublishKind::Upgrade, Some(3_000)),
+            cost("oracle", "0xb", PublishKind::Create, None),
+            cost("market", "0xb", PublishKind::Create, Some(8_000)),
+        ],
+    );
+    assert_eq!(
+        report
+            .publishers
+            .iter()
+            .map(|p| (p.publisher.as_str(), p.packages, p.gas_used, p.failed))
+            .collect::<Vec<_>>(),
+        [("0xa", 1, 3_000, 0), ("0xb", 3, 20_000, 1)]
+    );
+    assert_eq!(report.total_gas_used, 23_000);
+    assert_eq!(report.total_octas, 2_300_000);
+
+    let text = render_cost_report(&report);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("PACKAGE"), "{}", text);
+    assert!(lines[2].contains("upgrade"), "{}", text);
+    assert!(lines[3].ends_with("failed: LINKER_ERROR"), "{}", text);
+    assert!(
+        text.contains("0xb: 3 package(s), 20000 gas, 0.02000000 APT (1 failed, not counted)"),
+        "{}",
+        text
+    );
+    assert_eq!(
+        lines.last(),
+        Some(&"Total: 23000 gas at 100 octas per unit = 0.02300000 APT")
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["packages"][1]["kind"], "upgrade");
+}
+
+#[test]
+fn test_format_apt() {
+    assert_eq!(format_apt(0), "0.00000000 APT");
+    assert_eq!(format_apt(1_234_500), "0.01234500 APT");
+    assert_eq!(format_apt(250_000_000), "2.50000000 APT");
+}
@@ -0,0 +1,403 @@
+use aptos_types::account_address::AccountAddress;
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::Builder;
+use yeaptor::db_schema::{
+    DEFAULT_TABLE_GROUP, db_schema_to_csv, db_schema_to_toml, load_db_schema,
+    load_db_schema_table_options, load_schema_snapshot, parse_db_schema,
+    parse_db_schema_table_options, scaffold_db_schema, schema_snapshot_to_toml,
+    split_table_schemas_by_group, validate_table_indexes, validate_table_partitioning,
+};
+use yeaptor::event_definition::{EventDefinition, EventVersion};
+use yeaptor::processor_config::{
+    ConflictStrategy, IndexDefinition, IndexMethod, PartitionDefinition, PartitionInterval,
+    PartitionPeriod,
+};
+
+#[test]
+fn test_load_toml_schema() {
+    let content = r#"
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+lltv = { column_type = "u64", is_nullable = true, default_value = "0" }
+paused = { column_type = "bool", default_value = false }
+transaction_version = { column_type = "version", type = "transaction_metadata" }
+
+[tables.borrow_market_activities.columns]
+event_index = { column_type = "event_index", type = "event_metadata", is_primary_key = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let tables = load_db_schema(file.path()).unwrap();
+    assert_eq!(tables.len(), 2);
+
+    let market = tables.get("borrow_market").unwrap();
+    assert_eq!(market.len(), 4);
+    let column = market.get("market").unwrap();
+    assert_eq!(column.column_type.r#type, "move_type");
+    assert!(column.is_primary_key);
+    assert!(!column.is_index);
+    // typed like CSV cells: numbers as numbers, bools as strings
+    assert_eq!(
+        market.get("lltv").unwrap().default_value,
+        Some(YamlValue::from(0u64))
+    );
+    assert_eq!(
+        market.get("paused").unwrap().default_value,
+        Some(YamlValue::String("false".to_string()))
+    );
+    assert_eq!(
+        market
+            .get("transaction_version")
+            .unwrap()
+            .column_type
+            .r#type,
+        "transaction_metadata"
+    );
+}
+
+#[test]
+fn test_load_yaml_schema() {
+    let content = r#"
+tables:
+  borrow_market:
+    columns:
+      market:
+        column_type: address
+        is_primary_key: true
+"#;
+    let file = Builder::new().suffix(".yaml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let tables = load_db_schema(file.path()).unwrap();
+    assert!(tables.get("borrow_market").unwrap().contains_key("market"));
+}
+
+#[test]
+fn test_parse_schema_content() {
+    // content already read from the path, whose extension picks the format
+    let content = r#"
+[tables.market]
+group = "core"
+[tables.market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#;
+    let path = Path::new("inputs/db_schema.toml");
+    let schema = parse_db_schema(path, content).unwrap();
+    assert!(schema["market"]["market"].is_primary_key);
+    let options = parse_db_schema_table_options(path, content).unwrap();
+    assert_eq!(options["market"].group.as_deref(), Some("core"));
+
+    let csv = "table,column,column_type,type,is_index,is_nullable,is_option,is_primary_key,is_vec
+market,market,address,move_type,false,false,false,true,false
+";
+    let schema = parse_db_schema(Path::new("db_schema.csv"), csv).unwrap();
+    assert!(schema["market"]["market"].is_primary_key);
+}
+
+#[test]
+fn test_unknown_schema_keys_rejected() {
+    let content = r#"
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let err = load_db_schema(file.path()).unwrap_err();
+    assert!(format!("{:#}", err).contains("is_primary"));
+}
+
+#[test]
+fn test_unknown_column_kind_rejected() {
+    let content = r#"
+[tables.borrow_market.columns]
+market = { column_type = "address", type = "move" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    assert!(load_db_schema(file.path()).is_err());
+}
+
+fn position_updated_event() -> EventDefinition {
+    EventDefinition {
+        package_name: "yeap-borrow".to_string(),
+        module_address: AccountAddress::from_hex_literal("0x42").unwrap(),
+        module_name: "position".to_string(),
+        name: "PositionUpdatedEvent".to_string(),
+        fields: BTreeMap::from([
+            ("owner".to_string(), "address".to_string()),
+            ("amount".to_string(), "u128".to_string()),
+            (
+                "tags".to_string(),
+                "vector<0x1::string::String>".to_string(),
+            ),
+            ("limit".to_string(), "0x1::option::Option<u64>".to_string()),
+            (
+                "market".to_string(),
+                "0x1::object::Object<0x42::market::Market>".to_string(),
+            ),
+            ("state".to_string(), "0x42::position::State".to_string()),
+        ]),
+        version: EventVersion::V2,
+        dependency_of: None,
+        nested_fields: BTreeMap::from([("state.debt".to_string(), "u64".to_string())]),
+    }
+}
+
+#[test]
+fn test_scaffold_schema_from_events() {
+    let tables = scaffold_db_schema(&[position_updated_event()]);
+    let table = tables.get("position_updated_event").unwrap();
+
+    let column_type = |column: &str| table.get(column).unwrap().column_type.column_type.clone();
+    assert_eq!(column_type("owner"), "address");
+    assert_eq!(column_type("amount"), "u128");
+    assert_eq!(column_type("market"), "object");
+    assert_eq!(column_type("tags"), "string");
+    assert!(table.get("tags").unwrap().is_vec);
+    assert_eq!(column_type("limit"), "u64");
+    assert!(table.get("limit").unwrap().is_option);
+    // struct fields are replaced by their leaves
+    assert!(!table.contains_key("state"));
+    assert_eq!(column_type("state_debt"), "u64");
+
+    let version = table.get("transaction_version").unwrap();
+    assert_eq!(version.column_type.r#type, "transaction_metadata");
+    assert!(version.is_primary_key);
+    assert!(table.get("event_index").unwrap().is_primary_key);
+    assert!(table.contains_key("timestamp"));
+}
+
+#[test]
+fn test_scaffold_schema_round_trips() {
+    let tables = scaffold_db_schema(&[position_updated_event()]);
+
+    let csv = Builder::new().suffix(".csv").tempfile().unwrap();
+    fs::write(csv.path(), db_schema_to_csv(&tables).unwrap()).unwrap();
+    assert_eq!(load_db_schema(csv.path()).unwrap(), tables);
+
+    let toml = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(toml.path(), db_schema_to_toml(&tables).unwrap()).unwrap();
+    assert_eq!(load_db_schema(toml.path()).unwrap(), tables);
+}
+
+#[test]
+fn test_scaffold_schema_disambiguates_table_names() {
+    let mut other = position_updated_event();
+    other.module_name = "vault".to_string();
+    let tables = scaffold_db_schema(&[position_updated_event(), other]);
+    assert!(tables.contains_key("position_position_updated_event"));
+    assert!(tables.contains_key("vault_position_updated_event"));
+}
+
+#[test]
+fn test_table_options_from_toml() {
+    let content = r#"
+[tables.borrow_market]
+group = "core"
+on_conflict = "upsert"
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+
+[tables.market_stats]
+group = "analytics"
+[tables.market_stats.columns]
+market = { column_type = "address", is_primary_key = true }
+
+[tables.market_oracle.columns]
+oracle = { column_type = "address", is_primary_key = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let options = load_db_schema_table_options(file.path()).unwrap();
+    assert_eq!(
+        options["borrow_market"].on_conflict,
+        Some(ConflictStrategy::Upsert)
+    );
+    assert!(options["market_stats"].on_conflict.is_none());
+    assert!(!options.contains_key("market_oracle"));
+    let groups = options
+        .into_iter()
+        .filter_map(|(table, options)| options.group.map(|group| (table, group)))
+        .collect();
+
+    let split = split_table_schemas_by_group(&load_db_schema(file.path()).unwrap(), &groups);
+    assert_eq!(split.len(), 3);
+    assert!(split["core"].contains_key("borrow_market"));
+    assert!(split["analytics"].contains_key("market_stats"));
+    assert!(split[DEFAULT_TABLE_GROUP].contains_key("market_oracle"));
+}
+
+#[test]
+fn test_table_indexes_from_toml() {
+    let content = r#"
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+oracle = { column_type = "address" }
+transaction_version = { column_type = "version", type = "transaction_metadata" }
+
+[[tables.borrow_market.indexes]]
+columns = ["oracle", "market"]
+unique = true
+
+[[tables.borrow_market.indexes]]
+columns = ["transaction_version"]
+method = "brin"
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let snapshot = load_schema_snapshot(file.path()).unwrap();
+    let indexes = snapshot.indexes("borrow_market");
+    assert_eq!(indexes.len(), 2);
+    assert_eq!(
+        indexes[0].index_name("borrow_market"),
+        "borrow_market_oracle_market_key"
+    );
+    assert_eq!(indexes[1].method, Some(IndexMethod::Brin));
+    let schema = &snapshot.tables["borrow_market"];
+    assert!(validate_table_indexes("borrow_market", schema, indexes).is_ok());
+
+    // indexes survive the round trip through the structured layout
+    fs::write(file.path(), schema_snapshot_to_toml(&snapshot).unwrap()).unwrap();
+    assert_eq!(load_schema_snapshot(file.path()).unwrap(), snapshot);
+
+    let index = |columns: &[&str], unique, method| IndexDefinition {
+        name: None,
+        columns: columns.iter().map(|c| c.to_string()).collect(),
+        unique,
+        method,
+    };
+    for (invalid, message) in [
+        (index(&[], false, None), "has no columns"),
+        (index(&["lltv"], false, None), "unknown column lltv"),
+        (
+            index(&["oracle", "market"], false, Some(IndexMethod::Hash)),
+            "single non-unique column",
+        ),
+        (
+            index(&["transaction_version"], true, Some(IndexMethod::Brin)),
+            "cannot be unique",
+        ),
+    ] {
+        let err = validate_table_indexes("borrow_market", schema, &[invalid]).unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+    let duplicate = index(&["oracle"], false, None);
+    assert!(
+        validate_table_indexes("borrow_market", schema, &[duplicate.clone(), duplicate]).is_err()
+    );
+}
+
+#[test]
+fn test_table_partitioning_from_yaml() {
+    let content = r#"
+tables:
+  position_events:
+    partition_by: { column: timestamp, interval: week }
+    indexes:
+      - { columns: [owner], unique: true }
+    columns:
+      owner: { column_type: address }
+      note: { column_type: string, is_nullable: true }
+      event_index: { column_type: event_index, type: event_metadata }
+      timestamp: { column_type: timestamp, type: transaction_metadata }
+"#;
+    let file = Builder::new().suffix(".yaml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let snapshot = load_schema_snapshot(file.path()).unwrap();
+    let partition_by = snapshot.partition_by("position_events").unwrap();
+    assert_eq!(
+        partition_by.interval,
+        PartitionInterval::Period(PartitionPeriod::Week)
+    );
+    let schema = &snapshot.tables["position_events"];
+    let indexes = snapshot.indexes("position_events");
+    // Postgres enforces uniqueness per partition
+    let err =
+        validate_table_partitioning("position_events", schema, indexes, partition_by).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("must include partition column timestamp")
+    );
+    assert!(validate_table_partitioning("position_events", schema, &[], partition_by).is_ok());
+
+    let partition = |column: &str, interval| PartitionDefinition {
+        column: column.to_string(),
+        interval,
+    };
+    for (invalid, message) in [
+        (
+            partition("lltv", PartitionInterval::Values(100)),
+            "unknown column lltv",
+        ),
+        (
+            partition("note", PartitionInterval::Period(PartitionPeriod::Day)),
+            "non-nullable scalar",
+        ),
+        (
+            partition(
+                "event_index",
+                PartitionInterval::Period(PartitionPeriod::Day),
+            ),
+            "not a timestamp",
+        ),
+        (
+            partition("owner", PartitionInterval::Values(100)),
+            "not an integer",
+        ),
+        (
+            partition("event_index", PartitionInterval::Values(0)),
+            "interval of 0",
+        ),
+    ] {
+        let err =
+            validate_table_partitioning("position_events", schema, &[], &invalid).unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+}
+
+#[test]
+fn test_table_options_from_csv() {
+    let header = "table, column, column_type, type, default_value, is_index, is_nullable, is_option, is_primary_key, is_vec, group, on_conflict\n";
+    let file = Builder::new().suffix(".csv").tempfile().unwrap();
+    fs::write(
+        file.path(),
+        format!(
+            "{}{}{}",
+            header,
+            "borrow_market, market, address, move_type, , False, False, False, True, False, core, ignore-duplicates\n",
+            "borrow_market, lltv, u64, move_type, , False, True, False, False, False, , \n",
+        ),
+    )
+    .unwrap();
+    let options = load_db_schema_table_options(file.path()).unwrap();
+    assert_eq!(options["borrow_market"].group.as_deref(), Some("core"));
+    assert_eq!(
+        options["borrow_market"].on_conflict,
+        Some(ConflictStrategy::IgnoreDuplicates)
+    );
+    assert_eq!(
+        load_db_schema(file.path()).unwrap()["borrow_market"].len(),
+        2
+    );
+
+    fs::write(
+        file.path(),
+        format!(
+            "{}{}{}",
+            header,
+            "borrow_market, market, address, move_type, , False, False, False, True, False, core, \n",
+            "borrow_market, lltv, u64, move_type, , False, True, False, False, False, analytics, \n",
+        ),
+    )
+    .unwrap();
+    assert!(load_db_schema_table_options(file.path()).is_err());
+}
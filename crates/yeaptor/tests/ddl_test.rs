@@ -0,0 +1,241 @@
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::{SchemaSnapshot, load_schema_snapshot};
+use yeaptor::ddl::{clickhouse_ddl, postgres_ddl, postgres_migration};
+
+#[test]
+fn test_postgres_ddl() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u128", default_value = "0" }
+tags = { column_type = "string", is_vec = true, is_nullable = true }
+limit = { column_type = "u64", is_option = true }
+paused = { column_type = "bool", default_value = false, is_index = true }
+note = { column_type = "string", default_value = "it's" }
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+timestamp = { column_type = "timestamp", type = "transaction_metadata", is_index = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let ddl = postgres_ddl(&load_schema_snapshot(file.path()).unwrap()).unwrap();
+    assert!(ddl.starts_with("CREATE TABLE IF NOT EXISTS \"borrow_position\" (\n"));
+    assert!(ddl.contains("    \"amount\" NUMERIC(39, 0) NOT NULL DEFAULT 0,\n"));
+    assert!(ddl.contains("    \"tags\" TEXT[],\n"));
+    assert!(ddl.contains("    \"limit\" NUMERIC(20, 0),\n"));
+    assert!(ddl.contains("    \"paused\" BOOLEAN NOT NULL DEFAULT FALSE,\n"));
+    assert!(ddl.contains("    \"note\" TEXT NOT NULL DEFAULT 'it''s',\n"));
+    assert!(ddl.contains("    \"owner\" VARCHAR(66) NOT NULL,\n"));
+    assert!(ddl.contains("    \"timestamp\" TIMESTAMP NOT NULL,\n"));
+    assert!(ddl.contains("    PRIMARY KEY (\"owner\", \"transaction_version\")\n);\n"));
+    assert!(ddl.contains(
+        "CREATE INDEX IF NOT EXISTS \"borrow_position_paused_idx\" ON \"borrow_position\" (\"paused\");\n"
+    ));
+    assert!(ddl.contains("\"borrow_position_timestamp_idx\""));
+}
+
+#[test]
+fn test_postgres_ddl_rejects_unknown_type() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "vector<u8>", is_primary_key = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let err = postgres_ddl(&load_schema_snapshot(file.path()).unwrap()).unwrap_err();
+    assert!(format!("{:#}", err).contains("borrow_position::owner"));
+}
+
+fn load_toml_schema(content: &str) -> SchemaSnapshot {
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    load_schema_snapshot(file.path()).unwrap()
+}
+
+#[test]
+fn test_postgres_migration() {
+    let previous = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u64" }
+legacy = { column_type = "string", is_index = true }
+
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#,
+    );
+    let current = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u128", is_nullable = true }
+limit = { column_type = "u64", is_nullable = true, is_index = true }
+
+[tables.borrow_oracle.columns]
+oracle = { column_type = "address", is_primary_key = true }
+"#,
+    );
+
+    let up = postgres_migration(&previous, &current).unwrap();
+    assert_eq!(
+        up,
+        r#"DROP TABLE IF EXISTS "borrow_market";
+CREATE TABLE IF NOT EXISTS "borrow_oracle" (
+    "oracle" VARCHAR(66) NOT NULL,
+    PRIMARY KEY ("oracle")
+);
+DROP INDEX IF EXISTS "borrow_position_legacy_idx";
+ALTER TABLE "borrow_position" DROP COLUMN "legacy";
+ALTER TABLE "borrow_position" ALTER COLUMN "amount" TYPE NUMERIC(39, 0) USING "amount"::NUMERIC(39, 0);
+ALTER TABLE "borrow_position" ALTER COLUMN "amount" DROP NOT NULL;
+ALTER TABLE "borrow_position" ADD COLUMN "limit" NUMERIC(20, 0);
+CREATE INDEX IF NOT EXISTS "borrow_position_limit_idx" ON "borrow_position" ("limit");
+"#
+    );
+
+    let down = postgres_migration(&current, &previous).unwrap();
+    assert!(down.starts_with("DROP TABLE IF EXISTS \"borrow_oracle\";\n"));
+    assert!(
+        down.contains("ALTER TABLE \"borrow_position\" ADD COLUMN \"legacy\" TEXT NOT NULL;\n")
+    );
+    assert!(postgres_migration(&current, &current).unwrap().is_empty());
+}
+
+#[test]
+fn test_declared_indexes() {
+    let schema = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+market = { column_type = "address" }
+note = { column_type = "string" }
+transaction_version = { column_type = "version", type = "transaction_metadata" }
+
+[[tables.borrow_position.indexes]]
+columns = ["market", "owner"]
+unique = true
+
+[[tables.borrow_position.indexes]]
+name = "position_note_lookup"
+columns = ["note"]
+method = "hash"
+"#;
+    let ddl = postgres_ddl(&load_toml_schema(schema)).unwrap();
+    assert!(ddl.contains(
+        "CREATE UNIQUE INDEX IF NOT EXISTS \"borrow_position_market_owner_key\" ON \"borrow_position\" (\"market\", \"owner\");\n"
+    ));
+    assert!(ddl.contains(
+        "CREATE INDEX IF NOT EXISTS \"position_note_lookup\" ON \"borrow_position\" USING hash (\"note\");\n"
+    ));
+
+    let clickhouse = clickhouse_ddl(&load_toml_schema(schema)).unwrap();
+    assert!(clickhouse.contains(
+        "    INDEX `borrow_position_market_owner_key` (`market`, `owner`) TYPE bloom_filter GRANULARITY 4,\n"
+    ));
+
+    // changing the method of an index recreates it; other indexes are left alone
+    let current = load_toml_schema(&schema.replace("method = \"hash\"", "method = \"brin\""));
+    assert_eq!(
+        postgres_migration(&load_toml_schema(schema), &current).unwrap(),
+        "DROP INDEX IF EXISTS \"position_note_lookup\";\n\
+         CREATE INDEX IF NOT EXISTS \"position_note_lookup\" ON \"borrow_position\" USING brin (\"note\");\n"
+    );
+
+    let invalid =
+        load_toml_schema(&schema.replace("columns = [\"note\"]", "columns = [\"missing\"]"));
+    let err = postgres_ddl(&invalid).unwrap_err();
+    assert!(format!("{:#}", err).contains("unknown column missing"));
+}
+
+#[test]
+fn test_partitioned_tables() {
+    let schema = r#"
+[tables.position_events]
+partition_by = { column = "transaction_version", interval = 10000000 }
+[tables.position_events.columns]
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+event_index = { column_type = "event_index", type = "event_metadata", is_primary_key = true }
+timestamp = { column_type = "timestamp", type = "transaction_metadata" }
+"#;
+    let ddl = postgres_ddl(&load_toml_schema(schema)).unwrap();
+    assert_eq!(
+        ddl,
+        r#"CREATE TABLE IF NOT EXISTS "position_events" (
+    "event_index" BIGINT NOT NULL,
+    "timestamp" TIMESTAMP NOT NULL,
+    "transaction_version" BIGINT NOT NULL,
+    PRIMARY KEY ("event_index", "transaction_version")
+) PARTITION BY RANGE ("transaction_version");
+CREATE TABLE IF NOT EXISTS "position_events_default" PARTITION OF "position_events" DEFAULT;
+
+"#
+    );
+    let clickhouse = clickhouse_ddl(&load_toml_schema(schema)).unwrap();
+    assert!(clickhouse.contains(
+        "ENGINE = ReplacingMergeTree\nPARTITION BY intDiv(`transaction_version`, 10000000)\nORDER BY"
+    ));
+
+    let by_month = schema.replace(
+        "{ column = \"transaction_version\", interval = 10000000 }",
+        "{ column = \"timestamp\", interval = \"month\" }",
+    );
+    // the partition column must be part of the primary key
+    let err = postgres_ddl(&load_toml_schema(&by_month)).unwrap_err();
+    assert!(err.to_string().contains("must be part of its primary key"));
+    let by_month = by_month.replace(
+        "timestamp = { column_type = \"timestamp\", type = \"transaction_metadata\" }",
+        "timestamp = { column_type = \"timestamp\", type = \"transaction_metadata\", is_primary_key = true }",
+    );
+    let clickhouse = clickhouse_ddl(&load_toml_schema(&by_month)).unwrap();
+    assert!(clickhouse.contains("PARTITION BY toYYYYMM(`timestamp`)\n"));
+
+    // Postgres cannot repartition a table in place
+    let err =
+        postgres_migration(&load_toml_schema(schema), &load_toml_schema(&by_month)).unwrap_err();
+    assert!(err.to_string().contains("requires recreating it"));
+}
+
+#[test]
+fn test_clickhouse_ddl() {
+    let tables = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u128", is_nullable = true }
+tags = { column_type = "string", is_vec = true, is_option = true }
+note = { column_type = "string", default_value = "it's", is_index = true }
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+
+[tables.market_log.columns]
+market = { column_type = "address", is_index = true }
+timestamp = { column_type = "timestamp", type = "transaction_metadata" }
+"#,
+    );
+    let ddl = clickhouse_ddl(&tables).unwrap();
+    assert_eq!(
+        ddl,
+        r#"CREATE TABLE IF NOT EXISTS `borrow_position`
+(
+    `amount` Nullable(UInt128),
+    `note` String DEFAULT 'it\'s',
+    `owner` String,
+    `tags` Array(String),
+    `transaction_version` UInt64,
+    INDEX `borrow_position_note_idx` `note` TYPE bloom_filter GRANULARITY 4
+)
+ENGINE = ReplacingMergeTree
+ORDER BY (`owner`, `transaction_version`);
+
+CREATE TABLE IF NOT EXISTS `market_log`
+(
+    `market` String,
+    `timestamp` DateTime64(6)
+)
+ENGINE = MergeTree
+ORDER BY (`market`);
+
+"#
+    );
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_types::account_address::AccountAddress;
+use common::MetadataBuilder;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::config::{DeploymentMode, parse_config};
+use yeaptor::deployment_list::{PublishStatus, list_packages, render_package_table};
+use yeaptor_core::address::resource_account_address;
+
+/// `PackageMetadata` of `name` at `upgrade_number` with upgrade policy `policy`
+fn metadata(name: &str, policy: u8, upgrade_number: u64) -> PackageMetadata {
+    MetadataBuilder::new(name)
+        .upgrade_policy(policy)
+        .upgrade_number(upgrade_number)
+        .build()
+}
+
+#[test]
+fn test_list_packages() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    let vault = dir.path().join("vault");
+    fs::create_dir_all(&core).unwrap();
+    fs::write(
+        core.join("Move.toml"),
+        "[package]\nname = \"YeapCore\"\nversion = \"1.0.0\"\n",
+    )
+    .unwrap();
+    let config = parse_config(&format!(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+tags = ["core"]
+packages = [{{ address_name = "core", path = "{}" }}]
+
+[[deployments]]
+publisher = "0x20"
+mode = "account"
+packages = [{{ address_name = "vault", path = "{}", tags = ["periphery"] }}]
+"#,
+        core.display(),
+        vault.display()
+    ))
+    .unwrap();
+
+    let listings = list_packages(&config, &[]).unwrap();
+    assert_eq!(listings.len(), 2);
+    let alice = AccountAddress::from_hex_literal("0x10").unwrap();
+    assert_eq!(listings[0].location, "deployments[0]");
+    assert_eq!(listings[0].deployment, "alice-v1");
+    assert_eq!(listings[0].mode, DeploymentMode::ResourceAccount);
+    assert_eq!(listings[0].name.as_deref(), Some("YeapCore"));
+    assert_eq!(listings[0].publisher, alice.to_standard_string());
+    assert_eq!(listings[0].seed, "v1");
+    assert_eq!(
+        listings[0].account,
+        resource_account_address(&alice, b"v1").to_standard_string()
+    );
+    // no Move.toml: listed without a package name
+    assert_eq!(listings[1].mode, DeploymentMode::Account);
+    assert_eq!(listings[1].name, None);
+    assert_eq!(listings[1].seed, "");
+    assert_eq!(listings[1].account, listings[1].publisher);
+
+    let periphery = list_packages(&config, &["periphery".to_string()]).unwrap();
+    assert_eq!(periphery.len(), 1);
+    assert_eq!(periphery[0].package, "vault");
+
+    let table = render_package_table(&listings);
+    let lines = table.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("DEPLOYMENT"));
+    assert!(!lines[0].contains("STATUS"));
+    assert!(lines[1].contains("core (YeapCore)"));
+    assert!(lines[1].contains(&format!("alice ({})", alice.to_standard_string())));
+    // columns line up
+    let column = lines[0].find("PACKAGE").unwrap();
+    assert!(lines[1][column..].starts_with("core"));
+    assert!(lines[2][column..].starts_with("vault"));
+}
+
+#[test]
+fn test_list_packages_unknown_publisher() {
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "bob"
+seed = "v1"
+packages = [{ address_name = "core", path = "core" }]
+"#,
+    )
+    .unwrap();
+    let err = list_packages(&config, &[]).unwrap_err().to_string();
+    assert!(err.contains("deployments[0]"), "{}", err);
+    assert!(err.contains("`bob`"), "{}", err);
+}
+
+#[test]
+fn test_publish_status() {
+    let published = vec![metadata("Other", 0, 3), metadata("YeapCore", 1, 2)];
+    assert_eq!(
+        PublishStatus::of(Some("YeapCore"), &published),
+        PublishStatus {
+            published: true,
+            upgrade_number: Some(2),
+            upgrade_policy: Some("compatible".to_string()),
+        }
+    );
+    let unpublished = PublishStatus {
+        published: false,
+        upgrade_number: None,
+        upgrade_policy: None,
+    };
+    assert_eq!(PublishStatus::of(Some("Vault"), &published), unpublished);
+    assert_eq!(PublishStatus::of(None, &published), unpublished);
+
+    let config = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+packages = [
+    { address_name = "core", path = "core" },
+    { address_name = "vault", path = "vault" },
+]
+"#,
+    )
+    .unwrap();
+    let mut listings = list_packages(&config, &[]).unwrap();
+    listings[0].name = Some("YeapCore".to_string());
+    listings[0].status = Some(PublishStatus::of(Some("YeapCore"), &published));
+    listings[1].status = Some(unpublished);
+    let table = render_package_table(&listings);
+    let lines = table.lines().collect::<Vec<_>>();
+    assert!(lines[0].ends_with("STATUS"));
+    assert!(lines[1].ends_with("published, upgrade 2, compatible"));
+    assert!(lines[2].ends_with("not published"));
+
+    let json = serde_json::to_value(&listings[1]).unwrap();
+    assert_eq!(json["status"], serde_json::json!({ "published": false }));
+    assert_eq!(json["mode"], "resource-account");
+}
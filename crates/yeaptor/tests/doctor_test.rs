@@ -0,0 +1,173 @@
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::config::parse_config;
+use yeaptor::doctor::{
+    CheckStatus, DoctorCheck, check_aptos_profile, check_packages, check_publisher_keys,
+    default_faucet_url, parse_df_available, render_checks,
+};
+
+#[test]
+fn test_render_checks() {
+    let checks = [
+        DoctorCheck::ok("git", "git version 2.43.0"),
+        DoctorCheck::error("fullnode", "connection refused", "pass --node-url"),
+    ];
+    assert_eq!(
+        render_checks(&checks),
+        "ok[git] git version 2.43.0\nerror[fullnode] connection refused\n  fix: pass --node-url"
+    );
+}
+
+#[test]
+fn test_default_faucet_url() {
+    assert_eq!(
+        default_faucet_url("Testnet"),
+        Some("https://faucet.testnet.aptoslabs.com")
+    );
+    assert_eq!(default_faucet_url("mainnet"), None);
+}
+
+#[test]
+fn test_parse_df_available() {
+    let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                  /dev/sda1        102400000  51200000  51200000      50% /\n";
+    assert_eq!(parse_df_available(output), Some(51_200_000 * 1024));
+    assert_eq!(parse_df_available("Filesystem\n"), None);
+}
+
+#[test]
+fn test_check_aptos_profile() {
+    let dir = tempdir().unwrap();
+    let workspace = dir.path().join("workspace.yaml");
+    let global = dir.path().join("global.yaml");
+    fs::write(
+        &global,
+        "---\nprofiles:\n  default:\n    network: Testnet\n  prod:\n    network: Mainnet\n",
+    )
+    .unwrap();
+    let files = [workspace.clone(), global.clone()];
+
+    let check = check_aptos_profile("default", "testnet", &files);
+    assert_eq!(check.status, CheckStatus::Ok);
+    assert_eq!(check.message, format!("found in {}", global.display()));
+
+    let check = check_aptos_profile("prod", "testnet", &files);
+    assert_eq!(check.status, CheckStatus::Warning);
+    assert!(check.message.ends_with("targets Mainnet, not testnet"));
+
+    let check = check_aptos_profile("deployer", "testnet", &files);
+    assert_eq!(check.status, CheckStatus::Warning);
+    assert!(
+        check
+            .message
+            .ends_with("has no such profile (profiles: default, prod)")
+    );
+    assert_eq!(
+        check.fix.as_deref(),
+        Some("run `aptos init --profile deployer --network testnet`, or pass --profile")
+    );
+
+    // the workspace config wins over the global one
+    fs::write(&workspace, "---\nprofiles: {}\n").unwrap();
+    let check = check_aptos_profile("default", "testnet", &files);
+    assert!(
+        check
+            .message
+            .ends_with("has no such profile (profiles: none)")
+    );
+
+    let check = check_aptos_profile("default", "testnet", &[dir.path().join("missing.yaml")]);
+    assert_eq!(check.message, "no aptos CLI config found");
+}
+
+#[test]
+fn test_check_packages() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    fs::create_dir_all(&core).unwrap();
+    fs::write(
+        core.join("Move.toml"),
+        "[package]\nname = \"Core\"\nversion = \"1.0.0\"\n\n[addresses]\ncore = \"_\"\n",
+    )
+    .unwrap();
+    let config = |path: &str| {
+        parse_config(&format!(
+            r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [{{ address_name = "core", path = "{}" }}]
+"#,
+            path
+        ))
+        .unwrap()
+    };
+
+    let checks = check_packages(&config(&core.display().to_string()));
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, CheckStatus::Ok);
+
+    let missing = dir.path().join("missing");
+    let checks = check_packages(&config(&missing.display().to_string()));
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, CheckStatus::Error);
+    assert_eq!(checks[0].name, "deployments[0].packages[0]");
+}
+
+#[test]
+fn test_check_publisher_keys() {
+    let dir = tempdir().unwrap();
+    let aptos_config = dir.path().join("config.yaml");
+    fs::write(
+        &aptos_config,
+        "---\nprofiles:\n  deployer:\n    network: Mainnet\n",
+    )
+    .unwrap();
+    let keystore = dir.path().join("treasury.json");
+    fs::write(&keystore, "{}").unwrap();
+    let config = parse_config(&format!(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[publishers]
+alice = "0x10"
+bob = {{ address = "0x20", key = {{ profile = "deployer" }} }}
+carol = {{ address = "0x30", key = {{ profile = "ops" }} }}
+dave = {{ address = "0x40", key = {{ env = "YEAPTOR_TEST_UNSET_PRIVATE_KEY" }} }}
+erin = {{ address = "0x50", key = {{ keystore = '{}' }} }}
+frank = {{ address = "0x60", key = "external" }}
+"#,
+        keystore.display()
+    ))
+    .unwrap();
+
+    let checks = check_publisher_keys(&config, &[aptos_config.clone()]);
+    assert_eq!(
+        checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.status))
+            .collect::<Vec<_>>(),
+        [
+            ("publisher bob", CheckStatus::Ok),
+            ("publisher carol", CheckStatus::Warning),
+            ("publisher dave", CheckStatus::Warning),
+            ("publisher erin", CheckStatus::Ok),
+            ("publisher frank", CheckStatus::Ok),
+        ]
+    );
+    assert_eq!(
+        checks[1].message,
+        format!("aptos profile `ops` is not in {}", aptos_config.display())
+    );
+    assert_eq!(
+        checks[2].message,
+        "environment variable `YEAPTOR_TEST_UNSET_PRIVATE_KEY` is not set"
+    );
+}
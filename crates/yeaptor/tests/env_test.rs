@@ -0,0 +1,325 @@
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+use yeaptor::config::{DeploymentMode, load_config, parse_config};
+use yeaptor::env::YeaptorEnv;
+
+fn write_package(dir: &Path, name: &str, addresses: &str) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(
+        dir.join("Move.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"1.0.0\"\n\n[addresses]\n{}\n",
+            name, addresses
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_check_address_names() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    let oracle = dir.path().join("oracle");
+    write_package(&core, "Core", "core = \"_\"");
+    write_package(&oracle, "Oracle", "price_oracle = \"_\"\nstd = \"0x1\"");
+    let config = dir.path().join("yeaptor.toml");
+    fs::write(
+        &config,
+        format!(
+            r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [
+    {{ address_name = "core", path = "{}" }},
+    {{ address_name = "oracle", path = "{}" }},
+]
+"#,
+            core.display(),
+            oracle.display()
+        ),
+    )
+    .unwrap();
+    let env = YeaptorEnv::new(load_config(&config).unwrap());
+
+    env.check(Some(&core)).unwrap();
+    let err = env.check(None).unwrap_err();
+    assert_eq!(err.kind(), "config");
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "deployments[0].packages[1]: address_name `oracle` is not a named address in {} (declared: price_oracle, std)",
+            oracle.join("Move.toml").display()
+        )
+    );
+}
+
+#[test]
+fn test_check_reports_every_problem() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    write_package(&core, "Core", "core = \"_\"");
+    let config = dir.path().join("yeaptor.toml");
+    fs::write(
+        &config,
+        format!(
+            r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "bob"
+seed = "v1"
+packages = [
+    {{ address_name = "core", path = "{}" }},
+    {{ address_name = "missing", path = "{}" }},
+]
+
+[[deployments]]
+publisher = "0x20"
+seed = "v1"
+packages = [{{ address_name = "vault", path = "{}" }}]
+"#,
+            core.display(),
+            dir.path().join("missing").display(),
+            core.display()
+        ),
+    )
+    .unwrap();
+    // Unknown publishers no longer panic while resolving the named addresses
+    let env = YeaptorEnv::new(load_config(&config).unwrap());
+    assert!(!env.named_addresses().contains_key("core"));
+    assert!(env.named_addresses().contains_key("vault"));
+
+    let err = env.check(None).unwrap_err();
+    let diagnostics = err.diagnostics().unwrap();
+    assert_eq!(
+        diagnostics
+            .0
+            .iter()
+            .map(|diagnostic| diagnostic.location.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "deployments[0]",
+            "deployments[0].packages[1]",
+            "deployments[1].packages[0]"
+        ]
+    );
+    assert_eq!(
+        diagnostics.0[0].message,
+        "publisher `bob` is neither in [publishers] nor an address"
+    );
+    assert!(
+        err.to_string()
+            .starts_with("3 problems:\n  - deployments[0]: ")
+    );
+}
+
+#[test]
+fn test_check_reports_dependency_cycles() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    let oracle = dir.path().join("oracle");
+    let vault = dir.path().join("vault");
+    write_package(
+        &core,
+        "Core",
+        "core = \"_\"\n\n[dependencies]\nOracle = { local = \"../oracle\" }",
+    );
+    write_package(
+        &oracle,
+        "Oracle",
+        "oracle = \"_\"\n\n[dependencies]\nCore = { local = \"../core\" }",
+    );
+    write_package(
+        &vault,
+        "Vault",
+        "vault = \"_\"\n\n[dependencies]\nCore = { local = \"../core\" }",
+    );
+    let config = dir.path().join("yeaptor.toml");
+    fs::write(
+        &config,
+        format!(
+            r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+packages = [
+    {{ address_name = "core", path = "{}" }},
+    {{ address_name = "oracle", path = "{}" }},
+    {{ address_name = "vault", path = "{}" }},
+]
+"#,
+            core.display(),
+            oracle.display(),
+            vault.display()
+        ),
+    )
+    .unwrap();
+    let env = YeaptorEnv::new(load_config(&config).unwrap());
+
+    let err = env.check(None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "deployments[0].packages[0]: packages depend on each other in a cycle, so none can be published first: {} (deployments[0].packages[0]) -> {} (deployments[0].packages[1]) -> {} (deployments[0].packages[0])",
+            core.display(),
+            oracle.display(),
+            core.display()
+        )
+    );
+    // vault only depends on the cycle
+    env.check(Some(&vault)).unwrap();
+    assert!(env.check(Some(&oracle)).is_err());
+}
+
+#[test]
+fn test_derive_deployments() {
+    let cfg = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+tags = ["core"]
+packages = [{ address_name = "core", path = "packages/core" }]
+
+[[deployments]]
+mode = "account"
+publisher = "0x20"
+packages = [
+    { address_name = "vault", path = "packages/vault" },
+    { address_name = "oracle", path = "packages/oracle", tags = ["core"] },
+]
+"#,
+    )
+    .unwrap();
+    // nothing is read from the package directories, which do not exist
+    let env = YeaptorEnv::new(cfg);
+    let derived = env.derive_deployments(&[]).unwrap();
+    assert_eq!(derived.len(), 2);
+    assert_eq!(derived[0].deployment, "alice-v1");
+    assert_eq!(
+        derived[0].account.to_standard_string(),
+        "0x04df5c039d011a05c1f7f579b24ca4d74ee5285222c7ec31b61ffe2add8a2dfd"
+    );
+    assert_eq!(derived[1].mode, DeploymentMode::Account);
+    assert_eq!(derived[1].account, derived[1].publisher);
+    assert_eq!(derived[1].packages, ["vault", "oracle"]);
+
+    let derived = env.derive_deployments(&["core".to_string()]).unwrap();
+    assert_eq!(derived.len(), 2);
+    assert_eq!(derived[1].location, "deployments[1]");
+    assert_eq!(derived[1].packages, ["oracle"]);
+
+    let cfg = parse_config(
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[[deployments]]
+publisher = "bob"
+seed = "v1"
+"#,
+    )
+    .unwrap();
+    let err = YeaptorEnv::new(cfg).derive_deployments(&[]).unwrap_err();
+    assert_eq!(
+        err.diagnostics().unwrap().0[0].message,
+        "publisher `bob` is neither in [publishers] nor an address"
+    );
+}
+
+#[test]
+fn test_package_named_addresses() {
+    let dir = tempdir().unwrap();
+    let core = dir.path().join("core");
+    let market = dir.path().join("market");
+    write_package(&core, "Core", "core = \"_\"\noracle_addr = \"_\"");
+    write_package(&market, "Market", "market = \"_\"\noracle_addr = \"_\"");
+    let config = dir.path().join("yeaptor.toml");
+    let write_config = |market_addresses: &str| {
+        fs::write(
+            &config,
+            format!(
+                r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[named-addresses]
+oracle_addr = "0xa1"
+
+[[deployments]]
+publisher = "0x10"
+seed = "v1"
+packages = [
+    {{ address_name = "core", path = "{}" }},
+    {{ address_name = "market", path = "{}", addresses = {{ {} }} }},
+]
+"#,
+                core.display(),
+                market.display(),
+                market_addresses
+            ),
+        )
+        .unwrap();
+    };
+
+    write_config("oracle_addr = \"0xb2\"");
+    let env = YeaptorEnv::new(load_config(&config).unwrap());
+    env.check(None).unwrap();
+    let oracle = |package_dir: &Path| {
+        env.package_named_addresses(package_dir)["oracle_addr"].to_standard_string()
+    };
+    assert_eq!(
+        oracle(&core),
+        "0x00000000000000000000000000000000000000000000000000000000000000a1"
+    );
+    assert_eq!(
+        oracle(&market),
+        "0x00000000000000000000000000000000000000000000000000000000000000b2"
+    );
+    // not global: the other packages and the build's named addresses keep [named-addresses]
+    assert_eq!(
+        env.named_addresses()["oracle_addr"],
+        env.package_named_addresses(&core)["oracle_addr"]
+    );
+    assert_eq!(
+        env.package_named_addresses(&market)["core"],
+        env.named_addresses()["core"]
+    );
+
+    write_config("feed_addr = \"0xb2\"");
+    let env = YeaptorEnv::new(load_config(&config).unwrap());
+    let err = env.check(None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "deployments[0].packages[1]: `addresses` binds `feed_addr`, which is not a named address in {}",
+            market.join("Move.toml").display()
+        )
+    );
+
+    write_config("market = \"0xb2\"");
+    let err = format!("{:#}", load_config(&config).unwrap_err());
+    assert!(
+        err.contains("rebinds the package's own address_name `market`"),
+        "{}",
+        err
+    );
+}
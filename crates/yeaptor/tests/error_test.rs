@@ -0,0 +1,23 @@
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::YeaptorError;
+use yeaptor::config::load_config;
+
+#[test]
+fn test_load_errors_are_classified() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("yeaptor.toml");
+    let err = YeaptorError::load(&missing, load_config(&missing).unwrap_err());
+    assert_eq!(err.kind(), "io");
+    assert_eq!(err.path(), Some(&missing));
+
+    let invalid = dir.path().join("invalid.toml");
+    fs::write(&invalid, "yeaptor_address = 1").unwrap();
+    let err = YeaptorError::load(&invalid, load_config(&invalid).unwrap_err());
+    assert_eq!(err.kind(), "config");
+    assert!(
+        err.to_string()
+            .starts_with(&format!("invalid {}: ", invalid.display()))
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
@@ -0,0 +1,93 @@
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::CompiledModule;
+use move_binary_format::file_format::{
+    Ability, AbilitySet, AddressIdentifierIndex, FieldDefinition, IdentifierIndex, ModuleHandle,
+    ModuleHandleIndex, SignatureToken, StructDefinition, StructFieldInformation, StructHandle,
+    StructHandleIndex, StructTypeParameter, TypeSignature, empty_module,
+};
+use move_core_types::identifier::Identifier;
+use std::collections::HashSet;
+use yeaptor::event_definition::extract_event_handle_types;
+
+/// `0xcafe::vault` with `Vault has key { deposits: EventHandle<0xcafe::events::Deposit> }` and
+/// `Holder has store { withdrawals: EventHandle<0xcafe::events::Withdraw> }`, the event structs
+/// being declared in `0xcafe::events`
+fn vault_module() -> CompiledModule {
+    let mut module = empty_module();
+    module.address_identifiers = vec![
+        AccountAddress::from_hex_literal("0xcafe").unwrap(),
+        AccountAddress::ONE,
+    ];
+    module.identifiers = [
+        "vault",
+        "event",
+        "EventHandle",
+        "events",
+        "Deposit",
+        "Withdraw",
+        "Vault",
+        "Holder",
+        "deposits",
+        "withdrawals",
+    ]
+    .into_iter()
+    .map(|name| Identifier::new(name).unwrap())
+    .collect();
+    module.module_handles.extend([
+        ModuleHandle {
+            address: AddressIdentifierIndex(1),
+            name: IdentifierIndex(1),
+        },
+        ModuleHandle {
+            address: AddressIdentifierIndex(0),
+            name: IdentifierIndex(3),
+        },
+    ]);
+    let handle = |module: u16, name: u16, abilities: AbilitySet, type_parameters| StructHandle {
+        module: ModuleHandleIndex(module),
+        name: IdentifierIndex(name),
+        abilities,
+        type_parameters,
+    };
+    let phantom = StructTypeParameter {
+        constraints: AbilitySet::EMPTY,
+        is_phantom: true,
+    };
+    let drop_store = AbilitySet::singleton(Ability::Drop) | Ability::Store;
+    module.struct_handles = vec![
+        handle(1, 2, AbilitySet::singleton(Ability::Store), vec![phantom]),
+        handle(2, 4, drop_store, vec![]),
+        handle(2, 5, drop_store, vec![]),
+        handle(0, 6, AbilitySet::singleton(Ability::Key), vec![]),
+        handle(0, 7, AbilitySet::singleton(Ability::Store), vec![]),
+    ];
+    let event_handle_field = |name: u16, event: u16| FieldDefinition {
+        name: IdentifierIndex(name),
+        signature: TypeSignature(SignatureToken::StructInstantiation(
+            StructHandleIndex(0),
+            vec![SignatureToken::Struct(StructHandleIndex(event))],
+        )),
+    };
+    module.struct_defs = vec![
+        StructDefinition {
+            struct_handle: StructHandleIndex(3),
+            field_information: StructFieldInformation::Declared(vec![event_handle_field(8, 1)]),
+        },
+        StructDefinition {
+            struct_handle: StructHandleIndex(4),
+            field_information: StructFieldInformation::Declared(vec![event_handle_field(9, 2)]),
+        },
+    ];
+    module
+}
+
+#[test]
+fn test_event_handle_types_of_key_structs() {
+    let events = extract_event_handle_types(&vault_module()).unwrap();
+    let cafe = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    // the event type is declared in another module; the handle of the non-`key` Holder is ignored
+    assert_eq!(
+        events,
+        HashSet::from([(cafe, "events".to_string(), "Deposit".to_string())])
+    );
+}
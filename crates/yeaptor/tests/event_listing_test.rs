@@ -0,0 +1,103 @@
+use aptos_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+use yeaptor::event_definition::{EventDefinition, EventVersion};
+use yeaptor::event_listing::{list_events, render_event_table};
+use yeaptor::event_table_mapping::{EventTableMappings, TableMappingRule};
+
+fn event(
+    module_name: &str,
+    name: &str,
+    fields: &[&str],
+    version: EventVersion,
+    nested: usize,
+) -> EventDefinition {
+    EventDefinition {
+        package_name: "yeap-borrow".to_string(),
+        module_address: AccountAddress::from_hex_literal("0x42").unwrap(),
+        module_name: module_name.to_string(),
+        name: name.to_string(),
+        fields: fields
+            .iter()
+            .map(|field| (field.to_string(), "u64".to_string()))
+            .collect(),
+        version,
+        dependency_of: None,
+        nested_fields: (0..nested)
+            .map(|i| (format!("position.f{}", i), "u64".to_string()))
+            .collect(),
+    }
+}
+
+fn definitions() -> Vec<EventDefinition> {
+    vec![
+        event(
+            "market",
+            "SupplyEvent",
+            &["amount", "market"],
+            EventVersion::V2,
+            0,
+        ),
+        event("market", "BorrowEvent", &["amount"], EventVersion::V2, 2),
+        event("vault", "DepositEvent", &["amount"], EventVersion::V1, 0),
+    ]
+}
+
+#[test]
+fn test_list_events_without_mapping() {
+    let listings = list_events(&definitions(), None);
+    assert_eq!(
+        listings
+            .iter()
+            .map(|listing| listing.event.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "market::BorrowEvent",
+            "market::SupplyEvent",
+            "vault::DepositEvent"
+        ]
+    );
+    assert_eq!(listings[0].nested_fields, 2);
+    assert_eq!(listings[1].fields, 2);
+    assert!(listings.iter().all(|listing| listing.mapped().is_none()));
+
+    let table = render_event_table(&listings);
+    let lines = table.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(!lines[0].contains("STATUS"));
+    assert!(lines[1].contains("1 (+2 nested)"), "{}", table);
+    assert!(lines[3].contains("handle"), "{}", table);
+}
+
+#[test]
+fn test_list_events_with_mapping() {
+    let mapping: EventTableMappings = BTreeMap::from([
+        (
+            "yeap-borrow::market::SupplyEvent".to_string(),
+            vec![
+                TableMappingRule::new("supply"),
+                TableMappingRule::new("activity"),
+            ],
+        ),
+        (
+            "yeap-borrow::vault::DepositEvent".to_string(),
+            vec![TableMappingRule::new("deposits")],
+        ),
+    ]);
+    let listings = list_events(&definitions(), Some(&mapping));
+    assert_eq!(listings[0].mapped(), Some(false));
+    assert_eq!(
+        listings[1].tables,
+        Some(vec!["supply".to_string(), "activity".to_string()])
+    );
+
+    let table = render_event_table(&listings);
+    let lines = table.lines().collect::<Vec<_>>();
+    assert!(lines[0].ends_with("STATUS"));
+    assert!(lines[1].ends_with("unmapped"));
+    assert!(lines[2].ends_with("mapped: supply, activity"));
+    assert_eq!(lines.last(), Some(&"2 of 3 events mapped"));
+
+    let json = serde_json::to_value(&listings[2]).unwrap();
+    assert_eq!(json["version"], "v1");
+    assert_eq!(json["tables"], serde_json::json!(["deposits"]));
+}
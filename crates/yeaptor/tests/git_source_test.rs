@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+use yeaptor::config::parse_config;
+use yeaptor::git_source::fetch_git_sources;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=yeaptor",
+            "-c",
+            "user.email=yeaptor@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+#[test]
+fn test_fetch_git_sources() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("markets");
+    fs::create_dir_all(repo.join("packages/eth")).unwrap();
+    fs::write(
+        repo.join("packages/eth/Move.toml"),
+        "[package]\nname = \"EthMarket\"\n",
+    )
+    .unwrap();
+    git(&repo, &["init", "--quiet"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "markets"]);
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(&repo)
+        .output()
+        .unwrap();
+    let rev = String::from_utf8(output.stdout).unwrap().trim().to_string();
+
+    let mut config = parse_config(&format!(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "markets"
+packages = [
+    {{ address_name = "eth_market", git = {{ url = "file://{0}", rev = "{1}", subdir = "packages/eth" }} }},
+    {{ address_name = "eth_market_again", git = {{ url = "file://{0}", rev = "{1}", subdir = "packages/eth" }} }},
+]
+"#,
+        repo.display(),
+        rev
+    ))
+    .unwrap();
+    let cache = dir.path().join("cache");
+    config.resolve_git_sources(&cache).unwrap();
+    let package_dir = config.deployments[0].packages[0].path.clone();
+    assert!(package_dir.starts_with(&cache));
+    assert!(package_dir.ends_with("packages/eth"));
+
+    let fetched = fetch_git_sources(&config, &cache).unwrap();
+    assert_eq!(fetched.len(), 1);
+    assert!(
+        fetched[0]
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("markets-")
+    );
+    assert!(package_dir.join("Move.toml").is_file());
+
+    // cached: nothing fetched again
+    assert!(fetch_git_sources(&config, &cache).unwrap().is_empty());
+}
+
+#[test]
+fn test_fetch_git_sources_unknown_revision() {
+    let dir = tempdir().unwrap();
+    let repo = dir.path().join("empty");
+    fs::create_dir_all(&repo).unwrap();
+    git(&repo, &["init", "--quiet"]);
+    let mut config = parse_config(&format!(
+        r#"
+format_version = 1
+yeaptor_address = "0xa"
+
+[[deployments]]
+publisher = "0xb"
+seed = "s"
+packages = [{{ address_name = "core", git = {{ url = "file://{}", rev = "v9.9.9" }} }}]
+"#,
+        repo.display()
+    ))
+    .unwrap();
+    let cache = dir.path().join("cache");
+    config.resolve_git_sources(&cache).unwrap();
+    let err = fetch_git_sources(&config, &cache).unwrap_err().to_string();
+    assert!(err.contains("failed to fetch"), "{}", err);
+    // no partial checkout left behind
+    assert!(fs::read_dir(&cache).unwrap().next().is_none());
+}
@@ -0,0 +1,46 @@
+use serde_json::Value as JsonValue;
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::load_schema_snapshot;
+use yeaptor::grafana::grafana_dashboard;
+
+fn raw_sql(panel: &JsonValue) -> &str {
+    panel["targets"][0]["rawSql"].as_str().unwrap()
+}
+
+#[test]
+fn test_grafana_dashboard() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u128" }
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+event_index = { column_type = "event_index", type = "event_metadata", is_primary_key = true }
+
+[tables.market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let dashboard = grafana_dashboard(&load_schema_snapshot(file.path()).unwrap(), "Yeap").unwrap();
+    assert_eq!(dashboard["title"], "Yeap");
+    assert_eq!(dashboard["templating"]["list"][0]["name"], "datasource");
+    let panels = dashboard["panels"].as_array().unwrap();
+    assert_eq!(panels.len(), 5);
+    assert_eq!(panels[0]["datasource"]["uid"], "${datasource}");
+    assert!(raw_sql(&panels[0]).contains("FROM \"yeaptor_processor_status\""));
+    assert!(raw_sql(&panels[2]).contains("\"relname\" IN ('borrow_position', 'market')"));
+    assert_eq!(
+        raw_sql(&panels[3]),
+        "SELECT * FROM \"borrow_position\" ORDER BY \"transaction_version\" DESC, \
+         \"event_index\" DESC LIMIT 20"
+    );
+    // without metadata columns, newest by primary key
+    assert_eq!(
+        raw_sql(&panels[4]),
+        "SELECT * FROM \"market\" ORDER BY \"market\" DESC LIMIT 20"
+    );
+    // panels do not overlap
+    assert_eq!(panels[4]["gridPos"]["y"], 22);
+}
@@ -0,0 +1,45 @@
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::load_schema_snapshot;
+use yeaptor::graphql::graphql_schema;
+
+#[test]
+fn test_graphql_schema() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+market = { column_type = "address" }
+amount = { column_type = "u128", is_nullable = true }
+tags = { column_type = "string", is_vec = true, is_index = true }
+paused = { column_type = "bool", is_index = true }
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+
+[[tables.borrow_position.indexes]]
+columns = ["market"]
+
+[tables.market_log.columns]
+note = { column_type = "string" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let sdl = graphql_schema(&load_schema_snapshot(file.path()).unwrap()).unwrap();
+    assert!(sdl.contains(
+        "type BorrowPosition {\n  amount: BigInt\n  market: String!\n  owner: String!\n  paused: Boolean!\n  tags: [String!]!\n  transaction_version: BigInt!\n}\n"
+    ));
+    // primary key, is_index and declared index columns; vectors are left out
+    assert!(sdl.contains(
+        "input BorrowPositionFilter {\n  market: StringFilter\n  owner: StringFilter\n  paused: BooleanFilter\n  transaction_version: BigIntFilter\n}\n"
+    ));
+    assert!(sdl.contains("input BigIntFilter {\n  eq: BigInt\n  neq: BigInt\n  gt: BigInt\n"));
+    assert!(sdl.contains("input BooleanFilter {\n  eq: Boolean\n}\n"));
+    assert!(!sdl.contains("MarketLogFilter"));
+    assert!(sdl.ends_with(
+        r#"type Query {
+  borrow_position(where: BorrowPositionFilter, order_by: [BorrowPositionOrderBy!], limit: Int = 100, offset: Int = 0): [BorrowPosition!]!
+  borrow_position_by_pk(owner: String!, transaction_version: BigInt!): BorrowPosition
+  market_log(limit: Int = 100, offset: Int = 0): [MarketLog!]!
+}
+"#
+    ));
+}
@@ -0,0 +1,9 @@
+use yeaptor::logging::log_level;
+
+#[test]
+fn test_log_level() {
+    assert_eq!(log_level(0), "warn");
+    assert_eq!(log_level(1), "info");
+    assert_eq!(log_level(2), "debug");
+    assert_eq!(log_level(5), "trace");
+}
@@ -0,0 +1,654 @@
+use serde_json::json;
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::Builder;
+use yeaptor::mapping_engine::{
+    EntryFunctionCall, EventRecord, MappingEngine, Row, TableItemRecord, TransactionRecord,
+    load_transactions_jsonl,
+};
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_runtime::rows_to_csv;
+
+const CONFIG: &str = r#"
+spec_identifier:
+  spec_creator: yeap
+  spec_name: lending
+  spec_version: "1"
+common_config:
+  network: testnet
+  starting_version: 0
+  starting_version_override: null
+custom_config:
+  db_schema:
+    borrow:
+      borrower:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      amount:
+        column_type: { column_type: u128, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      fee:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      referrer:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: true
+        is_primary_key: false
+        is_vec: false
+      tags:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: true
+      source:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      paused:
+        column_type: { column_type: bool, type: move_type }
+        default_value: false
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      total:
+        column_type: { column_type: u128, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      event_index:
+        column_type: { column_type: event_index, type: event_metadata }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      transaction_version:
+        column_type: { column_type: version, type: transaction_metadata }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      transaction_timestamp:
+        column_type: { column_type: timestamp, type: transaction_metadata }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+  events:
+    0x42::pool::BorrowEvent:
+      constant_values:
+        - { column: source, table: borrow, value: pool }
+      event_fields:
+        $.borrower:
+          - { column: borrower, table: borrow }
+        $.amount:
+          - { column: amount, table: borrow }
+        $.fee.value:
+          - { column: fee, table: borrow }
+        $.referrer:
+          - { column: referrer, table: borrow }
+        $.tags:
+          - { column: tags, table: borrow }
+      event_metadata:
+        event_index:
+          - { column: event_index, table: borrow }
+      filters:
+        - { table: borrow, path: $.amount, op: gt, value: 100 }
+  transaction_metadata:
+    version:
+      - { column: transaction_version, table: borrow }
+    timestamp:
+      - { column: transaction_timestamp, table: borrow }
+  payload:
+    computed_columns:
+      - event: 0x42::pool::BorrowEvent
+        table: borrow
+        column: total
+        function: add
+        args: [{ path: $.amount }, { path: $.fee.value }]
+"#;
+
+fn config() -> ProcessorConfig {
+    serde_yaml::from_str(CONFIG).unwrap()
+}
+
+fn borrow_event(event_type: &str, amount: &str) -> EventRecord {
+    EventRecord {
+        account_address: "0x42".to_string(),
+        creation_number: 0,
+        sequence_number: 7,
+        event_type: event_type.to_string(),
+        data: json!({
+            "borrower": "0x1",
+            "amount": amount,
+            "fee": { "value": "5" },
+            "referrer": { "vec": ["0x2"] },
+            "tags": ["a", "b"],
+        }),
+    }
+}
+
+fn transaction(events: Vec<EventRecord>) -> TransactionRecord {
+    TransactionRecord {
+        version: 12,
+        block_height: 3,
+        epoch: 1,
+        timestamp: 1_700_000_000_123_456,
+        events,
+        payload: None,
+        table_items: Vec::new(),
+    }
+}
+
+#[test]
+fn test_map_transaction() {
+    let engine = MappingEngine::new(&config()).unwrap();
+    let rows = engine
+        .map_transaction(&transaction(vec![
+            borrow_event("0x42::pool::BorrowEvent", "1000"),
+            borrow_event("0x1::coin::DepositEvent", "1000"),
+        ]))
+        .unwrap();
+
+    let expected: BTreeMap<String, Option<String>> = [
+        ("amount", Some("1000")),
+        ("borrower", Some("0x1")),
+        ("event_index", Some("0")),
+        ("fee", Some("5")),
+        ("paused", Some("false")),
+        ("referrer", Some("0x2")),
+        ("source", Some("pool")),
+        ("tags", Some(r#"["a","b"]"#)),
+        ("total", Some("1005")),
+        ("transaction_timestamp", Some("2023-11-14 22:13:20.123456")),
+        ("transaction_version", Some("12")),
+    ]
+    .into_iter()
+    .map(|(column, value)| (column.to_string(), value.map(str::to_string)))
+    .collect();
+    assert_eq!(
+        rows,
+        vec![Row {
+            table: "borrow".to_string(),
+            event: "0x42::pool::BorrowEvent".to_string(),
+            values: expected
+        }]
+    );
+}
+
+#[test]
+fn test_map_transaction_matches_long_addresses_and_generic_instances() {
+    let engine = MappingEngine::new(&config()).unwrap();
+    let long = format!(
+        "0x{:0>64}::pool::BorrowEvent<0x1::aptos_coin::AptosCoin>",
+        "42"
+    );
+    let rows = engine
+        .map_transaction(&transaction(vec![borrow_event(&long, "1000")]))
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_map_transaction_filters_and_options() {
+    let engine = MappingEngine::new(&config()).unwrap();
+    // `amount > 100` compares numerically, so "99" is rejected even though "99" > "100" as text
+    let rows = engine
+        .map_transaction(&transaction(vec![borrow_event(
+            "0x42::pool::BorrowEvent",
+            "99",
+        )]))
+        .unwrap();
+    assert!(rows.is_empty());
+
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "1000");
+    event.data["referrer"] = json!({ "vec": [] });
+    let rows = engine.map_transaction(&transaction(vec![event])).unwrap();
+    assert_eq!(rows[0].values["referrer"], None);
+}
+
+#[test]
+fn test_map_transaction_rejects_missing_values() {
+    let engine = MappingEngine::new(&config()).unwrap();
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "1000");
+    event.data.as_object_mut().unwrap().remove("borrower");
+    let err = engine
+        .map_transaction(&transaction(vec![event]))
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("no value for non-nullable column borrower"));
+
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "1000");
+    event.data["fee"]["value"] = json!("not-a-number");
+    let err = engine
+        .map_transaction(&transaction(vec![event]))
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("computed column total"));
+}
+
+#[test]
+fn test_map_transaction_hashes_arguments_with_their_lengths() {
+    let hashed = |args: &str| {
+        let yaml = CONFIG.replace(
+            "function: add\n        args: [{ path: $.amount }, { path: $.fee.value }]",
+            &format!("function: hash\n        args: {}", args),
+        );
+        let config: ProcessorConfig = serde_yaml::from_str(&yaml).unwrap();
+        let engine = MappingEngine::new(&config).unwrap();
+        let event = borrow_event("0x42::pool::BorrowEvent", "1000");
+        let rows = engine.map_transaction(&transaction(vec![event])).unwrap();
+        rows[0].values["total"].clone().unwrap()
+    };
+    let split = hashed(r#"[{ path: $.borrower }, { literal: "23" }]"#);
+    let mut expected = Sha3_256::new();
+    for arg in ["0x1", "23"] {
+        expected.update((arg.len() as u64).to_le_bytes());
+        expected.update(arg);
+    }
+    assert_eq!(split, hex::encode(expected.finalize()));
+    // the same text once concatenated
+    assert_ne!(split, hashed(r#"[{ literal: "0x12" }, { literal: "3" }]"#));
+}
+
+#[test]
+fn test_map_transaction_applies_column_transforms() {
+    let yaml = CONFIG.replace(
+        "column_type: { column_type: u128, type: move_type }\n        is_index: false\n        is_nullable: false",
+        "column_type: { column_type: decimal, type: move_type }\n        is_index: false\n        is_nullable: false",
+    );
+    let mut config: ProcessorConfig = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(
+        config.custom_config.db_schema["borrow"]["amount"]
+            .column_type
+            .column_type,
+        "decimal"
+    );
+    config.custom_config.payload.insert(
+        "column_transforms".to_string(),
+        serde_yaml::from_str(
+            r#"
+- { event: 0x42::pool::BorrowEvent, table: borrow, column: amount, transform: { scale: 8 } }
+- { event: 0x42::pool::BorrowEvent, table: borrow, column: borrower, transform: lowercase }
+- { event: 0x42::pool::BorrowEvent, table: borrow, column: referrer, transform: lowercase }
+"#,
+        )
+        .unwrap(),
+    );
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "150000000");
+    event.data["borrower"] = json!("0xABC");
+    event.data["referrer"] = json!({ "vec": ["0xDEF"] });
+    let rows = engine.map_transaction(&transaction(vec![event])).unwrap();
+    let values = &rows[0].values;
+    assert_eq!(values["amount"].as_deref(), Some("1.5"));
+    assert_eq!(values["borrower"].as_deref(), Some("0xabc"));
+    assert_eq!(values["referrer"].as_deref(), Some("0xdef"));
+
+    for (amount, scaled) in [
+        ("101", "0.00000101"),
+        ("100000000", "1"),
+        ("1234500000000", "12345"),
+    ] {
+        let event = borrow_event("0x42::pool::BorrowEvent", amount);
+        let rows = engine.map_transaction(&transaction(vec![event])).unwrap();
+        assert_eq!(rows[0].values["amount"].as_deref(), Some(scaled));
+    }
+    // the filter `amount > 100` sees the raw amount, not the scaled one
+    let event = borrow_event("0x42::pool::BorrowEvent", "99");
+    assert!(
+        engine
+            .map_transaction(&transaction(vec![event]))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_map_transaction_hex_decodes_bytes() {
+    let mut config = config();
+    config.custom_config.payload.insert(
+        "column_transforms".to_string(),
+        serde_yaml::from_str(
+            "[{ event: 0x42::pool::BorrowEvent, table: borrow, column: borrower, transform: hex_decode }]",
+        )
+        .unwrap(),
+    );
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "1000");
+    event.data["borrower"] = json!("0x555344432e65");
+    let rows = engine.map_transaction(&transaction(vec![event])).unwrap();
+    assert_eq!(rows[0].values["borrower"].as_deref(), Some("USDC.e"));
+
+    let mut event = borrow_event("0x42::pool::BorrowEvent", "1000");
+    event.data["borrower"] = json!("0xff");
+    let err = engine
+        .map_transaction(&transaction(vec![event]))
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("transform of column borrower: `0xff` is not UTF-8 text")
+    );
+}
+
+#[test]
+fn test_mapping_engine_rejects_unknown_tables() {
+    let yaml = CONFIG.replace(
+        "- { column: source, table: borrow, value: pool }",
+        "- { column: source, table: repay, value: pool }",
+    );
+    let config: ProcessorConfig = serde_yaml::from_str(&yaml).unwrap();
+    let err = MappingEngine::new(&config).err().unwrap();
+    assert!(err.to_string().contains("mapped to unknown table repay"));
+}
+
+const SUPPLY_CONFIG: &str = r#"
+spec_identifier:
+  spec_creator: yeap
+  spec_name: lending
+  spec_version: "1"
+common_config:
+  network: testnet
+  starting_version: 0
+  starting_version_override: null
+custom_config:
+  db_schema:
+    supply_call:
+      user:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      coin:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      amount:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      market:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      action:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      transaction_version:
+        column_type: { column_type: version, type: transaction_metadata }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+  transaction_metadata:
+    version:
+      - { column: transaction_version, table: supply_call }
+  payload:
+    entry_functions:
+      - function: 0x42::market::supply
+        payload_fields:
+          $.sender:
+            - { column: user, table: supply_call }
+          $.type_arguments.0:
+            - { column: coin, table: supply_call }
+          $.arguments.0.inner:
+            - { column: market, table: supply_call }
+          $.arguments.1:
+            - { column: amount, table: supply_call }
+        constant_values:
+          - { column: action, table: supply_call, value: supply }
+"#;
+
+#[test]
+fn test_map_entry_function_call() {
+    let config: ProcessorConfig = serde_yaml::from_str(SUPPLY_CONFIG).unwrap();
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut txn = transaction(Vec::new());
+    txn.payload = Some(EntryFunctionCall {
+        sender: "0x7".to_string(),
+        function: format!("0x{:0>64}::market::supply", "42"),
+        type_arguments: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+        arguments: vec![json!({ "inner": "0x9" }), json!("250")],
+    });
+    let rows = engine.map_transaction(&txn).unwrap();
+
+    let expected: BTreeMap<String, Option<String>> = [
+        ("action", Some("supply")),
+        ("amount", Some("250")),
+        ("coin", Some("0x1::aptos_coin::AptosCoin")),
+        ("market", Some("0x9")),
+        ("transaction_version", Some("12")),
+        ("user", Some("0x7")),
+    ]
+    .into_iter()
+    .map(|(column, value)| (column.to_string(), value.map(str::to_string)))
+    .collect();
+    assert_eq!(
+        rows,
+        vec![Row {
+            table: "supply_call".to_string(),
+            event: "0x42::market::supply".to_string(),
+            values: expected
+        }]
+    );
+
+    // other functions, and transactions without an entry function payload, write nothing
+    txn.payload.as_mut().unwrap().function = "0x42::market::withdraw".to_string();
+    assert!(engine.map_transaction(&txn).unwrap().is_empty());
+    txn.payload = None;
+    assert!(engine.map_transaction(&txn).unwrap().is_empty());
+}
+
+#[test]
+fn test_map_entry_function_call_rejects_missing_arguments() {
+    let config: ProcessorConfig = serde_yaml::from_str(SUPPLY_CONFIG).unwrap();
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut txn = transaction(Vec::new());
+    txn.payload = Some(EntryFunctionCall {
+        sender: "0x7".to_string(),
+        function: "0x42::market::supply".to_string(),
+        type_arguments: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+        arguments: vec![json!({ "inner": "0x9" })],
+    });
+    let err = engine.map_transaction(&txn).unwrap_err();
+    assert!(format!("{:#}", err).contains("no value for non-nullable column amount"));
+}
+
+const POSITION_CONFIG: &str = r#"
+spec_identifier:
+  spec_creator: yeap
+  spec_name: lending
+  spec_version: "1"
+common_config:
+  network: testnet
+  starting_version: 0
+  starting_version_override: null
+custom_config:
+  db_schema:
+    position:
+      user:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      collateral:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      source:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+  payload:
+    table_items:
+      - value_type: 0x42::market::Position
+        key_type: address
+        payload_fields:
+          $.key:
+            - { column: user, table: position }
+          $.value.collateral:
+            - { column: collateral, table: position }
+        constant_values:
+          - { column: source, table: position, value: table }
+"#;
+
+fn position_write(handle: &str, key_type: &str, value_type: &str) -> TableItemRecord {
+    TableItemRecord {
+        handle: handle.to_string(),
+        key_type: key_type.to_string(),
+        key: json!("0x7"),
+        value_type: value_type.to_string(),
+        value: json!({ "collateral": "300", "debt": "10" }),
+    }
+}
+
+#[test]
+fn test_map_table_item_writes() {
+    let config: ProcessorConfig = serde_yaml::from_str(POSITION_CONFIG).unwrap();
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut txn = transaction(Vec::new());
+    txn.table_items = vec![
+        position_write(
+            "0xabc",
+            "address",
+            &format!("0x{:0>64}::market::Position", "42"),
+        ),
+        // other key and value types are not mapped
+        position_write("0xabc", "u64", "0x42::market::Position"),
+        position_write("0xabc", "address", "0x42::market::Market"),
+    ];
+    let rows = engine.map_transaction(&txn).unwrap();
+
+    let expected: BTreeMap<String, Option<String>> = [
+        ("collateral", Some("300")),
+        ("source", Some("table")),
+        ("user", Some("0x7")),
+    ]
+    .into_iter()
+    .map(|(column, value)| (column.to_string(), value.map(str::to_string)))
+    .collect();
+    assert_eq!(
+        rows,
+        vec![Row {
+            table: "position".to_string(),
+            event: "0x42::market::Position".to_string(),
+            values: expected
+        }]
+    );
+}
+
+#[test]
+fn test_map_table_item_writes_of_one_table() {
+    let mut config: ProcessorConfig = serde_yaml::from_str(POSITION_CONFIG).unwrap();
+    let mappings = config.custom_config.payload.get_mut("table_items").unwrap();
+    mappings[0]["handle"] = serde_yaml::Value::String(format!("0x{:0>64}", "abc"));
+    let engine = MappingEngine::new(&config).unwrap();
+    let mut txn = transaction(Vec::new());
+    txn.table_items = vec![
+        position_write("0xabc", "address", "0x42::market::Position"),
+        position_write("0xdef", "address", "0x42::market::Position"),
+    ];
+    let rows = engine.map_transaction(&txn).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values["collateral"], Some("300".to_string()));
+}
+
+#[test]
+fn test_transaction_record_from_json() {
+    let record: TransactionRecord = serde_json::from_str(
+        r#"{"version": 5, "block_height": 1, "epoch": 1, "timestamp": 0,
+            "events": [{"account_address": "0x42", "creation_number": 0, "sequence_number": 0,
+                        "type": "0x42::pool::BorrowEvent", "data": {}}]}"#,
+    )
+    .unwrap();
+    assert_eq!(record.events[0].event_type, "0x42::pool::BorrowEvent");
+}
+
+#[test]
+fn test_replay_transactions_to_csv() {
+    let borrow = |version: u64, borrower: &str, tags: &str| {
+        format!(
+            r#"{{"version": {}, "block_height": 1, "epoch": 1, "timestamp": 1700000000000000, "events": [{{"account_address": "0x42", "creation_number": 0, "sequence_number": 0, "type": "0x42::pool::BorrowEvent", "data": {{"borrower": "{}", "amount": "500", "fee": {{"value": "1"}}, "referrer": {{"vec": []}}, "tags": {}}}}}]}}"#,
+            version, borrower, tags
+        )
+    };
+    let content = format!(
+        "{}\n\n{}\n{{\"version\": 3, \"block_height\": 1, \"epoch\": 1, \"timestamp\": 0}}\n",
+        borrow(1, "0x1", r#"["a"]"#),
+        borrow(2, "0x2", "[]")
+    );
+    let file = Builder::new().suffix(".jsonl").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let transactions = load_transactions_jsonl(file.path()).unwrap();
+    assert_eq!(transactions.len(), 3);
+
+    let config = config();
+    let engine = MappingEngine::new(&config).unwrap();
+    let rows = engine.map_transactions(&transactions).unwrap();
+    let files = rows_to_csv(&config.custom_config.db_schema, &rows).unwrap();
+    assert_eq!(
+        files["borrow"],
+        "amount,borrower,event_index,fee,paused,referrer,source,tags,total,transaction_timestamp,transaction_version\n\
+         500,0x1,0,1,false,,pool,\"[\"\"a\"\"]\",501,2023-11-14 22:13:20.000000,1\n\
+         500,0x2,0,1,false,,pool,[],501,2023-11-14 22:13:20.000000,2\n"
+    );
+}
+
+#[test]
+fn test_load_transactions_jsonl_reports_line() {
+    let file = Builder::new().suffix(".jsonl").tempfile().unwrap();
+    fs::write(
+        file.path(),
+        "{\"version\": 1, \"block_height\": 1, \"epoch\": 1, \"timestamp\": 0}\nnot json\n",
+    )
+    .unwrap();
+    let err = load_transactions_jsonl(file.path()).unwrap_err();
+    assert!(err.to_string().contains("invalid transaction on line 2"));
+}
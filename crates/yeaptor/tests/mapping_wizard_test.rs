@@ -0,0 +1,223 @@
+use aptos_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+use std::path::Path;
+use yeaptor::event_definition::{EventDefinition, EventVersion};
+use yeaptor::event_table_mapping::{
+    EventTableMappings, TableMappingRule, parse_event_table_mappings, render_event_table_mappings,
+};
+use yeaptor::mapping_wizard::{auto_mapping, run_mapping_wizard, suggest_tables};
+use yeaptor::processor_config::{ColumnSpec, ColumnTypeSpec, TableSchema};
+
+const SUPPLY: &str = "yeap-borrow::market::SupplyEvent";
+
+fn event(name: &str, fields: &[(&str, &str)]) -> EventDefinition {
+    EventDefinition {
+        package_name: "yeap-borrow".to_string(),
+        module_address: AccountAddress::from_hex_literal("0x42").unwrap(),
+        module_name: "market".to_string(),
+        name: name.to_string(),
+        fields: fields
+            .iter()
+            .map(|(field, move_type)| (field.to_string(), move_type.to_string()))
+            .collect(),
+        version: EventVersion::V2,
+        dependency_of: None,
+        nested_fields: BTreeMap::new(),
+    }
+}
+
+fn definitions() -> Vec<EventDefinition> {
+    vec![
+        event(
+            "SupplyEvent",
+            &[
+                ("market", "address"),
+                ("user", "address"),
+                ("amount", "u64"),
+                ("collateral_asset", "address"),
+            ],
+        ),
+        event("PriceEvent", &[("price", "u64")]),
+        event("PausedEvent", &[("paused", "bool")]),
+    ]
+}
+
+fn table(columns: &[&str]) -> TableSchema {
+    columns
+        .iter()
+        .map(|column| {
+            (
+                column.to_string(),
+                ColumnSpec {
+                    column_type: ColumnTypeSpec {
+                        column_type: "string".to_string(),
+                        r#type: "move_type".to_string(),
+                    },
+                    default_value: None,
+                    is_index: false,
+                    is_nullable: true,
+                    is_option: false,
+                    is_primary_key: false,
+                    is_vec: false,
+                },
+            )
+        })
+        .collect()
+}
+
+fn tables() -> BTreeMap<String, TableSchema> {
+    BTreeMap::from([
+        (
+            "supply".to_string(),
+            table(&["market", "user", "amounts", "collateralAsset"]),
+        ),
+        (
+            "borrow".to_string(),
+            table(&["market", "user", "transaction_version"]),
+        ),
+        ("oracle_price".to_string(), table(&["price"])),
+    ])
+}
+
+fn supply_rule() -> TableMappingRule {
+    let mut rule = TableMappingRule::new("supply");
+    rule.rename = BTreeMap::from([
+        ("amount".to_string(), "amounts".to_string()),
+        (
+            "collateral_asset".to_string(),
+            "collateralAsset".to_string(),
+        ),
+    ]);
+    rule
+}
+
+fn wizard(input: &str, allow_exclude: bool) -> (EventTableMappings, String) {
+    let mut output = Vec::new();
+    let mapping = run_mapping_wizard(
+        &definitions(),
+        &tables(),
+        allow_exclude,
+        &mut input.as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    (mapping, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_suggest_tables() {
+    let suggestions = suggest_tables(&definitions()[0], &tables());
+    let ranked = suggestions
+        .iter()
+        .map(|s| (s.table.as_str(), s.score))
+        .collect::<Vec<_>>();
+    assert_eq!(ranked, vec![("supply", 100), ("borrow", 25)]);
+    assert_eq!(
+        suggestions[0].columns,
+        BTreeMap::from([
+            ("amount".to_string(), "amounts".to_string()),
+            (
+                "collateral_asset".to_string(),
+                "collateralAsset".to_string()
+            ),
+            ("market".to_string(), "market".to_string()),
+            ("user".to_string(), "user".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_auto_mapping() {
+    let mapping = auto_mapping(&definitions(), &tables());
+    assert_eq!(
+        mapping,
+        EventTableMappings::from([
+            (SUPPLY.to_string(), vec![supply_rule()]),
+            (
+                "yeap-borrow::market::PriceEvent".to_string(),
+                vec![TableMappingRule::new("oracle_price")]
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_run_mapping_wizard() {
+    // supply and borrow for the first event, accepting supply and editing borrow, then quit
+    let (mapping, output) = wizard("1,borrow\ny\ne\n\n\n-\n\nusr\nuser\nq\n", false);
+    assert_eq!(
+        mapping,
+        EventTableMappings::from([(
+            SUPPLY.to_string(),
+            vec![supply_rule(), TableMappingRule::new("borrow")]
+        )])
+    );
+    assert!(output.contains("[1/3] yeap-borrow::market::SupplyEvent"));
+    assert!(output.contains("  1) supply (100%): amount -> amounts"));
+    assert!(output.contains("column market exists, so a CSV mapping writes market to it"));
+    assert!(output.contains("no column usr; did you mean user?"));
+    assert!(!output.contains("[3/3]"));
+
+    // structured mappings can leave a field out; the end of the input ends the walk-through
+    let (mapping, _) = wizard("borrow\ne\n\n\n-\n\n", true);
+    let mut borrow = TableMappingRule::new("borrow");
+    borrow.exclude = vec!["market".to_string()];
+    assert_eq!(
+        mapping,
+        EventTableMappings::from([(SUPPLY.to_string(), vec![borrow])])
+    );
+
+    let (mapping, output) = wizard("s\nsupply\n\n\n", false);
+    assert_eq!(
+        mapping,
+        EventTableMappings::from([(
+            "yeap-borrow::market::PriceEvent".to_string(),
+            vec![TableMappingRule::new("supply")]
+        )])
+    );
+    assert!(output.contains("[3/3] yeap-borrow::market::PausedEvent"));
+
+    // no answer at all is an error rather than an empty mapping
+    assert!(
+        run_mapping_wizard(
+            &definitions(),
+            &tables(),
+            false,
+            &mut "".as_bytes(),
+            &mut Vec::new(),
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_render_event_table_mappings() {
+    let mapping = EventTableMappings::from([(SUPPLY.to_string(), vec![supply_rule()])]);
+    for file in [
+        "event_mapping.csv",
+        "event_mapping.toml",
+        "event_mapping.yaml",
+    ] {
+        let path = Path::new(file);
+        let rendered = render_event_table_mappings(path, &mapping).unwrap();
+        assert_eq!(
+            parse_event_table_mappings(path, &rendered).unwrap(),
+            mapping,
+            "{}",
+            file
+        );
+    }
+    assert_eq!(
+        render_event_table_mappings(Path::new("event_mapping.csv"), &mapping).unwrap(),
+        "event,table\n\
+         yeap-borrow::market::SupplyEvent,supply\n\
+         yeap-borrow::market::SupplyEvent::amount,supply::amounts\n\
+         yeap-borrow::market::SupplyEvent::collateral_asset,supply::collateralAsset\n"
+    );
+
+    let mut excluding = supply_rule();
+    excluding.exclude = vec!["user".to_string()];
+    let mapping = EventTableMappings::from([(SUPPLY.to_string(), vec![excluding])]);
+    assert!(render_event_table_mappings(Path::new("event_mapping.csv"), &mapping).is_err());
+    assert!(render_event_table_mappings(Path::new("event_mapping.toml"), &mapping).is_ok());
+}
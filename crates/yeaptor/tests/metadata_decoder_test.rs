@@ -0,0 +1,91 @@
+mod common;
+
+use aptos_types::account_address::AccountAddress;
+use common::MetadataBuilder;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::metadata_decoder::{decode_package_metadata, read_metadata_input};
+
+const MANIFEST: &str = "[package]\nname = \"core\"\nversion = \"1.0.0\"\n";
+const SOURCE: &str = "module 0x1::pool {}\n";
+
+/// BCS of a `PackageMetadata` named `name` with a zipped manifest, one module with its zipped
+/// source and a dependency on `0x1::AptosFramework`
+fn metadata(name: &str) -> Vec<u8> {
+    MetadataBuilder::new(name)
+        .upgrade_policy(2)
+        .upgrade_number(3)
+        .manifest(MANIFEST)
+        .module("pool", SOURCE, vec![1, 2, 3])
+        .dependency(AccountAddress::ONE, "AptosFramework")
+        .bcs()
+}
+
+#[test]
+fn test_decode_package_metadata() {
+    let packages = decode_package_metadata(&metadata("core"), false).unwrap();
+    assert_eq!(packages.len(), 1);
+    let core = &packages[0];
+    assert_eq!(core.name, "core");
+    assert_eq!(core.upgrade_policy, "immutable");
+    assert_eq!(core.upgrade_number, 3);
+    assert_eq!(core.source_digest, "C0FFEE");
+    assert_eq!(core.manifest, MANIFEST);
+    assert_eq!(core.modules[0].name, "pool");
+    assert_eq!(core.modules[0].source, None);
+    assert!(core.modules[0].source_size > 0);
+    assert_eq!(core.modules[0].source_map_size, 3);
+    assert_eq!(core.deps[0].package_name, "AptosFramework");
+    assert_eq!(
+        core.deps[0].account,
+        AccountAddress::ONE.to_standard_string()
+    );
+
+    let with_sources = decode_package_metadata(&metadata("core"), true).unwrap();
+    assert_eq!(with_sources[0].modules[0].source.as_deref(), Some(SOURCE));
+
+    let json = serde_json::to_value(&packages[0]).unwrap();
+    assert!(json["modules"][0].get("source").is_none());
+}
+
+#[test]
+fn test_decode_package_registry() {
+    // a vector of two packages: its length, then each of them
+    let mut registry = vec![2];
+    registry.extend(metadata("core"));
+    registry.extend(metadata("vault"));
+    let packages = decode_package_metadata(&registry, false).unwrap();
+    assert_eq!(
+        packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        ["core", "vault"]
+    );
+
+    let err = format!("{:#}", decode_package_metadata(&[1, 2], false).unwrap_err());
+    assert!(
+        err.contains("neither a PackageMetadata nor a PackageRegistry"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_read_metadata_input() {
+    let bytes = metadata("core");
+    let encoded = format!("0x{}", hex::encode(&bytes));
+    assert_eq!(read_metadata_input(&encoded).unwrap(), bytes);
+    assert_eq!(read_metadata_input(&encoded[2..]).unwrap(), bytes);
+
+    let dir = tempdir().unwrap();
+    let binary = dir.path().join("metadata.bcs");
+    fs::write(&binary, &bytes).unwrap();
+    assert_eq!(
+        read_metadata_input(binary.to_str().unwrap()).unwrap(),
+        bytes
+    );
+    let text = dir.path().join("metadata.hex");
+    fs::write(&text, format!("\"{}\"\n", encoded)).unwrap();
+    assert_eq!(read_metadata_input(text.to_str().unwrap()).unwrap(), bytes);
+
+    let err = read_metadata_input("not-hex").unwrap_err().to_string();
+    assert!(err.contains("neither a file nor hex"), "{}", err);
+}
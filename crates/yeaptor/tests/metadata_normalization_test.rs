@@ -0,0 +1,58 @@
+mod common;
+
+use aptos_framework::natives::code::PackageMetadata;
+use aptos_framework::unzip_metadata_str;
+use common::MetadataBuilder;
+use yeaptor::metadata_normalization::normalize_metadata;
+
+/// `PackageMetadata` of `core` with one module
+fn metadata(manifest: &str, source: &str, source_map: Vec<u8>) -> PackageMetadata {
+    MetadataBuilder::new("core")
+        .manifest(manifest)
+        .module("pool", source, source_map)
+        .build()
+}
+
+#[test]
+fn test_normalize_metadata() {
+    let mut windows = metadata(
+        "[package]\r\nname = \"core\"\r\n",
+        "module 0x1::pool {\r\n}\r\n",
+        vec![1, 2, 3],
+    );
+    let mut unix = metadata(
+        "[package]\nname = \"core\"\n",
+        "module 0x1::pool {\n}\n",
+        vec![4, 5],
+    );
+    assert_ne!(
+        bcs::to_bytes(&windows).unwrap(),
+        bcs::to_bytes(&unix).unwrap()
+    );
+
+    normalize_metadata(&mut windows).unwrap();
+    normalize_metadata(&mut unix).unwrap();
+    assert_eq!(
+        bcs::to_bytes(&windows).unwrap(),
+        bcs::to_bytes(&unix).unwrap()
+    );
+    assert_eq!(
+        unzip_metadata_str(&windows.manifest).unwrap(),
+        "[package]\nname = \"core\"\n"
+    );
+    assert!(windows.modules[0].source_map.is_empty());
+    assert_eq!(windows.source_digest, "C0FFEE");
+
+    // sparse artifacts carry no sources
+    let mut sparse = metadata("[package]\n", "", Vec::new());
+    normalize_metadata(&mut sparse).unwrap();
+    assert!(sparse.modules[0].source.is_empty());
+}
+
+#[test]
+fn test_normalize_corrupt_metadata() {
+    let mut corrupt = metadata("[package]\n", "", Vec::new());
+    corrupt.modules[0].source = vec![0xde, 0xad];
+    let err = normalize_metadata(&mut corrupt).unwrap_err().to_string();
+    assert_eq!(err, "source of core::pool does not decompress");
+}
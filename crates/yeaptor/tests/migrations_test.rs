@@ -0,0 +1,195 @@
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::{SchemaSnapshot, load_schema_snapshot};
+use yeaptor::migrations::{
+    SCHEMA_STATE_FILE, diesel_migration, diesel_schema_rs, sqlx_migration, write_diesel_migration,
+    write_sqlx_migration,
+};
+
+fn load_toml_schema(content: &str) -> SchemaSnapshot {
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    load_schema_snapshot(file.path()).unwrap()
+}
+
+#[test]
+fn test_diesel_migrations_are_incremental() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("00000000000000_diesel_initial_setup")).unwrap();
+
+    let first = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+"#,
+    );
+    let migration = diesel_migration(dir.path(), &first).unwrap().unwrap();
+    assert_eq!(migration.name, "00000000000001_yeaptor_schema");
+    assert!(
+        migration
+            .up
+            .starts_with("CREATE TABLE IF NOT EXISTS \"borrow_position\"")
+    );
+    assert_eq!(
+        migration.down,
+        "DROP TABLE IF EXISTS \"borrow_position\";\n"
+    );
+    let written = write_diesel_migration(dir.path(), &migration, &first).unwrap();
+    assert_eq!(
+        fs::read_to_string(written.join("up.sql")).unwrap(),
+        migration.up
+    );
+    assert!(dir.path().join(SCHEMA_STATE_FILE).exists());
+
+    // unchanged schema: nothing to write
+    assert!(diesel_migration(dir.path(), &first).unwrap().is_none());
+
+    let second = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u64", is_nullable = true }
+"#,
+    );
+    let migration = diesel_migration(dir.path(), &second).unwrap().unwrap();
+    assert_eq!(migration.name, "00000000000002_yeaptor_schema");
+    assert_eq!(
+        migration.up,
+        "ALTER TABLE \"borrow_position\" ADD COLUMN \"amount\" NUMERIC(20, 0);\n"
+    );
+    assert_eq!(
+        migration.down,
+        "ALTER TABLE \"borrow_position\" DROP COLUMN \"amount\";\n"
+    );
+}
+
+#[test]
+fn test_diesel_schema_rs() {
+    let tables = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+type = { column_type = "u8" }
+tags = { column_type = "string", is_vec = true, is_option = true }
+transaction_version = { column_type = "version", type = "transaction_metadata", is_primary_key = true }
+
+[tables.borrow_market.columns]
+market = { column_type = "address", is_primary_key = true }
+
+[tables.market_log.columns]
+market = { column_type = "address" }
+"#,
+    );
+    let schema_rs = diesel_schema_rs(&tables.tables).unwrap();
+    assert!(schema_rs.contains(
+        r#"diesel::table! {
+    borrow_position (owner, transaction_version) {
+        owner -> Varchar,
+        tags -> Nullable<Array<Text>>,
+        transaction_version -> Int8,
+        #[sql_name = "type"]
+        type_ -> Int2,
+    }
+}
+"#
+    ));
+    assert!(schema_rs.contains("// market_log has no primary key"));
+    assert!(schema_rs.contains(
+        "diesel::allow_tables_to_appear_in_same_query!(\n    borrow_market,\n    borrow_position,\n);\n"
+    ));
+}
+
+#[test]
+fn test_sqlx_migration_adds_nullable_columns_safely() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+"#,
+    );
+    let migration = sqlx_migration(dir.path(), &first, "20250101000000")
+        .unwrap()
+        .unwrap();
+    assert_eq!(migration.name, "20250101000000_yeaptor_schema");
+    let written = write_sqlx_migration(dir.path(), &migration, &first).unwrap();
+    assert_eq!(
+        written,
+        dir.path().join("20250101000000_yeaptor_schema.up.sql")
+    );
+    assert!(
+        dir.path()
+            .join("20250101000000_yeaptor_schema.down.sql")
+            .exists()
+    );
+
+    let nullable = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u64", is_nullable = true, is_index = true }
+"#,
+    );
+    let migration = sqlx_migration(dir.path(), &nullable, "20250102000000")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        migration.up,
+        "ALTER TABLE \"borrow_position\" ADD COLUMN IF NOT EXISTS \"amount\" NUMERIC(20, 0);\n\
+         CREATE INDEX IF NOT EXISTS \"borrow_position_amount_idx\" ON \"borrow_position\" (\"amount\");\n"
+    );
+    assert_eq!(
+        migration.down,
+        "DROP INDEX IF EXISTS \"borrow_position_amount_idx\";\n\
+         ALTER TABLE \"borrow_position\" DROP COLUMN IF EXISTS \"amount\";\n"
+    );
+
+    // a NOT NULL column is not a safe addition and goes through the full migration
+    let required = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u64" }
+"#,
+    );
+    let migration = sqlx_migration(dir.path(), &required, "20250103000000")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        migration.up,
+        "ALTER TABLE \"borrow_position\" ADD COLUMN \"amount\" NUMERIC(20, 0) NOT NULL;\n"
+    );
+}
+
+#[test]
+fn test_schema_state_records_indexes() {
+    let dir = tempfile::tempdir().unwrap();
+    let schema = load_toml_schema(
+        r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+market = { column_type = "address" }
+
+[[tables.borrow_position.indexes]]
+columns = ["market"]
+method = "brin"
+"#,
+    );
+    let migration = diesel_migration(dir.path(), &schema).unwrap().unwrap();
+    assert!(migration.up.ends_with(
+        "CREATE INDEX IF NOT EXISTS \"borrow_position_market_idx\" ON \"borrow_position\" USING brin (\"market\");\n"
+    ));
+    write_diesel_migration(dir.path(), &migration, &schema).unwrap();
+    assert!(diesel_migration(dir.path(), &schema).unwrap().is_none());
+
+    // dropping the index alone is a migration of its own
+    let mut without_index = schema.clone();
+    without_index.options.clear();
+    let migration = diesel_migration(dir.path(), &without_index)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        migration.up,
+        "DROP INDEX IF EXISTS \"borrow_position_market_idx\";\n"
+    );
+}
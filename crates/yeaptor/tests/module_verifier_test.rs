@@ -0,0 +1,21 @@
+use yeaptor::module_verifier::verify_modules;
+
+#[test]
+fn test_verify_modules() {
+    assert!(verify_modules(&[]).into_result().is_ok());
+
+    let diagnostics = verify_modules(&[vec![0xa1, 0x1c, 0xeb, 0x0b], vec![0xde, 0xad]]);
+    assert_eq!(
+        diagnostics
+            .0
+            .iter()
+            .map(|d| d.location.as_str())
+            .collect::<Vec<_>>(),
+        ["modules[0]", "modules[1]"]
+    );
+    assert!(
+        diagnostics.0[1]
+            .message
+            .starts_with("does not deserialize: ")
+    );
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use aptos_types::account_address::AccountAddress;
+use aptos_types::transaction::TransactionPayload;
+use common::planned_package;
+use ed25519_dalek::{Signer, SigningKey};
+use std::collections::BTreeMap;
+use yeaptor::config::{DeployFunction, DeploymentMode};
+use yeaptor::offline_signing::{
+    TransactionSettings, UnsignedBundle, match_signed_transactions, parse_signed_transaction,
+    unsigned_bundle,
+};
+use yeaptor::{DeploymentPlan, PlannedPackage};
+use yeaptor_core::address::object_code_address;
+
+fn settings() -> TransactionSettings {
+    TransactionSettings {
+        chain_id: 2,
+        max_gas_amount: 200_000,
+        gas_unit_price: 100,
+        expiration_timestamp_secs: 1_900_000_000,
+    }
+}
+
+fn package(
+    order: usize,
+    name: &str,
+    mode: DeploymentMode,
+    publisher: AccountAddress,
+) -> PlannedPackage {
+    PlannedPackage {
+        mode,
+        publisher,
+        seed: if mode == DeploymentMode::ResourceAccount {
+            "v1".to_string()
+        } else {
+            String::new()
+        },
+        account: publisher,
+        modules: vec![vec![4, 5]],
+        ..planned_package(order, name)
+    }
+}
+
+fn plan(publisher: AccountAddress, object_sequence_number: u64) -> DeploymentPlan {
+    let mut object = package(2, "vault", DeploymentMode::Object, publisher);
+    object.account = object_code_address(&publisher, object_sequence_number);
+    DeploymentPlan {
+        packages: vec![
+            package(0, "core", DeploymentMode::ResourceAccount, publisher),
+            package(1, "app", DeploymentMode::Account, publisher),
+            object,
+        ],
+        named_addresses: BTreeMap::new(),
+        dependencies: BTreeMap::new(),
+    }
+}
+
+/// BCS of a `SignedTransaction` of `raw_transaction` with an ed25519 authenticator
+fn sign(key: &SigningKey, bundle: &UnsignedBundle, i: usize) -> Vec<u8> {
+    let transaction = &bundle.transactions[i];
+    let message = hex::decode(transaction.signing_message.trim_start_matches("0x")).unwrap();
+    let mut signed = hex::decode(transaction.raw_transaction.trim_start_matches("0x")).unwrap();
+    signed.push(0); // TransactionAuthenticator::Ed25519
+    signed.extend(bcs::to_bytes(key.verifying_key().as_bytes().as_slice()).unwrap());
+    signed.extend(bcs::to_bytes(key.sign(&message).to_bytes().as_slice()).unwrap());
+    signed
+}
+
+#[test]
+fn test_unsigned_bundle() {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    let deploy = DeployFunction::new(AccountAddress::from_hex_literal("0xa").unwrap());
+    let sequence_numbers = BTreeMap::from([(publisher, 7)]);
+    let planned = plan(publisher, 9);
+    let bundle = unsigned_bundle(&planned, &deploy, &sequence_numbers, &settings()).unwrap();
+
+    assert_eq!(bundle.chain_id, 2);
+    let sequence = bundle
+        .transactions
+        .iter()
+        .map(|t| t.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(sequence, [7, 8, 9]);
+    let functions = bundle
+        .transactions
+        .iter()
+        .map(|t| t.function.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        functions,
+        [
+            "0xa::ra_code_deployment::deploy",
+            "0x1::code::publish_package_txn",
+            "0x1::object_code_deployment::publish"
+        ]
+    );
+
+    let raw = bundle.transactions[0].raw_transaction().unwrap();
+    assert_eq!(raw.sender(), publisher);
+    assert_eq!(raw.sequence_number(), 7);
+    assert_eq!(raw.gas_unit_price(), 100);
+    assert_eq!(raw.expiration_timestamp_secs(), 1_900_000_000);
+    let TransactionPayload::EntryFunction(entry_function) = raw.payload() else {
+        panic!("not an entry function");
+    };
+    assert_eq!(
+        entry_function.args(),
+        [
+            bcs::to_bytes("v1".as_bytes()).unwrap(),
+            bcs::to_bytes(&planned.packages[0].metadata).unwrap(),
+            bcs::to_bytes(&vec![vec![4u8, 5]]).unwrap(),
+        ]
+    );
+    // SHA3-256 of `APTOS::RawTransaction`, then the raw transaction
+    assert!(
+        bundle.transactions[0].signing_message.ends_with(
+            bundle.transactions[0]
+                .raw_transaction
+                .trim_start_matches("0x")
+        )
+    );
+    assert_eq!(
+        bundle.transactions[0].signing_message.len(),
+        bundle.transactions[0].raw_transaction.len() + 64
+    );
+
+    // the code object was derived from another sequence number than its transaction gets
+    let err = unsigned_bundle(
+        &plan(publisher, 12),
+        &deploy,
+        &sequence_numbers,
+        &settings(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.starts_with("vault: code object 0x"), "{}", err);
+    assert!(
+        err.contains("set sequence_number of its deployment to 9"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_match_signed_transactions() {
+    let key = SigningKey::from_bytes(&[3u8; 32]);
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    let deploy = DeployFunction::new(AccountAddress::from_hex_literal("0xa").unwrap());
+    let bundle =
+        unsigned_bundle(&plan(publisher, 2), &deploy, &BTreeMap::new(), &settings()).unwrap();
+
+    let signed = (0..3).map(|i| sign(&key, &bundle, i)).collect::<Vec<_>>();
+    // files in any order, as bytes or hex
+    let parsed = vec![
+        parse_signed_transaction(&signed[2]).unwrap(),
+        parse_signed_transaction(format!("0x{}\n", hex::encode(&signed[0])).as_bytes()).unwrap(),
+        parse_signed_transaction(&signed[1]).unwrap(),
+    ];
+    let ordered = match_signed_transactions(&bundle, parsed.clone()).unwrap();
+    let sequence = ordered
+        .iter()
+        .map(|t| t.sequence_number())
+        .collect::<Vec<_>>();
+    assert_eq!(sequence, [0, 1, 2]);
+
+    let err = match_signed_transactions(&bundle, parsed[1..].to_vec())
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "vault: no signed transaction for sequence number 2 of 0x0000000000000000000000000000000000000000000000000000000000000010"
+    );
+
+    // signed by a key over another message
+    let mut forged = signed[0].clone();
+    let len = forged.len();
+    forged[len - 1] ^= 1;
+    let err = match_signed_transactions(&bundle, vec![parse_signed_transaction(&forged).unwrap()])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("signed[0]: invalid signature"), "{}", err);
+}
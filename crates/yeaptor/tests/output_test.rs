@@ -0,0 +1,74 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use yeaptor::YeaptorError;
+use yeaptor::diagnostics::Diagnostics;
+use yeaptor::output::{CommandOutput, OutputFormat, render_error};
+
+#[test]
+fn test_render_command_output() {
+    let output = CommandOutput::new("Processor config generated")
+        .files([PathBuf::from("processor_config.yaml")])
+        .warnings(["unmapped event 0x1::coin::CoinDeposit".to_string()])
+        .detail("rows_written", BTreeMap::from([("coin_activities", 3)]));
+    assert_eq!(
+        output.render(OutputFormat::Text),
+        "warning: unmapped event 0x1::coin::CoinDeposit\nProcessor config generated"
+    );
+    let json: Value = serde_json::from_str(&output.render(OutputFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "status": "ok",
+            "message": "Processor config generated",
+            "files": ["processor_config.yaml"],
+            "warnings": ["unmapped event 0x1::coin::CoinDeposit"],
+            "rows_written": { "coin_activities": 3 },
+        })
+    );
+}
+
+#[test]
+fn test_render_error() {
+    let err = YeaptorError::config("db_schema.toml", anyhow::anyhow!("unknown column type"));
+    assert_eq!(
+        render_error(&err, OutputFormat::Text),
+        "invalid db_schema.toml: unknown column type"
+    );
+    let json: Value = serde_json::from_str(&render_error(&err, OutputFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "status": "error",
+            "kind": "config",
+            "path": "db_schema.toml",
+            "message": "invalid db_schema.toml: unknown column type",
+        })
+    );
+}
+
+#[test]
+fn test_render_error_diagnostics() {
+    let mut diagnostics = Diagnostics::default();
+    diagnostics.push(
+        "deployments[0]",
+        "publisher `bob` is neither in [publishers] nor an address",
+    );
+    diagnostics.push(
+        "deployments[1].packages[0]",
+        "packages/vault/Move.toml: not found",
+    );
+    let err = YeaptorError::config("yeaptor.toml", anyhow::Error::new(diagnostics));
+    let json: Value = serde_json::from_str(&render_error(&err, OutputFormat::Json)).unwrap();
+    assert_eq!(json["diagnostics"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        json["diagnostics"][1]["location"],
+        "deployments[1].packages[0]"
+    );
+    assert_eq!(
+        render_error(&err, OutputFormat::Text),
+        "invalid yeaptor.toml: 2 problems:\n  \
+         - deployments[0]: publisher `bob` is neither in [publishers] nor an address\n  \
+         - deployments[1].packages[0]: packages/vault/Move.toml: not found"
+    );
+}
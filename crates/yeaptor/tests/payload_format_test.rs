@@ -0,0 +1,295 @@
+use anyhow::Result;
+use aptos_types::account_address::{AccountAddress, create_resource_address};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use yeaptor::config::{DeployFunction, DeploymentMode, PayloadNaming};
+use yeaptor::deployment_planner::{
+    DeploymentPlan, address_constant_name, code_publish_payload_json, object_code_payload_json,
+    publish_payload_json,
+};
+use yeaptor::payload_format::{
+    DEFAULT_PAYLOAD_FILE_NAME, DEFAULT_PAYLOAD_FORMAT, EntryFunctionJson, check_distinct_paths,
+    check_payload_file_name, payload_path,
+};
+use yeaptor::{PayloadFormatter, PayloadFormatters, PlannedPackage};
+
+struct HexModules;
+
+impl PayloadFormatter for HexModules {
+    fn name(&self) -> &str {
+        "hex-modules"
+    }
+
+    fn file_name(&self, package: &PlannedPackage) -> String {
+        format!("{}-{}.modules.txt", package.order, package.name)
+    }
+
+    fn format(&self, package: &PlannedPackage) -> Result<Vec<u8>> {
+        let lines = package.modules.iter().map(hex::encode).collect::<Vec<_>>();
+        Ok(lines.join("\n").into_bytes())
+    }
+}
+
+/// `ra_code_deployment::deploy` at 0x1
+fn deploy() -> DeployFunction {
+    DeployFunction::new(AccountAddress::ONE)
+}
+
+fn package() -> PlannedPackage {
+    let metadata = vec![1, 2];
+    let modules = vec![vec![0xa1, 0x1c], vec![0xeb]];
+    PlannedPackage {
+        order: 0,
+        deployment: "alice-v1".to_string(),
+        name: "core".to_string(),
+        mode: DeploymentMode::ResourceAccount,
+        publisher: AccountAddress::from_hex_literal("0x10").unwrap(),
+        seed: "v1".to_string(),
+        account: create_resource_address(AccountAddress::from_hex_literal("0x10").unwrap(), b"v1"),
+        object_upgrade: false,
+        payload: publish_payload_json(&deploy(), "v1", &metadata, &modules),
+        metadata,
+        modules,
+        events: Vec::new(),
+    }
+}
+
+#[test]
+fn test_default_format() {
+    let formatters = PayloadFormatters::default();
+    let formatter = formatters.get(None).unwrap();
+    assert_eq!(formatter.name(), DEFAULT_PAYLOAD_FORMAT);
+    let package = package();
+    assert_eq!(formatter.file_name(&package), "0-core.package.json");
+    let payload: serde_json::Value =
+        serde_json::from_slice(&formatter.format(&package).unwrap()).unwrap();
+    assert_eq!(payload, package.payload);
+}
+
+#[test]
+fn test_registered_format() {
+    let formatters = PayloadFormatters::default().register(HexModules);
+    assert_eq!(
+        formatters.names(),
+        [
+            "entry-function-json",
+            "hex-modules",
+            "publish-args-bcs",
+            "publish-args-json"
+        ]
+    );
+    let formatter = formatters.get(Some("hex-modules")).unwrap();
+    let package = package();
+    assert_eq!(formatter.file_name(&package), "0-core.modules.txt");
+    assert_eq!(formatter.format(&package).unwrap(), b"a11c\neb");
+
+    let err = formatters.get(Some("envelope")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown payload format `envelope`; available: entry-function-json, hex-modules, publish-args-bcs, publish-args-json"
+    );
+}
+
+#[test]
+fn test_publish_args_formats() {
+    let formatters = PayloadFormatters::default();
+    let package = package();
+
+    let json = formatters.get(Some("publish-args-json")).unwrap();
+    assert_eq!(json.file_name(&package), "0-core.publish-args.json");
+    let args: serde_json::Value = serde_json::from_slice(&json.format(&package).unwrap()).unwrap();
+    assert_eq!(
+        args,
+        serde_json::json!({ "metadata_serialized": "0x0102", "code": ["0xa11c", "0xeb"] })
+    );
+
+    let bcs_format = formatters.get(Some("publish-args-bcs")).unwrap();
+    assert_eq!(bcs_format.file_name(&package), "0-core.publish-args.bcs");
+    let (metadata, code): (Vec<u8>, Vec<Vec<u8>>) =
+        bcs::from_bytes(&bcs_format.format(&package).unwrap()).unwrap();
+    assert_eq!(metadata, package.metadata);
+    assert_eq!(code, package.modules);
+}
+
+#[test]
+fn test_transaction_size() {
+    let mut package = package();
+    // 65 bytes of raw transaction fields, the entry function (1 + 32 + 19 + 7 + 1) and its
+    // BCS arguments (1 + 4 + 4 + 7)
+    assert_eq!(package.transaction_size(&deploy()), 141);
+    // lengths over 127 take two ULEB128 bytes
+    package.modules[1] = vec![0xeb; 201];
+    assert_eq!(package.transaction_size(&deploy()), 343);
+
+    // 0x1::code::publish_package_txn: the entry function (1 + 32 + 5 + 20 + 1) and its BCS
+    // arguments without the seed (1 + 4 + 209) besides the 65 bytes of raw transaction fields
+    package.mode = DeploymentMode::Account;
+    assert_eq!(package.transaction_size(&deploy()), 338);
+}
+
+#[test]
+fn test_renamed_deploy_function() {
+    let package = package();
+    assert_eq!(
+        package.payload["function_id"],
+        format!(
+            "{}::ra_code_deployment::deploy",
+            AccountAddress::ONE.to_standard_string()
+        )
+    );
+
+    let fork = DeployFunction {
+        address: AccountAddress::from_hex_literal("0xf0").unwrap(),
+        module: "fork_deployment".to_string(),
+        function: "deploy_package".to_string(),
+    };
+    let payload = publish_payload_json(&fork, "v1", &package.metadata, &package.modules);
+    assert_eq!(
+        payload["function_id"],
+        format!(
+            "{}::fork_deployment::deploy_package",
+            fork.address.to_standard_string()
+        )
+    );
+    assert_eq!(payload["args"], package.payload["args"]);
+    // 3 bytes fewer of module name, 8 more of function name
+    assert_eq!(package.transaction_size(&fork), 146);
+}
+
+#[test]
+fn test_account_mode_payload() {
+    let payload = code_publish_payload_json(&[1, 2], &[vec![0xa1, 0x1c]]);
+    assert_eq!(payload["function_id"], "0x1::code::publish_package_txn");
+    assert_eq!(
+        payload["args"],
+        serde_json::json!([
+            { "type": "hex", "value": "0x0102" },
+            { "type": "hex", "value": ["0xa11c"] },
+        ])
+    );
+}
+
+#[test]
+fn test_object_mode_payload() {
+    let payload = object_code_payload_json(&[1, 2], &[vec![0xa1, 0x1c]], None);
+    assert_eq!(
+        payload["function_id"],
+        "0x1::object_code_deployment::publish"
+    );
+    assert_eq!(payload["args"].as_array().unwrap().len(), 2);
+
+    let object = AccountAddress::from_hex_literal("0xc0de").unwrap();
+    let payload = object_code_payload_json(&[1, 2], &[vec![0xa1, 0x1c]], Some(object));
+    assert_eq!(
+        payload["function_id"],
+        "0x1::object_code_deployment::upgrade"
+    );
+    assert_eq!(
+        payload["args"][2],
+        serde_json::json!({ "type": "address", "value": object.to_standard_string() })
+    );
+
+    // the entry function (1 + 32 + 23 + 8 + 1) and its BCS arguments (1 + 4 + 209) besides the
+    // 65 bytes of raw transaction fields; an upgrade adds the object address (1 + 32)
+    let mut package = package();
+    package.modules[1] = vec![0xeb; 201];
+    package.mode = DeploymentMode::Object;
+    assert_eq!(package.transaction_size(&deploy()), 344);
+    package.object_upgrade = true;
+    package.account = object;
+    assert_eq!(package.transaction_size(&deploy()), 377);
+}
+
+#[test]
+fn test_payload_path() {
+    let package = package();
+    assert_eq!(
+        payload_path(
+            DEFAULT_PAYLOAD_FILE_NAME,
+            PayloadNaming::Ordered,
+            &EntryFunctionJson,
+            &package
+        )
+        .unwrap(),
+        PathBuf::from("alice-v1/0-core.package.json")
+    );
+    assert_eq!(
+        payload_path(
+            "{deployment}-{order}-{package}.json",
+            PayloadNaming::Ordered,
+            &HexModules,
+            &package
+        )
+        .unwrap(),
+        PathBuf::from("alice-v1-0-core.json")
+    );
+
+    assert_eq!(
+        check_payload_file_name("{deployment}/{name}.json")
+            .unwrap_err()
+            .to_string(),
+        "unknown placeholder `{name}` in payload file name `{deployment}/{name}.json`; expected one of `{deployment}`, `{order}`, `{package}`, `{file}`"
+    );
+    assert!(check_payload_file_name("../{file}").is_err());
+    assert!(check_payload_file_name("/tmp/{file}").is_err());
+    assert!(check_payload_file_name("{file").is_err());
+
+    assert!(check_distinct_paths([Path::new("a/0-core.json"), Path::new("b/0-core.json")]).is_ok());
+    assert!(check_distinct_paths([Path::new("payload.json"), Path::new("payload.json")]).is_err());
+}
+
+#[test]
+fn test_stable_payload_naming() {
+    let mut package = package();
+    package.order = 7;
+    assert_eq!(
+        payload_path(
+            DEFAULT_PAYLOAD_FILE_NAME,
+            PayloadNaming::Stable,
+            &EntryFunctionJson,
+            &package
+        )
+        .unwrap(),
+        PathBuf::from("alice-v1/core.package.json")
+    );
+    // formats of your own lose their `<order>-` prefix too
+    assert_eq!(HexModules.stable_file_name(&package), "core.modules.txt");
+
+    assert_eq!(
+        "stable".parse::<PayloadNaming>().unwrap(),
+        PayloadNaming::Stable
+    );
+    assert_eq!(PayloadNaming::default().to_string(), "ordered");
+    assert!("by-name".parse::<PayloadNaming>().is_err());
+}
+
+#[test]
+fn test_address_book_formats() {
+    let plan = DeploymentPlan {
+        packages: Vec::new(),
+        named_addresses: BTreeMap::from([
+            (
+                "lending_core".to_string(),
+                AccountAddress::from_hex_literal("0xa").unwrap(),
+            ),
+            ("std".to_string(), AccountAddress::ONE),
+        ]),
+        dependencies: BTreeMap::new(),
+    };
+    let a = AccountAddress::from_hex_literal("0xa")
+        .unwrap()
+        .to_standard_string();
+    let one = AccountAddress::ONE.to_standard_string();
+
+    let json: BTreeMap<String, String> = serde_json::from_str(&plan.addresses_json()).unwrap();
+    assert_eq!(json["lending_core"], a);
+    assert_eq!(json["std"], one);
+    assert_eq!(
+        plan.addresses_env(),
+        format!("LENDING_CORE_ADDRESS={a}\nSTD_ADDRESS={one}\n")
+    );
+    assert!(plan.addresses_typescript().ends_with(&format!(
+        "export const LENDING_CORE_ADDRESS = \"{a}\" as const;\nexport const STD_ADDRESS = \"{one}\" as const;\n"
+    )));
+    assert_eq!(address_constant_name("2fa"), "_2FA_ADDRESS");
+}
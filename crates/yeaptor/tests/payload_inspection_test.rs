@@ -0,0 +1,126 @@
+mod common;
+
+use aptos_types::account_address::AccountAddress;
+use common::MetadataBuilder;
+use move_binary_format::file_format::empty_module;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::config::DeployFunction;
+use yeaptor::deployment_planner::{
+    code_publish_payload_json, object_code_payload_json, publish_payload_json,
+};
+use yeaptor::payload_inspection::{inspect_payload_file, inspect_payload_json, render_inspection};
+
+/// BCS of a `PackageMetadata` named `core` with the compatible policy, a source digest and a
+/// dependency on `0x1::AptosFramework`, without manifest, modules or extension
+fn metadata() -> Vec<u8> {
+    MetadataBuilder::new("core")
+        .upgrade_number(2)
+        .dependency(AccountAddress::ONE, "AptosFramework")
+        .bcs()
+}
+
+fn module() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    empty_module().serialize(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn test_inspect_resource_account_payload() {
+    let deploy = DeployFunction::new(AccountAddress::ONE);
+    let payload = publish_payload_json(&deploy, "v1", &metadata(), &[module()]);
+    let inspection = inspect_payload_json(&payload).unwrap();
+    assert_eq!(inspection.format, "entry-function-json");
+    assert_eq!(
+        inspection.function.as_deref(),
+        Some(deploy.to_string().as_str())
+    );
+    assert_eq!(inspection.seed.as_deref(), Some("v1"));
+    assert_eq!(inspection.seed_hex.as_deref(), Some("0x7631"));
+    assert_eq!(inspection.object, None);
+    assert_eq!(inspection.package, "core");
+    assert_eq!(inspection.upgrade_policy, "compatible");
+    assert_eq!(inspection.upgrade_number, 2);
+    assert_eq!(inspection.source_digest, "C0FFEE");
+    assert_eq!(inspection.dependencies, vec!["0x1::AptosFramework"]);
+    assert_eq!(inspection.metadata_size, metadata().len());
+    assert_eq!(inspection.modules.len(), 1);
+    assert_eq!(inspection.modules[0].size, module().len());
+    assert!(!inspection.modules[0].source);
+
+    let text = render_inspection(&inspection);
+    assert!(text.contains("Seed:           \"v1\" (0x7631)"), "{}", text);
+    assert!(text.contains("Upgrade policy: compatible"), "{}", text);
+    assert!(
+        text.contains(&format!("Modules:        1 ({} bytes)", module().len())),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_inspect_account_and_object_payloads() {
+    let inspection = inspect_payload_json(&code_publish_payload_json(&metadata(), &[])).unwrap();
+    assert_eq!(
+        inspection.function.as_deref(),
+        Some("0x1::code::publish_package_txn")
+    );
+    assert_eq!(inspection.seed, None);
+    assert!(inspection.modules.is_empty());
+
+    let object = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    let payload = object_code_payload_json(&metadata(), &[], Some(object));
+    let inspection = inspect_payload_json(&payload).unwrap();
+    assert_eq!(inspection.seed, None);
+    assert_eq!(inspection.object, Some(object.to_standard_string()));
+}
+
+#[test]
+fn test_inspect_publish_args_files() {
+    let dir = tempdir().unwrap();
+    let json = dir.path().join("0-core.publish-args.json");
+    fs::write(
+        &json,
+        serde_json::to_vec(&serde_json::json!({
+            "metadata_serialized": format!("0x{}", hex::encode(metadata())),
+            "code": [],
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+    let inspection = inspect_payload_file(&json).unwrap();
+    assert_eq!(inspection.format, "publish-args-json");
+    assert_eq!(inspection.function, None);
+    assert_eq!(inspection.package, "core");
+
+    let bcs_file = dir.path().join("0-core.publish-args.bcs");
+    fs::write(
+        &bcs_file,
+        bcs::to_bytes(&(metadata(), Vec::<Vec<u8>>::new())).unwrap(),
+    )
+    .unwrap();
+    let inspection = inspect_payload_file(&bcs_file).unwrap();
+    assert_eq!(inspection.format, "publish-args-bcs");
+    assert!(render_inspection(&inspection).starts_with("Format:         publish-args-bcs"));
+}
+
+#[test]
+fn test_inspect_invalid_payloads() {
+    let err = inspect_payload_json(&serde_json::json!({ "args": [] }))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("function_id"), "{}", err);
+
+    let payload = code_publish_payload_json(&metadata(), &[vec![0xde, 0xad]]);
+    let err = format!("{:#}", inspect_payload_json(&payload).unwrap_err());
+    assert!(err.contains("module 0 does not deserialize"), "{}", err);
+
+    let payload = code_publish_payload_json(&[1, 2], &[]);
+    let err = inspect_payload_json(&payload).unwrap_err().to_string();
+    assert!(
+        err.contains("package metadata does not deserialize"),
+        "{}",
+        err
+    );
+}
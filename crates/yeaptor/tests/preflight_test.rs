@@ -0,0 +1,229 @@
+use aptos_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::config::{DeployFunction, DeploymentMode, load_config};
+use yeaptor::diagnostics::Diagnostics;
+use yeaptor::preflight::{
+    ChainState, PreflightTarget, ResourceAccountState, artifact_change, check_chain_state,
+    preflight_targets,
+};
+use yeaptor_core::address::object_code_address;
+
+fn targets() -> Vec<PreflightTarget> {
+    tagged_targets(&[])
+}
+
+fn tagged_targets(tags: &[String]) -> Vec<PreflightTarget> {
+    let dir = tempdir().unwrap();
+    let config = dir.path().join("yeaptor.toml");
+    fs::write(
+        &config,
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[publishers]
+alice = "0x10"
+
+[[deployments]]
+publisher = "alice"
+seed = "v1"
+packages = [
+    { address_name = "core", path = "core", tags = ["core"] },
+    { address_name = "oracle", path = "oracle" },
+]
+
+[[deployments]]
+publisher = "0x20"
+seed = "v1"
+packages = [{ address_name = "vault", path = "vault" }]
+
+[[deployments]]
+publisher = "carol"
+seed = "v1"
+packages = [{ address_name = "pool", path = "pool" }]
+"#,
+    )
+    .unwrap();
+    preflight_targets(&load_config(&config).unwrap(), tags)
+}
+
+fn funded(targets: &[PreflightTarget], balance: u64) -> ChainState {
+    ChainState {
+        publishers: targets
+            .iter()
+            .map(|t| (t.publisher, Some(balance)))
+            .collect(),
+        sequence_numbers: BTreeMap::new(),
+        resource_accounts: BTreeMap::new(),
+        gas_unit_price: 100,
+    }
+}
+
+/// `ra_code_deployment::deploy` at 0x1
+fn deploy() -> DeployFunction {
+    DeployFunction::new(AccountAddress::ONE)
+}
+
+#[test]
+fn test_preflight_targets() {
+    let targets = targets();
+    // carol is neither a publisher alias nor an address
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[0].location, "deployments[0]");
+    assert_eq!(
+        targets[0].publisher,
+        AccountAddress::from_hex_literal("0x10").unwrap()
+    );
+    assert_eq!(targets[0].packages, 2);
+    assert_eq!(targets[1].publisher_name, "0x20");
+
+    // only deployments with a selected package, counting those
+    let targets = tagged_targets(&["core".to_string()]);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].packages, 1);
+}
+
+#[test]
+fn test_check_chain_state_fresh_deployments() {
+    let targets = targets();
+    let upgrades =
+        check_chain_state(&targets, &funded(&targets, 100_000_000), &deploy(), 100_000).unwrap();
+    assert_eq!(
+        upgrades,
+        BTreeMap::from([
+            ("deployments[0]".to_string(), false),
+            ("deployments[1]".to_string(), false),
+        ])
+    );
+}
+
+#[test]
+fn test_check_chain_state_reports_every_problem() {
+    let targets = targets();
+    let mut state = funded(&targets, 15_000_000);
+    state.publishers.insert(targets[1].publisher, None);
+    state.resource_accounts.insert(
+        targets[0].resource_account,
+        ResourceAccountState {
+            exists: true,
+            publish_cap: true,
+            admin: Some(AccountAddress::from_hex_literal("0x30").unwrap()),
+        },
+    );
+    state.resource_accounts.insert(
+        targets[1].resource_account,
+        ResourceAccountState {
+            exists: true,
+            publish_cap: false,
+            admin: None,
+        },
+    );
+
+    let err = check_chain_state(&targets, &state, &deploy(), 100_000).unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    let messages = diagnostics
+        .0
+        .iter()
+        .map(|d| (d.location.as_str(), d.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[0].0, "deployments[0]");
+    assert!(messages[0].1.contains(
+        "holds 15000000 octas but publishing 2 package(s) may cost up to 20000000 octas"
+    ));
+    assert_eq!(messages[1].0, "deployments[1]");
+    assert!(messages[1].1.contains("does not exist on chain"));
+    assert_eq!(messages[2].0, "deployments[0]");
+    assert!(messages[2].1.contains("is administered by 0x0000000000000000000000000000000000000000000000000000000000000030, not by publisher `alice`"));
+    assert_eq!(messages[3].0, "deployments[1]");
+    assert!(
+        messages[3]
+            .1
+            .contains("holds no 0x1::ra_code_deployment::PublishPackageCap")
+    );
+
+    // the admin may upgrade
+    state
+        .publishers
+        .insert(targets[1].publisher, Some(100_000_000));
+    state
+        .publishers
+        .insert(targets[0].publisher, Some(100_000_000));
+    state.resource_accounts.remove(&targets[1].resource_account);
+    state
+        .resource_accounts
+        .get_mut(&targets[0].resource_account)
+        .unwrap()
+        .admin = Some(targets[0].publisher);
+    let upgrades = check_chain_state(&targets, &state, &deploy(), 100_000).unwrap();
+    assert!(upgrades["deployments[0]"]);
+    assert!(!upgrades["deployments[1]"]);
+}
+
+#[test]
+fn test_artifact_change() {
+    assert_eq!(artifact_change("core", true, true), None);
+    assert_eq!(artifact_change("core", false, false), None);
+    assert!(
+        artifact_change("core", true, false)
+            .unwrap()
+            .starts_with("package core was published with its sources")
+    );
+    assert!(
+        artifact_change("core", false, true)
+            .unwrap()
+            .starts_with("package core was published without sources")
+    );
+}
+
+#[test]
+fn test_check_chain_state_account_mode() {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    let targets = vec![PreflightTarget {
+        location: "deployments[0]".to_string(),
+        publisher_name: "alice".to_string(),
+        publisher,
+        mode: DeploymentMode::Account,
+        seed: String::new(),
+        resource_account: publisher,
+        sequence_number: None,
+        packages: 1,
+    }];
+    // the publisher's own account holds no PublishPackageCap and is no upgrade target
+    let upgrades =
+        check_chain_state(&targets, &funded(&targets, 100_000_000), &deploy(), 100_000).unwrap();
+    assert!(upgrades.is_empty());
+
+    let mut state = funded(&targets, 100_000_000);
+    state.publishers.insert(publisher, None);
+    let err = check_chain_state(&targets, &state, &deploy(), 100_000).unwrap_err();
+    assert!(err.to_string().contains("does not exist on chain"));
+}
+
+#[test]
+fn test_check_chain_state_object_sequence_number() {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    let targets = vec![PreflightTarget {
+        location: "deployments[0]".to_string(),
+        publisher_name: "alice".to_string(),
+        publisher,
+        mode: DeploymentMode::Object,
+        seed: String::new(),
+        resource_account: object_code_address(&publisher, 4),
+        sequence_number: Some(4),
+        packages: 1,
+    }];
+    let mut state = funded(&targets, 100_000_000);
+    state.sequence_numbers.insert(publisher, 4);
+    let upgrades = check_chain_state(&targets, &state, &deploy(), 100_000).unwrap();
+    assert!(upgrades.is_empty());
+
+    state.sequence_numbers.insert(publisher, 5);
+    let err = check_chain_state(&targets, &state, &deploy(), 100_000).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("deployments[0]: sequence_number 4 of publisher `alice` is already used (the account is at 5)")
+    );
+}
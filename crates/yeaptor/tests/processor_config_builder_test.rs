@@ -0,0 +1,125 @@
+use aptos::common::init::Network;
+use aptos_types::account_address::AccountAddress;
+use std::collections::BTreeMap;
+use yeaptor::ProcessorConfigBuilder;
+use yeaptor::db_schema::{DEFAULT_TABLE_GROUP, TableOptions};
+use yeaptor::processor_config::{ConflictStrategy, ProcessorConfig};
+use yeaptor::standard_templates::StandardTemplates;
+
+fn templates() -> StandardTemplates {
+    StandardTemplates {
+        fungible_asset: true,
+        coin: true,
+        object: false,
+    }
+}
+
+fn conflict_strategy(config: &ProcessorConfig, table: &str) -> Option<String> {
+    config.custom_config.payload.get("conflict_strategies")?[table]
+        .as_str()
+        .map(str::to_string)
+}
+
+#[test]
+fn test_builder_generates_config() {
+    let generated = ProcessorConfigBuilder::new(Network::Mainnet, 100)
+        .ending_version(Some(200))
+        .standard_templates(templates())
+        .table_options(BTreeMap::from([(
+            "coin_activities".to_string(),
+            TableOptions {
+                on_conflict: Some(ConflictStrategy::IgnoreDuplicates),
+                ..Default::default()
+            },
+        )]))
+        .build()
+        .unwrap();
+    assert!(generated.unmapped_events.is_empty());
+    assert!(generated.unmapped_columns.is_empty());
+    let config = &generated.config;
+    assert_eq!(config.common_config.network, "mainnet");
+    assert_eq!(config.common_config.starting_version, 100);
+    assert_eq!(config.common_config.ending_version, Some(200));
+    assert!(
+        config
+            .custom_config
+            .events
+            .contains_key("0x1::coin::DepositEvent")
+    );
+    assert_eq!(
+        conflict_strategy(config, "coin_activities").as_deref(),
+        Some("ignore-duplicates")
+    );
+
+    let err = ProcessorConfigBuilder::new(Network::Mainnet, 100)
+        .ending_version(Some(99))
+        .build()
+        .unwrap_err();
+    assert_eq!(err.kind(), "config");
+    assert_eq!(
+        err.to_string(),
+        "ending version 99 is before starting version 100"
+    );
+}
+
+#[test]
+fn test_builder_groups_and_catch_all() {
+    let builder = ProcessorConfigBuilder::new(Network::Testnet, 0)
+        .standard_templates(templates())
+        .catch_all_table(Some("raw_events".to_string()))
+        .table_options(BTreeMap::from([(
+            "coin_activities".to_string(),
+            TableOptions {
+                group: Some("coin".to_string()),
+                ..Default::default()
+            },
+        )]));
+    assert_eq!(builder.groups(), ["coin", DEFAULT_TABLE_GROUP]);
+
+    let coin = builder.build_group("coin").unwrap().config;
+    assert_eq!(coin.spec_identifier.spec_name, "remapping-processor-coin");
+    assert_eq!(
+        coin.custom_config.db_schema.keys().collect::<Vec<_>>(),
+        ["coin_activities"]
+    );
+    let default = builder.build_group(DEFAULT_TABLE_GROUP).unwrap().config;
+    assert!(default.custom_config.db_schema.contains_key("raw_events"));
+    assert!(builder.build_group("nft").is_err());
+
+    // the catch-all payload column is filled by the processor, not reported as unmapped
+    let generated = builder.build().unwrap();
+    assert!(
+        !generated
+            .unmapped_columns
+            .contains(&("raw_events".to_string(), "payload".to_string()))
+    );
+}
+
+#[test]
+fn test_builder_account_filter() {
+    let accounts = vec![
+        AccountAddress::from_hex_literal("0x42").unwrap(),
+        AccountAddress::from_hex_literal("0x7").unwrap(),
+        AccountAddress::from_hex_literal("0x42").unwrap(),
+    ];
+    let config = ProcessorConfigBuilder::new(Network::Mainnet, 0)
+        .standard_templates(templates())
+        .account_filter(accounts)
+        .build()
+        .unwrap()
+        .config;
+    assert_eq!(
+        config.common_config.account_filter,
+        ["0x7".to_string(), format!("0x{:0>64}", "42")]
+    );
+
+    // an unfiltered config does not mention the filter
+    let config = ProcessorConfigBuilder::new(Network::Mainnet, 0)
+        .standard_templates(templates())
+        .build()
+        .unwrap()
+        .config;
+    assert!(config.common_config.account_filter.is_empty());
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    assert!(!yaml.contains("account_filter"));
+}
@@ -0,0 +1,1293 @@
+use aptos::common::init::Network;
+use aptos_types::account_address::AccountAddress;
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::TableOptions;
+use yeaptor::dbt::dbt_scaffold;
+use yeaptor::diagnostics::Diagnostics;
+use yeaptor::event_definition::{EventDefinition, EventVersion};
+use yeaptor::event_table_mapping::{
+    EventTableMappings, TableMappingRule, check_mapping_references, load_entry_function_mappings,
+    load_event_table_mappings, load_table_item_mappings, normalize_move_type, parse_computed_expr,
+    parse_event_filter, parse_transform, restrict_mappings_to_tables,
+    table_mappings_from_csv_entries,
+};
+use yeaptor::processor_config::{
+    ColumnSpec, ColumnTarget, ColumnTransform, ColumnTypeSpec, ComputeArg, ComputeFunction,
+    ComputedColumn, ConflictStrategy, EntryFunctionMapping, FilterOp, IndexDefinition, IndexMethod,
+    PartitionDefinition, PartitionInterval, SpecIdentifier, TableItemMapping, TableSchema,
+    Transform, apply_processor_overrides, backfill_variant, bump_spec_version,
+};
+use yeaptor::processor_config_generator::{
+    CATCH_ALL_PAYLOAD_COLUMN, apply_conflict_strategies, apply_entry_function_mappings,
+    apply_table_indexes, apply_table_item_mappings, apply_table_partitioning,
+    attach_catch_all_payloads, catch_all_mappings, catch_all_table_schema, check_processor_mapping,
+    generate_processor_config,
+};
+use yeaptor::processor_config_lint::{Severity, lint_processor_config};
+
+const EVENT: &str = "yeap-borrow::market::MarketCreatedEvent";
+const MATERIALIZED_EVENT: &str = "0x42::market::MarketCreatedEvent";
+
+fn market_created_event() -> EventDefinition {
+    EventDefinition {
+        package_name: "yeap-borrow".to_string(),
+        module_address: AccountAddress::from_hex_literal("0x42").unwrap(),
+        module_name: "market".to_string(),
+        name: "MarketCreatedEvent".to_string(),
+        fields: BTreeMap::from([
+            ("market".to_string(), "address".to_string()),
+            ("collateral_asset".to_string(), "address".to_string()),
+            ("oracle".to_string(), "address".to_string()),
+        ]),
+        version: EventVersion::V2,
+        dependency_of: None,
+        nested_fields: BTreeMap::new(),
+    }
+}
+
+fn column(column_type: &str, r#type: &str) -> ColumnSpec {
+    ColumnSpec {
+        column_type: ColumnTypeSpec {
+            column_type: column_type.to_string(),
+            r#type: r#type.to_string(),
+        },
+        default_value: None,
+        is_index: false,
+        is_nullable: true,
+        is_option: false,
+        is_primary_key: false,
+        is_vec: false,
+    }
+}
+
+fn market_schema() -> BTreeMap<String, TableSchema> {
+    BTreeMap::from([(
+        "borrow_market".to_string(),
+        TableSchema::from([
+            ("market".to_string(), column("address", "move_type")),
+            ("collateral".to_string(), column("address", "move_type")),
+            ("oracle".to_string(), column("address", "move_type")),
+            ("event_source".to_string(), column("string", "move_type")),
+            (
+                "transaction_version".to_string(),
+                column("version", "transaction_metadata"),
+            ),
+        ]),
+    )])
+}
+
+fn generate(
+    mappings: &EventTableMappings,
+) -> anyhow::Result<(
+    yeaptor::processor_config::ProcessorConfig,
+    Vec<String>,
+    Vec<(String, String)>,
+)> {
+    generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &[market_created_event()],
+        &market_schema(),
+        mappings,
+    )
+}
+
+#[test]
+fn test_bump_spec_version() {
+    assert_eq!(bump_spec_version("0.0.10").as_deref(), Some("0.0.11"));
+    assert_eq!(bump_spec_version("1.2.9").as_deref(), Some("1.2.10"));
+    assert_eq!(bump_spec_version("7").as_deref(), Some("8"));
+}
+
+#[test]
+fn test_bump_spec_version_non_numeric() {
+    assert!(bump_spec_version("0.0.10-beta").is_none());
+    assert!(bump_spec_version("").is_none());
+}
+
+#[test]
+fn test_csv_entries_become_rules() {
+    let entries = BTreeMap::from([
+        (EVENT.to_string(), vec!["borrow_market".to_string()]),
+        (
+            format!("{}::collateral_asset", EVENT),
+            vec!["borrow_market::collateral".to_string()],
+        ),
+    ]);
+    let mappings = table_mappings_from_csv_entries(&entries).unwrap();
+    let rules = mappings.get(EVENT).unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].table, "borrow_market");
+    assert_eq!(
+        rules[0].rename.get("collateral_asset").map(String::as_str),
+        Some("collateral")
+    );
+}
+
+#[test]
+fn test_csv_field_row_without_table_row_is_rejected() {
+    let entries = BTreeMap::from([(
+        format!("{}::collateral_asset", EVENT),
+        vec!["borrow_market::collateral".to_string()],
+    )]);
+    assert!(table_mappings_from_csv_entries(&entries).is_err());
+}
+
+#[test]
+fn test_rules_drive_generated_mapping() {
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename
+        .insert("collateral_asset".to_string(), "collateral".to_string());
+    rule.exclude.push("oracle".to_string());
+    rule.constants.insert(
+        "event_source".to_string(),
+        YamlValue::String("lending_v2".to_string()),
+    );
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+
+    let (config, unmapped_events, _) = generate(&mappings).unwrap();
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    assert_eq!(
+        event.event_fields.get("$.collateral_asset").unwrap(),
+        &vec![ColumnTarget {
+            column: "collateral".to_string(),
+            table: "borrow_market".to_string(),
+        }]
+    );
+    assert!(event.event_fields.contains_key("$.market"));
+    // excluded fields are neither mapped nor reported
+    assert!(!event.event_fields.contains_key("$.oracle"));
+    assert!(unmapped_events.is_empty());
+    assert_eq!(event.constant_values.len(), 1);
+    assert_eq!(event.constant_values[0].column, "event_source");
+}
+
+#[test]
+fn test_rename_to_missing_column_fails() {
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename.insert(
+        "collateral_asset".to_string(),
+        "collateral_addr".to_string(),
+    );
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    assert!(generate(&mappings).is_err());
+}
+
+#[test]
+fn test_load_toml_mapping() {
+    let content = r#"
+[[events."yeap-borrow::market::MarketCreatedEvent"]]
+table = "borrow_market"
+rename = { collateral_asset = "collateral" }
+exclude = ["oracle"]
+constants = { event_source = "lending_v2" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let mappings = load_event_table_mappings(file.path()).unwrap();
+    let rules = mappings.get(EVENT).unwrap();
+    assert_eq!(rules[0].exclude, vec!["oracle".to_string()]);
+}
+
+#[test]
+fn test_load_mapping_rejects_unknown_keys() {
+    let content = r#"
+[[events."yeap-borrow::market::MarketCreatedEvent"]]
+table = "borrow_market"
+renames = { collateral_asset = "collateral" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    assert!(load_event_table_mappings(file.path()).is_err());
+}
+
+#[test]
+fn test_entry_function_mappings() {
+    let content = r#"
+[[entry_functions."0x42::market::set_oracle"]]
+table = "borrow_market"
+columns = { market = "$.arguments.0", oracle = "$.arguments.1", collateral = "$.sender" }
+constants = { event_source = "set_oracle" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let mappings = load_entry_function_mappings(file.path()).unwrap();
+
+    let rule = TableMappingRule::new("borrow_market");
+    let (mut config, _, unmapped_columns) =
+        generate(&EventTableMappings::from([(EVENT.to_string(), vec![rule])])).unwrap();
+    assert!(unmapped_columns.contains(&("borrow_market".to_string(), "event_source".to_string())));
+    let written = apply_entry_function_mappings(&mut config, &mappings).unwrap();
+    assert!(written.contains(&("borrow_market".to_string(), "event_source".to_string())));
+
+    let emitted: Vec<EntryFunctionMapping> =
+        serde_yaml::from_value(config.custom_config.payload["entry_functions"].clone()).unwrap();
+    assert_eq!(emitted.len(), 1);
+    assert_eq!(emitted[0].function, "0x42::market::set_oracle");
+    assert_eq!(
+        emitted[0].payload_fields["$.arguments.1"],
+        vec![ColumnTarget {
+            column: "oracle".to_string(),
+            table: "borrow_market".to_string(),
+        }]
+    );
+    assert_eq!(
+        emitted[0].constant_values[0].value,
+        YamlValue::String("set_oracle".to_string())
+    );
+
+    // CSV mappings have no entry functions
+    let csv = Builder::new().suffix(".csv").tempfile().unwrap();
+    fs::write(csv.path(), "event,table\n").unwrap();
+    assert!(load_entry_function_mappings(csv.path()).unwrap().is_empty());
+}
+
+#[test]
+fn test_entry_function_mappings_are_validated() {
+    for (content, expected) in [
+        (
+            r#"[[entry_functions."market::set_oracle"]]
+table = "borrow_market""#,
+            "expected <address>::<module>::<function>",
+        ),
+        (
+            r#"[[entry_functions."0x42::market::set_oracle"]]
+table = "borrow_market"
+columns = { market = "$.args.0" }"#,
+            "expected one of $.arguments, $.function",
+        ),
+        (
+            r#"[[entry_functions."0x42::market::set_oracle"]]
+table = "borrow_market"
+columns = { market = "$.arguments.first" }"#,
+            "addressed by position",
+        ),
+    ] {
+        let file = Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(file.path(), content).unwrap();
+        let err = load_entry_function_mappings(file.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains(expected), "{:#}", err);
+    }
+
+    let content = r#"[[entry_functions."0x42::market::set_oracle"]]
+table = "borrow_market"
+columns = { price = "$.arguments.0" }"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let mappings = load_entry_function_mappings(file.path()).unwrap();
+    let (mut config, _, _) = generate(&EventTableMappings::new()).unwrap();
+    let err = apply_entry_function_mappings(&mut config, &mappings).unwrap_err();
+    assert!(err.to_string().contains("borrow_market::price not found"));
+}
+
+#[test]
+fn test_table_item_mappings() {
+    let content = r#"
+[[table_items."0x0000000000000000000000000000000000000000000000000000000000000042::market::Position"]]
+table = "borrow_market"
+key_type = "address"
+columns = { market = "$.key", collateral = "$.value.collateral" }
+constants = { event_source = "position" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let mappings = load_table_item_mappings(file.path()).unwrap();
+
+    let (mut config, _, _) = generate(&EventTableMappings::new()).unwrap();
+    let written = apply_table_item_mappings(&mut config, &mappings).unwrap();
+    assert!(written.contains(&("borrow_market".to_string(), "collateral".to_string())));
+
+    let emitted: Vec<TableItemMapping> =
+        serde_yaml::from_value(config.custom_config.payload["table_items"].clone()).unwrap();
+    assert_eq!(emitted.len(), 1);
+    assert_eq!(emitted[0].value_type, "0x42::market::Position");
+    assert_eq!(emitted[0].key_type.as_deref(), Some("address"));
+    assert_eq!(emitted[0].handle, None);
+    assert_eq!(
+        emitted[0].payload_fields["$.value.collateral"],
+        vec![ColumnTarget {
+            column: "collateral".to_string(),
+            table: "borrow_market".to_string(),
+        }]
+    );
+
+    // CSV mappings have no table items
+    let csv = Builder::new().suffix(".csv").tempfile().unwrap();
+    fs::write(csv.path(), "event,table\n").unwrap();
+    assert!(load_table_item_mappings(csv.path()).unwrap().is_empty());
+}
+
+#[test]
+fn test_table_item_mappings_are_validated() {
+    for (content, expected) in [
+        (
+            r#"[[table_items."0xzz::market::Position"]]
+table = "borrow_market""#,
+            "invalid address in type 0xzz::market::Position",
+        ),
+        (
+            r#"[[table_items."0x42::market::Position"]]
+table = "borrow_market"
+handle = "table""#,
+            "invalid handle table",
+        ),
+        (
+            r#"[[table_items."0x42::market::Position"]]
+table = "borrow_market"
+columns = { market = "$.val" }"#,
+            "expected one of $.handle, $.key",
+        ),
+    ] {
+        let file = Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(file.path(), content).unwrap();
+        let err = load_table_item_mappings(file.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains(expected), "{:#}", err);
+    }
+}
+
+#[test]
+fn test_normalize_move_type() {
+    assert_eq!(
+        normalize_move_type(&format!(
+            "0x{:0>64}::coin::Coin< 0x1::aptos_coin::AptosCoin >",
+            "1"
+        ))
+        .unwrap(),
+        "0x1::coin::Coin<0x1::aptos_coin::AptosCoin>"
+    );
+    assert_eq!(
+        normalize_move_type("vector<0x42::market::Position>").unwrap(),
+        "vector<0x42::market::Position>"
+    );
+    assert_eq!(normalize_move_type("u64").unwrap(), "u64");
+    assert_eq!(
+        normalize_move_type("0x1::table::Table<address, u64>").unwrap(),
+        "0x1::table::Table<address,u64>"
+    );
+    assert!(normalize_move_type("").is_err());
+}
+
+#[test]
+fn test_check_mapping_references() {
+    let content = "event,table
+yeap-borrow::market::MarketCreatedEvent,borrow_market
+yeap-borrow::market::MarketCreatedEvent::colateral_asset,borrow_market::collateral
+yeap-borrow::market::MarketCreatedEvnt,borrow_market
+yeap-borrow::vault::VaultOpened,borrow_market
+";
+    let file = Builder::new().suffix(".csv").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let mappings = load_event_table_mappings(file.path()).unwrap();
+    let definitions = [market_created_event()];
+
+    let err = check_mapping_references(&mappings, &definitions).unwrap_err();
+    let diagnostics = err.downcast_ref::<Diagnostics>().unwrap();
+    assert_eq!(
+        diagnostics.to_string(),
+        "3 problems:
+  - yeap-borrow::market::MarketCreatedEvent::colateral_asset: MarketCreatedEvent has no field `colateral_asset` (mapped for table borrow_market); did you mean `collateral_asset`?
+  - yeap-borrow::market::MarketCreatedEvnt: no event definition has this name; did you mean `yeap-borrow::market::MarketCreatedEvent`?
+  - yeap-borrow::vault::VaultOpened: no event definition has this name; is its package's event file in the events directory?"
+    );
+
+    let mut mappings = mappings;
+    mappings.retain(|event, _| event == EVENT);
+    mappings.get_mut(EVENT).unwrap()[0].rename.clear();
+    check_mapping_references(&mappings, &definitions).unwrap();
+}
+
+#[test]
+fn test_nested_paths_are_mapped() {
+    let mut event = market_created_event();
+    event
+        .fields
+        .insert("position".to_string(), "0x42::market::Position".to_string());
+    event.nested_fields = BTreeMap::from([
+        ("position.collateral".to_string(), "u64".to_string()),
+        ("position.debt".to_string(), "u64".to_string()),
+    ]);
+    let mut schema = market_schema();
+    let table = schema.get_mut("borrow_market").unwrap();
+    table.insert(
+        "position_collateral".to_string(),
+        column("u64", "move_type"),
+    );
+    table.insert("debt_amount".to_string(), column("u64", "move_type"));
+
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename
+        .insert("position.debt".to_string(), "debt_amount".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+
+    let (config, unmapped_events, _) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &[event],
+        &schema,
+        &mappings,
+    )
+    .unwrap();
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    assert_eq!(
+        event.event_fields.get("$.position.collateral").unwrap()[0].column,
+        "position_collateral"
+    );
+    assert_eq!(
+        event.event_fields.get("$.position.debt").unwrap()[0].column,
+        "debt_amount"
+    );
+    // `position` is covered by its nested paths
+    assert!(!unmapped_events.iter().any(|e| e.ends_with("::position")));
+}
+
+#[test]
+fn test_parse_event_filter() {
+    let (path, op, value) = parse_event_filter(r#"$.market == "0xabc""#).unwrap();
+    assert_eq!(path, "$.market");
+    assert_eq!(op, FilterOp::Eq);
+    assert_eq!(value, YamlValue::String("0xabc".to_string()));
+
+    let (_, op, value) = parse_event_filter("$.amount != 0").unwrap();
+    assert_eq!(op, FilterOp::Ne);
+    assert_eq!(value, YamlValue::from(0u64));
+
+    let (_, op, _) = parse_event_filter("$.amount >= 10").unwrap();
+    assert_eq!(op, FilterOp::Ge);
+
+    // unquoted addresses are kept as strings
+    let (_, _, value) = parse_event_filter("$.market == 0x1").unwrap();
+    assert_eq!(value, YamlValue::String("0x1".to_string()));
+
+    assert!(parse_event_filter("market == 1").is_err());
+    assert!(parse_event_filter("$.market").is_err());
+}
+
+#[test]
+fn test_filters_are_emitted() {
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.filters.push(r#"$.market == "0xabc""#.to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+
+    let (config, _, _) = generate(&mappings).unwrap();
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    assert_eq!(event.filters.len(), 1);
+    assert_eq!(event.filters[0].table, "borrow_market");
+    assert_eq!(event.filters[0].path, "$.market");
+
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.filters.push("$.unknown_field != 0".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    assert!(generate(&mappings).is_err());
+}
+
+#[test]
+fn test_csv_constant_rows() {
+    let entries = BTreeMap::from([(
+        EVENT.to_string(),
+        vec![
+            "borrow_market".to_string(),
+            r#"borrow_market::event_source = "lending_v2""#.to_string(),
+        ],
+    )]);
+    let mappings = table_mappings_from_csv_entries(&entries).unwrap();
+    let rules = mappings.get(EVENT).unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(
+        rules[0].constants.get("event_source"),
+        Some(&YamlValue::String("lending_v2".to_string()))
+    );
+}
+
+#[test]
+fn test_constants_are_validated_against_column_types() {
+    let mut schema = market_schema();
+    schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("chain_id".to_string(), column("u8", "move_type"));
+    let generate_with = |column: &str, value: YamlValue| {
+        let mut rule = TableMappingRule::new("borrow_market");
+        rule.constants.insert(column.to_string(), value);
+        let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+        generate_processor_config(
+            SpecIdentifier::default(),
+            Network::Testnet,
+            0,
+            &[market_created_event()],
+            &schema,
+            &mappings,
+        )
+    };
+
+    let (config, _, _) = generate_with("chain_id", YamlValue::String("1".to_string())).unwrap();
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    // normalized to a number for integer columns
+    assert_eq!(event.constant_values[0].value, YamlValue::from(1u64));
+
+    assert!(generate_with("chain_id", YamlValue::from(300u64)).is_err());
+    assert!(generate_with("market", YamlValue::String("not-an-address".to_string())).is_err());
+    assert!(generate_with("transaction_version", YamlValue::from(1u64)).is_err());
+}
+
+#[test]
+fn test_parse_computed_expr() {
+    let (function, args) = parse_computed_expr(r#"concat($.market, ":", $.user)"#).unwrap();
+    assert_eq!(function, ComputeFunction::Concat);
+    assert_eq!(
+        args,
+        vec![
+            ComputeArg::Path("$.market".to_string()),
+            ComputeArg::Literal(YamlValue::String(":".to_string())),
+            ComputeArg::Path("$.user".to_string()),
+        ]
+    );
+
+    let (function, args) = parse_computed_expr("$.supplied - $.borrowed").unwrap();
+    assert_eq!(function, ComputeFunction::Sub);
+    assert_eq!(args.len(), 2);
+
+    let (function, _) = parse_computed_expr("hash($.market, $.user)").unwrap();
+    assert_eq!(function, ComputeFunction::Hash);
+
+    assert!(parse_computed_expr("hash()").is_err());
+    assert!(parse_computed_expr("$.market").is_err());
+}
+
+#[test]
+fn test_computed_columns_in_payload() {
+    let mut schema = market_schema();
+    schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("market_key".to_string(), column("string", "move_type"));
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.computed.insert(
+        "market_key".to_string(),
+        "hash($.market, $.collateral_asset)".to_string(),
+    );
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+
+    let (config, _, _) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &[market_created_event()],
+        &schema,
+        &mappings,
+    )
+    .unwrap();
+    let computed: Vec<ComputedColumn> = serde_yaml::from_value(
+        config
+            .custom_config
+            .payload
+            .get("computed_columns")
+            .unwrap()
+            .clone(),
+    )
+    .unwrap();
+    assert_eq!(computed.len(), 1);
+    assert_eq!(computed[0].event, MATERIALIZED_EVENT);
+    assert_eq!(computed[0].column, "market_key");
+
+    // arithmetic on addresses is rejected
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.computed
+        .insert("market_key".to_string(), "$.market + 1".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    assert!(
+        generate_processor_config(
+            SpecIdentifier::default(),
+            Network::Testnet,
+            0,
+            &[market_created_event()],
+            &schema,
+            &mappings,
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_parse_transform() {
+    assert_eq!(parse_transform("hex_decode").unwrap(), Transform::HexDecode);
+    assert_eq!(parse_transform("lowercase").unwrap(), Transform::Lowercase);
+    assert_eq!(parse_transform("scale(8)").unwrap(), Transform::Scale(8));
+    assert_eq!(
+        parse_transform(" scale( 6 ) ").unwrap(),
+        Transform::Scale(6)
+    );
+    assert!(parse_transform("scale").is_err());
+    assert!(parse_transform("scale(-1)").is_err());
+    assert!(parse_transform("scale(100)").is_err());
+    assert!(parse_transform("uppercase").is_err());
+}
+
+#[test]
+fn test_column_transforms_in_payload() {
+    let mut event = market_created_event();
+    event
+        .fields
+        .insert("symbol".to_string(), "vector<u8>".to_string());
+    event
+        .fields
+        .insert("supply_cap".to_string(), "u64".to_string());
+    let mut schema = market_schema();
+    let table = schema.get_mut("borrow_market").unwrap();
+    table.insert("symbol".to_string(), column("string", "move_type"));
+    table.insert("supply_cap".to_string(), column("decimal", "move_type"));
+    let mut rule = TableMappingRule::new("borrow_market");
+    for (column, transform) in [
+        ("market", "lowercase"),
+        ("symbol", "hex_decode"),
+        ("supply_cap", "scale(8)"),
+    ] {
+        rule.transforms
+            .insert(column.to_string(), transform.to_string());
+    }
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule.clone()])]);
+    let generate = |schema: &BTreeMap<String, TableSchema>, mappings: &EventTableMappings| {
+        generate_processor_config(
+            SpecIdentifier::default(),
+            Network::Testnet,
+            0,
+            &[event.clone()],
+            schema,
+            mappings,
+        )
+    };
+
+    let (config, _, _) = generate(&schema, &mappings).unwrap();
+    let transforms: Vec<ColumnTransform> = serde_yaml::from_value(
+        config
+            .custom_config
+            .payload
+            .get("column_transforms")
+            .unwrap()
+            .clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        transforms
+            .iter()
+            .map(|t| (t.event.as_str(), t.column.as_str(), t.transform))
+            .collect::<Vec<_>>(),
+        vec![
+            (MATERIALIZED_EVENT, "market", Transform::Lowercase),
+            (MATERIALIZED_EVENT, "supply_cap", Transform::Scale(8)),
+            (MATERIALIZED_EVENT, "symbol", Transform::HexDecode),
+        ]
+    );
+    assert!(check_processor_mapping(&[event.clone()], &schema, &mappings).is_empty());
+
+    // scaled values need a decimal column
+    let mut integer_schema = schema.clone();
+    integer_schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("supply_cap".to_string(), column("u64", "move_type"));
+    let err = generate(&integer_schema, &mappings).unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("expected a decimal column, found u64"),
+        "{:#}",
+        err
+    );
+    assert_eq!(
+        check_processor_mapping(&[event.clone()], &integer_schema, &mappings),
+        vec![format!(
+            "{}::supply_cap (u64) -> borrow_market::supply_cap: expected a decimal column, found u64",
+            EVENT
+        )]
+    );
+
+    // hex_decode only applies to vector<u8> fields
+    let mut wrong = rule.clone();
+    wrong
+        .transforms
+        .insert("oracle".to_string(), "hex_decode".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![wrong])]);
+    assert!(generate(&schema, &mappings).is_err());
+
+    // a transformed column must be written by a field
+    let mut excluded = rule;
+    excluded.exclude.push("symbol".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![excluded])]);
+    assert!(generate(&schema, &mappings).is_err());
+    assert_eq!(
+        check_processor_mapping(&[event], &schema, &mappings),
+        vec![format!(
+            "Column borrow_market::symbol transformed for {} is not written by an event field",
+            EVENT
+        )]
+    );
+}
+
+#[test]
+fn test_field_renamed_per_table() {
+    let mut schema = market_schema();
+    schema.insert(
+        "market_oracle".to_string(),
+        TableSchema::from([
+            ("market_address".to_string(), column("address", "move_type")),
+            ("oracle".to_string(), column("address", "move_type")),
+        ]),
+    );
+    let mut market = TableMappingRule::new("borrow_market");
+    market
+        .rename
+        .insert("collateral_asset".to_string(), "collateral".to_string());
+    let mut oracle = TableMappingRule::new("market_oracle");
+    oracle
+        .rename
+        .insert("market".to_string(), "market_address".to_string());
+    oracle.exclude.push("collateral_asset".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![market, oracle])]);
+
+    let (config, _, _) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &[market_created_event()],
+        &schema,
+        &mappings,
+    )
+    .unwrap();
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    let targets = event.event_fields.get("$.market").unwrap();
+    assert_eq!(
+        targets,
+        &vec![
+            ColumnTarget {
+                column: "market".to_string(),
+                table: "borrow_market".to_string(),
+            },
+            ColumnTarget {
+                column: "market_address".to_string(),
+                table: "market_oracle".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_column_written_twice_is_rejected() {
+    // oracle is auto-mapped to `oracle` while market is renamed onto it as well
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename
+        .insert("market".to_string(), "oracle".to_string());
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let err = generate(&mappings).unwrap_err();
+    assert!(err.to_string().contains("borrow_market::oracle"));
+
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.constants
+        .insert("market".to_string(), YamlValue::String("0x1".to_string()));
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    assert!(generate(&mappings).is_err());
+}
+
+#[test]
+fn test_overrides_are_deep_merged() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (config, _, _) = generate(&mappings).unwrap();
+
+    let overrides: YamlValue = serde_yaml::from_str(&format!(
+        r#"
+common_config:
+  starting_version_override: 500
+custom_config:
+  payload:
+    batch_size: 100
+  events:
+    "{}":
+      event_fields:
+        "$.oracle": ~
+"#,
+        MATERIALIZED_EVENT
+    ))
+    .unwrap();
+    let merged = apply_processor_overrides(&config, &overrides).unwrap();
+
+    assert_eq!(merged.common_config.starting_version_override, Some(500));
+    assert_eq!(merged.common_config.network, config.common_config.network);
+    assert_eq!(
+        merged.custom_config.payload.get("batch_size"),
+        Some(&YamlValue::from(100))
+    );
+    let event = merged.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    assert!(!event.event_fields.contains_key("$.oracle"));
+    assert!(event.event_fields.contains_key("$.market"));
+    assert_eq!(
+        merged.custom_config.db_schema,
+        config.custom_config.db_schema
+    );
+}
+
+#[test]
+fn test_invalid_overrides_are_rejected() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (config, _, _) = generate(&mappings).unwrap();
+    let overrides: YamlValue =
+        serde_yaml::from_str("common_config:\n  starting_version: not-a-number\n").unwrap();
+    assert!(apply_processor_overrides(&config, &overrides).is_err());
+}
+
+#[test]
+fn test_check_reports_all_issues() {
+    let mut schema = market_schema();
+    // a u64 column cannot hold an address field
+    schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("oracle".to_string(), column("u64", "move_type"));
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename
+        .insert("collateral_asset".to_string(), "missing_column".to_string());
+    rule.constants
+        .insert("transaction_version".to_string(), YamlValue::from(1));
+    rule.filters.push("$.unknown == 1".to_string());
+    let mappings = EventTableMappings::from([
+        (
+            EVENT.to_string(),
+            vec![rule, TableMappingRule::new("unknown_table")],
+        ),
+        (
+            "yeap-borrow::market::UnknownEvent".to_string(),
+            vec![TableMappingRule::new("borrow_market")],
+        ),
+    ]);
+
+    let issues = check_processor_mapping(&[market_created_event()], &schema, &mappings);
+    assert_eq!(issues.len(), 6, "{:#?}", issues);
+    assert!(issues.iter().any(|i| i.contains("missing_column")));
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.contains("expected a address column, found u64"))
+    );
+    assert!(issues.iter().any(|i| i.contains("constant")));
+    assert!(issues.iter().any(|i| i.contains("$.unknown")));
+    assert!(issues.iter().any(|i| i.contains("unknown_table")));
+    assert!(issues.iter().any(|i| i.contains("UnknownEvent")));
+}
+
+#[test]
+fn test_check_allows_integer_widening() {
+    let mut event = market_created_event();
+    event.fields.insert("lltv".to_string(), "u64".to_string());
+    let mut schema = market_schema();
+    schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("lltv".to_string(), column("u128", "move_type"));
+    let mappings = EventTableMappings::from([(
+        EVENT.to_string(),
+        vec![TableMappingRule::new("borrow_market")],
+    )]);
+    assert!(check_processor_mapping(&[event.clone()], &schema, &mappings).is_empty());
+
+    schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .insert("lltv".to_string(), column("u8", "move_type"));
+    assert_eq!(
+        check_processor_mapping(&[event], &schema, &mappings).len(),
+        1
+    );
+}
+
+#[test]
+fn test_ending_version_round_trips() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (mut config, _, _) = generate(&mappings).unwrap();
+    assert!(config.common_config.ending_version.is_none());
+    // open-ended configs keep their previous shape
+    assert!(
+        !serde_yaml::to_string(&config)
+            .unwrap()
+            .contains("ending_version")
+    );
+
+    config.common_config.ending_version = Some(1000);
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    let parsed: yeaptor::processor_config::ProcessorConfig = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed.common_config.ending_version, Some(1000));
+}
+
+#[test]
+fn test_restrict_mappings_to_group_tables() {
+    let mappings = EventTableMappings::from([
+        (
+            EVENT.to_string(),
+            vec![
+                TableMappingRule::new("borrow_market"),
+                TableMappingRule::new("market_stats"),
+            ],
+        ),
+        (
+            "yeap-borrow::market::MarketStatsEvent".to_string(),
+            vec![TableMappingRule::new("market_stats")],
+        ),
+    ]);
+    let restricted = restrict_mappings_to_tables(&mappings, &market_schema());
+    assert_eq!(restricted.len(), 1);
+    assert_eq!(
+        restricted[EVENT],
+        vec![TableMappingRule::new("borrow_market")]
+    );
+}
+
+#[test]
+fn test_conflict_strategies_in_payload() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (mut config, _, _) = generate(&mappings).unwrap();
+    let options = BTreeMap::from([
+        (
+            "borrow_market".to_string(),
+            TableOptions {
+                group: None,
+                on_conflict: Some(ConflictStrategy::Insert),
+                indexes: Vec::new(),
+                partition_by: None,
+            },
+        ),
+        // tables outside this config (e.g. another group) are skipped
+        (
+            "market_stats".to_string(),
+            TableOptions {
+                group: None,
+                on_conflict: Some(ConflictStrategy::Upsert),
+                indexes: Vec::new(),
+                partition_by: None,
+            },
+        ),
+    ]);
+    apply_conflict_strategies(&mut config, &options).unwrap();
+    let strategies: BTreeMap<String, ConflictStrategy> =
+        serde_yaml::from_value(config.custom_config.payload["conflict_strategies"].clone())
+            .unwrap();
+    assert_eq!(
+        strategies,
+        BTreeMap::from([("borrow_market".to_string(), ConflictStrategy::Insert)])
+    );
+
+    // borrow_market has no primary key to detect duplicates with
+    let options = BTreeMap::from([(
+        "borrow_market".to_string(),
+        TableOptions {
+            group: None,
+            on_conflict: Some(ConflictStrategy::IgnoreDuplicates),
+            indexes: Vec::new(),
+            partition_by: None,
+        },
+    )]);
+    assert!(apply_conflict_strategies(&mut config, &options).is_err());
+}
+
+#[test]
+fn test_table_indexes_in_payload() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (mut config, _, _) = generate(&mappings).unwrap();
+    let index = IndexDefinition {
+        name: None,
+        columns: vec!["oracle".to_string(), "market".to_string()],
+        unique: false,
+        method: Some(IndexMethod::Btree),
+    };
+    let mut options = BTreeMap::from([(
+        "borrow_market".to_string(),
+        TableOptions {
+            group: None,
+            on_conflict: None,
+            indexes: vec![index.clone()],
+            partition_by: None,
+        },
+    )]);
+    apply_table_indexes(&mut config, &options).unwrap();
+    let indexes: BTreeMap<String, Vec<IndexDefinition>> =
+        serde_yaml::from_value(config.custom_config.payload["indexes"].clone()).unwrap();
+    assert_eq!(
+        indexes,
+        BTreeMap::from([("borrow_market".to_string(), vec![index])])
+    );
+
+    options.get_mut("borrow_market").unwrap().indexes[0].columns[0] = "lltv".to_string();
+    assert!(apply_table_indexes(&mut config, &options).is_err());
+}
+
+#[test]
+fn test_table_partitioning_in_payload() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (mut config, _, _) = generate(&mappings).unwrap();
+    // partition columns must be non-nullable
+    config
+        .custom_config
+        .db_schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .get_mut("transaction_version")
+        .unwrap()
+        .is_nullable = false;
+    let partition_by = PartitionDefinition {
+        column: "transaction_version".to_string(),
+        interval: PartitionInterval::Values(10_000_000),
+    };
+    let mut options = BTreeMap::from([(
+        "borrow_market".to_string(),
+        TableOptions {
+            group: None,
+            on_conflict: None,
+            indexes: Vec::new(),
+            partition_by: Some(partition_by.clone()),
+        },
+    )]);
+    apply_table_partitioning(&mut config, &options).unwrap();
+    let partitioning: BTreeMap<String, PartitionDefinition> =
+        serde_yaml::from_value(config.custom_config.payload["partitioning"].clone()).unwrap();
+    assert_eq!(
+        partitioning,
+        BTreeMap::from([("borrow_market".to_string(), partition_by)])
+    );
+
+    // an address cannot be range partitioned by version
+    options.get_mut("borrow_market").unwrap().partition_by = Some(PartitionDefinition {
+        column: "market".to_string(),
+        interval: PartitionInterval::Values(10_000_000),
+    });
+    assert!(apply_table_partitioning(&mut config, &options).is_err());
+}
+
+#[test]
+fn test_catch_all_table_for_unmapped_events() {
+    let mut dependency_event = market_created_event();
+    dependency_event.name = "FrameworkEvent".to_string();
+    dependency_event.dependency_of = Some("yeap-borrow".to_string());
+    let events = [market_created_event(), dependency_event];
+    let mut schema = market_schema();
+    schema.insert("raw_events".to_string(), catch_all_table_schema());
+
+    let mut mappings = catch_all_mappings(&events, &EventTableMappings::new(), "raw_events");
+    // events of dependencies are not caught
+    assert_eq!(mappings.keys().collect::<Vec<_>>(), vec![EVENT]);
+    assert!(catch_all_mappings(&events, &mappings, "raw_events").is_empty());
+
+    let (mut config, unmapped_events, _) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &events,
+        &schema,
+        &mappings,
+    )
+    .unwrap();
+    assert_eq!(
+        unmapped_events,
+        vec!["yeap-borrow::market::FrameworkEvent".to_string()]
+    );
+    attach_catch_all_payloads(&mut config, &events, &mappings, "raw_events");
+
+    let event = config.custom_config.events.get(MATERIALIZED_EVENT).unwrap();
+    assert_eq!(
+        event.event_fields.get("$"),
+        Some(&vec![ColumnTarget {
+            column: CATCH_ALL_PAYLOAD_COLUMN.to_string(),
+            table: "raw_events".to_string(),
+        }])
+    );
+    assert!(!event.event_fields.contains_key("$.market"));
+    assert_eq!(
+        event.event_metadata["event_type"],
+        vec![ColumnTarget {
+            column: "event_type".to_string(),
+            table: "raw_events".to_string(),
+        }]
+    );
+
+    // explicitly mapped events are left alone
+    mappings.insert(
+        EVENT.to_string(),
+        vec![TableMappingRule::new("borrow_market")],
+    );
+    assert!(catch_all_mappings(&events, &mappings, "raw_events").is_empty());
+}
+
+#[test]
+fn test_backfill_variant() {
+    let rule = TableMappingRule::new("borrow_market");
+    let mappings = EventTableMappings::from([(EVENT.to_string(), vec![rule])]);
+    let (mut live, _, _) = generate(&mappings).unwrap();
+    live.common_config.starting_version_override = Some(42);
+
+    let backfill = backfill_variant(&live, 100, 200);
+    assert_eq!(
+        backfill.spec_identifier.spec_name,
+        "remapping-processor-backfill"
+    );
+    assert_eq!(
+        backfill.spec_identifier.spec_version,
+        live.spec_identifier.spec_version
+    );
+    assert_eq!(backfill.common_config.starting_version, 100);
+    assert!(backfill.common_config.starting_version_override.is_none());
+    assert_eq!(backfill.common_config.ending_version, Some(200));
+    assert_eq!(backfill.custom_config, live.custom_config);
+}
+
+#[test]
+fn test_lint_processor_config() {
+    let mut schema = market_schema();
+    let market = schema.get_mut("borrow_market").unwrap();
+    market.get_mut("market").unwrap().is_primary_key = true;
+    market.get_mut("event_source").unwrap().is_index = true;
+    market.insert(
+        "tx_version".to_string(),
+        column("version", "transaction_metadata"),
+    );
+    schema.insert(
+        "market_stats".to_string(),
+        TableSchema::from([("market".to_string(), column("address", "move_type"))]),
+    );
+    let mappings = EventTableMappings::from([(
+        EVENT.to_string(),
+        vec![TableMappingRule::new("borrow_market")],
+    )]);
+    let (config, _, _) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &[market_created_event()],
+        &schema,
+        &mappings,
+    )
+    .unwrap();
+
+    let findings = lint_processor_config(&config);
+    let rules = findings
+        .iter()
+        .map(|f| (f.severity, f.rule, f.location.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rules,
+        vec![
+            (Severity::Error, "table-without-primary-key", "market_stats"),
+            (
+                Severity::Warning,
+                "duplicate-metadata-column",
+                "borrow_market::tx_version"
+            ),
+            (
+                Severity::Warning,
+                "unmapped-index-column",
+                "borrow_market::event_source"
+            ),
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&findings[0]).unwrap()["severity"],
+        "error"
+    );
+}
+
+#[test]
+fn test_lint_unknown_target() {
+    let mappings = EventTableMappings::from([(
+        EVENT.to_string(),
+        vec![TableMappingRule::new("borrow_market")],
+    )]);
+    let (mut config, _, _) = generate(&mappings).unwrap();
+    config
+        .custom_config
+        .db_schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .remove("oracle");
+    let rules = |config: &yeaptor::processor_config::ProcessorConfig| {
+        lint_processor_config(config)
+            .into_iter()
+            .map(|f| f.rule)
+            .collect::<Vec<_>>()
+    };
+    assert!(rules(&config).contains(&"unknown-target"));
+
+    config
+        .custom_config
+        .events
+        .get_mut(MATERIALIZED_EVENT)
+        .unwrap()
+        .event_fields
+        .clear();
+    assert!(rules(&config).contains(&"event-without-fields"));
+}
+
+#[test]
+fn test_dbt_scaffold_documents_lineage() {
+    let mut rule = TableMappingRule::new("borrow_market");
+    rule.rename = BTreeMap::from([("collateral_asset".to_string(), "collateral".to_string())]);
+    rule.constants = BTreeMap::from([(
+        "event_source".to_string(),
+        YamlValue::String("lending".to_string()),
+    )]);
+    let (mut config, _, _) =
+        generate(&EventTableMappings::from([(EVENT.to_string(), vec![rule])])).unwrap();
+    config
+        .custom_config
+        .db_schema
+        .get_mut("borrow_market")
+        .unwrap()
+        .get_mut("market")
+        .unwrap()
+        .is_primary_key = true;
+
+    let files = dbt_scaffold(&config, "yeap", Some("indexer".to_string())).unwrap();
+    assert_eq!(
+        files.keys().collect::<Vec<_>>(),
+        vec![
+            "models/staging/yeap/_yeap__models.yml",
+            "models/staging/yeap/_yeap__sources.yml",
+            "models/staging/yeap/stg_yeap__borrow_market.sql",
+        ]
+    );
+
+    let sources: YamlValue =
+        serde_yaml::from_str(&files["models/staging/yeap/_yeap__sources.yml"]).unwrap();
+    let source = &sources["sources"][0];
+    assert_eq!(source["name"], "yeap");
+    assert_eq!(source["schema"], "indexer");
+    let table = &source["tables"][0];
+    assert_eq!(table["meta"]["events"][0], MATERIALIZED_EVENT);
+    let column = |name: &str| {
+        table["columns"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        column("collateral")["description"],
+        format!("`collateral_asset` of `{}`", MATERIALIZED_EVENT).as_str()
+    );
+    assert_eq!(
+        column("event_source")["description"],
+        format!("constant `lending` for `{}`", MATERIALIZED_EVENT).as_str()
+    );
+    assert_eq!(column("market")["data_tests"][1], "unique");
+
+    let sql = &files["models/staging/yeap/stg_yeap__borrow_market.sql"];
+    assert!(sql.starts_with(&format!("-- written from {}\n", MATERIALIZED_EVENT)));
+    assert!(sql.contains("select * from {{ source('yeap', 'borrow_market') }}"));
+    assert!(sql.contains("    collateral,\n"));
+}
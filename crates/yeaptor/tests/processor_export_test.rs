@@ -0,0 +1,119 @@
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::mapping_engine::Row;
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_export::{ExportFormat, ExportWriter};
+
+const CONFIG: &str = r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config: { network: testnet, starting_version: 0, starting_version_override: null }
+custom_config:
+  db_schema:
+    position:
+      owner:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      amount:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+"#;
+
+fn position(owner: &str, amount: Option<&str>) -> Row {
+    Row {
+        table: "position".to_string(),
+        event: "0x42::pool::PositionEvent".to_string(),
+        values: BTreeMap::from([
+            ("owner".to_string(), Some(owner.to_string())),
+            ("amount".to_string(), amount.map(str::to_string)),
+        ]),
+    }
+}
+
+#[test]
+fn test_export_csv_partitions() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let dir = tempdir().unwrap();
+    let mut writer = ExportWriter::new(
+        &config.custom_config.db_schema,
+        dir.path(),
+        ExportFormat::Csv,
+        2,
+    );
+    writer
+        .write(vec![position("0x1", Some("5"))], 10, 19)
+        .unwrap();
+    writer.write(vec![position("0x2", None)], 20, 29).unwrap();
+    writer
+        .write(vec![position("0x3", Some("7"))], 30, 39)
+        .unwrap();
+    let files = writer.finish().unwrap();
+
+    let names = files
+        .iter()
+        .map(|path| path.strip_prefix(dir.path()).unwrap().display().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "position/part-00000000000000000010-00000000000000000029.csv",
+            "position/part-00000000000000000030-00000000000000000039.csv",
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(&files[0]).unwrap(),
+        "amount,owner\n5,0x1\n,0x2\n"
+    );
+
+    let mut writer = ExportWriter::new(
+        &config.custom_config.db_schema,
+        dir.path(),
+        ExportFormat::Csv,
+        2,
+    );
+    let mut unknown = position("0x1", None);
+    unknown.table = "log".to_string();
+    assert!(writer.write(vec![unknown], 0, 0).is_err());
+}
+
+#[test]
+fn test_export_parquet() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let dir = tempdir().unwrap();
+    let mut writer = ExportWriter::new(
+        &config.custom_config.db_schema,
+        dir.path(),
+        ExportFormat::Parquet,
+        1_000,
+    );
+    writer
+        .write(
+            vec![position("0x1", Some("5")), position("0x2", None)],
+            10,
+            19,
+        )
+        .unwrap();
+    let files = writer.finish().unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(files[0].ends_with("position/part-00000000000000000010-00000000000000000019.parquet"));
+
+    let reader = SerializedFileReader::new(fs::File::open(&files[0]).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), 2);
+    let columns = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(columns, ["amount", "owner"]);
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "kafka")]
+
+use std::collections::BTreeMap;
+use yeaptor::mapping_engine::Row;
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_kafka::{KafkaOptions, TopicPer, kafka_key, kafka_payload, kafka_topic};
+
+const CONFIG: &str = r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config: { network: testnet, starting_version: 0, starting_version_override: null }
+custom_config:
+  db_schema:
+    position:
+      owner:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      amount:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+"#;
+
+fn position(owner: &str, amount: Option<&str>) -> Row {
+    Row {
+        table: "position".to_string(),
+        event: "0x42::pool::PositionEvent".to_string(),
+        values: BTreeMap::from([
+            ("owner".to_string(), Some(owner.to_string())),
+            ("amount".to_string(), amount.map(str::to_string)),
+        ]),
+    }
+}
+
+#[test]
+fn test_kafka_topics() {
+    let mut options = KafkaOptions {
+        brokers: "localhost:9092".to_string(),
+        topic_prefix: "lending.".to_string(),
+        topic_per: TopicPer::Table,
+        only: false,
+    };
+    let row = position("0x1", Some("5"));
+    assert_eq!(kafka_topic(&options, &row), "lending.position");
+    options.topic_per = TopicPer::Event;
+    assert_eq!(
+        kafka_topic(&options, &row),
+        "lending.0x42.pool.PositionEvent"
+    );
+}
+
+#[test]
+fn test_kafka_messages() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let tables = &config.custom_config.db_schema;
+    // updates of a position share a key, whatever their other values
+    assert_eq!(
+        kafka_key(tables, &position("0x1", Some("5"))),
+        r#"{"owner":"0x1"}"#
+    );
+    assert_eq!(
+        kafka_key(tables, &position("0x1", None)),
+        r#"{"owner":"0x1"}"#
+    );
+    assert_eq!(
+        kafka_payload(&position("0x1", None), 12),
+        r#"{"table":"position","event":"0x42::pool::PositionEvent","version":12,"values":{"amount":null,"owner":"0x1"}}"#
+    );
+
+    let mut log = position("0x1", Some("5"));
+    log.table = "log".to_string();
+    assert_eq!(kafka_key(tables, &log), r#"{"amount":"5","owner":"0x1"}"#);
+}
@@ -0,0 +1,57 @@
+use futures::StreamExt;
+use std::collections::{BTreeMap, BTreeSet};
+use yeaptor::mapping_engine::Row;
+use yeaptor::processor_live::{LiveFeed, LiveMessage};
+
+fn row(table: &str, owner: &str) -> Row {
+    Row {
+        table: table.to_string(),
+        event: "0x42::pool::RepayEvent".to_string(),
+        values: BTreeMap::from([("owner".to_string(), Some(owner.to_string()))]),
+    }
+}
+
+#[tokio::test]
+async fn test_live_feed_filters_tables() {
+    let feed = LiveFeed::new();
+    // nobody listens yet: nothing is buffered
+    feed.publish(1, 1_000, &[row("borrow", "0x1")]);
+
+    let all = feed.subscribe(BTreeSet::new());
+    let repays = feed.subscribe(BTreeSet::from(["repay".to_string()]));
+    assert_eq!(feed.subscribers(), 2);
+    let (mut all, mut repays) = (Box::pin(all), Box::pin(repays));
+
+    feed.publish(2, 2_000, &[row("borrow", "0x2")]);
+    feed.publish(3, 3_000, &[row("borrow", "0x3"), row("repay", "0x3")]);
+
+    let LiveMessage::Batch(batch) = all.next().await.unwrap() else {
+        panic!("expected rows");
+    };
+    assert_eq!(batch.version, 2);
+    let LiveMessage::Batch(batch) = repays.next().await.unwrap() else {
+        panic!("expected rows");
+    };
+    assert_eq!(batch.version, 3);
+    assert_eq!(batch.rows, vec![row("repay", "0x3")]);
+    assert_eq!(
+        LiveMessage::Batch(batch).to_json(),
+        r#"{"version":3,"timestamp":3000,"rows":[{"table":"repay","event":"0x42::pool::RepayEvent","values":{"owner":"0x3"}}]}"#
+    );
+}
+
+#[tokio::test]
+async fn test_live_feed_reports_lag() {
+    let feed = LiveFeed::new();
+    let mut slow = Box::pin(feed.subscribe(BTreeSet::new()));
+    for version in 0..1030 {
+        feed.publish(version, version, &[row("borrow", "0x1")]);
+    }
+    let lagged = slow.next().await.unwrap();
+    assert_eq!(lagged, LiveMessage::Lagged(6));
+    assert_eq!(lagged.to_json(), r#"{"lagged":6}"#);
+    let LiveMessage::Batch(batch) = slow.next().await.unwrap() else {
+        panic!("expected rows");
+    };
+    assert_eq!(batch.version, 6);
+}
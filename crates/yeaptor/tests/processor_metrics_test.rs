@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use yeaptor::processor_metrics::{ProcessorMetrics, STREAM_ERRORS, WRITE_ERRORS, prometheus_text};
+
+#[test]
+fn test_processor_metrics_status() {
+    let metrics = ProcessorMetrics::new("lending");
+    assert_eq!(metrics.status().lag, None);
+
+    let rows = BTreeMap::from([("borrow".to_string(), 2), ("repay".to_string(), 1)]);
+    metrics.record_batch(100, 1_700_000_000_000_000, &rows);
+    metrics.record_batch(150, 1_700_000_001_000_000, &rows);
+    metrics.record_chain_head(175);
+    metrics.record_error(STREAM_ERRORS);
+    metrics.record_error(STREAM_ERRORS);
+
+    let status = metrics.status();
+    assert_eq!(status.last_processed_version, Some(150));
+    assert_eq!(status.lag, Some(25));
+    assert_eq!(status.rows_written["borrow"], 4);
+    assert_eq!(status.errors[STREAM_ERRORS], 2);
+
+    // the chain head looked up before a batch commits can be behind it
+    metrics.record_chain_head(140);
+    assert_eq!(metrics.status().lag, Some(0));
+}
+
+#[test]
+fn test_prometheus_text() {
+    let metrics = ProcessorMetrics::new("lending");
+    metrics.record_batch(
+        150,
+        1_700_000_001_000_000,
+        &BTreeMap::from([("borrow".to_string(), 3)]),
+    );
+    metrics.record_error(WRITE_ERRORS);
+
+    let text = prometheus_text(&metrics.status());
+    assert!(text.contains(
+        "# TYPE yeaptor_processor_last_processed_version gauge\nyeaptor_processor_last_processed_version{processor=\"lending\"} 150\n"
+    ));
+    assert!(text.contains(
+        "yeaptor_processor_last_transaction_timestamp_seconds{processor=\"lending\"} 1700000001\n"
+    ));
+    assert!(text.contains(
+        "yeaptor_processor_rows_written_total{processor=\"lending\",table=\"borrow\"} 3\n"
+    ));
+    assert!(
+        text.contains("yeaptor_processor_errors_total{processor=\"lending\",kind=\"write\"} 1\n")
+    );
+    // no chain head yet: neither head nor lag are exported
+    assert!(!text.contains("yeaptor_processor_lag_versions"));
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use yeaptor::mapping_engine::Row;
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_pipeline::{PipelineOptions, check_parallel_writes, partition_rows};
+
+const CONFIG: &str = r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config: { network: testnet, starting_version: 0, starting_version_override: null }
+custom_config:
+  db_schema:
+    position:
+      owner:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      amount:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+    log:
+      note:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+  payload:
+    conflict_strategies:
+      position: upsert
+"#;
+
+fn row(table: &str, values: &[(&str, &str)]) -> Row {
+    Row {
+        table: table.to_string(),
+        event: "0x42::pool::PositionEvent".to_string(),
+        values: values
+            .iter()
+            .map(|(column, value)| (column.to_string(), Some(value.to_string())))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+#[test]
+fn test_partition_rows_keeps_primary_keys_together() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let rows = (0..40)
+        .map(|i| {
+            let owner = format!("0x{}", i % 5);
+            let amount = i.to_string();
+            row(
+                "position",
+                &[("owner", owner.as_str()), ("amount", amount.as_str())],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let shares = partition_rows(&config.custom_config.db_schema, rows.clone(), 3);
+    assert_eq!(shares.len(), 3);
+    assert_eq!(shares.iter().map(Vec::len).sum::<usize>(), rows.len());
+    for i in 0..5 {
+        let owner = Some(format!("0x{}", i));
+        let holders = shares
+            .iter()
+            .filter(|share| share.iter().any(|r| r.values["owner"] == owner))
+            .collect::<Vec<_>>();
+        // every update of a position goes to one worker, in the order it was mapped
+        assert_eq!(holders.len(), 1);
+        let amounts = holders[0]
+            .iter()
+            .filter(|r| r.values["owner"] == owner)
+            .map(|r| r.values["amount"].clone().unwrap().parse::<u32>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(amounts.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    let single = partition_rows(&config.custom_config.db_schema, rows.clone(), 1);
+    assert_eq!(single, vec![rows]);
+}
+
+#[test]
+fn test_check_parallel_writes() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let defaults = PipelineOptions::default();
+    assert!(check_parallel_writes(&config, defaults.write_workers).is_ok());
+
+    let err = check_parallel_writes(&config, 4).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "4 write workers need on_conflict upsert or ignore-duplicates, but log use insert"
+    );
+
+    let config: ProcessorConfig = serde_yaml::from_str(&CONFIG.replace(
+        "      position: upsert\n",
+        "      position: upsert\n      log: ignore-duplicates\n",
+    ))
+    .unwrap();
+    assert!(check_parallel_writes(&config, 4).is_ok());
+}
@@ -0,0 +1,116 @@
+use serde_json::json;
+use yeaptor::mapping_engine::{EventRecord, MappingEngine, MissingField, TransactionRecord};
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_preview::{preview_transactions, render_preview};
+
+const CONFIG: &str = r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config: { network: testnet, starting_version: 0, starting_version_override: null }
+custom_config:
+  db_schema:
+    borrow:
+      borrower:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      amount:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+      fee:
+        column_type: { column_type: u64, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+    large_borrow:
+      borrower:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+      memo:
+        column_type: { column_type: string, type: move_type }
+        is_index: false
+        is_nullable: true
+        is_option: false
+        is_primary_key: false
+        is_vec: false
+  events:
+    0x42::pool::BorrowEvent:
+      event_fields:
+        $.borrower:
+          - { column: borrower, table: borrow }
+          - { column: borrower, table: large_borrow }
+        $.amount:
+          - { column: amount, table: borrow }
+        $.fee.value:
+          - { column: fee, table: borrow }
+        $.memo:
+          - { column: memo, table: large_borrow }
+      filters:
+        - { table: large_borrow, path: $.amount, op: gt, value: 1000 }
+"#;
+
+fn transaction() -> TransactionRecord {
+    let event = |event_type: &str| EventRecord {
+        account_address: "0x42".to_string(),
+        creation_number: 0,
+        sequence_number: 0,
+        event_type: event_type.to_string(),
+        data: json!({ "borrower": "0x1", "amount": "100" }),
+    };
+    TransactionRecord {
+        version: 12,
+        block_height: 3,
+        epoch: 1,
+        timestamp: 1_700_000_000_000_000,
+        events: vec![
+            event("0x42::pool::BorrowEvent"),
+            event("0x42::pool::RepayEvent"),
+        ],
+        payload: None,
+        table_items: Vec::new(),
+    }
+}
+
+#[test]
+fn test_preview_transaction() {
+    let config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let engine = MappingEngine::new(&config).unwrap();
+    let previews = preview_transactions(&engine, &[transaction()]).unwrap();
+    let preview = &previews[0];
+    assert_eq!(preview.version, 12);
+    assert_eq!(preview.rows.len(), 1);
+    // the filter rejects the event for large_borrow, so its missing memo is not reported
+    assert_eq!(
+        preview.missing_fields,
+        vec![MissingField {
+            table: "borrow".to_string(),
+            column: "fee".to_string(),
+            source: "0x42::pool::BorrowEvent".to_string(),
+            path: "$.fee.value".to_string(),
+        }]
+    );
+    assert_eq!(preview.unmapped_events, vec!["0x42::pool::RepayEvent"]);
+
+    assert_eq!(
+        render_preview(&previews),
+        "version 12: 1 row
+  borrow (from 0x42::pool::BorrowEvent)
+    amount   = 100
+    borrower = 0x1
+    fee      = NULL
+  borrow.fee: 0x42::pool::BorrowEvent has no value at $.fee.value
+  not mapped: 0x42::pool::RepayEvent"
+    );
+}
@@ -0,0 +1,149 @@
+use aptos_protos::transaction::v1::transaction::TxnData;
+use aptos_protos::transaction::v1::{
+    Event, EventKey, Transaction, UserTransaction, UserTransactionRequest,
+};
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::load_schema_snapshot;
+use yeaptor::processor_config::{ConflictStrategy, ProcessorConfig};
+use yeaptor::processor_runtime::{
+    AccountFilter, default_grpc_address, filtered_transaction_record, postgres_insert_statement,
+};
+
+#[test]
+fn test_postgres_insert_statement() {
+    let content = r#"
+[tables.position.columns]
+owner = { column_type = "address", is_primary_key = true }
+amount = { column_type = "u128" }
+tags = { column_type = "string", is_vec = true, is_nullable = true }
+
+[tables.market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let snapshot = load_schema_snapshot(file.path()).unwrap();
+    let position = &snapshot.tables["position"];
+
+    assert_eq!(
+        postgres_insert_statement("position", position, ConflictStrategy::Insert).unwrap(),
+        "INSERT INTO \"position\" (\"amount\", \"owner\", \"tags\") VALUES ($1::TEXT::NUMERIC(39, 0), $2::TEXT::VARCHAR(66), CASE WHEN $3::TEXT IS NULL THEN NULL ELSE ARRAY(SELECT jsonb_array_elements_text($3::TEXT::JSONB))::TEXT[] END)"
+    );
+    assert!(
+        postgres_insert_statement("position", position, ConflictStrategy::Upsert)
+            .unwrap()
+            .ends_with(
+                " ON CONFLICT (\"owner\") DO UPDATE SET \"amount\" = EXCLUDED.\"amount\", \"tags\" = EXCLUDED.\"tags\""
+            )
+    );
+    assert!(
+        postgres_insert_statement("position", position, ConflictStrategy::IgnoreDuplicates)
+            .unwrap()
+            .ends_with(" ON CONFLICT DO NOTHING")
+    );
+    // nothing to update when every column is part of the primary key
+    assert!(
+        postgres_insert_statement(
+            "market",
+            &snapshot.tables["market"],
+            ConflictStrategy::Upsert
+        )
+        .unwrap()
+        .ends_with(" ON CONFLICT DO NOTHING")
+    );
+}
+
+#[test]
+fn test_postgres_insert_statement_upsert_requires_primary_key() {
+    let content = r#"
+[tables.log.columns]
+note = { column_type = "string" }
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let snapshot = load_schema_snapshot(file.path()).unwrap();
+    let err = postgres_insert_statement("log", &snapshot.tables["log"], ConflictStrategy::Upsert)
+        .unwrap_err();
+    assert!(err.to_string().contains("has no primary key"));
+}
+
+#[test]
+fn test_default_grpc_address() {
+    assert_eq!(
+        default_grpc_address("Mainnet"),
+        Some("https://grpc.mainnet.aptoslabs.com:443")
+    );
+    assert_eq!(default_grpc_address("local"), None);
+}
+
+fn user_transaction(sender: &str, events: Vec<Event>) -> Transaction {
+    Transaction {
+        version: 9,
+        txn_data: Some(TxnData::User(UserTransaction {
+            request: Some(UserTransactionRequest {
+                sender: sender.to_string(),
+                ..Default::default()
+            }),
+            events,
+        })),
+        ..Default::default()
+    }
+}
+
+fn event(account_address: &str, type_str: &str) -> Event {
+    Event {
+        key: Some(EventKey {
+            account_address: account_address.to_string(),
+            creation_number: 0,
+        }),
+        type_str: type_str.to_string(),
+        data: "{}".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_account_filter() {
+    let mut config: ProcessorConfig = serde_yaml::from_str(
+        r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config:
+  network: testnet
+  starting_version: 0
+  starting_version_override: null
+  account_filter: ["0x42"]
+custom_config: {}
+"#,
+    )
+    .unwrap();
+    let filter = AccountFilter::from_config(&config).unwrap().unwrap();
+
+    // sender, handle event account and module event type address (long form too)
+    assert!(filter.touches(&user_transaction(&format!("0x{:0>64}", "42"), vec![])));
+    assert!(filter.touches(&user_transaction(
+        "0x7",
+        vec![event("0x42", "0x1::coin::DepositEvent")]
+    )));
+    assert!(filter.touches(&user_transaction(
+        "0x7",
+        vec![event("0x0", "0x42::market::SupplyEvent")]
+    )));
+    let skipped = user_transaction("0x7", vec![event("0x0", "0x1::coin::CoinDeposit")]);
+    assert!(!filter.touches(&skipped));
+
+    // a skipped transaction keeps its version for the checkpoint but its events are not decoded
+    let mut undecodable = skipped.clone();
+    if let Some(TxnData::User(user)) = &mut undecodable.txn_data {
+        user.events[0].data = "not json".to_string();
+    }
+    let record = filtered_transaction_record(&undecodable, Some(&filter)).unwrap();
+    assert_eq!(record.version, 9);
+    assert!(record.events.is_empty());
+    assert!(filtered_transaction_record(&undecodable, None).is_err());
+
+    config.common_config.account_filter.clear();
+    assert!(AccountFilter::from_config(&config).unwrap().is_none());
+    config.common_config.account_filter = vec!["market".to_string()];
+    assert!(AccountFilter::from_config(&config).is_err());
+}
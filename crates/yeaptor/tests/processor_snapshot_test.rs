@@ -0,0 +1,64 @@
+use std::fs;
+use tempfile::{Builder, tempdir};
+use yeaptor::db_schema::load_schema_snapshot;
+use yeaptor::processor_config::ProcessorConfig;
+use yeaptor::processor_snapshot::{
+    CONFIG_SNAPSHOT_FILE, DDL_SNAPSHOT_FILE, check_snapshot, snapshot_files, write_snapshot,
+};
+
+const CONFIG: &str = r#"
+spec_identifier: { spec_creator: yeap, spec_name: lending, spec_version: "1" }
+common_config: { network: testnet, starting_version: 0, starting_version_override: null }
+custom_config:
+  db_schema:
+    market:
+      market:
+        column_type: { column_type: address, type: move_type }
+        is_index: false
+        is_nullable: false
+        is_option: false
+        is_primary_key: true
+        is_vec: false
+"#;
+
+#[test]
+fn test_processor_snapshot() {
+    let schema_file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(
+        schema_file.path(),
+        r#"
+[tables.market.columns]
+market = { column_type = "address", is_primary_key = true }
+"#,
+    )
+    .unwrap();
+    let schema = load_schema_snapshot(schema_file.path()).unwrap();
+    let mut config: ProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+    let files = snapshot_files(&config, &schema).unwrap();
+    assert_eq!(
+        files.keys().map(String::as_str).collect::<Vec<_>>(),
+        vec![CONFIG_SNAPSHOT_FILE, DDL_SNAPSHOT_FILE]
+    );
+    assert!(files[DDL_SNAPSHOT_FILE].contains("CREATE TABLE"));
+
+    let dir = tempdir().unwrap();
+    let missing = check_snapshot(dir.path(), &files).unwrap();
+    assert_eq!(missing.len(), 2);
+    assert!(missing[0].ends_with("processor_config.yaml is missing"));
+
+    let written = write_snapshot(dir.path(), &files).unwrap();
+    assert_eq!(written.len(), 2);
+    assert!(check_snapshot(dir.path(), &files).unwrap().is_empty());
+
+    config.common_config.starting_version = 42;
+    let regenerated = snapshot_files(&config, &schema).unwrap();
+    let differences = check_snapshot(dir.path(), &regenerated).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert!(
+        differences[0].contains(
+            "differs at line 7: snapshot has `  starting_version: 0`, regenerated `  starting_version: 42`"
+        ),
+        "{}",
+        differences[0]
+    );
+}
@@ -0,0 +1,43 @@
+use clap::Parser;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::Yeaptor;
+use yeaptor::prompt::Confirmation;
+
+#[test]
+fn test_assumed_answers() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("schema.sql");
+
+    // Nothing to confirm for a new file
+    Confirmation::default().write_file(&path, b"first").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+    let assume_no = Confirmation {
+        assume_yes: false,
+        assume_no: true,
+    };
+    let err = assume_no.write_file(&path, b"second").unwrap_err();
+    assert_eq!(err.kind(), "config");
+    assert!(err.to_string().contains("already exists"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+    let assume_yes = Confirmation {
+        assume_yes: true,
+        assume_no: false,
+    };
+    assume_yes.write_file(&path, b"second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+}
+
+#[test]
+fn test_global_flags() {
+    let parsed =
+        Yeaptor::try_parse_from(["yeaptor", "processor", "dbt", "--non-interactive"]).unwrap();
+    assert!(parsed.confirmation.assume_yes);
+    let parsed = Yeaptor::try_parse_from(["yeaptor", "--assume-no", "event", "generate"]).unwrap();
+    assert!(parsed.confirmation.assume_no);
+    assert!(
+        Yeaptor::try_parse_from(["yeaptor", "version", "--assume-yes", "--assume-no"]).is_err()
+    );
+}
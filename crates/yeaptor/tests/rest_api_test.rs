@@ -0,0 +1,46 @@
+use std::fs;
+use tempfile::Builder;
+use yeaptor::db_schema::load_schema_snapshot;
+use yeaptor::rest_api::rest_api_scaffold;
+
+#[test]
+fn test_rest_api_scaffold() {
+    let content = r#"
+[tables.borrow_position.columns]
+owner = { column_type = "address", is_primary_key = true }
+market = { column_type = "address", is_index = true }
+amount = { column_type = "u128" }
+tags = { column_type = "string", is_vec = true, is_index = true }
+
+[tables.market.columns]
+market = { column_type = "address", is_primary_key = true }
+symbol = { column_type = "string" }
+
+[[tables.market.indexes]]
+columns = ["symbol"]
+"#;
+    let file = Builder::new().suffix(".toml").tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    let schema = load_schema_snapshot(file.path()).unwrap();
+
+    let files = rest_api_scaffold(&schema, "lending-api").unwrap();
+    assert_eq!(
+        files.keys().map(String::as_str).collect::<Vec<_>>(),
+        vec!["Cargo.toml", "src/main.rs"]
+    );
+    assert!(files["Cargo.toml"].contains("name = \"lending-api\""));
+    let main = &files["src/main.rs"];
+    // vectors cannot be filtered on
+    assert!(main.contains(
+        "    Table {
+        name: \"borrow_position\",
+        primary_key: &[\"owner\"],
+        filters: &[\"market\", \"owner\"],
+    },
+"
+    ));
+    assert!(main.contains("filters: &[\"market\", \"symbol\"],"));
+
+    let err = rest_api_scaffold(&schema, "lending api").unwrap_err();
+    assert!(err.to_string().contains("invalid crate name"), "{}", err);
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use aptos_types::account_address::AccountAddress;
+use common::{MetadataBuilder, planned_package};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::config::DeploymentMode;
+use yeaptor::deployment_planner::bytecode_sha3_256;
+use yeaptor::lockfile::DependencyHashes;
+use yeaptor::sbom::{build_sbom, sbom_sources};
+use yeaptor::{DeploymentPlan, PlannedPackage};
+
+fn address(hex: &str) -> AccountAddress {
+    AccountAddress::from_hex_literal(hex).unwrap()
+}
+
+fn package(
+    order: usize,
+    name: &str,
+    account: &str,
+    deps: Vec<(AccountAddress, String)>,
+) -> PlannedPackage {
+    let mut metadata = MetadataBuilder::new(name);
+    for (account, package) in &deps {
+        metadata = metadata.dependency(*account, package);
+    }
+    PlannedPackage {
+        mode: DeploymentMode::ResourceAccount,
+        seed: "v1".to_string(),
+        account: address(account),
+        metadata: metadata.bcs(),
+        modules: vec![vec![order as u8]],
+        ..planned_package(order, name)
+    }
+}
+
+#[test]
+fn test_build_sbom() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("core")).unwrap();
+    fs::write(
+        dir.path().join("core/Move.toml"),
+        r#"
+[package]
+name = "core"
+version = "1.2.0"
+
+[dependencies]
+AptosFramework = { git = "https://github.com/aptos-labs/aptos-framework.git", rev = "mainnet", subdir = "aptos-framework" }
+"#,
+    )
+    .unwrap();
+    let core_dir = dir.path().join("core");
+    let sources = sbom_sources([("core", core_dir.as_path())]).unwrap();
+    assert_eq!(sources.versions["core"], "1.2.0");
+
+    let framework = DependencyHashes {
+        bytecode_sha3_256: "ab".repeat(32),
+        source_sha3_256: "cd".repeat(32),
+    };
+    let plan = DeploymentPlan {
+        packages: vec![
+            package(
+                0,
+                "core",
+                "0xc0",
+                vec![(AccountAddress::ONE, "AptosFramework".to_string())],
+            ),
+            package(
+                1,
+                "app",
+                "0xa0",
+                vec![
+                    (AccountAddress::ONE, "AptosFramework".to_string()),
+                    (address("0xc0"), "core".to_string()),
+                ],
+            ),
+        ],
+        named_addresses: BTreeMap::new(),
+        dependencies: BTreeMap::from([("AptosFramework".to_string(), framework.clone())]),
+    };
+    let sbom = serde_json::to_value(build_sbom(&plan, &sources).unwrap()).unwrap();
+    assert_eq!(sbom["bomFormat"], "CycloneDX");
+    assert_eq!(sbom["specVersion"], "1.5");
+    assert!(sbom["metadata"].get("timestamp").is_none());
+
+    let components = sbom["components"].as_array().unwrap();
+    let names = components
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["core", "app", "AptosFramework"]);
+
+    let core = &components[0];
+    assert_eq!(core["bom-ref"], "core");
+    assert_eq!(core["version"], "1.2.0");
+    assert_eq!(core["hashes"][0]["alg"], "SHA3-256");
+    assert_eq!(core["hashes"][0]["content"], bytecode_sha3_256(&[vec![0]]));
+    assert_eq!(core["properties"][0]["name"], "aptos:account");
+    assert_eq!(
+        core["properties"][0]["value"],
+        address("0xc0").to_standard_string()
+    );
+    assert_eq!(core["properties"][2]["value"], "compatible");
+    assert!(components[1].get("version").is_none());
+
+    let aptos_framework = &components[2];
+    assert_eq!(aptos_framework["version"], "mainnet");
+    assert_eq!(
+        aptos_framework["hashes"][0]["content"],
+        framework.bytecode_sha3_256
+    );
+    assert_eq!(
+        aptos_framework["externalReferences"][0],
+        serde_json::json!({
+            "type": "vcs",
+            "url": "https://github.com/aptos-labs/aptos-framework.git"
+        })
+    );
+    assert_eq!(
+        aptos_framework["properties"][0]["value"],
+        AccountAddress::ONE.to_standard_string()
+    );
+
+    assert_eq!(
+        sbom["dependencies"],
+        serde_json::json!([
+            { "ref": "core", "dependsOn": ["AptosFramework"] },
+            { "ref": "app", "dependsOn": ["AptosFramework", "core"] },
+        ])
+    );
+}
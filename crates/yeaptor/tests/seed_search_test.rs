@@ -0,0 +1,35 @@
+use aptos_types::account_address::{AccountAddress, create_resource_address};
+use indicatif::ProgressBar;
+use yeaptor::seed_search::find_seed;
+use yeaptor_core::address::AddressPrefix;
+
+#[test]
+fn test_find_seed() {
+    let publisher = AccountAddress::from_hex_literal("0x10").unwrap();
+    let prefix: AddressPrefix = "0xca".parse().unwrap();
+
+    let found = find_seed(publisher, &prefix, "v", 1, None, &ProgressBar::hidden()).unwrap();
+    assert_eq!(found.seed, "v232");
+    assert_eq!(found.attempts, 233);
+    assert_eq!(
+        found.address.to_standard_string(),
+        "0xca18aea53653478447560257138c45c759e4abf9cf85509a724a3d565e1f5cf9"
+    );
+
+    // with several workers any match may win, but it always derives from its seed
+    let found = find_seed(publisher, &prefix, "v", 4, None, &ProgressBar::hidden()).unwrap();
+    assert!(prefix.matches(&found.address));
+    assert_eq!(
+        found.address,
+        create_resource_address(publisher, found.seed.as_bytes())
+    );
+
+    // the first match of 0xcaf is v9214
+    let prefix: AddressPrefix = "caf".parse().unwrap();
+    let progress = ProgressBar::hidden();
+    assert_eq!(
+        find_seed(publisher, &prefix, "v", 3, Some(1000), &progress),
+        None
+    );
+    assert_eq!(progress.position(), 1000);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use aptos_framework::natives::code::PackageMetadata;
+use common::MetadataBuilder;
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::source_diff::{
+    ModuleChange, diff_module_sources, local_module_sources, module_functions, module_sections,
+    published_module_sources, render_module_diffs, unified_diff,
+};
+
+const POOL_V1: &str = "module yeap::pool {
+    // deposits
+    public entry fun deposit(user: &signer, amount: u64) {
+        assert!(amount > 0, 1);
+    }
+
+    #[view]
+    public fun total(): u64 { 0 }
+
+    fun legacy() {}
+}
+";
+
+const POOL_V2: &str = "module yeap::pool {
+    /* deposits, now capped */
+    public entry fun deposit(user: &signer, amount: u64) {
+        assert!(amount > 0 && amount < 100, 1);
+    }
+
+    #[view]
+    public fun total(): u64 {
+        0
+    }
+
+    public fun withdraw(user: &signer) {}
+}
+";
+
+/// `PackageMetadata` of `core` with `modules` as (name, source)
+fn metadata(modules: &[(&str, &str)]) -> PackageMetadata {
+    let mut metadata = MetadataBuilder::new("core");
+    for (name, source) in modules {
+        metadata = metadata.module(name, source, Vec::new());
+    }
+    metadata.build()
+}
+
+#[test]
+fn test_module_sections_and_functions() {
+    let source = "// module commented::out\n\
+                  module 0x1::first { fun a() { if (true) { b() } } }\n\
+                  module second { spec module { pragma verify = false; } native fun n(); }\n";
+    let sections = module_sections(source);
+    assert_eq!(sections.keys().collect::<Vec<_>>(), vec!["first", "second"]);
+    assert_eq!(
+        module_functions(source, "first"),
+        BTreeMap::from([("a".to_string(), "fun a() { if (true) { b() } }".to_string())])
+    );
+    assert_eq!(
+        module_functions(source, "second"),
+        BTreeMap::from([("n".to_string(), "native fun n();".to_string())])
+    );
+
+    let functions = module_functions(POOL_V1, "pool");
+    assert_eq!(functions["total"], "#[view] public fun total(): u64 { 0 }");
+    // comments and layout do not count as changes
+    assert_eq!(
+        functions["total"],
+        module_functions(POOL_V2, "pool")["total"]
+    );
+}
+
+#[test]
+fn test_unified_diff() {
+    assert!(unified_diff("a\nb\n", "a\nb\n", 3).is_empty());
+    assert_eq!(
+        unified_diff("a\nb\nc\nd\ne\nf\n", "a\nb\nC\nd\ne\nf\ng\n", 1),
+        vec![
+            "@@ -2,3 +2,3 @@",
+            " b",
+            "-c",
+            "+C",
+            " d",
+            "@@ -6,1 +6,2 @@",
+            " f",
+            "+g",
+        ]
+    );
+    assert_eq!(unified_diff("", "x\n", 3), vec!["@@ -0,0 +1,1 @@", "+x"]);
+}
+
+#[test]
+fn test_diff_module_sources() {
+    let published = published_module_sources(&metadata(&[
+        ("pool", POOL_V1),
+        ("oracle", "module yeap::oracle {}\n"),
+    ]))
+    .unwrap();
+
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("sources/extra")).unwrap();
+    fs::write(
+        dir.path().join("sources/pool.move"),
+        POOL_V2.replace('\n', "\r\n"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("sources/extra/vault.move"),
+        "module yeap::vault { public fun open() {} }\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("sources/notes.txt"),
+        "module yeap::notes {}",
+    )
+    .unwrap();
+    let local = local_module_sources(dir.path()).unwrap();
+    assert_eq!(local.keys().collect::<Vec<_>>(), vec!["pool", "vault"]);
+
+    let diffs = diff_module_sources(&published, &local);
+    let changes = diffs
+        .iter()
+        .map(|diff| (diff.module.as_str(), diff.change))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec![
+            ("oracle", ModuleChange::Removed),
+            ("pool", ModuleChange::Changed),
+            ("vault", ModuleChange::Added),
+        ]
+    );
+    let pool = &diffs[1];
+    assert_eq!(pool.added_functions, vec!["withdraw"]);
+    assert_eq!(pool.removed_functions, vec!["legacy"]);
+    assert_eq!(pool.changed_functions, vec!["deposit"]);
+    assert!(
+        pool.diff
+            .contains(&"+    public fun withdraw(user: &signer) {}".to_string())
+    );
+
+    assert_eq!(
+        render_module_diffs(&diffs, false),
+        "removed oracle\n\
+         changed pool\n  added functions: withdraw\n  removed functions: legacy\n  changed functions: deposit\n\
+         added vault\n  added functions: open"
+    );
+    assert_eq!(
+        diff_module_sources(&published, &published)[1].change,
+        ModuleChange::Unchanged
+    );
+}
+
+#[test]
+fn test_published_module_sources_without_sources() {
+    let error = published_module_sources(&metadata(&[("pool", "")])).unwrap_err();
+    assert!(error.to_string().contains("published without its sources"));
+}
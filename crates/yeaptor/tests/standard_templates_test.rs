@@ -0,0 +1,120 @@
+use aptos::common::init::Network;
+use serde_yaml::Value as YamlValue;
+use std::fs;
+use tempfile::NamedTempFile;
+use yeaptor::config::load_config;
+use yeaptor::event_table_mapping::{EventTableMappings, TableMappingRule};
+use yeaptor::processor_config::SpecIdentifier;
+use yeaptor::processor_config_generator::generate_processor_config;
+use yeaptor::standard_templates::StandardTemplates;
+
+#[test]
+fn test_standard_templates_from_config() {
+    let temp_file = NamedTempFile::new().unwrap();
+    fs::write(
+        temp_file.path(),
+        r#"
+format_version = 1
+yeaptor_address = "0x1"
+
+[processor.standard-templates]
+fungible-asset = true
+object = true
+"#,
+    )
+    .unwrap();
+    let config = load_config(temp_file.path()).unwrap();
+    assert_eq!(
+        config.processor.standard_templates,
+        StandardTemplates {
+            fungible_asset: true,
+            coin: false,
+            object: true,
+        }
+    );
+
+    fs::write(
+        temp_file.path(),
+        "format_version = 1\nyeaptor_address = \"0x1\"\n[processor.standard-templates]\nnft = true\n",
+    )
+    .unwrap();
+    assert!(load_config(temp_file.path()).is_err());
+}
+
+#[test]
+fn test_standard_templates_generate() {
+    let templates = StandardTemplates {
+        fungible_asset: true,
+        coin: true,
+        object: true,
+    };
+    let mut db_schema = Default::default();
+    let mut definitions = Vec::new();
+    let mut mapping = EventTableMappings::new();
+    templates.extend_table_schemas(&mut db_schema);
+    templates.extend_event_definitions(&mut definitions);
+    templates.extend_event_mapping(&mut mapping);
+    assert_eq!(
+        db_schema.keys().collect::<Vec<_>>(),
+        [
+            "coin_activities",
+            "fungible_asset_activities",
+            "object_transfers"
+        ]
+    );
+
+    let (config, unmapped_events, unmapped_columns) = generate_processor_config(
+        SpecIdentifier::default(),
+        Network::Testnet,
+        0,
+        &definitions,
+        &db_schema,
+        &mapping,
+    )
+    .unwrap();
+    assert!(unmapped_events.is_empty(), "{:?}", unmapped_events);
+    assert!(unmapped_columns.is_empty(), "{:?}", unmapped_columns);
+
+    let events = &config.custom_config.events;
+    let deposit = &events["0x1::coin::DepositEvent"];
+    assert_eq!(deposit.event_fields["$.amount"][0].column, "amount");
+    assert_eq!(deposit.event_metadata["account_address"][0].column, "owner");
+    assert_eq!(deposit.constant_values[0].column, "activity_type");
+    assert_eq!(
+        deposit.constant_values[0].value,
+        YamlValue::String("deposit".to_string())
+    );
+    let withdraw = &events["0x1::fungible_asset::Withdraw"];
+    assert_eq!(
+        withdraw.event_fields["$.store"][0].table,
+        "fungible_asset_activities"
+    );
+    assert!(events.contains_key("0x1::object::Transfer"));
+    assert!(events.contains_key("0x1::object::TransferEvent"));
+}
+
+#[test]
+fn test_standard_templates_yield_to_user_inputs() {
+    let templates = StandardTemplates {
+        coin: true,
+        ..Default::default()
+    };
+    let mut mapping = EventTableMappings::from([(
+        "AptosFramework::coin::DepositEvent".to_string(),
+        vec![TableMappingRule::new("deposits")],
+    )]);
+    templates.extend_event_mapping(&mut mapping);
+    assert_eq!(
+        mapping["AptosFramework::coin::DepositEvent"][0].table,
+        "deposits"
+    );
+    assert_eq!(
+        mapping["AptosFramework::coin::WithdrawEvent"][0].table,
+        "coin_activities"
+    );
+
+    let mut definitions = Vec::new();
+    templates.extend_event_definitions(&mut definitions);
+    templates.extend_event_definitions(&mut definitions);
+    assert_eq!(definitions.len(), 2);
+}
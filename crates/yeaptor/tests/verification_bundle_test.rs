@@ -0,0 +1,93 @@
+mod common;
+
+use common::planned_package;
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+use yeaptor::verification_bundle::{
+    BuildSettings, verification_manifest, write_verification_bundle,
+};
+
+#[test]
+fn test_verification_manifest() {
+    let package = planned_package(0, "core");
+    let named_addresses = BTreeMap::from([("core".to_string(), package.account)]);
+    let build = BuildSettings {
+        dev: false,
+        compiler_version: Some("2.0".to_string()),
+        language_version: None,
+        bytecode_version: Some(7),
+    };
+    let manifest = verification_manifest(&package, &named_addresses, &build).unwrap();
+    let account = package.account.to_standard_string();
+    assert_eq!(manifest.upgrade_policy, "compatible");
+    assert_eq!(manifest.source_digest, "C0FFEE");
+    assert_eq!(
+        manifest.metadata_sha3_256,
+        hex::encode(Sha3_256::digest(&package.metadata))
+    );
+    assert_eq!(manifest.named_addresses["core"], account);
+    assert_eq!(
+        manifest.verify_command,
+        format!(
+            "aptos move verify-package --package-dir . --account {account} --named-addresses \
+             core={account} --compiler-version 2.0 --bytecode-version 7"
+        )
+    );
+}
+
+#[test]
+fn test_write_verification_bundle() {
+    let dir = tempdir().unwrap();
+    let package_dir = dir.path().join("core");
+    fs::create_dir_all(package_dir.join("sources/nested")).unwrap();
+    fs::write(
+        package_dir.join("Move.toml"),
+        "[package]\nname = \"Core\"\n",
+    )
+    .unwrap();
+    fs::write(
+        package_dir.join("sources/vault.move"),
+        "module core::vault {}",
+    )
+    .unwrap();
+    fs::write(
+        package_dir.join("sources/nested/math.move"),
+        "module core::math {}",
+    )
+    .unwrap();
+    fs::write(package_dir.join("sources/notes.txt"), "not a source").unwrap();
+
+    let manifest = verification_manifest(
+        &planned_package(0, "core"),
+        &BTreeMap::new(),
+        &BuildSettings::default(),
+    )
+    .unwrap();
+    let bundle_dir = dir.path().join("bundle");
+    let written = write_verification_bundle(&package_dir, &bundle_dir, &manifest).unwrap();
+    let relative = written
+        .iter()
+        .map(|path| {
+            path.strip_prefix(&bundle_dir)
+                .unwrap()
+                .display()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relative,
+        [
+            "Move.toml",
+            "sources/nested/math.move",
+            "sources/vault.move",
+            "verification.json"
+        ]
+    );
+    let verification: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(bundle_dir.join("verification.json")).unwrap())
+            .unwrap();
+    assert_eq!(verification["package"], "core");
+    assert_eq!(verification["build"]["dev"], false);
+}